@@ -1,15 +1,18 @@
 //! Ahead-of-time (AOT) compilation for tscl
 //!
 //! This module provides AOT compilation to standalone executables.
-//! Currently a scaffold for future implementation.
 //!
-//! Future features:
-//! - Object file generation
-//! - Static linking with runtime
-//! - Platform-specific binary output
-//! - Link-time optimization (LTO)
+//! Two backends are supported:
+//! - LLVM (`LlvmAot`): optimized output with LTO support
+//! - Cranelift (`CraneliftAot`): object files via `object.rs`; does not
+//!   require LLVM
+//!
+//! Executables and shared libraries of both backends link against the runtime
+//! static library (`runtime_lib.rs`).
 
+use super::runtime_lib::find_runtime_library;
 use super::{BackendConfig, BackendError, BackendKind, LtoMode};
+use crate::build::cache::{Artifact, BuildCache, CacheKey};
 use crate::ir::IrModule;
 use std::path::{Path, PathBuf};
 
@@ -32,25 +35,32 @@ pub enum OutputFormat {
 pub struct AotOptions {
     /// Output format
     pub format: OutputFormat,
-    /// Target triple (e.g., "x86_64-apple-darwin")
-    pub target: Option<String>,
     /// Link-time optimization mode
     pub lto_mode: LtoMode,
     /// Strip debug symbols
     pub strip: bool,
+    /// Link a fully static executable (musl targets)
+    pub static_link: bool,
 }
 
 impl Default for AotOptions {
     fn default() -> Self {
         Self {
             format: OutputFormat::Executable,
-            target: None,
             lto_mode: LtoMode::None,
             strip: false,
+            static_link: false,
         }
     }
 }
 
+/// A module of a cached build (see [`AotCompiler::compile_cached`])
+pub struct BuildUnit<'a> {
+    pub key: CacheKey,
+    /// The module's IR; only needed when its artifact is not cached
+    pub module: Option<&'a IrModule>,
+}
+
 /// AOT compiler state
 pub struct AotCompiler {
     config: BackendConfig,
@@ -78,24 +88,17 @@ impl AotCompiler {
         modules: &[&IrModule],
         output: &Path,
     ) -> Result<(), BackendError> {
-        if modules.is_empty() {
-            return Err(BackendError::AotError("No modules to compile".into()));
-        }
-
-        // If only one module and no LTO, use single-module path
-        if modules.len() == 1 && self.options.lto_mode == super::LtoMode::None {
-            return self.compile_to_file(modules[0], output);
-        }
-
-        match self.config.kind {
-            BackendKind::LlvmAot => self.compile_modules_llvm(modules, output),
-            BackendKind::CraneliftAot => Err(BackendError::AotError(
-                "Cranelift AOT compilation not yet implemented".into(),
-            )),
-            _ => Err(BackendError::AotError(
-                "AOT compilation requires LlvmAot or CraneliftAot backend".into(),
-            )),
-        }
+        self.check_module_count(modules.len())?;
+        self.with_temp_dir(output, |aot, temp_dir| {
+            let artifact = aot.module_artifact();
+            let mut files = Vec::new();
+            for (i, module) in modules.iter().enumerate() {
+                let file = temp_dir.join(format!("module_{}.{}", i, artifact.extension()));
+                aot.compile_artifact(module, artifact, &file)?;
+                files.push(file);
+            }
+            aot.link_artifacts(&files, output, temp_dir)
+        })
     }
 
     /// Compile an IR module to a file (single module, with optional LTO)
@@ -104,45 +107,62 @@ impl AotCompiler {
         module: &IrModule,
         output: &Path,
     ) -> Result<(), BackendError> {
-        // If LTO is enabled, use LTO path even for single module
-        if self.options.lto_mode != super::LtoMode::None {
-            return self.compile_modules_to_file(&[module], output);
-        }
+        self.compile_modules_to_file(&[module], output)
+    }
 
-        match self.config.kind {
-            BackendKind::LlvmAot => {
-                // Use LLVM backend
-                let obj_file = output.with_extension("o");
-                super::llvm::compile_to_object_file(module, &self.config, &obj_file)?;
-
-                // Link if output format is executable or shared library
-                match self.options.format {
-                    OutputFormat::Executable | OutputFormat::SharedLib => {
-                        // Find runtime library (required)
-                        let runtime_lib = find_runtime_library()?;
-                        super::llvm::linker::link_object_files_with_lto(
-                            std::slice::from_ref(&obj_file),
-                            output,
-                            self.options.format,
-                            Some(&runtime_lib),
-                            self.options.lto_mode,
-                        )?;
-                    }
-                    OutputFormat::Object => {
-                        // Just copy object file to output
-                        std::fs::copy(&obj_file, output).map_err(|e| {
-                            BackendError::AotError(format!("Failed to copy object file: {}", e))
-                        })?;
-                    }
-                    OutputFormat::StaticLib => {
-                        super::llvm::linker::create_static_library(&[obj_file], output)?;
-                    }
+    /// Like [`Self::compile_modules_to_file`], but reuses the per-module
+    /// artifacts in `cache` and stores the ones it compiles. A unit whose
+    /// artifact is cached needs no IR.
+    pub fn compile_cached(
+        &mut self,
+        cache: &BuildCache,
+        units: &[BuildUnit],
+        output: &Path,
+    ) -> Result<(), BackendError> {
+        self.check_module_count(units.len())?;
+        self.with_temp_dir(output, |aot, temp_dir| {
+            let artifact = aot.module_artifact();
+            let mut files = Vec::new();
+            for unit in units {
+                if let Some(file) = cache.get(&unit.key, artifact) {
+                    files.push(file);
+                    continue;
                 }
-
-                Ok(())
+                let module = unit.module.ok_or_else(|| {
+                    BackendError::AotError(format!(
+                        "Module {} is neither cached nor compiled",
+                        unit.key.as_str()
+                    ))
+                })?;
+                let file = temp_dir.join(format!("{}.{}", unit.key.as_str(), artifact.extension()));
+                aot.compile_artifact(module, artifact, &file)?;
+                let cached = cache.store_file(&unit.key, artifact, &file).map_err(|e| {
+                    BackendError::AotError(format!("Failed to store build artifact: {}", e))
+                })?;
+                files.push(cached);
             }
+            aot.link_artifacts(&files, output, temp_dir)
+        })
+    }
+
+    /// What each module compiles to before linking: bitcode when LLVM runs
+    /// LTO over all modules, an object file otherwise
+    pub fn module_artifact(&self) -> Artifact {
+        if self.config.kind == BackendKind::LlvmAot && self.options.lto_mode != LtoMode::None {
+            Artifact::Bitcode
+        } else {
+            Artifact::Object
+        }
+    }
+
+    fn check_module_count(&self, count: usize) -> Result<(), BackendError> {
+        match self.config.kind {
+            _ if count == 0 => Err(BackendError::AotError("No modules to compile".into())),
+            BackendKind::LlvmAot => Ok(()),
+            BackendKind::CraneliftAot if count == 1 => Ok(()),
             BackendKind::CraneliftAot => Err(BackendError::AotError(
-                "Cranelift AOT compilation not yet implemented".into(),
+                "Cranelift AOT compiles a single module; use --backend llvm for multi-module builds"
+                    .into(),
             )),
             _ => Err(BackendError::AotError(
                 "AOT compilation requires LlvmAot or CraneliftAot backend".into(),
@@ -150,15 +170,12 @@ impl AotCompiler {
         }
     }
 
-    /// Compile multiple modules using LLVM (with LTO support)
-    fn compile_modules_llvm(
+    /// Run `f` with a scratch directory next to `output`, removed afterwards
+    fn with_temp_dir<T>(
         &mut self,
-        modules: &[&IrModule],
         output: &Path,
-    ) -> Result<(), BackendError> {
-        use super::llvm::lto;
-        use std::path::PathBuf;
-
+        f: impl FnOnce(&mut Self, &Path) -> Result<T, BackendError>,
+    ) -> Result<T, BackendError> {
         let temp_dir = output
             .parent()
             .unwrap_or_else(|| Path::new("."))
@@ -166,92 +183,106 @@ impl AotCompiler {
         std::fs::create_dir_all(&temp_dir).map_err(|e| {
             BackendError::AotError(format!("Failed to create temp directory: {}", e))
         })?;
+        let result = f(self, &temp_dir);
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        result
+    }
 
-        let mut bitcode_files = Vec::new();
-
-        // Compile each module to bitcode
-        for (i, module) in modules.iter().enumerate() {
-            let bc_file = temp_dir.join(format!("module_{}.bc", i));
-
-            // For now, skip cache (would need source paths tracked)
-            // Compile to bitcode
-            super::llvm::compile_to_bitcode_file(module, &self.config, &bc_file)?;
-            bitcode_files.push(bc_file);
+    /// Compile one module to the artifact it is linked from
+    fn compile_artifact(
+        &mut self,
+        module: &IrModule,
+        artifact: Artifact,
+        file: &Path,
+    ) -> Result<(), BackendError> {
+        match (self.config.kind, artifact) {
+            (BackendKind::LlvmAot, Artifact::Bitcode) => {
+                super::llvm::compile_to_bitcode_file(module, &self.config, file)
+            }
+            (BackendKind::LlvmAot, Artifact::Object) => {
+                super::llvm::compile_to_object_file(module, &self.config, file)
+            }
+            (BackendKind::CraneliftAot, Artifact::Object) => {
+                // Only executables get a C `main`
+                let entry = self.options.format == OutputFormat::Executable;
+                let bytes = super::object::compile_to_object(module, &self.config, entry)?;
+                std::fs::write(file, bytes).map_err(|e| {
+                    BackendError::AotError(format!("Failed to write object file: {}", e))
+                })
+            }
+            (kind, artifact) => Err(BackendError::AotError(format!(
+                "{:?} does not compile modules to {:?}",
+                kind, artifact
+            ))),
         }
+    }
 
-        // Run LTO if enabled
-        let obj_file = if self.options.lto_mode != super::LtoMode::None {
-            let temp_obj = temp_dir.join("lto_output.o");
-            lto::run_lto(
-                &bitcode_files,
-                &temp_obj,
+    /// Produce the output from per-module artifacts, running LTO over
+    /// bitcode and linking executables against the runtime library
+    fn link_artifacts(
+        &mut self,
+        files: &[PathBuf],
+        output: &Path,
+        temp_dir: &Path,
+    ) -> Result<(), BackendError> {
+        let objects = if self.module_artifact() == Artifact::Bitcode {
+            let lto_object = temp_dir.join("lto_output.o");
+            super::llvm::lto::run_lto(
+                files,
+                &lto_object,
                 self.options.lto_mode,
                 self.config.opt_level,
             )?;
-            temp_obj
+            vec![lto_object]
         } else {
-            // No LTO: compile each bitcode to object, then link
-            let mut obj_files = Vec::new();
-            let tools_dir = PathBuf::new(); // Will use PATH
-            for (i, bc_file) in bitcode_files.iter().enumerate() {
-                let obj_file = temp_dir.join(format!("module_{}.o", i));
-                // Use llc to generate object from bitcode
-                lto::generate_object_file(bc_file, &obj_file, self.config.opt_level, &tools_dir)?;
-                obj_files.push(obj_file);
-            }
-
-            // Link objects
-            let linked_obj = temp_dir.join("linked.o");
-            super::llvm::linker::link_object_files(
-                &obj_files,
-                &linked_obj,
-                OutputFormat::Object,
-                None,
-            )?;
-            linked_obj
+            files.to_vec()
         };
 
-        // Link if output format is executable or shared library
         match self.options.format {
             OutputFormat::Executable | OutputFormat::SharedLib => {
-                // Runtime stubs are now implemented directly in LLVM IR (see abi.rs),
-                // so no external runtime library is needed for basic operations.
-                let runtime_lib: Option<std::path::PathBuf> = None;
+                let target = self.config.target.as_ref();
+                let runtime_lib = find_runtime_library(target)?;
+                // Cranelift has no LTO
+                let lto_mode = match self.config.kind {
+                    BackendKind::LlvmAot => self.options.lto_mode,
+                    _ => LtoMode::None,
+                };
                 super::llvm::linker::link_object_files_with_lto(
-                    &[obj_file],
+                    &objects,
                     output,
                     self.options.format,
-                    runtime_lib.as_deref(),
-                    self.options.lto_mode,
-                )?;
+                    Some(&runtime_lib),
+                    lto_mode,
+                    target,
+                    self.options.static_link,
+                )
             }
-            OutputFormat::Object => {
-                std::fs::copy(&obj_file, output).map_err(|e| {
+            OutputFormat::Object => match objects.as_slice() {
+                [object] => std::fs::copy(object, output).map(|_| ()).map_err(|e| {
                     BackendError::AotError(format!("Failed to copy object file: {}", e))
-                })?;
-            }
-            OutputFormat::StaticLib => {
-                super::llvm::linker::create_static_library(&[obj_file], output)?;
-            }
+                }),
+                _ => Err(BackendError::AotError(
+                    "An object file output takes a single module".into(),
+                )),
+            },
+            OutputFormat::StaticLib => super::llvm::linker::create_static_library(&objects, output),
         }
-
-        // Clean up temp directory
-        let _ = std::fs::remove_dir_all(&temp_dir);
-
-        Ok(())
     }
 
     /// Compile an IR module to bytes (object file in memory)
     pub fn compile_to_bytes(&mut self, module: &IrModule) -> Result<Vec<u8>, BackendError> {
         match self.config.kind {
             BackendKind::LlvmAot => {
-                // Use LLVM backend
-                use std::time::{SystemTime, UNIX_EPOCH};
-                let timestamp = SystemTime::now()
-                    .duration_since(UNIX_EPOCH)
-                    .unwrap()
-                    .as_nanos();
-                let temp_file = std::env::temp_dir().join(format!("ot_{}.o", timestamp));
+                // Use LLVM backend; the temp file is named after the build
+                // timestamp and the module, never the process
+                let module_hash = crate::build::hash_bytes(
+                    crate::ir::format::serialize_module(module).as_bytes(),
+                );
+                let temp_file = std::env::temp_dir().join(format!(
+                    "ot_{}_{}.o",
+                    crate::build::deterministic_timestamp(),
+                    &module_hash[..16]
+                ));
                 super::llvm::compile_to_object_file(module, &self.config, &temp_file)?;
 
                 // Read object file bytes
@@ -264,8 +295,11 @@ impl AotCompiler {
 
                 Ok(bytes)
             }
+            BackendKind::CraneliftAot => {
+                super::object::compile_to_object(module, &self.config, false)
+            }
             _ => Err(BackendError::AotError(
-                "AOT compilation to bytes requires LlvmAot backend".into(),
+                "AOT compilation to bytes requires LlvmAot or CraneliftAot backend".into(),
             )),
         }
     }
@@ -286,8 +320,14 @@ impl AotCompiler {
                 super::llvm::compile_to_object_file(modules[0], &self.config, output)?;
                 Ok(())
             }
+            BackendKind::CraneliftAot => {
+                let bytes = super::object::compile_to_object(modules[0], &self.config, false)?;
+                std::fs::write(output, bytes).map_err(|e| {
+                    BackendError::AotError(format!("Failed to write object file: {}", e))
+                })
+            }
             _ => Err(BackendError::AotError(
-                "Object file compilation requires LlvmAot backend".into(),
+                "Object file compilation requires LlvmAot or CraneliftAot backend".into(),
             )),
         }
     }
@@ -320,175 +360,6 @@ pub fn default_target() -> String {
     target_lexicon::Triple::host().to_string()
 }
 
-/// Find or build the runtime library
-///
-/// NOTE: Runtime stubs are now implemented directly in LLVM IR in abi.rs,
-/// so this function is no longer needed for basic programs. It remains
-/// for future use when we want to link additional runtime features.
-fn find_runtime_library() -> Result<PathBuf, BackendError> {
-    // Runtime stubs are now generated in LLVM IR, so no external library needed
-    return Err(BackendError::AotError(
-        "Runtime library not needed - stubs are generated in LLVM IR".into(),
-    ));
-
-    #[allow(unreachable_code)]
-    // Get manifest directory (project root)
-    let manifest_dir = std::env::var("CARGO_MANIFEST_DIR")
-        .map(PathBuf::from)
-        .unwrap_or_else(|_| {
-            // Fall back to current directory if not in cargo build
-            std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."))
-        });
-
-    // Determine profile based on whether we're in release mode
-    // Default to release for AOT builds
-    let profile = if cfg!(debug_assertions) {
-        "debug"
-    } else {
-        "release"
-    };
-    let runtime_lib = manifest_dir
-        .join("target")
-        .join(profile)
-        .join("libruntime.a");
-
-    // If library exists, return it
-    if runtime_lib.exists() {
-        return Ok(runtime_lib);
-    }
-
-    // Library doesn't exist - try to build it on-demand
-    // Note: This may fail due to Rust std linking issues, which is okay for simple programs
-    println!("Building runtime library...");
-    if let Err(e) = build_runtime_library(
-        &manifest_dir,
-        runtime_lib.parent().unwrap(),
-        profile == "release",
-    ) {
-        eprintln!("[WARN] Failed to build runtime library: {}", e);
-        return Err(BackendError::AotError(format!(
-            "Runtime library build failed: {}. Simple programs may work without it.",
-            e
-        )));
-    }
-
-    if runtime_lib.exists() {
-        Ok(runtime_lib)
-    } else {
-        Err(BackendError::AotError(format!(
-            "Failed to build runtime library at {}",
-            runtime_lib.display()
-        )))
-    }
-}
-
-/// Build the runtime library on-demand
-fn build_runtime_library(
-    manifest_dir: &PathBuf,
-    output_dir: &Path,
-    release: bool,
-) -> Result<(), BackendError> {
-    use std::process::Command;
-
-    // Create output directory if it doesn't exist
-    std::fs::create_dir_all(output_dir)
-        .map_err(|e| BackendError::AotError(format!("Failed to create output directory: {}", e)))?;
-
-    let cargo_toml = manifest_dir.join("Cargo.toml");
-    if !cargo_toml.exists() {
-        return Err(BackendError::AotError(format!(
-            "Cargo.toml not found at {}",
-            cargo_toml.display()
-        )));
-    }
-
-    // Clean old rlibs to ensure we build fresh without hashbrown/vm_interop
-    let profile_dir = if release { "release" } else { "debug" };
-    let deps_dir = manifest_dir.join("target").join(profile_dir).join("deps");
-    if let Ok(entries) = std::fs::read_dir(&deps_dir) {
-        for entry in entries.flatten() {
-            let path = entry.path();
-            if let Some(name) = path.file_name().and_then(|n| n.to_str())
-                && name.starts_with("libscript")
-                && name.ends_with(".rlib")
-            {
-                let _ = std::fs::remove_file(&path);
-            }
-        }
-    }
-
-    // Use cargo build to build the library
-    // This will handle dependencies correctly
-    let mut cmd = Command::new("cargo");
-    cmd.arg("build")
-        .arg("--lib")
-        .arg("--manifest-path")
-        .arg(cargo_toml.to_string_lossy().as_ref())
-        // Disable default features (vm_interop) for standalone staticlib
-        .arg("--no-default-features")
-        // Prevent build script from running (avoid recursion)
-        .env("TSCL_BUILDING_RUNTIME", "1");
-
-    // Use release profile (LTO is disabled in Cargo.toml)
-    if release {
-        cmd.arg("--release");
-    }
-
-    let output = cmd
-        .output()
-        .map_err(|e| BackendError::AotError(format!("Failed to execute cargo rustc: {}", e)))?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(BackendError::AotError(format!(
-            "cargo rustc failed to build runtime library:\n{}",
-            stderr
-        )));
-    }
-
-    // cargo build outputs rlib by default
-    // rlibs are actually ar archives and can be used for static linking
-    let profile_dir = if release { "release" } else { "debug" };
-    let deps_dir = manifest_dir.join("target").join(profile_dir).join("deps");
-
-    // Look for libscript*.rlib (cargo build produces rlib by default)
-    let runtime_lib = output_dir.join("libruntime.a");
-
-    // Try to find the rlib file
-    let mut found_lib = None;
-    if let Ok(entries) = std::fs::read_dir(&deps_dir) {
-        for entry in entries.flatten() {
-            let path = entry.path();
-            if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-                // Look for libscript*.rlib files
-                if name.starts_with("libscript") && name.ends_with(".rlib") {
-                    found_lib = Some(path);
-                    break;
-                }
-            }
-        }
-    }
-
-    if let Some(source_lib) = found_lib {
-        // Copy rlib and rename to .a (rlibs are ar archives, compatible with static linking)
-        std::fs::copy(&source_lib, &runtime_lib).map_err(|e| {
-            BackendError::AotError(format!(
-                "Failed to copy {} to {}: {}",
-                source_lib.display(),
-                runtime_lib.display(),
-                e
-            ))
-        })?;
-    } else {
-        return Err(BackendError::AotError(format!(
-            "Runtime library not found after build in {}. Expected libscript*.rlib",
-            deps_dir.display()
-        )));
-    }
-
-    Ok(())
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -513,4 +384,96 @@ mod tests {
         assert_eq!(opts.lto_mode, LtoMode::None);
         assert!(!opts.strip);
     }
+
+    /// LLVM emits x86_64 and aarch64 objects, whichever the host is
+    #[cfg(feature = "llvm")]
+    #[test]
+    fn test_llvm_emits_objects_for_other_targets() {
+        use crate::backend::target::Target;
+        use crate::ir::{IrFunction, Terminator};
+        use object::{Architecture, Object};
+
+        let mut func = IrFunction::new("main".to_string());
+        let entry = func.alloc_block();
+        func.block_mut(entry).terminate(Terminator::Return(None));
+        let mut module = IrModule::new();
+        module.add_function(func);
+
+        let dir = std::env::temp_dir().join(format!("oite_llvm_cross_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for (triple, arch) in [
+            ("aarch64-unknown-linux-gnu", Architecture::Aarch64),
+            ("x86_64-unknown-linux-gnu", Architecture::X86_64),
+        ] {
+            let config = BackendConfig {
+                kind: BackendKind::LlvmAot,
+                target: Some(Target::parse(triple).unwrap()),
+                ..Default::default()
+            };
+            let mut aot = AotCompiler::new(&config).with_options(AotOptions {
+                format: OutputFormat::Object,
+                ..Default::default()
+            });
+            let obj = dir.join(format!("{}.o", triple));
+            aot.compile_to_file(&module, &obj).unwrap();
+            let bytes = std::fs::read(&obj).unwrap();
+            let file = object::File::parse(&*bytes).unwrap();
+            assert_eq!(file.architecture(), arch, "{}", triple);
+        }
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    /// The executable gets string and number ops from the runtime library
+    #[cfg(feature = "llvm")]
+    #[test]
+    fn test_llvm_executable_links_runtime_library() {
+        use crate::ir::{IrFunction, IrOp, IrType, Literal, Terminator};
+
+        if super::super::llvm::linker::detect_linker().is_err() {
+            return;
+        }
+        let mut func = IrFunction::new("main".to_string());
+        let entry = func.alloc_block();
+        let text = func.alloc_value(IrType::String);
+        let logged = func.alloc_value(IrType::Any);
+        let a = func.alloc_value(IrType::Any);
+        let b = func.alloc_value(IrType::Any);
+        let sum = func.alloc_value(IrType::Any);
+        let logged_sum = func.alloc_value(IrType::Any);
+        let block = func.block_mut(entry);
+        block.push(IrOp::Const(text, Literal::String("hello".to_string())));
+        block.push(IrOp::CallMethod(
+            logged,
+            text,
+            "log".to_string(),
+            vec![text],
+        ));
+        block.push(IrOp::Const(a, Literal::Number(40.0)));
+        block.push(IrOp::Const(b, Literal::Number(2.5)));
+        block.push(IrOp::AddAny(sum, a, b));
+        block.push(IrOp::CallMethod(
+            logged_sum,
+            sum,
+            "log".to_string(),
+            vec![sum],
+        ));
+        block.terminate(Terminator::Return(Some(sum)));
+        let mut module = IrModule::new();
+        module.add_function(func);
+
+        let dir = std::env::temp_dir().join(format!("oite_llvm_aot_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let exe = dir.join("sample");
+        let config = BackendConfig {
+            kind: BackendKind::LlvmAot,
+            ..Default::default()
+        };
+        let mut aot = AotCompiler::new(&config);
+        let result = aot.compile_to_file(&module, &exe);
+        let output = result.map(|()| std::process::Command::new(&exe).output().unwrap());
+        let _ = std::fs::remove_dir_all(&dir);
+        let output = output.unwrap();
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "hello\n42.5\n");
+    }
 }
@@ -7,17 +7,26 @@
 //! - Specialized ops (AddNum, etc.) compile to direct FP instructions
 //! - Dynamic ops (AddAny, etc.) call runtime stubs
 //! - Borrow ops are zero-cost (just pointer copies)
+//!
+//! Translation is generic over `cranelift_module::Module`, so the same code
+//! feeds the JIT (`JITModule`) and object-file emission (`ObjectModule`,
+//! see `object.rs`).
 
 use cranelift::prelude::*;
 use cranelift_codegen::ir::{FuncRef, StackSlot};
 use cranelift_codegen::settings;
 use cranelift_jit::{JITBuilder, JITModule};
-use cranelift_module::{FuncId, Linkage, Module};
+use cranelift_module::{DataDescription, FuncId, Linkage, Module};
 use std::collections::HashMap;
 
-use super::layout::VALUE_SIZE;
+use super::layout::{VALUE_SIZE, compute_array_layout};
 use super::{BackendConfig, BackendError};
-use crate::ir::{BasicBlock, BlockId, IrFunction, IrModule, IrOp, Literal, Terminator, ValueId};
+use crate::ir::stubs::{CompileStrategy, InlineOp, compile_strategy};
+use crate::ir::{
+    BasicBlock, BlockId, ElementKind, IrFunction, IrModule, IrOp, IrType, Literal, MathFn,
+    Terminator, ValueId,
+};
+use crate::runtime::heap::NativeTypedArray;
 
 /// Cranelift code generator
 #[allow(dead_code)]
@@ -34,6 +43,8 @@ pub struct CraneliftCodegen {
     compiled_funcs: HashMap<String, *const u8>,
     /// Backend configuration
     config: BackendConfig,
+    /// Wrap compiled functions in calls to the profiler hooks
+    profile: bool,
 }
 
 impl CraneliftCodegen {
@@ -72,9 +83,17 @@ impl CraneliftCodegen {
             stubs: HashMap::new(),
             compiled_funcs: HashMap::new(),
             config: config.clone(),
+            profile: false,
         })
     }
 
+    /// Instrument functions compiled from now on for the profiler: each one
+    /// reports entry and exit through `ot_profile_enter` and `ot_profile_exit`
+    /// with its index in the IR module
+    pub fn set_profiling(&mut self, enabled: bool) {
+        self.profile = enabled;
+    }
+
     /// Register runtime stub functions as symbols for the JIT
     fn register_runtime_symbols(builder: &mut JITBuilder) {
         use crate::runtime::stubs::*;
@@ -83,6 +102,7 @@ impl CraneliftCodegen {
         builder.symbol("ot_alloc_object", ot_alloc_object as *const u8);
         builder.symbol("ot_alloc_array", ot_alloc_array as *const u8);
         builder.symbol("ot_alloc_string", ot_alloc_string as *const u8);
+        builder.symbol("ot_new_typed_array", ot_new_typed_array as *const u8);
 
         // Property access stubs
         builder.symbol("ot_get_prop", ot_get_prop as *const u8);
@@ -112,10 +132,23 @@ impl CraneliftCodegen {
 
         // Console/IO stubs
         builder.symbol("ot_console_log", ot_console_log as *const u8);
+        builder.symbol("ot_performance_now", ot_performance_now as *const u8);
         builder.symbol("ot_call", ot_call as *const u8);
 
         // Closure stubs
         builder.symbol("ot_make_closure", ot_make_closure as *const u8);
+
+        // Profiler hooks
+        builder.symbol(
+            "ot_profile_enter",
+            super::jit::ot_profile_enter as *const u8,
+        );
+        builder.symbol("ot_profile_exit", super::jit::ot_profile_exit as *const u8);
+
+        // Math stubs
+        for (name, address) in math_stub_symbols() {
+            builder.symbol(name, address);
+        }
     }
 
     /// Declare a runtime stub function in the module
@@ -150,6 +183,9 @@ impl CraneliftCodegen {
         // Step 1: Declare all functions first (so we can reference them from each other)
         let mut func_ids: HashMap<String, FuncId> = HashMap::new();
         let mut func_sigs: HashMap<String, Signature> = HashMap::new();
+        // When profiling, functions are called through a wrapper holding
+        // their name, and their code is defined under a local body symbol
+        let mut body_ids: HashMap<String, FuncId> = HashMap::new();
 
         for func in &ir_module.functions {
             let func_name = if func.name.is_empty() {
@@ -175,12 +211,26 @@ impl CraneliftCodegen {
                     ))
                 })?;
 
+            if self.profile {
+                let body_name = format!("{}$body", func_name);
+                let body_id = self
+                    .module
+                    .declare_function(&body_name, Linkage::Local, &sig)
+                    .map_err(|e| {
+                        BackendError::Cranelift(format!(
+                            "Failed to declare function {}: {}",
+                            body_name, e
+                        ))
+                    })?;
+                body_ids.insert(func_name.clone(), body_id);
+            }
+
             func_ids.insert(func_name.clone(), func_id);
             func_sigs.insert(func_name, sig);
         }
 
         // Step 2: Compile each function
-        for func in &ir_module.functions {
+        for (index, func) in ir_module.functions.iter().enumerate() {
             let func_name = if func.name.is_empty() {
                 "anonymous".to_string()
             } else {
@@ -193,20 +243,38 @@ impl CraneliftCodegen {
             // Build the function body with access to all declared functions
             {
                 let mut builder = FunctionBuilder::new(&mut self.ctx.func, &mut self.builder_ctx);
-                translate_function(&mut builder, &mut self.module, func, ir_module, &func_ids)?;
+                translate_function(
+                    &mut builder,
+                    &mut self.module,
+                    func,
+                    ir_module,
+                    &func_ids,
+                    false,
+                )?;
                 builder.finalize();
             }
 
             // Define the function
             let func_id = func_ids[&func_name];
+            let body_id = body_ids.get(&func_name).copied().unwrap_or(func_id);
             self.module
-                .define_function(func_id, &mut self.ctx)
+                .define_function(body_id, &mut self.ctx)
                 .map_err(|e| {
                     BackendError::Cranelift(format!(
                         "Failed to compile function {}: {}",
                         func_name, e
                     ))
                 })?;
+
+            if body_id != func_id {
+                self.define_profile_wrapper(index, func_id, body_id, &func_sigs[&func_name])
+                    .map_err(|e| {
+                        BackendError::Cranelift(format!(
+                            "Failed to compile profiling wrapper of {}: {}",
+                            func_name, e
+                        ))
+                    })?;
+            }
         }
 
         // Step 3: Finalize all definitions
@@ -223,6 +291,50 @@ impl CraneliftCodegen {
         Ok(())
     }
 
+    /// Define `wrapper` as a call of `body` between the profiler's entry and
+    /// exit hooks for function `index`
+    fn define_profile_wrapper(
+        &mut self,
+        index: usize,
+        wrapper: FuncId,
+        body: FuncId,
+        sig: &Signature,
+    ) -> Result<(), Box<cranelift_module::ModuleError>> {
+        let mut hook_sig = self.module.make_signature();
+        hook_sig.params.push(AbiParam::new(types::I64));
+        hook_sig.returns.push(AbiParam::new(types::I64));
+        let enter = self
+            .module
+            .declare_function("ot_profile_enter", Linkage::Import, &hook_sig)?;
+        let exit = self
+            .module
+            .declare_function("ot_profile_exit", Linkage::Import, &hook_sig)?;
+
+        self.ctx.clear();
+        self.ctx.func.signature = sig.clone();
+        {
+            let mut builder = FunctionBuilder::new(&mut self.ctx.func, &mut self.builder_ctx);
+            let block = builder.create_block();
+            builder.append_block_params_for_function_params(block);
+            builder.switch_to_block(block);
+            builder.seal_block(block);
+
+            let enter = self.module.declare_func_in_func(enter, builder.func);
+            let body = self.module.declare_func_in_func(body, builder.func);
+            let exit = self.module.declare_func_in_func(exit, builder.func);
+            let args = builder.block_params(block).to_vec();
+            let index = builder.ins().iconst(types::I64, index as i64);
+            builder.ins().call(enter, &[index]);
+            let call = builder.ins().call(body, &args);
+            let result = builder.inst_results(call)[0];
+            builder.ins().call(exit, &[index]);
+            builder.ins().return_(&[result]);
+            builder.finalize();
+        }
+        self.module.define_function(wrapper, &mut self.ctx)?;
+        Ok(())
+    }
+
     /// Compile a single function (legacy method - use compile_module for better inter-function calls)
     pub fn compile_function(
         &mut self,
@@ -265,7 +377,14 @@ impl CraneliftCodegen {
 
             // Create empty func_ids map for single-function compilation
             let func_ids = HashMap::new();
-            translate_function(&mut builder, &mut self.module, func, ir_module, &func_ids)?;
+            translate_function(
+                &mut builder,
+                &mut self.module,
+                func,
+                ir_module,
+                &func_ids,
+                false,
+            )?;
 
             builder.finalize();
         }
@@ -300,14 +419,16 @@ impl CraneliftCodegen {
 }
 
 /// Translate a function from tscl IR to Cranelift IR
-fn translate_function(
+pub(crate) fn translate_function<M: Module>(
     builder: &mut FunctionBuilder,
-    module: &mut JITModule,
+    module: &mut M,
     ir_func: &IrFunction,
     ir_module: &IrModule,
     func_ids: &HashMap<String, FuncId>,
+    aot: bool,
 ) -> Result<(), BackendError> {
     let mut ctx = TranslationContext {
+        aot,
         values: HashMap::new(),
         blocks: HashMap::new(),
         locals: Vec::new(),
@@ -318,6 +439,7 @@ fn translate_function(
         local_stores: HashMap::new(),
         phi_params: HashMap::new(),
         block_phis: HashMap::new(),
+        value_types: &ir_func.value_types,
     };
 
     // Create Cranelift blocks for each IR block
@@ -399,6 +521,10 @@ fn translate_function(
 
 /// Translation context holding state during function translation
 struct TranslationContext<'a> {
+    /// Emitting a relocatable object rather than JIT code: string constants
+    /// are stored as data and allocated at run time instead of being embedded
+    /// as pointers into the compiler's heap
+    aot: bool,
     /// Map from IR ValueId to Cranelift Value
     values: HashMap<ValueId, Value>,
     /// Map from IR BlockId to Cranelift Block
@@ -419,12 +545,14 @@ struct TranslationContext<'a> {
     phi_params: HashMap<ValueId, (BlockId, usize)>,
     /// Phi entries for each block: BlockId -> Vec<(dst, entries)>
     block_phis: HashMap<BlockId, Vec<(ValueId, Vec<(BlockId, ValueId)>)>>,
+    /// Inferred value types, for inline typed array access
+    value_types: &'a HashMap<ValueId, IrType>,
 }
 
 /// Translate a single basic block
-fn translate_block(
+fn translate_block<M: Module>(
     builder: &mut FunctionBuilder,
-    module: &mut JITModule,
+    module: &mut M,
     ctx: &mut TranslationContext,
     block: &BasicBlock,
 ) -> Result<(), BackendError> {
@@ -440,14 +568,31 @@ fn translate_block(
 }
 
 /// Translate a single IR operation
-fn translate_op(
+fn translate_op<M: Module>(
     builder: &mut FunctionBuilder,
-    module: &mut JITModule,
+    module: &mut M,
     ctx: &mut TranslationContext,
     op: &IrOp,
 ) -> Result<(), BackendError> {
     match op {
         // === Constants ===
+        IrOp::Const(dst, Literal::String(text)) if ctx.aot => {
+            let data_id = module.declare_anonymous_data(false, false).map_err(|e| {
+                BackendError::Cranelift(format!("Failed to declare string data: {}", e))
+            })?;
+            let mut data = DataDescription::new();
+            data.define(text.as_bytes().to_vec().into_boxed_slice());
+            module.define_data(data_id, &data).map_err(|e| {
+                BackendError::Cranelift(format!("Failed to define string data: {}", e))
+            })?;
+            let global = module.declare_data_in_func(data_id, builder.func);
+            let ptr = builder.ins().symbol_value(types::I64, global);
+            let len = builder.ins().iconst(types::I64, text.len() as i64);
+            let val = call_stub_with_values(builder, module, ctx, "ot_alloc_string", &[ptr, len])?;
+            ctx.values.insert(*dst, val);
+            ctx.constants.insert(*dst, Literal::String(text.clone()));
+        }
+
         IrOp::Const(dst, lit) => {
             let val = translate_literal(builder, lit);
             ctx.values.insert(*dst, val);
@@ -663,13 +808,19 @@ fn translate_op(
         }
 
         IrOp::GetElement(dst, obj, idx) => {
-            let result = call_stub(builder, module, ctx, "ot_get_element", &[*obj, *idx])?;
+            let result = match typed_array_kind(ctx, *obj) {
+                Some(kind) => translate_typed_array_get(builder, module, ctx, kind, *obj, *idx)?,
+                None => call_stub(builder, module, ctx, "ot_get_element", &[*obj, *idx])?,
+            };
             ctx.values.insert(*dst, result);
         }
 
-        IrOp::SetElement(obj, idx, val) => {
-            call_stub(builder, module, ctx, "ot_set_element", &[*obj, *idx, *val])?;
-        }
+        IrOp::SetElement(obj, idx, val) => match typed_array_kind(ctx, *obj) {
+            Some(kind) => translate_typed_array_set(builder, module, ctx, kind, *obj, *idx, *val)?,
+            None => {
+                call_stub(builder, module, ctx, "ot_set_element", &[*obj, *idx, *val])?;
+            }
+        },
 
         // === Array Operations ===
         IrOp::NewArray(dst) => {
@@ -679,6 +830,14 @@ fn translate_op(
             ctx.values.insert(*dst, result);
         }
 
+        IrOp::NewTypedArray(dst, kind, src) => {
+            let kind = builder.ins().iconst(types::I64, *kind as i64);
+            let src = get_value(ctx, *src)?;
+            let result =
+                call_stub_with_values(builder, module, ctx, "ot_new_typed_array", &[kind, src])?;
+            ctx.values.insert(*dst, result);
+        }
+
         IrOp::ArrayLen(dst, arr) => {
             // Get length property
             let result = call_stub(builder, module, ctx, "ot_get_prop", &[*arr])?;
@@ -890,6 +1049,45 @@ fn translate_op(
             ));
         }
 
+        // === Math ===
+        IrOp::MathAny(dst, func, args) | IrOp::MathNum(dst, func, args) => {
+            let result = match compile_strategy(op) {
+                CompileStrategy::Inline(inline) => {
+                    let x = match args.first() {
+                        Some(&arg) => get_value(ctx, arg)?,
+                        None => translate_literal(builder, &Literal::Number(f64::NAN)),
+                    };
+                    let fx = builder.ins().bitcast(types::F64, MemFlags::new(), x);
+                    let result = match inline {
+                        InlineOp::FAbs => builder.ins().fabs(fx),
+                        InlineOp::FSqrt => builder.ins().sqrt(fx),
+                        InlineOp::FFloor => builder.ins().floor(fx),
+                        InlineOp::FCeil => builder.ins().ceil(fx),
+                        InlineOp::FTrunc => builder.ins().trunc(fx),
+                        InlineOp::FRound32 => {
+                            let single = builder.ins().fdemote(types::F32, fx);
+                            builder.ins().fpromote(types::F64, single)
+                        }
+                        other => {
+                            return Err(BackendError::UnsupportedOp(format!(
+                                "{:?} for Math.{}",
+                                other,
+                                func.name()
+                            )));
+                        }
+                    };
+                    builder.ins().bitcast(types::I64, MemFlags::new(), result)
+                }
+                _ => call_math_stub(builder, module, ctx, *func, args)?,
+            };
+            ctx.values.insert(*dst, result);
+        }
+
+        IrOp::PerformanceNow(dst) => {
+            let result = call_stub_no_args(builder, module, ctx, "ot_performance_now")?;
+            ctx.values.insert(*dst, result);
+        }
+
         // TypeOf - returns type string (not yet implemented)
         IrOp::TypeOf(dst, _val) => {
             // TODO: Implement typeof by calling a runtime stub
@@ -995,7 +1193,7 @@ fn translate_literal_static(lit: &Literal) -> Value {
 }
 
 /// Translate a literal to a Cranelift value
-fn translate_literal(builder: &mut FunctionBuilder, lit: &Literal) -> Value {
+pub(crate) fn translate_literal(builder: &mut FunctionBuilder, lit: &Literal) -> Value {
     match lit {
         Literal::Number(n) => {
             let bits = n.to_bits();
@@ -1055,9 +1253,9 @@ fn resolve_function_address(ctx: &TranslationContext, func_val: ValueId) -> Opti
 }
 
 /// Call a function indirectly using the ot_call runtime stub.
-fn call_indirect_function(
+fn call_indirect_function<M: Module>(
     builder: &mut FunctionBuilder,
-    module: &mut JITModule,
+    module: &mut M,
     ctx: &mut TranslationContext,
     func_ptr: Value,
     args: &[Value],
@@ -1079,10 +1277,201 @@ fn call_indirect_function(
     }
 }
 
+/// The element kind of a value known to be a typed array
+fn typed_array_kind(ctx: &TranslationContext, id: ValueId) -> Option<ElementKind> {
+    ctx.value_types.get(&id)?.scalar_element()
+}
+
+/// The address of element `idx` of typed array `arr`, relative to the
+/// layout's header, in a new block that is reached only when `arr` is a
+/// pointer, `idx` an integral index below its length and `extra` (if any)
+/// holds. Otherwise control goes to `fallback`.
+fn typed_array_element(
+    builder: &mut FunctionBuilder,
+    kind: ElementKind,
+    arr: Value,
+    idx: Value,
+    extra: Option<Value>,
+    fallback: Block,
+) -> Value {
+    const QNAN_POINTER_TAG: i64 = 0x7FFC;
+    const PAYLOAD_MASK: i64 = 0x0000_FFFF_FFFF_FFFF;
+    let layout = compute_array_layout(&IrType::Scalar(kind));
+
+    // An allocation failure leaves undefined rather than a pointer
+    let tag = builder.ins().ushr_imm(arr, 48);
+    let is_pointer = builder.ins().icmp_imm(IntCC::Equal, tag, QNAN_POINTER_TAG);
+    let pointer_block = builder.create_block();
+    builder
+        .ins()
+        .brif(is_pointer, pointer_block, &[], fallback, &[]);
+
+    builder.switch_to_block(pointer_block);
+    let base = builder.ins().band_imm(arr, PAYLOAD_MASK);
+    let len_offset = std::mem::offset_of!(NativeTypedArray, len) as i32;
+    let len = builder.ins().uload32(MemFlags::trusted(), base, len_offset);
+
+    // Non-numbers are NaN, which converts to 0 but does not convert back
+    let index = builder.ins().bitcast(types::F64, MemFlags::new(), idx);
+    let i = builder.ins().fcvt_to_sint_sat(types::I64, index);
+    let back = builder.ins().fcvt_from_sint(types::F64, i);
+    let integral = builder.ins().fcmp(FloatCC::Equal, back, index);
+    let in_bounds = builder.ins().icmp(IntCC::UnsignedLessThan, i, len);
+    let mut ok = builder.ins().band(integral, in_bounds);
+    if let Some(extra) = extra {
+        ok = builder.ins().band(ok, extra);
+    }
+    let element_block = builder.create_block();
+    builder.ins().brif(ok, element_block, &[], fallback, &[]);
+
+    builder.switch_to_block(element_block);
+    let offset = builder.ins().imul_imm(i, layout.element_size as i64);
+    builder.ins().iadd(base, offset)
+}
+
+/// `arr[idx]` on a typed array: an inline load and conversion to a number,
+/// or `ot_get_element` for other keys and indices out of bounds
+fn translate_typed_array_get<M: Module>(
+    builder: &mut FunctionBuilder,
+    module: &mut M,
+    ctx: &mut TranslationContext,
+    kind: ElementKind,
+    arr: ValueId,
+    idx: ValueId,
+) -> Result<Value, BackendError> {
+    let arr = get_value(ctx, arr)?;
+    let idx = get_value(ctx, idx)?;
+    let fallback = builder.create_block();
+    let done = builder.create_block();
+    builder.append_block_param(done, types::I64);
+
+    let addr = typed_array_element(builder, kind, arr, idx, None, fallback);
+    let header = compute_array_layout(&IrType::Scalar(kind)).element_offset(0) as i32;
+    let flags = MemFlags::trusted();
+    let ins = builder.ins();
+    let number = match kind {
+        ElementKind::Int8 => {
+            let v = ins.sload8(types::I64, flags, addr, header);
+            builder.ins().fcvt_from_sint(types::F64, v)
+        }
+        ElementKind::Uint8 | ElementKind::Uint8Clamped => {
+            let v = ins.uload8(types::I64, flags, addr, header);
+            builder.ins().fcvt_from_uint(types::F64, v)
+        }
+        ElementKind::Int16 => {
+            let v = ins.sload16(types::I64, flags, addr, header);
+            builder.ins().fcvt_from_sint(types::F64, v)
+        }
+        ElementKind::Uint16 => {
+            let v = ins.uload16(types::I64, flags, addr, header);
+            builder.ins().fcvt_from_uint(types::F64, v)
+        }
+        ElementKind::Int32 => {
+            let v = ins.sload32(flags, addr, header);
+            builder.ins().fcvt_from_sint(types::F64, v)
+        }
+        ElementKind::Uint32 => {
+            let v = ins.uload32(flags, addr, header);
+            builder.ins().fcvt_from_uint(types::F64, v)
+        }
+        ElementKind::Float32 => {
+            let v = ins.load(types::F32, flags, addr, header);
+            builder.ins().fpromote(types::F64, v)
+        }
+        ElementKind::Float64 => ins.load(types::F64, flags, addr, header),
+    };
+    let mut bits = builder.ins().bitcast(types::I64, MemFlags::new(), number);
+    if kind.is_float() {
+        // A NaN payload from the buffer must not read as a tagged value
+        let is_nan = builder.ins().fcmp(FloatCC::Unordered, number, number);
+        let nan = builder.ins().iconst(types::I64, f64::NAN.to_bits() as i64);
+        bits = builder.ins().select(is_nan, nan, bits);
+    }
+    builder.ins().jump(done, &[bits]);
+
+    builder.switch_to_block(fallback);
+    let result = call_stub_with_values(builder, module, ctx, "ot_get_element", &[arr, idx])?;
+    builder.ins().jump(done, &[result]);
+
+    builder.switch_to_block(done);
+    Ok(builder.block_params(done)[0])
+}
+
+/// `arr[idx] = val` on a typed array: an inline conversion and store, or
+/// `ot_set_element` for other keys, indices out of bounds and integers too
+/// large to wrap with a 64-bit conversion
+fn translate_typed_array_set<M: Module>(
+    builder: &mut FunctionBuilder,
+    module: &mut M,
+    ctx: &mut TranslationContext,
+    kind: ElementKind,
+    arr: ValueId,
+    idx: ValueId,
+    val: ValueId,
+) -> Result<(), BackendError> {
+    let arr_val = get_value(ctx, arr)?;
+    let idx_val = get_value(ctx, idx)?;
+    let mut bits = get_value(ctx, val)?;
+    if ctx.value_types.get(&val) != Some(&IrType::Number) {
+        bits = call_stub_with_values(builder, module, ctx, "ot_to_number", &[bits])?;
+    }
+    let number = builder.ins().bitcast(types::F64, MemFlags::new(), bits);
+
+    let (stored, in_range) = match kind {
+        ElementKind::Float64 => (number, None),
+        ElementKind::Float32 => (builder.ins().fdemote(types::F32, number), None),
+        ElementKind::Uint8Clamped => {
+            // NaN stays NaN through min and max, and converts to 0
+            let zero = builder.ins().f64const(0.0);
+            let max = builder.ins().f64const(255.0);
+            let clamped = builder.ins().fmin(number, max);
+            let clamped = builder.ins().fmax(clamped, zero);
+            let rounded = builder.ins().nearest(clamped);
+            let byte = builder.ins().fcvt_to_sint_sat(types::I32, rounded);
+            (builder.ins().ireduce(types::I8, byte), None)
+        }
+        _ => {
+            // Truncating keeps the low bits of ToUint32 while it is exact
+            let magnitude = builder.ins().fabs(number);
+            let limit = builder.ins().f64const(9_223_372_036_854_775_808.0);
+            let in_range = builder.ins().fcmp(FloatCC::LessThan, magnitude, limit);
+            let wide = builder.ins().fcvt_to_sint_sat(types::I64, number);
+            let narrow = match kind.size() {
+                1 => builder.ins().ireduce(types::I8, wide),
+                2 => builder.ins().ireduce(types::I16, wide),
+                _ => builder.ins().ireduce(types::I32, wide),
+            };
+            (narrow, Some(in_range))
+        }
+    };
+
+    let fallback = builder.create_block();
+    let done = builder.create_block();
+    let addr = typed_array_element(builder, kind, arr_val, idx_val, in_range, fallback);
+    let header = compute_array_layout(&IrType::Scalar(kind)).element_offset(0) as i32;
+    builder
+        .ins()
+        .store(MemFlags::trusted(), stored, addr, header);
+    builder.ins().jump(done, &[]);
+
+    builder.switch_to_block(fallback);
+    call_stub_with_values(
+        builder,
+        module,
+        ctx,
+        "ot_set_element",
+        &[arr_val, idx_val, bits],
+    )?;
+    builder.ins().jump(done, &[]);
+
+    builder.switch_to_block(done);
+    Ok(())
+}
+
 /// Call a runtime stub with IR value IDs as arguments
-fn call_stub(
+fn call_stub<M: Module>(
     builder: &mut FunctionBuilder,
-    module: &mut JITModule,
+    module: &mut M,
     ctx: &mut TranslationContext,
     name: &str,
     args: &[ValueId],
@@ -1096,9 +1485,9 @@ fn call_stub(
 }
 
 /// Call a runtime stub with Cranelift values as arguments
-fn call_stub_with_values(
+fn call_stub_with_values<M: Module>(
     builder: &mut FunctionBuilder,
-    module: &mut JITModule,
+    module: &mut M,
     ctx: &mut TranslationContext,
     name: &str,
     args: &[Value],
@@ -1139,10 +1528,39 @@ fn call_stub_with_values(
     Ok(result)
 }
 
+/// Call the `ot_math_*` stub of a Math function. Missing arguments are
+/// undefined, and variadic functions fold their two-argument stub.
+fn call_math_stub<M: Module>(
+    builder: &mut FunctionBuilder,
+    module: &mut M,
+    ctx: &mut TranslationContext,
+    func: MathFn,
+    args: &[ValueId],
+) -> Result<Value, BackendError> {
+    let mut values = args
+        .iter()
+        .map(|&arg| get_value(ctx, arg))
+        .collect::<Result<Vec<_>, _>>()?;
+    match func.identity() {
+        Some(identity) => {
+            let mut acc = translate_literal(builder, &Literal::Number(identity));
+            for value in values {
+                acc = call_stub_with_values(builder, module, ctx, func.stub_name(), &[acc, value])?;
+            }
+            Ok(acc)
+        }
+        None => {
+            let undefined = translate_literal(builder, &Literal::Undefined);
+            values.resize(func.arity(), undefined);
+            call_stub_with_values(builder, module, ctx, func.stub_name(), &values)
+        }
+    }
+}
+
 /// Call a runtime stub with no arguments
-fn call_stub_no_args(
+fn call_stub_no_args<M: Module>(
     builder: &mut FunctionBuilder,
-    module: &mut JITModule,
+    module: &mut M,
     ctx: &mut TranslationContext,
     name: &str,
 ) -> Result<Value, BackendError> {
@@ -1150,7 +1568,7 @@ fn call_stub_no_args(
 }
 
 /// Convert a Cranelift boolean to a NaN-boxed boolean
-fn bool_to_ot_value(builder: &mut FunctionBuilder, b: Value) -> Value {
+pub(crate) fn bool_to_ot_value(builder: &mut FunctionBuilder, b: Value) -> Value {
     const QNAN: u64 = 0x7FFC_0000_0000_0000;
     const TAG_BOOLEAN: u64 = 0x0001_0000_0000_0000;
     let base = builder
@@ -1161,7 +1579,7 @@ fn bool_to_ot_value(builder: &mut FunctionBuilder, b: Value) -> Value {
 }
 
 /// Convert a NaN-boxed boolean to a Cranelift boolean
-fn ot_value_to_bool(builder: &mut FunctionBuilder, val: Value) -> Value {
+pub(crate) fn ot_value_to_bool(builder: &mut FunctionBuilder, val: Value) -> Value {
     // Check if the low bit is set (for booleans)
     // This is a simplified check - proper impl would check type tag
     let one = builder.ins().iconst(types::I64, 1);
@@ -4,6 +4,7 @@
 //! It manages compiled functions and provides the interface for executing
 //! native code.
 
+use std::cell::RefCell;
 use std::collections::HashMap;
 
 use super::cranelift::CraneliftCodegen;
@@ -11,6 +12,39 @@ use super::{BackendConfig, BackendError};
 use crate::ir::IrModule;
 use crate::runtime::abi::OtValue;
 
+/// Receives `(function index, entered)` for each call of an instrumented function
+pub type ProfileHook = Box<dyn FnMut(usize, bool)>;
+
+thread_local! {
+    static PROFILE_HOOK: RefCell<Option<ProfileHook>> = const { RefCell::new(None) };
+}
+
+/// Install the hook that functions compiled with profiling report to on this
+/// thread, or remove it with `None`
+pub fn set_profile_hook(hook: Option<ProfileHook>) {
+    PROFILE_HOOK.with(|slot| *slot.borrow_mut() = hook);
+}
+
+fn profile_event(index: u64, entered: bool) {
+    PROFILE_HOOK.with(|slot| {
+        if let Some(hook) = slot.borrow_mut().as_mut() {
+            hook(index as usize, entered);
+        }
+    });
+}
+
+/// Called on entry to a function compiled with profiling
+pub extern "C" fn ot_profile_enter(index: u64) -> u64 {
+    profile_event(index, true);
+    0
+}
+
+/// Called before a function compiled with profiling returns
+pub extern "C" fn ot_profile_exit(index: u64) -> u64 {
+    profile_event(index, false);
+    0
+}
+
 /// JIT runtime for executing compiled code
 pub struct JitRuntime {
     /// The Cranelift code generator
@@ -34,6 +68,12 @@ impl JitRuntime {
         self.codegen.get_func(name)
     }
 
+    /// Report entry and exit of functions compiled from now on to the
+    /// thread's profile hook (see [`set_profile_hook`])
+    pub fn set_profiling(&mut self, enabled: bool) {
+        self.codegen.set_profiling(enabled);
+    }
+
     /// Compile an IR module
     pub fn compile(&mut self, module: &IrModule) -> Result<(), BackendError> {
         // Use the new compile_module method for proper inter-function call support
@@ -167,7 +207,9 @@ impl CompiledFunction {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::ir::{IrFunction, IrModule, IrOp, IrType, Literal, Terminator, ValueId};
+    use crate::ir::{
+        ElementKind, IrFunction, IrModule, IrOp, IrType, Literal, Terminator, ValueId,
+    };
 
     #[test]
     fn test_jit_runtime_creation() {
@@ -247,4 +289,44 @@ mod tests {
         let val = result.unwrap();
         assert_eq!(val.as_number(), Some(7.0));
     }
+
+    #[test]
+    fn test_compile_typed_array_access() {
+        let config = BackendConfig::default();
+        let mut runtime = JitRuntime::new(&config).unwrap();
+
+        // store(n, i, x) { let a = new Uint8ClampedArray(n); a[i] = x; return a[i]; }
+        let mut func = IrFunction::new("store".to_string());
+        func.params = vec![
+            ("n".to_string(), IrType::Number),
+            ("i".to_string(), IrType::Number),
+            ("x".to_string(), IrType::Number),
+        ];
+        let entry = func.alloc_block();
+        let (n, i, x) = (ValueId(0), ValueId(1), ValueId(2));
+        for param in [n, i, x] {
+            func.value_types.insert(param, IrType::Number);
+        }
+        let kind = ElementKind::Uint8Clamped;
+        let arr = func.alloc_value(IrType::typed_array(kind));
+        let elem = func.alloc_value(IrType::Number);
+        let block = func.block_mut(entry);
+        block.push(IrOp::NewTypedArray(arr, kind, n));
+        block.push(IrOp::SetElement(arr, i, x));
+        block.push(IrOp::GetElement(elem, arr, i));
+        block.terminate(Terminator::Return(Some(elem)));
+
+        let mut module = IrModule::new();
+        module.add_function(func);
+        runtime.compile(&module).unwrap();
+
+        let store = |n: f64, i: f64, x: f64| {
+            let args = [OtValue::number(n), OtValue::number(i), OtValue::number(x)];
+            runtime.call_func("store", &args).unwrap()
+        };
+        assert_eq!(store(4.0, 3.0, 300.0).as_number(), Some(255.0));
+        assert_eq!(store(4.0, 0.0, 2.5).as_number(), Some(2.0));
+        assert!(store(4.0, 4.0, 1.0).is_undefined());
+        assert!(store(4.0, 1.5, 1.0).is_undefined());
+    }
 }
@@ -130,6 +130,9 @@ pub fn value_size(ty: &IrType) -> u32 {
         // Typed arrays: same as untyped (pointer to heap)
         IrType::TypedArray(_) => VALUE_SIZE,
 
+        // Typed array elements: packed at their own width
+        IrType::Scalar(kind) => kind.size() as u32,
+
         // Struct reference: pointer to heap-allocated struct
         IrType::Struct(_) => VALUE_SIZE,
 
@@ -147,6 +150,7 @@ pub fn value_align(ty: &IrType) -> u32 {
     // All values are 8-byte aligned for NaN-boxing compatibility
     match ty {
         IrType::Void | IrType::Never => 1,
+        IrType::Scalar(kind) => kind.size() as u32,
         _ => VALUE_ALIGN,
     }
 }
@@ -190,9 +194,9 @@ pub fn compute_array_layout(element_ty: &IrType) -> ArrayLayout {
     let element_size = value_size(element_ty);
     let element_align = value_align(element_ty);
 
-    // Array header: length (u32) + capacity (u32) + pointer to data
-    // In our NaN-boxed world, arrays are heap objects with inline header
-    let header_size = 16; // ObjectHeader + length + capacity
+    // Elements are inline after the header: ObjectHeader (12 bytes) + length
+    // (u32), as in `runtime::heap::NativeTypedArray`
+    let header_size = 16;
 
     ArrayLayout {
         element_ty: element_ty.clone(),
@@ -286,6 +290,20 @@ mod tests {
         assert_eq!(layout.size_for_length(10), 16 + 80); // header + 10 elements
     }
 
+    #[test]
+    fn test_typed_array_layout_matches_native_heap() {
+        use crate::runtime::heap::NativeTypedArray;
+        use crate::runtime::typed_array::ElementKind;
+
+        let layout = compute_array_layout(&IrType::Scalar(ElementKind::Int16));
+        assert_eq!(layout.element_size, 2);
+        assert_eq!(
+            layout.element_offset(0) as usize,
+            std::mem::size_of::<NativeTypedArray>()
+        );
+        assert_eq!(layout.element_offset(3), 22);
+    }
+
     #[test]
     fn test_align_up() {
         assert_eq!(align_up(0, 8), 0);
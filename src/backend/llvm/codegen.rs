@@ -11,9 +11,13 @@ use std::collections::{BTreeMap, HashMap};
 use std::ffi::{CString, c_char};
 
 use crate::backend::BackendError;
+use crate::backend::layout::compute_array_layout;
+use crate::ir::stubs::{CompileStrategy, InlineOp, compile_strategy};
 use crate::ir::{
-    BasicBlock, BlockId, IrFunction, IrModule, IrOp, IrType, Literal, Terminator, ValueId,
+    BasicBlock, BlockId, ElementKind, IrFunction, IrModule, IrOp, IrType, Literal, MathFn,
+    Terminator, ValueId,
 };
+use crate::runtime::heap::NativeTypedArray;
 
 use super::abi;
 use super::types;
@@ -37,9 +41,7 @@ impl LlvmCodegen {
     pub fn new(target_triple: String) -> Result<Self, BackendError> {
         unsafe {
             // Initialize LLVM
-            llvm_sys::target::LLVM_InitializeNativeTarget();
-            llvm_sys::target::LLVM_InitializeNativeAsmPrinter();
-            llvm_sys::target::LLVM_InitializeNativeAsmParser();
+            super::object::initialize_targets();
 
             // Create context
             let context = llvm_sys::core::LLVMContextCreate();
@@ -327,6 +329,7 @@ impl LlvmCodegen {
                 functions: &self.functions,
                 function_addrs: &ir_module.function_addrs,
                 return_ty: func.return_ty.clone(),
+                value_types: &func.value_types,
             };
 
             // Create blocks for all IR blocks
@@ -438,6 +441,8 @@ struct TranslationContext<'a> {
     function_addrs: &'a HashMap<usize, usize>,
     /// Function return type (for handling Return(None) correctly)
     return_ty: IrType,
+    /// Inferred value types, for inline typed array access
+    value_types: &'a HashMap<ValueId, IrType>,
 }
 
 /// Translate a basic block
@@ -933,14 +938,37 @@ unsafe fn translate_op(ctx: &mut TranslationContext, op: &IrOp) -> Result<(), Ba
             IrOp::GetElement(dst, obj, idx) => {
                 let obj_val = get_value(ctx, *obj)?;
                 let idx_val = get_value(ctx, *idx)?;
-                let result = call_stub(ctx, "ot_get_element", &[obj_val, idx_val])?;
+                let result = match typed_array_kind(ctx, *obj) {
+                    Some(kind) => translate_typed_array_get(ctx, kind, obj_val, idx_val)?,
+                    None => call_stub(ctx, "ot_get_element", &[obj_val, idx_val])?,
+                };
                 ctx.values.insert(*dst, result);
             }
             IrOp::SetElement(obj, idx, val) => {
                 let obj_val = get_value(ctx, *obj)?;
                 let idx_val = get_value(ctx, *idx)?;
-                let val_val = get_value(ctx, *val)?;
-                call_stub(ctx, "ot_set_element", &[obj_val, idx_val, val_val])?;
+                let mut val_val = get_value(ctx, *val)?;
+                match typed_array_kind(ctx, *obj) {
+                    Some(kind) => {
+                        if ctx.value_types.get(val) != Some(&IrType::Number) {
+                            val_val = call_stub(ctx, "ot_to_number", &[val_val])?;
+                        }
+                        translate_typed_array_set(ctx, kind, obj_val, idx_val, val_val)?;
+                    }
+                    None => {
+                        call_stub(ctx, "ot_set_element", &[obj_val, idx_val, val_val])?;
+                    }
+                }
+            }
+            IrOp::NewTypedArray(dst, kind, src) => {
+                let kind = llvm_sys::core::LLVMConstInt(
+                    llvm_sys::core::LLVMInt64TypeInContext(ctx.context),
+                    *kind as u64,
+                    0,
+                );
+                let src_val = get_value(ctx, *src)?;
+                let result = call_stub(ctx, "ot_new_typed_array", &[kind, src_val])?;
+                ctx.values.insert(*dst, result);
             }
             IrOp::NewArray(dst) => {
                 let capacity = llvm_sys::core::LLVMConstInt(
@@ -976,6 +1004,65 @@ unsafe fn translate_op(ctx: &mut TranslationContext, op: &IrOp) -> Result<(), Ba
                     ctx.values.insert(*dst, undefined);
                 }
             }
+            IrOp::PerformanceNow(dst) => {
+                let result = call_stub(ctx, "ot_performance_now", &[])?;
+                ctx.values.insert(*dst, result);
+            }
+            IrOp::MathAny(dst, func, args) | IrOp::MathNum(dst, func, args) => {
+                let result = match compile_strategy(op) {
+                    CompileStrategy::Inline(inline) => {
+                        let x = match args.first() {
+                            Some(&arg) => get_value(ctx, arg)?,
+                            None => translate_literal(ctx, &Literal::Number(f64::NAN))?,
+                        };
+                        let double_ty = llvm_sys::core::LLVMDoubleTypeInContext(ctx.context);
+                        let i64_ty = llvm_sys::core::LLVMInt64TypeInContext(ctx.context);
+                        let fx = llvm_sys::core::LLVMBuildBitCast(
+                            ctx.builder,
+                            x,
+                            double_ty,
+                            b"bitcast\0".as_ptr() as *const c_char,
+                        );
+                        let result_fp = match inline {
+                            InlineOp::FAbs => call_f64_intrinsic(ctx, "llvm.fabs.f64", fx),
+                            InlineOp::FSqrt => call_f64_intrinsic(ctx, "llvm.sqrt.f64", fx),
+                            InlineOp::FFloor => call_f64_intrinsic(ctx, "llvm.floor.f64", fx),
+                            InlineOp::FCeil => call_f64_intrinsic(ctx, "llvm.ceil.f64", fx),
+                            InlineOp::FTrunc => call_f64_intrinsic(ctx, "llvm.trunc.f64", fx),
+                            InlineOp::FRound32 => {
+                                let float_ty = llvm_sys::core::LLVMFloatTypeInContext(ctx.context);
+                                let single = llvm_sys::core::LLVMBuildFPTrunc(
+                                    ctx.builder,
+                                    fx,
+                                    float_ty,
+                                    b"fround\0".as_ptr() as *const c_char,
+                                );
+                                llvm_sys::core::LLVMBuildFPExt(
+                                    ctx.builder,
+                                    single,
+                                    double_ty,
+                                    b"fround\0".as_ptr() as *const c_char,
+                                )
+                            }
+                            other => {
+                                return Err(BackendError::UnsupportedOp(format!(
+                                    "{:?} for Math.{}",
+                                    other,
+                                    func.name()
+                                )));
+                            }
+                        };
+                        llvm_sys::core::LLVMBuildBitCast(
+                            ctx.builder,
+                            result_fp,
+                            i64_ty,
+                            b"bitcast\0".as_ptr() as *const c_char,
+                        )
+                    }
+                    _ => call_math_stub(ctx, *func, args)?,
+                };
+                ctx.values.insert(*dst, result);
+            }
             IrOp::MakeClosure(dst, addr, env) => {
                 let func_addr = llvm_sys::core::LLVMConstInt(
                     llvm_sys::core::LLVMInt64TypeInContext(ctx.context),
@@ -1094,9 +1181,31 @@ unsafe fn translate_literal(
                 };
                 Ok(llvm_sys::core::LLVMConstInt(ty, qnan | tag, 0))
             }
-            Literal::String(_s) => {
-                let ty = llvm_sys::core::LLVMInt64TypeInContext(ctx.context);
-                Ok(llvm_sys::core::LLVMConstInt(ty, 0, 0))
+            Literal::String(s) => {
+                // The bytes live in a private global; the runtime copies them
+                // into a heap string
+                let bytes = llvm_sys::core::LLVMConstStringInContext(
+                    ctx.context,
+                    s.as_ptr() as *const c_char,
+                    s.len() as u32,
+                    1,
+                );
+                let global = llvm_sys::core::LLVMAddGlobal(
+                    ctx.module,
+                    llvm_sys::core::LLVMTypeOf(bytes),
+                    b".str\0".as_ptr() as *const c_char,
+                );
+                llvm_sys::core::LLVMSetInitializer(global, bytes);
+                llvm_sys::core::LLVMSetGlobalConstant(global, 1);
+                llvm_sys::core::LLVMSetLinkage(global, llvm_sys::LLVMLinkage::LLVMPrivateLinkage);
+                let i8_ptr_ty = llvm_sys::core::LLVMPointerType(
+                    llvm_sys::core::LLVMInt8TypeInContext(ctx.context),
+                    0,
+                );
+                let data = llvm_sys::core::LLVMConstPointerCast(global, i8_ptr_ty);
+                let i64_ty = llvm_sys::core::LLVMInt64TypeInContext(ctx.context);
+                let len = llvm_sys::core::LLVMConstInt(i64_ty, s.len() as u64, 0);
+                call_stub(ctx, "ot_alloc_string", &[data, len])
             }
         }
     }
@@ -1153,11 +1262,16 @@ unsafe fn call_stub(
             .copied()
             .ok_or_else(|| BackendError::Llvm(format!("Runtime stub not found: {}", name)))?;
 
-        let name_cstr = CString::new(name).unwrap();
+        let fn_ty = llvm_sys::core::LLVMGlobalGetValueType(stub);
+        // Calls returning void cannot be named
+        let returns_void =
+            llvm_sys::core::LLVMGetTypeKind(llvm_sys::core::LLVMGetReturnType(fn_ty))
+                == llvm_sys::LLVMTypeKind::LLVMVoidTypeKind;
+        let name_cstr = CString::new(if returns_void { "" } else { name }).unwrap();
         let mut args_mut = args.to_vec();
         let call = llvm_sys::core::LLVMBuildCall2(
             ctx.builder,
-            llvm_sys::core::LLVMGlobalGetValueType(stub),
+            fn_ty,
             stub,
             args_mut.as_mut_ptr(),
             args_mut.len() as u32,
@@ -1168,6 +1282,342 @@ unsafe fn call_stub(
     }
 }
 
+/// Call the `ot_math_*` stub of a Math function. Missing arguments are
+/// undefined, and variadic functions fold their two-argument stub.
+unsafe fn call_math_stub(
+    ctx: &TranslationContext,
+    func: MathFn,
+    args: &[ValueId],
+) -> Result<LLVMValueRef, BackendError> {
+    unsafe {
+        let mut values: Vec<LLVMValueRef> = args
+            .iter()
+            .map(|id| get_value(ctx, *id))
+            .collect::<Result<_, _>>()?;
+        match func.identity() {
+            Some(identity) => {
+                let mut acc = translate_literal(ctx, &Literal::Number(identity))?;
+                for value in values {
+                    acc = call_stub(ctx, func.stub_name(), &[acc, value])?;
+                }
+                Ok(acc)
+            }
+            None => {
+                let undefined = translate_literal(ctx, &Literal::Undefined)?;
+                values.resize(func.arity(), undefined);
+                call_stub(ctx, func.stub_name(), &values)
+            }
+        }
+    }
+}
+
+/// The element kind of a value known to be a typed array
+fn typed_array_kind(ctx: &TranslationContext, id: ValueId) -> Option<ElementKind> {
+    ctx.value_types.get(&id)?.scalar_element()
+}
+
+/// A new basic block at the end of the current function
+unsafe fn append_block(ctx: &TranslationContext, name: &[u8]) -> LLVMBasicBlockRef {
+    unsafe {
+        llvm_sys::core::LLVMAppendBasicBlockInContext(
+            ctx.context,
+            ctx.func_val,
+            name.as_ptr() as *const c_char,
+        )
+    }
+}
+
+/// The address of element `idx` of typed array `arr`, in a new block that
+/// is reached only when `arr` is a pointer, `idx` an integral index below
+/// its length and `extra` (if any) holds. Otherwise control goes to
+/// `fallback`. Mirrors the Cranelift backend's inline access.
+unsafe fn typed_array_element(
+    ctx: &TranslationContext,
+    kind: ElementKind,
+    arr: LLVMValueRef,
+    idx: LLVMValueRef,
+    extra: Option<LLVMValueRef>,
+    fallback: LLVMBasicBlockRef,
+) -> LLVMValueRef {
+    unsafe {
+        use llvm_sys::core::*;
+        use llvm_sys::{LLVMIntPredicate, LLVMRealPredicate};
+
+        let b = ctx.builder;
+        let i8_ty = LLVMInt8TypeInContext(ctx.context);
+        let i32_ty = LLVMInt32TypeInContext(ctx.context);
+        let i64_ty = LLVMInt64TypeInContext(ctx.context);
+        let double_ty = LLVMDoubleTypeInContext(ctx.context);
+        let ptr_ty = LLVMPointerType(i8_ty, 0);
+        let layout = compute_array_layout(&IrType::Scalar(kind));
+
+        // An allocation failure leaves undefined rather than a pointer
+        let tag = LLVMBuildLShr(b, arr, LLVMConstInt(i64_ty, 48, 0), b"tag\0".as_ptr() as _);
+        let is_pointer = LLVMBuildICmp(
+            b,
+            LLVMIntPredicate::LLVMIntEQ,
+            tag,
+            LLVMConstInt(i64_ty, 0x7FFC, 0),
+            b"is_ptr\0".as_ptr() as _,
+        );
+        let pointer_block = append_block(ctx, b"typed.ptr\0");
+        LLVMBuildCondBr(b, is_pointer, pointer_block, fallback);
+
+        LLVMPositionBuilderAtEnd(b, pointer_block);
+        let payload = LLVMConstInt(i64_ty, 0x0000_FFFF_FFFF_FFFF, 0);
+        let base = LLVMBuildAnd(b, arr, payload, b"base\0".as_ptr() as _);
+        let base = LLVMBuildIntToPtr(b, base, ptr_ty, b"base_ptr\0".as_ptr() as _);
+        let len_offset = std::mem::offset_of!(NativeTypedArray, len) as u64;
+        let mut indices = [LLVMConstInt(i64_ty, len_offset, 0)];
+        let len_ptr = LLVMBuildGEP2(
+            b,
+            i8_ty,
+            base,
+            indices.as_mut_ptr(),
+            1,
+            b"len_ptr\0".as_ptr() as _,
+        );
+        let len_ptr = LLVMBuildBitCast(
+            b,
+            len_ptr,
+            LLVMPointerType(i32_ty, 0),
+            b"len_ptr\0".as_ptr() as _,
+        );
+        let len = LLVMBuildLoad2(b, i32_ty, len_ptr, b"len\0".as_ptr() as _);
+        let len = LLVMBuildUIToFP(b, len, double_ty, b"len_f\0".as_ptr() as _);
+
+        // Non-numbers are NaN, which fails every ordered comparison
+        let index = LLVMBuildBitCast(b, idx, double_ty, b"index\0".as_ptr() as _);
+        let floor = call_f64_intrinsic(ctx, "llvm.floor.f64", index);
+        let integral = LLVMBuildFCmp(
+            b,
+            LLVMRealPredicate::LLVMRealOEQ,
+            index,
+            floor,
+            b"integral\0".as_ptr() as _,
+        );
+        let non_negative = LLVMBuildFCmp(
+            b,
+            LLVMRealPredicate::LLVMRealOGE,
+            index,
+            LLVMConstReal(double_ty, 0.0),
+            b"non_negative\0".as_ptr() as _,
+        );
+        let in_bounds = LLVMBuildFCmp(
+            b,
+            LLVMRealPredicate::LLVMRealOLT,
+            index,
+            len,
+            b"in_bounds\0".as_ptr() as _,
+        );
+        let mut ok = LLVMBuildAnd(b, integral, non_negative, b"ok\0".as_ptr() as _);
+        ok = LLVMBuildAnd(b, ok, in_bounds, b"ok\0".as_ptr() as _);
+        if let Some(extra) = extra {
+            ok = LLVMBuildAnd(b, ok, extra, b"ok\0".as_ptr() as _);
+        }
+        let element_block = append_block(ctx, b"typed.elem\0");
+        LLVMBuildCondBr(b, ok, element_block, fallback);
+
+        LLVMPositionBuilderAtEnd(b, element_block);
+        let i = LLVMBuildFPToUI(b, index, i64_ty, b"i\0".as_ptr() as _);
+        let offset = LLVMBuildMul(
+            b,
+            i,
+            LLVMConstInt(i64_ty, layout.element_size as u64, 0),
+            b"offset\0".as_ptr() as _,
+        );
+        let offset = LLVMBuildAdd(
+            b,
+            offset,
+            LLVMConstInt(i64_ty, layout.element_offset(0) as u64, 0),
+            b"offset\0".as_ptr() as _,
+        );
+        let mut indices = [offset];
+        let addr = LLVMBuildGEP2(
+            b,
+            i8_ty,
+            base,
+            indices.as_mut_ptr(),
+            1,
+            b"elem_ptr\0".as_ptr() as _,
+        );
+        LLVMBuildBitCast(
+            b,
+            addr,
+            LLVMPointerType(element_type(ctx, kind), 0),
+            b"elem_ptr\0".as_ptr() as _,
+        )
+    }
+}
+
+/// The LLVM type elements of `kind` are stored as
+unsafe fn element_type(ctx: &TranslationContext, kind: ElementKind) -> LLVMTypeRef {
+    unsafe {
+        use llvm_sys::core::*;
+        match kind {
+            ElementKind::Float32 => LLVMFloatTypeInContext(ctx.context),
+            ElementKind::Float64 => LLVMDoubleTypeInContext(ctx.context),
+            _ => LLVMIntTypeInContext(ctx.context, kind.size() as u32 * 8),
+        }
+    }
+}
+
+/// `arr[idx]` on a typed array: an inline load and conversion to a number,
+/// or `ot_get_element` for other keys and indices out of bounds
+unsafe fn translate_typed_array_get(
+    ctx: &TranslationContext,
+    kind: ElementKind,
+    arr: LLVMValueRef,
+    idx: LLVMValueRef,
+) -> Result<LLVMValueRef, BackendError> {
+    unsafe {
+        use llvm_sys::LLVMRealPredicate;
+        use llvm_sys::core::*;
+
+        let b = ctx.builder;
+        let i64_ty = LLVMInt64TypeInContext(ctx.context);
+        let double_ty = LLVMDoubleTypeInContext(ctx.context);
+        let fallback = append_block(ctx, b"typed.get_slow\0");
+        let done = append_block(ctx, b"typed.get_done\0");
+
+        let addr = typed_array_element(ctx, kind, arr, idx, None, fallback);
+        let raw = LLVMBuildLoad2(b, element_type(ctx, kind), addr, b"elem\0".as_ptr() as _);
+        let number = match kind {
+            ElementKind::Int8 | ElementKind::Int16 | ElementKind::Int32 => {
+                LLVMBuildSIToFP(b, raw, double_ty, b"num\0".as_ptr() as _)
+            }
+            ElementKind::Float32 => LLVMBuildFPExt(b, raw, double_ty, b"num\0".as_ptr() as _),
+            ElementKind::Float64 => raw,
+            _ => LLVMBuildUIToFP(b, raw, double_ty, b"num\0".as_ptr() as _),
+        };
+        let mut bits = LLVMBuildBitCast(b, number, i64_ty, b"bits\0".as_ptr() as _);
+        if kind.is_float() {
+            // A NaN payload from the buffer must not read as a tagged value
+            let is_nan = LLVMBuildFCmp(
+                b,
+                LLVMRealPredicate::LLVMRealUNO,
+                number,
+                number,
+                b"is_nan\0".as_ptr() as _,
+            );
+            let nan = LLVMConstInt(i64_ty, f64::NAN.to_bits(), 0);
+            bits = LLVMBuildSelect(b, is_nan, nan, bits, b"bits\0".as_ptr() as _);
+        }
+        let fast_end = LLVMGetInsertBlock(b);
+        LLVMBuildBr(b, done);
+
+        LLVMPositionBuilderAtEnd(b, fallback);
+        let slow = call_stub(ctx, "ot_get_element", &[arr, idx])?;
+        let slow_end = LLVMGetInsertBlock(b);
+        LLVMBuildBr(b, done);
+
+        LLVMPositionBuilderAtEnd(b, done);
+        let phi = LLVMBuildPhi(b, i64_ty, b"elem\0".as_ptr() as _);
+        let mut values = [bits, slow];
+        let mut blocks = [fast_end, slow_end];
+        LLVMAddIncoming(phi, values.as_mut_ptr(), blocks.as_mut_ptr(), 2);
+        Ok(phi)
+    }
+}
+
+/// `arr[idx] = number` on a typed array: an inline conversion and store, or
+/// `ot_set_element` for other keys, indices out of bounds and integers too
+/// large to wrap with a 64-bit conversion
+unsafe fn translate_typed_array_set(
+    ctx: &TranslationContext,
+    kind: ElementKind,
+    arr: LLVMValueRef,
+    idx: LLVMValueRef,
+    number_bits: LLVMValueRef,
+) -> Result<(), BackendError> {
+    unsafe {
+        use llvm_sys::LLVMRealPredicate;
+        use llvm_sys::core::*;
+
+        let b = ctx.builder;
+        let i64_ty = LLVMInt64TypeInContext(ctx.context);
+        let double_ty = LLVMDoubleTypeInContext(ctx.context);
+        let stored_ty = element_type(ctx, kind);
+        let number = LLVMBuildBitCast(b, number_bits, double_ty, b"num\0".as_ptr() as _);
+        let fcmp = |pred, x, y| LLVMBuildFCmp(b, pred, x, y, b"cmp\0".as_ptr() as _);
+        let constant = |x: f64| LLVMConstReal(double_ty, x);
+
+        // Integer conversion is exact while truncation fits in 64 bits; NaN
+        // and infinities (which store 0) take the slow path too
+        let in_range = match kind {
+            ElementKind::Float32 | ElementKind::Float64 | ElementKind::Uint8Clamped => None,
+            _ => {
+                let magnitude = call_f64_intrinsic(ctx, "llvm.fabs.f64", number);
+                Some(fcmp(
+                    LLVMRealPredicate::LLVMRealOLT,
+                    magnitude,
+                    constant(9_223_372_036_854_775_808.0),
+                ))
+            }
+        };
+
+        let fallback = append_block(ctx, b"typed.set_slow\0");
+        let done = append_block(ctx, b"typed.set_done\0");
+        let addr = typed_array_element(ctx, kind, arr, idx, in_range, fallback);
+        let stored = match kind {
+            ElementKind::Float64 => number,
+            ElementKind::Float32 => LLVMBuildFPTrunc(b, number, stored_ty, b"f32\0".as_ptr() as _),
+            ElementKind::Uint8Clamped => {
+                let zero = constant(0.0);
+                let max = constant(255.0);
+                let is_nan = fcmp(LLVMRealPredicate::LLVMRealUNO, number, number);
+                let x = LLVMBuildSelect(b, is_nan, zero, number, b"x\0".as_ptr() as _);
+                let below = fcmp(LLVMRealPredicate::LLVMRealOLT, x, zero);
+                let x = LLVMBuildSelect(b, below, zero, x, b"x\0".as_ptr() as _);
+                let above = fcmp(LLVMRealPredicate::LLVMRealOGT, x, max);
+                let x = LLVMBuildSelect(b, above, max, x, b"x\0".as_ptr() as _);
+                let rounded = call_f64_intrinsic(ctx, "llvm.roundeven.f64", x);
+                LLVMBuildFPToUI(b, rounded, stored_ty, b"u8\0".as_ptr() as _)
+            }
+            _ => {
+                let wide = LLVMBuildFPToSI(b, number, i64_ty, b"wide\0".as_ptr() as _);
+                LLVMBuildTrunc(b, wide, stored_ty, b"narrow\0".as_ptr() as _)
+            }
+        };
+        LLVMBuildStore(b, stored, addr);
+        LLVMBuildBr(b, done);
+
+        LLVMPositionBuilderAtEnd(b, fallback);
+        call_stub(ctx, "ot_set_element", &[arr, idx, number_bits])?;
+        LLVMBuildBr(b, done);
+
+        LLVMPositionBuilderAtEnd(b, done);
+        Ok(())
+    }
+}
+
+/// Call a `double (double)` LLVM intrinsic such as `llvm.floor.f64`
+unsafe fn call_f64_intrinsic(
+    ctx: &TranslationContext,
+    name: &str,
+    x: LLVMValueRef,
+) -> LLVMValueRef {
+    unsafe {
+        let double_ty = llvm_sys::core::LLVMDoubleTypeInContext(ctx.context);
+        let mut param_types = [double_ty];
+        let func_ty = llvm_sys::core::LLVMFunctionType(double_ty, param_types.as_mut_ptr(), 1, 0);
+        let name_cstr = CString::new(name).unwrap();
+        let mut intrinsic = llvm_sys::core::LLVMGetNamedFunction(ctx.module, name_cstr.as_ptr());
+        if intrinsic.is_null() {
+            intrinsic = llvm_sys::core::LLVMAddFunction(ctx.module, name_cstr.as_ptr(), func_ty);
+        }
+        let mut args = [x];
+        llvm_sys::core::LLVMBuildCall2(
+            ctx.builder,
+            func_ty,
+            intrinsic,
+            args.as_mut_ptr(),
+            1,
+            b"math\0".as_ptr() as *const c_char,
+        )
+    }
+}
+
 /// Call a function indirectly (or directly if it's a known function)
 ///
 /// This generates a direct LLVM call by:
@@ -3,10 +3,14 @@
 //! This module provides functions to link object files with the runtime library
 //! using external linkers (clang/ld).
 
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::process::{Command, Stdio};
+use std::sync::{Mutex, OnceLock};
 
+use super::super::target::Target;
 use super::super::{BackendError, LtoMode, aot::OutputFormat};
+use crate::build::normalize_command_env;
 
 /// Link object files with runtime library to create an executable or library
 pub fn link_object_files(
@@ -15,21 +19,39 @@ pub fn link_object_files(
     format: OutputFormat,
     runtime_lib: Option<&Path>,
 ) -> Result<(), BackendError> {
-    link_object_files_with_lto(objects, output, format, runtime_lib, LtoMode::None)
+    link_object_files_with_lto(
+        objects,
+        output,
+        format,
+        runtime_lib,
+        LtoMode::None,
+        None,
+        false,
+    )
 }
 
-/// Link object files with runtime library, supporting LTO
+/// Link object files with runtime library, supporting LTO.
+///
+/// `target` selects a cross linker (`None` links for the host); with
+/// `static_link` the executable is linked with `-static`.
 pub fn link_object_files_with_lto(
     objects: &[PathBuf],
     output: &Path,
     format: OutputFormat,
     runtime_lib: Option<&Path>,
     lto_mode: LtoMode,
+    target: Option<&Target>,
+    static_link: bool,
 ) -> Result<(), BackendError> {
     // Detect linker (prefer clang, fall back to cc/ld)
-    let linker = detect_linker()?;
+    let (linker, target_args) = detect_target_linker(target)?;
 
     let mut cmd = Command::new(&linker);
+    normalize_command_env(&mut cmd);
+    cmd.args(&target_args);
+    if static_link && format == OutputFormat::Executable {
+        cmd.arg("-static");
+    }
 
     // Add LTO flags if LTO is enabled
     if lto_mode != LtoMode::None {
@@ -122,8 +144,8 @@ pub fn link_object_files_with_lto(
             cmd.arg(lib);
         }
 
-        // Runtime uses Vec/String from std, which should be statically linked in libruntime.a
-        // No additional libraries needed since we removed HashMap dependency
+        // The runtime is a Rust staticlib; std needs these system libraries
+        cmd.args(runtime_system_libs(target));
     }
 
     // Set output format
@@ -171,11 +193,101 @@ pub fn link_object_files_with_lto(
     Ok(())
 }
 
+/// System libraries a Rust `staticlib` depends on, as rustc lists them for
+/// `target`. The list is queried once per target; if rustc can't be asked,
+/// the usual list for the platform stands in.
+fn runtime_system_libs(target: Option<&Target>) -> Vec<String> {
+    static QUERIED: OnceLock<Mutex<HashMap<Option<Target>, Option<Vec<String>>>>> = OnceLock::new();
+
+    // musl's libunwind ships with the Rust target, not the C toolchain
+    if let Some(target) = target.filter(|target| target.is_musl()) {
+        let mut args = rust_self_contained_dir(target)
+            .map(|dir| vec![format!("-L{}", dir.display())])
+            .unwrap_or_default();
+        args.extend(["-lunwind", "-lc"].map(String::from));
+        return args;
+    }
+    let queried = QUERIED.get_or_init(Default::default);
+    let libs = queried
+        .lock()
+        .unwrap()
+        .entry(target.cloned())
+        .or_insert_with(|| query_native_static_libs(target))
+        .clone();
+    libs.unwrap_or_else(|| default_system_libs(target))
+}
+
+/// `rustc --print native-static-libs` for an empty `staticlib`, which lists
+/// what the standard library needs
+fn query_native_static_libs(target: Option<&Target>) -> Option<Vec<String>> {
+    let out_dir = std::env::temp_dir().join(format!("oite_native_libs_{}", std::process::id()));
+    let mut cmd = Command::new("rustc");
+    cmd.args([
+        "--crate-type",
+        "staticlib",
+        "--crate-name",
+        "native_libs_probe",
+    ])
+    .args(["--print", "native-static-libs", "--out-dir"])
+    .arg(&out_dir)
+    .stdin(Stdio::null());
+    if let Some(target) = target {
+        cmd.arg("--target").arg(target.to_string());
+    }
+    let output = cmd.arg("-").output();
+    let _ = std::fs::remove_dir_all(&out_dir);
+    let output = output.ok().filter(|output| output.status.success())?;
+    // The list comes as a note on stderr
+    String::from_utf8_lossy(&output.stderr)
+        .lines()
+        .find_map(|line| line.split_once("native-static-libs:"))
+        .map(|(_, libs)| libs.split_whitespace().map(String::from).collect())
+}
+
+/// The libraries rustc usually lists for the platform of `target`
+fn default_system_libs(target: Option<&Target>) -> Vec<String> {
+    let libs: &[&str] = if target.map_or(cfg!(target_os = "macos"), Target::is_macos) {
+        &["-lSystem", "-lc", "-lm"]
+    } else {
+        &[
+            "-lgcc_s",
+            "-lutil",
+            "-lrt",
+            "-lpthread",
+            "-lm",
+            "-ldl",
+            "-lc",
+        ]
+    };
+    libs.iter().map(|lib| lib.to_string()).collect()
+}
+
+/// `lib/self-contained` of the Rust standard library for `target`
+fn rust_self_contained_dir(target: &Target) -> Option<PathBuf> {
+    let output = Command::new("rustc")
+        .args(["--print", "sysroot"])
+        .output()
+        .ok()?;
+    let sysroot = String::from_utf8(output.stdout).ok()?;
+    let dir = Path::new(sysroot.trim())
+        .join("lib/rustlib")
+        .join(target.to_string())
+        .join("lib/self-contained");
+    dir.is_dir().then_some(dir)
+}
+
 /// Create a static library from object files
 pub fn create_static_library(objects: &[PathBuf], output: &Path) -> Result<(), BackendError> {
-    // Use ar to create static library
+    // Use ar to create static library, with zeroed member timestamps and
+    // owners (`D`; Apple `ar` takes `ZERO_AR_DATE` instead)
     let mut cmd = Command::new("ar");
-    cmd.arg("rcs").arg(output);
+    normalize_command_env(&mut cmd);
+    let flags = if cfg!(target_os = "macos") {
+        "rcs"
+    } else {
+        "rcsD"
+    };
+    cmd.arg(flags).arg(output);
 
     for obj in objects {
         cmd.arg(obj);
@@ -252,7 +364,7 @@ fn link_with_rustc(
 
 /// Detect available linker on the system
 /// For Rust runtime libraries, prefer rustc which handles std linking automatically
-fn detect_linker() -> Result<String, BackendError> {
+pub fn detect_linker() -> Result<String, BackendError> {
     // If we have Rust runtime dependencies, use rustc for linking
     // This ensures libstd and other Rust libraries are linked correctly
     if Command::new("rustc").arg("--version").output().is_ok() {
@@ -280,3 +392,31 @@ fn detect_linker() -> Result<String, BackendError> {
         "No suitable linker found (tried clang, gcc, cc)".into(),
     ))
 }
+
+/// Linker for `target` (`None` for the host) and the arguments that make it
+/// link for that target: a GCC cross toolchain, or `clang --target`
+pub fn detect_target_linker(
+    target: Option<&Target>,
+) -> Result<(String, Vec<String>), BackendError> {
+    let target = match target {
+        Some(target) if !target.is_host() => target,
+        _ => return Ok((detect_linker()?, Vec::new())),
+    };
+    let found = |linker: &str| Command::new(linker).arg("--version").output().is_ok();
+
+    let mut gcc = vec![format!("{}-gcc", target.gcc_prefix())];
+    if target.is_musl() && target.triple().architecture == Target::host().triple().architecture {
+        gcc.push("musl-gcc".to_string());
+    }
+    if let Some(linker) = gcc.iter().find(|linker| found(linker)) {
+        return Ok((linker.clone(), Vec::new()));
+    }
+    if found("clang") {
+        return Ok(("clang".to_string(), vec![format!("--target={}", target)]));
+    }
+    Err(BackendError::Llvm(format!(
+        "No linker found for {} (tried {}, clang)",
+        target,
+        gcc.join(", ")
+    )))
+}
@@ -65,6 +65,9 @@ pub fn run_thinlto(
         let mut cmd = Command::new(&llvm_lto);
         cmd.arg("-o").arg(output_obj);
 
+        // Position-independent code links into PIE executables
+        cmd.arg("--relocation-model=pic");
+
         // Export main symbol to prevent elimination
         cmd.arg("--exported-symbol=main");
         cmd.arg("--exported-symbol=_main");
@@ -384,6 +387,7 @@ pub fn generate_object_file(
     let mut cmd = Command::new(&llc);
     cmd.arg("-filetype=obj")
         .arg("--function-sections") // Preserve function sections
+        .arg("--relocation-model=pic") // Links into PIE executables
         .arg("-o")
         .arg(output_file);
 
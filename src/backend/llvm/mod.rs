@@ -2,6 +2,10 @@
 //!
 //! This module provides AOT compilation using LLVM. It translates tscl SSA IR
 //! to LLVM IR and generates optimized native object files.
+//!
+//! Everything that talks to LLVM is behind the `llvm` feature. The linker and
+//! LTO driver only shell out to external tools, so the Cranelift AOT backend
+//! can use them in builds without LLVM.
 
 // Allow these for LLVM FFI code
 #![allow(clippy::manual_c_str_literals)]
@@ -9,18 +13,25 @@
 #![allow(clippy::type_complexity)]
 #![allow(clippy::uninit_vec)]
 
+#[cfg(feature = "llvm")]
 pub mod abi;
+#[cfg(feature = "llvm")]
 pub mod bitcode;
-pub mod cache;
+#[cfg(feature = "llvm")]
 pub mod codegen;
 pub mod linker;
 pub mod lto;
+#[cfg(feature = "llvm")]
 pub mod object;
+#[cfg(feature = "llvm")]
 pub mod optimizer;
+#[cfg(feature = "llvm")]
 pub mod types;
 
+#[cfg(feature = "llvm")]
 pub use codegen::LlvmCodegen;
 
+#[cfg(feature = "llvm")]
 use std::ffi::c_char;
 use std::path::Path;
 
@@ -28,13 +39,14 @@ use crate::backend::{BackendConfig, BackendError};
 use crate::ir::IrModule;
 
 /// Compile an IR module and emit an object file
+#[cfg(feature = "llvm")]
 pub fn compile_to_object_file(
     module: &IrModule,
     config: &BackendConfig,
     output_path: &Path,
 ) -> Result<(), BackendError> {
     // Get target triple
-    let target_triple = object::get_default_target_triple()?;
+    let target_triple = object::target_triple(config)?;
 
     // Create codegen
     let mut codegen = LlvmCodegen::new(target_triple.clone())?;
@@ -61,13 +73,14 @@ pub fn compile_to_object_file(
 }
 
 /// Compile an IR module and emit a bitcode file
+#[cfg(feature = "llvm")]
 pub fn compile_to_bitcode_file(
     module: &IrModule,
     config: &BackendConfig,
     output_path: &Path,
 ) -> Result<(), BackendError> {
     // Get target triple
-    let target_triple = object::get_default_target_triple()?;
+    let target_triple = object::target_triple(config)?;
 
     // Create codegen
     let mut codegen = LlvmCodegen::new(target_triple.clone())?;
@@ -107,13 +120,14 @@ pub fn compile_to_bitcode_file(
 }
 
 /// Compile an IR module and emit an LLVM IR text file
+#[cfg(feature = "llvm")]
 pub fn compile_to_llvm_ir_file(
     module: &IrModule,
     config: &BackendConfig,
     output_path: &Path,
 ) -> Result<(), BackendError> {
     // Get target triple
-    let target_triple = object::get_default_target_triple()?;
+    let target_triple = object::target_triple(config)?;
 
     // Create codegen
     let mut codegen = LlvmCodegen::new(target_triple.clone())?;
@@ -176,3 +190,40 @@ pub fn compile_to_llvm_ir_file(
 
     Ok(())
 }
+
+#[cfg(not(feature = "llvm"))]
+fn llvm_unavailable() -> BackendError {
+    BackendError::Llvm(
+        "oitec was built without the `llvm` feature; use `--backend cranelift`".into(),
+    )
+}
+
+/// Compile an IR module and emit an object file
+#[cfg(not(feature = "llvm"))]
+pub fn compile_to_object_file(
+    _module: &IrModule,
+    _config: &BackendConfig,
+    _output_path: &Path,
+) -> Result<(), BackendError> {
+    Err(llvm_unavailable())
+}
+
+/// Compile an IR module and emit a bitcode file
+#[cfg(not(feature = "llvm"))]
+pub fn compile_to_bitcode_file(
+    _module: &IrModule,
+    _config: &BackendConfig,
+    _output_path: &Path,
+) -> Result<(), BackendError> {
+    Err(llvm_unavailable())
+}
+
+/// Compile an IR module and emit an LLVM IR text file
+#[cfg(not(feature = "llvm"))]
+pub fn compile_to_llvm_ir_file(
+    _module: &IrModule,
+    _config: &BackendConfig,
+    _output_path: &Path,
+) -> Result<(), BackendError> {
+    Err(llvm_unavailable())
+}
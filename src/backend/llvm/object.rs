@@ -9,7 +9,27 @@ use std::ffi::{CStr, CString, c_char};
 use std::path::Path;
 use std::ptr;
 
-use crate::backend::{BackendError, OptLevel};
+use crate::backend::{BackendConfig, BackendError, OptLevel};
+
+/// Register every target LLVM was built with, so `--target` can name any
+pub fn initialize_targets() {
+    static INIT: std::sync::Once = std::sync::Once::new();
+    INIT.call_once(|| unsafe {
+        llvm_sys::target::LLVM_InitializeAllTargetInfos();
+        llvm_sys::target::LLVM_InitializeAllTargets();
+        llvm_sys::target::LLVM_InitializeAllTargetMCs();
+        llvm_sys::target::LLVM_InitializeAllAsmPrinters();
+        llvm_sys::target::LLVM_InitializeAllAsmParsers();
+    });
+}
+
+/// Triple to compile `config` for: its target, or LLVM's host triple
+pub fn target_triple(config: &BackendConfig) -> Result<String, BackendError> {
+    match &config.target {
+        Some(target) => Ok(target.to_string()),
+        None => get_default_target_triple(),
+    }
+}
 
 /// Get the default target triple for the current platform
 pub fn get_default_target_triple() -> Result<String, BackendError> {
@@ -33,10 +53,7 @@ pub unsafe fn create_target_machine(
     opt_level: OptLevel,
 ) -> Result<LLVMTargetMachineRef, BackendError> {
     unsafe {
-        // Initialize LLVM targets
-        llvm_sys::target::LLVM_InitializeNativeTarget();
-        llvm_sys::target::LLVM_InitializeNativeAsmPrinter();
-        llvm_sys::target::LLVM_InitializeNativeAsmParser();
+        initialize_targets();
 
         let triple_cstr = CString::new(target_triple).unwrap();
 
@@ -80,7 +97,8 @@ pub unsafe fn create_target_machine(
             cpu_cstr.as_ptr(),
             features_cstr.as_ptr(),
             level,
-            LLVMRelocMode::LLVMRelocDefault,
+            // Position-independent code links into PIE executables and shared libraries
+            LLVMRelocMode::LLVMRelocPIC,
             LLVMCodeModel::LLVMCodeModelDefault,
         );
 
@@ -4,7 +4,7 @@ use llvm_sys::prelude::*;
 use std::collections::BTreeMap;
 
 use crate::backend::BackendError;
-use crate::ir::{IrStructDef, IrType};
+use crate::ir::{ElementKind, IrStructDef, IrType};
 
 /// Convert an IR type to an LLVM type
 pub fn ir_type_to_llvm_type(
@@ -31,6 +31,11 @@ pub fn ir_type_to_llvm_type(
                 // Typed arrays are pointers
                 Ok(llvm_sys::core::LLVMInt64TypeInContext(ctx))
             }
+            IrType::Scalar(kind) => Ok(match kind {
+                ElementKind::Float32 => llvm_sys::core::LLVMFloatTypeInContext(ctx),
+                ElementKind::Float64 => llvm_sys::core::LLVMDoubleTypeInContext(ctx),
+                _ => llvm_sys::core::LLVMIntTypeInContext(ctx, kind.size() as u32 * 8),
+            }),
             IrType::Function => {
                 // Functions are pointers
                 Ok(llvm_sys::core::LLVMInt64TypeInContext(ctx))
@@ -7,7 +7,10 @@
 //! - `layout.rs` - Memory layout calculation for structs/arrays
 //! - `cranelift.rs` - IR to Cranelift IR translation
 //! - `jit.rs` - JIT compilation and execution runtime
-//! - `aot.rs` - Ahead-of-time compilation pipeline (future)
+//! - `object.rs` - Cranelift object-file emission (AOT without LLVM)
+//! - `aot.rs` - Ahead-of-time compilation pipeline
+//! - `runtime_lib.rs` - Runtime static library that AOT output links against
+//! - `target.rs` - Target triples for cross-compilation
 //! - `tier.rs` - Tiered compilation manager
 
 pub mod aot;
@@ -15,9 +18,13 @@ pub mod cranelift;
 pub mod jit;
 pub mod layout;
 pub mod llvm;
+pub mod object;
+pub mod runtime_lib;
+pub mod target;
 pub mod tier;
 
 use crate::ir::IrModule;
+use target::Target;
 
 /// Backend compilation target
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
@@ -25,7 +32,7 @@ pub enum BackendKind {
     /// JIT compilation with Cranelift
     #[default]
     CraneliftJit,
-    /// AOT compilation with Cranelift (future)
+    /// AOT compilation with Cranelift
     CraneliftAot,
     /// AOT compilation with LLVM
     LlvmAot,
@@ -70,6 +77,8 @@ pub struct BackendConfig {
     pub bounds_check: bool,
     /// Link-time optimization mode
     pub lto_mode: LtoMode,
+    /// Target of AOT compilation; `None` compiles for the host
+    pub target: Option<Target>,
 }
 
 impl Default for BackendConfig {
@@ -80,6 +89,7 @@ impl Default for BackendConfig {
             debug_info: false,
             bounds_check: true,
             lto_mode: LtoMode::None,
+            target: None,
         }
     }
 }
@@ -135,10 +145,7 @@ pub fn compile(module: &IrModule, config: &BackendConfig) -> Result<CompiledModu
                 functions: runtime.get_all_funcs(),
             })
         }
-        BackendKind::CraneliftAot => Err(BackendError::AotError(
-            "AOT compilation not yet implemented".into(),
-        )),
-        BackendKind::LlvmAot => {
+        BackendKind::CraneliftAot | BackendKind::LlvmAot => {
             // For AOT, use AotCompiler
             let mut aot = aot::AotCompiler::new(config);
             aot.compile_to_bytes(module)?;
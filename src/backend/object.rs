@@ -0,0 +1,253 @@
+//! Cranelift AOT backend
+//!
+//! Compiles an IR module to a relocatable object file with `cranelift-object`,
+//! reusing the translation in `cranelift.rs`, so no LLVM install is needed.
+//! The `ot_*` runtime stubs the generated code imports come from the runtime
+//! library (`runtime_lib.rs`), the same stubs the JIT registers.
+//!
+//! The program's `main` is emitted as `ot_main`; objects for executables also
+//! get a C `main` that calls it.
+
+use std::collections::HashMap;
+
+use cranelift::prelude::*;
+use cranelift_codegen::settings;
+use cranelift_module::{FuncId, Linkage, Module};
+use cranelift_object::{ObjectBuilder, ObjectModule};
+
+use super::cranelift::translate_function;
+use super::{BackendConfig, BackendError, OptLevel};
+use crate::ir::IrModule;
+
+/// Symbol name of an IR function in the object file
+fn symbol_name(func_name: &str) -> String {
+    match func_name {
+        "" => "anonymous".to_string(),
+        "main" => "ot_main".to_string(),
+        name => name.to_string(),
+    }
+}
+
+/// Compile an IR module to the bytes of a relocatable object file for
+/// `config.target`. With `entry` set, the object also defines the C `main`
+/// of an executable.
+pub fn compile_to_object(
+    ir_module: &IrModule,
+    config: &BackendConfig,
+    entry: bool,
+) -> Result<Vec<u8>, BackendError> {
+    let mut flag_builder = settings::builder();
+    // Position-independent code links into PIE executables and shared libraries
+    flag_builder.set("is_pic", "true").unwrap();
+    let opt_level = match config.opt_level {
+        OptLevel::None => "none",
+        OptLevel::Speed => "speed",
+        OptLevel::SpeedAndSize => "speed_and_size",
+    };
+    flag_builder.set("opt_level", opt_level).unwrap();
+
+    let isa_builder = match &config.target {
+        Some(target) => cranelift_codegen::isa::lookup(target.triple().clone()).map_err(|e| {
+            BackendError::Cranelift(format!("Unsupported target {}: {}", target, e))
+        })?,
+        None => cranelift_native::builder()
+            .map_err(|e| BackendError::Cranelift(format!("Failed to create ISA builder: {}", e)))?,
+    };
+    let isa = isa_builder
+        .finish(settings::Flags::new(flag_builder))
+        .map_err(|e| BackendError::Cranelift(format!("Failed to create ISA: {}", e)))?;
+
+    let builder = ObjectBuilder::new(isa, "oite", cranelift_module::default_libcall_names())
+        .map_err(|e| BackendError::Cranelift(format!("Failed to create object builder: {}", e)))?;
+    let mut module = ObjectModule::new(builder);
+
+    // Declare all functions first so they can call each other
+    let mut func_ids: HashMap<String, FuncId> = HashMap::new();
+    let mut func_sigs: HashMap<String, Signature> = HashMap::new();
+    for func in &ir_module.functions {
+        let mut sig = module.make_signature();
+        for _ in &func.params {
+            sig.params.push(AbiParam::new(types::I64));
+        }
+        sig.returns.push(AbiParam::new(types::I64));
+
+        let symbol = symbol_name(&func.name);
+        let func_id = module
+            .declare_function(&symbol, Linkage::Export, &sig)
+            .map_err(|e| {
+                BackendError::Cranelift(format!("Failed to declare function {}: {}", symbol, e))
+            })?;
+        func_ids.insert(func.name.clone(), func_id);
+        func_sigs.insert(func.name.clone(), sig);
+    }
+
+    let mut ctx = module.make_context();
+    let mut builder_ctx = FunctionBuilderContext::new();
+    for func in &ir_module.functions {
+        ctx.clear();
+        ctx.func.signature = func_sigs[&func.name].clone();
+        {
+            let mut builder = FunctionBuilder::new(&mut ctx.func, &mut builder_ctx);
+            translate_function(&mut builder, &mut module, func, ir_module, &func_ids, true)?;
+            builder.finalize();
+        }
+        module
+            .define_function(func_ids[&func.name], &mut ctx)
+            .map_err(|e| {
+                BackendError::Cranelift(format!(
+                    "Failed to compile function {}: {}",
+                    symbol_name(&func.name),
+                    e
+                ))
+            })?;
+    }
+
+    if entry {
+        let ot_main = func_ids
+            .get("main")
+            .copied()
+            .ok_or_else(|| BackendError::AotError("Executable has no main function".to_string()))?;
+        define_entry_point(&mut module, ot_main, &mut builder_ctx)?;
+    }
+
+    module
+        .finish()
+        .emit()
+        .map_err(|e| BackendError::AotError(format!("Failed to emit object file: {}", e)))
+}
+
+/// Define `int main(void)` that runs `ot_main`
+fn define_entry_point(
+    module: &mut ObjectModule,
+    ot_main: FuncId,
+    builder_ctx: &mut FunctionBuilderContext,
+) -> Result<(), BackendError> {
+    let mut sig = module.make_signature();
+    sig.returns.push(AbiParam::new(types::I32));
+    let main_id = module
+        .declare_function("main", Linkage::Export, &sig)
+        .map_err(|e| BackendError::Cranelift(format!("Failed to declare main: {}", e)))?;
+
+    let mut ctx = module.make_context();
+    ctx.func.signature = sig;
+    {
+        let mut builder = FunctionBuilder::new(&mut ctx.func, builder_ctx);
+        let block = builder.create_block();
+        builder.switch_to_block(block);
+        builder.seal_block(block);
+        let callee = module.declare_func_in_func(ot_main, builder.func);
+        builder.ins().call(callee, &[]);
+        let status = builder.ins().iconst(types::I32, 0);
+        builder.ins().return_(&[status]);
+        builder.finalize();
+    }
+    module
+        .define_function(main_id, &mut ctx)
+        .map_err(|e| BackendError::Cranelift(format!("Failed to compile main: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    use crate::backend::BackendKind;
+    use crate::backend::aot::{AotCompiler, AotOptions, OutputFormat};
+    use crate::ir::{IrFunction, IrOp, IrType, Literal, Terminator};
+
+    /// `main` logs a string and the sum of two numbers
+    fn sample_module() -> IrModule {
+        let mut func = IrFunction::new("main".to_string());
+        let entry = func.alloc_block();
+        let text = func.alloc_value(IrType::String);
+        let a = func.alloc_value(IrType::Number);
+        let b = func.alloc_value(IrType::Number);
+        let sum = func.alloc_value(IrType::Number);
+        let logged = func.alloc_value(IrType::Any);
+        let logged_sum = func.alloc_value(IrType::Any);
+        let block = func.block_mut(entry);
+        block.push(IrOp::Const(text, Literal::String("hello".to_string())));
+        block.push(IrOp::CallMethod(
+            logged,
+            text,
+            "log".to_string(),
+            vec![text],
+        ));
+        block.push(IrOp::Const(a, Literal::Number(40.0)));
+        block.push(IrOp::Const(b, Literal::Number(2.5)));
+        block.push(IrOp::AddNum(sum, a, b));
+        block.push(IrOp::CallMethod(
+            logged_sum,
+            sum,
+            "log".to_string(),
+            vec![sum],
+        ));
+        block.terminate(Terminator::Return(Some(sum)));
+
+        let mut module = IrModule::new();
+        module.add_function(func);
+        module
+    }
+
+    fn cranelift_config() -> BackendConfig {
+        BackendConfig {
+            kind: BackendKind::CraneliftAot,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_emits_object_with_entry_symbol() {
+        let bytes = compile_to_object(&sample_module(), &cranelift_config(), true).unwrap();
+        let is_object = bytes.starts_with(b"\x7fELF")
+            || bytes.starts_with(&[0xcf, 0xfa, 0xed, 0xfe])
+            || bytes.starts_with(&[0x64, 0x86]);
+        assert!(is_object);
+        let contains = |needle: &[u8]| bytes.windows(needle.len()).any(|w| w == needle);
+        assert!(contains(b"ot_main"));
+        assert!(contains(b"ot_console_log"));
+        assert!(contains(b"hello"));
+    }
+
+    /// x86_64 and aarch64 objects, whichever the host is
+    #[test]
+    fn test_emits_objects_for_other_targets() {
+        use crate::backend::target::Target;
+        use object::{Architecture, Object};
+
+        for (triple, arch) in [
+            ("aarch64-unknown-linux-gnu", Architecture::Aarch64),
+            ("x86_64-unknown-linux-gnu", Architecture::X86_64),
+        ] {
+            let config = BackendConfig {
+                target: Some(Target::parse(triple).unwrap()),
+                ..cranelift_config()
+            };
+            let bytes = compile_to_object(&sample_module(), &config, true).unwrap();
+            let file = object::File::parse(&*bytes).unwrap();
+            assert_eq!(file.architecture(), arch, "{}", triple);
+            assert_eq!(file.format(), object::BinaryFormat::Elf);
+        }
+    }
+
+    #[test]
+    fn test_links_and_runs_executable() {
+        if super::super::llvm::linker::detect_linker().is_err() {
+            return;
+        }
+        let dir = std::env::temp_dir().join(format!("oite_cranelift_aot_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let exe = dir.join("sample");
+
+        let mut aot = AotCompiler::new(&cranelift_config()).with_options(AotOptions {
+            format: OutputFormat::Executable,
+            ..Default::default()
+        });
+        aot.compile_to_file(&sample_module(), &exe).unwrap();
+
+        let output = Command::new(&exe).output().unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "hello\n42.5\n");
+    }
+}
@@ -0,0 +1,274 @@
+//! Runtime static library for AOT output
+//!
+//! AOT executables link against `src/runtime` compiled as a `staticlib`, the
+//! same `ot_*` stubs the JIT registers, so both backends share one runtime.
+//! The library is taken from `OITE_RUNTIME_LIB` when set, or else from a
+//! prebuilt `liboite.a` shipped with `oitec` (see [`prebuilt_locations`]).
+//! A compiler run from its source tree has neither, so as a fallback for
+//! development it builds the library with `cargo rustc` into
+//! `target/runtime` (a separate target dir, so it can run while the outer
+//! build holds its lock). Cross builds compile it for the target, which
+//! needs that target's Rust standard library (`rustup target add <triple>`).
+//!
+//! Before linking, the archive must export `ABI_SYMBOL` for the ABI version
+//! this compiler generates code for.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Mutex, OnceLock};
+
+use super::BackendError;
+use super::target::Target;
+use crate::build::{normalize_command_env, remap_path_prefix};
+use crate::runtime::abi_version::{ABI_SYMBOL, ABI_VERSION};
+
+/// Environment variable pointing at a prebuilt runtime library
+pub const RUNTIME_LIB_ENV: &str = "OITE_RUNTIME_LIB";
+
+/// File name of the runtime library
+const RUNTIME_LIB_NAME: &str = "liboite.a";
+
+/// Prefix of the versioned symbol the runtime exports
+const ABI_SYMBOL_PREFIX: &[u8] = b"ot_abi_version_";
+
+/// Find or build the runtime library for `target` (`None` for the host) and
+/// check its ABI version
+pub fn find_runtime_library(target: Option<&Target>) -> Result<PathBuf, BackendError> {
+    static BUILT: OnceLock<Mutex<HashMap<Option<Target>, PathBuf>>> = OnceLock::new();
+
+    if let Some(path) = std::env::var_os(RUNTIME_LIB_ENV) {
+        let lib = PathBuf::from(path);
+        check_abi_version(&lib)?;
+        return Ok(lib);
+    }
+
+    let exe_dir = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(Path::to_path_buf));
+    let prebuilt = exe_dir
+        .as_deref()
+        .map(|dir| prebuilt_locations(dir, target))
+        .unwrap_or_default();
+    if let Some(lib) = prebuilt.iter().find(|lib| lib.is_file()) {
+        check_abi_version(lib)?;
+        return Ok(lib.clone());
+    }
+
+    // Development fallback: build it from the source tree this compiler
+    // was built from, if that is still there
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    if !manifest_dir.join("Cargo.toml").is_file() {
+        let searched: Vec<String> = prebuilt
+            .iter()
+            .map(|lib| format!("\n  {}", lib.display()))
+            .collect();
+        let target = match target {
+            Some(target) => target.to_string(),
+            None => "the host".to_string(),
+        };
+        return Err(BackendError::AotError(format!(
+            "No runtime library for {}; looked for a prebuilt {} in:{}\n\
+             Set {} to a prebuilt {}, or run oitec from its source tree to build one",
+            target,
+            RUNTIME_LIB_NAME,
+            searched.concat(),
+            RUNTIME_LIB_ENV,
+            RUNTIME_LIB_NAME
+        )));
+    }
+    let built = BUILT.get_or_init(Default::default);
+    let cached = built.lock().unwrap().get(&target.cloned()).cloned();
+    let lib = match cached {
+        Some(lib) => lib,
+        None => {
+            let lib = build_runtime_library(manifest_dir, target)?;
+            built.lock().unwrap().insert(target.cloned(), lib.clone());
+            lib
+        }
+    };
+    check_abi_version(&lib)?;
+    Ok(lib)
+}
+
+/// Where a distribution of `oitec` in `exe_dir` ships the runtime library,
+/// in search order: beside the binary, or in `lib/oite` of the prefix the
+/// binary is installed under. The library for a cross target sits in a
+/// subdirectory named after its triple.
+fn prebuilt_locations(exe_dir: &Path, target: Option<&Target>) -> Vec<PathBuf> {
+    let mut dirs = vec![exe_dir.to_path_buf()];
+    if let Some(prefix) = exe_dir.parent() {
+        dirs.push(prefix.join("lib").join("oite"));
+    }
+    dirs.into_iter()
+        .map(|dir| match target {
+            Some(target) => dir.join(target.to_string()).join(RUNTIME_LIB_NAME),
+            None => dir.join(RUNTIME_LIB_NAME),
+        })
+        .collect()
+}
+
+/// Build `liboite.a` from the crate at `manifest_dir` without `vm_interop`,
+/// so only the runtime and its dependencies are compiled
+fn build_runtime_library(
+    manifest_dir: &Path,
+    target: Option<&Target>,
+) -> Result<PathBuf, BackendError> {
+    let cargo_toml = manifest_dir.join("Cargo.toml");
+    let target_dir = manifest_dir.join("target").join("runtime");
+
+    let cargo = std::env::var_os("CARGO").unwrap_or_else(|| "cargo".into());
+    let mut cmd = Command::new(&cargo);
+    normalize_command_env(&mut cmd)
+        .args(["rustc", "--lib", "--release", "--no-default-features"])
+        .args(["--crate-type", "staticlib"])
+        .arg("--manifest-path")
+        .arg(&cargo_toml)
+        .arg("--target-dir")
+        .arg(&target_dir);
+    let mut profile_dir = target_dir.clone();
+    if let Some(target) = target {
+        cmd.arg("--target").arg(target.to_string());
+        profile_dir.push(target.to_string());
+    }
+    // The archive must not depend on where the compiler was checked out
+    cmd.arg("--").arg(remap_path_prefix(manifest_dir));
+    let output = cmd
+        .output()
+        .map_err(|e| BackendError::AotError(format!("Failed to execute cargo: {}", e)))?;
+
+    if !output.status.success() {
+        let hint = match target {
+            Some(target) => format!(
+                " (is the {0} standard library installed? `rustup target add {0}`)",
+                target
+            ),
+            None => String::new(),
+        };
+        return Err(BackendError::AotError(format!(
+            "cargo failed to build the runtime library{}:\n{}",
+            hint,
+            String::from_utf8_lossy(&output.stderr)
+        )));
+    }
+
+    let lib = profile_dir.join("release").join(RUNTIME_LIB_NAME);
+    if !lib.exists() {
+        return Err(BackendError::AotError(format!(
+            "Runtime library not found after build at {}",
+            lib.display()
+        )));
+    }
+    Ok(lib)
+}
+
+/// Check that the archive exports the ABI symbol of this compiler
+pub fn check_abi_version(lib: &Path) -> Result<(), BackendError> {
+    let bytes = std::fs::read(lib).map_err(|e| {
+        BackendError::AotError(format!(
+            "Failed to read runtime library {}: {}",
+            lib.display(),
+            e
+        ))
+    })?;
+
+    let versions = exported_abi_versions(&bytes);
+    if versions.contains(&ABI_VERSION) {
+        return Ok(());
+    }
+    let found = match versions.as_slice() {
+        [] => "no ABI version".to_string(),
+        versions => format!(
+            "ABI version {}",
+            versions
+                .iter()
+                .map(u32::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    };
+    Err(BackendError::AotError(format!(
+        "Runtime library {} has {}, but this compiler needs ABI version {} ({}); rebuild the runtime",
+        lib.display(),
+        found,
+        ABI_VERSION,
+        ABI_SYMBOL
+    )))
+}
+
+/// Versions of every `ot_abi_version_<n>` symbol name in an archive
+fn exported_abi_versions(bytes: &[u8]) -> Vec<u32> {
+    let mut versions = Vec::new();
+    let mut rest = bytes;
+    while let Some(pos) = rest
+        .windows(ABI_SYMBOL_PREFIX.len())
+        .position(|w| w == ABI_SYMBOL_PREFIX)
+    {
+        rest = &rest[pos + ABI_SYMBOL_PREFIX.len()..];
+        let digits = rest.iter().take_while(|b| b.is_ascii_digit()).count();
+        if let Some(version) = std::str::from_utf8(&rest[..digits])
+            .ok()
+            .and_then(|d| d.parse().ok())
+            && !versions.contains(&version)
+        {
+            versions.push(version);
+        }
+    }
+    versions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exported_abi_versions() {
+        assert_eq!(exported_abi_versions(b"\0ot_abi_version_1\0"), vec![1]);
+        assert_eq!(
+            exported_abi_versions(b"ot_abi_version_2\0ot_abi_version_12\0ot_abi_version_2"),
+            vec![2, 12]
+        );
+        assert!(exported_abi_versions(b"ot_abi_version_\0ot_main").is_empty());
+    }
+
+    #[test]
+    fn test_prebuilt_locations() {
+        let exe_dir = Path::new("/opt/oite/bin");
+        assert_eq!(
+            prebuilt_locations(exe_dir, None),
+            vec![
+                PathBuf::from("/opt/oite/bin/liboite.a"),
+                PathBuf::from("/opt/oite/lib/oite/liboite.a"),
+            ]
+        );
+        let target = Target::parse("aarch64-unknown-linux-gnu").unwrap();
+        assert_eq!(
+            prebuilt_locations(exe_dir, Some(&target))[0],
+            PathBuf::from("/opt/oite/bin/aarch64-unknown-linux-gnu/liboite.a")
+        );
+    }
+
+    #[test]
+    fn test_rejects_runtime_with_other_abi_version() {
+        let dir = std::env::temp_dir().join(format!("oite_runtime_abi_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let lib = dir.join("liboite.a");
+
+        std::fs::write(&lib, b"!<arch>\not_abi_version_0\0").unwrap();
+        let err = check_abi_version(&lib).unwrap_err().to_string();
+        assert!(err.contains("ABI version 0"), "{}", err);
+        assert!(err.contains(ABI_SYMBOL), "{}", err);
+
+        std::fs::write(&lib, format!("!<arch>\n{}\0", ABI_SYMBOL)).unwrap();
+        let result = check_abi_version(&lib);
+        let _ = std::fs::remove_dir_all(&dir);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    #[ignore = "builds the runtime with a nested `cargo rustc --release`; run with --ignored"]
+    fn test_built_runtime_exports_abi_symbol() {
+        let lib = find_runtime_library(None).unwrap();
+        assert!(exported_abi_versions(&std::fs::read(lib).unwrap()).contains(&ABI_VERSION));
+    }
+}
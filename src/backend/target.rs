@@ -0,0 +1,160 @@
+//! Compilation targets for `oitec build --target`
+//!
+//! Generated code NaN-boxes every value into 64 bits with pointers in the
+//! 48-bit payload (`runtime::abi`), and `layout.rs` gives every value an
+//! 8-byte slot. A target therefore needs 64-bit little-endian pointers of at
+//! most 48 significant bits, which x86_64 and aarch64 have.
+//!
+//! `--static` switches a Linux target to musl and links a static executable.
+
+use std::fmt;
+use std::str::FromStr;
+
+use target_lexicon::{
+    Architecture, Endianness, Environment, OperatingSystem, PointerWidth, Triple, Vendor,
+};
+
+use super::BackendError;
+use super::layout::VALUE_SIZE;
+
+/// A target triple the AOT backends can generate code for
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Target {
+    triple: Triple,
+}
+
+impl Target {
+    /// The platform the compiler runs on
+    pub fn host() -> Self {
+        Self {
+            triple: Triple::host(),
+        }
+    }
+
+    /// Parse a triple such as `aarch64-unknown-linux-gnu`
+    pub fn parse(triple: &str) -> Result<Self, BackendError> {
+        let parsed = Triple::from_str(triple).map_err(|e| {
+            BackendError::AotError(format!("Invalid target triple {}: {}", triple, e))
+        })?;
+        let target = Self { triple: parsed };
+        target.check_value_layout()?;
+        Ok(target)
+    }
+
+    /// Reject targets whose pointers do not fit a NaN-boxed value
+    fn check_value_layout(&self) -> Result<(), BackendError> {
+        let unsupported = |reason: &str| {
+            Err(BackendError::AotError(format!(
+                "Unsupported target {}: {} (supported architectures: x86_64, aarch64)",
+                self.triple, reason
+            )))
+        };
+        match self.triple.pointer_width() {
+            Ok(width) if width.bytes() as u32 == VALUE_SIZE => {}
+            Ok(PointerWidth::U16 | PointerWidth::U32) => {
+                return unsupported("NaN-boxed values need 64-bit pointers");
+            }
+            _ => return unsupported("unknown pointer width"),
+        }
+        if self.triple.endianness() != Ok(Endianness::Little) {
+            return unsupported("NaN-boxed values need a little-endian target");
+        }
+        match self.triple.architecture {
+            Architecture::X86_64 | Architecture::Aarch64(_) => Ok(()),
+            _ => unsupported("pointers may not fit the 48-bit NaN-box payload"),
+        }
+    }
+
+    /// The same architecture on Linux with the musl C library, for fully
+    /// static executables
+    pub fn with_musl(&self) -> Result<Self, BackendError> {
+        if self.triple.operating_system != OperatingSystem::Linux {
+            return Err(BackendError::AotError(format!(
+                "Static linking needs a Linux target, not {}",
+                self.triple
+            )));
+        }
+        let mut triple = self.triple.clone();
+        triple.vendor = Vendor::Unknown;
+        triple.environment = Environment::Musl;
+        Ok(Self { triple })
+    }
+
+    pub fn triple(&self) -> &Triple {
+        &self.triple
+    }
+
+    pub fn is_host(&self) -> bool {
+        self.triple == Triple::host()
+    }
+
+    pub fn is_musl(&self) -> bool {
+        self.triple.environment == Environment::Musl
+    }
+
+    pub fn is_macos(&self) -> bool {
+        matches!(self.triple.operating_system, OperatingSystem::Darwin)
+    }
+
+    /// Prefix of the target's GCC cross toolchain, e.g. `aarch64-linux-gnu`
+    pub fn gcc_prefix(&self) -> String {
+        match self.triple.operating_system {
+            OperatingSystem::Linux => format!(
+                "{}-linux-{}",
+                self.triple.architecture, self.triple.environment
+            ),
+            _ => self.triple.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.triple.fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_accepts_64_bit_little_endian_targets() {
+        for triple in [
+            "x86_64-unknown-linux-gnu",
+            "aarch64-unknown-linux-gnu",
+            "aarch64-apple-darwin",
+            "x86_64-unknown-linux-musl",
+        ] {
+            assert_eq!(Target::parse(triple).unwrap().to_string(), triple);
+        }
+        assert!(Target::parse(&Target::host().to_string()).is_ok());
+    }
+
+    #[test]
+    fn test_rejects_targets_that_break_nan_boxing() {
+        for (triple, reason) in [
+            ("i686-unknown-linux-gnu", "64-bit pointers"),
+            ("armv7-unknown-linux-gnueabihf", "64-bit pointers"),
+            ("powerpc64-unknown-linux-gnu", "little-endian"),
+            ("riscv64gc-unknown-linux-gnu", "48-bit"),
+        ] {
+            let err = Target::parse(triple).unwrap_err().to_string();
+            assert!(err.contains(reason), "{}: {}", triple, err);
+        }
+        assert!(Target::parse("not-a-triple").is_err());
+    }
+
+    #[test]
+    fn test_musl_target() {
+        let gnu = Target::parse("aarch64-unknown-linux-gnu").unwrap();
+        let musl = gnu.with_musl().unwrap();
+        assert_eq!(musl.to_string(), "aarch64-unknown-linux-musl");
+        assert!(musl.is_musl() && !gnu.is_musl());
+        assert_eq!(gnu.gcc_prefix(), "aarch64-linux-gnu");
+        assert_eq!(musl.gcc_prefix(), "aarch64-linux-musl");
+
+        let macos = Target::parse("aarch64-apple-darwin").unwrap();
+        assert!(macos.with_musl().is_err());
+    }
+}
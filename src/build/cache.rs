@@ -0,0 +1,532 @@
+//! Content-addressed build cache
+//!
+//! `oitec build` stores the IR, object file and bitcode of every module it
+//! compiles under a key derived from:
+//! - the SHA-256 of the module's source and of everything it imports,
+//!   transitively (asset imports included, since their contents are embedded)
+//! - the compiler version, the SHA-256 of the compiler binary itself and the
+//!   runtime ABI version, so a rebuilt compiler or runtime never picks up
+//!   artifacts of the old one
+//! - the backend, optimization, debug info, bounds checking and LTO settings,
+//!   target triple and output format, each written out by name so the key
+//!   does not depend on how a type happens to print
+//!
+//! The modules are the files named on the `oitec build` command line, each
+//! compiled and cached on its own; a module a file imports is not built into
+//! its artifact, but its contents are part of the file's key, so editing it
+//! rebuilds every file that imports it.
+//!
+//! An unchanged module with the same settings maps to the same key, so a
+//! rebuild only compiles the modules whose key has no artifact yet.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use sha2::{Digest, Sha256};
+
+use swc_common::{FileName, SourceMap, sync::Lrc};
+use swc_ecma_ast::{ModuleDecl, ModuleItem, Program};
+use swc_ecma_parser::{Parser, StringInput, Syntax, TsSyntax, lexer::Lexer};
+
+use super::hash_bytes;
+use crate::backend::aot::OutputFormat;
+use crate::backend::{BackendConfig, BackendKind, LtoMode, OptLevel};
+use crate::module::resolver::ModuleResolver;
+use crate::runtime::abi_version::ABI_VERSION;
+
+/// Bumped whenever the layout of the cache or of a key changes
+pub const CACHE_FORMAT_VERSION: u32 = 3;
+
+/// Default cache directory, relative to the working directory
+pub const DEFAULT_CACHE_DIR: &str = ".cache/oite";
+
+/// Kind of artifact stored for a module
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Artifact {
+    /// Canonical IR text (`ir::format`)
+    Ir,
+    /// Relocatable object file
+    Object,
+    /// LLVM bitcode, the input of LTO
+    Bitcode,
+}
+
+impl Artifact {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Artifact::Ir => "ir",
+            Artifact::Object => "o",
+            Artifact::Bitcode => "bc",
+        }
+    }
+}
+
+/// Hex SHA-256 identifying one module built with one configuration
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CacheKey(String);
+
+impl CacheKey {
+    /// Key of a module whose source and imports hash to `module_hash`
+    pub fn new(
+        module_hash: &str,
+        config: &BackendConfig,
+        target: &str,
+        format: OutputFormat,
+    ) -> Self {
+        let format = match format {
+            OutputFormat::Object => "object",
+            OutputFormat::StaticLib => "staticlib",
+            OutputFormat::SharedLib => "sharedlib",
+            OutputFormat::Executable => "executable",
+        };
+        let material = format!(
+            "oite build cache v{}\ncompiler {} {}\nabi {}\nmodule {}\n{}target {}\nformat {}\n",
+            CACHE_FORMAT_VERSION,
+            env!("CARGO_PKG_VERSION"),
+            compiler_build_hash(),
+            ABI_VERSION,
+            module_hash,
+            config_material(config),
+            target,
+            format
+        );
+        CacheKey(hash_bytes(material.as_bytes()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// The settings of `config` that change what a module compiles to, one per
+/// line
+fn config_material(config: &BackendConfig) -> String {
+    // Destructured so that a new field has to be added here or left out on
+    // purpose. The target is keyed by its resolved triple instead.
+    let BackendConfig {
+        kind,
+        opt_level,
+        debug_info,
+        bounds_check,
+        lto_mode,
+        target: _,
+    } = config;
+    let kind = match kind {
+        BackendKind::CraneliftJit => "cranelift-jit",
+        BackendKind::CraneliftAot => "cranelift-aot",
+        BackendKind::LlvmAot => "llvm-aot",
+        BackendKind::Interpreter => "interpreter",
+    };
+    let opt_level = match opt_level {
+        OptLevel::None => "none",
+        OptLevel::Speed => "speed",
+        OptLevel::SpeedAndSize => "speed-and-size",
+    };
+    let lto_mode = match lto_mode {
+        LtoMode::None => "none",
+        LtoMode::Thin => "thin",
+        LtoMode::Full => "full",
+    };
+    format!(
+        "backend {}\nopt {}\ndebug-info {}\nbounds-check {}\nlto {}\n",
+        kind, opt_level, debug_info, bounds_check, lto_mode
+    )
+}
+
+/// SHA-256 of the running compiler binary, read once per process. If the
+/// binary can't be read, the key has only the version to go on.
+pub fn compiler_build_hash() -> &'static str {
+    static HASH: OnceLock<String> = OnceLock::new();
+    HASH.get_or_init(|| {
+        let hash = || -> io::Result<String> {
+            let mut binary = fs::File::open(std::env::current_exe()?)?;
+            let mut hasher = Sha256::new();
+            io::copy(&mut binary, &mut hasher)?;
+            Ok(format!("{:x}", hasher.finalize()))
+        };
+        hash().unwrap_or_else(|_| "unknown".to_string())
+    })
+}
+
+/// Artifacts on disk, one file per key and artifact kind
+#[derive(Debug, Clone)]
+pub struct BuildCache {
+    dir: PathBuf,
+}
+
+impl BuildCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Where the artifact of `key` lives, whether or not it exists
+    pub fn path(&self, key: &CacheKey, artifact: Artifact) -> PathBuf {
+        let key = key.as_str();
+        self.dir
+            .join(&key[..2])
+            .join(format!("{}.{}", key, artifact.extension()))
+    }
+
+    /// The cached artifact of `key`, if any
+    pub fn get(&self, key: &CacheKey, artifact: Artifact) -> Option<PathBuf> {
+        let path = self.path(key, artifact);
+        path.is_file().then_some(path)
+    }
+
+    /// Store an artifact. The file is written next to its final path and
+    /// renamed into place, so readers never see a partial artifact.
+    pub fn store(&self, key: &CacheKey, artifact: Artifact, bytes: &[u8]) -> io::Result<PathBuf> {
+        let path = self.path(key, artifact);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let temp = path.with_extension(format!(
+            "{}.tmp{}",
+            artifact.extension(),
+            std::process::id()
+        ));
+        fs::write(&temp, bytes)?;
+        fs::rename(&temp, &path)?;
+        Ok(path)
+    }
+
+    /// Store the artifact a backend wrote to `file`
+    pub fn store_file(
+        &self,
+        key: &CacheKey,
+        artifact: Artifact,
+        file: &Path,
+    ) -> io::Result<PathBuf> {
+        self.store(key, artifact, &fs::read(file)?)
+    }
+
+    /// Remove every cached artifact
+    pub fn clear(&self) -> io::Result<()> {
+        match fs::remove_dir_all(&self.dir) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Hashes modules together with everything they import
+#[derive(Default)]
+pub struct ModuleHasher {
+    /// Resolvers by importer directory, so files from different projects
+    /// resolve their imports each with their own project settings
+    resolvers: HashMap<PathBuf, ModuleResolver>,
+    hashes: HashMap<PathBuf, String>,
+    in_progress: HashSet<PathBuf>,
+}
+
+impl ModuleHasher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// SHA-256 over a file's contents and, for source files, over the hashes
+    /// of its static imports in source order
+    pub fn hash_module(&mut self, path: &Path) -> io::Result<String> {
+        let path = fs::canonicalize(path)?;
+        if let Some(hash) = self.hashes.get(&path) {
+            return Ok(hash.clone());
+        }
+        let bytes = fs::read(&path)?;
+        let Some(syntax) = source_syntax(&path) else {
+            let hash = hash_bytes(&bytes);
+            self.hashes.insert(path, hash.clone());
+            return Ok(hash);
+        };
+
+        self.in_progress.insert(path.clone());
+        let mut material = format!("source {}\n", hash_bytes(&bytes));
+        let source = String::from_utf8_lossy(&bytes);
+        for specifier in static_imports(&source, syntax) {
+            let import_hash = self.hash_import(&specifier, &path);
+            material.push_str(&format!("import {} {}\n", specifier, import_hash));
+        }
+        self.in_progress.remove(&path);
+
+        let hash = hash_bytes(material.as_bytes());
+        self.hashes.insert(path, hash.clone());
+        Ok(hash)
+    }
+
+    /// Hash of an imported file. Imports that cannot be hashed, and imports
+    /// back into a module being hashed, contribute a fixed marker instead.
+    fn hash_import(&mut self, specifier: &str, importer: &Path) -> String {
+        let dir = importer.parent().unwrap_or(importer).to_path_buf();
+        let resolver = self.resolvers.entry(dir).or_insert_with_key(|dir| {
+            ModuleResolver::discover(dir).unwrap_or_else(|_| ModuleResolver::new())
+        });
+        let Ok(resolved) = resolver.resolve(specifier, importer) else {
+            return "unresolved".to_string();
+        };
+        let path = fs::canonicalize(resolved.path.as_ref())
+            .unwrap_or_else(|_| resolved.path.as_ref().clone());
+        if self.in_progress.contains(&path) {
+            return "cycle".to_string();
+        }
+        self.hash_module(&path)
+            .unwrap_or_else(|_| "unreadable".to_string())
+    }
+}
+
+/// Parser syntax for source files; `None` for assets
+fn source_syntax(path: &Path) -> Option<Syntax> {
+    let extension = path.extension()?.to_str()?;
+    match extension {
+        "js" | "jsx" | "mjs" => Some(Syntax::Es(Default::default())),
+        "ot" | "ts" | "tsx" | "mts" => Some(Syntax::Typescript(TsSyntax {
+            decorators: true,
+            tsx: extension == "tsx",
+            ..Default::default()
+        })),
+        _ => None,
+    }
+}
+
+/// Specifiers of `import ... from`, `export ... from` and `export * from`.
+/// A file that does not parse has none; compiling it reports the error.
+fn static_imports(source: &str, syntax: Syntax) -> Vec<String> {
+    let cm: Lrc<SourceMap> = Default::default();
+    let fm = cm.new_source_file(FileName::Anon.into(), source.to_string());
+    let lexer = Lexer::new(syntax, Default::default(), StringInput::from(&*fm), None);
+    let Ok(Program::Module(module)) = Parser::new_from(lexer).parse_program() else {
+        return Vec::new();
+    };
+
+    module
+        .body
+        .iter()
+        .filter_map(|item| match item {
+            ModuleItem::ModuleDecl(ModuleDecl::Import(import)) => Some(&import.src),
+            ModuleItem::ModuleDecl(ModuleDecl::ExportAll(export)) => Some(&export.src),
+            ModuleItem::ModuleDecl(ModuleDecl::ExportNamed(export)) => export.src.as_ref(),
+            _ => None,
+        })
+        .map(|src| src.value.to_string_lossy().into_owned())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("oite_cache_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_module_hash_follows_transitive_imports() {
+        let dir = temp_dir("imports");
+        fs::write(
+            dir.join("main.ot"),
+            "import { a } from './a.ot';\nconsole.log(a);\n",
+        )
+        .unwrap();
+        fs::write(dir.join("a.ot"), "export { b as a } from './b.ot';\n").unwrap();
+        fs::write(dir.join("b.ot"), "export const b = 1;\n").unwrap();
+        fs::write(dir.join("other.ot"), "console.log(2);\n").unwrap();
+
+        let main_hash = ModuleHasher::new()
+            .hash_module(&dir.join("main.ot"))
+            .unwrap();
+        let other_hash = ModuleHasher::new()
+            .hash_module(&dir.join("other.ot"))
+            .unwrap();
+        assert_eq!(
+            ModuleHasher::new()
+                .hash_module(&dir.join("main.ot"))
+                .unwrap(),
+            main_hash
+        );
+
+        // A change two imports away reaches the entry module only
+        fs::write(dir.join("b.ot"), "export const b = 2;\n").unwrap();
+        let new_main_hash = ModuleHasher::new()
+            .hash_module(&dir.join("main.ot"))
+            .unwrap();
+        let new_other_hash = ModuleHasher::new()
+            .hash_module(&dir.join("other.ot"))
+            .unwrap();
+        let _ = fs::remove_dir_all(&dir);
+        assert_ne!(new_main_hash, main_hash);
+        assert_eq!(new_other_hash, other_hash);
+    }
+
+    #[test]
+    fn test_module_hash_handles_cycles_and_assets() {
+        let dir = temp_dir("cycles");
+        fs::write(dir.join("a.ot"), "import './b.ot';\nexport const a = 1;\n").unwrap();
+        fs::write(
+            dir.join("b.ot"),
+            "import './a.ot';\nimport data from './data.json' with { type: 'json' };\n",
+        )
+        .unwrap();
+        fs::write(dir.join("data.json"), "{\"n\": 1}").unwrap();
+
+        let hash = ModuleHasher::new().hash_module(&dir.join("a.ot")).unwrap();
+        fs::write(dir.join("data.json"), "{\"n\": 2}").unwrap();
+        let changed = ModuleHasher::new().hash_module(&dir.join("a.ot")).unwrap();
+        let _ = fs::remove_dir_all(&dir);
+        assert_ne!(hash, changed);
+    }
+
+    #[test]
+    fn test_module_hash_resolves_imports_per_project() {
+        let dir = temp_dir("projects");
+        fs::create_dir_all(dir.join("app")).unwrap();
+        fs::create_dir_all(dir.join("tool")).unwrap();
+        fs::write(
+            dir.join("app").join("oite.json"),
+            r#"{ "importMap": { "imports": { "dep": "./dep.ot" } } }"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.join("app").join("main.ot"),
+            "import { d } from 'dep';\n",
+        )
+        .unwrap();
+        fs::write(dir.join("app").join("dep.ot"), "export const d = 1;\n").unwrap();
+        fs::write(dir.join("tool").join("main.ot"), "import './util.ot';\n").unwrap();
+        fs::write(dir.join("tool").join("util.ot"), "console.log(1);\n").unwrap();
+
+        // The tool comes first, so its resolver is created first
+        let hash_app = || {
+            let mut hasher = ModuleHasher::new();
+            hasher
+                .hash_module(&dir.join("tool").join("main.ot"))
+                .unwrap();
+            hasher
+                .hash_module(&dir.join("app").join("main.ot"))
+                .unwrap()
+        };
+        let hash = hash_app();
+        fs::write(dir.join("app").join("dep.ot"), "export const d = 2;\n").unwrap();
+        let changed = hash_app();
+        let _ = fs::remove_dir_all(&dir);
+        assert_ne!(hash, changed);
+    }
+
+    #[test]
+    fn test_compiler_build_hash_is_stable() {
+        let hash = compiler_build_hash();
+        assert_eq!(hash.len(), 64);
+        assert!(hash.bytes().all(|b| b.is_ascii_hexdigit()));
+        assert_eq!(compiler_build_hash(), hash);
+    }
+
+    #[test]
+    fn test_key_depends_on_config_and_target() {
+        let config = BackendConfig {
+            kind: BackendKind::LlvmAot,
+            opt_level: OptLevel::None,
+            ..Default::default()
+        };
+        let key = CacheKey::new(
+            "abc",
+            &config,
+            "x86_64-unknown-linux-gnu",
+            OutputFormat::Executable,
+        );
+        assert_eq!(key.as_str().len(), 64);
+        assert_eq!(
+            key,
+            CacheKey::new(
+                "abc",
+                &config,
+                "x86_64-unknown-linux-gnu",
+                OutputFormat::Executable
+            )
+        );
+
+        let release = BackendConfig {
+            opt_level: OptLevel::SpeedAndSize,
+            ..config.clone()
+        };
+        let variants = [
+            BackendConfig {
+                kind: BackendKind::CraneliftAot,
+                ..config.clone()
+            },
+            BackendConfig {
+                debug_info: true,
+                ..config.clone()
+            },
+            BackendConfig {
+                bounds_check: false,
+                ..config.clone()
+            },
+            BackendConfig {
+                lto_mode: LtoMode::Thin,
+                ..config.clone()
+            },
+        ];
+        for variant in &variants {
+            let other = CacheKey::new(
+                "abc",
+                variant,
+                "x86_64-unknown-linux-gnu",
+                OutputFormat::Executable,
+            );
+            assert_ne!(other, key, "{:?}", variant);
+        }
+        for other in [
+            CacheKey::new(
+                "abd",
+                &config,
+                "x86_64-unknown-linux-gnu",
+                OutputFormat::Executable,
+            ),
+            CacheKey::new(
+                "abc",
+                &release,
+                "x86_64-unknown-linux-gnu",
+                OutputFormat::Executable,
+            ),
+            CacheKey::new(
+                "abc",
+                &config,
+                "aarch64-unknown-linux-gnu",
+                OutputFormat::Executable,
+            ),
+            CacheKey::new(
+                "abc",
+                &config,
+                "x86_64-unknown-linux-gnu",
+                OutputFormat::SharedLib,
+            ),
+        ] {
+            assert_ne!(other, key);
+        }
+    }
+
+    #[test]
+    fn test_store_and_get_artifacts() {
+        let dir = temp_dir("store");
+        let cache = BuildCache::new(dir.join("cache"));
+        let key = CacheKey::new("abc", &BackendConfig::default(), "t", OutputFormat::Object);
+        assert!(cache.get(&key, Artifact::Object).is_none());
+
+        let path = cache.store(&key, Artifact::Object, b"object").unwrap();
+        assert_eq!(cache.get(&key, Artifact::Object), Some(path.clone()));
+        assert_eq!(fs::read(&path).unwrap(), b"object");
+        assert!(cache.get(&key, Artifact::Bitcode).is_none());
+
+        cache.clear().unwrap();
+        let cleared = cache.get(&key, Artifact::Object).is_none();
+        let _ = fs::remove_dir_all(&dir);
+        assert!(cleared);
+    }
+}
@@ -7,6 +7,7 @@ use sha2::{Digest, Sha256};
 use std::fs;
 use std::io::{self, Read};
 use std::path::Path;
+use std::process::Command;
 
 /// Version of the determinism verification protocol
 pub const DETERMINISM_VERSION: u32 = 1;
@@ -52,14 +53,16 @@ pub struct VerificationResult {
     pub differences: Vec<DifferenceReport>,
 }
 
-/// Report of a single difference between two artifacts
+/// Report of a range of differing bytes between two artifacts
 #[derive(Debug)]
 pub struct DifferenceReport {
-    /// Byte offset where difference was found
+    /// Byte offset where the range starts
     pub offset: usize,
-    /// Byte value in first artifact
+    /// Number of consecutive differing bytes
+    pub len: usize,
+    /// First byte of the range in the first artifact
     pub byte_a: u8,
-    /// Byte value in second artifact
+    /// First byte of the range in the second artifact
     pub byte_b: u8,
     /// Section name if known (e.g., ".text", ".data")
     pub section: Option<String>,
@@ -99,28 +102,34 @@ pub fn compare_artifacts(path_a: &Path, path_b: &Path) -> io::Result<Verificatio
 
     let mut differences = Vec::new();
 
-    // Find first N differences
+    // Find the first N ranges of differing bytes
     const MAX_DIFFERENCES: usize = 10;
     let min_len = data_a.len().min(data_b.len());
 
-    for i in 0..min_len {
-        if data_a[i] != data_b[i] {
-            differences.push(DifferenceReport {
-                offset: i,
-                byte_a: data_a[i],
-                byte_b: data_b[i],
-                section: None,
-            });
-            if differences.len() >= MAX_DIFFERENCES {
-                break;
-            }
+    let mut i = 0;
+    while i < min_len && differences.len() < MAX_DIFFERENCES {
+        if data_a[i] == data_b[i] {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < min_len && data_a[i] != data_b[i] {
+            i += 1;
         }
+        differences.push(DifferenceReport {
+            offset: start,
+            len: i - start,
+            byte_a: data_a[start],
+            byte_b: data_b[start],
+            section: section_at(&data_a, start),
+        });
     }
 
     // Report size difference
     if data_a.len() != data_b.len() && differences.len() < MAX_DIFFERENCES {
         differences.push(DifferenceReport {
             offset: min_len,
+            len: 0,
             byte_a: 0,
             byte_b: 0,
             section: Some(format!(
@@ -141,28 +150,57 @@ pub fn compare_artifacts(path_a: &Path, path_b: &Path) -> io::Result<Verificatio
     })
 }
 
+/// Name of the section of an object file or executable containing `offset`
+fn section_at(data: &[u8], offset: usize) -> Option<String> {
+    use object::{Object, ObjectSection};
+
+    let file = object::File::parse(data).ok()?;
+    let offset = offset as u64;
+    file.sections()
+        .find(|section| {
+            section
+                .file_range()
+                .is_some_and(|(start, size)| (start..start + size).contains(&offset))
+        })
+        .and_then(|section| section.name().ok().map(str::to_string))
+}
+
 /// Verify that two consecutive builds produce identical output
 ///
 /// This function compiles the same source twice using the provided build function
-/// and verifies that the outputs are identical.
+/// and verifies that the outputs are identical. Each build writes into its own
+/// subdirectory of `temp_dir`, at paths of different lengths, and the second
+/// one runs in a `NormalizedEnv::varied` environment with its own temporary
+/// directory and user, so output that depends on the build path or the
+/// environment shows up as a difference.
 pub fn verify_determinism<F, E>(
     source_path: &Path,
     temp_dir: &Path,
-    build_fn: F,
+    mut build_fn: F,
 ) -> Result<VerificationResult, E>
 where
-    F: Fn(&Path, &Path) -> Result<(), E>,
+    F: FnMut(&Path, &Path) -> Result<(), E>,
+    E: From<io::Error>,
 {
-    let output_a = temp_dir.join("determinism_test_a");
-    let output_b = temp_dir.join("determinism_test_b");
-
-    // Build twice
-    build_fn(source_path, &output_a)?;
-    build_fn(source_path, &output_b)?;
+    let dir_a = temp_dir.join("a");
+    let dir_b = temp_dir.join("b").join("second-build");
+    let output_a = dir_a.join("determinism_test");
+    let output_b = dir_b.join("determinism_test");
+    fs::create_dir_all(&dir_a)?;
+    fs::create_dir_all(dir_b.join("tmp"))?;
+
+    // Build twice, the second time in a different environment
+    {
+        let _env = NormalizedEnv::new();
+        build_fn(source_path, &output_a)?;
+    }
+    {
+        let _env = NormalizedEnv::varied(&dir_b.join("tmp"));
+        build_fn(source_path, &output_b)?;
+    }
 
     // Compare outputs
-    compare_artifacts(&output_a, &output_b)
-        .map_err(|e| panic!("Failed to compare artifacts: {}", e))
+    Ok(compare_artifacts(&output_a, &output_b)?)
 }
 
 /// Get deterministic timestamp for build metadata
@@ -176,12 +214,41 @@ pub fn deterministic_timestamp() -> u64 {
         .unwrap_or(0)
 }
 
+/// Locale, time zone and timestamp variables of a normalized environment
+const NORMALIZED_VARS: &[(&str, &str)] = &[("LANG", "C"), ("LC_ALL", "C"), ("TZ", "UTC")];
+
+/// Directory that source paths are remapped to in build artifacts
+pub const NORMALIZED_SOURCE_DIR: &str = "/oite";
+
+/// Run `cmd` in a normalized environment without touching our own: fixed
+/// locale and time zone, and `deterministic_timestamp` as the build time of
+/// tools that embed one (`SOURCE_DATE_EPOCH`, and `ZERO_AR_DATE` for Apple `ar`)
+pub fn normalize_command_env(cmd: &mut Command) -> &mut Command {
+    cmd.envs(NORMALIZED_VARS.iter().copied())
+        .env("SOURCE_DATE_EPOCH", deterministic_timestamp().to_string())
+        .env("ZERO_AR_DATE", "1")
+}
+
+/// `rustc` flag that rewrites `dir` to `NORMALIZED_SOURCE_DIR` in paths the
+/// compiler embeds (panic locations, debug info)
+pub fn remap_path_prefix(dir: &Path) -> String {
+    format!(
+        "--remap-path-prefix={}={}",
+        dir.display(),
+        NORMALIZED_SOURCE_DIR
+    )
+}
+
+/// User and host name of a `NormalizedEnv::varied` environment
+const VARIED_IDENTITY: &str = "oite-verify";
+
 /// Normalize environment for deterministic builds
 ///
 /// This function saves the current environment and sets up
 /// a normalized environment for reproducible compilation.
 pub struct NormalizedEnv {
-    original_env: std::collections::HashMap<String, String>,
+    /// Value of each variable we set before we set it, `None` if it was unset
+    original_env: std::collections::HashMap<&'static str, Option<std::ffi::OsString>>,
 }
 
 impl NormalizedEnv {
@@ -192,30 +259,40 @@ impl NormalizedEnv {
     /// unsafe in multi-threaded contexts. Use only in single-threaded
     /// build processes.
     pub fn new() -> Self {
-        let mut original_env = std::collections::HashMap::new();
+        let mut env = Self {
+            original_env: std::collections::HashMap::new(),
+        };
+        for (key, val) in NORMALIZED_VARS {
+            env.set(key, val);
+        }
+        // Set SOURCE_DATE_EPOCH if not already set (for reproducible timestamps)
+        if std::env::var_os("SOURCE_DATE_EPOCH").is_none() {
+            env.set("SOURCE_DATE_EPOCH", "0");
+        }
+        env
+    }
 
-        // Save and normalize relevant env vars
-        for key in &["LANG", "LC_ALL", "TZ", "SOURCE_DATE_EPOCH"] {
-            if let Ok(val) = std::env::var(key) {
-                original_env.insert(key.to_string(), val);
-            }
+    /// A normalized environment that differs from `new` in everything a
+    /// reproducible build must not depend on: the temporary directory is
+    /// `temp_dir`, and the user and host names are changed. The same
+    /// safety caveat as `new` applies.
+    pub fn varied(temp_dir: &Path) -> Self {
+        let mut env = Self::new();
+        env.set("TMPDIR", temp_dir);
+        for key in ["USER", "LOGNAME", "HOSTNAME"] {
+            env.set(key, VARIED_IDENTITY);
         }
+        env
+    }
 
+    /// Set `key`, remembering its value from before the first change
+    fn set(&mut self, key: &'static str, val: impl AsRef<std::ffi::OsStr>) {
+        self.original_env
+            .entry(key)
+            .or_insert_with(|| std::env::var_os(key));
         // SAFETY: Environment variable modification is only safe in single-threaded
         // contexts. Build processes are typically single-threaded.
-        unsafe {
-            // Set normalized values
-            std::env::set_var("LANG", "C");
-            std::env::set_var("LC_ALL", "C");
-            std::env::set_var("TZ", "UTC");
-
-            // Set SOURCE_DATE_EPOCH if not already set (for reproducible timestamps)
-            if std::env::var("SOURCE_DATE_EPOCH").is_err() {
-                std::env::set_var("SOURCE_DATE_EPOCH", "0");
-            }
-        }
-
-        Self { original_env }
+        unsafe { std::env::set_var(key, val) }
     }
 }
 
@@ -230,15 +307,11 @@ impl Drop for NormalizedEnv {
         // SAFETY: Environment variable modification is only safe in single-threaded
         // contexts. Build processes are typically single-threaded.
         unsafe {
-            // Restore original environment
+            // Restore original environment, removing vars that weren't set
             for (key, val) in &self.original_env {
-                std::env::set_var(key, val);
-            }
-
-            // Remove vars that weren't originally set
-            for key in &["LANG", "LC_ALL", "TZ", "SOURCE_DATE_EPOCH"] {
-                if !self.original_env.contains_key(*key) {
-                    std::env::remove_var(key);
+                match val {
+                    Some(val) => std::env::set_var(key, val),
+                    None => std::env::remove_var(key),
                 }
             }
         }
@@ -327,6 +400,92 @@ mod tests {
         cleanup_test_dir(&dir);
     }
 
+    #[test]
+    fn test_compare_reports_difference_ranges() {
+        let dir = create_test_dir("compare_ranges");
+        let path_a = dir.join("a.bin");
+        let path_b = dir.join("b.bin");
+
+        fs::write(&path_a, b"aaaaXXaaaaYaaa").unwrap();
+        fs::write(&path_b, b"aaaaZZaaaaWaaa!").unwrap();
+
+        let result = compare_artifacts(&path_a, &path_b).unwrap();
+        let ranges: Vec<(usize, usize)> = result
+            .differences
+            .iter()
+            .map(|d| (d.offset, d.len))
+            .collect();
+        assert_eq!(ranges, vec![(4, 2), (10, 1), (14, 0)]);
+        assert_eq!(
+            (result.differences[0].byte_a, result.differences[0].byte_b),
+            (b'X', b'Z')
+        );
+
+        cleanup_test_dir(&dir);
+    }
+
+    #[test]
+    fn test_section_at_finds_containing_section() {
+        use object::{Object, ObjectSection};
+
+        let exe = fs::read(std::env::current_exe().unwrap()).unwrap();
+        let file = object::File::parse(&*exe).unwrap();
+        let (name, (start, size)) = file
+            .sections()
+            .filter_map(|s| Some((s.name().ok()?.to_string(), s.file_range()?)))
+            .find(|(_, (_, size))| *size > 0)
+            .unwrap();
+        assert_eq!(section_at(&exe, (start + size - 1) as usize), Some(name));
+        assert_eq!(section_at(b"not an object file", 0), None);
+    }
+
+    #[test]
+    fn test_verify_determinism_builds_in_separate_dirs() {
+        let dir = create_test_dir("verify_separate_dirs");
+        let source = dir.join("main.ot");
+
+        // Output that embeds the build path is not reproducible
+        let result = verify_determinism(&source, &dir, |_, output| -> io::Result<()> {
+            fs::write(output, output.display().to_string())
+        })
+        .unwrap();
+        assert!(!result.is_deterministic);
+
+        let result = verify_determinism(&source, &dir, |source, output| -> io::Result<()> {
+            fs::write(output, source.display().to_string())
+        })
+        .unwrap();
+        assert!(result.is_deterministic);
+
+        cleanup_test_dir(&dir);
+    }
+
+    #[test]
+    fn test_verify_determinism_varies_environment() {
+        let dir = create_test_dir("verify_varied_env");
+        let source = dir.join("main.ot");
+
+        // Output that embeds the temporary directory or user is not reproducible
+        let result = verify_determinism(&source, &dir, |_, output| -> io::Result<()> {
+            let user = std::env::var("USER").unwrap_or_default();
+            fs::write(output, format!("{} {user}", std::env::temp_dir().display()))
+        })
+        .unwrap();
+        assert!(!result.is_deterministic);
+
+        cleanup_test_dir(&dir);
+    }
+
+    #[test]
+    fn test_normalized_env_restores_varied_vars() {
+        let before = std::env::var_os("LOGNAME");
+        {
+            let _env = NormalizedEnv::varied(&std::env::temp_dir());
+            assert_eq!(std::env::var("LOGNAME").unwrap(), VARIED_IDENTITY);
+        }
+        assert_eq!(std::env::var_os("LOGNAME"), before);
+    }
+
     #[test]
     fn test_deterministic_timestamp() {
         // SAFETY: Environment variable tests should run in single-threaded context
@@ -353,6 +512,26 @@ mod tests {
         // NormalizedEnv is dropped here, restoring original env
     }
 
+    #[test]
+    fn test_normalize_command_env() {
+        let mut cmd = Command::new("true");
+        normalize_command_env(&mut cmd);
+        let envs: std::collections::HashMap<_, _> = cmd
+            .get_envs()
+            .map(|(key, val)| (key.to_owned(), val.map(|v| v.to_owned())))
+            .collect();
+        assert_eq!(envs[std::ffi::OsStr::new("TZ")], Some("UTC".into()));
+        assert!(envs.contains_key(std::ffi::OsStr::new("SOURCE_DATE_EPOCH")));
+    }
+
+    #[test]
+    fn test_remap_path_prefix() {
+        assert_eq!(
+            remap_path_prefix(Path::new("/home/me/oite")),
+            "--remap-path-prefix=/home/me/oite=/oite"
+        );
+    }
+
     #[test]
     fn test_determinism_config_default() {
         let config = DeterminismConfig::default();
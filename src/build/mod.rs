@@ -1,7 +1,9 @@
 //! Build utilities for deterministic compilation
 //!
 //! This module provides tools for verifying that builds are reproducible
-//! and for comparing build artifacts across compilations.
+//! and for comparing build artifacts across compilations, and the
+//! content-addressed cache that makes `oitec build` incremental.
 
+pub mod cache;
 pub mod deterministic;
 pub use deterministic::*;
@@ -13,6 +13,16 @@ use crate::types::Type;
 use crate::types::error::{BorrowKind, Span, TypeError, TypeErrors};
 use crate::types::registry::TypeRegistry;
 
+/// Globals that hand their callback to the event loop. The callback outlives
+/// the statement that schedules it, so it takes ownership of everything it
+/// captures, top-level bindings included.
+const ASYNC_SCHEDULERS: &[&str] = &[
+    "setTimeout",
+    "setInterval",
+    "setImmediate",
+    "queueMicrotask",
+];
+
 #[derive(PartialEq, Clone, Copy, Debug)]
 pub enum VarKind {
     Primitive,
@@ -328,11 +338,23 @@ impl BorrowChecker {
                 self.analyze_expr(&un.arg)?;
             }
             Expr::Call(call) => {
+                let schedules_callback = matches!(
+                    &call.callee,
+                    Callee::Expr(callee) if matches!(
+                        callee.as_ref(),
+                        Expr::Ident(id) if ASYNC_SCHEDULERS.contains(&id.sym.as_ref())
+                    )
+                );
                 for arg in &call.args {
-                    if let Expr::Ident(id) = arg.expr.as_ref() {
-                        self.process_borrow(id.sym.as_ref(), false)?;
-                    } else {
-                        self.analyze_expr(&arg.expr)?;
+                    match arg.expr.as_ref() {
+                        Expr::Ident(id) => self.process_borrow(id.sym.as_ref(), false)?,
+                        Expr::Arrow(arrow) if schedules_callback => {
+                            self.analyze_closure(&arrow.params, &arrow.body, true)?
+                        }
+                        Expr::Fn(fn_expr) if schedules_callback => {
+                            self.analyze_fn_closure(fn_expr, true)?
+                        }
+                        other => self.analyze_expr(other)?,
                     }
                 }
                 if let Callee::Expr(callee_expr) = &call.callee {
@@ -354,10 +376,10 @@ impl BorrowChecker {
                 }
             }
             Expr::Arrow(arrow) => {
-                self.analyze_closure(&arrow.params, &arrow.body)?;
+                self.analyze_closure(&arrow.params, &arrow.body, false)?;
             }
             Expr::Fn(fn_expr) => {
-                self.analyze_fn_closure(fn_expr)?;
+                self.analyze_fn_closure(fn_expr, false)?;
             }
             Expr::Cond(cond) => {
                 self.analyze_expr(&cond.test)?;
@@ -496,7 +518,14 @@ impl BorrowChecker {
         }
     }
 
-    fn analyze_closure(&mut self, params: &[Pat], body: &BlockStmtOrExpr) -> Result<(), String> {
+    /// Record what a closure captures. An `escaping` closure (one handed to
+    /// the event loop) also captures globals.
+    fn analyze_closure(
+        &mut self,
+        params: &[Pat],
+        body: &BlockStmtOrExpr,
+        escaping: bool,
+    ) -> Result<(), String> {
         let param_names: HashSet<String> = params
             .iter()
             .filter_map(|p| {
@@ -521,13 +550,13 @@ impl BorrowChecker {
         }
 
         for var_name in &captured {
-            self.process_capture(var_name)?;
+            self.process_capture(var_name, escaping)?;
         }
 
         Ok(())
     }
 
-    fn analyze_fn_closure(&mut self, fn_expr: &FnExpr) -> Result<(), String> {
+    fn analyze_fn_closure(&mut self, fn_expr: &FnExpr, escaping: bool) -> Result<(), String> {
         let param_names: HashSet<String> = fn_expr
             .function
             .params
@@ -549,15 +578,15 @@ impl BorrowChecker {
         }
 
         for var_name in &captured {
-            self.process_capture(var_name)?;
+            self.process_capture(var_name, escaping)?;
         }
 
         Ok(())
     }
 
-    fn process_capture(&mut self, name: &str) -> Result<(), String> {
+    fn process_capture(&mut self, name: &str, escaping: bool) -> Result<(), String> {
         if let Some(info) = self.symbols.get_mut(name) {
-            if info.is_global() {
+            if info.is_global() && !escaping {
                 return Ok(());
             }
 
@@ -811,6 +840,17 @@ mod tests {
         assert!(checker.process_use("Pipeline").is_ok()); // Globals aren't moved
     }
 
+    #[test]
+    fn test_escaping_closure_captures_globals() {
+        let mut checker = BorrowChecker::new();
+        checker.define("data".to_string(), Type::Any, Span::default());
+
+        assert!(checker.process_capture("data", false).is_ok());
+        assert!(checker.process_use("data").is_ok());
+        assert!(checker.process_capture("data", true).is_ok());
+        assert!(checker.process_use("data").is_err());
+    }
+
     #[test]
     fn test_primitive_move_is_copy() {
         let mut checker = BorrowChecker::new();
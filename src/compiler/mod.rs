@@ -1,14 +1,74 @@
+use crate::module::ModuleResolver;
+use crate::module::diagnostics::ModuleError;
+use crate::module::resolver::ImportAssertions;
 use crate::vm::opcodes::OpCode;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use swc_ecma_ast::*;
 pub mod borrow_ck;
+pub mod source_map;
 use crate::compiler::borrow_ck::BorrowChecker;
-use crate::vm::value::JsValue;
-use swc_common::{FileName, SourceMap, sync::Lrc};
-use swc_ecma_parser::{Parser, StringInput, Syntax, lexer::Lexer};
+use crate::compiler::source_map::{BytecodeSourceMap, SourceLocation};
+use crate::vm::value::{JsString, JsValue};
+use swc_common::{BytePos, FileName, SourceMap, Spanned, sync::Lrc};
+use swc_ecma_parser::{Parser, StringInput, Syntax, TsSyntax, lexer::Lexer};
 
 pub struct Compiler {
     pub borrow_checker: BorrowChecker,
+    /// Next slot for a hidden import namespace variable; never reset, so
+    /// names stay unique across REPL lines compiled by the same compiler
+    next_import_slot: usize,
+    /// Import bindings of script compiles, kept so later REPL lines see them
+    import_bindings: HashMap<String, ImportBinding>,
+    /// File being compiled; asset imports are resolved against it
+    source_path: Option<PathBuf>,
+}
+
+/// An imported name: read live from a module namespace held in a hidden variable
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportBinding {
+    pub namespace_var: String,
+    pub export: String,
+}
+
+/// How a module export is bound, for the VM's module linker
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExportBinding {
+    /// A top-level variable of the module
+    Local { export: String, local: String },
+    /// `export { name as export } from "..."`; the source namespace is held in
+    /// `namespace_var`
+    Reexport {
+        export: String,
+        namespace_var: String,
+        name: String,
+    },
+    /// `export * from "..."`
+    Star { namespace_var: String },
+}
+
+/// Bytecode and export bindings of a compiled module
+pub struct CompiledModule {
+    pub bytecode: Vec<OpCode>,
+    pub exports: Vec<ExportBinding>,
+    /// Specifiers of the module's static imports, in source order
+    pub imports: Vec<String>,
+    pub source_map: BytecodeSourceMap,
+}
+
+/// Syntax for a source file, picked by extension: JavaScript for `.js` and
+/// `.jsx`, TypeScript with decorators otherwise
+pub fn syntax_for_path(path: &Path) -> Syntax {
+    let path = path.to_string_lossy();
+    if path.ends_with(".js") || path.ends_with(".jsx") {
+        Syntax::Es(Default::default())
+    } else {
+        Syntax::Typescript(TsSyntax {
+            decorators: true,
+            tsx: path.ends_with(".tsx"),
+            ..Default::default()
+        })
+    }
 }
 
 impl Default for Compiler {
@@ -21,9 +81,18 @@ impl Compiler {
     pub fn new() -> Self {
         Self {
             borrow_checker: BorrowChecker::new(),
+            next_import_slot: 0,
+            import_bindings: HashMap::new(),
+            source_path: None,
         }
     }
 
+    /// Set the file the next sources come from. Without it, asset imports
+    /// (`with { type: "json" }`) resolve against the working directory.
+    pub fn set_source_path(&mut self, path: impl Into<PathBuf>) {
+        self.source_path = Some(path.into());
+    }
+
     pub fn compile(&mut self, source: &str) -> Result<Vec<OpCode>, String> {
         self.compile_with_syntax(source, None)
     }
@@ -33,6 +102,68 @@ impl Compiler {
         source: &str,
         syntax_override: Option<Syntax>,
     ) -> Result<Vec<OpCode>, String> {
+        self.compile_with_source_map(source, syntax_override)
+            .map(|(bytecode, _)| bytecode)
+    }
+
+    /// Compile source and also return a map from instruction addresses to source lines
+    pub fn compile_with_source_map(
+        &mut self,
+        source: &str,
+        syntax_override: Option<Syntax>,
+    ) -> Result<(Vec<OpCode>, BytecodeSourceMap), String> {
+        let import_bindings = std::mem::take(&mut self.import_bindings);
+        let result = self.compile_unit(source, syntax_override, 0, import_bindings, false);
+        result.map(|(codegen, source_map)| {
+            self.import_bindings = codegen.import_bindings;
+            (codegen.instructions, source_map)
+        })
+    }
+
+    /// Compile one REPL input. If it ends with an expression statement, the
+    /// program leaves that value on the stack when it halts, and the returned
+    /// flag is true.
+    pub fn compile_repl_input(
+        &mut self,
+        source: &str,
+        syntax_override: Option<Syntax>,
+    ) -> Result<(Vec<OpCode>, bool), String> {
+        let import_bindings = std::mem::take(&mut self.import_bindings);
+        let result = self.compile_unit(source, syntax_override, 0, import_bindings, true);
+        result.map(|(codegen, _)| {
+            self.import_bindings = codegen.import_bindings;
+            (codegen.instructions, codegen.result_captured)
+        })
+    }
+
+    /// Compile an imported module.
+    ///
+    /// `module_id` (nonzero, unique per loaded module) keeps the module's
+    /// hidden variables apart from other modules' in the shared global scope.
+    pub fn compile_module(
+        &mut self,
+        source: &str,
+        syntax_override: Option<Syntax>,
+        module_id: usize,
+    ) -> Result<CompiledModule, String> {
+        let (codegen, source_map) =
+            self.compile_unit(source, syntax_override, module_id, HashMap::new(), false)?;
+        Ok(CompiledModule {
+            bytecode: codegen.instructions,
+            exports: codegen.exports,
+            imports: codegen.imports,
+            source_map,
+        })
+    }
+
+    fn compile_unit(
+        &mut self,
+        source: &str,
+        syntax_override: Option<Syntax>,
+        module_id: usize,
+        import_bindings: HashMap<String, ImportBinding>,
+        capture_result: bool,
+    ) -> Result<(Codegen, BytecodeSourceMap), String> {
         let cm: Lrc<SourceMap> = Default::default();
         let fm = cm.new_source_file(
             FileName::Custom("main.ot".into()).into(),
@@ -82,6 +213,11 @@ impl Compiler {
         result?;
 
         let mut codegen = Codegen::new();
+        codegen.module_id = module_id;
+        codegen.next_import_slot = self.next_import_slot;
+        codegen.import_bindings = import_bindings;
+        codegen.source_path = self.source_path.clone();
+        codegen.capture_result = capture_result;
         match &program {
             Program::Module(module) => {
                 codegen.generate(module);
@@ -90,8 +226,29 @@ impl Compiler {
                 codegen.generate_script(script);
             }
         }
+        self.next_import_slot = codegen.next_import_slot;
+        if !codegen.errors.is_empty() {
+            return Err(codegen.errors.join("\n"));
+        }
 
-        Ok(codegen.instructions)
+        let locations = codegen
+            .statement_starts
+            .iter()
+            .map(|&(ip, pos)| {
+                let loc = cm.lookup_char_pos(pos);
+                SourceLocation {
+                    ip,
+                    line: loc.line as u32,
+                    column: loc.col_display as u32,
+                }
+            })
+            .collect();
+        let source_map = BytecodeSourceMap::from_unsorted(
+            locations,
+            std::mem::take(&mut codegen.function_starts),
+        );
+
+        Ok((codegen, source_map))
     }
 }
 
@@ -117,6 +274,30 @@ pub struct Codegen {
     private_method_indices: std::collections::HashMap<String, usize>,
     /// Warnings collected during compilation
     pub warnings: Vec<String>,
+    /// Address and source position of each generated statement
+    pub statement_starts: Vec<(usize, BytePos)>,
+    /// Entry address of each named function declaration
+    pub function_starts: Vec<(usize, String)>,
+    /// Identifies the module being compiled in hidden variable names (0 for scripts)
+    pub module_id: usize,
+    next_import_slot: usize,
+    /// Imported names in scope; reads go through the module namespace
+    import_bindings: HashMap<String, ImportBinding>,
+    /// Hidden namespace variable for each specifier imported by this unit
+    namespace_vars: HashMap<String, String>,
+    /// Exports declared by the module
+    pub exports: Vec<ExportBinding>,
+    /// Specifiers imported by the module (not counting asset imports)
+    pub imports: Vec<String>,
+    /// File being compiled, for resolving asset imports
+    source_path: Option<PathBuf>,
+    /// Errors that fail the compile (e.g. a missing asset file)
+    pub errors: Vec<String>,
+    /// Keep the value of a trailing top-level expression statement on the
+    /// stack at `Halt` (REPL inputs)
+    pub capture_result: bool,
+    /// Set when `capture_result` applied, i.e. the unit ended with an expression
+    pub result_captured: bool,
 }
 
 impl Default for Codegen {
@@ -137,6 +318,18 @@ impl Codegen {
             private_field_indices: std::collections::HashMap::new(),
             private_method_indices: std::collections::HashMap::new(),
             warnings: Vec::new(),
+            statement_starts: Vec::new(),
+            function_starts: Vec::new(),
+            module_id: 0,
+            next_import_slot: 0,
+            import_bindings: HashMap::new(),
+            namespace_vars: HashMap::new(),
+            exports: Vec::new(),
+            imports: Vec::new(),
+            source_path: None,
+            errors: Vec::new(),
+            capture_result: false,
+            result_captured: false,
         }
     }
 
@@ -261,12 +454,41 @@ impl Codegen {
     }
 
     pub fn generate(&mut self, module: &Module) -> Vec<OpCode> {
-        for item in &module.body {
+        // Module order: bind function declarations, evaluate imports, then run
+        // the body. Binding functions first lets a module that imports us back
+        // (an import cycle) call them before our body has run.
+        let is_hoisted_fn = |item: &ModuleItem| {
+            matches!(
+                item,
+                ModuleItem::Stmt(Stmt::Decl(Decl::Fn(_)))
+                    | ModuleItem::ModuleDecl(ModuleDecl::ExportDecl(ExportDecl {
+                        decl: Decl::Fn(_),
+                        ..
+                    }))
+                    | ModuleItem::ModuleDecl(ModuleDecl::ExportDefaultDecl(ExportDefaultDecl {
+                        decl: DefaultDecl::Fn(_),
+                        ..
+                    }))
+            )
+        };
+        let is_import =
+            |item: &ModuleItem| matches!(item, ModuleItem::ModuleDecl(ModuleDecl::Import(_)));
+
+        let hoisted = module.body.iter().filter(|item| is_hoisted_fn(item));
+        let imports = module.body.iter().filter(|item| is_import(item));
+        let rest = module
+            .body
+            .iter()
+            .filter(|item| !is_hoisted_fn(item) && !is_import(item));
+        let items: Vec<&ModuleItem> = hoisted.chain(imports).chain(rest).collect();
+        for (i, item) in items.iter().enumerate() {
             match item {
                 ModuleItem::Stmt(stmt) => {
-                    self.gen_stmt(stmt);
+                    self.gen_top_level_stmt(stmt, i + 1 == items.len());
                 }
                 ModuleItem::ModuleDecl(decl) => {
+                    self.statement_starts
+                        .push((self.instructions.len(), decl.span().lo));
                     self.gen_module_decl(decl);
                 }
             }
@@ -275,51 +497,303 @@ impl Codegen {
         self.instructions.clone()
     }
 
-    fn gen_module_decl(&mut self, decl: &ModuleDecl) {
-        match decl {
-            ModuleDecl::ExportDecl(export_decl) => {
-                self.gen_decl(&export_decl.decl);
-            }
-            ModuleDecl::ExportDefaultDecl(export_default) => match &export_default.decl {
-                DefaultDecl::Class(class_expr) => {
-                    self.gen_class(
-                        &class_expr.class,
-                        class_expr.ident.as_ref().map(|id| id.sym.as_str()),
+    /// Hidden variable holding the namespace of `specifier`, importing it on first use
+    fn import_namespace(&mut self, specifier: &str) -> String {
+        if let Some(var) = self.namespace_vars.get(specifier) {
+            return var.clone();
+        }
+        let var = format!("__ns{}_{}__", self.module_id, self.next_import_slot);
+        self.next_import_slot += 1;
+        self.instructions
+            .push(OpCode::Push(JsValue::String(specifier.into())));
+        self.instructions
+            .push(OpCode::ImportAsync(specifier.to_string()));
+        self.instructions.push(OpCode::Let(var.clone()));
+        self.namespace_vars
+            .insert(specifier.to_string(), var.clone());
+        self.imports.push(specifier.to_string());
+        var
+    }
+
+    /// Hidden variable holding an anonymous default export
+    fn default_export_var(&self) -> String {
+        format!("__default{}__", self.module_id)
+    }
+
+    /// Embed a data file imported `with { type: "json" | "text" | "bytes" }`.
+    ///
+    /// The value is built from the file's contents at compile time, so it
+    /// needs no file access at run time (including in AOT builds). Returns the
+    /// hidden variable holding the value, or `None` for a regular module import.
+    fn import_asset(&mut self, specifier: &str, with: Option<&ObjectLit>) -> Option<String> {
+        let resolver = ModuleResolver::new();
+        let kind = match resolver.parse_import_assertions(with) {
+            Some(kind) if kind.is_asset() => kind,
+            Some(ImportAssertions::Custom(entries)) => {
+                if let Some((_, ty)) = entries.iter().find(|(key, _)| key == "type") {
+                    self.errors.push(
+                        ModuleError::unsupported_assertion(ty.clone(), specifier.to_string())
+                            .to_string(),
                     );
                 }
-                DefaultDecl::Fn(fn_expr) => {
-                    let name = if let Some(id) = fn_expr.ident.clone() {
-                        Some(id.sym.to_string())
-                    } else {
-                        None
-                    };
-                    self.gen_fn_decl(name, &fn_expr.function);
+                return None;
+            }
+            _ => {
+                if specifier.ends_with(".json") {
+                    self.errors.push(format!(
+                        "JSON module '{}' must be imported with {{ type: \"json\" }}",
+                        specifier
+                    ));
+                }
+                return None;
+            }
+        };
+
+        let importer = self
+            .source_path
+            .clone()
+            .or_else(|| std::env::current_dir().ok())
+            .unwrap_or_default();
+        let importer = std::fs::canonicalize(&importer).unwrap_or(importer);
+        let resolver =
+            ModuleResolver::discover(&importer).unwrap_or_else(|_| ModuleResolver::new());
+        let path = match resolver.resolve(specifier, &importer) {
+            Ok(resolved) => resolved.path.as_ref().clone(),
+            Err(e) => {
+                self.errors.push(e.to_string());
+                return None;
+            }
+        };
+
+        // Importing the same file twice gives the same value
+        let key = format!("{:?}:{}", kind, path.display());
+        if let Some(var) = self.namespace_vars.get(&key) {
+            return Some(var.clone());
+        }
+
+        let contents = match std::fs::read(&path) {
+            Ok(contents) => contents,
+            Err(e) => {
+                self.errors
+                    .push(format!("Failed to read '{}': {}", path.display(), e));
+                return None;
+            }
+        };
+        match kind {
+            ImportAssertions::Json => match serde_json::from_slice(&contents) {
+                Ok(json) => self.gen_json_value(&json),
+                Err(e) => {
+                    self.errors
+                        .push(format!("Invalid JSON in '{}': {}", path.display(), e));
+                    return None;
                 }
-                _ => {}
             },
-            ModuleDecl::ExportDefaultExpr(export_default) => match &*export_default.expr {
-                Expr::Fn(fn_expr) => {
-                    let name = if let Some(id) = fn_expr.ident.clone() {
-                        Some(id.sym.to_string())
-                    } else {
-                        None
-                    };
-                    self.gen_fn_decl(name, &fn_expr.function);
+            ImportAssertions::Text => match String::from_utf8(contents) {
+                Ok(text) => self
+                    .instructions
+                    .push(OpCode::Push(JsValue::String(text.into()))),
+                Err(_) => {
+                    self.errors
+                        .push(format!("'{}' is not valid UTF-8 text", path.display()));
+                    return None;
                 }
-                Expr::Class(class_expr) => {
-                    self.gen_class(
-                        &class_expr.class,
-                        class_expr.ident.as_ref().map(|id| id.sym.as_str()),
-                    );
+            },
+            _ => {
+                // An array of byte values
+                self.instructions.push(OpCode::NewArray(contents.len()));
+                for (i, byte) in contents.iter().enumerate() {
+                    self.instructions.push(OpCode::Dup);
+                    self.instructions
+                        .push(OpCode::Push(JsValue::Number(*byte as f64)));
+                    self.instructions
+                        .push(OpCode::Push(JsValue::Number(i as f64)));
+                    self.instructions.push(OpCode::StoreElement);
                 }
-                _ => {}
+                self.instructions.push(OpCode::Freeze);
+            }
+        }
+
+        let var = format!("__asset{}_{}__", self.module_id, self.next_import_slot);
+        self.next_import_slot += 1;
+        self.instructions.push(OpCode::Let(var.clone()));
+        self.namespace_vars.insert(key, var.clone());
+        Some(var)
+    }
+
+    /// Build a frozen copy of a JSON value on the stack
+    fn gen_json_value(&mut self, value: &serde_json::Value) {
+        use serde_json::Value;
+        match value {
+            Value::Null => self.instructions.push(OpCode::Push(JsValue::Null)),
+            Value::Bool(b) => self.instructions.push(OpCode::Push(JsValue::Boolean(*b))),
+            Value::Number(n) => self.instructions.push(OpCode::Push(JsValue::Number(
+                n.as_f64().unwrap_or(f64::NAN),
+            ))),
+            Value::String(s) => self
+                .instructions
+                .push(OpCode::Push(JsValue::String(s.into()))),
+            Value::Array(items) => {
+                self.instructions.push(OpCode::NewArray(items.len()));
+                for (i, item) in items.iter().enumerate() {
+                    self.instructions.push(OpCode::Dup);
+                    self.gen_json_value(item);
+                    self.instructions
+                        .push(OpCode::Push(JsValue::Number(i as f64)));
+                    self.instructions.push(OpCode::StoreElement);
+                }
+                self.instructions.push(OpCode::Freeze);
+            }
+            Value::Object(map) => {
+                self.instructions.push(OpCode::NewObject);
+                for (key, item) in map {
+                    self.instructions.push(OpCode::Dup);
+                    self.gen_json_value(item);
+                    self.instructions.push(OpCode::SetProp(key.clone()));
+                }
+                self.instructions.push(OpCode::Freeze);
+            }
+        }
+    }
+
+    /// Bind the specifiers of an asset import; its value is the default export
+    fn bind_asset_import(
+        &mut self,
+        specifier: &str,
+        asset_var: &str,
+        specifiers: &[ImportSpecifier],
+    ) {
+        for spec in specifiers {
+            let local = match spec {
+                ImportSpecifier::Default(default) => default.local.sym.to_string(),
+                ImportSpecifier::Named(named) => {
+                    let imported = named
+                        .imported
+                        .as_ref()
+                        .map(|i| i.atom().to_string())
+                        .unwrap_or_else(|| named.local.sym.to_string());
+                    if imported != "default" {
+                        self.errors.push(format!(
+                            "'{}' has no export named '{}'; it only has a default export",
+                            specifier, imported
+                        ));
+                        continue;
+                    }
+                    named.local.sym.to_string()
+                }
+                ImportSpecifier::Namespace(ns) => {
+                    // `{ default: value }`
+                    self.instructions.push(OpCode::NewObject);
+                    self.instructions.push(OpCode::Dup);
+                    self.instructions.push(OpCode::Load(asset_var.to_string()));
+                    self.instructions
+                        .push(OpCode::SetProp("default".to_string()));
+                    self.instructions.push(OpCode::Freeze);
+                    let local = ns.local.sym.to_string();
+                    self.instructions.push(OpCode::Let(local.clone()));
+                    self.outer_scope_vars.insert(local);
+                    continue;
+                }
+            };
+            self.instructions.push(OpCode::Load(asset_var.to_string()));
+            self.instructions.push(OpCode::Let(local.clone()));
+            self.outer_scope_vars.insert(local);
+        }
+    }
+
+    /// Hide imports named like a function's parameters while its body compiles.
+    /// Returns the bindings to restore once the body is done.
+    fn shadow_imports<'a>(
+        &mut self,
+        names: impl IntoIterator<Item = &'a String>,
+    ) -> HashMap<String, ImportBinding> {
+        let saved = self.import_bindings.clone();
+        for name in names {
+            self.import_bindings.remove(name);
+        }
+        saved
+    }
+
+    fn export_local(&mut self, export: String, local: String) {
+        // Re-exporting an import forwards to the source module's binding
+        let binding = match self.import_bindings.get(&local) {
+            Some(import) => ExportBinding::Reexport {
+                export,
+                namespace_var: import.namespace_var.clone(),
+                name: import.export.clone(),
             },
+            None => ExportBinding::Local { export, local },
+        };
+        self.exports.push(binding);
+    }
+
+    fn gen_module_decl(&mut self, decl: &ModuleDecl) {
+        match decl {
+            ModuleDecl::ExportDecl(export_decl) => {
+                self.gen_decl(&export_decl.decl);
+                let names: Vec<String> = match &export_decl.decl {
+                    Decl::Fn(fn_decl) => vec![fn_decl.ident.sym.to_string()],
+                    Decl::Class(class_decl) => vec![class_decl.ident.sym.to_string()],
+                    Decl::TsEnum(enum_decl) => vec![enum_decl.id.sym.to_string()],
+                    Decl::Var(var_decl) => var_decl
+                        .decls
+                        .iter()
+                        .filter_map(|d| d.name.as_ident().map(|id| id.id.sym.to_string()))
+                        .collect(),
+                    _ => Vec::new(),
+                };
+                for name in names {
+                    self.export_local(name.clone(), name);
+                }
+            }
+            ModuleDecl::ExportDefaultDecl(export_default) => {
+                let local = match &export_default.decl {
+                    DefaultDecl::Class(class_expr) => {
+                        let name = class_expr
+                            .ident
+                            .as_ref()
+                            .map(|id| id.sym.to_string())
+                            .unwrap_or_else(|| self.default_export_var());
+                        self.gen_class(&class_expr.class, Some(name.as_str()));
+                        self.instructions.push(OpCode::Let(name.clone()));
+                        self.outer_scope_vars.insert(name.clone());
+                        name
+                    }
+                    DefaultDecl::Fn(fn_expr) => {
+                        let name = fn_expr
+                            .ident
+                            .as_ref()
+                            .map(|id| id.sym.to_string())
+                            .unwrap_or_else(|| self.default_export_var());
+                        self.gen_fn_decl(Some(name.clone()), &fn_expr.function);
+                        name
+                    }
+                    DefaultDecl::TsInterfaceDecl(_) => return,
+                };
+                self.export_local("default".to_string(), local);
+            }
+            ModuleDecl::ExportDefaultExpr(export_default) => {
+                let local = self.default_export_var();
+                self.gen_expr(&export_default.expr);
+                self.instructions.push(OpCode::Let(local.clone()));
+                self.export_local("default".to_string(), local);
+            }
             ModuleDecl::Import(import) => {
+                if import.type_only {
+                    return;
+                }
                 let src = import.src.value.to_string_lossy().into_owned();
+                if let Some(asset_var) = self.import_asset(&src, import.with.as_deref()) {
+                    self.bind_asset_import(&src, &asset_var, &import.specifiers);
+                    return;
+                }
+                let namespace_var = self.import_namespace(&src);
 
                 for spec in &import.specifiers {
-                    match spec {
+                    let (local, export) = match spec {
                         ImportSpecifier::Named(named) => {
+                            if named.is_type_only {
+                                continue;
+                            }
                             let local = named.local.sym.to_string();
                             let imported = named
                                 .imported
@@ -330,170 +804,101 @@ impl Codegen {
                                     s.to_string()
                                 })
                                 .unwrap_or_else(|| local.clone());
-
-                            self.instructions
-                                .push(OpCode::Push(JsValue::String(src.clone())));
-                            self.instructions.push(OpCode::ImportAsync(src.clone()));
-                            self.instructions.push(OpCode::GetExport {
-                                name: imported.clone(),
-                                is_default: false,
-                            });
-                            self.instructions.push(OpCode::Let(local));
+                            (local, imported)
                         }
                         ImportSpecifier::Default(default) => {
-                            let local = default.local.sym.to_string();
-
-                            self.instructions
-                                .push(OpCode::Push(JsValue::String(src.clone())));
-                            self.instructions.push(OpCode::ImportAsync(src.clone()));
-                            self.instructions.push(OpCode::GetExport {
-                                name: "default".to_string(),
-                                is_default: true,
-                            });
-                            self.instructions.push(OpCode::Let(local));
+                            (default.local.sym.to_string(), "default".to_string())
                         }
                         ImportSpecifier::Namespace(ns) => {
                             let local = ns.local.sym.to_string();
-
-                            self.instructions
-                                .push(OpCode::Push(JsValue::String(src.clone())));
-                            self.instructions.push(OpCode::ImportAsync(src.clone()));
+                            self.instructions.push(OpCode::Load(namespace_var.clone()));
                             self.instructions.push(OpCode::Let(local));
+                            continue;
                         }
-                    }
-                }
-
-                if import.specifiers.is_empty() {
-                    self.instructions
-                        .push(OpCode::Push(JsValue::String(src.clone())));
-                    self.instructions.push(OpCode::ImportAsync(src.clone()));
-                    self.instructions.push(OpCode::Pop);
-                }
-
-                if let Some(_with) = &import.with {
-                    self.warnings.push(format!(
-                        "Warning: Import assertions for '{}' are not fully supported",
-                        import.src.value.to_string_lossy()
-                    ));
+                    };
+                    // Reads of `local` go through the namespace, so they see
+                    // the exporter's current value (live binding)
+                    self.import_bindings.insert(
+                        local,
+                        ImportBinding {
+                            namespace_var: namespace_var.clone(),
+                            export,
+                        },
+                    );
                 }
             }
             ModuleDecl::ExportNamed(named) => {
+                let atom_string = |name: &ModuleExportName| {
+                    let atom = name.atom();
+                    let s: &str = &atom;
+                    s.to_string()
+                };
                 if let Some(src) = &named.src {
+                    if named.type_only {
+                        return;
+                    }
                     let src_str = src.value.to_string_lossy().into_owned();
-                    for spec in &named.specifiers {
-                        match spec {
-                            ExportSpecifier::Named(named) => {
-                                let export_name = named
-                                    .exported
-                                    .as_ref()
-                                    .map(|e| {
-                                        let atom = e.atom();
-                                        let s: &str = &atom;
-                                        s.to_string()
-                                    })
-                                    .unwrap_or_else(|| {
-                                        let atom = named.orig.atom();
-                                        let s: &str = &atom;
-                                        s.to_string()
-                                    });
-                                let _local_name = {
-                                    let atom = named.orig.atom();
-                                    let s: &str = &atom;
-                                    s.to_string()
-                                };
-
-                                self.instructions
-                                    .push(OpCode::Push(JsValue::String(src_str.clone())));
-                                self.instructions.push(OpCode::ImportAsync(src_str.clone()));
-                                self.instructions.push(OpCode::GetExport {
-                                    name: export_name.clone(),
-                                    is_default: false,
-                                });
-                                self.instructions.push(OpCode::Let(export_name.clone()));
-                                self.instructions.push(OpCode::Load(export_name.clone()));
-                                self.instructions.push(OpCode::Store(export_name));
-                            }
-                            ExportSpecifier::Default(_) => {
-                                self.instructions
-                                    .push(OpCode::Push(JsValue::String(src_str.clone())));
-                                self.instructions.push(OpCode::ImportAsync(src_str.clone()));
-                                self.instructions.push(OpCode::GetExport {
-                                    name: "default".to_string(),
-                                    is_default: true,
-                                });
-                                self.instructions.push(OpCode::Let("default".to_string()));
-                                self.instructions.push(OpCode::Load("default".to_string()));
-                                self.instructions.push(OpCode::Store("default".to_string()));
-                            }
-                            ExportSpecifier::Namespace(ns) => {
-                                let name = {
-                                    let atom = ns.name.atom();
-                                    let s: &str = &atom;
-                                    s.to_string()
-                                };
-                                self.instructions
-                                    .push(OpCode::Push(JsValue::String(src_str.clone())));
-                                self.instructions.push(OpCode::ImportAsync(src_str.clone()));
-                                self.instructions.push(OpCode::Let(name.clone()));
+                    if let Some(asset_var) = self.import_asset(&src_str, named.with.as_deref()) {
+                        for spec in &named.specifiers {
+                            match spec {
+                                ExportSpecifier::Named(spec) if atom_string(&spec.orig) == "default" => {
+                                    let export = atom_string(spec.exported.as_ref().unwrap_or(&spec.orig));
+                                    self.export_local(export, asset_var.clone());
+                                }
+                                _ => self.errors.push(format!(
+                                    "'{}' only has a default export; re-export it with `export {{ default as name }}`",
+                                    src_str
+                                )),
                             }
                         }
+                        return;
+                    }
+                    let namespace_var = self.import_namespace(&src_str);
+                    for spec in &named.specifiers {
+                        let binding = match spec {
+                            ExportSpecifier::Named(named) => ExportBinding::Reexport {
+                                export: atom_string(named.exported.as_ref().unwrap_or(&named.orig)),
+                                namespace_var: namespace_var.clone(),
+                                name: atom_string(&named.orig),
+                            },
+                            ExportSpecifier::Default(default) => ExportBinding::Reexport {
+                                export: default.exported.sym.to_string(),
+                                namespace_var: namespace_var.clone(),
+                                name: "default".to_string(),
+                            },
+                            // `export * as name from "..."` exports the namespace itself
+                            ExportSpecifier::Namespace(ns) => ExportBinding::Local {
+                                export: atom_string(&ns.name),
+                                local: namespace_var.clone(),
+                            },
+                        };
+                        self.exports.push(binding);
                     }
                 } else {
                     for spec in &named.specifiers {
-                        match spec {
-                            ExportSpecifier::Named(named) => {
-                                let export_name = named
-                                    .exported
-                                    .as_ref()
-                                    .map(|e| {
-                                        let atom = e.atom();
-                                        let s: &str = &atom;
-                                        s.to_string()
-                                    })
-                                    .unwrap_or_else(|| {
-                                        let atom = named.orig.atom();
-                                        let s: &str = &atom;
-                                        s.to_string()
-                                    });
-                                let local_name = {
-                                    let atom = named.orig.atom();
-                                    let s: &str = &atom;
-                                    s.to_string()
-                                };
-
-                                self.instructions.push(OpCode::Load(local_name));
-                                self.instructions.push(OpCode::Dup);
-                                self.instructions.push(OpCode::Store(export_name));
-                            }
-                            ExportSpecifier::Default(_) => {
-                                self.instructions.push(OpCode::Load("default".to_string()));
-                                self.instructions.push(OpCode::Dup);
-                                self.instructions.push(OpCode::Store("default".to_string()));
-                            }
-                            ExportSpecifier::Namespace(ns) => {
-                                let name = {
-                                    let atom = ns.name.atom();
-                                    let s: &str = &atom;
-                                    s.to_string()
-                                };
-                                self.instructions.push(OpCode::Load(name.clone()));
-                                self.instructions.push(OpCode::Dup);
-                                self.instructions.push(OpCode::Store(name));
-                            }
+                        if let ExportSpecifier::Named(named) = spec {
+                            let local = atom_string(&named.orig);
+                            let export = named
+                                .exported
+                                .as_ref()
+                                .map(atom_string)
+                                .unwrap_or_else(|| local.clone());
+                            self.export_local(export, local);
                         }
                     }
                 }
             }
             ModuleDecl::ExportAll(all) => {
+                if all.type_only {
+                    return;
+                }
                 let src_str = all.src.value.to_string_lossy().into_owned();
-                self.warnings.push(format!(
-                    "Warning: 'export * from' is not yet fully implemented for '{}'",
-                    src_str
-                ));
-                self.instructions
-                    .push(OpCode::Push(JsValue::String(src_str.clone())));
-                self.instructions.push(OpCode::ImportAsync(src_str.clone()));
-                self.instructions.push(OpCode::Pop);
+                // `export *` skips `default`, the only export of an asset
+                if self.import_asset(&src_str, all.with.as_deref()).is_some() {
+                    return;
+                }
+                let namespace_var = self.import_namespace(&src_str);
+                self.exports.push(ExportBinding::Star { namespace_var });
             }
             ModuleDecl::TsImportEquals(_) => {}
             ModuleDecl::TsExportAssignment(_) => {}
@@ -508,7 +913,10 @@ impl Codegen {
                 self.gen_fn_decl(Some(name), &fn_decl.function);
             }
             Decl::Class(class_decl) => {
-                self.gen_class(&class_decl.class, Some(class_decl.ident.sym.as_str()));
+                let class_name = class_decl.ident.sym.to_string();
+                self.gen_class(&class_decl.class, Some(class_name.as_str()));
+                self.instructions.push(OpCode::Let(class_name.clone()));
+                self.outer_scope_vars.insert(class_name);
             }
             Decl::Var(var_decl) => {
                 self.gen_var_decl(var_decl);
@@ -575,13 +983,27 @@ impl Codegen {
     }
 
     pub fn generate_script(&mut self, script: &Script) -> Vec<OpCode> {
-        for stmt in &script.body {
-            self.gen_stmt(stmt);
+        for (i, stmt) in script.body.iter().enumerate() {
+            self.gen_top_level_stmt(stmt, i + 1 == script.body.len());
         }
         self.instructions.push(OpCode::Halt);
         self.instructions.clone()
     }
 
+    /// Generate a top-level statement. With `capture_result`, a trailing
+    /// expression statement keeps its value on the stack for the `Halt` after it.
+    fn gen_top_level_stmt(&mut self, stmt: &Stmt, is_last: bool) {
+        match stmt {
+            Stmt::Expr(expr_stmt) if is_last && self.capture_result => {
+                self.statement_starts
+                    .push((self.instructions.len(), stmt.span().lo));
+                self.gen_expr(&expr_stmt.expr);
+                self.result_captured = true;
+            }
+            _ => self.gen_stmt(stmt),
+        }
+    }
+
     fn gen_fn_decl(&mut self, name: Option<String>, fn_decl: &Function) {
         let is_async = fn_decl.is_async;
 
@@ -599,6 +1021,7 @@ impl Codegen {
 
             // Track this function name in outer scope
             self.outer_scope_vars.insert(name.clone());
+            self.function_starts.push((ip, name.clone()));
 
             // 2. Add jump to skip over function body
             let jump_target = self.instructions.len() + 1; // Will be updated after compiling body
@@ -612,8 +1035,16 @@ impl Codegen {
         };
 
         // 3. Compile function body
+        let prev_in_function = self.in_function;
+        let prev_async = self.in_async_function;
         self.in_function = true;
         self.in_async_function = is_async;
+        let param_names: Vec<String> = fn_decl
+            .params
+            .iter()
+            .filter_map(|p| p.pat.as_ident().map(|id| id.id.sym.to_string()))
+            .collect();
+        let saved_imports = self.shadow_imports(&param_names);
 
         // Inside the function body, we must pop arguments into locals
         // We process them in REVERSE order because of how they sit on the stack
@@ -625,6 +1056,9 @@ impl Codegen {
                 self.instructions.push(OpCode::Let(param_name));
             }
         }
+        if is_async {
+            self.instructions.push(OpCode::EnterAsync);
+        }
         let stmts = &fn_decl.body.as_ref().unwrap().stmts;
 
         let mut last_instr_was_return = false;
@@ -636,54 +1070,13 @@ impl Codegen {
                 && matches!(self.instructions.last(), Some(OpCode::Return));
         }
 
-        self.in_function = false;
-        self.in_async_function = false;
+        self.in_function = prev_in_function;
+        self.in_async_function = prev_async;
+        self.import_bindings = saved_imports;
 
-        // If the last statement wasn't a return, we need to handle implicit return
+        // Falling off the end of the body returns undefined
         if !last_instr_was_return {
-            if stmts.is_empty() {
-                // Empty function body - return undefined
-                self.instructions.push(OpCode::Push(JsValue::Undefined));
-                // For async functions, wrap in Promise.resolve()
-                if is_async {
-                    self.instructions
-                        .push(OpCode::Push(JsValue::String("Promise".to_string())));
-                    self.instructions.push(OpCode::Load("Promise".to_string()));
-                    self.instructions
-                        .push(OpCode::Push(JsValue::String("resolve".to_string())));
-                    self.instructions
-                        .push(OpCode::GetProp("resolve".to_string()));
-                    // Stack: [undefined, Promise, PromiseObj, resolveFn]
-                    // Pop PromiseObj and Promise, keeping resolveFn and undefined
-                    self.instructions.push(OpCode::Pop);
-                    self.instructions.push(OpCode::Pop);
-                    // Stack: [undefined, resolveFn]
-                    // Swap to get [resolveFn, undefined]
-                    self.instructions.push(OpCode::Swap);
-                    self.instructions.push(OpCode::Call(1));
-                }
-            } else {
-                // Non-empty body but last statement wasn't a return
-                // The last expression's result is on the stack, need to return it
-                // For async functions, wrap in Promise.resolve()
-                if is_async {
-                    self.instructions
-                        .push(OpCode::Push(JsValue::String("Promise".to_string())));
-                    self.instructions.push(OpCode::Load("Promise".to_string()));
-                    self.instructions
-                        .push(OpCode::Push(JsValue::String("resolve".to_string())));
-                    self.instructions
-                        .push(OpCode::GetProp("resolve".to_string()));
-                    // Stack: [returnValue, Promise, PromiseObj, resolveFn]
-                    // Pop PromiseObj and Promise, keeping resolveFn
-                    self.instructions.push(OpCode::Pop);
-                    self.instructions.push(OpCode::Pop);
-                    // Stack: [returnValue, resolveFn]
-                    // Swap to get [resolveFn, returnValue]
-                    self.instructions.push(OpCode::Swap);
-                    self.instructions.push(OpCode::Call(1));
-                }
-            }
+            self.instructions.push(OpCode::Push(JsValue::Undefined));
             self.instructions.push(OpCode::Return);
         }
 
@@ -698,10 +1091,12 @@ impl Codegen {
 
     fn gen_var_decl(&mut self, var_decl: &VarDecl) {
         for decl in &var_decl.decls {
-            if let Some(init) = &decl.init {
-                self.gen_expr(init);
-                self.gen_pattern_binding(&decl.name);
+            match &decl.init {
+                Some(init) => self.gen_expr(init),
+                // `let x;` declares x as undefined
+                None => self.instructions.push(OpCode::Push(JsValue::Undefined)),
             }
+            self.gen_pattern_binding(&decl.name);
         }
     }
 
@@ -712,6 +1107,9 @@ impl Codegen {
             Pat::Ident(id) => {
                 // Simple variable binding
                 let name = id.id.sym.to_string();
+                if self.in_function {
+                    self.import_bindings.remove(&name);
+                }
                 self.instructions.push(OpCode::Let(name.clone()));
                 self.outer_scope_vars.insert(name);
             }
@@ -827,6 +1225,8 @@ impl Codegen {
     }
 
     fn gen_stmt(&mut self, stmt: &Stmt) {
+        self.statement_starts
+            .push((self.instructions.len(), stmt.span().lo));
         match stmt {
             Stmt::Return(ret_stmt) => {
                 if let Some(arg) = &ret_stmt.arg {
@@ -834,18 +1234,6 @@ impl Codegen {
                 } else {
                     self.instructions.push(OpCode::Push(JsValue::Undefined));
                 }
-                // For async functions, wrap the return value in Promise.resolve()
-                if self.in_async_function {
-                    self.instructions
-                        .push(OpCode::Push(JsValue::String("Promise".to_string())));
-                    self.instructions.push(OpCode::Load("Promise".to_string()));
-                    self.instructions
-                        .push(OpCode::Push(JsValue::String("resolve".to_string())));
-                    self.instructions
-                        .push(OpCode::GetProp("resolve".to_string()));
-                    self.instructions.push(OpCode::Swap);
-                    self.instructions.push(OpCode::Call(1));
-                }
                 self.instructions.push(OpCode::Return);
             }
             // RECURSION: Handle the Block
@@ -931,6 +1319,10 @@ impl Codegen {
             Stmt::Decl(Decl::TsTypeAlias(_)) => {
                 // Type aliases are compile-time only, skip at runtime
             }
+            Stmt::Expr(expr_stmt) if is_require_directive(&expr_stmt.expr) => {
+                // Dependencies named by `require ./path;` are loaded in
+                // order by the caller, skip at runtime
+            }
             Stmt::Expr(expr_stmt) => {
                 self.gen_expr(&expr_stmt.expr);
                 // Expression statements (e.g. `foo();`) should always discard their result in JS.
@@ -1069,6 +1461,7 @@ impl Codegen {
             Stmt::ForOf(for_of_stmt) => {
                 self.scope_stack.push(Vec::new());
                 self.gen_expr(&for_of_stmt.right);
+                self.instructions.push(OpCode::ToIterable);
                 let iter_name = "__for_of_iter__".to_string();
                 self.instructions.push(OpCode::Let(iter_name.clone()));
                 if let Some(scope) = self.scope_stack.last_mut() {
@@ -1096,27 +1489,28 @@ impl Codegen {
                 self.instructions.push(OpCode::Load(iter_name.clone()));
                 self.instructions.push(OpCode::Load(idx_name.clone()));
                 self.instructions.push(OpCode::LoadElement);
+                let mut bound = Vec::new();
                 if let Some(var_decl) = &for_of_stmt.left.as_var_decl()
                     && let Some(decl) = var_decl.decls.first()
-                    && let Pat::Ident(id) = &decl.name
                 {
-                    let var_name = id.id.sym.to_string();
-                    self.instructions.push(OpCode::Let(var_name.clone()));
-                    if let Some(scope) = self.scope_stack.last_mut() {
-                        scope.push(var_name);
+                    if let Pat::Ident(id) = &decl.name {
+                        self.instructions.push(OpCode::Let(id.id.sym.to_string()));
+                    } else {
+                        // `for (const [key, value] of map)`
+                        self.gen_pattern_binding(&decl.name);
                     }
+                    collect_pattern_names(&decl.name, &mut bound);
+                }
+                if let Some(scope) = self.scope_stack.last_mut() {
+                    scope.extend(bound.iter().cloned());
                 }
                 self.gen_stmt(&for_of_stmt.body);
                 let continue_target = self.instructions.len();
-                if let Some(var_decl) = &for_of_stmt.left.as_var_decl()
-                    && let Some(decl) = var_decl.decls.first()
-                    && let Pat::Ident(id) = &decl.name
-                {
-                    let var_name = id.id.sym.to_string();
-                    self.instructions.push(OpCode::Drop(var_name));
-                    if let Some(scope) = self.scope_stack.last_mut() {
-                        scope.retain(|n| n != &id.id.sym.to_string());
-                    }
+                for var_name in &bound {
+                    self.instructions.push(OpCode::Drop(var_name.clone()));
+                }
+                if let Some(scope) = self.scope_stack.last_mut() {
+                    scope.retain(|n| !bound.contains(n));
                 }
                 self.instructions.push(OpCode::Load(idx_name.clone()));
                 self.instructions.push(OpCode::Push(JsValue::Number(1.0)));
@@ -1440,6 +1834,7 @@ impl Codegen {
                 let prev_async = self.in_async_function;
                 self.in_function = true;
                 self.in_async_function = is_async;
+                let saved_imports = self.shadow_imports(&params);
 
                 // Pop args into locals (reverse order)
                 // Parameters are new bindings in the function scope
@@ -1449,6 +1844,9 @@ impl Codegen {
                         self.instructions.push(OpCode::Let(param_name));
                     }
                 }
+                if is_async {
+                    self.instructions.push(OpCode::EnterAsync);
+                }
 
                 if let Some(body) = &fn_expr.function.body {
                     let stmts = &body.stmts;
@@ -1461,52 +1859,18 @@ impl Codegen {
                             && matches!(self.instructions.last(), Some(OpCode::Return));
                     }
 
-                    // For async functions with no return statement at the end, wrap the result
-                    if is_async && !last_instr_was_return {
-                        if stmts.is_empty() {
-                            self.instructions.push(OpCode::Push(JsValue::Undefined));
-                        }
-                        // Wrap in Promise.resolve() and add Return
-                        self.instructions
-                            .push(OpCode::Push(JsValue::String("Promise".to_string())));
-                        self.instructions.push(OpCode::Load("Promise".to_string()));
-                        self.instructions
-                            .push(OpCode::Push(JsValue::String("resolve".to_string())));
-                        self.instructions
-                            .push(OpCode::GetProp("resolve".to_string()));
-                        // Stack: [returnValue, Promise, PromiseObj, resolveFn]
-                        // Pop PromiseObj and Promise, keeping resolveFn
-                        self.instructions.push(OpCode::Pop);
-                        self.instructions.push(OpCode::Pop);
-                        // Stack: [returnValue, resolveFn]
-                        // Swap to get [resolveFn, returnValue]
-                        self.instructions.push(OpCode::Swap);
-                        self.instructions.push(OpCode::Call(1));
+                    if !last_instr_was_return {
+                        // Without this the body would fall through into the
+                        // code following the function expression
+                        self.instructions.push(OpCode::Push(JsValue::Undefined));
                         self.instructions.push(OpCode::Return);
                     }
                 } else {
                     self.instructions.push(OpCode::Push(JsValue::Undefined));
-                    // For async functions with no body, wrap undefined in Promise.resolve()
-                    if is_async {
-                        self.instructions
-                            .push(OpCode::Push(JsValue::String("Promise".to_string())));
-                        self.instructions.push(OpCode::Load("Promise".to_string()));
-                        self.instructions
-                            .push(OpCode::Push(JsValue::String("resolve".to_string())));
-                        self.instructions
-                            .push(OpCode::GetProp("resolve".to_string()));
-                        // Stack: [undefined, Promise, PromiseObj, resolveFn]
-                        // Pop PromiseObj and Promise, keeping resolveFn
-                        self.instructions.push(OpCode::Pop);
-                        self.instructions.push(OpCode::Pop);
-                        // Stack: [undefined, resolveFn]
-                        // Swap to get [resolveFn, undefined]
-                        self.instructions.push(OpCode::Swap);
-                        self.instructions.push(OpCode::Call(1));
-                    }
                     self.instructions.push(OpCode::Return);
                 }
                 self.in_function = prev_in_function;
+                self.import_bindings = saved_imports;
                 self.in_async_function = prev_async;
 
                 let after_body = self.instructions.len();
@@ -1569,6 +1933,7 @@ impl Codegen {
                 let prev_async = self.in_async_function;
                 self.in_function = true;
                 self.in_async_function = arrow.is_async;
+                let saved_imports = self.shadow_imports(&params);
 
                 // Pop args into locals (reverse order)
                 // Parameters are new bindings in the function scope
@@ -1580,29 +1945,14 @@ impl Codegen {
                         eprintln!("Warning: Non-identifier arrow params not supported yet.");
                     }
                 }
+                if arrow.is_async {
+                    self.instructions.push(OpCode::EnterAsync);
+                }
 
                 match &*arrow.body {
                     BlockStmtOrExpr::Expr(e) => {
                         // Expression-bodied arrows implicitly return the expression.
                         self.gen_expr(e);
-                        // For async arrows, wrap the return value in Promise.resolve()
-                        if arrow.is_async {
-                            self.instructions
-                                .push(OpCode::Push(JsValue::String("Promise".to_string())));
-                            self.instructions.push(OpCode::Load("Promise".to_string()));
-                            self.instructions
-                                .push(OpCode::Push(JsValue::String("resolve".to_string())));
-                            self.instructions
-                                .push(OpCode::GetProp("resolve".to_string()));
-                            // Stack: [returnValue, Promise, PromiseObj, resolveFn]
-                            // Pop PromiseObj and Promise, keeping resolveFn
-                            self.instructions.push(OpCode::Pop);
-                            self.instructions.push(OpCode::Pop);
-                            // Stack: [returnValue, resolveFn]
-                            // Swap to get [resolveFn, returnValue]
-                            self.instructions.push(OpCode::Swap);
-                            self.instructions.push(OpCode::Call(1));
-                        }
                         self.instructions.push(OpCode::Return);
                     }
                     BlockStmtOrExpr::BlockStmt(block) => {
@@ -1616,36 +1966,16 @@ impl Codegen {
                                 && matches!(self.instructions.last(), Some(OpCode::Return));
                         }
 
-                        if stmts.is_empty() {
-                            self.instructions.push(OpCode::Push(JsValue::Undefined));
-                        }
-
-                        // For async arrows with no return statement at the end, wrap the result
-                        if arrow.is_async && !last_instr_was_return {
-                            self.instructions
-                                .push(OpCode::Push(JsValue::String("Promise".to_string())));
-                            self.instructions.push(OpCode::Load("Promise".to_string()));
-                            self.instructions
-                                .push(OpCode::Push(JsValue::String("resolve".to_string())));
-                            self.instructions
-                                .push(OpCode::GetProp("resolve".to_string()));
-                            // Stack: [returnValue, Promise, PromiseObj, resolveFn]
-                            // Pop PromiseObj and Promise, keeping resolveFn
-                            self.instructions.push(OpCode::Pop);
-                            self.instructions.push(OpCode::Pop);
-                            // Stack: [returnValue, resolveFn]
-                            // Swap to get [resolveFn, returnValue]
-                            self.instructions.push(OpCode::Swap);
-                            self.instructions.push(OpCode::Call(1));
-                        }
-
+                        // Falling off the end of the body returns undefined
                         if !last_instr_was_return {
+                            self.instructions.push(OpCode::Push(JsValue::Undefined));
                             self.instructions.push(OpCode::Return);
                         }
                     }
                 }
 
                 self.in_function = prev_in_function;
+                self.import_bindings = saved_imports;
                 self.in_async_function = prev_async;
 
                 let after_body = self.instructions.len();
@@ -1658,9 +1988,14 @@ impl Codegen {
                     .push(OpCode::Push(JsValue::Number(num.value)));
             }
             Expr::Lit(Lit::Str(s)) => {
-                self.instructions.push(OpCode::Push(JsValue::String(
-                    s.value.to_string_lossy().to_string(),
-                )));
+                let value = match s.value.as_str() {
+                    Some(text) => JsString::from(text),
+                    // An escaped lone surrogate, which only code units can hold
+                    None => {
+                        JsString::from_units(&s.value.to_ill_formed_utf16().collect::<Vec<_>>())
+                    }
+                };
+                self.instructions.push(OpCode::Push(JsValue::String(value)));
             }
             Expr::Lit(Lit::Bool(b)) => {
                 self.instructions
@@ -1670,7 +2005,17 @@ impl Codegen {
                 self.instructions.push(OpCode::Push(JsValue::Null));
             }
             Expr::Ident(id) => {
-                self.instructions.push(OpCode::Load(id.sym.to_string()));
+                let name = id.sym.to_string();
+                if let Some(import) = self.import_bindings.get(&name) {
+                    self.instructions
+                        .push(OpCode::Load(import.namespace_var.clone()));
+                    self.instructions.push(OpCode::GetExport {
+                        is_default: import.export == "default",
+                        name: import.export.clone(),
+                    });
+                } else {
+                    self.instructions.push(OpCode::Load(name));
+                }
             }
             Expr::Bin(bin) => {
                 self.gen_expr(&bin.left);
@@ -1707,6 +2052,13 @@ impl Codegen {
                 match unary.op {
                     UnaryOp::TypeOf => {
                         self.gen_expr(&unary.arg);
+                        // `typeof x` is "undefined" for an undeclared name
+                        if let Expr::Ident(_) = unary.arg.as_ref()
+                            && let Some(OpCode::Load(name)) = self.instructions.last()
+                        {
+                            let name = name.clone();
+                            *self.instructions.last_mut().unwrap() = OpCode::LoadOrUndefined(name);
+                        }
                         self.instructions.push(OpCode::TypeOf);
                     }
                     UnaryOp::Delete => {
@@ -1816,6 +2168,16 @@ impl Codegen {
                     return;
                 }
 
+                // import(specifier) loads the module in the background
+                if let Callee::Import(_) = &call_expr.callee {
+                    match call_expr.args.first() {
+                        Some(arg) => self.gen_expr(&arg.expr),
+                        None => self.instructions.push(OpCode::Push(JsValue::Undefined)),
+                    }
+                    self.instructions.push(OpCode::ImportDynamic);
+                    return;
+                }
+
                 let arg_count = call_expr.args.len();
                 for arg in &call_expr.args {
                     self.gen_expr(&arg.expr);
@@ -1829,7 +2191,7 @@ impl Codegen {
                         // 2. The CallSuper opcode will use it
                         self.instructions.push(OpCode::LoadSuper);
                     }
-                    Callee::Import(_) => {} // Handle import calls if needed
+                    Callee::Import(_) => unreachable!("import() is handled above"),
                 }
                 // Call it
                 match &call_expr.callee {
@@ -2022,8 +2384,18 @@ impl Codegen {
                         self.instructions.push(OpCode::NewTarget);
                     }
                     MetaPropKind::ImportMeta => {
-                        // import.meta - not yet supported
-                        self.instructions.push(OpCode::Push(JsValue::Undefined));
+                        // Keyed by file, so it is the same object wherever it is read
+                        let path = self
+                            .source_path
+                            .as_ref()
+                            .map(|path| std::fs::canonicalize(path).unwrap_or(path.clone()));
+                        self.instructions.push(OpCode::Push(match path {
+                            Some(path) => {
+                                JsValue::String(path.to_string_lossy().into_owned().into())
+                            }
+                            None => JsValue::Undefined,
+                        }));
+                        self.instructions.push(OpCode::ImportMeta);
                     }
                 }
             }
@@ -2096,20 +2468,27 @@ impl Codegen {
                 // Handle empty template literal ``
                 if tpl.quasis.is_empty() && tpl.exprs.is_empty() {
                     self.instructions
-                        .push(OpCode::Push(JsValue::String("".to_string())));
+                        .push(OpCode::Push(JsValue::String("".into())));
                     return;
                 }
 
                 // Start with empty string
                 self.instructions
-                    .push(OpCode::Push(JsValue::String("".to_string())));
+                    .push(OpCode::Push(JsValue::String("".into())));
 
                 // Iterate through quasis and exprs
                 for (i, quasi) in tpl.quasis.iter().enumerate() {
                     // Push the quasi string (cooked value - escapes processed, or raw if not available)
                     let s_str = match quasi.cooked.as_ref() {
-                        Some(wtf8) => String::from_utf8_lossy(wtf8.as_bytes()).into_owned(),
-                        None => String::from_utf8_lossy(quasi.raw.as_bytes()).into_owned(),
+                        Some(wtf8) => match wtf8.as_str() {
+                            Some(text) => JsString::from(text),
+                            None => JsString::from_units(
+                                &wtf8.to_ill_formed_utf16().collect::<Vec<_>>(),
+                            ),
+                        },
+                        None => String::from_utf8_lossy(quasi.raw.as_bytes())
+                            .as_ref()
+                            .into(),
                     };
                     self.instructions.push(OpCode::Push(JsValue::String(s_str)));
                     // Concatenate: "prefix" + result so far
@@ -2124,10 +2503,9 @@ impl Codegen {
                 }
             }
             Expr::Await(await_expr) => {
-                if !self.in_async_function {
-                    self.gen_expr(&await_expr.arg);
-                } else {
-                    self.gen_expr(&await_expr.arg);
+                self.gen_expr(&await_expr.arg);
+                // Top-level await is allowed outside any function
+                if self.in_async_function || !self.in_function {
                     self.instructions.push(OpCode::Await);
                 }
             }
@@ -2360,6 +2738,7 @@ impl Codegen {
         // Constructor body
         let saved_in_function = self.in_function;
         self.in_function = true;
+        let saved_imports = self.shadow_imports(&constructor_params);
 
         for param in constructor_params.iter().rev() {
             self.instructions.push(OpCode::Let(param.clone()));
@@ -2398,11 +2777,6 @@ impl Codegen {
             .push(OpCode::SetProp("__private_storage__".to_string()));
         // Stack: []
 
-        // Store the private storage array in a temp for later use
-        self.instructions
-            .push(OpCode::Let("__private_storage__".to_string()));
-        // Stack: []
-
         // Initialize private field declarations
         for (field_name, value_expr) in &private_field_decls {
             // Generate the value
@@ -2448,6 +2822,7 @@ impl Codegen {
         self.instructions.push(OpCode::LoadThis);
         self.instructions.push(OpCode::Return);
         self.in_function = saved_in_function;
+        self.import_bindings = saved_imports;
 
         // Backpatch jump
         let after_constructor = self.instructions.len();
@@ -2519,7 +2894,7 @@ impl Codegen {
                 .push(OpCode::Load("__wrapper__".to_string()));
             // Stack: [wrapper]
             self.instructions
-                .push(OpCode::Push(JsValue::String(class_name.to_string())));
+                .push(OpCode::Push(JsValue::String(class_name.into())));
             // Stack: [wrapper, name_string]
             self.instructions.push(OpCode::SetProp("name".to_string()));
             // Stack: []
@@ -2648,12 +3023,19 @@ impl Codegen {
                 self.instructions.push(OpCode::Jump(0));
 
                 // Compile method body
+                let is_async = method.function.is_async;
                 let saved_in_function = self.in_function;
+                let saved_async = self.in_async_function;
                 self.in_function = true;
+                self.in_async_function = is_async;
+                let saved_imports = self.shadow_imports(&params);
 
                 for param in params.iter().rev() {
                     self.instructions.push(OpCode::Let(param.clone()));
                 }
+                if is_async {
+                    self.instructions.push(OpCode::EnterAsync);
+                }
 
                 if let Some(body) = &method.function.body {
                     for stmt in &body.stmts {
@@ -2661,9 +3043,15 @@ impl Codegen {
                     }
                 }
 
-                self.instructions.push(OpCode::LoadThis);
+                if is_async {
+                    self.instructions.push(OpCode::Push(JsValue::Undefined));
+                } else {
+                    self.instructions.push(OpCode::LoadThis);
+                }
                 self.instructions.push(OpCode::Return);
                 self.in_function = saved_in_function;
+                self.in_async_function = saved_async;
+                self.import_bindings = saved_imports;
 
                 // Backpatch method jump
                 let after_method = self.instructions.len();
@@ -2702,3 +3090,43 @@ impl Codegen {
         }
     }
 }
+
+/// Whether `expr` is Oite's `require ./path;` directive, which the parser
+/// recovers as `<invalid> / path / ...`
+fn is_require_directive(expr: &Expr) -> bool {
+    match expr {
+        Expr::Invalid(_) => true,
+        Expr::Bin(bin) if bin.op == BinaryOp::Div => is_require_directive(&bin.left),
+        _ => false,
+    }
+}
+
+/// The variables `pat` binds, in source order
+fn collect_pattern_names(pat: &Pat, names: &mut Vec<String>) {
+    match pat {
+        Pat::Ident(id) => names.push(id.id.sym.to_string()),
+        Pat::Array(arr_pat) => {
+            for elem in arr_pat.elems.iter().flatten() {
+                collect_pattern_names(elem, names);
+            }
+        }
+        Pat::Object(obj_pat) => {
+            for prop in &obj_pat.props {
+                match prop {
+                    swc_ecma_ast::ObjectPatProp::KeyValue(kv) => {
+                        collect_pattern_names(&kv.value, names)
+                    }
+                    swc_ecma_ast::ObjectPatProp::Assign(assign) => {
+                        names.push(assign.key.sym.to_string())
+                    }
+                    swc_ecma_ast::ObjectPatProp::Rest(rest) => {
+                        collect_pattern_names(&rest.arg, names)
+                    }
+                }
+            }
+        }
+        Pat::Rest(rest) => collect_pattern_names(&rest.arg, names),
+        Pat::Assign(assign) => collect_pattern_names(&assign.left, names),
+        _ => {}
+    }
+}
@@ -0,0 +1,141 @@
+//! Bytecode Source Maps
+//!
+//! Maps instruction addresses back to source lines. The code generator records
+//! the address at which each statement starts; lookups for an arbitrary
+//! address resolve to the closest preceding statement.
+
+/// A statement boundary in the generated bytecode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SourceLocation {
+    /// Address of the first instruction of the statement
+    pub ip: usize,
+    /// 1-based source line
+    pub line: u32,
+    /// 0-based source column
+    pub column: u32,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BytecodeSourceMap {
+    /// Statement boundaries, sorted by `ip`
+    pub locations: Vec<SourceLocation>,
+    /// Entry addresses of named functions
+    pub functions: Vec<(usize, String)>,
+}
+
+impl BytecodeSourceMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn from_unsorted(
+        mut locations: Vec<SourceLocation>,
+        mut functions: Vec<(usize, String)>,
+    ) -> Self {
+        // Nested statements are recorded after their parent, so sort by address
+        // and keep the innermost statement for each address
+        locations.sort_by_key(|loc| loc.ip);
+        let mut deduped: Vec<SourceLocation> = Vec::with_capacity(locations.len());
+        for loc in locations {
+            match deduped.last_mut() {
+                Some(last) if last.ip == loc.ip => *last = loc,
+                _ => deduped.push(loc),
+            }
+        }
+        functions.sort_by_key(|(addr, _)| *addr);
+        Self {
+            locations: deduped,
+            functions,
+        }
+    }
+
+    /// Shift all addresses by `offset` (for bytecode appended to a running program)
+    pub fn rebase(&mut self, offset: usize) {
+        for loc in &mut self.locations {
+            loc.ip += offset;
+        }
+        for (addr, _) in &mut self.functions {
+            *addr += offset;
+        }
+    }
+
+    /// The statement containing the instruction at `ip`
+    pub fn lookup(&self, ip: usize) -> Option<SourceLocation> {
+        let idx = self.locations.partition_point(|loc| loc.ip <= ip);
+        if idx == 0 {
+            None
+        } else {
+            Some(self.locations[idx - 1])
+        }
+    }
+
+    /// Whether a statement starts exactly at `ip`
+    pub fn is_statement_start(&self, ip: usize) -> bool {
+        self.locations
+            .binary_search_by_key(&ip, |loc| loc.ip)
+            .is_ok()
+    }
+
+    /// Resolve a requested breakpoint line to the first statement on that line,
+    /// or on the nearest following line that has code
+    pub fn resolve_line(&self, line: u32) -> Option<SourceLocation> {
+        self.locations
+            .iter()
+            .filter(|loc| loc.line >= line)
+            .min_by_key(|loc| (loc.line, loc.ip))
+            .copied()
+    }
+
+    /// Name of the innermost named function whose body starts at or before `ip`
+    pub fn enclosing_function(&self, ip: usize) -> Option<&str> {
+        let idx = self.functions.partition_point(|(addr, _)| *addr <= ip);
+        idx.checked_sub(1).map(|i| self.functions[i].1.as_str())
+    }
+
+    /// Name of the function whose body is at `address`
+    pub fn function_name(&self, address: usize) -> Option<&str> {
+        self.functions
+            .binary_search_by_key(&address, |(addr, _)| *addr)
+            .ok()
+            .map(|idx| self.functions[idx].1.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn loc(ip: usize, line: u32) -> SourceLocation {
+        SourceLocation {
+            ip,
+            line,
+            column: 0,
+        }
+    }
+
+    #[test]
+    fn test_lookup_resolves_to_enclosing_statement() {
+        let map = BytecodeSourceMap::from_unsorted(vec![loc(5, 2), loc(0, 1), loc(9, 4)], vec![]);
+        assert_eq!(map.lookup(0).unwrap().line, 1);
+        assert_eq!(map.lookup(7).unwrap().line, 2);
+        assert_eq!(map.lookup(100).unwrap().line, 4);
+        assert!(map.is_statement_start(5));
+        assert!(!map.is_statement_start(6));
+    }
+
+    #[test]
+    fn test_resolve_line_skips_blank_lines() {
+        let map = BytecodeSourceMap::from_unsorted(vec![loc(0, 1), loc(4, 3), loc(2, 3)], vec![]);
+        assert_eq!(map.resolve_line(2), Some(loc(2, 3)));
+        assert_eq!(map.resolve_line(9), None);
+    }
+
+    #[test]
+    fn test_rebase_shifts_addresses() {
+        let mut map =
+            BytecodeSourceMap::from_unsorted(vec![loc(0, 1)], vec![(3, "main".to_string())]);
+        map.rebase(10);
+        assert_eq!(map.lookup(10).unwrap().line, 1);
+        assert_eq!(map.function_name(13), Some("main"));
+    }
+}
@@ -0,0 +1,836 @@
+//! Step debugger for the VM interpreter
+//!
+//! Implements the Debug Adapter Protocol over stdio (`oitec debug`), so any
+//! DAP-capable editor can set breakpoints, step through code and inspect
+//! frame locals, the operand stack and heap objects. Instruction addresses are
+//! mapped to source lines with the compiler's `BytecodeSourceMap`.
+
+pub mod protocol;
+
+use crate::compiler::source_map::BytecodeSourceMap;
+use crate::compiler::{Compiler, syntax_for_path};
+use crate::stdlib::inspect_value;
+use crate::vm::VM;
+use crate::vm::value::{HeapData, JsValue};
+use serde_json::{Value, json};
+use std::collections::{HashSet, VecDeque};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+
+/// DAP thread id of the (single) interpreter thread
+const THREAD_ID: u64 = 1;
+
+/// Number of instructions executed between checks for incoming requests
+const POLL_INTERVAL: u64 = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum StepMode {
+    Continue,
+    In,
+    Over,
+    Out,
+}
+
+#[derive(Debug, PartialEq)]
+enum StopReason {
+    Entry,
+    Breakpoint,
+    Step,
+    Pause,
+    Exception(String),
+    Terminated,
+}
+
+/// Target of a DAP `variablesReference`
+#[derive(Debug, Clone, Copy)]
+enum VarRef {
+    Locals(usize),
+    IndexedLocals(usize),
+    OperandStack,
+    Heap(usize),
+}
+
+pub struct DebugAdapter<W: Write> {
+    output: W,
+    incoming: mpsc::Receiver<Value>,
+    /// Requests received while the program was running
+    pending: VecDeque<Value>,
+    seq: u64,
+    vm: VM,
+    compiler: Compiler,
+    source_map: BytecodeSourceMap,
+    program_path: Option<PathBuf>,
+    breakpoints: HashSet<usize>,
+    stop_on_entry: bool,
+    /// Variable references handed out since the last stop
+    var_refs: Vec<VarRef>,
+    terminated: bool,
+}
+
+/// Run a debug session on stdin/stdout until the client disconnects
+pub fn run_stdio() {
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let stdin = io::stdin();
+        let mut reader = stdin.lock();
+        while let Ok(Some(message)) = protocol::read_message(&mut reader) {
+            if tx.send(message).is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut adapter = DebugAdapter::new(io::stdout(), rx);
+    adapter.run();
+}
+
+impl<W: Write> DebugAdapter<W> {
+    pub fn new(output: W, incoming: mpsc::Receiver<Value>) -> Self {
+        let mut vm = VM::new();
+        vm.console_capture = Some(Vec::new());
+        Self {
+            output,
+            incoming,
+            pending: VecDeque::new(),
+            seq: 1,
+            vm,
+            compiler: Compiler::new(),
+            source_map: BytecodeSourceMap::new(),
+            program_path: None,
+            breakpoints: HashSet::new(),
+            stop_on_entry: false,
+            var_refs: Vec::new(),
+            terminated: false,
+        }
+    }
+
+    /// Process requests until the client disconnects or the input closes
+    pub fn run(&mut self) {
+        loop {
+            let request = match self.pending.pop_front() {
+                Some(request) => request,
+                None => match self.incoming.recv() {
+                    Ok(request) => request,
+                    Err(_) => break,
+                },
+            };
+            if !self.handle_request(&request) {
+                break;
+            }
+        }
+    }
+
+    /// Handle one request. Returns false when the session should end.
+    fn handle_request(&mut self, request: &Value) -> bool {
+        let command = request["command"].as_str().unwrap_or("");
+        let args = &request["arguments"];
+
+        match command {
+            "initialize" => {
+                self.respond(
+                    request,
+                    json!({
+                        "supportsConfigurationDoneRequest": true,
+                        "supportsEvaluateForHovers": true,
+                        "supportsTerminateRequest": true,
+                    }),
+                );
+            }
+            "launch" => match self.launch(args) {
+                Ok(()) => {
+                    self.respond(request, json!({}));
+                    self.send_event("initialized", json!({}));
+                }
+                Err(e) => self.respond_error(request, &e),
+            },
+            "setBreakpoints" => {
+                let body = self.set_breakpoints(args);
+                self.respond(request, body);
+            }
+            "setExceptionBreakpoints" => self.respond(request, json!({})),
+            "configurationDone" => {
+                self.respond(request, json!({}));
+                if self.stop_on_entry {
+                    self.report_stop(StopReason::Entry);
+                } else {
+                    self.resume(StepMode::Continue);
+                }
+            }
+            "threads" => {
+                self.respond(
+                    request,
+                    json!({ "threads": [{ "id": THREAD_ID, "name": "main" }] }),
+                );
+            }
+            "stackTrace" => {
+                let body = self.stack_trace();
+                self.respond(request, body);
+            }
+            "scopes" => {
+                let frame = args["frameId"].as_u64().unwrap_or(1) as usize;
+                let body = self.scopes(frame.saturating_sub(1));
+                self.respond(request, body);
+            }
+            "variables" => {
+                let reference = args["variablesReference"].as_u64().unwrap_or(0) as usize;
+                let body = self.variables(reference);
+                self.respond(request, body);
+            }
+            "evaluate" => {
+                let expression = args["expression"].as_str().unwrap_or("");
+                let frame = args["frameId"].as_u64().unwrap_or(0) as usize;
+                match self.evaluate(expression, frame.checked_sub(1)) {
+                    Ok(body) => self.respond(request, body),
+                    Err(e) => self.respond_error(request, &e),
+                }
+            }
+            "continue" => {
+                self.respond(request, json!({ "allThreadsContinued": true }));
+                self.resume(StepMode::Continue);
+            }
+            "next" => {
+                self.respond(request, json!({}));
+                self.resume(StepMode::Over);
+            }
+            "stepIn" => {
+                self.respond(request, json!({}));
+                self.resume(StepMode::In);
+            }
+            "stepOut" => {
+                self.respond(request, json!({}));
+                self.resume(StepMode::Out);
+            }
+            // Pausing only has an effect while running (see `resume`)
+            "pause" => self.respond(request, json!({})),
+            "disconnect" | "terminate" => {
+                self.respond(request, json!({}));
+                if !self.terminated {
+                    self.send_event("terminated", json!({}));
+                }
+                return false;
+            }
+            _ => self.respond_error(request, &format!("Unsupported request: {}", command)),
+        }
+        true
+    }
+
+    fn launch(&mut self, args: &Value) -> Result<(), String> {
+        let program = args["program"]
+            .as_str()
+            .ok_or_else(|| "launch: missing 'program'".to_string())?;
+        let path = fs::canonicalize(program).unwrap_or_else(|_| PathBuf::from(program));
+        let source =
+            fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", program, e))?;
+        self.stop_on_entry = args["stopOnEntry"].as_bool().unwrap_or(false);
+
+        // The prelude runs to completion before the debuggee, as in `oitec <file>`
+        if Path::new(crate::PRELUDE_PATH).exists() {
+            let prelude = fs::read_to_string(crate::PRELUDE_PATH)
+                .map_err(|e| format!("Failed to read {}: {}", crate::PRELUDE_PATH, e))?;
+            let bytecode = self.compiler.compile_with_syntax(
+                &prelude,
+                Some(syntax_for_path(Path::new(crate::PRELUDE_PATH))),
+            )?;
+            self.vm.append_program(bytecode);
+            self.vm
+                .run_until_halt()
+                .map_err(|e| format!("Prelude failed to run: {}", e))?;
+        }
+
+        let (bytecode, mut source_map) = self
+            .compiler
+            .compile_with_source_map(&source, Some(syntax_for_path(&path)))?;
+        let offset = self.vm.append_program(bytecode);
+        source_map.rebase(offset);
+        self.source_map = source_map;
+        self.vm.set_current_module_path(path.clone());
+
+        let script_args = args["args"]
+            .as_array()
+            .map(|a| {
+                a.iter()
+                    .filter_map(|v| v.as_str().map(String::from))
+                    .collect()
+            })
+            .unwrap_or_default();
+        self.vm.set_script_args(script_args);
+        self.program_path = Some(path);
+        Ok(())
+    }
+
+    fn set_breakpoints(&mut self, args: &Value) -> Value {
+        let requested: Vec<u64> = args["breakpoints"]
+            .as_array()
+            .map(|bps| bps.iter().filter_map(|bp| bp["line"].as_u64()).collect())
+            .unwrap_or_default();
+
+        let is_program = match (args["source"]["path"].as_str(), &self.program_path) {
+            (Some(path), Some(program)) => {
+                fs::canonicalize(path).unwrap_or_else(|_| PathBuf::from(path)) == *program
+            }
+            _ => false,
+        };
+
+        // Breakpoints are only supported in the launched program
+        self.breakpoints.clear();
+        let breakpoints: Vec<Value> = requested
+            .iter()
+            .map(|&line| {
+                match self
+                    .source_map
+                    .resolve_line(line as u32)
+                    .filter(|_| is_program)
+                {
+                    Some(loc) => {
+                        self.breakpoints.insert(loc.ip);
+                        json!({ "verified": true, "line": loc.line })
+                    }
+                    None => json!({ "verified": false, "line": line }),
+                }
+            })
+            .collect();
+        json!({ "breakpoints": breakpoints })
+    }
+
+    /// Run the debuggee until it stops according to `mode`, then report why
+    fn resume(&mut self, mode: StepMode) {
+        if self.terminated {
+            return;
+        }
+        let reason = self.run_until_stop(mode);
+        self.report_stop(reason);
+    }
+
+    fn run_until_stop(&mut self, mode: StepMode) -> StopReason {
+        let start_ip = self.vm.ip;
+        let start_depth = self.vm.call_stack.len();
+        let start_line = self.source_map.lookup(start_ip).map(|loc| loc.line);
+        let mut executed: u64 = 0;
+
+        loop {
+            if executed > 0 {
+                let ip = self.vm.ip;
+                let depth = self.vm.call_stack.len();
+                if self.breakpoints.contains(&ip) {
+                    return StopReason::Breakpoint;
+                }
+                // A new statement: a different line, or the same line again via a loop back-edge
+                let at_new_statement = self.source_map.is_statement_start(ip)
+                    && (self.source_map.lookup(ip).map(|loc| loc.line) != start_line
+                        || ip <= start_ip
+                        || depth != start_depth);
+                let stop = match mode {
+                    StepMode::Continue => false,
+                    StepMode::In => at_new_statement,
+                    StepMode::Over => at_new_statement && depth <= start_depth,
+                    StepMode::Out => depth < start_depth,
+                };
+                if stop {
+                    return StopReason::Step;
+                }
+            }
+
+            if executed > 0 && executed.is_multiple_of(POLL_INTERVAL) {
+                self.flush_output();
+                if self.poll_pause() {
+                    return StopReason::Pause;
+                }
+            }
+
+            match self.vm.step() {
+                Ok(true) => executed += 1,
+                Ok(false) => break,
+                Err(error) => return StopReason::Exception(error.to_string()),
+            }
+        }
+
+        // The main script finished; run remaining timers and tasks without stepping
+        match self.vm.drain_event_loop() {
+            Ok(()) => StopReason::Terminated,
+            Err(error) => StopReason::Exception(error.to_string()),
+        }
+    }
+
+    /// Drain incoming requests, returning true if a pause was requested.
+    /// Other requests are answered once the debuggee stops.
+    fn poll_pause(&mut self) -> bool {
+        let mut paused = false;
+        while let Ok(request) = self.incoming.try_recv() {
+            if request["command"] == "pause" {
+                self.respond(&request, json!({}));
+                paused = true;
+            } else {
+                self.pending.push_back(request);
+            }
+        }
+        paused
+    }
+
+    fn report_stop(&mut self, reason: StopReason) {
+        self.flush_output();
+        self.var_refs.clear();
+
+        let (reason, text) = match reason {
+            StopReason::Terminated => {
+                self.terminated = true;
+                self.send_event("exited", json!({ "exitCode": 0 }));
+                self.send_event("terminated", json!({}));
+                return;
+            }
+            StopReason::Exception(message) => {
+                // The VM cannot resume after an uncaught exception
+                self.terminated = true;
+                self.send_event(
+                    "output",
+                    json!({ "category": "stderr", "output": format!("{}\n", message) }),
+                );
+                ("exception", Some(message))
+            }
+            StopReason::Entry => ("entry", None),
+            StopReason::Breakpoint => ("breakpoint", None),
+            StopReason::Step => ("step", None),
+            StopReason::Pause => ("pause", None),
+        };
+        self.send_event(
+            "stopped",
+            json!({
+                "reason": reason,
+                "text": text,
+                "threadId": THREAD_ID,
+                "allThreadsStopped": true,
+            }),
+        );
+    }
+
+    /// Instruction address each frame is executing, innermost last.
+    /// Callers are positioned at their call instruction.
+    fn frame_ips(&self) -> Vec<usize> {
+        let depth = self.vm.call_stack.len();
+        (0..depth)
+            .map(|i| {
+                if i + 1 == depth {
+                    self.vm.ip
+                } else {
+                    self.vm.call_stack[i + 1].return_address.wrapping_sub(1)
+                }
+            })
+            .collect()
+    }
+
+    fn stack_trace(&self) -> Value {
+        let ips = self.frame_ips();
+        let source = self.program_path.as_ref().map(|path| {
+            json!({
+                "name": path.file_name().map(|n| n.to_string_lossy().to_string()),
+                "path": path.to_string_lossy(),
+            })
+        });
+
+        let frames: Vec<Value> = ips
+            .iter()
+            .enumerate()
+            .rev()
+            .map(|(i, &ip)| {
+                let name = if i == 0 {
+                    "<main>".to_string()
+                } else {
+                    self.source_map
+                        .enclosing_function(ip)
+                        .unwrap_or("<anonymous>")
+                        .to_string()
+                };
+                match self.source_map.lookup(ip) {
+                    Some(loc) => json!({
+                        "id": i + 1,
+                        "name": name,
+                        "source": source,
+                        "line": loc.line,
+                        "column": loc.column + 1,
+                    }),
+                    None => json!({ "id": i + 1, "name": name, "line": 0, "column": 0 }),
+                }
+            })
+            .collect();
+        json!({ "stackFrames": frames, "totalFrames": frames.len() })
+    }
+
+    /// Scopes shown for call frame `frame` (0 is the global frame)
+    fn scopes(&mut self, frame: usize) -> Value {
+        if frame >= self.vm.call_stack.len() {
+            return json!({ "scopes": [] });
+        }
+        let mut scopes = vec![json!({
+            "name": if frame == 0 { "Globals" } else { "Locals" },
+            "variablesReference": self.add_ref(VarRef::Locals(frame)),
+            "expensive": frame == 0,
+        })];
+        if !self.vm.call_stack[frame].indexed_locals.is_empty() {
+            scopes.push(json!({
+                "name": "Indexed Locals",
+                "variablesReference": self.add_ref(VarRef::IndexedLocals(frame)),
+                "expensive": false,
+            }));
+        }
+        if frame + 1 == self.vm.call_stack.len() {
+            scopes.push(json!({
+                "name": "Operand Stack",
+                "variablesReference": self.add_ref(VarRef::OperandStack),
+                "expensive": false,
+            }));
+        }
+        json!({ "scopes": scopes })
+    }
+
+    fn variables(&mut self, reference: usize) -> Value {
+        let Some(target) = reference
+            .checked_sub(1)
+            .and_then(|i| self.var_refs.get(i))
+            .copied()
+        else {
+            return json!({ "variables": [] });
+        };
+
+        let entries: Vec<(String, JsValue)> = match target {
+            VarRef::Locals(frame) => {
+                let frame = &self.vm.call_stack[frame];
+                let mut entries: Vec<(String, JsValue)> = frame
+                    .locals
+                    .iter()
+                    .map(|(name, value)| (name.clone(), value.clone()))
+                    .collect();
+                entries.sort_by(|a, b| a.0.cmp(&b.0));
+                if frame.this_context != JsValue::Undefined {
+                    entries.insert(0, ("this".to_string(), frame.this_context.clone()));
+                }
+                entries
+            }
+            VarRef::IndexedLocals(frame) => indexed(&self.vm.call_stack[frame].indexed_locals),
+            // Top of stack first
+            VarRef::OperandStack => {
+                let mut entries = indexed(&self.vm.stack);
+                entries.reverse();
+                entries
+            }
+            VarRef::Heap(ptr) => match self.vm.heap.get(ptr).map(|obj| &obj.data) {
+                Some(HeapData::Object(props)) => {
+                    let mut entries: Vec<(String, JsValue)> = props
+                        .iter()
+                        .map(|(name, value)| (name.clone(), value.clone()))
+                        .collect();
+                    entries.sort_by(|a, b| a.0.cmp(&b.0));
+                    entries
+                }
+                Some(HeapData::Array(items)) => indexed(items),
+                Some(HeapData::Set(set)) => indexed(&set.keys().cloned().collect::<Vec<_>>()),
+                Some(HeapData::Map(map)) => map
+                    .iter()
+                    .map(|(k, v)| (inspect_value(&self.vm, k), v.clone()))
+                    .collect(),
+                Some(HeapData::ByteStream(bytes)) => bytes
+                    .iter()
+                    .enumerate()
+                    .map(|(i, b)| (i.to_string(), JsValue::Number(*b as f64)))
+                    .collect(),
+                Some(HeapData::ArrayBuffer(bytes)) => bytes
+                    .iter()
+                    .enumerate()
+                    .map(|(i, b)| (i.to_string(), JsValue::Number(*b as f64)))
+                    .collect(),
+                Some(HeapData::TypedArray { length, .. }) => (0..*length)
+                    .map(|i| {
+                        let index = JsValue::Number(i as f64);
+                        let value = crate::stdlib::typed_array::get(&self.vm, ptr, &index);
+                        (i.to_string(), value.unwrap_or(JsValue::Undefined))
+                    })
+                    .collect(),
+                Some(HeapData::Date(_))
+                | Some(HeapData::DataView { .. })
+                | Some(HeapData::WeakMap(_))
+                | Some(HeapData::WeakSet(_))
+                | Some(HeapData::CollectionIterator { .. })
+                | None => Vec::new(),
+            },
+        };
+
+        let variables: Vec<Value> = entries
+            .into_iter()
+            .map(|(name, value)| {
+                let mut variable = self.describe(&value);
+                variable["name"] = json!(name);
+                variable
+            })
+            .collect();
+        json!({ "variables": variables })
+    }
+
+    /// Look up a variable or property path (`a.b.c`) in the given frame
+    fn evaluate(&mut self, expression: &str, frame: Option<usize>) -> Result<Value, String> {
+        let mut parts = expression.trim().split('.');
+        let root = parts.next().unwrap_or("");
+        let frame = frame
+            .filter(|f| *f < self.vm.call_stack.len())
+            .unwrap_or(self.vm.call_stack.len() - 1);
+
+        let mut value = if root == "this" {
+            self.vm.call_stack[frame].this_context.clone()
+        } else {
+            self.vm.call_stack[frame]
+                .locals
+                .get(root)
+                .or_else(|| self.vm.call_stack[0].locals.get(root))
+                .cloned()
+                .ok_or_else(|| format!("{} is not defined", root))?
+        };
+        for part in parts {
+            value = match value {
+                JsValue::Object(ptr) => self.vm.get_prop_with_proto_chain(ptr, part),
+                _ => JsValue::Undefined,
+            };
+        }
+
+        let mut body = self.describe(&value);
+        body["result"] = body["value"].take();
+        Ok(body)
+    }
+
+    /// DAP variable fields for a value; objects get an expandable reference
+    fn describe(&mut self, value: &JsValue) -> Value {
+        let reference = match value {
+            JsValue::Object(ptr) => self.add_ref(VarRef::Heap(*ptr)),
+            _ => 0,
+        };
+        json!({
+            "value": inspect_value(&self.vm, value),
+            "type": type_name(value),
+            "variablesReference": reference,
+        })
+    }
+
+    fn add_ref(&mut self, target: VarRef) -> usize {
+        self.var_refs.push(target);
+        self.var_refs.len()
+    }
+
+    fn flush_output(&mut self) {
+        let captured = match &mut self.vm.console_capture {
+            Some(buffer) if !buffer.is_empty() => std::mem::take(buffer),
+            _ => return,
+        };
+        for (category, output) in captured {
+            self.send_event("output", json!({ "category": category, "output": output }));
+        }
+    }
+
+    fn respond(&mut self, request: &Value, body: Value) {
+        let message = json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "success": true,
+            "command": request["command"],
+            "body": body,
+        });
+        self.send(message);
+    }
+
+    fn respond_error(&mut self, request: &Value, error: &str) {
+        let message = json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "success": false,
+            "command": request["command"],
+            "message": error,
+        });
+        self.send(message);
+    }
+
+    fn send_event(&mut self, event: &str, body: Value) {
+        self.send(json!({ "type": "event", "event": event, "body": body }));
+    }
+
+    fn send(&mut self, mut message: Value) {
+        message["seq"] = json!(self.seq);
+        self.seq += 1;
+        let _ = protocol::write_message(&mut self.output, &message);
+    }
+}
+
+fn indexed(values: &[JsValue]) -> Vec<(String, JsValue)> {
+    values
+        .iter()
+        .enumerate()
+        .map(|(i, v)| (i.to_string(), v.clone()))
+        .collect()
+}
+
+fn type_name(value: &JsValue) -> &'static str {
+    match value {
+        JsValue::Number(_) => "number",
+        JsValue::String(_) => "string",
+        JsValue::Boolean(_) => "boolean",
+        JsValue::Object(_) => "object",
+        JsValue::Function { .. } | JsValue::NativeFunction(_) => "function",
+        JsValue::Null => "null",
+        JsValue::Undefined => "undefined",
+        JsValue::Accessor(_, _) => "accessor",
+        JsValue::Promise(_) => "promise",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    const PROGRAM: &str = "let total = 0;
+function add(n) {
+  let next = total + n;
+  return next;
+}
+total = add(2);
+console.log(\"total\", total);
+";
+
+    struct Session {
+        adapter: DebugAdapter<Vec<u8>>,
+        _tx: mpsc::Sender<Value>,
+        next_seq: u64,
+        /// Temporary copy of the debuggee, removed when the session is dropped
+        path: PathBuf,
+    }
+
+    impl Drop for Session {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+
+    impl Session {
+        fn launch(stop_on_entry: bool) -> Self {
+            Self::launch_source(PROGRAM, stop_on_entry)
+        }
+
+        fn launch_source(program: &str, stop_on_entry: bool) -> Self {
+            static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+            let id = NEXT_ID.fetch_add(1, Ordering::SeqCst);
+            let path =
+                std::env::temp_dir().join(format!("oite_debug_{}_{}.ot", std::process::id(), id));
+            fs::write(&path, program).unwrap();
+            let (tx, rx) = mpsc::channel();
+            let mut session = Session {
+                adapter: DebugAdapter::new(Vec::new(), rx),
+                _tx: tx,
+                next_seq: 1,
+                path: path.clone(),
+            };
+            session.request("initialize", json!({}));
+            session.request(
+                "launch",
+                json!({ "program": path.to_str().unwrap(), "stopOnEntry": stop_on_entry }),
+            );
+            session.request(
+                "setBreakpoints",
+                json!({ "source": { "path": path.to_str().unwrap() }, "breakpoints": [{ "line": 3 }] }),
+            );
+            session
+        }
+
+        /// Send a request and return every message the adapter wrote in response
+        fn request(&mut self, command: &str, arguments: Value) -> Vec<Value> {
+            let request = json!({
+                "seq": self.next_seq,
+                "type": "request",
+                "command": command,
+                "arguments": arguments,
+            });
+            self.next_seq += 1;
+            self.adapter.handle_request(&request);
+
+            let output = std::mem::take(&mut self.adapter.output);
+            let mut reader = io::Cursor::new(output);
+            let mut messages = Vec::new();
+            while let Some(message) = protocol::read_message(&mut reader).unwrap() {
+                messages.push(message);
+            }
+            messages
+        }
+
+        fn top_frame_line(&mut self) -> u64 {
+            let messages = self.request("stackTrace", json!({ "threadId": THREAD_ID }));
+            messages[0]["body"]["stackFrames"][0]["line"]
+                .as_u64()
+                .unwrap()
+        }
+    }
+
+    fn events<'a>(messages: &'a [Value], name: &str) -> Vec<&'a Value> {
+        messages.iter().filter(|m| m["event"] == name).collect()
+    }
+
+    #[test]
+    fn test_breakpoint_stops_inside_function() {
+        let mut session = Session::launch(false);
+        let messages = session.request("configurationDone", json!({}));
+        let stopped = events(&messages, "stopped");
+        assert_eq!(stopped[0]["body"]["reason"], "breakpoint");
+        assert_eq!(session.top_frame_line(), 3);
+
+        let trace = session.request("stackTrace", json!({}));
+        let frames = trace[0]["body"]["stackFrames"].as_array().unwrap();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0]["name"], "add");
+        assert_eq!(frames[1]["line"], 6);
+    }
+
+    #[test]
+    fn test_step_over_and_inspect_locals() {
+        let mut session = Session::launch(false);
+        session.request("configurationDone", json!({}));
+        session.request("next", json!({ "threadId": THREAD_ID }));
+        assert_eq!(session.top_frame_line(), 4);
+
+        let scopes = session.request("scopes", json!({ "frameId": 2 }));
+        let locals_ref = scopes[0]["body"]["scopes"][0]["variablesReference"].clone();
+        let vars = session.request("variables", json!({ "variablesReference": locals_ref }));
+        let vars = vars[0]["body"]["variables"].as_array().unwrap();
+        let next = vars.iter().find(|v| v["name"] == "next").unwrap();
+        assert_eq!(next["value"], "2");
+    }
+
+    #[test]
+    fn test_step_out_and_continue_to_end() {
+        let mut session = Session::launch(false);
+        session.request("configurationDone", json!({}));
+        session.request("stepOut", json!({ "threadId": THREAD_ID }));
+        assert_eq!(session.top_frame_line(), 6);
+
+        let messages = session.request("continue", json!({ "threadId": THREAD_ID }));
+        let output = events(&messages, "output");
+        assert_eq!(output[0]["body"]["output"], "total 2\n");
+        assert_eq!(events(&messages, "terminated").len(), 1);
+    }
+
+    #[test]
+    fn test_stop_on_entry_and_step_in() {
+        let mut session = Session::launch(true);
+        let messages = session.request("configurationDone", json!({}));
+        assert_eq!(events(&messages, "stopped")[0]["body"]["reason"], "entry");
+        assert_eq!(session.top_frame_line(), 1);
+
+        session.request("stepIn", json!({ "threadId": THREAD_ID }));
+        assert_eq!(session.top_frame_line(), 2);
+        let result = session.request("evaluate", json!({ "expression": "total" }));
+        assert_eq!(result[0]["body"]["result"], "0");
+    }
+
+    #[test]
+    fn test_uncaught_exception_stops_with_exception_reason() {
+        let mut session = Session::launch_source("let x = 1;\nthrow new Error(\"boom\");\n", false);
+        let messages = session.request("configurationDone", json!({}));
+        let stopped = events(&messages, "stopped");
+        assert_eq!(stopped[0]["body"]["reason"], "exception");
+        let text = stopped[0]["body"]["text"].as_str().unwrap();
+        assert!(text.starts_with("Uncaught Error: boom"), "{}", text);
+    }
+}
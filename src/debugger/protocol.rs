@@ -0,0 +1,63 @@
+//! Debug Adapter Protocol wire format
+//!
+//! Messages are JSON bodies preceded by a `Content-Length` header, exactly
+//! like the Language Server Protocol.
+
+use serde_json::Value;
+use std::io::{self, BufRead, Write};
+
+/// Read one message. Returns `Ok(None)` at end of input.
+pub fn read_message<R: BufRead>(reader: &mut R) -> io::Result<Option<Value>> {
+    let mut content_length: Option<usize> = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            if content_length.is_some() {
+                break;
+            }
+            // Tolerate stray blank lines between messages
+            continue;
+        }
+        if let Some((name, value)) = header.split_once(':')
+            && name.trim().eq_ignore_ascii_case("Content-Length")
+        {
+            content_length = value.trim().parse().ok();
+        }
+    }
+
+    let mut body = vec![0u8; content_length.unwrap_or(0)];
+    reader.read_exact(&mut body)?;
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Write one message with its `Content-Length` header
+pub fn write_message<W: Write>(writer: &mut W, message: &Value) -> io::Result<()> {
+    let body = message.to_string();
+    write!(writer, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_message_round_trip() {
+        let message = json!({"seq": 1, "type": "request", "command": "initialize"});
+        let mut buffer = Vec::new();
+        write_message(&mut buffer, &message).unwrap();
+        write_message(&mut buffer, &json!({"seq": 2})).unwrap();
+
+        let mut reader = io::Cursor::new(buffer);
+        assert_eq!(read_message(&mut reader).unwrap(), Some(message));
+        assert_eq!(read_message(&mut reader).unwrap(), Some(json!({"seq": 2})));
+        assert_eq!(read_message(&mut reader).unwrap(), None);
+    }
+}
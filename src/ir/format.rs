@@ -156,6 +156,22 @@ fn serialize_op(output: &mut String, op: &IrOp) {
         IrOp::Shr(d, a, b) => output.push_str(&format!("{} = shr {}, {}", d, a, b)),
         IrOp::ShrU(d, a, b) => output.push_str(&format!("{} = shr.u {}, {}", d, a, b)),
         IrOp::Pow(d, a, b) => output.push_str(&format!("{} = pow {}, {}", d, a, b)),
+        IrOp::MathAny(d, func, args) | IrOp::MathNum(d, func, args) => {
+            let args_str: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+            let kind = if matches!(op, IrOp::MathNum(..)) {
+                "num"
+            } else {
+                "any"
+            };
+            output.push_str(&format!(
+                "{} = math.{} {}({})",
+                d,
+                kind,
+                func.name(),
+                args_str.join(", ")
+            ));
+        }
+        IrOp::PerformanceNow(d) => output.push_str(&format!("{} = performance.now", d)),
         IrOp::LoadLocal(d, slot) => output.push_str(&format!("{} = load.local ${}", d, slot)),
         IrOp::StoreLocal(slot, v) => output.push_str(&format!("store.local ${}, {}", slot, v)),
         IrOp::LoadGlobal(d, name) => output.push_str(&format!("{} = load.global @{}", d, name)),
@@ -174,6 +190,12 @@ fn serialize_op(output: &mut String, op: &IrOp) {
             output.push_str(&format!("set.elem {}, [{}], {}", obj, key, val))
         }
         IrOp::NewArray(d) => output.push_str(&format!("{} = new.array", d)),
+        IrOp::NewTypedArray(d, kind, src) => output.push_str(&format!(
+            "{} = new.typed_array {}, {}",
+            d,
+            kind.short_name(),
+            src
+        )),
         IrOp::ArrayLen(d, arr) => output.push_str(&format!("{} = array.len {}", d, arr)),
         IrOp::ArrayPush(arr, val) => output.push_str(&format!("array.push {}, {}", arr, val)),
         IrOp::Call(d, func, args) => {
@@ -7,7 +7,10 @@
 //! 3. Convert stack operations to explicit value assignments
 //! 4. Insert phi nodes at CFG merge points
 
-use crate::ir::{BlockId, IrFunction, IrModule, IrOp, IrType, Literal, Terminator, ValueId};
+use crate::ir::{
+    BlockId, ElementKind, IrFunction, IrModule, IrOp, IrType, Literal, MathFn, Terminator, ValueId,
+};
+use crate::runtime::math;
 use crate::vm::opcodes::OpCode;
 use crate::vm::value::JsValue;
 use std::collections::{HashMap, HashSet};
@@ -56,6 +59,46 @@ pub struct Lowerer {
     local_values: HashMap<u32, ValueId>,
     /// Block entry states for phi node generation.
     block_entry_stacks: HashMap<BlockId, Vec<ValueId>>,
+    /// Builtins the program binds itself, hiding the real ones.
+    shadowed: Vec<Builtin>,
+    /// Values holding a builtin object.
+    builtin_objects: HashMap<ValueId, Builtin>,
+}
+
+/// Globals whose constants and methods lower to dedicated ops.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Builtin {
+    Math,
+    Performance,
+    TypedArray(ElementKind),
+}
+
+impl Builtin {
+    const ALL: [Builtin; 11] = [
+        Builtin::Math,
+        Builtin::Performance,
+        Builtin::TypedArray(ElementKind::Int8),
+        Builtin::TypedArray(ElementKind::Uint8),
+        Builtin::TypedArray(ElementKind::Uint8Clamped),
+        Builtin::TypedArray(ElementKind::Int16),
+        Builtin::TypedArray(ElementKind::Uint16),
+        Builtin::TypedArray(ElementKind::Int32),
+        Builtin::TypedArray(ElementKind::Uint32),
+        Builtin::TypedArray(ElementKind::Float32),
+        Builtin::TypedArray(ElementKind::Float64),
+    ];
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|b| b.name() == name)
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Builtin::Math => "Math",
+            Builtin::Performance => "performance",
+            Builtin::TypedArray(kind) => kind.name(),
+        }
+    }
 }
 
 impl Lowerer {
@@ -73,6 +116,8 @@ impl Lowerer {
             var_to_slot: HashMap::new(),
             local_values: HashMap::new(),
             block_entry_stacks: HashMap::new(),
+            shadowed: Vec::new(),
+            builtin_objects: HashMap::new(),
         }
     }
 
@@ -93,6 +138,8 @@ impl Lowerer {
 
     /// Lower a sequence of bytecode instructions to SSA IR.
     pub fn lower(mut self, instructions: &[OpCode]) -> Result<IrFunction, LowerError> {
+        self.shadowed.extend(shadowed_builtins(instructions));
+
         // Pass 1: Identify basic block boundaries
         self.find_block_boundaries(instructions);
 
@@ -131,11 +178,9 @@ impl Lowerer {
                         self.block_starts.insert(i + 1);
                     }
                 }
-                OpCode::Return | OpCode::Halt => {
-                    // Instruction after terminator is a block start
-                    if i + 1 < instructions.len() {
-                        self.block_starts.insert(i + 1);
-                    }
+                // Instruction after terminator is a block start
+                OpCode::Return | OpCode::Halt if i + 1 < instructions.len() => {
+                    self.block_starts.insert(i + 1);
                 }
                 OpCode::Call(_) | OpCode::CallMethod(_, _) => {
                     // Calls can throw, so next instruction could be a catch block
@@ -578,11 +623,16 @@ impl Lowerer {
                 self.local_values.insert(slot, val);
             }
 
-            OpCode::Load(name) => {
+            OpCode::Load(name) | OpCode::LoadOrUndefined(name) => {
                 let slot = self.get_or_create_local(name);
                 let dst = self.alloc_value(IrType::Any);
                 self.emit(IrOp::LoadLocal(dst, slot));
                 self.local_values.insert(slot, dst);
+                if let Some(builtin) = Builtin::from_name(name)
+                    && !self.shadowed.contains(&builtin)
+                {
+                    self.builtin_objects.insert(dst, builtin);
+                }
                 self.push(dst);
             }
 
@@ -621,6 +671,14 @@ impl Lowerer {
 
             OpCode::GetProp(name) => {
                 let obj = self.pop()?;
+                if self.builtin_objects.get(&obj) == Some(&Builtin::Math)
+                    && let Some(value) = math::constant(name)
+                {
+                    let dst = self.alloc_value(IrType::Number);
+                    self.emit(IrOp::Const(dst, Literal::Number(value)));
+                    self.push(dst);
+                    return Ok(());
+                }
                 let dst = self.alloc_value(IrType::Any);
                 self.emit(IrOp::GetProp(dst, obj, name.clone()));
                 self.push(dst);
@@ -635,18 +693,18 @@ impl Lowerer {
             }
 
             OpCode::SetPropComputed => {
-                // For now, just pop the values - the VM handles this
-                let _key = self.pop()?;
-                let _val = self.pop()?;
+                let key = self.pop()?;
+                let val = self.pop()?;
                 let obj = self.pop()?;
+                self.emit(IrOp::SetElement(obj, key, val));
                 self.push(obj);
             }
 
             OpCode::GetPropComputed => {
-                // For now, just pop the values and push undefined
-                let _key = self.pop()?;
-                let _obj = self.pop()?;
+                let key = self.pop()?;
+                let obj = self.pop()?;
                 let dst = self.alloc_value(IrType::Any);
+                self.emit(IrOp::GetElement(dst, obj, key));
                 self.push(dst);
             }
 
@@ -746,6 +804,22 @@ impl Lowerer {
                 }
                 args.reverse();
 
+                let builtin = self.builtin_objects.get(&obj).copied();
+                if builtin == Some(Builtin::Math)
+                    && let Some(func) = MathFn::from_name(name)
+                {
+                    let dst = self.alloc_value(IrType::Number);
+                    self.emit(IrOp::MathAny(dst, func, args));
+                    self.push(dst);
+                    return Ok(());
+                }
+                if builtin == Some(Builtin::Performance) && name == "now" {
+                    let dst = self.alloc_value(IrType::Number);
+                    self.emit(IrOp::PerformanceNow(dst));
+                    self.push(dst);
+                    return Ok(());
+                }
+
                 let dst = self.alloc_value(IrType::Any);
                 self.emit(IrOp::CallMethod(dst, obj, name.clone(), args));
                 self.push(dst);
@@ -760,15 +834,38 @@ impl Lowerer {
             }
 
             OpCode::Construct(argc) => {
-                // Pop arguments
+                // Stack: [..., this, this, arg0, ..., argN, ctor], where `this`
+                // is the `NewObject, Dup` pair the compiler emits
+                let ctor = self.pop()?;
+
                 let mut args = Vec::with_capacity(*argc);
                 for _ in 0..*argc {
                     args.push(self.pop()?);
                 }
                 args.reverse();
 
-                // Pop constructor
-                let ctor = self.pop()?;
+                if let [.., a, b] = self.stack.as_slice()
+                    && a == b
+                {
+                    self.stack.truncate(self.stack.len() - 2);
+                }
+
+                if let Some(&Builtin::TypedArray(kind)) = self.builtin_objects.get(&ctor)
+                    && args.len() <= 1
+                {
+                    let source = match args.first() {
+                        Some(&source) => source,
+                        None => {
+                            let undefined = self.alloc_value(IrType::Any);
+                            self.emit(IrOp::Const(undefined, Literal::Undefined));
+                            undefined
+                        }
+                    };
+                    let dst = self.alloc_value(IrType::typed_array(kind));
+                    self.emit(IrOp::NewTypedArray(dst, kind, source));
+                    self.push(dst);
+                    return Ok(());
+                }
 
                 // For now, treat construct as a call
                 let dst = self.alloc_value(IrType::Object);
@@ -859,6 +956,12 @@ impl Lowerer {
                 self.push(val);
             }
 
+            // AOT objects have no read-only flag yet; the value passes through
+            OpCode::Freeze => {}
+
+            // AOT `for...of` only iterates arrays, which pass through as they are
+            OpCode::ToIterable => {}
+
             // === ES Modules ===
             // ES modules require async loading which isn't supported in AOT yet
             // These opcodes will work in JIT mode but emit stubs for AOT
@@ -871,6 +974,22 @@ impl Lowerer {
                 self.push(dst);
             }
 
+            // A native binary has no module loader to fetch code at run time
+            OpCode::ImportDynamic => {
+                return Err(LowerError::UnsupportedOpcode(
+                    "dynamic import() in a native build".to_string(),
+                ));
+            }
+            OpCode::ImportMeta => {
+                return Err(LowerError::UnsupportedOpcode(
+                    "import.meta in a native build".to_string(),
+                ));
+            }
+
+            OpCode::EnterAsync => {
+                // Async calls run synchronously in a native build
+            }
+
             OpCode::Await => {
                 // Await requires async runtime support - emit undefined for now
                 let _promise = self.pop()?;
@@ -969,7 +1088,7 @@ impl Lowerer {
     fn jsvalue_to_literal(&self, value: &JsValue) -> (Literal, IrType) {
         match value {
             JsValue::Number(n) => (Literal::Number(*n), IrType::Number),
-            JsValue::String(s) => (Literal::String(s.clone()), IrType::String),
+            JsValue::String(s) => (Literal::String(s.to_string()), IrType::String),
             JsValue::Boolean(b) => (Literal::Boolean(*b), IrType::Boolean),
             JsValue::Null => (Literal::Null, IrType::Any),
             JsValue::Undefined => (Literal::Undefined, IrType::Any),
@@ -1021,6 +1140,9 @@ pub fn lower_module(instructions: &[OpCode]) -> Result<IrModule, LowerError> {
         }
     }
 
+    // A `Math` declared anywhere may be the one a function sees, and so on
+    let shadowed = shadowed_builtins(instructions);
+
     // Step 2: Lower each extracted function
     for func_info in &extracted_funcs {
         let func_bytecode = &instructions[func_info.address..=func_info.end_address];
@@ -1034,6 +1156,7 @@ pub fn lower_module(instructions: &[OpCode]) -> Result<IrModule, LowerError> {
             func_info.address,
             func_info.self_reference_var.as_ref(),
             &func_var_addrs,
+            &shadowed,
         ) {
             Ok(ir_func) => {
                 module.add_function(ir_func);
@@ -1078,10 +1201,12 @@ fn lower_extracted_function(
     base_addr: usize,
     self_ref_var: Option<&String>,
     func_var_addrs: &HashMap<String, usize>,
+    shadowed: &[Builtin],
 ) -> Result<IrFunction, LowerError> {
     // Rebase jump targets to be relative to the function start
     let rebased = rebase_jump_targets(instructions, base_addr);
     let mut lowerer = Lowerer::new_with_params(name.to_string(), param_names);
+    lowerer.shadowed = shadowed.to_vec();
 
     for param_name in param_names {
         lowerer.get_or_create_local(param_name);
@@ -1275,6 +1400,18 @@ fn find_function_end(start: usize, instructions: &[OpCode]) -> Option<usize> {
     last_return
 }
 
+/// Builtins whose name the bytecode declares or assigns as a variable.
+fn shadowed_builtins(instructions: &[OpCode]) -> Vec<Builtin> {
+    Builtin::ALL
+        .into_iter()
+        .filter(|b| {
+            instructions
+                .iter()
+                .any(|op| matches!(op, OpCode::Let(n) | OpCode::Store(n) if n == b.name()))
+        })
+        .collect()
+}
+
 /// Lower a single function's bytecode to SSA IR.
 pub fn lower_function(name: &str, instructions: &[OpCode]) -> Result<IrFunction, LowerError> {
     let lowerer = Lowerer::new(name.to_string());
@@ -1413,4 +1550,98 @@ mod tests {
         // Should have multiple blocks due to the loop
         assert!(func.blocks.len() >= 3);
     }
+
+    #[test]
+    fn test_lower_math() {
+        // Math.floor(x * Math.PI)
+        let instructions = vec![
+            OpCode::Load("x".to_string()),
+            OpCode::Load("Math".to_string()),
+            OpCode::GetProp("PI".to_string()),
+            OpCode::Mul,
+            OpCode::Load("Math".to_string()),
+            OpCode::CallMethod("floor".to_string(), 1),
+            OpCode::Return,
+        ];
+
+        let func = lower_function("test", &instructions).unwrap();
+        let ops = &func.blocks[0].ops;
+        assert!(ops.iter().any(
+            |op| matches!(op, IrOp::Const(_, Literal::Number(n)) if *n == std::f64::consts::PI)
+        ));
+        assert!(
+            ops.iter()
+                .any(|op| matches!(op, IrOp::MathAny(_, MathFn::Floor, args) if args.len() == 1))
+        );
+
+        // A program's own `Math` is an ordinary object
+        let mut shadowed = vec![OpCode::NewObject, OpCode::Let("Math".to_string())];
+        shadowed.extend(instructions);
+        let func = lower_function("test", &shadowed).unwrap();
+        let ops = &func.blocks[0].ops;
+        assert!(!ops.iter().any(|op| matches!(op, IrOp::MathAny(..))));
+        assert!(ops.iter().any(|op| matches!(op, IrOp::CallMethod(..))));
+    }
+
+    #[test]
+    fn test_lower_performance_now() {
+        let instructions = vec![
+            OpCode::Load("performance".to_string()),
+            OpCode::CallMethod("now".to_string(), 0),
+            OpCode::Return,
+        ];
+        let func = lower_function("test", &instructions).unwrap();
+        assert!(
+            func.blocks[0]
+                .ops
+                .iter()
+                .any(|op| matches!(op, IrOp::PerformanceNow(_)))
+        );
+
+        let mut shadowed = vec![OpCode::NewObject, OpCode::Let("performance".to_string())];
+        shadowed.extend(instructions);
+        let func = lower_function("test", &shadowed).unwrap();
+        assert!(
+            !func.blocks[0]
+                .ops
+                .iter()
+                .any(|op| matches!(op, IrOp::PerformanceNow(_)))
+        );
+    }
+
+    #[test]
+    fn test_lower_typed_array() {
+        // let a = new Float64Array(4); a[1] = 2.5; return a[1];
+        let instructions = vec![
+            OpCode::NewObject,
+            OpCode::Dup,
+            OpCode::Push(JsValue::Number(4.0)),
+            OpCode::Load("Float64Array".to_string()),
+            OpCode::Construct(1),
+            OpCode::Let("a".to_string()),
+            OpCode::Load("a".to_string()),
+            OpCode::Push(JsValue::Number(2.5)),
+            OpCode::Push(JsValue::Number(1.0)),
+            OpCode::SetPropComputed,
+            OpCode::Load("a".to_string()),
+            OpCode::Push(JsValue::Number(1.0)),
+            OpCode::GetPropComputed,
+            OpCode::Return,
+        ];
+        let func = lower_function("test", &instructions).unwrap();
+        let ops = &func.blocks[0].ops;
+        let created = ops
+            .iter()
+            .find_map(|op| match op {
+                IrOp::NewTypedArray(dst, ElementKind::Float64, _) => Some(*dst),
+                _ => None,
+            })
+            .expect("no new.typed_array");
+        assert_eq!(
+            func.value_types.get(&created),
+            Some(&IrType::typed_array(ElementKind::Float64))
+        );
+        assert!(ops.iter().any(|op| matches!(op, IrOp::SetElement(..))));
+        assert!(ops.iter().any(|op| matches!(op, IrOp::GetElement(..))));
+    }
 }
@@ -22,6 +22,9 @@ pub mod verify;
 use std::collections::HashMap;
 use std::fmt;
 
+pub use crate::runtime::math::MathFn;
+pub use crate::runtime::typed_array::ElementKind;
+
 // ============================================================================
 // Type System
 // ============================================================================
@@ -71,6 +74,8 @@ pub enum IrType {
     Array,
     /// Typed array with element type
     TypedArray(Box<IrType>),
+    /// Unboxed typed array element, stored packed at its kind's width
+    Scalar(ElementKind),
     /// Function closure
     Function,
     /// Named struct type (with known layout)
@@ -148,6 +153,22 @@ impl IrType {
         }
     }
 
+    /// The type of a typed array of `kind` elements.
+    pub fn typed_array(kind: ElementKind) -> IrType {
+        IrType::TypedArray(Box::new(IrType::Scalar(kind)))
+    }
+
+    /// The element kind of a typed array of unboxed elements.
+    pub fn scalar_element(&self) -> Option<ElementKind> {
+        match self {
+            IrType::TypedArray(elem) => match **elem {
+                IrType::Scalar(kind) => Some(kind),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
     /// Create an immutable reference to this type.
     pub fn as_ref(self) -> IrType {
         IrType::Ref(Box::new(self))
@@ -168,6 +189,7 @@ impl fmt::Display for IrType {
             IrType::Object => write!(f, "obj"),
             IrType::Array => write!(f, "arr"),
             IrType::TypedArray(elem) => write!(f, "{}[]", elem),
+            IrType::Scalar(kind) => write!(f, "{}", kind.short_name()),
             IrType::Function => write!(f, "fn"),
             IrType::Struct(id) => write!(f, "{}", id),
             IrType::Ref(inner) => write!(f, "&{}", inner),
@@ -427,6 +449,16 @@ pub enum IrOp {
     /// Power: dst = a ** b
     Pow(ValueId, ValueId, ValueId),
 
+    // === Math ===
+    /// Math function on dynamic values: dst = Math.f(args...)
+    MathAny(ValueId, MathFn, Vec<ValueId>),
+    /// Math function on numbers: dst = Math.f(args...)
+    MathNum(ValueId, MathFn, Vec<ValueId>),
+
+    // === Time ===
+    /// Monotonic clock in milliseconds: dst = performance.now()
+    PerformanceNow(ValueId),
+
     // === Comparison ===
     /// Strict equality: dst = a === b
     EqStrict(ValueId, ValueId, ValueId),
@@ -476,6 +508,8 @@ pub enum IrOp {
     // === Array Operations ===
     /// Create new array: dst = []
     NewArray(ValueId),
+    /// Create typed array from a length or array: dst = new Float64Array(src)
+    NewTypedArray(ValueId, ElementKind, ValueId),
     /// Get array length: dst = arr.length
     ArrayLen(ValueId, ValueId),
     /// Push to array: arr.push(val)
@@ -581,12 +615,16 @@ impl IrOp {
             | IrOp::Shr(d, _, _)
             | IrOp::ShrU(d, _, _)
             | IrOp::Pow(d, _, _)
+            | IrOp::MathAny(d, _, _)
+            | IrOp::MathNum(d, _, _)
+            | IrOp::PerformanceNow(d)
             | IrOp::LoadLocal(d, _)
             | IrOp::LoadGlobal(d, _)
             | IrOp::NewObject(d)
             | IrOp::GetProp(d, _, _)
             | IrOp::GetElement(d, _, _)
             | IrOp::NewArray(d)
+            | IrOp::NewTypedArray(d, _, _)
             | IrOp::ArrayLen(d, _)
             | IrOp::Call(d, _, _)
             | IrOp::CallMethod(d, _, _, _)
@@ -668,6 +706,7 @@ impl IrOp {
             | IrOp::ToNum(_, a)
             | IrOp::Copy(_, a)
             | IrOp::ArrayLen(_, a)
+            | IrOp::NewTypedArray(_, _, a)
             | IrOp::TypeCheck(_, a, _)
             | IrOp::TypeGuard(_, a, _)
             // Borrow operations
@@ -682,7 +721,7 @@ impl IrOp {
             IrOp::LoadLocal(_, _) | IrOp::LoadGlobal(_, _) | IrOp::LoadThis(_) => vec![],
             IrOp::StoreLocal(_, v) | IrOp::StoreGlobal(_, v) => vec![*v],
 
-            IrOp::NewObject(_) | IrOp::NewArray(_) => vec![],
+            IrOp::NewObject(_) | IrOp::NewArray(_) | IrOp::PerformanceNow(_) => vec![],
             // Struct new
             IrOp::StructNew(_, _) => vec![],
 
@@ -710,7 +749,9 @@ impl IrOp {
                 uses.extend(args.iter().copied());
                 uses
             }
-            IrOp::CallMono(_, _, args) => args.clone(),
+            IrOp::CallMono(_, _, args)
+            | IrOp::MathAny(_, _, args)
+            | IrOp::MathNum(_, _, args) => args.clone(),
             IrOp::MakeClosure(_, _, env) => vec![*env],
 
             IrOp::Phi(_, entries) => entries.iter().map(|(_, v)| *v).collect(),
@@ -1010,6 +1051,7 @@ impl IrStructDef {
         match ty {
             IrType::Number => 8,
             IrType::Boolean => 1,
+            IrType::Scalar(kind) => kind.size() as u32,
             IrType::Void => 0,
             IrType::Never => 0,
             // Reference types are pointers
@@ -1030,6 +1072,7 @@ impl IrStructDef {
         match ty {
             IrType::Number => 8,
             IrType::Boolean => 1,
+            IrType::Scalar(kind) => kind.size() as u32,
             IrType::Void | IrType::Never => 1,
             _ => 8, // All reference types and Any are 8-byte aligned
         }
@@ -1182,6 +1225,23 @@ impl fmt::Display for IrOp {
             IrOp::Shr(d, a, b) => write!(f, "{} = shr {}, {}", d, a, b),
             IrOp::ShrU(d, a, b) => write!(f, "{} = shr.u {}, {}", d, a, b),
             IrOp::Pow(d, a, b) => write!(f, "{} = pow {}, {}", d, a, b),
+            IrOp::MathAny(d, func, args) | IrOp::MathNum(d, func, args) => {
+                let args_str: Vec<_> = args.iter().map(|a| format!("{}", a)).collect();
+                let kind = if matches!(self, IrOp::MathNum(..)) {
+                    "num"
+                } else {
+                    "any"
+                };
+                write!(
+                    f,
+                    "{} = math.{} {}({})",
+                    d,
+                    kind,
+                    func.name(),
+                    args_str.join(", ")
+                )
+            }
+            IrOp::PerformanceNow(d) => write!(f, "{} = performance.now", d),
             IrOp::LoadLocal(d, slot) => write!(f, "{} = load.local ${}", d, slot),
             IrOp::StoreLocal(slot, v) => write!(f, "store.local ${}, {}", slot, v),
             IrOp::LoadGlobal(d, name) => write!(f, "{} = load.global @{}", d, name),
@@ -1192,6 +1252,9 @@ impl fmt::Display for IrOp {
             IrOp::GetElement(d, obj, key) => write!(f, "{} = get.elem {}, [{}]", d, obj, key),
             IrOp::SetElement(obj, key, val) => write!(f, "set.elem {}, [{}], {}", obj, key, val),
             IrOp::NewArray(d) => write!(f, "{} = new.array", d),
+            IrOp::NewTypedArray(d, kind, src) => {
+                write!(f, "{} = new.typed_array {}, {}", d, kind.short_name(), src)
+            }
             IrOp::ArrayLen(d, arr) => write!(f, "{} = array.len {}", d, arr),
             IrOp::ArrayPush(arr, val) => write!(f, "array.push {}, {}", arr, val),
             IrOp::Call(d, func, args) => {
@@ -6,7 +6,7 @@
 //! - Common Subexpression Elimination (CSE)
 //! - Copy Propagation
 
-use crate::ir::{IrFunction, IrModule, IrOp, Literal, Terminator, ValueId};
+use crate::ir::{IrFunction, IrModule, IrOp, Literal, MathFn, Terminator, ValueId};
 use std::collections::{HashMap, HashSet};
 
 // ============================================================================
@@ -123,6 +123,28 @@ pub fn constant_folding(func: &mut IrFunction) {
     }
 }
 
+/// Fold a call of a pure Math function whose arguments are all numbers.
+fn fold_math(
+    dst: ValueId,
+    func: MathFn,
+    args: &[ValueId],
+    constants: &mut HashMap<ValueId, Literal>,
+) -> Option<IrOp> {
+    if !func.is_pure() {
+        return None;
+    }
+    let values = args
+        .iter()
+        .map(|arg| match constants.get(arg) {
+            Some(Literal::Number(n)) => Some(*n),
+            _ => None,
+        })
+        .collect::<Option<Vec<f64>>>()?;
+    let result = func.apply(&values);
+    constants.insert(dst, Literal::Number(result));
+    Some(IrOp::Const(dst, Literal::Number(result)))
+}
+
 /// Attempt to fold a single operation.
 fn fold_op(
     op: IrOp,
@@ -197,6 +219,14 @@ fn fold_op(
             IrOp::NegNum(dst, a)
         }
 
+        // Pure Math functions of constant numbers
+        IrOp::MathAny(dst, func, args) => {
+            fold_math(dst, func, &args, constants).unwrap_or(IrOp::MathAny(dst, func, args))
+        }
+        IrOp::MathNum(dst, func, args) => {
+            fold_math(dst, func, &args, constants).unwrap_or(IrOp::MathNum(dst, func, args))
+        }
+
         // Comparison operations
         IrOp::Lt(dst, a, b) => {
             if let (Some(Literal::Number(va)), Some(Literal::Number(vb))) =
@@ -515,6 +545,10 @@ fn replace_uses_in_op(op: &mut IrOp, copies: &HashMap<ValueId, ValueId>) {
             resolve(val);
         }
 
+        IrOp::NewTypedArray(_, _, src) => {
+            resolve(src);
+        }
+
         IrOp::Call(_, func_val, args) => {
             resolve(func_val);
             for arg in args {
@@ -546,7 +580,7 @@ fn replace_uses_in_op(op: &mut IrOp, copies: &HashMap<ValueId, ValueId>) {
             resolve(b);
         }
 
-        IrOp::CallMono(_, _, args) => {
+        IrOp::CallMono(_, _, args) | IrOp::MathAny(_, _, args) | IrOp::MathNum(_, _, args) => {
             for arg in args {
                 resolve(arg);
             }
@@ -558,6 +592,7 @@ fn replace_uses_in_op(op: &mut IrOp, copies: &HashMap<ValueId, ValueId>) {
         | IrOp::LoadGlobal(_, _)
         | IrOp::NewObject(_)
         | IrOp::NewArray(_)
+        | IrOp::PerformanceNow(_)
         | IrOp::LoadThis(_)
         | IrOp::StructNew(_, _) => {}
     }
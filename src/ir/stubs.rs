@@ -7,7 +7,7 @@
 //!
 //! This mapping is used by the Cranelift/LLVM backends to generate native code.
 
-use crate::ir::{IrOp, IrType};
+use crate::ir::{IrOp, IrType, MathFn};
 
 /// How an IR operation should be compiled.
 #[derive(Debug, Clone)]
@@ -37,6 +37,18 @@ pub enum InlineOp {
     FRem,
     /// Floating-point negate.
     FNeg,
+    /// Floating-point absolute value.
+    FAbs,
+    /// Floating-point square root.
+    FSqrt,
+    /// Round towards negative infinity.
+    FFloor,
+    /// Round towards positive infinity.
+    FCeil,
+    /// Round towards zero.
+    FTrunc,
+    /// Round to the nearest single-precision value.
+    FRound32,
     /// Floating-point compare less than.
     FCmpLt,
     /// Floating-point compare less than or equal.
@@ -117,11 +129,14 @@ impl StubCall {
 
 pub mod stubs {
     use super::StubCall;
+    use crate::ir::MathFn;
 
     // Allocation stubs
     pub const ALLOC_OBJECT: StubCall = StubCall::new("ot_alloc_object", 0).with_side_effects();
     pub const ALLOC_ARRAY: StubCall = StubCall::new("ot_alloc_array", 1).with_side_effects();
     pub const ALLOC_STRING: StubCall = StubCall::new("ot_alloc_string", 2).with_side_effects();
+    pub const NEW_TYPED_ARRAY: StubCall =
+        StubCall::new("ot_new_typed_array", 2).with_side_effects();
 
     // Property access stubs
     pub const GET_PROP: StubCall = StubCall::new("ot_get_prop", 3);
@@ -154,6 +169,33 @@ pub mod stubs {
 
     // Console/IO stubs
     pub const CONSOLE_LOG: StubCall = StubCall::new("ot_console_log", 1).with_side_effects();
+
+    // Time stubs
+    pub const PERFORMANCE_NOW: StubCall =
+        StubCall::new("ot_performance_now", 0).with_side_effects();
+
+    // Math stubs (`ot_math_*`)
+    pub fn math(func: MathFn) -> StubCall {
+        let stub = StubCall::new(func.stub_name(), func.arity());
+        if func.is_pure() {
+            stub
+        } else {
+            stub.with_side_effects()
+        }
+    }
+}
+
+/// The instruction for a Math function of one number, if there is one.
+fn math_inline_op(func: MathFn) -> Option<InlineOp> {
+    match func {
+        MathFn::Abs => Some(InlineOp::FAbs),
+        MathFn::Sqrt => Some(InlineOp::FSqrt),
+        MathFn::Floor => Some(InlineOp::FFloor),
+        MathFn::Ceil => Some(InlineOp::FCeil),
+        MathFn::Trunc => Some(InlineOp::FTrunc),
+        MathFn::Fround => Some(InlineOp::FRound32),
+        _ => None,
+    }
 }
 
 // ============================================================================
@@ -204,6 +246,14 @@ pub fn compile_strategy(op: &IrOp) -> CompileStrategy {
         IrOp::ShrU(_, _, _) => CompileStrategy::Inline(InlineOp::ShrU),
         IrOp::Pow(_, _, _) => CompileStrategy::StubCall(stubs::POW),
 
+        // Math functions - FP instructions for numbers where one exists
+        IrOp::MathNum(_, func, _) => match math_inline_op(*func) {
+            Some(inline) => CompileStrategy::Inline(inline),
+            None => CompileStrategy::StubCall(stubs::math(*func)),
+        },
+        IrOp::MathAny(_, func, _) => CompileStrategy::StubCall(stubs::math(*func)),
+        IrOp::PerformanceNow(_) => CompileStrategy::StubCall(stubs::PERFORMANCE_NOW),
+
         // Local variable access - inline stack operations
         IrOp::LoadLocal(_, _) => CompileStrategy::Inline(InlineOp::LoadLocal),
         IrOp::StoreLocal(_, _) => CompileStrategy::Inline(InlineOp::StoreLocal),
@@ -216,11 +266,14 @@ pub fn compile_strategy(op: &IrOp) -> CompileStrategy {
         IrOp::NewObject(_) => CompileStrategy::StubCall(stubs::ALLOC_OBJECT),
         IrOp::GetProp(_, _, _) => CompileStrategy::StubCall(stubs::GET_PROP),
         IrOp::SetProp(_, _, _) => CompileStrategy::StubCall(stubs::SET_PROP),
+        // Backends load and store typed array elements inline when the
+        // array's type is known
         IrOp::GetElement(_, _, _) => CompileStrategy::StubCall(stubs::GET_ELEMENT),
         IrOp::SetElement(_, _, _) => CompileStrategy::StubCall(stubs::SET_ELEMENT),
 
         // Array operations
         IrOp::NewArray(_) => CompileStrategy::StubCall(stubs::ALLOC_ARRAY),
+        IrOp::NewTypedArray(_, _, _) => CompileStrategy::StubCall(stubs::NEW_TYPED_ARRAY),
         IrOp::ArrayLen(_, _) => CompileStrategy::StubCall(stubs::GET_PROP), // .length property
         IrOp::ArrayPush(_, _) => CompileStrategy::StubCall(stubs::CALL),    // .push method
 
@@ -337,6 +390,31 @@ mod tests {
         assert!(!stubs::ADD_ANY.may_trap);
     }
 
+    #[test]
+    fn test_math_strategy() {
+        let x = ValueId(0);
+        let d = ValueId(1);
+
+        assert!(matches!(
+            compile_strategy(&IrOp::MathNum(d, MathFn::Floor, vec![x])),
+            CompileStrategy::Inline(InlineOp::FFloor)
+        ));
+        assert!(matches!(
+            compile_strategy(&IrOp::MathNum(d, MathFn::Sqrt, vec![x])),
+            CompileStrategy::Inline(InlineOp::FSqrt)
+        ));
+        // Unknown argument types go through the stub, which converts them
+        let stub = get_stub(&IrOp::MathAny(d, MathFn::Floor, vec![x])).unwrap();
+        assert_eq!((stub.name, stub.arg_count), ("ot_math_floor", 1));
+        let stub = get_stub(&IrOp::MathNum(d, MathFn::Max, vec![x, x, x])).unwrap();
+        assert_eq!((stub.name, stub.arg_count), ("ot_math_max", 2));
+        assert!(
+            get_stub(&IrOp::MathNum(d, MathFn::Random, vec![]))
+                .unwrap()
+                .has_side_effects
+        );
+    }
+
     #[test]
     fn test_const_is_inline() {
         let d = ValueId(0);
@@ -7,7 +7,7 @@
 //!   Before: v3 = add.any v1, v2  (where v1: num, v2: num)
 //!   After:  v3 = add.num v1, v2
 
-use crate::ir::{BlockId, IrFunction, IrModule, IrOp, IrType, ValueId};
+use crate::ir::{BlockId, ElementKind, IrFunction, IrModule, IrOp, IrType, ValueId};
 use std::collections::{HashMap, HashSet, VecDeque};
 
 /// Type inference context for a function.
@@ -19,6 +19,8 @@ pub struct TypeChecker<'a> {
     in_worklist: HashSet<BlockId>,
     /// Type information for each value.
     types: HashMap<ValueId, IrType>,
+    /// Types of local slots that only ever hold one type.
+    local_types: HashMap<u32, IrType>,
     /// Whether any changes were made in the current iteration.
     changed: bool,
 }
@@ -32,22 +34,30 @@ impl<'a> TypeChecker<'a> {
         for (&val, ty) in &func.value_types {
             types.insert(val, ty.clone());
         }
+        let local_types = typed_array_locals(func);
 
         Self {
             func,
             worklist: VecDeque::new(),
             in_worklist: HashSet::new(),
             types,
+            local_types,
             changed: false,
         }
     }
 
     /// Run type inference on the function.
     pub fn infer(&mut self) {
-        // Initialize worklist with entry block
+        // Visit every block once, entry first; later visits follow changes
         let entry = self.func.entry_block();
-        self.worklist.push_back(entry);
-        self.in_worklist.insert(entry);
+        let blocks = std::iter::once(entry)
+            .chain((0..self.func.blocks.len() as u32).map(BlockId))
+            .collect::<Vec<_>>();
+        for block in blocks {
+            if self.in_worklist.insert(block) {
+                self.worklist.push_back(block);
+            }
+        }
 
         // Process blocks until fixpoint
         while let Some(block_id) = self.worklist.pop_front() {
@@ -186,9 +196,10 @@ impl<'a> TypeChecker<'a> {
                 self.set_type(*dst, result_ty);
             }
 
-            // Local loads get Any (unless we track local types)
-            IrOp::LoadLocal(dst, _) => {
-                self.set_type(*dst, IrType::Any);
+            // Local loads get Any, except from slots only holding typed arrays
+            IrOp::LoadLocal(dst, slot) => {
+                let ty = self.local_types.get(slot).cloned().unwrap_or(IrType::Any);
+                self.set_type(*dst, ty);
             }
 
             // Global loads get Any
@@ -206,8 +217,13 @@ impl<'a> TypeChecker<'a> {
                 self.set_type(*dst, IrType::Any);
             }
 
-            IrOp::GetElement(dst, _, _) => {
-                self.set_type(*dst, IrType::Any);
+            // Typed array elements read as numbers
+            IrOp::GetElement(dst, obj, _) => {
+                let ty = match self.get_type(*obj).scalar_element() {
+                    Some(_) => IrType::Number,
+                    None => IrType::Any,
+                };
+                self.set_type(*dst, ty);
             }
 
             // Array creation
@@ -215,6 +231,10 @@ impl<'a> TypeChecker<'a> {
                 self.set_type(*dst, IrType::Array);
             }
 
+            IrOp::NewTypedArray(dst, kind, _) => {
+                self.set_type(*dst, IrType::typed_array(*kind));
+            }
+
             // Array length is a number
             IrOp::ArrayLen(dst, _) => {
                 self.set_type(*dst, IrType::Number);
@@ -338,6 +358,11 @@ impl<'a> TypeChecker<'a> {
                 self.set_type(*dst, IrType::Number);
             }
 
+            // Every Math function returns a number, as does the clock
+            IrOp::MathAny(dst, _, _) | IrOp::MathNum(dst, _, _) | IrOp::PerformanceNow(dst) => {
+                self.set_type(*dst, IrType::Number);
+            }
+
             // TypeOf always produces a string
             IrOp::TypeOf(dst, _) => {
                 self.set_type(*dst, IrType::String);
@@ -351,6 +376,33 @@ impl<'a> TypeChecker<'a> {
     }
 }
 
+/// Local slots whose every store is a new typed array of the same kind.
+/// Loads from them are typed arrays whatever the control flow.
+fn typed_array_locals(func: &IrFunction) -> HashMap<u32, IrType> {
+    let ops = || func.blocks.iter().flat_map(|block| &block.ops);
+    let created: HashMap<ValueId, ElementKind> = ops()
+        .filter_map(|op| match op {
+            IrOp::NewTypedArray(dst, kind, _) => Some((*dst, *kind)),
+            _ => None,
+        })
+        .collect();
+
+    let mut slots: HashMap<u32, Option<ElementKind>> = HashMap::new();
+    for op in ops() {
+        if let IrOp::StoreLocal(slot, val) = op {
+            let kind = created.get(val).copied();
+            let entry = slots.entry(*slot).or_insert(kind);
+            if *entry != kind {
+                *entry = None;
+            }
+        }
+    }
+    slots
+        .into_iter()
+        .filter_map(|(slot, kind)| Some((slot, IrType::typed_array(kind?))))
+        .collect()
+}
+
 /// Compute the meet (least upper bound) of two types.
 fn type_meet(a: IrType, b: IrType) -> IrType {
     if a == b {
@@ -430,6 +482,14 @@ fn specialize_op(op: IrOp, types: &HashMap<ValueId, IrType>) -> IrOp {
             }
         }
 
+        IrOp::MathAny(dst, func, args) => {
+            if args.iter().all(|&arg| get_type(arg) == IrType::Number) {
+                IrOp::MathNum(dst, func, args)
+            } else {
+                IrOp::MathAny(dst, func, args)
+            }
+        }
+
         // All other operations pass through unchanged
         other => other,
     }
@@ -452,7 +512,7 @@ pub fn typecheck_module(module: &mut IrModule) {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::ir::{Literal, Terminator};
+    use crate::ir::{Literal, MathFn, Terminator};
 
     #[test]
     fn test_type_inference_numeric() {
@@ -510,6 +570,66 @@ mod tests {
         assert!(has_add_any, "String concat should remain AddAny");
     }
 
+    #[test]
+    fn test_math_specialized_for_numbers() {
+        let mut func = IrFunction::new("test".to_string());
+        let entry = func.alloc_block();
+
+        let x = func.alloc_value(IrType::Number);
+        let s = func.alloc_value(IrType::String);
+        let floor = func.alloc_value(IrType::Any);
+        let max = func.alloc_value(IrType::Any);
+
+        {
+            let block = func.block_mut(entry);
+            block.push(IrOp::Const(x, Literal::Number(2.5)));
+            block.push(IrOp::Const(s, Literal::String("3".to_string())));
+            block.push(IrOp::MathAny(floor, MathFn::Floor, vec![x]));
+            block.push(IrOp::MathAny(max, MathFn::Max, vec![floor, s]));
+            block.terminate(Terminator::Return(Some(max)));
+        }
+
+        typecheck_function(&mut func);
+
+        assert_eq!(func.value_types.get(&max), Some(&IrType::Number));
+        let ops = &func.blocks[entry.0 as usize].ops;
+        assert!(matches!(ops[2], IrOp::MathNum(_, MathFn::Floor, _)));
+        assert!(matches!(ops[3], IrOp::MathAny(_, MathFn::Max, _)));
+    }
+
+    #[test]
+    fn test_typed_array_locals() {
+        let mut func = IrFunction::new("test".to_string());
+        let entry = func.alloc_block();
+
+        let len = func.alloc_value(IrType::Number);
+        let arr = func.alloc_value(IrType::Any);
+        let loaded = func.alloc_value(IrType::Any);
+        let elem = func.alloc_value(IrType::Any);
+        let other = func.alloc_value(IrType::Any);
+
+        {
+            let block = func.block_mut(entry);
+            block.push(IrOp::Const(len, Literal::Number(4.0)));
+            block.push(IrOp::NewTypedArray(arr, ElementKind::Int32, len));
+            block.push(IrOp::StoreLocal(0, arr));
+            block.push(IrOp::LoadLocal(loaded, 0));
+            block.push(IrOp::GetElement(elem, loaded, len));
+            // Slot 1 also holds a plain number, so it stays untyped
+            block.push(IrOp::StoreLocal(1, arr));
+            block.push(IrOp::StoreLocal(1, len));
+            block.push(IrOp::LoadLocal(other, 1));
+            block.terminate(Terminator::Return(Some(elem)));
+        }
+
+        typecheck_function(&mut func);
+
+        let int32s = IrType::typed_array(ElementKind::Int32);
+        assert_eq!(func.value_types.get(&loaded), Some(&int32s));
+        assert_eq!(func.value_types.get(&elem), Some(&IrType::Number));
+        assert_eq!(func.value_types.get(&other), Some(&IrType::Any));
+    }
+
     #[test]
     fn test_type_meet() {
         assert_eq!(type_meet(IrType::Number, IrType::Number), IrType::Number);
@@ -39,6 +39,8 @@ pub mod ir;
 #[cfg(feature = "vm_interop")]
 pub mod loader;
 #[cfg(feature = "vm_interop")]
+pub mod module;
+#[cfg(feature = "vm_interop")]
 pub mod stdlib;
 #[cfg(feature = "vm_interop")]
 pub mod types;
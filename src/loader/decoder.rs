@@ -1,4 +1,8 @@
-//! Bytecode decoder for loading binary files produced by bootstrap/emitter.ot
+//! Bytecode decoder for loading binary files
+//!
+//! Version 1 files are produced by bootstrap/emitter.ot; version 2 files (with a
+//! string table and constant pool) by `BytecodeEncoder`. See encoder.rs for the
+//! version 2 layout.
 //!
 //! The binary format uses:
 //! - u8 for opcodes and small values
@@ -7,14 +11,20 @@
 //! - Little-endian f64 for floating point numbers
 //! - Varint-prefixed UTF-8 for strings
 
+use super::encoder::{
+    CONST_FALSE, CONST_FUNCTION, CONST_NULL, CONST_NUMBER, CONST_STRING, CONST_TRUE,
+    CONST_UNDEFINED, CONST_UTF16,
+};
 use crate::vm::opcodes::OpCode;
-use crate::vm::value::JsValue;
+use crate::vm::value::{JsString, JsValue};
 use std::collections::HashMap;
 
 /// Magic bytes for TSCL bytecode files
 pub const MAGIC: &[u8; 4] = b"TSCL";
 /// Current bytecode format version
-pub const VERSION: u8 = 1;
+pub const VERSION: u8 = 2;
+/// Version written by the self-hosted emitter (byte-offset addresses, inline operands)
+pub const LEGACY_VERSION: u8 = 1;
 
 /// Errors that can occur during bytecode loading
 #[derive(Debug)]
@@ -35,6 +45,24 @@ pub enum LoaderError {
     VarintOverflow,
     /// Address not found in mapping (internal error)
     AddressNotFound(u32),
+    /// String table index out of range
+    InvalidStringIndex(u64),
+    /// Constant pool index out of range
+    InvalidConstantIndex(u64),
+    /// Value that only exists at runtime (heap pointer, native function, ...)
+    UnencodableValue(String),
+    /// Jump, handler or closure address outside the program
+    InvalidAddress { at: usize, target: usize },
+    /// Instruction pops more values than the stack holds
+    StackUnderflow { at: usize, depth: usize },
+    /// Local slot out of range, or read without ever being written
+    InvalidLocal { at: usize, slot: u32 },
+    /// Control flow reaches an instruction with two different stack depths
+    StackMismatch {
+        at: usize,
+        expected: usize,
+        found: usize,
+    },
 }
 
 impl std::fmt::Display for LoaderError {
@@ -50,6 +78,31 @@ impl std::fmt::Display for LoaderError {
             LoaderError::AddressNotFound(addr) => {
                 write!(f, "Address {} not found in mapping", addr)
             }
+            LoaderError::InvalidStringIndex(idx) => write!(f, "Invalid string index: {}", idx),
+            LoaderError::InvalidConstantIndex(idx) => {
+                write!(f, "Invalid constant index: {}", idx)
+            }
+            LoaderError::UnencodableValue(value) => {
+                write!(f, "Cannot encode runtime value: {}", value)
+            }
+            LoaderError::InvalidAddress { at, target } => {
+                write!(f, "Instruction {} refers to invalid address {}", at, target)
+            }
+            LoaderError::StackUnderflow { at, depth } => {
+                write!(f, "Stack underflow at instruction {} (depth {})", at, depth)
+            }
+            LoaderError::InvalidLocal { at, slot } => {
+                write!(f, "Invalid local slot {} at instruction {}", slot, at)
+            }
+            LoaderError::StackMismatch {
+                at,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Inconsistent stack depth at instruction {}: {} vs {}",
+                at, expected, found
+            ),
         }
     }
 }
@@ -60,12 +113,22 @@ impl std::error::Error for LoaderError {}
 pub struct BytecodeDecoder<'a> {
     bytes: &'a [u8],
     pos: usize,
+    /// Version from the header (legacy headerless files are version 1)
+    version: u8,
+    strings: Vec<String>,
+    constants: Vec<JsValue>,
 }
 
 impl<'a> BytecodeDecoder<'a> {
     /// Create a new decoder for the given bytes
     pub fn new(bytes: &'a [u8]) -> Self {
-        Self { bytes, pos: 0 }
+        Self {
+            bytes,
+            pos: 0,
+            version: LEGACY_VERSION,
+            strings: Vec::new(),
+            constants: Vec::new(),
+        }
     }
 
     /// Reset position to start (useful for legacy files without header)
@@ -96,12 +159,13 @@ impl<'a> BytecodeDecoder<'a> {
         }
 
         let version = self.bytes[4];
-        if version != VERSION {
+        if version != VERSION && version != LEGACY_VERSION {
             return Err(LoaderError::UnsupportedVersion(version));
         }
 
         // Skip header (8 bytes: magic + version + reserved)
         self.pos = 8;
+        self.version = version;
         Ok(version)
     }
 
@@ -194,6 +258,10 @@ impl<'a> BytecodeDecoder<'a> {
             }
         }
 
+        if self.version == VERSION {
+            return self.decode_all_v2();
+        }
+
         // First pass: decode all instructions, record byte offsets
         // Note: We use absolute file positions (including header) because that's
         // what the emitter writes when computing jump addresses with currentOffset()
@@ -260,7 +328,187 @@ impl<'a> BytecodeDecoder<'a> {
         Ok(instructions)
     }
 
-    /// Decode a single instruction
+    /// Decode a version 2 body: string table, constant pool, then instructions.
+    /// Addresses are already instruction indices.
+    fn decode_all_v2(&mut self) -> Result<Vec<OpCode>, LoaderError> {
+        let string_count = self.read_varint()?;
+        self.strings = (0..string_count)
+            .map(|_| self.read_string())
+            .collect::<Result<_, _>>()?;
+
+        let constant_count = self.read_varint()?;
+        self.constants = Vec::new();
+        for _ in 0..constant_count {
+            let tag = self.read_u8()?;
+            let value = match tag {
+                CONST_NUMBER => JsValue::Number(self.read_f64_le()?),
+                CONST_STRING => JsValue::String(self.table_string()?.into()),
+                CONST_UTF16 => {
+                    let count = self.read_varint()?;
+                    let units = (0..count)
+                        .map(|_| Ok(self.read_varint()? as u16))
+                        .collect::<Result<Vec<_>, LoaderError>>()?;
+                    JsValue::String(JsString::from_units(&units))
+                }
+                CONST_TRUE => JsValue::Boolean(true),
+                CONST_FALSE => JsValue::Boolean(false),
+                CONST_NULL => JsValue::Null,
+                CONST_UNDEFINED => JsValue::Undefined,
+                CONST_FUNCTION => JsValue::Function {
+                    address: self.read_varint()? as usize,
+                    env: None,
+                },
+                _ => return Err(LoaderError::InvalidTypeTag(tag)),
+            };
+            self.constants.push(value);
+        }
+
+        let instruction_count = self.read_varint()?;
+        let mut instructions = Vec::new();
+        for _ in 0..instruction_count {
+            instructions.push(self.decode_instruction_v2()?);
+        }
+        Ok(instructions)
+    }
+
+    /// Read a string table reference
+    fn table_string(&mut self) -> Result<String, LoaderError> {
+        let idx = self.read_varint()?;
+        self.strings
+            .get(idx as usize)
+            .cloned()
+            .ok_or(LoaderError::InvalidStringIndex(idx))
+    }
+
+    fn read_usize(&mut self) -> Result<usize, LoaderError> {
+        Ok(self.read_varint()? as usize)
+    }
+
+    /// Decode a single version 2 instruction (numbering matches `BytecodeEncoder`)
+    fn decode_instruction_v2(&mut self) -> Result<OpCode, LoaderError> {
+        let opcode = self.read_u8()?;
+
+        let op = match opcode {
+            0 => OpCode::LoadThis,
+            1 => {
+                let idx = self.read_varint()?;
+                let value = self
+                    .constants
+                    .get(idx as usize)
+                    .cloned()
+                    .ok_or(LoaderError::InvalidConstantIndex(idx))?;
+                OpCode::Push(value)
+            }
+            2 => OpCode::Add,
+            3 => OpCode::Sub,
+            4 => OpCode::Mul,
+            5 => OpCode::Div,
+            6 => OpCode::Print,
+            7 => OpCode::Pop,
+            8 => OpCode::Store(self.table_string()?),
+            9 => OpCode::Load(self.table_string()?),
+            10 => OpCode::Drop(self.table_string()?),
+            11 => OpCode::Call(self.read_usize()?),
+            12 => OpCode::Return,
+            13 => OpCode::Jump(self.read_usize()?),
+            14 => OpCode::NewObject,
+            15 => OpCode::SetProp(self.table_string()?),
+            16 => OpCode::GetProp(self.table_string()?),
+            17 => OpCode::Dup,
+            18 => OpCode::Eq,
+            19 => OpCode::EqEq,
+            20 => OpCode::Ne,
+            21 => OpCode::NeEq,
+            22 => OpCode::Lt,
+            23 => OpCode::LtEq,
+            24 => OpCode::Gt,
+            25 => OpCode::GtEq,
+            26 => OpCode::Mod,
+            27 => OpCode::And,
+            28 => OpCode::Or,
+            29 => OpCode::Not,
+            30 => OpCode::Neg,
+            31 => OpCode::NewArray(self.read_usize()?),
+            32 => OpCode::StoreElement,
+            33 => OpCode::LoadElement,
+            34 => OpCode::JumpIfFalse(self.read_usize()?),
+            37 => OpCode::Pow,
+            46 => OpCode::ShiftLeft,
+            47 => OpCode::ShiftRight,
+            48 => OpCode::ShiftRightUnsigned,
+            49 => OpCode::BitAnd,
+            50 => OpCode::Xor,
+            51 => OpCode::BitOr,
+            54 => {
+                let name = self.table_string()?;
+                OpCode::CallMethod(name, self.read_usize()?)
+            }
+            55 => OpCode::Require,
+            56 => OpCode::MakeClosure(self.read_usize()?),
+            57 => OpCode::Construct(self.read_usize()?),
+            58 => OpCode::StoreLocal(self.read_varint()? as u32),
+            59 => OpCode::LoadLocal(self.read_varint()? as u32),
+            60 => OpCode::Swap,
+            61 => OpCode::TypeOf,
+            62 => OpCode::Throw,
+            63 => OpCode::SetupTry {
+                catch_addr: self.read_usize()?,
+                finally_addr: self.read_usize()?,
+            },
+            64 => OpCode::PopTry,
+            65 => OpCode::GetPropComputed,
+            66 => OpCode::SetPropComputed,
+            67 => OpCode::ArrayPush,
+            68 => OpCode::ArraySpread,
+            69 => OpCode::ObjectSpread,
+            70 => OpCode::Let(self.table_string()?),
+            71 => OpCode::NewObjectWithProto,
+            72 => OpCode::Swap3,
+            73 => OpCode::Delete(self.table_string()?),
+            74 => OpCode::EnterFinally(self.read_u8()? != 0),
+            75 => OpCode::SetProto,
+            76 => OpCode::LoadSuper,
+            77 => OpCode::CallSuper(self.read_usize()?),
+            78 => OpCode::GetSuperProp(self.table_string()?),
+            79 => OpCode::GetPrivateProp(self.read_usize()?),
+            80 => OpCode::SetPrivateProp(self.read_usize()?),
+            81 => OpCode::InstanceOf,
+            82 => OpCode::NewTarget,
+            83 => OpCode::ApplyDecorator,
+            84 => OpCode::ImportAsync(self.table_string()?),
+            85 => OpCode::Await,
+            86 => OpCode::GetExport {
+                name: self.table_string()?,
+                is_default: self.read_u8()? != 0,
+            },
+            87 => {
+                let message = self.table_string()?;
+                let specifier = self.table_string()?;
+                let importer = self.table_string()?;
+                let chain_len = self.read_varint()?;
+                let dependency_chain = (0..chain_len)
+                    .map(|_| self.table_string())
+                    .collect::<Result<_, _>>()?;
+                OpCode::ModuleResolutionError {
+                    message,
+                    specifier,
+                    importer,
+                    dependency_chain,
+                }
+            }
+            88 => OpCode::Freeze,
+            89 => OpCode::ImportDynamic,
+            90 => OpCode::ImportMeta,
+            91 => OpCode::ToIterable,
+            92 => OpCode::EnterAsync,
+            93 => OpCode::LoadOrUndefined(self.table_string()?),
+            255 => OpCode::Halt,
+            _ => return Err(LoaderError::InvalidOpcode(opcode)),
+        };
+        Ok(op)
+    }
+
+    /// Decode a single version 1 instruction
     fn decode_instruction(&mut self) -> Result<OpCode, LoaderError> {
         let opcode = self.read_u8()?;
 
@@ -273,7 +521,7 @@ impl<'a> BytecodeDecoder<'a> {
                 let type_tag = self.read_u8()?;
                 let value = match type_tag {
                     0 => JsValue::Number(self.read_f64_le()?),
-                    1 => JsValue::String(self.read_string()?),
+                    1 => JsValue::String(self.read_string()?.into()),
                     2 => JsValue::Boolean(true),
                     3 => JsValue::Boolean(false),
                     4 => JsValue::Null,
@@ -590,6 +838,29 @@ mod tests {
         assert_eq!(decoder.position(), 8);
     }
 
+    #[test]
+    fn test_legacy_header_still_decodes() {
+        let mut bytes = b"TSCL".to_vec();
+        bytes.push(LEGACY_VERSION);
+        bytes.extend_from_slice(&[0, 0, 0]);
+        bytes.extend_from_slice(&[13, 13, 0, 0, 0]); // JUMP to byte 13 (the HALT)
+        bytes.push(255); // HALT
+
+        let program = BytecodeDecoder::new(&bytes).decode_all().unwrap();
+        assert!(matches!(program[0], OpCode::Jump(1)));
+        assert!(matches!(program[1], OpCode::Halt));
+    }
+
+    #[test]
+    fn test_unsupported_version() {
+        let mut bytes = b"TSCL".to_vec();
+        bytes.extend_from_slice(&[9, 0, 0, 0]);
+        assert!(matches!(
+            BytecodeDecoder::new(&bytes).validate_header(),
+            Err(LoaderError::UnsupportedVersion(9))
+        ));
+    }
+
     #[test]
     fn test_invalid_magic() {
         let bytes = b"NOTV1234";
@@ -0,0 +1,500 @@
+//! Bytecode encoder producing version 2 `.otb` files
+//!
+//! Version 2 layout (all integers LEB128 varints unless noted):
+//! - Header: `TSCL`, version byte, 3 reserved bytes
+//! - String table: count, then varint-prefixed UTF-8 strings
+//! - Constant pool: count, then tagged values (strings refer to the string
+//!   table, except those with a lone surrogate, which UTF-8 can't hold: they
+//!   are a count of UTF-16 code units, then the units)
+//! - Code: instruction count, then instructions
+//!
+//! Unlike version 1, jump and function addresses are instruction indices, so
+//! no byte-offset fixup is needed when loading. `Push` operands are constant
+//! pool indices and identifiers are string table indices.
+
+use super::decoder::{LoaderError, MAGIC, VERSION};
+use crate::vm::opcodes::OpCode;
+use crate::vm::value::JsValue;
+use std::collections::HashMap;
+
+// Constant pool tags
+pub(crate) const CONST_NUMBER: u8 = 0;
+pub(crate) const CONST_STRING: u8 = 1;
+pub(crate) const CONST_TRUE: u8 = 2;
+pub(crate) const CONST_FALSE: u8 = 3;
+pub(crate) const CONST_NULL: u8 = 4;
+pub(crate) const CONST_UNDEFINED: u8 = 5;
+pub(crate) const CONST_FUNCTION: u8 = 6;
+pub(crate) const CONST_UTF16: u8 = 7;
+
+/// Constant pool entry, keyed so that equal constants share a slot
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Constant {
+    /// Stored as raw bits so NaN and -0.0 round-trip exactly
+    Number(u64),
+    String(u32),
+    Utf16(Vec<u16>),
+    Boolean(bool),
+    Null,
+    Undefined,
+    Function(usize),
+}
+
+/// Serializes `OpCode` programs into the version 2 binary format
+#[derive(Default)]
+pub struct BytecodeEncoder {
+    strings: Vec<String>,
+    string_ids: HashMap<String, u32>,
+    constants: Vec<Constant>,
+    constant_ids: HashMap<Constant, u32>,
+    code: Vec<u8>,
+}
+
+impl BytecodeEncoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Encode a complete program, including header and tables
+    pub fn encode(program: &[OpCode]) -> Result<Vec<u8>, LoaderError> {
+        let mut encoder = Self::new();
+        for op in program {
+            encoder.encode_instruction(op)?;
+        }
+        Ok(encoder.finish(program.len()))
+    }
+
+    fn finish(self, instruction_count: usize) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.code.len() + 64);
+        out.extend_from_slice(MAGIC);
+        out.push(VERSION);
+        out.extend_from_slice(&[0, 0, 0]);
+
+        write_varint(&mut out, self.strings.len() as u64);
+        for s in &self.strings {
+            write_varint(&mut out, s.len() as u64);
+            out.extend_from_slice(s.as_bytes());
+        }
+
+        write_varint(&mut out, self.constants.len() as u64);
+        for constant in &self.constants {
+            match constant {
+                Constant::Number(bits) => {
+                    out.push(CONST_NUMBER);
+                    out.extend_from_slice(&bits.to_le_bytes());
+                }
+                Constant::String(id) => {
+                    out.push(CONST_STRING);
+                    write_varint(&mut out, *id as u64);
+                }
+                Constant::Utf16(units) => {
+                    out.push(CONST_UTF16);
+                    write_varint(&mut out, units.len() as u64);
+                    for &unit in units {
+                        write_varint(&mut out, unit as u64);
+                    }
+                }
+                Constant::Boolean(true) => out.push(CONST_TRUE),
+                Constant::Boolean(false) => out.push(CONST_FALSE),
+                Constant::Null => out.push(CONST_NULL),
+                Constant::Undefined => out.push(CONST_UNDEFINED),
+                Constant::Function(address) => {
+                    out.push(CONST_FUNCTION);
+                    write_varint(&mut out, *address as u64);
+                }
+            }
+        }
+
+        write_varint(&mut out, instruction_count as u64);
+        out.extend_from_slice(&self.code);
+        out
+    }
+
+    fn string_id(&mut self, s: &str) -> u32 {
+        if let Some(&id) = self.string_ids.get(s) {
+            return id;
+        }
+        let id = self.strings.len() as u32;
+        self.strings.push(s.to_string());
+        self.string_ids.insert(s.to_string(), id);
+        id
+    }
+
+    fn constant_id(&mut self, value: &JsValue) -> Result<u32, LoaderError> {
+        let constant = match value {
+            JsValue::Number(n) => Constant::Number(n.to_bits()),
+            JsValue::String(s) if s.has_lone_surrogates() => Constant::Utf16(s.units().collect()),
+            JsValue::String(s) => Constant::String(self.string_id(s)),
+            JsValue::Boolean(b) => Constant::Boolean(*b),
+            JsValue::Null => Constant::Null,
+            JsValue::Undefined => Constant::Undefined,
+            JsValue::Function { address, env: None } => Constant::Function(*address),
+            // Heap pointers and native handles only exist at runtime
+            other => return Err(LoaderError::UnencodableValue(format!("{:?}", other))),
+        };
+        if let Some(&id) = self.constant_ids.get(&constant) {
+            return Ok(id);
+        }
+        let id = self.constants.len() as u32;
+        self.constants.push(constant.clone());
+        self.constant_ids.insert(constant, id);
+        Ok(id)
+    }
+
+    fn op(&mut self, tag: u8) {
+        self.code.push(tag);
+    }
+
+    fn varint(&mut self, value: u64) {
+        write_varint(&mut self.code, value);
+    }
+
+    fn string(&mut self, s: &str) {
+        let id = self.string_id(s);
+        self.varint(id as u64);
+    }
+
+    fn op_string(&mut self, tag: u8, s: &str) {
+        self.op(tag);
+        self.string(s);
+    }
+
+    fn op_varint(&mut self, tag: u8, value: usize) {
+        self.op(tag);
+        self.varint(value as u64);
+    }
+
+    /// Append one instruction. Opcode numbers match version 1 where the
+    /// instruction exists there; the rest start at 71.
+    pub fn encode_instruction(&mut self, op: &OpCode) -> Result<(), LoaderError> {
+        match op {
+            OpCode::LoadThis => self.op(0),
+            OpCode::Push(value) => {
+                let id = self.constant_id(value)?;
+                self.op_varint(1, id as usize);
+            }
+            OpCode::Add => self.op(2),
+            OpCode::Sub => self.op(3),
+            OpCode::Mul => self.op(4),
+            OpCode::Div => self.op(5),
+            OpCode::Print => self.op(6),
+            OpCode::Pop => self.op(7),
+            OpCode::Store(name) => self.op_string(8, name),
+            OpCode::Load(name) => self.op_string(9, name),
+            OpCode::Drop(name) => self.op_string(10, name),
+            OpCode::Call(argc) => self.op_varint(11, *argc),
+            OpCode::Return => self.op(12),
+            OpCode::Jump(addr) => self.op_varint(13, *addr),
+            OpCode::NewObject => self.op(14),
+            OpCode::SetProp(name) => self.op_string(15, name),
+            OpCode::GetProp(name) => self.op_string(16, name),
+            OpCode::Dup => self.op(17),
+            OpCode::Eq => self.op(18),
+            OpCode::EqEq => self.op(19),
+            OpCode::Ne => self.op(20),
+            OpCode::NeEq => self.op(21),
+            OpCode::Lt => self.op(22),
+            OpCode::LtEq => self.op(23),
+            OpCode::Gt => self.op(24),
+            OpCode::GtEq => self.op(25),
+            OpCode::Mod => self.op(26),
+            OpCode::And => self.op(27),
+            OpCode::Or => self.op(28),
+            OpCode::Not => self.op(29),
+            OpCode::Neg => self.op(30),
+            OpCode::NewArray(size) => self.op_varint(31, *size),
+            OpCode::StoreElement => self.op(32),
+            OpCode::LoadElement => self.op(33),
+            OpCode::JumpIfFalse(addr) => self.op_varint(34, *addr),
+            OpCode::Pow => self.op(37),
+            OpCode::ShiftLeft => self.op(46),
+            OpCode::ShiftRight => self.op(47),
+            OpCode::ShiftRightUnsigned => self.op(48),
+            OpCode::BitAnd => self.op(49),
+            OpCode::Xor => self.op(50),
+            OpCode::BitOr => self.op(51),
+            OpCode::CallMethod(name, argc) => {
+                self.op_string(54, name);
+                self.varint(*argc as u64);
+            }
+            OpCode::Require => self.op(55),
+            OpCode::MakeClosure(addr) => self.op_varint(56, *addr),
+            OpCode::Construct(argc) => self.op_varint(57, *argc),
+            OpCode::StoreLocal(slot) => self.op_varint(58, *slot as usize),
+            OpCode::LoadLocal(slot) => self.op_varint(59, *slot as usize),
+            OpCode::Swap => self.op(60),
+            OpCode::TypeOf => self.op(61),
+            OpCode::Throw => self.op(62),
+            OpCode::SetupTry {
+                catch_addr,
+                finally_addr,
+            } => {
+                self.op_varint(63, *catch_addr);
+                self.varint(*finally_addr as u64);
+            }
+            OpCode::PopTry => self.op(64),
+            OpCode::GetPropComputed => self.op(65),
+            OpCode::SetPropComputed => self.op(66),
+            OpCode::ArrayPush => self.op(67),
+            OpCode::ArraySpread => self.op(68),
+            OpCode::ObjectSpread => self.op(69),
+            OpCode::Let(name) => self.op_string(70, name),
+            OpCode::NewObjectWithProto => self.op(71),
+            OpCode::Swap3 => self.op(72),
+            OpCode::Delete(name) => self.op_string(73, name),
+            OpCode::EnterFinally(rethrow) => {
+                self.op(74);
+                self.code.push(*rethrow as u8);
+            }
+            OpCode::SetProto => self.op(75),
+            OpCode::LoadSuper => self.op(76),
+            OpCode::CallSuper(argc) => self.op_varint(77, *argc),
+            OpCode::GetSuperProp(name) => self.op_string(78, name),
+            OpCode::GetPrivateProp(index) => self.op_varint(79, *index),
+            OpCode::SetPrivateProp(index) => self.op_varint(80, *index),
+            OpCode::InstanceOf => self.op(81),
+            OpCode::NewTarget => self.op(82),
+            OpCode::ApplyDecorator => self.op(83),
+            OpCode::ImportAsync(specifier) => self.op_string(84, specifier),
+            OpCode::Await => self.op(85),
+            OpCode::GetExport { name, is_default } => {
+                self.op_string(86, name);
+                self.code.push(*is_default as u8);
+            }
+            OpCode::Freeze => self.op(88),
+            OpCode::ImportDynamic => self.op(89),
+            OpCode::ImportMeta => self.op(90),
+            OpCode::ToIterable => self.op(91),
+            OpCode::EnterAsync => self.op(92),
+            OpCode::LoadOrUndefined(name) => self.op_string(93, name),
+            OpCode::ModuleResolutionError {
+                message,
+                specifier,
+                importer,
+                dependency_chain,
+            } => {
+                self.op_string(87, message);
+                self.string(specifier);
+                self.string(importer);
+                self.varint(dependency_chain.len() as u64);
+                for entry in dependency_chain {
+                    self.string(entry);
+                }
+            }
+            OpCode::Halt => self.op(255),
+        }
+        Ok(())
+    }
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            break;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compiler::Compiler;
+    use crate::loader::BytecodeDecoder;
+    use crate::vm::value::JsString;
+
+    fn round_trip(program: &[OpCode]) -> Vec<OpCode> {
+        let bytes = BytecodeEncoder::encode(program).unwrap();
+        BytecodeDecoder::new(&bytes).decode_all().unwrap()
+    }
+
+    fn assert_round_trips(program: &[OpCode]) {
+        let decoded = round_trip(program);
+        assert_eq!(format!("{:?}", decoded), format!("{:?}", program));
+    }
+
+    /// One instance of every instruction variant
+    fn every_variant() -> Vec<OpCode> {
+        vec![
+            OpCode::LoadThis,
+            OpCode::Push(JsValue::Number(-0.0)),
+            OpCode::Push(JsValue::Number(f64::NAN)),
+            OpCode::Push(JsValue::String("héllo".into())),
+            OpCode::Push(JsValue::Boolean(true)),
+            OpCode::Push(JsValue::Boolean(false)),
+            OpCode::Push(JsValue::Null),
+            OpCode::Push(JsValue::Undefined),
+            OpCode::Push(JsValue::Function {
+                address: 3,
+                env: None,
+            }),
+            OpCode::Add,
+            OpCode::Sub,
+            OpCode::Print,
+            OpCode::Pop,
+            OpCode::Let("x".to_string()),
+            OpCode::Store("x".to_string()),
+            OpCode::Load("x".to_string()),
+            OpCode::Drop("x".to_string()),
+            OpCode::Call(300),
+            OpCode::Return,
+            OpCode::Jump(0),
+            OpCode::NewObject,
+            OpCode::NewObjectWithProto,
+            OpCode::SetProp("p".to_string()),
+            OpCode::GetProp("p".to_string()),
+            OpCode::SetPropComputed,
+            OpCode::GetPropComputed,
+            OpCode::Dup,
+            OpCode::Swap,
+            OpCode::Swap3,
+            OpCode::Eq,
+            OpCode::EqEq,
+            OpCode::Ne,
+            OpCode::NeEq,
+            OpCode::Lt,
+            OpCode::LtEq,
+            OpCode::Gt,
+            OpCode::GtEq,
+            OpCode::Mod,
+            OpCode::And,
+            OpCode::Or,
+            OpCode::Not,
+            OpCode::Neg,
+            OpCode::TypeOf,
+            OpCode::Delete("p".to_string()),
+            OpCode::NewArray(4),
+            OpCode::StoreElement,
+            OpCode::LoadElement,
+            OpCode::ArrayPush,
+            OpCode::ArraySpread,
+            OpCode::ObjectSpread,
+            OpCode::JumpIfFalse(1),
+            OpCode::CallMethod("log".to_string(), 2),
+            OpCode::Mul,
+            OpCode::Div,
+            OpCode::Require,
+            OpCode::MakeClosure(2),
+            OpCode::Construct(1),
+            OpCode::StoreLocal(70000),
+            OpCode::LoadLocal(70000),
+            OpCode::BitAnd,
+            OpCode::BitOr,
+            OpCode::Xor,
+            OpCode::ShiftLeft,
+            OpCode::ShiftRight,
+            OpCode::ShiftRightUnsigned,
+            OpCode::Pow,
+            OpCode::Throw,
+            OpCode::SetupTry {
+                catch_addr: 5,
+                finally_addr: 0,
+            },
+            OpCode::PopTry,
+            OpCode::EnterFinally(true),
+            OpCode::SetProto,
+            OpCode::LoadSuper,
+            OpCode::CallSuper(2),
+            OpCode::GetSuperProp("m".to_string()),
+            OpCode::GetPrivateProp(1),
+            OpCode::SetPrivateProp(1),
+            OpCode::InstanceOf,
+            OpCode::NewTarget,
+            OpCode::ApplyDecorator,
+            OpCode::Freeze,
+            OpCode::ImportAsync("./mod.ot".to_string()),
+            OpCode::ImportDynamic,
+            OpCode::ImportMeta,
+            OpCode::ToIterable,
+            OpCode::Await,
+            OpCode::EnterAsync,
+            OpCode::LoadOrUndefined("x".to_string()),
+            OpCode::GetExport {
+                name: "default".to_string(),
+                is_default: true,
+            },
+            OpCode::ModuleResolutionError {
+                message: "not found".to_string(),
+                specifier: "./missing".to_string(),
+                importer: "main.ot".to_string(),
+                dependency_chain: vec!["main.ot".to_string(), "a.ot".to_string()],
+            },
+            OpCode::Halt,
+        ]
+    }
+
+    #[test]
+    fn test_round_trip_every_variant() {
+        assert_round_trips(&every_variant());
+    }
+
+    #[test]
+    fn test_round_trip_random_programs() {
+        let variants = every_variant();
+        let mut rng = fastrand::Rng::with_seed(0x7e5c1);
+        for _ in 0..200 {
+            let len = rng.usize(1..64);
+            let program: Vec<OpCode> = (0..len)
+                .map(|_| match variants[rng.usize(..variants.len())].clone() {
+                    OpCode::Push(JsValue::Number(_)) => {
+                        OpCode::Push(JsValue::Number(f64::from_bits(rng.u64(..))))
+                    }
+                    OpCode::Jump(_) => OpCode::Jump(rng.usize(..len)),
+                    OpCode::Load(_) => OpCode::Load(format!("v{}", rng.u8(..8))),
+                    OpCode::Call(_) => OpCode::Call(rng.usize(..1000)),
+                    other => other,
+                })
+                .collect();
+            assert_round_trips(&program);
+        }
+    }
+
+    #[test]
+    fn test_round_trip_compiled_program() {
+        let source = "
+            function fib(n) { if (n < 2) { return n; } return fib(n - 1) + fib(n - 2); }
+            class Point { constructor(x) { this.x = x; } }
+            try { throw new Point(1); } catch (e) { console.log(e.x); }
+            let xs = [1, 2, 3];
+            console.log(fib(10), xs.length, `t${xs[0]}`);
+        ";
+        let program = Compiler::new().compile(source).unwrap();
+        assert_round_trips(&program);
+    }
+
+    #[test]
+    fn test_constants_and_strings_are_shared() {
+        let program = vec![
+            OpCode::Push(JsValue::String("name".into())),
+            OpCode::Push(JsValue::String("name".into())),
+            OpCode::Load("name".to_string()),
+            OpCode::Halt,
+        ];
+        let bytes = BytecodeEncoder::encode(&program).unwrap();
+        // header, 1 string ("name"), 1 constant, 4 instructions
+        let expected_len = 8 + (1 + 1 + 4) + (1 + 2) + (1 + 2 + 2 + 2 + 1);
+        assert_eq!(bytes.len(), expected_len);
+    }
+
+    #[test]
+    fn test_lone_surrogates_round_trip() {
+        let lone = JsString::from_units(&[0x61, 0xD83D]);
+        let program = vec![OpCode::Push(JsValue::String(lone.clone())), OpCode::Halt];
+        match &round_trip(&program)[0] {
+            OpCode::Push(JsValue::String(s)) => assert_eq!(*s, lone),
+            other => panic!("Expected Push(String), got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_runtime_values_are_rejected() {
+        let program = vec![OpCode::Push(JsValue::Object(0))];
+        assert!(matches!(
+            BytecodeEncoder::encode(&program),
+            Err(LoaderError::UnencodableValue(_))
+        ));
+    }
+}
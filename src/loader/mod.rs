@@ -1,8 +1,14 @@
-//! Bytecode loader module for loading pre-compiled .bc files
+//! Bytecode loader module for loading pre-compiled .bc/.otb files
 //!
 //! This module provides functionality to decode binary bytecode files
-//! produced by the bootstrap compiler (bootstrap/emitter.ot).
+//! produced by the bootstrap compiler (bootstrap/emitter.ot) and to encode
+//! compiled programs with `BytecodeEncoder` (`oitec compile`). Decoded programs
+//! are checked with `verify` before they run.
 
 mod decoder;
+mod encoder;
+mod verifier;
 
-pub use decoder::BytecodeDecoder;
+pub use decoder::{BytecodeDecoder, LoaderError};
+pub use encoder::BytecodeEncoder;
+pub use verifier::verify;
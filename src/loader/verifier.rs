@@ -0,0 +1,482 @@
+//! Bytecode verifier
+//!
+//! Checks a decoded program before it is handed to the VM. Every jump,
+//! exception handler and closure address must point inside the program, and
+//! abstract interpretation of the operand stack (the same technique
+//! `ir::lower` uses to build SSA) must assign each reachable instruction a
+//! single stack depth. A malformed file is rejected with a `LoaderError`
+//! instead of crashing the VM halfway through execution.
+//!
+//! Depths are relative to the start of the enclosing code region: the
+//! program entry starts at depth 0, and a function body starts with its
+//! arguments on the stack, one for each leading `Let` of its prologue.
+//!
+//! Indexed local slots are checked too: a `StoreLocal` grows the frame's
+//! slots up to its index, so slots are bounded, and a `LoadLocal` must read a
+//! slot that some `StoreLocal` writes.
+
+use super::LoaderError;
+use crate::vm::opcodes::OpCode;
+use crate::vm::value::JsValue;
+use std::collections::HashSet;
+
+/// Indexed local slots a frame may use
+pub const MAX_LOCAL_SLOTS: u32 = 1 << 16;
+
+/// Verify `program` and return the operand stack depth on entry to each
+/// instruction (`None` for unreachable instructions).
+pub fn verify(program: &[OpCode]) -> Result<Vec<Option<usize>>, LoaderError> {
+    check_addresses(program)?;
+    check_locals(program)?;
+
+    let mut depths: Vec<Option<usize>> = vec![None; program.len()];
+    let mut worklist: Vec<usize> = Vec::new();
+
+    if !program.is_empty() {
+        depths[0] = Some(0);
+        worklist.push(0);
+    }
+    for entry in function_entries(program) {
+        let params = program[entry..]
+            .iter()
+            .take_while(|op| matches!(op, OpCode::Let(_)))
+            .count();
+        merge(&mut depths, &mut worklist, entry, params)?;
+    }
+
+    while let Some(ip) = worklist.pop() {
+        let depth = depths[ip].expect("worklist entries have a depth");
+        let op = &program[ip];
+
+        let (pops, pushes) = stack_effect(op);
+        if depth < pops && !(ip > 0 && pops_leniently(op, &program[ip - 1])) {
+            return Err(LoaderError::StackUnderflow { at: ip, depth });
+        }
+        let after = depth.saturating_sub(pops) + pushes;
+
+        match op {
+            OpCode::Jump(target) => merge(&mut depths, &mut worklist, *target, after)?,
+            OpCode::JumpIfFalse(target) => {
+                merge(&mut depths, &mut worklist, *target, after)?;
+                fall_through(program, &mut depths, &mut worklist, ip, after)?;
+            }
+            OpCode::SetupTry {
+                catch_addr,
+                finally_addr,
+            } => {
+                // The VM truncates the stack to the depth recorded here before
+                // entering a handler; catch blocks receive the exception on top
+                if *catch_addr != 0 {
+                    merge(&mut depths, &mut worklist, *catch_addr, depth + 1)?;
+                }
+                if *finally_addr != 0 {
+                    merge(&mut depths, &mut worklist, *finally_addr, depth)?;
+                }
+                fall_through(program, &mut depths, &mut worklist, ip, after)?;
+            }
+            OpCode::Return | OpCode::Halt | OpCode::Throw => {}
+            _ => fall_through(program, &mut depths, &mut worklist, ip, after)?,
+        }
+    }
+
+    Ok(depths)
+}
+
+/// Check that every address operand points inside the program
+fn check_addresses(program: &[OpCode]) -> Result<(), LoaderError> {
+    for (ip, op) in program.iter().enumerate() {
+        let targets: &[usize] = match op {
+            OpCode::Jump(target) | OpCode::JumpIfFalse(target) | OpCode::MakeClosure(target) => {
+                std::slice::from_ref(target)
+            }
+            OpCode::Push(JsValue::Function { address, .. }) => std::slice::from_ref(address),
+            OpCode::SetupTry {
+                catch_addr,
+                finally_addr,
+            } => {
+                // 0 means "no handler"
+                for &target in [catch_addr, finally_addr] {
+                    if target != 0 && target >= program.len() {
+                        return Err(LoaderError::InvalidAddress { at: ip, target });
+                    }
+                }
+                &[]
+            }
+            _ => &[],
+        };
+        for &target in targets {
+            if target >= program.len() {
+                return Err(LoaderError::InvalidAddress { at: ip, target });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Check that local slots are in range and only read when some
+/// instruction writes them
+fn check_locals(program: &[OpCode]) -> Result<(), LoaderError> {
+    let mut stored = HashSet::new();
+    for (ip, op) in program.iter().enumerate() {
+        if let OpCode::StoreLocal(slot) = op {
+            if *slot >= MAX_LOCAL_SLOTS {
+                return Err(LoaderError::InvalidLocal {
+                    at: ip,
+                    slot: *slot,
+                });
+            }
+            stored.insert(*slot);
+        }
+    }
+    for (ip, op) in program.iter().enumerate() {
+        match op {
+            OpCode::LoadLocal(slot) if !stored.contains(slot) => {
+                return Err(LoaderError::InvalidLocal {
+                    at: ip,
+                    slot: *slot,
+                });
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Entry addresses of every function body referenced by the program
+fn function_entries(program: &[OpCode]) -> Vec<usize> {
+    let mut entries: Vec<usize> = program
+        .iter()
+        .filter_map(|op| match op {
+            OpCode::MakeClosure(address) => Some(*address),
+            OpCode::Push(JsValue::Function { address, .. }) => Some(*address),
+            _ => None,
+        })
+        .collect();
+    entries.sort_unstable();
+    entries.dedup();
+    entries
+}
+
+/// Record the depth on entry to `target`, queueing it the first time it is seen
+fn merge(
+    depths: &mut [Option<usize>],
+    worklist: &mut Vec<usize>,
+    target: usize,
+    depth: usize,
+) -> Result<(), LoaderError> {
+    match depths[target] {
+        Some(expected) if expected != depth => Err(LoaderError::StackMismatch {
+            at: target,
+            expected,
+            found: depth,
+        }),
+        Some(_) => Ok(()),
+        None => {
+            depths[target] = Some(depth);
+            worklist.push(target);
+            Ok(())
+        }
+    }
+}
+
+fn fall_through(
+    program: &[OpCode],
+    depths: &mut [Option<usize>],
+    worklist: &mut Vec<usize>,
+    ip: usize,
+    depth: usize,
+) -> Result<(), LoaderError> {
+    // Running off the end of the program stops the VM like `Halt`
+    if ip + 1 < program.len() {
+        merge(depths, worklist, ip + 1, depth)
+    } else {
+        Ok(())
+    }
+}
+
+/// Whether `op` may run on a short stack, the VM substituting `undefined`
+/// for the missing operand. Only the compiler's expression statement `Pop`
+/// after a property assignment needs this: the store leaves no value behind,
+/// but the statement still pops one.
+fn pops_leniently(op: &OpCode, previous: &OpCode) -> bool {
+    matches!(op, OpCode::Pop)
+        && matches!(
+            previous,
+            OpCode::SetProp(_) | OpCode::SetPropComputed | OpCode::SetPrivateProp(_)
+        )
+}
+
+/// Number of values an instruction pops and pushes
+fn stack_effect(op: &OpCode) -> (usize, usize) {
+    match op {
+        OpCode::Push(_)
+        | OpCode::Load(_)
+        | OpCode::LoadOrUndefined(_)
+        | OpCode::LoadThis
+        | OpCode::LoadLocal(_)
+        | OpCode::NewObject
+        | OpCode::NewArray(_)
+        | OpCode::LoadSuper
+        | OpCode::GetSuperProp(_)
+        | OpCode::NewTarget => (0, 1),
+
+        OpCode::Pop
+        | OpCode::Print
+        | OpCode::Let(_)
+        | OpCode::Store(_)
+        | OpCode::StoreLocal(_)
+        | OpCode::JumpIfFalse(_)
+        | OpCode::Throw => (1, 0),
+
+        OpCode::Dup => (1, 2),
+        OpCode::Swap => (2, 2),
+        OpCode::Swap3 => (3, 3),
+
+        OpCode::Add
+        | OpCode::Sub
+        | OpCode::Mul
+        | OpCode::Div
+        | OpCode::Mod
+        | OpCode::Pow
+        | OpCode::Eq
+        | OpCode::EqEq
+        | OpCode::Ne
+        | OpCode::NeEq
+        | OpCode::Lt
+        | OpCode::LtEq
+        | OpCode::Gt
+        | OpCode::GtEq
+        | OpCode::And
+        | OpCode::Or
+        | OpCode::BitAnd
+        | OpCode::BitOr
+        | OpCode::Xor
+        | OpCode::ShiftLeft
+        | OpCode::ShiftRight
+        | OpCode::ShiftRightUnsigned
+        | OpCode::InstanceOf
+        | OpCode::GetPropComputed
+        | OpCode::LoadElement
+        | OpCode::ArrayPush
+        | OpCode::ArraySpread
+        | OpCode::ObjectSpread
+        | OpCode::SetProto
+        | OpCode::ApplyDecorator => (2, 1),
+
+        OpCode::Not
+        | OpCode::Neg
+        | OpCode::TypeOf
+        | OpCode::Delete(_)
+        | OpCode::GetProp(_)
+        | OpCode::GetPrivateProp(_)
+        | OpCode::NewObjectWithProto
+        | OpCode::Require
+        | OpCode::MakeClosure(_)
+        | OpCode::ImportAsync(_)
+        | OpCode::ImportDynamic
+        | OpCode::ImportMeta
+        | OpCode::Await
+        | OpCode::Freeze
+        | OpCode::ToIterable
+        | OpCode::GetExport { .. } => (1, 1),
+
+        OpCode::SetProp(_) | OpCode::SetPrivateProp(_) => (2, 0),
+        OpCode::SetPropComputed | OpCode::StoreElement => (3, 0),
+
+        // Callee and arguments in, result out
+        OpCode::Call(argc) | OpCode::Construct(argc) | OpCode::CallMethod(_, argc) => (argc + 1, 1),
+        OpCode::CallSuper(argc) => (argc + 1, 1),
+
+        OpCode::ModuleResolutionError { .. } => (2, 1),
+
+        // A rethrowing `EnterFinally` pushes the pending exception for the
+        // `Throw` that follows it
+        OpCode::EnterFinally(true) => (0, 1),
+        OpCode::EnterFinally(false) => (0, 0),
+
+        // `Return` takes its value leniently (an empty stack returns undefined)
+        OpCode::Return
+        | OpCode::Halt
+        | OpCode::Jump(_)
+        | OpCode::Drop(_)
+        | OpCode::SetupTry { .. }
+        | OpCode::PopTry
+        | OpCode::EnterAsync => (0, 0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compiler::Compiler;
+
+    fn num(n: f64) -> OpCode {
+        OpCode::Push(JsValue::Number(n))
+    }
+
+    #[test]
+    fn test_accepts_straight_line_code() {
+        let program = vec![num(1.0), num(2.0), OpCode::Add, OpCode::Pop, OpCode::Halt];
+        let depths = verify(&program).unwrap();
+        assert_eq!(depths, vec![Some(0), Some(1), Some(2), Some(1), Some(0)]);
+    }
+
+    #[test]
+    fn test_rejects_out_of_range_addresses() {
+        for op in [
+            OpCode::Jump(9),
+            OpCode::JumpIfFalse(9),
+            OpCode::MakeClosure(9),
+            OpCode::SetupTry {
+                catch_addr: 9,
+                finally_addr: 0,
+            },
+            OpCode::SetupTry {
+                catch_addr: 0,
+                finally_addr: 9,
+            },
+        ] {
+            let program = vec![num(0.0), op, OpCode::Halt];
+            assert!(matches!(
+                verify(&program),
+                Err(LoaderError::InvalidAddress { at: 1, target: 9 })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_rejects_inconsistent_merge() {
+        // The fall-through path pushes an extra value before joining at 4
+        let program = vec![
+            OpCode::Push(JsValue::Boolean(true)),
+            OpCode::JumpIfFalse(4),
+            num(1.0),
+            OpCode::Jump(4),
+            OpCode::Halt,
+        ];
+        assert!(matches!(
+            verify(&program),
+            Err(LoaderError::StackMismatch {
+                at: 4,
+                expected: 0,
+                found: 1
+            })
+        ));
+    }
+
+    #[test]
+    fn test_underflow_only_for_pop_after_property_store() {
+        assert!(matches!(
+            verify(&[num(1.0), OpCode::Add, OpCode::Halt]),
+            Err(LoaderError::StackUnderflow { at: 1, depth: 1 })
+        ));
+        // `o.x = 1;` pops a value the store never pushed
+        let assignment = [
+            OpCode::Load("o".to_string()),
+            num(1.0),
+            OpCode::SetProp("x".to_string()),
+            OpCode::Pop,
+            OpCode::Halt,
+        ];
+        assert!(verify(&assignment).is_ok());
+        assert!(matches!(
+            verify(&[OpCode::Pop, OpCode::Halt]),
+            Err(LoaderError::StackUnderflow { at: 0, depth: 0 })
+        ));
+        for op in [
+            OpCode::JumpIfFalse(1),
+            OpCode::Throw,
+            OpCode::GetProp("x".to_string()),
+            OpCode::Let("x".to_string()),
+        ] {
+            assert!(matches!(
+                verify(&[op, OpCode::Halt]),
+                Err(LoaderError::StackUnderflow { at: 0, depth: 0 })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_rejects_invalid_local_slots() {
+        let program = [
+            num(1.0),
+            OpCode::StoreLocal(2),
+            OpCode::LoadLocal(2),
+            OpCode::Halt,
+        ];
+        assert!(verify(&program).is_ok());
+        assert!(matches!(
+            verify(&[num(1.0), OpCode::StoreLocal(MAX_LOCAL_SLOTS), OpCode::Halt]),
+            Err(LoaderError::InvalidLocal {
+                at: 1,
+                slot: MAX_LOCAL_SLOTS
+            })
+        ));
+        assert!(matches!(
+            verify(&[
+                num(1.0),
+                OpCode::StoreLocal(0),
+                OpCode::LoadLocal(1),
+                OpCode::Halt
+            ]),
+            Err(LoaderError::InvalidLocal { at: 2, slot: 1 })
+        ));
+    }
+
+    #[test]
+    fn test_function_and_handler_entry_depths() {
+        let program = vec![
+            OpCode::Push(JsValue::Function {
+                address: 3,
+                env: None,
+            }),
+            OpCode::Let("f".to_string()),
+            OpCode::Jump(6),
+            OpCode::Let("a".to_string()),
+            OpCode::Load("a".to_string()),
+            OpCode::Return,
+            OpCode::SetupTry {
+                catch_addr: 9,
+                finally_addr: 0,
+            },
+            OpCode::PopTry,
+            OpCode::Jump(10),
+            OpCode::Let("e".to_string()),
+            OpCode::Halt,
+        ];
+        let depths = verify(&program).unwrap();
+        // One parameter on entry to the function body
+        assert_eq!(depths[3], Some(1));
+        // The exception is on the stack on entry to the catch block
+        assert_eq!(depths[9], Some(1));
+        assert_eq!(depths[10], Some(0));
+    }
+
+    #[test]
+    fn test_accepts_compiled_programs() {
+        let source = r#"
+            class Point {
+                constructor(x, y) { this.x = x; this.y = y; }
+                sum() { return this.x + this.y; }
+            }
+            function each(items, callback) {
+                for (let i = 0; i < items.length; i++) { callback(items[i]); }
+            }
+            let total = 0;
+            each([1, 2, 3], function(n) { total = total + n; });
+            try {
+                throw new Point(1, 2);
+            } catch (e) {
+                total = total + e.sum();
+            } finally {
+                total = total * 2;
+            }
+            while (total > 0) {
+                if (total % 2 == 0) { break; }
+                total = total - 1;
+            }
+        "#;
+        let program = Compiler::new().compile(source).unwrap();
+        assert!(verify(&program).is_ok());
+    }
+}
@@ -22,10 +22,15 @@
 #![allow(clippy::field_reassign_with_default)]
 
 mod backend;
+mod build;
 mod compiler;
 use compiler::Compiler;
+mod debugger;
 mod ir;
 mod loader;
+mod module;
+mod profiler;
+mod repl;
 mod runtime;
 mod stdlib;
 pub mod types;
@@ -39,6 +44,7 @@ use crate::vm::VM;
 use std::env;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 #[cfg(test)]
 mod tests;
 
@@ -116,22 +122,24 @@ fn load_and_run_script(
         Some(Syntax::Typescript(ts_syntax))
     };
 
-    let bytecode = compiler
-        .compile_with_syntax(&source, syntax)
+    compiler.set_source_path(path);
+    let (bytecode, source_map) = compiler
+        .compile_with_source_map(&source, syntax)
         .map_err(|e| format!("Failed to compile {}: {}", path, e))?;
     let bytecode_len = bytecode.len();
 
     if append {
         let offset = vm.append_program(bytecode);
+        vm.add_source_map(offset..offset + bytecode_len, Path::new(path), source_map);
         eprintln!("  {} ({} ops at offset {})", path, bytecode_len, offset);
     } else {
         let path_buf = PathBuf::from(path);
         vm.load_program_with_path(bytecode, path_buf);
+        vm.add_source_map(0..bytecode_len, Path::new(path), source_map);
         eprintln!("  {} ({} ops)", path, bytecode_len);
     }
 
-    vm.run_until_halt();
-    Ok(())
+    vm.run_until_halt().map_err(|e| e.to_string())
 }
 
 /// Load and run a pre-compiled bytecode file
@@ -141,28 +149,13 @@ fn run_binary_file(vm: &mut VM, path: &str) -> Result<(), String> {
 
     let mut decoder = BytecodeDecoder::new(&bytes);
 
-    match decoder.decode_all() {
-        Ok(program) => {
-            eprintln!("Loaded {} instructions from binary file", program.len());
-            // Debug: print each instruction
-            for (i, op) in program.iter().enumerate() {
-                eprintln!("  [{}] {:?}", i, op);
-            }
-            // Debug: check if console is in global frame
-            if let Some(frame) = vm.call_stack.first() {
-                eprintln!("Global frame has {} locals", frame.locals.len());
-                if frame.locals.contains_key("console") {
-                    eprintln!("  - console: found");
-                } else {
-                    eprintln!("  - console: NOT FOUND!");
-                }
-            }
-            let offset = vm.append_program(program);
-            vm.run_event_loop();
-            Ok(())
-        }
-        Err(e) => Err(format!("Failed to decode bytecode: {}", e)),
-    }
+    let program = decoder
+        .decode_all()
+        .map_err(|e| format!("Failed to decode bytecode: {}", e))?;
+    loader::verify(&program).map_err(|e| format!("Invalid bytecode in {}: {}", path, e))?;
+
+    vm.append_program(program);
+    vm.run_event_loop().map_err(|e| e.to_string())
 }
 
 fn main() {
@@ -174,12 +167,19 @@ fn main() {
         eprintln!("  ir <filename>        Dump SSA IR for a .ot file");
         eprintln!("  jit <filename>       Run a .ot file with JIT compilation");
         eprintln!("  bench <filename>     Benchmark VM vs JIT for a .ot file");
+        eprintln!("  profile [--jit] [-o <file>] [--top <n>] <filename>  Profile a .ot file");
         eprintln!("  build [options] <filename>  Build a .ot file to native binary");
+        eprintln!("  compile <filename> [-o <file>]  Compile a .ot file to bytecode (.otb)");
+        eprintln!("  repl                 Start an interactive session");
+        eprintln!("  debug                Start a Debug Adapter Protocol session on stdio");
         eprintln!("  <filename>           Run a .ot file (VM interpreter)");
+        eprintln!("  --watch <filename>   Run a .ot file and reload modules as they change");
         eprintln!("  --run-binary <file>  Run a bytecode file (.bc)");
         eprintln!();
         eprintln!("Build options:");
-        eprintln!("  --backend <llvm|cranelift>  Choose code generator (default: llvm)");
+        eprintln!(
+            "  --backend <llvm|cranelift>  Choose code generator (default: llvm if built in)"
+        );
         eprintln!("  --output <file>, -o <file>  Output file name");
         eprintln!("  --release                      Optimize with ThinLTO");
         eprintln!("  --dist                         Full LTO for maximum performance");
@@ -238,13 +238,51 @@ fn main() {
         return;
     }
 
+    // Handle "repl" command for interactive sessions
+    if command == "repl" {
+        let mut repl = repl::Repl::new();
+        repl.init();
+        repl.run();
+        return;
+    }
+
+    // Handle "debug" command: DAP server for editors
+    if command == "debug" {
+        debugger::run_stdio();
+        return;
+    }
+
+    // Handle "profile" command: folded stacks + top-N table
+    if command == "profile" {
+        profiler::run(&args[2..]);
+        return;
+    }
+
+    // Handle "compile" command: serialize bytecode to .otb
+    if command == "compile" {
+        compile_to_bytecode(&args[2..]);
+        return;
+    }
+
     // Handle "build" command for AOT compilation
     if command == "build" {
         build_file(&args[2..]);
         return;
     }
 
-    let filename = command;
+    // `--run-binary <file>` and `--watch <file>` name the file after the flag
+    let watch = command == "--watch";
+    let filename = if command == "--run-binary" || watch {
+        match args.get(2) {
+            Some(file) => file,
+            None => {
+                eprintln!("Usage: {} {} <file>", args[0], command);
+                return;
+            }
+        }
+    } else {
+        command
+    };
 
     // Check if we should run in binary mode
     let run_binary = args.iter().any(|a| a == "--run-binary")
@@ -262,6 +300,7 @@ fn main() {
         // Running bytecode file
         if let Err(e) = run_binary_file(&mut vm, filename) {
             eprintln!("{}", e);
+            std::process::exit(1);
         }
         return;
     }
@@ -340,23 +379,171 @@ fn main() {
         Some(Syntax::Typescript(ts_syntax))
     };
 
-    match compiler.compile_with_syntax(&main_source, syntax) {
-        Ok(main_bytecode) => {
-            let offset = vm.append_program(main_bytecode);
+    if watch {
+        vm.enable_hot_reload();
+    }
+    compiler.set_source_path(filename);
+    match compiler.compile_module(&main_source, syntax, 0) {
+        Ok(main_module) => {
+            let main_len = main_module.bytecode.len();
+            let offset = vm.append_program(main_module.bytecode);
+            vm.add_source_map(
+                offset..offset + main_len,
+                Path::new(filename),
+                main_module.source_map,
+            );
+            vm.register_entry_module(Path::new(filename), &main_source, &main_module.exports);
             // Update the current module path to the main script for relative imports
             vm.set_current_module_path(PathBuf::from(filename));
 
             // Set script arguments (__args__) for the script
             // Arguments after the filename are passed to the script
-            let script_args: Vec<String> = args[2..].to_vec();
+            let script_args: Vec<String> = args[if watch { 3 } else { 2 }..].to_vec();
             vm.set_script_args(script_args);
 
-            vm.run_event_loop();
+            if watch {
+                run_watch(&mut vm);
+            } else if let Err(e) = vm.run_event_loop() {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        Err(e) => {
+            eprintln!("Compilation failed: {}", e);
+        }
+    }
+}
+
+/// How often `--watch` checks the loaded modules for changes
+const WATCH_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Run the loaded program, then keep serving its event loop and re-run
+/// modules whose files change. Runs until the process is killed.
+fn run_watch(vm: &mut VM) {
+    if let Err(e) = vm.run_until_halt() {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+    eprintln!(
+        "[watch] Watching {} module(s) for changes",
+        vm.module_cache.len()
+    );
+    loop {
+        let deadline = Instant::now() + WATCH_POLL_INTERVAL;
+        while Instant::now() < deadline {
+            // An uncaught error ends the task that threw it, not the watch
+            let busy = vm
+                .run_event_loop_step_until(Some(deadline))
+                .unwrap_or_else(|e| {
+                    eprintln!("{}", e);
+                    vm.call_stack.truncate(1);
+                    vm.exception_handlers.clear();
+                    vm.current_exception = None;
+                    true
+                });
+            if !busy {
+                std::thread::sleep(deadline.saturating_duration_since(Instant::now()));
+            }
+        }
+        for path in vm.reload_changed_modules() {
+            eprintln!("[watch] Reloaded {}", path.display());
+        }
+    }
+}
+
+/// Compile a file to a version 2 bytecode file (.otb)
+fn compile_to_bytecode(args: &[String]) {
+    use crate::loader::BytecodeEncoder;
+
+    let mut filename: Option<&str> = None;
+    let mut output: Option<String> = None;
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "-o" | "--output" => {
+                i += 1;
+                output = args.get(i).cloned();
+                if output.is_none() {
+                    eprintln!("Error: -o requires a file name");
+                    std::process::exit(1);
+                }
+            }
+            arg if filename.is_none() && !arg.starts_with('-') => filename = Some(arg),
+            arg => {
+                eprintln!("Error: Unknown option: {}", arg);
+                std::process::exit(1);
+            }
+        }
+        i += 1;
+    }
+
+    let Some(filename) = filename else {
+        eprintln!("Usage: oitec compile <filename> [-o <file>]");
+        std::process::exit(1);
+    };
+    let output = output.unwrap_or_else(|| {
+        Path::new(filename)
+            .with_extension("otb")
+            .to_string_lossy()
+            .to_string()
+    });
+
+    let source = match fs::read_to_string(filename) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("Failed to read {}: {}", filename, e);
+            std::process::exit(1);
         }
+    };
+
+    // Determine syntax based on file extension
+    let syntax = if filename.ends_with(".ts") || filename.ends_with(".tsx") {
+        let ts_syntax = TsSyntax {
+            decorators: true,
+            tsx: filename.ends_with(".tsx"),
+            ..Default::default()
+        };
+        Some(Syntax::Typescript(ts_syntax))
+    } else if filename.ends_with(".js") || filename.ends_with(".jsx") {
+        Some(Syntax::Es(Default::default()))
+    } else {
+        // Default to TypeScript with decorators for .ot files
+        let ts_syntax = TsSyntax {
+            decorators: true,
+            ..Default::default()
+        };
+        Some(Syntax::Typescript(ts_syntax))
+    };
+
+    let mut compiler = Compiler::new();
+    compiler.set_source_path(filename);
+    let bytecode = match compiler.compile_with_syntax(&source, syntax) {
+        Ok(bc) => bc,
         Err(e) => {
             eprintln!("Compilation failed: {}", e);
+            std::process::exit(1);
         }
+    };
+
+    let bytes = match BytecodeEncoder::encode(&bytecode) {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            eprintln!("Failed to encode bytecode: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    if let Err(e) = fs::write(&output, &bytes) {
+        eprintln!("Failed to write {}: {}", output, e);
+        std::process::exit(1);
     }
+    println!(
+        "Compiled {} -> {} ({} instructions, {} bytes)",
+        filename,
+        output,
+        bytecode.len(),
+        bytes.len()
+    );
 }
 
 /// Dump SSA IR for a file
@@ -389,6 +576,7 @@ fn dump_ir(filename: &str) {
     };
 
     let mut compiler = Compiler::new();
+    compiler.set_source_path(filename);
     let bytecode = match compiler.compile_with_syntax(&source, syntax) {
         Ok(bc) => bc,
         Err(e) => {
@@ -456,6 +644,7 @@ fn check_file(filename: &str) {
     };
 
     let mut compiler = Compiler::new();
+    compiler.set_source_path(filename);
     match compiler.compile_with_syntax(&source, syntax) {
         Ok(_) => {
             // Success - no errors
@@ -524,6 +713,7 @@ fn run_jit(filename: &str) {
 
     // Compile to bytecode
     let mut compiler = Compiler::new();
+    compiler.set_source_path(filename);
     let bytecode = match compiler.compile_with_syntax(&source, syntax) {
         Ok(bc) => bc,
         Err(e) => {
@@ -639,6 +829,7 @@ fn run_benchmark(filename: &str) {
 
     // Compile to bytecode
     let mut compiler = Compiler::new();
+    compiler.set_source_path(filename);
     let bytecode = match compiler.compile_with_syntax(&source, syntax) {
         Ok(bc) => bc,
         Err(e) => {
@@ -672,7 +863,10 @@ fn run_benchmark(filename: &str) {
     for _ in 0..ITERATIONS {
         let mut vm = VM::new_bare(); // Use bare VM without stdlib for benchmark
         vm.load_program(vm_bytecode.clone());
-        vm.run_until_halt();
+        if let Err(e) = vm.run_until_halt() {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
         // Get the result (top of stack or undefined)
         let result = vm
             .stack
@@ -768,12 +962,19 @@ fn run_benchmark(filename: &str) {
 fn build_file(args: &[String]) {
     use crate::backend::{
         BackendConfig, BackendKind, LtoMode, OptLevel,
-        aot::{AotCompiler, AotOptions, OutputFormat},
+        aot::{AotCompiler, AotOptions, BuildUnit, OutputFormat},
+        target::Target,
     };
+    use crate::build::cache::{Artifact, BuildCache, CacheKey, DEFAULT_CACHE_DIR, ModuleHasher};
 
     let mut filenames = Vec::new();
     let mut output = None;
-    let mut backend = BackendKind::LlvmAot;
+    // Builds without the `llvm` feature can only use Cranelift
+    let mut backend = if cfg!(feature = "llvm") {
+        BackendKind::LlvmAot
+    } else {
+        BackendKind::CraneliftAot
+    };
     let mut opt_level = OptLevel::None; // Default to dev mode
     let mut format = OutputFormat::Executable;
     let mut lto_mode = LtoMode::None;
@@ -781,6 +982,10 @@ fn build_file(args: &[String]) {
     let mut emit_llvm = false;
     let mut emit_obj = false;
     let mut verify_ir = false;
+    let mut no_cache = false;
+    let mut verify_reproducible = false;
+    let mut target = None;
+    let mut static_link = false;
 
     // Parse arguments
     let mut i = 0;
@@ -850,6 +1055,29 @@ fn build_file(args: &[String]) {
             "--verify-ir" => {
                 verify_ir = true;
             }
+            "--no-cache" => {
+                no_cache = true;
+            }
+            "--verify-reproducible" => {
+                verify_reproducible = true;
+            }
+            "--target" => {
+                i += 1;
+                if i >= args.len() {
+                    eprintln!("Error: --target requires a value");
+                    std::process::exit(1);
+                }
+                target = match Target::parse(&args[i]) {
+                    Ok(target) => Some(target),
+                    Err(e) => {
+                        eprintln!("Error: {}", e);
+                        std::process::exit(1);
+                    }
+                };
+            }
+            "--static" => {
+                static_link = true;
+            }
             _ => {
                 if !args[i].starts_with('-') {
                     filenames.push(args[i].clone());
@@ -865,72 +1093,110 @@ fn build_file(args: &[String]) {
     if filenames.is_empty() {
         eprintln!("Error: No input file specified");
         eprintln!(
-            "Usage: {} build [--backend llvm] [--output <file>] [--release|--dist] [--emit-ir|--emit-llvm|--emit-obj] [--verify-ir] <filename>...",
+            "Usage: {} build [--backend <llvm|cranelift>] [--output <file>] [--release|--dist] [--target <triple>] [--static] [--emit-ir|--emit-llvm|--emit-obj] [--verify-ir] [--no-cache] [--verify-reproducible] <filename>...",
             env::args().next().unwrap()
         );
+        eprintln!("Target flags:");
+        eprintln!("  --target <triple>  Cross-compile, e.g. aarch64-unknown-linux-gnu");
+        eprintln!("  --static        Link a static executable against musl");
         eprintln!("Emission flags:");
         eprintln!("  --emit-ir       Output SSA IR to file.ir");
         eprintln!("  --emit-llvm     Output LLVM IR to file.ll");
         eprintln!("  --emit-obj      Output object file to file.o");
         eprintln!("  --verify-ir     Validate SSA IR and exit");
+        eprintln!(
+            "  --no-cache      Recompile every module instead of reusing {DEFAULT_CACHE_DIR}"
+        );
+        eprintln!("  --verify-reproducible");
+        eprintln!("                  Build twice and check that the outputs are bit-identical");
         std::process::exit(1);
     }
 
-    // Compile all source files to IR modules
-    let mut modules = Vec::new();
-    let mut compiler = Compiler::new();
-
-    for filename in &filenames {
-        // Read source file
-        let source = match fs::read_to_string(filename) {
-            Ok(s) => s,
+    // Static executables link against musl
+    if static_link {
+        target = match target.unwrap_or_else(Target::host).with_musl() {
+            Ok(target) => Some(target),
             Err(e) => {
-                eprintln!("Failed to read {}: {}", filename, e);
+                eprintln!("Error: {}", e);
                 std::process::exit(1);
             }
         };
+    }
 
-        // Determine syntax
-        let syntax = if filename.ends_with(".ts") || filename.ends_with(".tsx") {
-            let ts_syntax = TsSyntax {
-                decorators: true,
-                tsx: filename.ends_with(".tsx"),
-                ..Default::default()
-            };
-            Some(Syntax::Typescript(ts_syntax))
-        } else if filename.ends_with(".js") || filename.ends_with(".jsx") {
-            Some(Syntax::Es(Default::default()))
-        } else {
-            // Default to TypeScript with decorators for .ot files
-            let ts_syntax = TsSyntax {
-                decorators: true,
-                ..Default::default()
-            };
-            Some(Syntax::Typescript(ts_syntax))
-        };
+    let config = BackendConfig {
+        kind: backend,
+        opt_level,
+        debug_info: opt_level == OptLevel::None,
+        bounds_check: true,
+        lto_mode,
+        target: target.clone(),
+    };
 
-        // Compile to bytecode
-        let bytecode = match compiler.compile_with_syntax(&source, syntax) {
-            Ok(bc) => bc,
-            Err(e) => {
-                eprintln!("Compilation failed for {}: {}", filename, e);
-                std::process::exit(1);
-            }
+    let mut aot = AotCompiler::new(&config);
+    let mut options = AotOptions::default();
+    options.format = format;
+    options.lto_mode = lto_mode;
+    options.static_link = static_link;
+    aot = aot.with_options(options);
+
+    if verify_reproducible {
+        let output_path = output.unwrap_or_else(|| default_output_name(&filenames[0]));
+        verify_reproducible_build(&mut aot, &filenames, Path::new(&output_path));
+        return;
+    }
+
+    // Modules whose artifact is cached skip compilation; the emit and verify
+    // flags need every module's IR
+    let cache = (!no_cache && !verify_ir && !emit_llvm && !emit_obj)
+        .then(|| BuildCache::new(DEFAULT_CACHE_DIR));
+    let mut hasher = ModuleHasher::new();
+    let target = target.unwrap_or_else(Target::host).to_string();
+    // Per file: its cache key and, unless it was cached, its index in `modules`
+    let mut units: Vec<(Option<CacheKey>, Option<usize>)> = Vec::new();
+    let mut cached_count = 0;
+
+    // Compile all source files to IR modules
+    let mut modules = Vec::new();
+    let mut compiler = Compiler::new();
+
+    for filename in &filenames {
+        let key = match &cache {
+            Some(cache) => match hasher.hash_module(Path::new(filename)) {
+                Ok(hash) => Some(CacheKey::new(&hash, &config, &target, format)),
+                Err(e) => {
+                    eprintln!("Failed to read {}: {}", filename, e);
+                    std::process::exit(1);
+                }
+            },
+            None => None,
         };
+        let ir_output = Path::new(filename).with_extension("ir");
+        // A module with a cached artifact needs no compiling, unless its IR
+        // is wanted and was not cached
+        if let (Some(cache), Some(key)) = (&cache, &key)
+            && cache.get(key, aot.module_artifact()).is_some()
+            && (!emit_ir || cache.get(key, Artifact::Ir).is_some())
+        {
+            if emit_ir {
+                if let Err(e) = fs::copy(cache.path(key, Artifact::Ir), &ir_output) {
+                    eprintln!("Failed to write IR: {}", e);
+                    std::process::exit(1);
+                }
+                println!("IR written to: {}", ir_output.display());
+            }
+            units.push((Some(key.clone()), None));
+            cached_count += 1;
+            continue;
+        }
 
-        // Lower to SSA IR
-        let mut module = match ir::lower::lower_module(&bytecode) {
-            Ok(m) => m,
+        let module = match lower_file(&mut compiler, filename) {
+            Ok(module) => module,
             Err(e) => {
-                eprintln!("IR lowering failed for {}: {}", filename, e);
+                eprintln!("{}", e);
                 std::process::exit(1);
             }
         };
 
-        // Run type inference and optimizations
-        ir::typecheck::typecheck_module(&mut module);
-        ir::opt::optimize_module(&mut module);
-
         // Verify IR if requested
         if verify_ir {
             match ir::verify::verify_module(&module) {
@@ -948,13 +1214,15 @@ fn build_file(args: &[String]) {
             continue; // Skip to next file
         }
 
+        if let (Some(cache), Some(key)) = (&cache, &key) {
+            let ir_text = ir::format::serialize_module(&module);
+            if let Err(e) = cache.store(key, Artifact::Ir, ir_text.as_bytes()) {
+                eprintln!("Warning: failed to cache IR for {}: {}", filename, e);
+            }
+        }
+
         // Emit IR if requested
         if emit_ir {
-            let ir_output = Path::new(filename)
-                .file_stem()
-                .map(|s| Path::new(filename).with_extension("ir").to_path_buf())
-                .unwrap_or_else(|| PathBuf::from("output.ir"));
-
             match ir::format::write_ir_to_file(&module, &ir_output) {
                 Ok(()) => {
                     println!("IR written to: {}", ir_output.display());
@@ -966,6 +1234,7 @@ fn build_file(args: &[String]) {
             }
         }
 
+        units.push((key, Some(modules.len())));
         modules.push(module);
     }
 
@@ -987,32 +1256,11 @@ fn build_file(args: &[String]) {
         println!("Compiling {} files to native binary...", filenames.len());
     }
 
-    let config = BackendConfig {
-        kind: backend,
-        opt_level,
-        debug_info: opt_level == OptLevel::None,
-        bounds_check: true,
-        lto_mode,
-    };
-
-    let mut aot = AotCompiler::new(&config);
-    let mut options = AotOptions::default();
-    options.format = format;
-    options.lto_mode = lto_mode;
-    aot = aot.with_options(options);
-
     // Compile all modules (with LTO support if enabled)
     let module_refs: Vec<&IrModule> = modules.iter().collect();
 
     // Determine output path
-    let output_path = output.unwrap_or_else(|| {
-        // Use first filename as default output name
-        Path::new(&filenames[0])
-            .file_stem()
-            .unwrap()
-            .to_string_lossy()
-            .to_string()
-    });
+    let output_path = output.unwrap_or_else(|| default_output_name(&filenames[0]));
 
     // Emit object file if requested
     if emit_obj {
@@ -1043,7 +1291,29 @@ fn build_file(args: &[String]) {
     }
 
     // Full compilation to executable/library
-    match aot.compile_modules_to_file(&module_refs, Path::new(&output_path)) {
+    let result = match &cache {
+        Some(cache) => {
+            if cached_count > 0 {
+                println!(
+                    "Reusing cached build of {} of {} module(s)",
+                    cached_count,
+                    filenames.len()
+                );
+            }
+            let units: Vec<BuildUnit> = units
+                .iter()
+                .filter_map(|(key, index)| {
+                    Some(BuildUnit {
+                        key: key.clone()?,
+                        module: index.map(|i| &modules[i]),
+                    })
+                })
+                .collect();
+            aot.compile_cached(cache, &units, Path::new(&output_path))
+        }
+        None => aot.compile_modules_to_file(&module_refs, Path::new(&output_path)),
+    };
+    match result {
         Ok(()) => {
             println!("Successfully compiled to: {}", output_path);
         }
@@ -1053,3 +1323,132 @@ fn build_file(args: &[String]) {
         }
     }
 }
+
+/// Compile a source file to optimized SSA IR
+fn lower_file(compiler: &mut Compiler, filename: &str) -> Result<IrModule, String> {
+    // Read source file
+    let source =
+        fs::read_to_string(filename).map_err(|e| format!("Failed to read {}: {}", filename, e))?;
+
+    // Determine syntax
+    let syntax = if filename.ends_with(".ts") || filename.ends_with(".tsx") {
+        let ts_syntax = TsSyntax {
+            decorators: true,
+            tsx: filename.ends_with(".tsx"),
+            ..Default::default()
+        };
+        Some(Syntax::Typescript(ts_syntax))
+    } else if filename.ends_with(".js") || filename.ends_with(".jsx") {
+        Some(Syntax::Es(Default::default()))
+    } else {
+        // Default to TypeScript with decorators for .ot files
+        let ts_syntax = TsSyntax {
+            decorators: true,
+            ..Default::default()
+        };
+        Some(Syntax::Typescript(ts_syntax))
+    };
+
+    // Compile to bytecode
+    compiler.set_source_path(filename);
+    let bytecode = compiler
+        .compile_with_syntax(&source, syntax)
+        .map_err(|e| format!("Compilation failed for {}: {}", filename, e))?;
+
+    // Lower to SSA IR
+    let mut module = ir::lower::lower_module(&bytecode)
+        .map_err(|e| format!("IR lowering failed for {}: {}", filename, e))?;
+
+    // Run type inference and optimizations
+    ir::typecheck::typecheck_module(&mut module);
+    ir::opt::optimize_module(&mut module);
+    Ok(module)
+}
+
+/// Output name of a build: the first file's name without its extension
+fn default_output_name(filename: &str) -> String {
+    Path::new(filename)
+        .file_stem()
+        .unwrap()
+        .to_string_lossy()
+        .to_string()
+}
+
+/// `oitec build --verify-reproducible`: build twice from source, each build
+/// in its own directory and the second with a different temporary directory
+/// and user, and compare the outputs. Identical outputs are kept as the build result.
+fn verify_reproducible_build(
+    aot: &mut backend::aot::AotCompiler,
+    filenames: &[String],
+    output: &Path,
+) {
+    use crate::build::verify_determinism;
+
+    println!(
+        "Verifying that {} builds reproducibly...",
+        filenames.join(", ")
+    );
+    let temp_dir = output
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+        .join(".reproducible");
+
+    let result = verify_determinism(
+        Path::new(&filenames[0]),
+        &temp_dir,
+        |_, build_output| -> Result<(), Box<dyn std::error::Error>> {
+            let mut compiler = Compiler::new();
+            let modules = filenames
+                .iter()
+                .map(|filename| lower_file(&mut compiler, filename))
+                .collect::<Result<Vec<_>, _>>()?;
+            let module_refs: Vec<&IrModule> = modules.iter().collect();
+            aot.compile_modules_to_file(&module_refs, build_output)?;
+            Ok(())
+        },
+    );
+    let result = match result {
+        Ok(result) => result,
+        Err(e) => {
+            let _ = fs::remove_dir_all(&temp_dir);
+            eprintln!("Build failed: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    println!("  build A: {} ({} bytes)", result.hash_a, result.size_a);
+    println!("  build B: {} ({} bytes)", result.hash_b, result.size_b);
+    if !result.is_deterministic {
+        let _ = fs::remove_dir_all(&temp_dir);
+        eprintln!("Builds differ:");
+        for difference in &result.differences {
+            let section = difference.section.as_deref().unwrap_or("unknown section");
+            if difference.len == 0 {
+                eprintln!("  at {:#x}: {}", difference.offset, section);
+            } else {
+                eprintln!(
+                    "  {:#x}..{:#x} ({} bytes) in {}",
+                    difference.offset,
+                    difference.offset + difference.len,
+                    difference.len,
+                    section
+                );
+            }
+        }
+        std::process::exit(1);
+    }
+
+    let built = temp_dir.join("a").join("determinism_test");
+    let copied = fs::copy(&built, output);
+    let _ = fs::remove_dir_all(&temp_dir);
+    match copied {
+        Ok(_) => println!(
+            "Builds are bit-identical; compiled to: {}",
+            output.display()
+        ),
+        Err(e) => {
+            eprintln!("Failed to write {}: {}", output.display(), e);
+            std::process::exit(1);
+        }
+    }
+}
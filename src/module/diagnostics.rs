@@ -1,5 +1,5 @@
 use std::fmt;
-use std::path::{Path, PathBuf};
+use std::path::PathBuf;
 
 #[derive(Debug, Clone)]
 pub struct SourceLocation {
@@ -54,11 +54,16 @@ pub enum ModuleErrorKind {
         path: PathBuf,
         message: String,
     },
+    CompileError {
+        path: PathBuf,
+        message: String,
+    },
 }
 
 #[derive(Debug)]
 pub struct ModuleError {
-    pub kind: ModuleErrorKind,
+    /// Boxed to keep `ModuleResult` small; errors are rare
+    pub kind: Box<ModuleErrorKind>,
     pub source_location: Option<SourceLocation>,
     pub dependency_chain: Vec<DependencyInfo>,
     pub suggestion: Option<String>,
@@ -67,7 +72,7 @@ pub struct ModuleError {
 impl ModuleError {
     pub fn not_found(specifier: String, tried_paths: Vec<String>) -> Self {
         Self {
-            kind: ModuleErrorKind::NotFound { specifier, tried_paths },
+            kind: Box::new(ModuleErrorKind::NotFound { specifier, tried_paths }),
             source_location: None,
             dependency_chain: Vec::new(),
             suggestion: Some("Check the file path and ensure the file exists with a supported extension (.ot, .ts, .js)".to_string()),
@@ -76,7 +81,7 @@ impl ModuleError {
 
     pub fn cycle_detected(cycle: Vec<String>) -> Self {
         Self {
-            kind: ModuleErrorKind::CycleDetected { cycle },
+            kind: Box::new(ModuleErrorKind::CycleDetected { cycle }),
             source_location: None,
             dependency_chain: Vec::new(),
             suggestion: Some(
@@ -87,11 +92,11 @@ impl ModuleError {
 
     pub fn parse_error(message: String, file: PathBuf, line: usize, column: usize) -> Self {
         Self {
-            kind: ModuleErrorKind::ParseError {
+            kind: Box::new(ModuleErrorKind::ParseError {
                 message,
                 line,
                 column,
-            },
+            }),
             source_location: Some(SourceLocation { file, line, column }),
             dependency_chain: Vec::new(),
             suggestion: None,
@@ -103,21 +108,22 @@ impl ModuleError {
         module_path: String,
         available: Vec<String>,
     ) -> Self {
+        let suggestion = Some(format!("Available exports: {}", available.join(", ")));
         Self {
-            kind: ModuleErrorKind::ExportError {
+            kind: Box::new(ModuleErrorKind::ExportError {
                 export_name,
                 module_path,
                 available_exports: available,
-            },
+            }),
             source_location: None,
             dependency_chain: Vec::new(),
-            suggestion: Some(format!("Available exports: {}", available.join(", "))),
+            suggestion,
         }
     }
 
     pub fn unsupported_specifier(spec: String) -> Self {
         Self {
-            kind: ModuleErrorKind::UnsupportedSpec(spec),
+            kind: Box::new(ModuleErrorKind::UnsupportedSpec(spec)),
             source_location: None,
             dependency_chain: Vec::new(),
             suggestion: Some("Use relative paths (./, ../) for local imports".to_string()),
@@ -126,22 +132,30 @@ impl ModuleError {
 
     pub fn unsupported_assertion(assertion_type: String, specifier: String) -> Self {
         Self {
-            kind: ModuleErrorKind::UnsupportedAssertion {
+            kind: Box::new(ModuleErrorKind::UnsupportedAssertion {
                 assertion_type,
                 specifier,
-            },
+            }),
             source_location: None,
             dependency_chain: Vec::new(),
             suggestion: Some(
-                "Import assertions are parsed but not enforced. The assertion will be ignored."
-                    .to_string(),
+                "Supported import types are \"json\", \"text\" and \"bytes\"".to_string(),
             ),
         }
     }
 
     pub fn io_error(path: PathBuf, message: String) -> Self {
         Self {
-            kind: ModuleErrorKind::IOError { path, message },
+            kind: Box::new(ModuleErrorKind::IOError { path, message }),
+            source_location: None,
+            dependency_chain: Vec::new(),
+            suggestion: None,
+        }
+    }
+
+    pub fn compile_error(path: PathBuf, message: String) -> Self {
+        Self {
+            kind: Box::new(ModuleErrorKind::CompileError { path, message }),
             source_location: None,
             dependency_chain: Vec::new(),
             suggestion: None,
@@ -157,11 +171,16 @@ impl ModuleError {
         self.source_location = Some(loc);
         self
     }
+
+    pub fn with_suggestion<S: Into<String>>(mut self, suggestion: S) -> Self {
+        self.suggestion = Some(suggestion.into());
+        self
+    }
 }
 
 impl fmt::Display for ModuleError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match &self.kind {
+        match &*self.kind {
             ModuleErrorKind::NotFound {
                 specifier,
                 tried_paths,
@@ -217,13 +236,16 @@ impl fmt::Display for ModuleError {
             } => {
                 writeln!(
                     f,
-                    "Warning: Import assertion '{}' for '{}' is not fully supported",
+                    "Unsupported import type '{}' for '{}'",
                     assertion_type, specifier
                 )?;
             }
             ModuleErrorKind::IOError { path, message } => {
                 writeln!(f, "IO error reading '{}': {}", path.display(), message)?;
             }
+            ModuleErrorKind::CompileError { path, message } => {
+                writeln!(f, "Failed to compile '{}': {}", path.display(), message)?;
+            }
         }
 
         if !self.dependency_chain.is_empty() {
@@ -1,30 +1,18 @@
+//! ES module resolution
+//!
+//! - `resolver.rs` - Maps import specifiers to files (relative, absolute, bare)
+//! - `package.rs` - Package manifests (`exports`/`main`) and import maps
+//! - `diagnostics.rs` - Resolution errors with tried paths and suggestions
+//!
+//! Loading and linking happen in the VM (`vm/module_loader.rs`), which
+//! resolves through [`ModuleResolver`]. That replaced the async
+//! `module::loader::ModuleLoader::load` of earlier versions: a static import
+//! goes through `VM::import_module`, which links cyclic graphs with live
+//! bindings, and `import()` through `load_dynamic_import`, which settles a
+//! promise once the graph has loaded.
+
 pub mod diagnostics;
-pub mod loader;
+pub mod package;
 pub mod resolver;
 
-pub use diagnostics::{DependencyInfo, ModuleError, ModuleErrorKind, ModuleResult, SourceLocation};
-pub use loader::{
-    LoadedModule, ModuleCache, ModuleExport, ModuleImport, ModuleLoader, ModuleNamespace,
-    ModuleValue, ParsedModule,
-};
-pub use resolver::{ImportAssertions, ModuleResolver, ResolvedModule};
-
-use std::path::PathBuf;
-use std::sync::Arc;
-
-pub async fn load_module(entry: &str) -> ModuleResult<Arc<loader::LoadedModule>> {
-    let mut loader = loader::ModuleLoader::new();
-    let entry_path = PathBuf::from(entry);
-    loader.load(&entry_path).await
-}
-
-pub fn load_module_sync(entry: &str) -> ModuleResult<Arc<loader::LoadedModule>> {
-    let runtime = tokio::runtime::Runtime::new()
-        .map_err(|e| diagnostics::ModuleError::io_error(PathBuf::from(entry), e.to_string()))?;
-
-    runtime.block_on(async {
-        let mut loader = loader::ModuleLoader::new();
-        let entry_path = PathBuf::from(entry);
-        loader.load(&entry_path).await
-    })
-}
+pub use resolver::ModuleResolver;
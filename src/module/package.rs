@@ -0,0 +1,361 @@
+//! Package manifests and import maps
+//!
+//! Bare specifiers (`"lodash"`, `"@rolls/http/client"`) resolve through:
+//! - an import map (`{"imports": {...}, "scopes": {...}}`)
+//! - a package manifest's `exports` field, matched against export conditions
+//! - the manifest's `main` field, then `index.*`
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+use crate::module::diagnostics::{ModuleError, ModuleResult};
+use crate::module::resolver::join_relative;
+
+/// File name of a package manifest
+pub const MANIFEST_FILE: &str = "package.json";
+
+/// Read and parse a JSON file
+pub(crate) fn read_json(path: &Path) -> ModuleResult<Value> {
+    let text =
+        fs::read_to_string(path).map_err(|e| ModuleError::io_error(path.into(), e.to_string()))?;
+    serde_json::from_str(&text)
+        .map_err(|e| ModuleError::io_error(path.into(), format!("invalid JSON: {}", e)))
+}
+
+/// Split a bare specifier into package name and subpath.
+///
+/// `"@rolls/http/client"` -> `("@rolls/http", "./client")`, `"lodash"` -> `("lodash", ".")`
+pub fn split_package_specifier(specifier: &str) -> Option<(&str, String)> {
+    let mut parts = specifier.splitn(3, '/');
+    let first = parts.next()?;
+    let name_len = if first.starts_with('@') {
+        let second = parts.next().filter(|s| !s.is_empty())?;
+        first.len() + 1 + second.len()
+    } else {
+        first.len()
+    };
+    if first.is_empty() || first == "@" {
+        return None;
+    }
+
+    let name = &specifier[..name_len];
+    let rest = &specifier[name_len..];
+    let subpath = if rest.is_empty() {
+        ".".to_string()
+    } else {
+        format!(".{}", rest)
+    };
+    Some((name, subpath))
+}
+
+/// Outcome of looking a subpath up in a manifest's `exports`
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExportTarget {
+    /// The manifest has no `exports` field
+    NoExports,
+    /// Package-relative path of the matched export
+    Path(String),
+    /// `exports` exists but does not expose the subpath
+    NotExported,
+}
+
+/// The parts of a package manifest used for resolution
+#[derive(Debug, Clone, Default)]
+pub struct PackageManifest {
+    pub name: Option<String>,
+    pub main: Option<String>,
+    pub exports: Option<Value>,
+}
+
+impl PackageManifest {
+    pub fn load(path: &Path) -> ModuleResult<Self> {
+        Ok(Self::from_json(&read_json(path)?))
+    }
+
+    pub fn from_json(json: &Value) -> Self {
+        Self {
+            name: json.get("name").and_then(Value::as_str).map(str::to_string),
+            main: json.get("main").and_then(Value::as_str).map(str::to_string),
+            exports: json.get("exports").cloned(),
+        }
+    }
+
+    /// Map a subpath (`"."` or `"./feature"`) through `exports`
+    pub fn resolve_export(&self, subpath: &str, conditions: &[String]) -> ExportTarget {
+        let Some(exports) = &self.exports else {
+            return ExportTarget::NoExports;
+        };
+
+        // `"exports": "./x"` and a bare conditions object both describe "."
+        let is_subpath_map = matches!(exports, Value::Object(map)
+            if map.keys().next().is_some_and(|k| k.starts_with('.')));
+        let target = if is_subpath_map {
+            lookup_subpath(exports.as_object().unwrap(), subpath, conditions)
+        } else if subpath == "." {
+            resolve_target(exports, None, conditions)
+        } else {
+            None
+        };
+
+        match target {
+            Some(path) => ExportTarget::Path(path),
+            None => ExportTarget::NotExported,
+        }
+    }
+}
+
+/// Find the entry for `subpath`: an exact key, else the `*` pattern with the longest prefix
+fn lookup_subpath(
+    map: &serde_json::Map<String, Value>,
+    subpath: &str,
+    conditions: &[String],
+) -> Option<String> {
+    if let Some(target) = map.get(subpath) {
+        return resolve_target(target, None, conditions);
+    }
+
+    let mut best: Option<(&str, &Value, &str)> = None;
+    for (key, target) in map {
+        let Some((prefix, suffix)) = key.split_once('*') else {
+            continue;
+        };
+        if subpath.len() >= prefix.len() + suffix.len()
+            && subpath.starts_with(prefix)
+            && subpath.ends_with(suffix)
+            && best.is_none_or(|(p, _, _)| prefix.len() > p.len())
+        {
+            let matched = &subpath[prefix.len()..subpath.len() - suffix.len()];
+            best = Some((prefix, target, matched));
+        }
+    }
+    best.and_then(|(_, target, matched)| resolve_target(target, Some(matched), conditions))
+}
+
+/// Resolve an export target: a path, a fallback array, or a conditions object.
+///
+/// Conditions are tried in the object's key order; `default` always matches.
+fn resolve_target(target: &Value, matched: Option<&str>, conditions: &[String]) -> Option<String> {
+    match target {
+        Value::String(path) => Some(match matched {
+            Some(m) => path.replace('*', m),
+            None => path.clone(),
+        }),
+        Value::Array(fallbacks) => fallbacks
+            .iter()
+            .find_map(|t| resolve_target(t, matched, conditions)),
+        Value::Object(map) => map.iter().find_map(|(condition, t)| {
+            if condition == "default" || conditions.iter().any(|c| c == condition) {
+                resolve_target(t, matched, conditions)
+            } else {
+                None
+            }
+        }),
+        _ => None,
+    }
+}
+
+/// An import map: `imports` apply everywhere, `scopes` only to importers under the scope
+#[derive(Debug, Clone, Default)]
+pub struct ImportMap {
+    imports: Vec<(String, String)>,
+    /// Scope directories, longest first
+    scopes: Vec<(PathBuf, Vec<(String, String)>)>,
+    /// Directory relative targets are resolved against
+    base: PathBuf,
+}
+
+/// Result of mapping a specifier through an import map
+#[derive(Debug, Clone, PartialEq)]
+pub enum MappedSpecifier {
+    /// A file path (from a relative or absolute target)
+    Path(PathBuf),
+    /// Another bare specifier, resolved as a package
+    Bare(String),
+}
+
+impl ImportMap {
+    pub fn load(path: &Path) -> ModuleResult<Self> {
+        let base = path.parent().unwrap_or(Path::new(".")).to_path_buf();
+        Ok(Self::from_json(&read_json(path)?, base))
+    }
+
+    pub fn from_json(json: &Value, base: PathBuf) -> Self {
+        fn entries(value: Option<&Value>) -> Vec<(String, String)> {
+            value
+                .and_then(Value::as_object)
+                .map(|map| {
+                    map.iter()
+                        .filter_map(|(k, v)| Some((k.clone(), v.as_str()?.to_string())))
+                        .collect()
+                })
+                .unwrap_or_default()
+        }
+
+        let mut scopes: Vec<(PathBuf, Vec<(String, String)>)> = json
+            .get("scopes")
+            .and_then(Value::as_object)
+            .map(|map| {
+                map.iter()
+                    .map(|(scope, v)| (join_relative(&base, scope), entries(Some(v))))
+                    .collect()
+            })
+            .unwrap_or_default();
+        scopes.sort_by_key(|(scope, _)| std::cmp::Reverse(scope.as_os_str().len()));
+
+        Self {
+            imports: entries(json.get("imports")),
+            scopes,
+            base,
+        }
+    }
+
+    /// Map `specifier` as imported from `importer`, if an entry matches
+    pub fn lookup(&self, specifier: &str, importer: &Path) -> Option<MappedSpecifier> {
+        self.scopes
+            .iter()
+            .filter(|(scope, _)| importer.starts_with(scope))
+            .find_map(|(_, entries)| Self::lookup_in(entries, specifier))
+            .or_else(|| Self::lookup_in(&self.imports, specifier))
+            .map(|target| {
+                if target.starts_with("./") || target.starts_with("../") {
+                    MappedSpecifier::Path(join_relative(&self.base, &target))
+                } else if target.starts_with('/') {
+                    MappedSpecifier::Path(PathBuf::from(target))
+                } else {
+                    MappedSpecifier::Bare(target)
+                }
+            })
+    }
+
+    /// Exact match first, then the longest `prefix/` entry
+    fn lookup_in(entries: &[(String, String)], specifier: &str) -> Option<String> {
+        if let Some((_, target)) = entries.iter().find(|(key, _)| key == specifier) {
+            return Some(target.clone());
+        }
+        entries
+            .iter()
+            .filter(|(key, _)| key.ends_with('/') && specifier.starts_with(key.as_str()))
+            .max_by_key(|(key, _)| key.len())
+            .map(|(key, target)| format!("{}{}", target, &specifier[key.len()..]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn conditions() -> Vec<String> {
+        vec!["oite".to_string(), "import".to_string()]
+    }
+
+    #[test]
+    fn test_split_package_specifier() {
+        assert_eq!(
+            split_package_specifier("lodash"),
+            Some(("lodash", ".".to_string()))
+        );
+        assert_eq!(
+            split_package_specifier("lodash/fp/map"),
+            Some(("lodash", "./fp/map".to_string()))
+        );
+        assert_eq!(
+            split_package_specifier("@rolls/http"),
+            Some(("@rolls/http", ".".to_string()))
+        );
+        assert_eq!(
+            split_package_specifier("@rolls/http/client"),
+            Some(("@rolls/http", "./client".to_string()))
+        );
+        assert_eq!(split_package_specifier("@rolls"), None);
+    }
+
+    #[test]
+    fn test_exports_conditions_in_key_order() {
+        let manifest = PackageManifest::from_json(&json!({
+            "exports": {
+                ".": { "require": "./cjs.js", "import": "./esm.js", "default": "./any.js" },
+                "./client": "./src/client.ot",
+                "./feature/*": { "oite": "./src/feature/*.ot" },
+                "./internal/*": null
+            }
+        }));
+        let c = conditions();
+        assert_eq!(
+            manifest.resolve_export(".", &c),
+            ExportTarget::Path("./esm.js".to_string())
+        );
+        assert_eq!(
+            manifest.resolve_export("./client", &c),
+            ExportTarget::Path("./src/client.ot".to_string())
+        );
+        assert_eq!(
+            manifest.resolve_export("./feature/a", &c),
+            ExportTarget::Path("./src/feature/a.ot".to_string())
+        );
+        assert_eq!(
+            manifest.resolve_export("./internal/x", &c),
+            ExportTarget::NotExported
+        );
+        assert_eq!(
+            manifest.resolve_export("./missing", &c),
+            ExportTarget::NotExported
+        );
+    }
+
+    #[test]
+    fn test_exports_shorthand() {
+        let manifest = PackageManifest::from_json(&json!({ "exports": "./main.ot" }));
+        assert_eq!(
+            manifest.resolve_export(".", &conditions()),
+            ExportTarget::Path("./main.ot".to_string())
+        );
+        assert_eq!(
+            manifest.resolve_export("./x", &conditions()),
+            ExportTarget::NotExported
+        );
+
+        let manifest = PackageManifest::from_json(&json!({ "main": "lib.ot" }));
+        assert_eq!(
+            manifest.resolve_export(".", &conditions()),
+            ExportTarget::NoExports
+        );
+    }
+
+    #[test]
+    fn test_import_map_lookup() {
+        let map = ImportMap::from_json(
+            &json!({
+                "imports": {
+                    "utils": "./lib/utils.ot",
+                    "utils/": "./lib/utils/",
+                    "http": "@rolls/http"
+                },
+                "scopes": { "./vendor/": { "utils": "./vendor/utils.ot" } }
+            }),
+            PathBuf::from("/app"),
+        );
+        let importer = Path::new("/app/src/main.ot");
+        assert_eq!(
+            map.lookup("utils", importer),
+            Some(MappedSpecifier::Path(PathBuf::from("/app/lib/utils.ot")))
+        );
+        assert_eq!(
+            map.lookup("utils/string", importer),
+            Some(MappedSpecifier::Path(PathBuf::from(
+                "/app/lib/utils/string"
+            )))
+        );
+        assert_eq!(
+            map.lookup("http", importer),
+            Some(MappedSpecifier::Bare("@rolls/http".to_string()))
+        );
+        assert_eq!(
+            map.lookup("utils", Path::new("/app/vendor/x.ot")),
+            Some(MappedSpecifier::Path(PathBuf::from("/app/vendor/utils.ot")))
+        );
+        assert_eq!(map.lookup("lodash", importer), None);
+    }
+}
@@ -1,9 +1,33 @@
 use std::fs;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 use std::sync::Arc;
 
-use crate::module::diagnostics::ModuleErrorKind;
+use serde_json::Value;
+
 use crate::module::diagnostics::{ModuleError, ModuleResult};
+use crate::module::package::{
+    ExportTarget, ImportMap, MANIFEST_FILE, MappedSpecifier, PackageManifest, read_json,
+    split_package_specifier,
+};
+
+/// Join a `/`-separated relative specifier onto `base`, folding `.` and `..`
+pub(crate) fn join_relative(base: &Path, relative: &str) -> PathBuf {
+    let mut path = base.to_path_buf();
+    for component in relative.split('/') {
+        match component {
+            "" | "." => {}
+            ".." => {
+                if matches!(path.components().next_back(), Some(Component::Normal(_))) {
+                    path.pop();
+                } else {
+                    path.push("..");
+                }
+            }
+            _ => path.push(component),
+        }
+    }
+    path
+}
 
 #[derive(Debug, Clone)]
 pub struct ResolvedModule {
@@ -42,7 +66,11 @@ impl ResolvedModule {
 #[derive(Debug, Clone, PartialEq)]
 pub enum ImportAssertions {
     TypeOnly,
-    JSON,
+    Json,
+    /// `type: "text"`: the file's contents as a string
+    Text,
+    /// `type: "bytes"`: the file's raw bytes
+    Bytes,
     Custom(Vec<(String, String)>),
 }
 
@@ -52,13 +80,32 @@ impl ImportAssertions {
     }
 
     pub fn is_json(&self) -> bool {
-        matches!(self, ImportAssertions::JSON)
+        matches!(self, ImportAssertions::Json)
+    }
+
+    /// The import loads a data file instead of a module
+    pub fn is_asset(&self) -> bool {
+        matches!(
+            self,
+            ImportAssertions::Json | ImportAssertions::Text | ImportAssertions::Bytes
+        )
     }
 }
 
+/// Project configuration file read by [`ModuleResolver::discover`]
+pub const PROJECT_FILE: &str = "oite.json";
+
 pub struct ModuleResolver {
     extensions: [&'static str; 3],
     base_paths: Vec<PathBuf>,
+    /// Directory names searched for packages, walking up from the importer
+    package_dirs: Vec<String>,
+    /// Conditions matched against a manifest's `exports`, in priority order
+    conditions: Vec<String>,
+    import_map: Option<ImportMap>,
+    /// `paths` aliases as (pattern, replacements), relative to `paths_base`
+    paths: Vec<(String, Vec<String>)>,
+    paths_base: PathBuf,
 }
 
 impl Default for ModuleResolver {
@@ -72,7 +119,81 @@ impl ModuleResolver {
         Self {
             extensions: [".ot", ".ts", ".js"],
             base_paths: Vec::new(),
+            package_dirs: vec!["node_modules".to_string()],
+            conditions: vec!["oite".to_string(), "import".to_string()],
+            import_map: None,
+            paths: Vec::new(),
+            paths_base: PathBuf::from("."),
+        }
+    }
+
+    /// Build a resolver from the nearest `oite.json` at or above `start`.
+    ///
+    /// ```json
+    /// {
+    ///   "importMap": "./import_map.json",
+    ///   "baseUrl": ".",
+    ///   "paths": { "@app/*": ["src/*"] },
+    ///   "conditions": ["browser"]
+    /// }
+    /// ```
+    ///
+    /// `importMap` may also be an inline map. Without a project file this is
+    /// [`ModuleResolver::new`].
+    pub fn discover(start: &Path) -> ModuleResult<Self> {
+        let mut resolver = Self::new();
+        let Some(config_path) = start
+            .ancestors()
+            .map(|dir| dir.join(PROJECT_FILE))
+            .find(|path| path.is_file())
+        else {
+            return Ok(resolver);
+        };
+        let project_dir = config_path.parent().unwrap_or(Path::new("."));
+        let config = read_json(&config_path)?;
+
+        match config.get("importMap") {
+            Some(Value::String(path)) => {
+                resolver.import_map = Some(ImportMap::load(&join_relative(project_dir, path))?);
+            }
+            Some(inline @ Value::Object(_)) => {
+                resolver.import_map = Some(ImportMap::from_json(inline, project_dir.into()));
+            }
+            _ => {}
         }
+
+        if let Some(Value::Object(paths)) = config.get("paths") {
+            let base = match config.get("baseUrl").and_then(Value::as_str) {
+                Some(base_url) => join_relative(project_dir, base_url),
+                None => project_dir.to_path_buf(),
+            };
+            let aliases = paths
+                .iter()
+                .map(|(pattern, targets)| {
+                    let targets = match targets {
+                        Value::String(t) => vec![t.clone()],
+                        Value::Array(ts) => ts
+                            .iter()
+                            .filter_map(|t| t.as_str().map(str::to_string))
+                            .collect(),
+                        _ => Vec::new(),
+                    };
+                    (pattern.clone(), targets)
+                })
+                .collect();
+            resolver = resolver.with_paths(base, aliases);
+        }
+
+        if let Some(Value::Array(extra)) = config.get("conditions") {
+            let mut conditions: Vec<String> = extra
+                .iter()
+                .filter_map(|c| c.as_str().map(str::to_string))
+                .collect();
+            conditions.append(&mut resolver.conditions);
+            resolver.conditions = conditions;
+        }
+
+        Ok(resolver)
     }
 
     pub fn with_base_path<P: Into<PathBuf>>(mut self, path: P) -> Self {
@@ -80,8 +201,36 @@ impl ModuleResolver {
         self
     }
 
+    /// Also search `name` directories (in addition to `node_modules`) for packages
+    pub fn with_package_dir<S: Into<String>>(mut self, name: S) -> Self {
+        self.package_dirs.push(name.into());
+        self
+    }
+
+    /// Set the export conditions, highest priority first (`default` always matches)
+    pub fn with_conditions(mut self, conditions: Vec<String>) -> Self {
+        self.conditions = conditions;
+        self
+    }
+
+    pub fn with_import_map(mut self, import_map: ImportMap) -> Self {
+        self.import_map = Some(import_map);
+        self
+    }
+
+    /// Set `paths` aliases; a `*` in a pattern is substituted into its targets
+    pub fn with_paths<P: Into<PathBuf>>(
+        mut self,
+        base: P,
+        aliases: Vec<(String, Vec<String>)>,
+    ) -> Self {
+        self.paths_base = base.into();
+        self.paths = aliases;
+        self
+    }
+
     pub fn resolve(&self, specifier: &str, importer: &Path) -> ModuleResult<ResolvedModule> {
-        if specifier.is_empty() {
+        if specifier.is_empty() || specifier.contains("://") {
             return Err(ModuleError::unsupported_specifier(specifier.to_string()));
         }
 
@@ -89,152 +238,260 @@ impl ModuleResolver {
 
         match first_char {
             '.' => self.resolve_relative(specifier, importer),
-            _ => Err(ModuleError::unsupported_specifier(specifier.to_string())),
+            '/' => {
+                let mut tried_paths = Vec::new();
+                match self.try_file(Path::new(specifier), &mut tried_paths) {
+                    Some(path) => self.resolved(path, specifier),
+                    None => Err(ModuleError::not_found(specifier.to_string(), tried_paths)),
+                }
+            }
+            _ => self.resolve_bare(specifier, importer),
         }
     }
 
-    fn resolve_relative(&self, specifier: &str, importer: &Path) -> ModuleResult<ResolvedModule> {
-        let importer_dir = if importer.is_file() {
+    fn importer_dir(importer: &Path) -> &Path {
+        if importer.is_file() {
             importer.parent().unwrap_or(Path::new("."))
         } else {
             importer
+        }
+    }
+
+    fn resolved(&self, path: PathBuf, specifier: &str) -> ModuleResult<ResolvedModule> {
+        let canonical =
+            fs::canonicalize(&path).map_err(|e| ModuleError::io_error(path, e.to_string()))?;
+        Ok(ResolvedModule::new(
+            canonical,
+            specifier.to_string(),
+            false,
+            None,
+        ))
+    }
+
+    fn resolve_relative(&self, specifier: &str, importer: &Path) -> ModuleResult<ResolvedModule> {
+        let path = join_relative(Self::importer_dir(importer), specifier);
+        let mut tried_paths = Vec::new();
+
+        let found = if specifier.ends_with('/') {
+            self.try_index(&path, &mut tried_paths)
+        } else {
+            self.try_file(&path, &mut tried_paths)
         };
 
-        let mut path = importer_dir.to_path_buf();
+        match found {
+            Some(path) => self.resolved(path, specifier),
+            None => Err(ModuleError::not_found(specifier.to_string(), tried_paths)),
+        }
+    }
+
+    /// Resolve a bare specifier: import map, then `paths` aliases, then packages,
+    /// then base paths
+    fn resolve_bare(&self, specifier: &str, importer: &Path) -> ModuleResult<ResolvedModule> {
         let mut tried_paths = Vec::new();
+        let mut name = specifier.to_string();
+
+        if let Some(import_map) = &self.import_map {
+            match import_map.lookup(specifier, importer) {
+                Some(MappedSpecifier::Path(path)) => {
+                    return match self.try_file(&path, &mut tried_paths) {
+                        Some(path) => self.resolved(path, specifier),
+                        None => Err(ModuleError::not_found(specifier.to_string(), tried_paths)),
+                    };
+                }
+                Some(MappedSpecifier::Bare(mapped)) => name = mapped,
+                None => {}
+            }
+        }
+
+        if let Some(path) = self.resolve_alias(&name, &mut tried_paths) {
+            return self.resolved(path, specifier);
+        }
 
-        for component in specifier.split('/') {
-            match component {
-                "." => {}
-                ".." => {
-                    if !path.as_os_str().is_empty() {
-                        path.pop();
+        if let Some((package, subpath)) = split_package_specifier(&name) {
+            for dir in Self::importer_dir(importer).ancestors() {
+                for package_dir in &self.package_dirs {
+                    let package_root = dir.join(package_dir).join(package);
+                    if !package_root.is_dir() {
+                        tried_paths.push(package_root.display().to_string());
+                        continue;
                     }
+                    // The nearest installed copy of a package wins, found or not
+                    return match self.resolve_package(&package_root, &subpath, &mut tried_paths)? {
+                        Some(path) => self.resolved(path, specifier),
+                        None => Err(ModuleError::not_found(specifier.to_string(), tried_paths)
+                            .with_suggestion(format!(
+                                "Check that package '{}' provides '{}' (its `exports` or `main` in {})",
+                                package, subpath, MANIFEST_FILE
+                            ))),
+                    };
                 }
-                "" if specifier.starts_with("./") => {}
-                "" if specifier.starts_with("../") => {}
-                _ => path.push(component),
-            };
+            }
         }
 
-        if path.as_os_str().is_empty() || specifier.ends_with('/') {
-            for ext in self.extensions {
-                let index_path = path.join("index").with_extension(&ext[1..]);
-                tried_paths.push(index_path.display().to_string());
-                if index_path.exists() {
-                    let canonical = fs::canonicalize(&index_path)?;
-                    return Ok(ResolvedModule::new(
-                        canonical,
-                        specifier.to_string(),
-                        false,
-                        None,
-                    ));
+        for base in &self.base_paths {
+            if let Some(path) = self.try_file(&join_relative(base, &name), &mut tried_paths) {
+                return self.resolved(path, specifier);
+            }
+        }
+
+        Err(
+            ModuleError::not_found(specifier.to_string(), tried_paths).with_suggestion(format!(
+                "Install the package into node_modules, or map it with an import map or `paths` alias in {}",
+                PROJECT_FILE
+            )),
+        )
+    }
+
+    /// Try the `paths` alias with an exact match, else the longest matching prefix
+    fn resolve_alias(&self, specifier: &str, tried_paths: &mut Vec<String>) -> Option<PathBuf> {
+        let (targets, matched) = self
+            .paths
+            .iter()
+            .find(|(pattern, _)| pattern == specifier)
+            .map(|(_, targets)| (targets, ""))
+            .or_else(|| {
+                self.paths
+                    .iter()
+                    .filter_map(|(pattern, targets)| {
+                        let (prefix, suffix) = pattern.split_once('*')?;
+                        let matched = specifier
+                            .strip_prefix(prefix)?
+                            .strip_suffix(suffix)
+                            .filter(|_| specifier.len() >= prefix.len() + suffix.len())?;
+                        Some((prefix.len(), targets, matched))
+                    })
+                    .max_by_key(|(prefix_len, _, _)| *prefix_len)
+                    .map(|(_, targets, matched)| (targets, matched))
+            })?;
+
+        targets.iter().find_map(|target| {
+            let path = join_relative(&self.paths_base, &target.replace('*', matched));
+            self.try_file(&path, tried_paths)
+        })
+    }
+
+    /// Resolve `subpath` inside an installed package via its manifest
+    fn resolve_package(
+        &self,
+        package_root: &Path,
+        subpath: &str,
+        tried_paths: &mut Vec<String>,
+    ) -> ModuleResult<Option<PathBuf>> {
+        let manifest_path = package_root.join(MANIFEST_FILE);
+        let manifest = if manifest_path.is_file() {
+            PackageManifest::load(&manifest_path)?
+        } else {
+            PackageManifest::default()
+        };
+
+        match manifest.resolve_export(subpath, &self.conditions) {
+            ExportTarget::Path(target) => {
+                let path = join_relative(package_root, &target);
+                tried_paths.push(path.display().to_string());
+                Ok(path.is_file().then_some(path))
+            }
+            ExportTarget::NotExported => {
+                tried_paths.push(format!(
+                    "{} (\"{}\" is not exported)",
+                    manifest_path.display(),
+                    subpath
+                ));
+                Ok(None)
+            }
+            ExportTarget::NoExports if subpath == "." => {
+                if let Some(main) = &manifest.main
+                    && let Some(path) =
+                        self.try_file(&join_relative(package_root, main), tried_paths)
+                {
+                    return Ok(Some(path));
                 }
+                Ok(self.try_index(package_root, tried_paths))
             }
-            return Err(ModuleError::not_found(specifier.to_string(), tried_paths));
+            ExportTarget::NoExports => {
+                Ok(self.try_file(&join_relative(package_root, subpath), tried_paths))
+            }
+        }
+    }
+
+    /// Try `path` as a file, with each extension appended, then as a directory index
+    fn try_file(&self, path: &Path, tried_paths: &mut Vec<String>) -> Option<PathBuf> {
+        tried_paths.push(path.display().to_string());
+        if path.is_file() {
+            return Some(path.to_path_buf());
         }
 
         for ext in self.extensions {
-            let with_ext = path.with_extension(&ext[1..]);
+            let mut with_ext = path.as_os_str().to_owned();
+            with_ext.push(ext);
+            let with_ext = PathBuf::from(with_ext);
             tried_paths.push(with_ext.display().to_string());
-            if with_ext.exists() {
-                let canonical = fs::canonicalize(&with_ext)?;
-                return Ok(ResolvedModule::new(
-                    canonical,
-                    specifier.to_string(),
-                    false,
-                    None,
-                ));
+            if with_ext.is_file() {
+                return Some(with_ext);
             }
         }
 
-        if path.exists() && path.is_dir() {
-            for ext in self.extensions {
-                let index_path = path.join("index").with_extension(&ext[1..]);
-                tried_paths.push(index_path.display().to_string());
-                if index_path.exists() {
-                    let canonical = fs::canonicalize(&index_path)?;
-                    return Ok(ResolvedModule::new(
-                        canonical,
-                        specifier.to_string(),
-                        false,
-                        None,
-                    ));
-                }
-            }
+        if path.is_dir() {
+            return self.try_index(path, tried_paths);
         }
+        None
+    }
 
-        Err(ModuleError::not_found(specifier.to_string(), tried_paths))
+    fn try_index(&self, dir: &Path, tried_paths: &mut Vec<String>) -> Option<PathBuf> {
+        for ext in self.extensions {
+            let index_path = dir.join(format!("index{}", ext));
+            tried_paths.push(index_path.display().to_string());
+            if index_path.is_file() {
+                return Some(index_path);
+            }
+        }
+        None
     }
 
     pub fn parse_import_assertions(
         &self,
         with: Option<&swc_ecma_ast::ObjectLit>,
     ) -> Option<ImportAssertions> {
-        if with.is_none() {
-            return None;
-        }
+        use swc_ecma_ast::{Expr, Lit, Prop, PropName, PropOrSpread};
 
-        let with = with.unwrap();
-
-        for prop in &with.props {
-            if let swc_ecma_ast::PropOrSpread::Prop(prop) = prop {
-                if let swc_ecma_ast::Prop::KeyValue(kv) = prop.as_ref() {
-                    if let swc_ecma_ast::PropName::Str(key) = &kv.key {
-                        if key.value == "type" {
-                            if let swc_ecma_ast::Expr::Lit(lit) = kv.value.as_ref() {
-                                if let swc_ecma_ast::Lit::Str(str_lit) = lit {
-                                    match str_lit.value.as_str() {
-                                        "json" => return Some(ImportAssertions::JSON),
-                                        "typescript" | "ts" => {
-                                            return Some(ImportAssertions::TypeOnly);
-                                        }
-                                        _ => {}
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
-
-        let custom_assertions: Vec<(String, String)> = with
+        let entries: Vec<(String, String)> = with?
             .props
             .iter()
             .filter_map(|prop| {
-                if let swc_ecma_ast::PropOrSpread::Prop(prop) = prop {
-                    if let swc_ecma_ast::Prop::KeyValue(kv) = prop.as_ref() {
-                        let key = match &kv.key {
-                            swc_ecma_ast::PropName::Str(s) => {
-                                s.value.to_string_lossy().into_owned()
-                            }
-                            swc_ecma_ast::PropName::Ident(i) => i.sym.to_string(),
-                            _ => return None,
-                        };
-                        let value = match kv.value.as_ref() {
-                            swc_ecma_ast::Expr::Lit(lit) => match lit {
-                                swc_ecma_ast::Lit::Str(s) => s.value.to_string_lossy().into_owned(),
-                                swc_ecma_ast::Lit::Bool(b) => b.value.to_string(),
-                                swc_ecma_ast::Lit::Num(n) => n.value.to_string(),
-                                _ => return None,
-                            },
-                            _ => return None,
-                        };
-                        Some((key, value))
-                    } else {
-                        None
-                    }
-                } else {
-                    None
-                }
+                let PropOrSpread::Prop(prop) = prop else {
+                    return None;
+                };
+                let Prop::KeyValue(kv) = prop.as_ref() else {
+                    return None;
+                };
+                let key = match &kv.key {
+                    PropName::Str(s) => s.value.to_string_lossy().into_owned(),
+                    PropName::Ident(i) => i.sym.to_string(),
+                    _ => return None,
+                };
+                let value = match kv.value.as_ref() {
+                    Expr::Lit(Lit::Str(s)) => s.value.to_string_lossy().into_owned(),
+                    Expr::Lit(Lit::Bool(b)) => b.value.to_string(),
+                    Expr::Lit(Lit::Num(n)) => n.value.to_string(),
+                    _ => return None,
+                };
+                Some((key, value))
             })
             .collect();
 
-        if custom_assertions.is_empty() {
+        if let Some((_, ty)) = entries.iter().find(|(key, _)| key == "type") {
+            match ty.as_str() {
+                "json" => return Some(ImportAssertions::Json),
+                "text" => return Some(ImportAssertions::Text),
+                "bytes" => return Some(ImportAssertions::Bytes),
+                "typescript" | "ts" => return Some(ImportAssertions::TypeOnly),
+                _ => {}
+            }
+        }
+
+        if entries.is_empty() {
             None
         } else {
-            Some(ImportAssertions::Custom(custom_assertions))
+            Some(ImportAssertions::Custom(entries))
         }
     }
 }
@@ -242,38 +499,76 @@ impl ModuleResolver {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::module::diagnostics::ModuleErrorKind;
+
+    /// A scratch project tree, removed on drop
+    struct Project(PathBuf);
+
+    impl Project {
+        fn new(name: &str) -> Self {
+            let root =
+                std::env::temp_dir().join(format!("oite_resolver_{}_{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&root);
+            fs::create_dir_all(&root).unwrap();
+            Self(fs::canonicalize(root).unwrap())
+        }
+
+        fn file(&self, path: &str, contents: &str) -> PathBuf {
+            let path = self.0.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, contents).unwrap();
+            path
+        }
+    }
+
+    impl Drop for Project {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn tried_paths(err: ModuleError) -> Vec<String> {
+        match *err.kind {
+            ModuleErrorKind::NotFound { tried_paths, .. } => tried_paths,
+            other => panic!("Expected NotFound, got {:?}", other),
+        }
+    }
 
     #[test]
     fn test_resolve_relative_file() {
-        let resolver = ModuleResolver::new();
-        let importer = PathBuf::from("/project/src/main.ot");
-
-        let result = resolver.resolve("./utils", &importer);
-        assert!(result.is_ok());
+        let project = Project::new("relative");
+        let utils = project.file("src/utils.ot", "");
+        let importer = project.file("src/main.ot", "");
 
-        let resolved = result.unwrap();
+        let resolved = ModuleResolver::new().resolve("./utils", &importer).unwrap();
+        assert_eq!(*resolved.path, utils);
         assert_eq!(resolved.original_specifier, "./utils");
     }
 
     #[test]
     fn test_resolve_parent_directory() {
-        let resolver = ModuleResolver::new();
-        let importer = PathBuf::from("/project/src/utils/helper.ot");
-
-        let result = resolver.resolve("../lib/math", &importer);
-        assert!(result.is_ok());
-
-        let resolved = result.unwrap();
+        let project = Project::new("parent");
+        let math = project.file("src/lib/math/index.ts", "");
+        let importer = project.file("src/utils/helper.ot", "");
+
+        let resolved = ModuleResolver::new()
+            .resolve("../lib/math", &importer)
+            .unwrap();
+        assert_eq!(*resolved.path, math);
         assert_eq!(resolved.original_specifier, "../lib/math");
     }
 
     #[test]
     fn test_resolve_with_extension() {
-        let resolver = ModuleResolver::new();
-        let importer = PathBuf::from("/project/src/main.ot");
-
-        let result = resolver.resolve("./foo.js", &importer);
-        assert!(result.is_ok());
+        let project = Project::new("extension");
+        let foo = project.file("src/foo.js", "");
+        project.file("src/foo.ot", "");
+        let importer = project.file("src/main.ot", "");
+
+        let resolved = ModuleResolver::new()
+            .resolve("./foo.js", &importer)
+            .unwrap();
+        assert_eq!(*resolved.path, foo);
     }
 
     #[test]
@@ -281,14 +576,148 @@ mod tests {
         let resolver = ModuleResolver::new();
         let importer = PathBuf::from("/project/src/main.ot");
 
-        let result = resolver.resolve("react", &importer);
-        assert!(result.is_err());
-
-        if let Err(e) = result {
-            match &e.kind {
+        for specifier in ["", "https://esm.sh/react"] {
+            match resolver
+                .resolve(specifier, &importer)
+                .unwrap_err()
+                .kind
+                .as_ref()
+            {
                 ModuleErrorKind::UnsupportedSpec(_) => {}
-                _ => panic!("Expected UnsupportedSpec error"),
+                other => panic!("Expected UnsupportedSpec error, got {:?}", other),
             }
         }
     }
+
+    #[test]
+    fn test_resolve_package_main_walking_up() {
+        let project = Project::new("main");
+        project.file(
+            "node_modules/lodash/package.json",
+            r#"{"main": "lib/lodash"}"#,
+        );
+        let lodash = project.file("node_modules/lodash/lib/lodash.js", "");
+        let fp = project.file("node_modules/lodash/fp/map.ot", "");
+        let importer = project.file("src/deep/main.ot", "");
+
+        let resolver = ModuleResolver::new();
+        assert_eq!(*resolver.resolve("lodash", &importer).unwrap().path, lodash);
+        assert_eq!(
+            *resolver.resolve("lodash/fp/map", &importer).unwrap().path,
+            fp
+        );
+    }
+
+    #[test]
+    fn test_resolve_scoped_package_exports() {
+        let project = Project::new("exports");
+        project.file(
+            "node_modules/@rolls/http/package.json",
+            r#"{
+                "exports": {
+                    ".": { "require": "./dist/index.cjs", "oite": "./src/index.ot" },
+                    "./client": { "browser": "./src/browser.ot", "default": "./src/client.ot" }
+                }
+            }"#,
+        );
+        let index = project.file("node_modules/@rolls/http/src/index.ot", "");
+        let client = project.file("node_modules/@rolls/http/src/client.ot", "");
+        let browser = project.file("node_modules/@rolls/http/src/browser.ot", "");
+        project.file("node_modules/@rolls/http/src/secret.ot", "");
+        let importer = project.file("main.ot", "");
+
+        let resolver = ModuleResolver::new();
+        assert_eq!(
+            *resolver.resolve("@rolls/http", &importer).unwrap().path,
+            index
+        );
+        assert_eq!(
+            *resolver
+                .resolve("@rolls/http/client", &importer)
+                .unwrap()
+                .path,
+            client
+        );
+        // Not listed in `exports`, even though the file exists
+        assert!(
+            resolver
+                .resolve("@rolls/http/src/secret", &importer)
+                .is_err()
+        );
+
+        let browser_resolver = ModuleResolver::new().with_conditions(vec!["browser".to_string()]);
+        assert_eq!(
+            *browser_resolver
+                .resolve("@rolls/http/client", &importer)
+                .unwrap()
+                .path,
+            browser
+        );
+    }
+
+    #[test]
+    fn test_not_found_lists_tried_paths() {
+        let project = Project::new("not_found");
+        let importer = project.file("src/main.ot", "");
+        let root = &project.0;
+
+        let tried = tried_paths(
+            ModuleResolver::new()
+                .with_base_path(root.join("vendor"))
+                .resolve("missing-pkg", &importer)
+                .unwrap_err(),
+        );
+        let expect = |path: PathBuf| path.display().to_string();
+        assert_eq!(tried[0], expect(root.join("src/node_modules/missing-pkg")));
+        assert_eq!(tried[1], expect(root.join("node_modules/missing-pkg")));
+        assert!(tried.contains(&expect(root.join("vendor/missing-pkg.ot"))));
+    }
+
+    #[test]
+    fn test_discover_import_map_and_paths() {
+        let project = Project::new("discover");
+        project.file(
+            PROJECT_FILE,
+            r#"{
+                "importMap": "./import_map.json",
+                "baseUrl": "src",
+                "paths": { "@app/*": ["app/*", "shared/*"], "config": ["config/index.ot"] }
+            }"#,
+        );
+        project.file(
+            "import_map.json",
+            r#"{ "imports": { "std/": "./vendor/std/", "http": "@rolls/http" } }"#,
+        );
+        let fs_module = project.file("vendor/std/fs.ot", "");
+        project.file("node_modules/@rolls/http/index.ot", "");
+        let shared = project.file("src/shared/log.ot", "");
+        let config = project.file("src/config/index.ot", "");
+        let importer = project.file("src/app/main.ot", "");
+
+        let resolver = ModuleResolver::discover(&importer).unwrap();
+        assert_eq!(
+            *resolver.resolve("std/fs", &importer).unwrap().path,
+            fs_module
+        );
+        assert_eq!(
+            *resolver.resolve("http", &importer).unwrap().path,
+            project.0.join("node_modules/@rolls/http/index.ot")
+        );
+        assert_eq!(
+            *resolver.resolve("@app/log", &importer).unwrap().path,
+            shared
+        );
+        assert_eq!(*resolver.resolve("config", &importer).unwrap().path, config);
+    }
+
+    #[test]
+    fn test_join_relative() {
+        let base = Path::new("/a/b");
+        assert_eq!(join_relative(base, "./c"), PathBuf::from("/a/b/c"));
+        assert_eq!(join_relative(base, "../../c/d"), PathBuf::from("/c/d"));
+        assert_eq!(
+            join_relative(Path::new("."), "../x"),
+            PathBuf::from("./../x")
+        );
+    }
 }
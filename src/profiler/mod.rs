@@ -0,0 +1,620 @@
+//! Profiler for the VM interpreter and JIT
+//!
+//! `oitec profile <file>` steps the VM one instruction at a time, tracking
+//! call frames to record per-function call counts, inclusive and exclusive
+//! time, and per-opcode execution counts. With `--jit`, the program is
+//! compiled with the Cranelift JIT, with every compiled function wrapped in
+//! calls that report its entry and exit, and time is attributed per symbol.
+//!
+//! Results are written as folded stacks (`frame;frame;frame <nanoseconds>`),
+//! which `flamegraph.pl` and `inferno-flamegraph` accept directly, and a
+//! top-N table is printed to stderr.
+
+use crate::backend::BackendConfig;
+use crate::backend::jit::{self, CompiledFunction, JitRuntime};
+use crate::compiler::source_map::BytecodeSourceMap;
+use crate::compiler::{Compiler, syntax_for_path};
+use crate::ir;
+use crate::vm::VM;
+use crate::vm::opcodes::OpCode;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::mem::Discriminant;
+use std::path::Path;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+/// Default number of rows in the top-N table
+const DEFAULT_TOP: usize = 20;
+
+/// Default output path for folded stacks
+const DEFAULT_OUTPUT: &str = "oite-profile.folded";
+
+/// Root frame name for top-level code
+const MAIN_FRAME: &str = "<main>";
+
+/// Frame name for timers and tasks run after the main script
+const EVENT_LOOP_FRAME: &str = "<event loop>";
+
+/// Aggregated timing for one function
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FunctionProfile {
+    pub name: String,
+    pub calls: u64,
+    /// Time spent in the function and its callees
+    pub inclusive: Duration,
+    /// Time spent in the function's own instructions
+    pub exclusive: Duration,
+}
+
+#[derive(Debug, Default)]
+pub struct Profile {
+    /// Sorted by exclusive time, descending
+    pub functions: Vec<FunctionProfile>,
+    /// Opcode name and execution count, sorted by count, descending
+    pub opcodes: Vec<(String, u64)>,
+    /// Stack path (outermost first) and exclusive time spent in it
+    pub stacks: Vec<(Vec<String>, Duration)>,
+    pub total_instructions: u64,
+    pub total_time: Duration,
+}
+
+impl Profile {
+    /// Render folded stacks, one `a;b;c <nanoseconds>` line per unique stack
+    pub fn folded_stacks(&self) -> String {
+        self.stacks
+            .iter()
+            .filter(|(_, time)| !time.is_zero())
+            .map(|(stack, time)| format!("{} {}\n", stack.join(";"), time.as_nanos()))
+            .collect()
+    }
+
+    /// Render the top `n` functions and opcodes as plain-text tables
+    pub fn top_table(&self, n: usize) -> String {
+        let mut out = String::new();
+        out.push_str(&format!(
+            "Total: {:.3} ms, {} instructions\n\n",
+            ms(self.total_time),
+            self.total_instructions
+        ));
+
+        out.push_str(&format!(
+            "{:<32} {:>10} {:>12} {:>8} {:>12} {:>8}\n",
+            "Function", "Calls", "Self (ms)", "Self %", "Total (ms)", "Total %"
+        ));
+        for func in self.functions.iter().take(n) {
+            out.push_str(&format!(
+                "{:<32} {:>10} {:>12.3} {:>7.1}% {:>12.3} {:>7.1}%\n",
+                truncate(&func.name, 32),
+                func.calls,
+                ms(func.exclusive),
+                self.percent(func.exclusive),
+                ms(func.inclusive),
+                self.percent(func.inclusive)
+            ));
+        }
+
+        if !self.opcodes.is_empty() {
+            out.push_str(&format!("\n{:<32} {:>10} {:>8}\n", "Opcode", "Count", "%"));
+            for (name, count) in self.opcodes.iter().take(n) {
+                let pct = if self.total_instructions == 0 {
+                    0.0
+                } else {
+                    *count as f64 * 100.0 / self.total_instructions as f64
+                };
+                out.push_str(&format!("{:<32} {:>10} {:>7.1}%\n", name, count, pct));
+            }
+        }
+        out
+    }
+
+    fn percent(&self, time: Duration) -> f64 {
+        if self.total_time.is_zero() {
+            0.0
+        } else {
+            time.as_secs_f64() * 100.0 / self.total_time.as_secs_f64()
+        }
+    }
+}
+
+struct ActiveFrame {
+    function: usize,
+    entered: Instant,
+}
+
+#[derive(Default)]
+struct FunctionTotals {
+    calls: u64,
+    inclusive: Duration,
+    exclusive: Duration,
+}
+
+/// Instrumenting profiler state, fed one VM instruction at a time
+struct Recorder {
+    names: Vec<String>,
+    name_ids: HashMap<String, usize>,
+    totals: Vec<FunctionTotals>,
+    stack: Vec<ActiveFrame>,
+    /// Exclusive time per stack path, keyed by function ids
+    stacks: HashMap<Vec<usize>, Duration>,
+    opcodes: HashMap<Discriminant<OpCode>, (String, u64)>,
+    /// Exclusive time accumulated by the current top frame since the last stack change
+    pending: Duration,
+    started: Instant,
+}
+
+impl Recorder {
+    fn new() -> Self {
+        Self {
+            names: Vec::new(),
+            name_ids: HashMap::new(),
+            totals: Vec::new(),
+            stack: Vec::new(),
+            stacks: HashMap::new(),
+            opcodes: HashMap::new(),
+            pending: Duration::ZERO,
+            started: Instant::now(),
+        }
+    }
+
+    fn intern(&mut self, name: &str) -> usize {
+        if let Some(&id) = self.name_ids.get(name) {
+            return id;
+        }
+        let id = self.names.len();
+        self.names.push(name.to_string());
+        self.name_ids.insert(name.to_string(), id);
+        self.totals.push(FunctionTotals::default());
+        id
+    }
+
+    fn count_opcode(&mut self, op: &OpCode) {
+        let entry = self
+            .opcodes
+            .entry(std::mem::discriminant(op))
+            .or_insert_with(|| (opcode_name(op), 0));
+        entry.1 += 1;
+    }
+
+    /// Move exclusive time accumulated by the top frame into the stack and function totals
+    fn flush_pending(&mut self) {
+        if self.stack.is_empty() || self.pending.is_zero() {
+            return;
+        }
+        let path: Vec<usize> = self.stack.iter().map(|f| f.function).collect();
+        let top = *path.last().unwrap();
+        *self.stacks.entry(path).or_default() += self.pending;
+        self.totals[top].exclusive += self.pending;
+        self.pending = Duration::ZERO;
+    }
+
+    fn enter(&mut self, name: &str, now: Instant) {
+        self.flush_pending();
+        let function = self.intern(name);
+        self.totals[function].calls += 1;
+        self.stack.push(ActiveFrame {
+            function,
+            entered: now,
+        });
+    }
+
+    fn exit(&mut self, now: Instant) {
+        self.flush_pending();
+        if let Some(frame) = self.stack.pop() {
+            // Recursive calls are already covered by the outermost activation
+            if !self.stack.iter().any(|f| f.function == frame.function) {
+                self.totals[frame.function].inclusive += now - frame.entered;
+            }
+        }
+    }
+
+    fn finish(mut self, total_instructions: u64) -> Profile {
+        let now = Instant::now();
+        while !self.stack.is_empty() {
+            self.exit(now);
+        }
+
+        let mut functions: Vec<FunctionProfile> = self
+            .totals
+            .iter()
+            .enumerate()
+            .map(|(id, totals)| FunctionProfile {
+                name: self.names[id].clone(),
+                calls: totals.calls,
+                inclusive: totals.inclusive,
+                exclusive: totals.exclusive,
+            })
+            .collect();
+        functions.sort_by(|a, b| b.exclusive.cmp(&a.exclusive).then(a.name.cmp(&b.name)));
+
+        let mut opcodes: Vec<(String, u64)> = self.opcodes.into_values().collect();
+        opcodes.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+        let mut stacks: Vec<(Vec<String>, Duration)> = self
+            .stacks
+            .into_iter()
+            .map(|(path, time)| {
+                let names = path.iter().map(|&id| self.names[id].clone()).collect();
+                (names, time)
+            })
+            .collect();
+        stacks.sort();
+
+        Profile {
+            functions,
+            opcodes,
+            stacks,
+            total_instructions,
+            total_time: now - self.started,
+        }
+    }
+}
+
+/// Run the program loaded in `vm` (starting at `vm.ip`) to completion under the
+/// profiler. An uncaught error is reported and ends the run; the time up to it
+/// is still profiled.
+pub fn profile_vm(vm: &mut VM, source_map: &BytecodeSourceMap) -> Profile {
+    let mut recorder = Recorder::new();
+    let base_depth = vm.call_stack.len();
+    let start_instructions = vm.total_instructions;
+    let mut last = Instant::now();
+    recorder.enter(MAIN_FRAME, last);
+
+    let mut uncaught = false;
+    loop {
+        if let Some(op) = vm.program.get(vm.ip) {
+            recorder.count_opcode(op);
+        }
+        let running = vm.step().unwrap_or_else(|error| {
+            eprintln!("{}", error);
+            uncaught = true;
+            false
+        });
+
+        let now = Instant::now();
+        recorder.pending += now - last;
+        last = now;
+
+        // Frames pushed by calls (or popped by returns and unwinding) since the last step
+        let depth = vm.call_stack.len().saturating_sub(base_depth) + 1;
+        while recorder.stack.len() > depth {
+            recorder.exit(now);
+        }
+        while recorder.stack.len() < depth {
+            let name = frame_name(source_map, vm.ip);
+            recorder.enter(&name, now);
+        }
+
+        if !running {
+            break;
+        }
+    }
+
+    // Timers and queued tasks run to completion; attribute them as a whole
+    if !uncaught {
+        recorder.enter(EVENT_LOOP_FRAME, Instant::now());
+        let start = Instant::now();
+        if let Err(error) = vm.drain_event_loop() {
+            eprintln!("{}", error);
+        }
+        recorder.pending += start.elapsed();
+        recorder.exit(Instant::now());
+    }
+
+    recorder.finish(vm.total_instructions - start_instructions)
+}
+
+/// JIT-compile `bytecode` and profile a single call of its `main` symbol.
+/// Every compiled function reports its entry and exit to the profiler, so
+/// time is attributed per compiled function.
+pub fn profile_jit(bytecode: &[OpCode], source_map: &BytecodeSourceMap) -> Result<Profile, String> {
+    let mut module =
+        ir::lower::lower_module(bytecode).map_err(|e| format!("IR lowering failed: {}", e))?;
+    ir::typecheck::typecheck_module(&mut module);
+    ir::opt::optimize_module(&mut module);
+
+    let mut runtime = JitRuntime::new(&BackendConfig::default())
+        .map_err(|e| format!("Failed to create JIT runtime: {}", e))?;
+    runtime.set_profiling(true);
+    runtime
+        .compile(&module)
+        .map_err(|e| format!("JIT compilation failed: {}", e))?;
+
+    let main = runtime
+        .get_func("main")
+        .map(|ptr| CompiledFunction::new(ptr, 0))
+        .ok_or_else(|| "No 'main' function found".to_string())?;
+
+    // Hook events carry the function's index in the IR module
+    let symbols: Vec<String> = module
+        .functions
+        .iter()
+        .map(|func| match func.name.as_str() {
+            "" => jit_symbol_name("anonymous", source_map),
+            name => jit_symbol_name(name, source_map),
+        })
+        .collect();
+    let mut recorder = Recorder::new();
+    // Functions never called still show up in the table
+    for symbol in &symbols {
+        recorder.intern(symbol);
+    }
+
+    let recorder = Rc::new(RefCell::new(recorder));
+    let hook_recorder = Rc::clone(&recorder);
+    let mut last = Instant::now();
+    jit::set_profile_hook(Some(Box::new(move |index, entered| {
+        let now = Instant::now();
+        let mut recorder = hook_recorder.borrow_mut();
+        recorder.pending += now - last;
+        last = now;
+        if entered {
+            recorder.enter(&symbols[index], now);
+        } else {
+            recorder.exit(now);
+        }
+    })));
+    main.call0();
+    jit::set_profile_hook(None);
+
+    let recorder = Rc::try_unwrap(recorder)
+        .map_err(|_| "Profiler state still shared".to_string())?
+        .into_inner();
+    Ok(recorder.finish(0))
+}
+
+/// Display name for a JIT symbol: `func_<addr>` symbols are resolved to their
+/// source function name when known
+fn jit_symbol_name(symbol: &str, source_map: &BytecodeSourceMap) -> String {
+    symbol
+        .strip_prefix("func_")
+        .and_then(|addr| addr.parse::<usize>().ok())
+        .and_then(|addr| source_map.function_name(addr))
+        .map(|name| format!("{} [{}]", name, symbol))
+        .unwrap_or_else(|| format!("[{}]", symbol))
+}
+
+/// Name for a frame entered at `ip` (the callee's first instruction)
+fn frame_name(source_map: &BytecodeSourceMap, ip: usize) -> String {
+    if let Some(name) = source_map.function_name(ip) {
+        return name.to_string();
+    }
+    match source_map.lookup(ip) {
+        Some(loc) => format!("<anonymous>:{}", loc.line),
+        None => format!("<anonymous>@{}", ip),
+    }
+}
+
+/// Variant name of an opcode, without its operands
+fn opcode_name(op: &OpCode) -> String {
+    let debug = format!("{:?}", op);
+    debug
+        .split(['(', ' ', '{'])
+        .next()
+        .unwrap_or(&debug)
+        .to_string()
+}
+
+fn ms(time: Duration) -> f64 {
+    time.as_secs_f64() * 1000.0
+}
+
+fn truncate(name: &str, width: usize) -> String {
+    if name.chars().count() <= width {
+        name.to_string()
+    } else {
+        let mut short: String = name.chars().take(width - 1).collect();
+        short.push('…');
+        short
+    }
+}
+
+/// Entry point for `oitec profile [options] <file>`
+pub fn run(args: &[String]) {
+    let mut filename: Option<&str> = None;
+    let mut output = DEFAULT_OUTPUT.to_string();
+    let mut top = DEFAULT_TOP;
+    let mut use_jit = false;
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--jit" => use_jit = true,
+            "-o" | "--output" => {
+                i += 1;
+                match args.get(i) {
+                    Some(path) => output = path.clone(),
+                    None => {
+                        eprintln!("Error: {} requires a file name", args[i - 1]);
+                        std::process::exit(1);
+                    }
+                }
+            }
+            "--top" => {
+                i += 1;
+                match args.get(i).and_then(|n| n.parse().ok()) {
+                    Some(n) => top = n,
+                    None => {
+                        eprintln!("Error: --top requires a number");
+                        std::process::exit(1);
+                    }
+                }
+            }
+            arg if filename.is_none() && !arg.starts_with('-') => filename = Some(arg),
+            arg => {
+                eprintln!("Error: Unknown option: {}", arg);
+                std::process::exit(1);
+            }
+        }
+        i += 1;
+    }
+
+    let Some(filename) = filename else {
+        eprintln!("Usage: oitec profile [--jit] [-o <file>] [--top <n>] <filename>");
+        std::process::exit(1);
+    };
+
+    let source = match fs::read_to_string(filename) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("Failed to read {}: {}", filename, e);
+            std::process::exit(1);
+        }
+    };
+
+    let mut compiler = Compiler::new();
+    let (bytecode, mut source_map) = match compiler
+        .compile_with_source_map(&source, Some(syntax_for_path(Path::new(filename))))
+    {
+        Ok(result) => result,
+        Err(e) => {
+            eprintln!("Compilation failed: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let profile = if use_jit {
+        match profile_jit(&bytecode, &source_map) {
+            Ok(profile) => profile,
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+    } else {
+        let mut vm = VM::new();
+        // The prelude is not profiled
+        if Path::new(crate::PRELUDE_PATH).exists()
+            && let Err(e) =
+                crate::load_and_run_script(&mut vm, &mut compiler, crate::PRELUDE_PATH, false)
+        {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        let offset = vm.append_program(bytecode);
+        source_map.rebase(offset);
+        vm.set_current_module_path(filename.into());
+        profile_vm(&mut vm, &source_map)
+    };
+
+    if let Err(e) = fs::write(&output, profile.folded_stacks()) {
+        eprintln!("Failed to write {}: {}", output, e);
+        std::process::exit(1);
+    }
+    eprintln!("\n=== Profile: {} ===", filename);
+    eprint!("{}", profile.top_table(top));
+    eprintln!("\nFolded stacks written to {}", output);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile_source(source: &str) -> Profile {
+        let mut compiler = Compiler::new();
+        let (bytecode, source_map) = compiler
+            .compile_with_source_map(source, Some(syntax_for_path(Path::new("test.ot"))))
+            .unwrap();
+        let mut vm = VM::new();
+        vm.load_program(bytecode);
+        profile_vm(&mut vm, &source_map)
+    }
+
+    #[test]
+    fn test_profile_counts_calls_and_nests_stacks() {
+        let profile = profile_source(
+            "function fib(n) {
+  if (n < 2) { return n; }
+  return fib(n - 1) + fib(n - 2);
+}
+function run() { return fib(6); }
+let result = run();",
+        );
+
+        let fib = profile.functions.iter().find(|f| f.name == "fib").unwrap();
+        assert_eq!(fib.calls, 25);
+        let run = profile.functions.iter().find(|f| f.name == "run").unwrap();
+        assert_eq!(run.calls, 1);
+        assert!(run.inclusive >= fib.inclusive);
+
+        let folded = profile.folded_stacks();
+        assert!(folded.contains("<main>;run;fib;fib "));
+        assert!(profile.total_instructions > 0);
+    }
+
+    #[test]
+    fn test_profile_jit_attributes_time_per_function() {
+        let mut compiler = Compiler::new();
+        let (bytecode, source_map) = compiler
+            .compile_with_source_map(
+                "function fib(n) {
+  if (n < 2) { return n; }
+  return fib(n - 1) + fib(n - 2);
+}
+function run() { return fib(6); }
+let result = run();",
+                Some(syntax_for_path(Path::new("test.ot"))),
+            )
+            .unwrap();
+        let profile = profile_jit(&bytecode, &source_map).unwrap();
+
+        let find = |prefix: &str| {
+            profile
+                .functions
+                .iter()
+                .find(|f| f.name.starts_with(prefix))
+                .unwrap()
+        };
+        let fib = find("fib [");
+        assert_eq!(fib.calls, 25);
+        assert!(!fib.exclusive.is_zero());
+        let run = find("run [");
+        assert_eq!(run.calls, 1);
+        assert!(run.inclusive >= fib.inclusive);
+        assert_eq!(find("[main]").calls, 1);
+
+        let folded = profile.folded_stacks();
+        assert!(
+            folded
+                .lines()
+                .any(|l| l.starts_with("[main];run [") && l.contains(";fib ["))
+        );
+    }
+
+    #[test]
+    fn test_profile_counts_opcodes() {
+        let profile = profile_source("let a = 1; let b = a + 2; let c = b * 3;");
+        let count = |name: &str| {
+            profile
+                .opcodes
+                .iter()
+                .find(|(op, _)| op == name)
+                .map(|(_, n)| *n)
+                .unwrap_or(0)
+        };
+        assert_eq!(count("Add"), 1);
+        assert_eq!(count("Mul"), 1);
+        assert_eq!(count("Halt"), 1);
+        let total: u64 = profile.opcodes.iter().map(|(_, n)| n).sum();
+        assert_eq!(total, profile.total_instructions);
+    }
+
+    #[test]
+    fn test_top_table_lists_functions() {
+        let profile = profile_source("function f() { return 1; } f(); f();");
+        let table = profile.top_table(5);
+        assert!(table.contains("Function"));
+        assert!(
+            table
+                .lines()
+                .any(|l| l.starts_with("f ") && l.contains(" 2 "))
+        );
+    }
+
+    #[test]
+    fn test_opcode_name_strips_operands() {
+        assert_eq!(opcode_name(&OpCode::Load("x".to_string())), "Load");
+        assert_eq!(opcode_name(&OpCode::Halt), "Halt");
+    }
+}
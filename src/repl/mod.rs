@@ -0,0 +1,400 @@
+//! Interactive REPL for the VM
+//!
+//! Each input is compiled separately and appended to the running program with
+//! `VM::append_program`, so global bindings persist across inputs. The value
+//! of a trailing expression statement is captured and printed with the same
+//! formatter as `console.log`.
+
+use crate::compiler::Compiler;
+use crate::stdlib::inspect_value;
+use crate::vm::VM;
+use crate::vm::value::JsValue;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use swc_common::{FileName, SourceMap, Spanned, sync::Lrc};
+use swc_ecma_ast::{ModuleItem, Program, Stmt};
+use swc_ecma_parser::error::SyntaxError;
+use swc_ecma_parser::{Parser, StringInput, Syntax, TsSyntax, lexer::Lexer};
+
+/// History file name, stored in the user's home directory
+const HISTORY_FILE: &str = ".oite_history";
+
+/// Maximum number of history entries kept on disk
+const MAX_HISTORY: usize = 1000;
+
+const HELP_TEXT: &str = "\
+.break         Discard the current multi-line input
+.exit          Exit the REPL
+.help          Show this help
+.history       Show input history
+.load <file>   Evaluate a file in this session
+.save <file>   Save all evaluated inputs to a file";
+
+/// Outcome of parsing a (possibly partial) input
+#[derive(Debug, PartialEq)]
+pub enum ParseStatus {
+    /// Input parsed; `ends_with_expr` is true if the last item is an expression statement
+    Complete { ends_with_expr: bool },
+    /// Input ended before the parser was done; wait for more lines
+    Incomplete,
+    /// Input is malformed regardless of what follows
+    Invalid(String),
+}
+
+pub struct Repl {
+    pub vm: VM,
+    compiler: Compiler,
+    /// Successfully evaluated inputs, written out by `.save`
+    session: Vec<String>,
+    history: Vec<String>,
+    history_path: Option<PathBuf>,
+}
+
+impl Default for Repl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Repl {
+    pub fn new() -> Self {
+        Self {
+            vm: VM::new(),
+            compiler: Compiler::new(),
+            session: Vec::new(),
+            history: Vec::new(),
+            history_path: None,
+        }
+    }
+
+    /// Load the prelude (if present) and persisted history
+    pub fn init(&mut self) {
+        if Path::new(crate::PRELUDE_PATH).exists()
+            && let Ok(source) = fs::read_to_string(crate::PRELUDE_PATH)
+            && let Err(e) = self.eval(&source)
+        {
+            eprintln!("Failed to load prelude: {}", e);
+        }
+        // The prelude is not part of the user's session
+        self.session.clear();
+
+        self.history_path =
+            std::env::var_os("HOME").map(|home| PathBuf::from(home).join(HISTORY_FILE));
+        if let Some(path) = &self.history_path {
+            self.history = load_history(path);
+        }
+    }
+
+    /// Compile and run one complete input, returning the value of its trailing
+    /// expression (if any). Top-level `await` settles on the event loop, which
+    /// is drained before returning.
+    pub fn eval(&mut self, source: &str) -> Result<Option<JsValue>, String> {
+        match parse_status(source) {
+            ParseStatus::Complete { .. } => {}
+            ParseStatus::Incomplete => return Err("Unexpected end of input".to_string()),
+            ParseStatus::Invalid(e) => return Err(e),
+        }
+
+        let (bytecode, captures_result) = self
+            .compiler
+            .compile_repl_input(source, Some(repl_syntax()))?;
+
+        let stack_len = self.vm.stack.len();
+        self.vm.append_program(bytecode);
+        let outcome = self.vm.run_until_halt().and_then(|()| {
+            let result = if captures_result && self.vm.stack.len() > stack_len {
+                self.vm.stack.pop()
+            } else {
+                None
+            };
+            self.vm.stack.truncate(stack_len);
+            self.vm.drain_event_loop().map(|()| result)
+        });
+
+        match outcome {
+            Ok(result) => {
+                self.session.push(source.to_string());
+                Ok(result)
+            }
+            Err(error) => {
+                self.vm.reset_after_uncaught();
+                self.vm.stack.truncate(stack_len);
+                Err(error.to_string())
+            }
+        }
+    }
+
+    /// Run the interactive loop on stdin until `.exit` or EOF
+    pub fn run(&mut self) {
+        println!(
+            "Oite {} REPL. Type .help for commands, .exit to quit.",
+            env!("CARGO_PKG_VERSION")
+        );
+
+        let stdin = io::stdin();
+        let mut lines = stdin.lock().lines();
+        let mut buffer = String::new();
+
+        loop {
+            print!("{}", if buffer.is_empty() { "> " } else { "... " });
+            let _ = io::stdout().flush();
+
+            let line = match lines.next() {
+                Some(Ok(line)) => line,
+                _ => break,
+            };
+
+            if buffer.is_empty() && line.trim_start().starts_with('.') {
+                if !self.run_command(line.trim()) {
+                    break;
+                }
+                continue;
+            }
+            if line.trim() == ".break" {
+                buffer.clear();
+                continue;
+            }
+
+            if !buffer.is_empty() {
+                buffer.push('\n');
+            }
+            buffer.push_str(&line);
+
+            if buffer.trim().is_empty() {
+                buffer.clear();
+                continue;
+            }
+            if parse_status(&buffer) == ParseStatus::Incomplete {
+                continue;
+            }
+
+            let input = std::mem::take(&mut buffer);
+            self.push_history(&input);
+            self.print_eval(&input);
+        }
+
+        self.save_history();
+    }
+
+    fn print_eval(&mut self, source: &str) {
+        match self.eval(source) {
+            Ok(Some(value)) => println!("{}", inspect_value(&self.vm, &value)),
+            Ok(None) => println!("undefined"),
+            Err(e) => eprintln!("Error: {}", e),
+        }
+    }
+
+    /// Handle a dot-command. Returns false when the REPL should exit.
+    fn run_command(&mut self, line: &str) -> bool {
+        let (command, arg) = match line.split_once(char::is_whitespace) {
+            Some((command, arg)) => (command, arg.trim()),
+            None => (line, ""),
+        };
+
+        match command {
+            ".exit" => return false,
+            ".help" => println!("{}", HELP_TEXT),
+            ".break" => {}
+            ".history" => {
+                for (i, entry) in self.history.iter().enumerate() {
+                    println!("{:4}  {}", i + 1, entry);
+                }
+            }
+            ".load" if !arg.is_empty() => match fs::read_to_string(arg) {
+                Ok(source) => self.print_eval(&source),
+                Err(e) => eprintln!("Failed to read {}: {}", arg, e),
+            },
+            ".save" if !arg.is_empty() => {
+                let mut contents = self.session.join("\n");
+                contents.push('\n');
+                match fs::write(arg, contents) {
+                    Ok(()) => println!("Session saved to {}", arg),
+                    Err(e) => eprintln!("Failed to write {}: {}", arg, e),
+                }
+            }
+            ".load" | ".save" => eprintln!("Usage: {} <file>", command),
+            _ => eprintln!("Unknown command: {} (type .help for commands)", command),
+        }
+        true
+    }
+
+    fn push_history(&mut self, input: &str) {
+        if self.history.last().map(String::as_str) != Some(input) {
+            self.history.push(input.to_string());
+        }
+    }
+
+    fn save_history(&self) {
+        let Some(path) = &self.history_path else {
+            return;
+        };
+        let start = self.history.len().saturating_sub(MAX_HISTORY);
+        // One JSON string per line so multi-line inputs survive the round trip
+        let contents: String = self.history[start..]
+            .iter()
+            .filter_map(|entry| serde_json::to_string(entry).ok())
+            .map(|line| line + "\n")
+            .collect();
+        if let Err(e) = fs::write(path, contents) {
+            eprintln!("Failed to save history to {}: {}", path.display(), e);
+        }
+    }
+}
+
+/// Syntax used for REPL input (same as `.ot` files)
+fn repl_syntax() -> Syntax {
+    Syntax::Typescript(TsSyntax {
+        decorators: true,
+        ..Default::default()
+    })
+}
+
+/// Classify an input as complete, incomplete (needs more lines), or invalid
+pub fn parse_status(source: &str) -> ParseStatus {
+    let cm: Lrc<SourceMap> = Default::default();
+    let fm = cm.new_source_file(FileName::Custom("repl".into()).into(), source.to_string());
+    let lexer = Lexer::new(
+        repl_syntax(),
+        Default::default(),
+        StringInput::from(&*fm),
+        None,
+    );
+    let mut parser = Parser::new_from(lexer);
+
+    // The parser recovers from some errors (e.g. a missing closing brace);
+    // treat the first recovered error like a fatal one
+    let parsed =
+        parser
+            .parse_program()
+            .and_then(|program| match parser.take_errors().into_iter().next() {
+                Some(e) => Err(e),
+                None => Ok(program),
+            });
+
+    match parsed {
+        Ok(program) => {
+            let last_is_expr = match &program {
+                Program::Module(module) => {
+                    matches!(module.body.last(), Some(ModuleItem::Stmt(Stmt::Expr(_))))
+                }
+                Program::Script(script) => matches!(script.body.last(), Some(Stmt::Expr(_))),
+            };
+            ParseStatus::Complete {
+                ends_with_expr: last_is_expr,
+            }
+        }
+        Err(e) => {
+            let unterminated = matches!(
+                e.kind(),
+                SyntaxError::Eof
+                    | SyntaxError::UnterminatedTpl
+                    | SyntaxError::UnterminatedBlockComment
+            );
+            // Errors reported at the very end of the input mean the parser ran out of tokens
+            if unterminated || e.span().lo >= fm.end_pos {
+                ParseStatus::Incomplete
+            } else {
+                ParseStatus::Invalid(format!("Parsing error: {:?}", e.kind()))
+            }
+        }
+    }
+}
+
+fn load_history(path: &Path) -> Vec<String> {
+    fs::read_to_string(path)
+        .map(|contents| {
+            contents
+                .lines()
+                .filter_map(|line| serde_json::from_str::<String>(line).ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_status_detects_incomplete_input() {
+        assert_eq!(parse_status("function f(x) {"), ParseStatus::Incomplete);
+        assert_eq!(parse_status("function f() {"), ParseStatus::Incomplete);
+        assert_eq!(parse_status("let s = `abc"), ParseStatus::Incomplete);
+        assert_eq!(parse_status("let o = { a: 1,"), ParseStatus::Incomplete);
+        assert_eq!(
+            parse_status("1 + 2"),
+            ParseStatus::Complete {
+                ends_with_expr: true
+            }
+        );
+        assert_eq!(
+            parse_status("let x = 1;"),
+            ParseStatus::Complete {
+                ends_with_expr: false
+            }
+        );
+        assert!(matches!(parse_status("let = ;"), ParseStatus::Invalid(_)));
+    }
+
+    #[test]
+    fn test_bindings_persist_across_inputs() {
+        let mut repl = Repl::new();
+        assert_eq!(repl.eval("let x = 40;").unwrap(), None);
+        assert_eq!(
+            repl.eval("function add(a, b) { return a + b; }").unwrap(),
+            None
+        );
+        assert_eq!(repl.eval("add(x, 2)").unwrap(), Some(JsValue::Number(42.0)));
+    }
+
+    #[test]
+    fn test_result_is_formatted_like_console_log() {
+        let mut repl = Repl::new();
+        let value = repl.eval("({ b: [1, 'two'], a: true })").unwrap().unwrap();
+        assert_eq!(
+            inspect_value(&repl.vm, &value),
+            "{ a: true, b: [ 1, 'two' ] }"
+        );
+    }
+
+    #[test]
+    fn test_recovers_after_uncaught_error() {
+        let mut repl = Repl::new();
+        repl.eval("let y = 5;").unwrap();
+        assert!(repl.eval("throw 'boom';").is_err());
+        assert_eq!(repl.eval("y * 2").unwrap(), Some(JsValue::Number(10.0)));
+    }
+
+    #[test]
+    fn test_uncaught_error_is_reported() {
+        let mut repl = Repl::new();
+        let error = repl.eval("throw new TypeError('bad');").unwrap_err();
+        assert!(error.starts_with("Uncaught TypeError"), "{}", error);
+    }
+
+    #[test]
+    fn test_top_level_await_outside_trailing_expression() {
+        let mut repl = Repl::new();
+        assert_eq!(
+            repl.eval("let v = await Promise.resolve(5); v").unwrap(),
+            Some(JsValue::Number(5.0))
+        );
+        assert_eq!(
+            repl.eval("let w = 0; { w = await Promise.resolve(5).then((n) => n + 1); }")
+                .unwrap(),
+            None
+        );
+        assert_eq!(repl.eval("w").unwrap(), Some(JsValue::Number(6.0)));
+    }
+
+    #[test]
+    fn test_trailing_await_yields_settled_value() {
+        let mut repl = Repl::new();
+        assert_eq!(
+            repl.eval("await Promise.resolve(2).then((n) => n * 21)")
+                .unwrap(),
+            Some(JsValue::Number(42.0))
+        );
+    }
+}
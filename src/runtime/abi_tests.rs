@@ -11,7 +11,7 @@ mod tests {
     /// Test that ABI version is set to the expected value.
     #[test]
     fn test_abi_version() {
-        assert_eq!(ABI_VERSION, 1, "ABI version must be 1");
+        assert_eq!(ABI_VERSION, 2, "ABI version must be 2");
     }
 
     /// Test that IR format version is set to the expected value.
@@ -156,7 +156,7 @@ mod ir_format_tests {
             "IR must contain format version"
         );
         assert!(
-            output1.contains("; ABI version: 1"),
+            output1.contains("; ABI version: 2"),
             "IR must contain ABI version"
         );
     }
@@ -215,14 +215,14 @@ mod abi_stability_tests {
         // This test serves as a canary - if it fails, the ABI has changed
         // and we need to decide whether to bump ABI_VERSION
         assert_eq!(
-            ABI_VERSION, 1,
-            "ABI version must remain 1 until intentional change"
+            ABI_VERSION, 2,
+            "ABI version must remain 2 until intentional change"
         );
 
         // Verify we haven't accidentally changed to a development version
         assert!(
-            ABI_VERSION < 2,
-            "ABI should not be version 2+ without explicit decision"
+            ABI_VERSION < 3,
+            "ABI should not be version 3+ without explicit decision"
         );
     }
 }
@@ -317,7 +317,7 @@ mod additional_abi_tests {
     #[test]
     fn test_ir_module_header() {
         assert_eq!(IR_FORMAT_VERSION, 1, "IR format version must be 1");
-        assert_eq!(ABI_VERSION, 1, "ABI version must be 1");
+        assert_eq!(ABI_VERSION, 2, "ABI version must be 2");
     }
 
     /// Test 17: Object header size
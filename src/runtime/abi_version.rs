@@ -1,18 +1,31 @@
-pub const ABI_VERSION: u32 = 1;
+pub const ABI_VERSION: u32 = 2;
 
 pub const ABI_NAME: &str = "tscl";
 
+/// Symbol the runtime library exports for its ABI version. AOT builds look for
+/// it before linking, so a runtime built for another ABI is rejected.
+pub const ABI_SYMBOL: &str = "ot_abi_version_2";
+
+#[unsafe(export_name = "ot_abi_version_2")]
+pub static OT_ABI_VERSION: u32 = ABI_VERSION;
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn test_abi_version() {
-        assert_eq!(ABI_VERSION, 1);
+        assert_eq!(ABI_VERSION, 2);
     }
 
     #[test]
     fn test_abi_name() {
         assert_eq!(ABI_NAME, "tscl");
     }
+
+    #[test]
+    fn test_abi_symbol_tracks_version() {
+        assert_eq!(ABI_SYMBOL, format!("ot_abi_version_{}", ABI_VERSION));
+        assert_eq!(OT_ABI_VERSION, ABI_VERSION);
+    }
 }
@@ -0,0 +1,106 @@
+//! A pool of threads for blocking system calls
+//!
+//! Regular files are always ready as far as epoll and kqueue are concerned,
+//! so the reactor cannot make file I/O wait without blocking the thread
+//! that polls it. Such calls run here instead, on threads spawned as the
+//! work arrives and kept for the next job.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// Threads a pool spawns by default
+pub const DEFAULT_MAX_THREADS: usize = 4;
+
+type Job = Box<dyn FnOnce() + Send>;
+
+pub struct BlockingPool {
+    sender: Sender<Job>,
+    receiver: Arc<Mutex<Receiver<Job>>>,
+    /// Threads waiting for a job
+    idle: Arc<AtomicUsize>,
+    threads: usize,
+    max_threads: usize,
+}
+
+impl Default for BlockingPool {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_THREADS)
+    }
+}
+
+impl BlockingPool {
+    /// A pool of up to `max_threads` threads, none of which start until
+    /// there is work for them
+    pub fn new(max_threads: usize) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Self {
+            sender,
+            receiver: Arc::new(Mutex::new(receiver)),
+            idle: Arc::new(AtomicUsize::new(0)),
+            threads: 0,
+            max_threads: max_threads.max(1),
+        }
+    }
+
+    /// Threads spawned so far
+    pub fn threads(&self) -> usize {
+        self.threads
+    }
+
+    /// Run `job` on the next free thread. Jobs queue up in order once every
+    /// thread is busy. The threads exit when the pool is dropped, after
+    /// finishing the jobs already queued.
+    pub fn execute(&mut self, job: impl FnOnce() + Send + 'static) {
+        if self.idle.load(Ordering::Acquire) == 0 && self.threads < self.max_threads {
+            self.spawn_thread();
+        }
+        // The pool holds the receiver, so the channel is never disconnected
+        let _ = self.sender.send(Box::new(job));
+    }
+
+    fn spawn_thread(&mut self) {
+        let receiver = Arc::clone(&self.receiver);
+        let idle = Arc::clone(&self.idle);
+        thread::Builder::new()
+            .name("oite-blocking".to_string())
+            .spawn(move || {
+                loop {
+                    idle.fetch_add(1, Ordering::AcqRel);
+                    let job = receiver.lock().unwrap().recv();
+                    idle.fetch_sub(1, Ordering::AcqRel);
+                    match job {
+                        Ok(job) => job(),
+                        Err(_) => break,
+                    }
+                }
+            })
+            .expect("failed to spawn a blocking pool thread");
+        self.threads += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_jobs_run_off_thread_on_a_bounded_pool() {
+        let mut pool = BlockingPool::new(2);
+        assert_eq!(pool.threads(), 0);
+        let (sender, receiver) = mpsc::channel();
+        let caller = thread::current().id();
+        for n in 0..8 {
+            let sender = sender.clone();
+            pool.execute(move || {
+                sender.send((n, thread::current().id() != caller)).unwrap();
+            });
+        }
+        let mut results: Vec<(i32, bool)> = receiver.iter().take(8).collect();
+        results.sort();
+        let expected: Vec<(i32, bool)> = (0..8).map(|n| (n, true)).collect();
+        assert_eq!(results, expected);
+        assert!(pool.threads() <= 2);
+    }
+}
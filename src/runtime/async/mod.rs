@@ -7,6 +7,7 @@ use std::os::unix::io::AsRawFd;
 use std::pin::Pin;
 use std::task::{Context, Poll, Waker};
 
+pub mod blocking;
 pub mod reactor;
 pub mod runtime_impl;
 pub mod task;
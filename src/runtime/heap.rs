@@ -13,6 +13,9 @@
 use std::alloc::{self, Layout};
 use std::sync::atomic::{AtomicUsize, Ordering};
 
+use super::typed_array::ElementKind;
+use super::utf16;
+
 /// Simple property storage using a Vec instead of HashMap to avoid hashbrown dependency.
 /// This is a tradeoff: O(n) lookup but no external dependencies.
 pub type PropertyMap = Vec<(String, u64)>;
@@ -119,6 +122,8 @@ pub enum ObjectKind {
     Function = 3,
     /// A ByteStream buffer (for bytecode generation).
     ByteStream = 4,
+    /// A typed array with inline elements.
+    TypedArray = 5,
 }
 
 /// Header for all heap-allocated objects.
@@ -131,8 +136,11 @@ pub struct ObjectHeader {
     pub kind: ObjectKind,
     /// GC mark bit (for future mark-sweep).
     pub marked: bool,
+    /// Kind-specific tag (a typed array's `ElementKind`, a string's
+    /// [`NativeString::ASCII`] flag).
+    pub tag: u8,
     /// Reserved for alignment and future use.
-    pub _reserved: [u8; 6],
+    pub _reserved: [u8; 5],
     /// Size of the object data (excluding header).
     pub size: u32,
 }
@@ -144,7 +152,8 @@ impl ObjectHeader {
         Self {
             kind,
             marked: false,
-            _reserved: [0; 6],
+            tag: 0,
+            _reserved: [0; 5],
             size,
         }
     }
@@ -156,7 +165,7 @@ impl ObjectHeader {
 
 /// A native string object.
 ///
-/// Inline data follows (variable length).
+/// Inline UTF-8 data follows (variable length).
 /// Accessed via pointer arithmetic: (self as *const u8) + size_of::<NativeString>()
 #[repr(C)]
 pub struct NativeString {
@@ -166,17 +175,35 @@ pub struct NativeString {
 }
 
 impl NativeString {
+    /// `header.tag` flag of a string that is all ASCII, whose UTF-16 code
+    /// units are its bytes
+    pub const ASCII: u8 = 1;
+
     /// Get the string data as a slice.
     ///
     /// # Safety
-    /// The object must be properly allocated with the correct length.
+    /// The object must be allocated by [`NativeHeap::alloc_string`], which
+    /// copies the data from a `str`, so it is valid UTF-8.
     pub unsafe fn as_str(&self) -> &str {
         unsafe {
             let data_ptr = (self as *const Self as *const u8).add(std::mem::size_of::<Self>());
             let slice = std::slice::from_raw_parts(data_ptr, self.len as usize);
+            debug_assert!(std::str::from_utf8(slice).is_ok());
             std::str::from_utf8_unchecked(slice)
         }
     }
+
+    /// The length in UTF-16 code units, JS's `length`.
+    ///
+    /// # Safety
+    /// As for [`NativeString::as_str`].
+    pub unsafe fn utf16_len(&self) -> usize {
+        if self.header.tag & Self::ASCII != 0 {
+            self.len as usize
+        } else {
+            unsafe { utf16::len(self.as_str()) }
+        }
+    }
 }
 
 /// A native array object.
@@ -191,6 +218,47 @@ pub struct NativeArray {
     pub elements: *mut u64,
 }
 
+/// A native typed array.
+///
+/// `len` elements of the kind in `header.tag` follow inline, packed, so an
+/// element lives at `backend::layout::compute_array_layout`'s offset.
+#[repr(C)]
+pub struct NativeTypedArray {
+    pub header: ObjectHeader,
+    /// Number of elements.
+    pub len: u32,
+}
+
+impl NativeTypedArray {
+    /// The element kind.
+    pub fn kind(&self) -> ElementKind {
+        ElementKind::from_tag(self.header.tag).unwrap_or(ElementKind::Uint8)
+    }
+
+    /// Get the element bytes as a slice.
+    ///
+    /// # Safety
+    /// The object must be allocated by [`NativeHeap::alloc_typed_array`].
+    pub unsafe fn bytes(&self) -> &[u8] {
+        unsafe {
+            let data_ptr = (self as *const Self as *const u8).add(std::mem::size_of::<Self>());
+            std::slice::from_raw_parts(data_ptr, self.len as usize * self.kind().size())
+        }
+    }
+
+    /// Get the element bytes as a mutable slice.
+    ///
+    /// # Safety
+    /// The object must be allocated by [`NativeHeap::alloc_typed_array`].
+    pub unsafe fn bytes_mut(&mut self) -> &mut [u8] {
+        unsafe {
+            let len = self.len as usize * self.kind().size();
+            let data_ptr = (self as *mut Self as *mut u8).add(std::mem::size_of::<Self>());
+            std::slice::from_raw_parts_mut(data_ptr, len)
+        }
+    }
+}
+
 /// A native object (key-value map).
 ///
 /// For simplicity, we use a Rust HashMap internally.
@@ -314,6 +382,9 @@ impl NativeHeap {
         unsafe {
             let header = ptr.as_mut::<ObjectHeader>();
             *header = ObjectHeader::new(ObjectKind::String, data_size as u32);
+            if s.is_ascii() {
+                header.tag = NativeString::ASCII;
+            }
 
             let string_obj = ptr.as_mut::<NativeString>();
             string_obj.len = s.len() as u32;
@@ -363,6 +434,26 @@ impl NativeHeap {
         Some(ptr)
     }
 
+    /// Allocate a zero-filled typed array of `len` elements.
+    pub fn alloc_typed_array(&self, kind: ElementKind, len: usize) -> Option<HeapPtr> {
+        let byte_len = len.checked_mul(kind.size())?;
+        let data_size = std::mem::size_of::<NativeTypedArray>() - ObjectHeader::SIZE + byte_len;
+        let size = u32::try_from(data_size).ok()?;
+        let ptr = self.alloc(data_size)?;
+
+        unsafe {
+            let header = ptr.as_mut::<ObjectHeader>();
+            *header = ObjectHeader::new(ObjectKind::TypedArray, size);
+            header.tag = kind as u8;
+
+            let arr = ptr.as_mut::<NativeTypedArray>();
+            arr.len = len as u32;
+            arr.bytes_mut().fill(0);
+        }
+
+        Some(ptr)
+    }
+
     /// Get the total bytes allocated.
     pub fn total_allocated(&self) -> usize {
         self.total_allocated.load(Ordering::Relaxed)
@@ -438,6 +529,12 @@ mod tests {
 
             let s = ptr.as_ref::<NativeString>();
             assert_eq!(s.as_str(), "hello");
+            assert_eq!(s.utf16_len(), 5);
+
+            let s = heap.alloc_string("h😀").expect("allocation failed");
+            let s = s.as_ref::<NativeString>();
+            assert_eq!(s.header.tag & NativeString::ASCII, 0);
+            assert_eq!(s.utf16_len(), 3);
         }
     }
 
@@ -456,6 +553,25 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_alloc_typed_array() {
+        let heap = NativeHeap::new();
+        let ptr = heap
+            .alloc_typed_array(ElementKind::Int16, 3)
+            .expect("allocation failed");
+
+        unsafe {
+            let header = ptr.as_ref::<ObjectHeader>();
+            assert_eq!(header.kind, ObjectKind::TypedArray);
+
+            let arr = ptr.as_mut::<NativeTypedArray>();
+            assert_eq!(arr.kind(), ElementKind::Int16);
+            assert_eq!(arr.bytes(), &[0; 6]);
+            ElementKind::Int16.write(&mut arr.bytes_mut()[2..], -2.0, true);
+            assert_eq!(arr.bytes(), &[0, 0, 0xFE, 0xFF, 0, 0]);
+        }
+    }
+
     #[test]
     fn test_heap_ptr_roundtrip() {
         let addr: usize = 0x1234_5678_9ABC;
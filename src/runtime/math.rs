@@ -0,0 +1,303 @@
+//! The ES `Math` namespace
+//!
+//! One table of functions and constants shared by every backend: the VM
+//! natives (`stdlib::math`), the `ot_math_*` stubs called from native code
+//! (`stubs.rs`), and the IR, which names functions by [`MathFn`]. Arguments
+//! are numbers that have already been through ToNumber.
+
+/// `Math` value properties
+pub const CONSTANTS: &[(&str, f64)] = &[
+    ("E", std::f64::consts::E),
+    ("LN10", std::f64::consts::LN_10),
+    ("LN2", std::f64::consts::LN_2),
+    ("LOG10E", std::f64::consts::LOG10_E),
+    ("LOG2E", std::f64::consts::LOG2_E),
+    ("PI", std::f64::consts::PI),
+    ("SQRT1_2", std::f64::consts::FRAC_1_SQRT_2),
+    ("SQRT2", std::f64::consts::SQRT_2),
+];
+
+/// Value of the `Math` constant `name`
+pub fn constant(name: &str) -> Option<f64> {
+    CONSTANTS
+        .iter()
+        .find(|(constant, _)| *constant == name)
+        .map(|&(_, value)| value)
+}
+
+macro_rules! math_functions {
+    ($($variant:ident => $name:literal / $arity:literal,)*) => {
+        /// A `Math` function
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum MathFn {
+            $($variant,)*
+        }
+
+        impl MathFn {
+            pub const ALL: &'static [MathFn] = &[$(MathFn::$variant,)*];
+
+            /// Property name on `Math`
+            pub fn name(self) -> &'static str {
+                match self {
+                    $(MathFn::$variant => $name,)*
+                }
+            }
+
+            /// Symbol of the runtime stub
+            pub fn stub_name(self) -> &'static str {
+                match self {
+                    $(MathFn::$variant => concat!("ot_math_", $name),)*
+                }
+            }
+
+            /// Parameter count of the runtime stub. Variadic functions take
+            /// two and are folded over their arguments from [`MathFn::identity`].
+            pub fn arity(self) -> usize {
+                match self {
+                    $(MathFn::$variant => $arity,)*
+                }
+            }
+        }
+    };
+}
+
+math_functions! {
+    Abs => "abs" / 1,
+    Acos => "acos" / 1,
+    Acosh => "acosh" / 1,
+    Asin => "asin" / 1,
+    Asinh => "asinh" / 1,
+    Atan => "atan" / 1,
+    Atan2 => "atan2" / 2,
+    Atanh => "atanh" / 1,
+    Cbrt => "cbrt" / 1,
+    Ceil => "ceil" / 1,
+    Clz32 => "clz32" / 1,
+    Cos => "cos" / 1,
+    Cosh => "cosh" / 1,
+    Exp => "exp" / 1,
+    Expm1 => "expm1" / 1,
+    Floor => "floor" / 1,
+    Fround => "fround" / 1,
+    Hypot => "hypot" / 2,
+    Imul => "imul" / 2,
+    Log => "log" / 1,
+    Log10 => "log10" / 1,
+    Log1p => "log1p" / 1,
+    Log2 => "log2" / 1,
+    Max => "max" / 2,
+    Min => "min" / 2,
+    Pow => "pow" / 2,
+    Random => "random" / 0,
+    Round => "round" / 1,
+    Sign => "sign" / 1,
+    Sin => "sin" / 1,
+    Sinh => "sinh" / 1,
+    Sqrt => "sqrt" / 1,
+    Tan => "tan" / 1,
+    Tanh => "tanh" / 1,
+    Trunc => "trunc" / 1,
+}
+
+impl MathFn {
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|f| f.name() == name)
+    }
+
+    /// Result for no arguments, for the variadic `max`, `min` and `hypot`
+    pub fn identity(self) -> Option<f64> {
+        match self {
+            MathFn::Max => Some(f64::NEG_INFINITY),
+            MathFn::Min => Some(f64::INFINITY),
+            MathFn::Hypot => Some(0.0),
+            _ => None,
+        }
+    }
+
+    /// Whether equal arguments always give equal results
+    pub fn is_pure(self) -> bool {
+        self != MathFn::Random
+    }
+
+    /// Call the function; missing arguments are `NaN` (ToNumber of undefined)
+    pub fn apply(self, args: &[f64]) -> f64 {
+        if let Some(identity) = self.identity() {
+            return args
+                .iter()
+                .fold(identity, |acc, &x| self.apply_fixed(acc, x));
+        }
+        let x = args.first().copied().unwrap_or(f64::NAN);
+        let y = args.get(1).copied().unwrap_or(f64::NAN);
+        self.apply_fixed(x, y)
+    }
+
+    fn apply_fixed(self, x: f64, y: f64) -> f64 {
+        match self {
+            MathFn::Abs => x.abs(),
+            MathFn::Acos => x.acos(),
+            MathFn::Acosh => x.acosh(),
+            MathFn::Asin => x.asin(),
+            MathFn::Asinh => x.asinh(),
+            MathFn::Atan => x.atan(),
+            MathFn::Atan2 => x.atan2(y),
+            MathFn::Atanh => x.atanh(),
+            MathFn::Cbrt => x.cbrt(),
+            MathFn::Ceil => x.ceil(),
+            MathFn::Clz32 => to_uint32(x).leading_zeros() as f64,
+            MathFn::Cos => x.cos(),
+            MathFn::Cosh => x.cosh(),
+            MathFn::Exp => x.exp(),
+            MathFn::Expm1 => x.exp_m1(),
+            MathFn::Floor => x.floor(),
+            MathFn::Fround => x as f32 as f64,
+            // C hypot already lets an infinity win over NaN
+            MathFn::Hypot => x.hypot(y),
+            MathFn::Imul => (to_uint32(x) as i32).wrapping_mul(to_uint32(y) as i32) as f64,
+            MathFn::Log => x.ln(),
+            MathFn::Log10 => x.log10(),
+            MathFn::Log1p => x.ln_1p(),
+            MathFn::Log2 => x.log2(),
+            MathFn::Max => max(x, y),
+            MathFn::Min => min(x, y),
+            MathFn::Pow => pow(x, y),
+            MathFn::Random => fastrand::f64(),
+            MathFn::Round => round(x),
+            MathFn::Sign => {
+                if x == 0.0 || x.is_nan() {
+                    x
+                } else {
+                    x.signum()
+                }
+            }
+            MathFn::Sin => x.sin(),
+            MathFn::Sinh => x.sinh(),
+            MathFn::Sqrt => x.sqrt(),
+            MathFn::Tan => x.tan(),
+            MathFn::Tanh => x.tanh(),
+            MathFn::Trunc => x.trunc(),
+        }
+    }
+}
+
+/// ES ToUint32
+pub fn to_uint32(x: f64) -> u32 {
+    if !x.is_finite() {
+        return 0;
+    }
+    (x.trunc() % 4294967296.0) as i64 as u32
+}
+
+/// `Math.max` of two numbers: NaN wins, and +0 is greater than -0
+fn max(x: f64, y: f64) -> f64 {
+    if x.is_nan() || y.is_nan() {
+        f64::NAN
+    } else if x == y {
+        if x.is_sign_positive() { x } else { y }
+    } else {
+        x.max(y)
+    }
+}
+
+/// `Math.min` of two numbers: NaN wins, and -0 is less than +0
+fn min(x: f64, y: f64) -> f64 {
+    if x.is_nan() || y.is_nan() {
+        f64::NAN
+    } else if x == y {
+        if x.is_sign_negative() { x } else { y }
+    } else {
+        x.min(y)
+    }
+}
+
+/// `x ** y`, which unlike C `pow` is NaN for `1 ** NaN` and `1 ** Infinity`
+pub fn pow(x: f64, y: f64) -> f64 {
+    if y.is_nan() || (x.abs() == 1.0 && y.is_infinite()) {
+        f64::NAN
+    } else {
+        x.powf(y)
+    }
+}
+
+/// `Math.round`: halfway cases go towards +Infinity, and -0.5..-0 give -0
+fn round(x: f64) -> f64 {
+    if !x.is_finite() || x == 0.0 {
+        return x;
+    }
+    let floor = x.floor();
+    let rounded = if x - floor >= 0.5 { floor + 1.0 } else { floor };
+    if rounded == 0.0 && x < 0.0 {
+        -0.0
+    } else {
+        rounded
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(name: &str, args: &[f64]) -> f64 {
+        MathFn::from_name(name).unwrap().apply(args)
+    }
+
+    #[test]
+    fn test_names_round_trip() {
+        assert_eq!(MathFn::ALL.len(), 35);
+        for &f in MathFn::ALL {
+            assert_eq!(MathFn::from_name(f.name()), Some(f));
+            assert_eq!(f.stub_name(), format!("ot_math_{}", f.name()));
+        }
+        assert_eq!(MathFn::from_name("PI"), None);
+        assert_eq!(constant("PI"), Some(std::f64::consts::PI));
+        assert_eq!(constant("floor"), None);
+    }
+
+    #[test]
+    fn test_round_follows_js() {
+        assert_eq!(call("round", &[2.5]), 3.0);
+        assert_eq!(call("round", &[-2.5]), -2.0);
+        assert_eq!(call("round", &[0.49999999999999994]), 0.0);
+        assert!(call("round", &[-0.4]).is_sign_negative());
+        assert_eq!(call("round", &[-0.6]), -1.0);
+    }
+
+    #[test]
+    fn test_max_min_hypot_are_variadic() {
+        assert_eq!(call("max", &[]), f64::NEG_INFINITY);
+        assert_eq!(call("min", &[]), f64::INFINITY);
+        assert_eq!(call("max", &[1.0, 5.0, 3.0]), 5.0);
+        assert_eq!(call("min", &[1.0, -5.0, 3.0]), -5.0);
+        assert!(call("max", &[1.0, f64::NAN, 3.0]).is_nan());
+        assert!(call("max", &[-0.0, 0.0]).is_sign_positive());
+        assert!(call("min", &[0.0, -0.0]).is_sign_negative());
+        assert_eq!(call("hypot", &[3.0, 4.0]), 5.0);
+        assert_eq!(call("hypot", &[-3.0]), 3.0);
+        assert_eq!(call("hypot", &[f64::NAN, f64::INFINITY]), f64::INFINITY);
+        assert_eq!(call("hypot", &[]), 0.0);
+    }
+
+    #[test]
+    fn test_integer_functions() {
+        assert_eq!(call("clz32", &[1.0]), 31.0);
+        assert_eq!(call("clz32", &[0.0]), 32.0);
+        assert_eq!(call("clz32", &[-1.0]), 0.0);
+        assert_eq!(call("imul", &[0xffffffffu32 as f64, 5.0]), -5.0);
+        assert_eq!(call("imul", &[3.0, 4.0]), 12.0);
+        assert_eq!(to_uint32(4294967297.0), 1);
+        assert_eq!(to_uint32(f64::NAN), 0);
+    }
+
+    #[test]
+    fn test_edge_cases() {
+        assert!(call("pow", &[1.0, f64::INFINITY]).is_nan());
+        assert!(call("pow", &[1.0, f64::NAN]).is_nan());
+        assert_eq!(call("pow", &[f64::NAN, 0.0]), 1.0);
+        assert!(call("sign", &[-0.0]).is_sign_negative());
+        assert_eq!(call("sign", &[-7.0]), -1.0);
+        assert!(call("sqrt", &[]).is_nan());
+        assert_eq!(call("fround", &[5.5]), 5.5);
+        assert_eq!(call("fround", &[5.05]), 5.050000190734863);
+        let r = call("random", &[]);
+        assert!((0.0..1.0).contains(&r));
+    }
+}
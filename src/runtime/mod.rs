@@ -5,6 +5,11 @@
 //! - Memory allocation and GC (heap.rs)
 //! - Value representation for native interop (abi.rs)
 //! - Extern "C" stubs callable from JIT/AOT code (stubs.rs)
+//! - The ES `Math` functions shared by the VM and native code (math.rs)
+//! - Number formatting and parsing (number.rs)
+//! - The `Date.now()` and `performance.now()` clocks (time.rs)
+//! - Typed array element kinds and conversions (typed_array.rs)
+//! - JS's UTF-16 string indexing over UTF-8 strings (utf16.rs)
 //!
 //! The VM interpreter continues to use JsValue/HeapObject for backwards compatibility.
 //! Native code uses OtValue (NaN-boxed) for efficient representation.
@@ -14,21 +19,11 @@ pub mod abi_tests;
 pub mod abi_version;
 pub mod r#async;
 pub mod heap;
+pub mod math;
+pub mod number;
 pub mod stubs;
+pub mod time;
+pub mod typed_array;
+pub mod utf16;
 
 pub use abi_version::ABI_VERSION;
-
-// Provide rust_eh_personality for panic handling when linking standalone
-#[cfg(not(feature = "vm_interop"))]
-#[unsafe(no_mangle)]
-pub extern "C" fn rust_eh_personality(
-    _version: u32,
-    _actions: u32,
-    _exception_class: u64,
-    _exception_object: *mut std::ffi::c_void,
-    _context: *mut std::ffi::c_void,
-) -> u32 {
-    // Minimal panic personality function for standalone builds
-    // Returns 0 to indicate we can't handle the exception (abort on panic)
-    0
-}
@@ -0,0 +1,531 @@
+//! Number formatting and parsing
+//!
+//! ES Number::toString and the `Number.prototype` formatting methods, and
+//! the string-to-number conversions, shared by the VM and native code.
+//! Rust's float formatting already finds the shortest digits that round-trip
+//! and can print the exact decimal expansion of a double; these functions
+//! lay the digits out the way the spec does. `toFixed`, `toExponential` and
+//! `toPrecision` round halfway cases of the exact value up, where Rust rounds
+//! them to even.
+
+/// `Number.MAX_SAFE_INTEGER`, 2^53 - 1
+pub const MAX_SAFE_INTEGER: f64 = 9007199254740991.0;
+
+/// Most fraction digits `toFixed` and `toExponential` take, and most
+/// significant digits `toPrecision` takes
+pub const MAX_DIGITS: usize = 100;
+
+const DIGIT_CHARS: &[u8; 36] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+
+/// Decimal digits `d1 d2 ... dk` with the value `0.d1d2...dk × 10^point`
+struct Digits {
+    digits: Vec<u8>,
+    point: i32,
+}
+
+impl Digits {
+    /// The digits of a finite `x > 0` from its scientific notation
+    fn parse(scientific: &str) -> Digits {
+        let (mantissa, exponent) = scientific.split_once('e').unwrap();
+        let digits: Vec<u8> = mantissa.bytes().filter(u8::is_ascii_digit).collect();
+        let exponent: i32 = exponent.parse().unwrap();
+        Digits {
+            digits,
+            point: exponent + 1,
+        }
+    }
+
+    /// The shortest digits that round-trip to finite `x > 0`
+    fn shortest(x: f64) -> Digits {
+        Digits::parse(&format!("{:e}", x))
+    }
+
+    /// Every digit of the exact value of finite `x > 0`. A double has at
+    /// most 767 significant digits.
+    fn exact(x: f64) -> Digits {
+        let mut digits = Digits::parse(&format!("{:.800e}", x));
+        while digits.digits.len() > 1 && digits.digits.last() == Some(&b'0') {
+            digits.digits.pop();
+        }
+        digits
+    }
+
+    /// Round to `count` significant digits, halfway cases up, padding with
+    /// zeros
+    fn round(mut self, count: usize) -> Digits {
+        if self.digits.len() > count {
+            let round_up = self.digits[count] >= b'5';
+            self.digits.truncate(count);
+            if round_up {
+                self.increment();
+            }
+        }
+        self.digits.resize(count, b'0');
+        self
+    }
+
+    /// Round to the digits before `10^-fraction_digits`, halfway cases up
+    fn round_at(self, fraction_digits: usize) -> Digits {
+        let count = self.point + fraction_digits as i32;
+        if count < 0 {
+            return Digits {
+                digits: Vec::new(),
+                point: self.point,
+            };
+        }
+        if count == 0 {
+            // Only the rounding digit is left
+            let point = self.point;
+            return match self.digits.first() {
+                Some(&d) if d >= b'5' => Digits {
+                    digits: vec![b'1'],
+                    point: point + 1,
+                },
+                _ => Digits {
+                    digits: Vec::new(),
+                    point,
+                },
+            };
+        }
+        self.round(count as usize)
+    }
+
+    /// Add one in the last place, carrying into a new leading digit
+    fn increment(&mut self) {
+        for digit in self.digits.iter_mut().rev() {
+            if *digit == b'9' {
+                *digit = b'0';
+            } else {
+                *digit += 1;
+                return;
+            }
+        }
+        self.digits.insert(0, b'1');
+        self.digits.pop();
+        self.point += 1;
+    }
+
+    fn string(&self) -> &str {
+        std::str::from_utf8(&self.digits).unwrap()
+    }
+
+    /// `d.ddde+n`, with the digits as they are
+    fn exponential(&self) -> String {
+        let exponent = self.point - 1;
+        let sign = if exponent < 0 { '-' } else { '+' };
+        let (first, rest) = self.string().split_at(1);
+        let point = if rest.is_empty() { "" } else { "." };
+        format!("{}{}{}e{}{}", first, point, rest, sign, exponent.abs())
+    }
+
+    /// The digits with the decimal point placed, without an exponent
+    fn fixed(&self) -> String {
+        let s = self.string();
+        let k = s.len() as i32;
+        let n = self.point;
+        if n >= k {
+            format!("{}{}", s, "0".repeat((n - k) as usize))
+        } else if n > 0 {
+            format!("{}.{}", &s[..n as usize], &s[n as usize..])
+        } else {
+            format!("0.{}{}", "0".repeat(-n as usize), s)
+        }
+    }
+}
+
+/// `-` for negative numbers, and their magnitude. `-0` has no sign.
+fn split_sign(x: f64) -> (&'static str, f64) {
+    if x < 0.0 { ("-", -x) } else { ("", x.abs()) }
+}
+
+/// The name of a number that has no digits
+fn non_finite(x: f64) -> Option<String> {
+    if x.is_nan() {
+        Some("NaN".to_string())
+    } else if x.is_infinite() {
+        Some(if x > 0.0 { "Infinity" } else { "-Infinity" }.to_string())
+    } else {
+        None
+    }
+}
+
+/// ES Number::toString(x): the shortest digits that round-trip, in fixed
+/// notation from 1e-7 to 1e21 and in exponential notation beyond
+pub fn to_string(x: f64) -> String {
+    if let Some(name) = non_finite(x) {
+        return name;
+    }
+    if x == 0.0 {
+        return "0".to_string();
+    }
+    let (sign, x) = split_sign(x);
+    let digits = Digits::shortest(x);
+    if -6 < digits.point && digits.point <= 21 {
+        format!("{}{}", sign, digits.fixed())
+    } else {
+        format!("{}{}", sign, digits.exponential())
+    }
+}
+
+/// `x.toString(radix)` for a radix from 2 to 36: the shortest fraction
+/// digits that still identify `x`, as V8 prints them
+pub fn to_string_radix(x: f64, radix: u32) -> String {
+    if radix == 10 {
+        return to_string(x);
+    }
+    if let Some(name) = non_finite(x) {
+        return name;
+    }
+    if x == 0.0 {
+        return "0".to_string();
+    }
+    let (sign, x) = split_sign(x);
+    let radix_f = radix as f64;
+    let mut integer = x.floor();
+    let mut fraction = x - integer;
+
+    // Half the distance to the next double, below which digits say nothing
+    let next = f64::from_bits(x.to_bits() + 1);
+    let mut delta = (0.5 * (next - x)).max(f64::from_bits(1));
+    let mut fraction_digits = Vec::new();
+    if fraction >= delta {
+        loop {
+            fraction *= radix_f;
+            delta *= radix_f;
+            let digit = fraction as usize;
+            fraction_digits.push(digit);
+            fraction -= digit as f64;
+            if (fraction > 0.5 || (fraction == 0.5 && digit & 1 == 1)) && fraction + delta > 1.0 {
+                // Round up, carrying into the integer part
+                loop {
+                    match fraction_digits.pop() {
+                        None => {
+                            integer += 1.0;
+                            break;
+                        }
+                        Some(digit) if digit + 1 < radix as usize => {
+                            fraction_digits.push(digit + 1);
+                            break;
+                        }
+                        Some(_) => {}
+                    }
+                }
+                break;
+            }
+            if fraction < delta {
+                break;
+            }
+        }
+    }
+
+    // Digits below the precision of the integer part are zeros
+    let mut integer_digits = Vec::new();
+    while integer / radix_f >= 2f64.powi(53) {
+        integer /= radix_f;
+        integer_digits.push(b'0');
+    }
+    loop {
+        let remainder = integer % radix_f;
+        integer_digits.push(DIGIT_CHARS[remainder as usize]);
+        integer = (integer - remainder) / radix_f;
+        if integer <= 0.0 {
+            break;
+        }
+    }
+    integer_digits.reverse();
+
+    let mut result = format!("{}{}", sign, String::from_utf8(integer_digits).unwrap());
+    if !fraction_digits.is_empty() {
+        result.push('.');
+        result.extend(fraction_digits.iter().map(|&d| DIGIT_CHARS[d] as char));
+    }
+    result
+}
+
+/// `x.toFixed(fraction_digits)`: rounded to a multiple of
+/// `10^-fraction_digits`, in exponential notation from 1e21
+pub fn to_fixed(x: f64, fraction_digits: usize) -> String {
+    if let Some(name) = non_finite(x) {
+        return name;
+    }
+    if x.abs() >= 1e21 {
+        return to_string(x);
+    }
+    let (sign, x) = split_sign(x);
+    let mut digits = if x == 0.0 {
+        Digits {
+            digits: Vec::new(),
+            point: 1,
+        }
+    } else {
+        Digits::exact(x).round_at(fraction_digits)
+    };
+    // Lay the digits out from the units place to the last fraction digit
+    if digits.point < 1 {
+        let leading = (1 - digits.point) as usize;
+        digits
+            .digits
+            .splice(0..0, std::iter::repeat_n(b'0', leading));
+        digits.point = 1;
+    }
+    let count = digits.point as usize + fraction_digits;
+    digits.digits.resize(count, b'0');
+    let s = digits.string();
+    let (integer, fraction) = s.split_at(digits.point as usize);
+    if fraction.is_empty() {
+        format!("{}{}", sign, integer)
+    } else {
+        format!("{}{}.{}", sign, integer, fraction)
+    }
+}
+
+/// `x.toExponential(fraction_digits)`: one digit before the point, and
+/// with `None` as many after it as `x` needs to round-trip
+pub fn to_exponential(x: f64, fraction_digits: Option<usize>) -> String {
+    if let Some(name) = non_finite(x) {
+        return name;
+    }
+    let (sign, x) = split_sign(x);
+    let digits = match (x == 0.0, fraction_digits) {
+        (true, f) => Digits {
+            digits: vec![b'0'; f.unwrap_or(0) + 1],
+            point: 1,
+        },
+        (false, None) => Digits::shortest(x),
+        (false, Some(f)) => Digits::exact(x).round(f + 1),
+    };
+    format!("{}{}", sign, digits.exponential())
+}
+
+/// `x.toPrecision(precision)`: `precision` significant digits, in
+/// exponential notation when the exponent is below -6 or not below
+/// `precision`
+pub fn to_precision(x: f64, precision: usize) -> String {
+    if let Some(name) = non_finite(x) {
+        return name;
+    }
+    let (sign, x) = split_sign(x);
+    let digits = if x == 0.0 {
+        Digits {
+            digits: vec![b'0'; precision],
+            point: 1,
+        }
+    } else {
+        Digits::exact(x).round(precision)
+    };
+    let exponent = digits.point - 1;
+    if exponent < -6 || exponent >= precision as i32 {
+        format!("{}{}", sign, digits.exponential())
+    } else {
+        format!("{}{}", sign, digits.fixed())
+    }
+}
+
+/// JS StrWhiteSpaceChar: what `trim` and number parsing skip
+fn is_js_whitespace(c: char) -> bool {
+    c.is_whitespace() || c == '\u{FEFF}'
+}
+
+/// ES StringToNumber: decimal literals, `Infinity`, and `0x`/`0o`/`0b`
+/// integers, surrounded by whitespace; anything else is `NaN`
+pub fn string_to_number(s: &str) -> f64 {
+    let s = s.trim_matches(is_js_whitespace);
+    let radix = match s.get(..2) {
+        Some("0x" | "0X") => 16,
+        Some("0o" | "0O") => 8,
+        Some("0b" | "0B") => 2,
+        _ => 10,
+    };
+    if radix != 10 {
+        let digits = &s[2..];
+        if digits.is_empty() || !digits.chars().all(|c| c.is_digit(radix)) {
+            return f64::NAN;
+        }
+        return digits.chars().fold(0.0, |acc, c| {
+            acc * radix as f64 + c.to_digit(radix).unwrap() as f64
+        });
+    }
+    match s {
+        "" => 0.0,
+        "Infinity" | "+Infinity" => f64::INFINITY,
+        "-Infinity" => f64::NEG_INFINITY,
+        // Rust also accepts "inf" and "NaN", which are not numeric literals
+        s if s.contains(|c: char| c.is_ascii_alphabetic() && c != 'e' && c != 'E') => f64::NAN,
+        s => s.parse().unwrap_or(f64::NAN),
+    }
+}
+
+/// The length of the longest prefix of `s` that is an unsigned decimal
+/// literal, `digits[.digits][e[+-]digits]`, with a digit in the mantissa
+fn decimal_prefix(s: &[u8]) -> usize {
+    let digits_from = |i: usize| i + s[i..].iter().take_while(|b| b.is_ascii_digit()).count();
+    let integer_end = digits_from(0);
+    let mut end = integer_end;
+    if s.get(end) == Some(&b'.') {
+        let fraction_end = digits_from(end + 1);
+        if integer_end > 0 || fraction_end > end + 1 {
+            end = fraction_end;
+        }
+    }
+    if end == 0 {
+        return 0;
+    }
+    if matches!(s.get(end), Some(b'e' | b'E')) {
+        let sign = usize::from(matches!(s.get(end + 1), Some(b'+' | b'-')));
+        let exponent_end = digits_from(end + 1 + sign);
+        if exponent_end > end + 1 + sign {
+            end = exponent_end;
+        }
+    }
+    end
+}
+
+/// `parseFloat(s)`: the longest decimal literal or `Infinity` at the start
+/// of `s` after whitespace, ignoring the rest
+pub fn parse_float(s: &str) -> f64 {
+    let s = s.trim_start_matches(is_js_whitespace);
+    let (negative, unsigned) = match s.as_bytes().first() {
+        Some(b'-') => (true, &s[1..]),
+        Some(b'+') => (false, &s[1..]),
+        _ => (false, s),
+    };
+    let magnitude = if unsigned.starts_with("Infinity") {
+        f64::INFINITY
+    } else {
+        let end = decimal_prefix(unsigned.as_bytes());
+        if end == 0 {
+            return f64::NAN;
+        }
+        unsigned[..end].parse().unwrap_or(f64::NAN)
+    };
+    if negative { -magnitude } else { magnitude }
+}
+
+/// `parseInt(s, radix)`: the digits at the start of `s` after whitespace,
+/// in `radix` from 2 to 36. Radix 0 means 10, or 16 with a `0x` prefix.
+pub fn parse_int(s: &str, radix: i32) -> f64 {
+    let s = s.trim_start_matches(is_js_whitespace);
+    let (negative, mut s) = match s.as_bytes().first() {
+        Some(b'-') => (true, &s[1..]),
+        Some(b'+') => (false, &s[1..]),
+        _ => (false, s),
+    };
+    let mut radix = radix;
+    let hex_prefix = matches!(s.get(..2), Some("0x" | "0X"));
+    if radix == 0 {
+        radix = if hex_prefix { 16 } else { 10 };
+    }
+    if !(2..=36).contains(&radix) {
+        return f64::NAN;
+    }
+    if radix == 16 && hex_prefix {
+        s = &s[2..];
+    }
+    let radix = radix as u32;
+    let end = s.find(|c: char| !c.is_digit(radix)).unwrap_or(s.len());
+    let digits = &s[..end];
+    if digits.is_empty() {
+        return f64::NAN;
+    }
+    let magnitude = if radix == 10 {
+        // Rounded once, rather than at every digit
+        digits.parse().unwrap_or(f64::NAN)
+    } else {
+        digits.chars().fold(0.0, |acc, c| {
+            acc * radix as f64 + c.to_digit(radix).unwrap() as f64
+        })
+    };
+    if negative { -magnitude } else { magnitude }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_string_is_shortest_round_trip() {
+        assert_eq!(to_string(0.1 + 0.2), "0.30000000000000004");
+        assert_eq!(to_string(-0.0), "0");
+        assert_eq!(to_string(123.0), "123");
+        assert_eq!(to_string(1e21), "1e+21");
+        assert_eq!(to_string(1e20), "100000000000000000000");
+        assert_eq!(to_string(1.5e-7), "1.5e-7");
+        assert_eq!(to_string(0.000001), "0.000001");
+        assert_eq!(to_string(5e-324), "5e-324");
+        assert_eq!(
+            to_string(-1.7976931348623157e308),
+            "-1.7976931348623157e+308"
+        );
+        assert_eq!(to_string(f64::NEG_INFINITY), "-Infinity");
+        assert_eq!(to_string(f64::NAN), "NaN");
+    }
+
+    #[test]
+    fn test_to_string_radix() {
+        assert_eq!(to_string_radix(255.0, 16), "ff");
+        assert_eq!(to_string_radix(-255.0, 2), "-11111111");
+        assert_eq!(to_string_radix(0.5, 2), "0.1");
+        assert_eq!(
+            to_string_radix(0.1, 3),
+            "0.0022002200220022002200220022002201"
+        );
+        assert_eq!(to_string_radix(3.75, 16), "3.c");
+        assert_eq!(
+            to_string_radix(std::f64::consts::PI, 7),
+            "3.066365143203613411"
+        );
+        assert_eq!(
+            to_string_radix(-0.0001234, 5),
+            "-0.0000014310014340322421131001"
+        );
+        assert_eq!(to_string_radix(2f64.powi(60), 36), "8rc4kbdvss00");
+        assert_eq!(to_string_radix(35.0, 36), "z");
+    }
+
+    #[test]
+    fn test_fixed_exponential_and_precision_round_halfway_up() {
+        assert_eq!(to_fixed(1.005, 2), "1.00"); // 1.00499999999999989...
+        assert_eq!(to_fixed(8.345, 2), "8.35");
+        assert_eq!(to_fixed(1.45, 1), "1.4");
+        assert_eq!(to_fixed(2.5, 0), "3");
+        assert_eq!(to_fixed(-2.5, 0), "-3");
+        assert_eq!(to_fixed(0.000001, 2), "0.00");
+        assert_eq!(to_fixed(0.5, 0), "1");
+        assert_eq!(to_fixed(0.05, 1), "0.1");
+        assert_eq!(to_fixed(99.99, 1), "100.0");
+        assert_eq!(to_fixed(123.456, 0), "123");
+        assert_eq!(to_fixed(-0.0, 2), "0.00");
+        assert_eq!(to_fixed(1e21, 2), "1e+21");
+        assert_eq!(to_exponential(123456.0, Some(2)), "1.23e+5");
+        assert_eq!(to_exponential(0.00015, None), "1.5e-4");
+        assert_eq!(to_exponential(0.0, Some(2)), "0.00e+0");
+        assert_eq!(to_exponential(9.99, Some(1)), "1.0e+1");
+        assert_eq!(to_precision(123.456, 4), "123.5");
+        assert_eq!(to_precision(1.25, 2), "1.3");
+        assert_eq!(to_precision(5e-7, 3), "5.00e-7");
+        assert_eq!(to_exponential(123.456, None), "1.23456e+2");
+        assert_eq!(to_precision(0.000123, 2), "0.00012");
+        assert_eq!(to_precision(123456.0, 2), "1.2e+5");
+        assert_eq!(to_precision(1e-7, 1), "1e-7");
+        assert_eq!(to_precision(0.0, 3), "0.00");
+    }
+
+    #[test]
+    fn test_parse_float_and_parse_int_read_a_prefix() {
+        assert_eq!(parse_float("  3.14abc"), 3.14);
+        assert_eq!(parse_float("-.5e1x"), -5.0);
+        assert_eq!(parse_float("1e"), 1.0);
+        assert_eq!(parse_float("-Infinityx"), f64::NEG_INFINITY);
+        assert!(parse_float(".").is_nan());
+        assert!(parse_float("abc").is_nan());
+        assert_eq!(parse_int("  42px", 0), 42.0);
+        assert_eq!(parse_int("0x1F", 0), 31.0);
+        assert_eq!(parse_int("0x1F", 16), 31.0);
+        assert_eq!(parse_int("-101", 2), -5.0);
+        assert_eq!(parse_int("3.9", 10), 3.0);
+        assert!(parse_int("z", 10).is_nan());
+        assert!(parse_int("1", 37).is_nan());
+        assert_eq!(string_to_number(" 0b101 "), 5.0);
+        assert!(string_to_number("1px").is_nan());
+    }
+}
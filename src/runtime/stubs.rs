@@ -14,8 +14,13 @@
 
 use super::abi::OtValue;
 use super::heap::{
-    NativeArray, NativeObject, NativeString, ObjectHeader, ObjectKind, PropertyMap, heap,
+    NativeArray, NativeObject, NativeString, NativeTypedArray, ObjectHeader, ObjectKind,
+    PropertyMap, heap,
 };
+use super::math::MathFn;
+use super::number;
+use super::typed_array::ElementKind;
+use super::utf16;
 
 // =========================================================================
 // Allocation Stubs
@@ -43,6 +48,50 @@ pub extern "C" fn ot_alloc_array(capacity: usize) -> u64 {
     }
 }
 
+/// Allocate a typed array: `new Float64Array(length)`, or a copy of a native
+/// array's elements. `kind` is an `ElementKind` tag, not a NaN-boxed value.
+///
+/// Returns undefined for an invalid length or on allocation failure.
+#[unsafe(no_mangle)]
+pub extern "C" fn ot_new_typed_array(kind: u64, source: u64) -> u64 {
+    let Some(kind) = u8::try_from(kind).ok().and_then(ElementKind::from_tag) else {
+        return OtValue::undefined().to_bits();
+    };
+    let source = OtValue::from_bits(source);
+
+    let elements: Vec<f64> = match source.as_pointer() {
+        Some(ptr) => unsafe {
+            if ptr.as_ref::<ObjectHeader>().kind != ObjectKind::Array {
+                return OtValue::undefined().to_bits();
+            }
+            let arr = ptr.as_ref::<NativeArray>();
+            (0..arr.len as usize)
+                .map(|i| number_arg(*arr.elements.add(i)))
+                .collect()
+        },
+        None => {
+            // ToIndex: undefined and NaN are 0
+            let len = number_arg(source.to_bits());
+            let len = if len.is_nan() { 0.0 } else { len.trunc() };
+            if !(0.0..=u32::MAX as f64).contains(&len) {
+                return OtValue::undefined().to_bits();
+            }
+            vec![0.0; len as usize]
+        }
+    };
+
+    let Some(ptr) = heap().alloc_typed_array(kind, elements.len()) else {
+        return OtValue::undefined().to_bits();
+    };
+    unsafe {
+        let bytes = ptr.as_mut::<NativeTypedArray>().bytes_mut();
+        for (chunk, value) in bytes.chunks_exact_mut(kind.size()).zip(elements) {
+            kind.write(chunk, value, true);
+        }
+    }
+    OtValue::pointer(ptr).to_bits()
+}
+
 /// Allocate a new string from UTF-8 bytes.
 ///
 /// Returns a OtValue containing the string pointer, or undefined on failure.
@@ -125,23 +174,33 @@ pub extern "C" fn ot_get_prop(obj: u64, key: *const u8, key_len: usize) -> u64 {
                 }
                 OtValue::undefined().to_bits()
             }
+            ObjectKind::TypedArray => {
+                let arr = ptr.as_ref::<NativeTypedArray>();
+                match key_str {
+                    "length" => OtValue::number(arr.len as f64).to_bits(),
+                    "byteLength" => {
+                        OtValue::number((arr.len as usize * arr.kind().size()) as f64).to_bits()
+                    }
+                    _ => match key_str.parse::<usize>() {
+                        Ok(idx) => ot_get_element(obj, OtValue::number(idx as f64).to_bits()),
+                        Err(_) => OtValue::undefined().to_bits(),
+                    },
+                }
+            }
             ObjectKind::String => {
                 let s = ptr.as_ref::<NativeString>();
                 // Handle "length" property
                 if key_str == "length" {
-                    return OtValue::number(s.len as f64).to_bits();
+                    return OtValue::number(s.utf16_len() as f64).to_bits();
                 }
-                // Try to parse as index (for charAt)
-                if let Ok(idx) = key_str.parse::<usize>() {
-                    let str_data = s.as_str();
-                    if let Some(ch) = str_data.chars().nth(idx) {
-                        // Allocate a single-char string
-                        let mut buf = [0u8; 4];
-                        let ch_str = ch.encode_utf8(&mut buf);
-                        match heap().alloc_string(ch_str) {
-                            Some(ptr) => return OtValue::pointer(ptr).to_bits(),
-                            None => return OtValue::undefined().to_bits(),
-                        }
+                // Try to parse as a code unit index (for charAt)
+                if let Ok(idx) = key_str.parse::<usize>()
+                    && idx < s.utf16_len()
+                {
+                    let unit = utf16::slice(s.as_str(), idx, idx + 1);
+                    match heap().alloc_string(&unit) {
+                        Some(ptr) => return OtValue::pointer(ptr).to_bits(),
+                        None => return OtValue::undefined().to_bits(),
                     }
                 }
                 OtValue::undefined().to_bits()
@@ -213,10 +272,32 @@ pub extern "C" fn ot_set_prop(obj: u64, key: *const u8, key_len: usize, value: u
 // Array Access Stubs
 // =========================================================================
 
-/// Get an element from an array by index.
+/// An element index: a non-negative integer number.
+fn element_index(index: OtValue) -> Option<usize> {
+    let n = index.as_number()?;
+    (n >= 0.0 && n.fract() == 0.0 && n <= u32::MAX as f64).then_some(n as usize)
+}
+
+/// The property key an element access means on a plain object.
+fn element_key(index: OtValue) -> Option<String> {
+    if let Some(n) = index.as_number() {
+        return Some(number::to_string(n));
+    }
+    let ptr = index.as_pointer()?;
+    unsafe {
+        (ptr.as_ref::<ObjectHeader>().kind == ObjectKind::String)
+            .then(|| ptr.as_ref::<NativeString>().as_str().to_string())
+    }
+}
+
+/// Get an element: `arr[index]`.
+///
+/// Arrays and typed arrays take a numeric index; on other objects the index
+/// is a property key.
 #[unsafe(no_mangle)]
-pub extern "C" fn ot_get_element(arr: u64, index: usize) -> u64 {
+pub extern "C" fn ot_get_element(arr: u64, index: u64) -> u64 {
     let val = OtValue::from_bits(arr);
+    let index = OtValue::from_bits(index);
 
     let ptr = match val.as_pointer() {
         Some(p) => p,
@@ -225,23 +306,41 @@ pub extern "C" fn ot_get_element(arr: u64, index: usize) -> u64 {
 
     unsafe {
         let header = ptr.as_ref::<ObjectHeader>();
-        if header.kind != ObjectKind::Array {
-            return OtValue::undefined().to_bits();
-        }
-
-        let arr = ptr.as_ref::<NativeArray>();
-        if index < arr.len as usize {
-            *arr.elements.add(index)
-        } else {
-            OtValue::undefined().to_bits()
+        match header.kind {
+            ObjectKind::Array => {
+                let arr = ptr.as_ref::<NativeArray>();
+                match element_index(index) {
+                    Some(i) if i < arr.len as usize => *arr.elements.add(i),
+                    _ => OtValue::undefined().to_bits(),
+                }
+            }
+            ObjectKind::TypedArray => {
+                let arr = ptr.as_ref::<NativeTypedArray>();
+                let kind = arr.kind();
+                match element_index(index) {
+                    Some(i) if i < arr.len as usize => {
+                        let offset = i * kind.size();
+                        OtValue::number(kind.read(&arr.bytes()[offset..], true)).to_bits()
+                    }
+                    _ => OtValue::undefined().to_bits(),
+                }
+            }
+            _ => match element_key(index) {
+                Some(key) => ot_get_prop(arr, key.as_ptr(), key.len()),
+                None => OtValue::undefined().to_bits(),
+            },
         }
     }
 }
 
-/// Set an element in an array by index.
+/// Set an element: `arr[index] = value`.
+///
+/// Typed arrays convert the value to their element kind and ignore indices
+/// out of bounds; on other objects the index is a property key.
 #[unsafe(no_mangle)]
-pub extern "C" fn ot_set_element(arr: u64, index: usize, value: u64) {
+pub extern "C" fn ot_set_element(arr: u64, index: u64, value: u64) {
     let val = OtValue::from_bits(arr);
+    let index = OtValue::from_bits(index);
 
     let ptr = match val.as_pointer() {
         Some(p) => p,
@@ -250,15 +349,32 @@ pub extern "C" fn ot_set_element(arr: u64, index: usize, value: u64) {
 
     unsafe {
         let header = ptr.as_ref::<ObjectHeader>();
-        if header.kind != ObjectKind::Array {
-            return;
-        }
-
-        let arr = ptr.as_mut::<NativeArray>();
-        if index < arr.capacity as usize {
-            *arr.elements.add(index) = value;
-            if index >= arr.len as usize {
-                arr.len = (index + 1) as u32;
+        match header.kind {
+            ObjectKind::Array => {
+                let arr = ptr.as_mut::<NativeArray>();
+                if let Some(i) = element_index(index)
+                    && i < arr.capacity as usize
+                {
+                    *arr.elements.add(i) = value;
+                    if i >= arr.len as usize {
+                        arr.len = (i + 1) as u32;
+                    }
+                }
+            }
+            ObjectKind::TypedArray => {
+                let arr = ptr.as_mut::<NativeTypedArray>();
+                let kind = arr.kind();
+                if let Some(i) = element_index(index)
+                    && i < arr.len as usize
+                {
+                    let offset = i * kind.size();
+                    kind.write(&mut arr.bytes_mut()[offset..], number_arg(value), true);
+                }
+            }
+            _ => {
+                if let Some(key) = element_key(index) {
+                    ot_set_prop(arr, key.as_ptr(), key.len(), value);
+                }
             }
         }
     }
@@ -399,10 +515,7 @@ pub extern "C" fn ot_eq_strict(a: u64, b: u64) -> u64 {
 
 /// Try to extract string refs from two pointer OtValues for comparison.
 /// Returns None if either value is not a string pointer.
-unsafe fn try_get_string_pair(
-    va: OtValue,
-    vb: OtValue,
-) -> Option<(*const u8, usize, *const u8, usize)> {
+unsafe fn try_get_string_pair<'a>(va: OtValue, vb: OtValue) -> Option<(&'a str, &'a str)> {
     let ptr_a = va.as_pointer()?;
     let ptr_b = vb.as_pointer()?;
     unsafe {
@@ -413,9 +526,7 @@ unsafe fn try_get_string_pair(
         }
         let sa = ptr_a.as_ref::<NativeString>();
         let sb = ptr_b.as_ref::<NativeString>();
-        let str_a = sa.as_str();
-        let str_b = sb.as_str();
-        Some((str_a.as_ptr(), str_a.len(), str_b.as_ptr(), str_b.len()))
+        Some((sa.as_str(), sb.as_str()))
     }
 }
 
@@ -428,10 +539,8 @@ pub extern "C" fn ot_lt(a: u64, b: u64) -> u64 {
         return OtValue::boolean(va.as_number_unchecked() < vb.as_number_unchecked()).to_bits();
     }
     unsafe {
-        if let Some((pa, la, pb, lb)) = try_get_string_pair(va, vb) {
-            let sa = std::str::from_utf8_unchecked(std::slice::from_raw_parts(pa, la));
-            let sb = std::str::from_utf8_unchecked(std::slice::from_raw_parts(pb, lb));
-            return OtValue::boolean(sa < sb).to_bits();
+        if let Some((sa, sb)) = try_get_string_pair(va, vb) {
+            return OtValue::boolean(utf16::compare(sa, sb).is_lt()).to_bits();
         }
     }
     OtValue::boolean(false).to_bits()
@@ -446,10 +555,8 @@ pub extern "C" fn ot_gt(a: u64, b: u64) -> u64 {
         return OtValue::boolean(va.as_number_unchecked() > vb.as_number_unchecked()).to_bits();
     }
     unsafe {
-        if let Some((pa, la, pb, lb)) = try_get_string_pair(va, vb) {
-            let sa = std::str::from_utf8_unchecked(std::slice::from_raw_parts(pa, la));
-            let sb = std::str::from_utf8_unchecked(std::slice::from_raw_parts(pb, lb));
-            return OtValue::boolean(sa > sb).to_bits();
+        if let Some((sa, sb)) = try_get_string_pair(va, vb) {
+            return OtValue::boolean(utf16::compare(sa, sb).is_gt()).to_bits();
         }
     }
     OtValue::boolean(false).to_bits()
@@ -464,10 +571,8 @@ pub extern "C" fn ot_lte(a: u64, b: u64) -> u64 {
         return OtValue::boolean(va.as_number_unchecked() <= vb.as_number_unchecked()).to_bits();
     }
     unsafe {
-        if let Some((pa, la, pb, lb)) = try_get_string_pair(va, vb) {
-            let sa = std::str::from_utf8_unchecked(std::slice::from_raw_parts(pa, la));
-            let sb = std::str::from_utf8_unchecked(std::slice::from_raw_parts(pb, lb));
-            return OtValue::boolean(sa <= sb).to_bits();
+        if let Some((sa, sb)) = try_get_string_pair(va, vb) {
+            return OtValue::boolean(utf16::compare(sa, sb).is_le()).to_bits();
         }
     }
     OtValue::boolean(false).to_bits()
@@ -482,10 +587,8 @@ pub extern "C" fn ot_gte(a: u64, b: u64) -> u64 {
         return OtValue::boolean(va.as_number_unchecked() >= vb.as_number_unchecked()).to_bits();
     }
     unsafe {
-        if let Some((pa, la, pb, lb)) = try_get_string_pair(va, vb) {
-            let sa = std::str::from_utf8_unchecked(std::slice::from_raw_parts(pa, la));
-            let sb = std::str::from_utf8_unchecked(std::slice::from_raw_parts(pb, lb));
-            return OtValue::boolean(sa >= sb).to_bits();
+        if let Some((sa, sb)) = try_get_string_pair(va, vb) {
+            return OtValue::boolean(utf16::compare(sa, sb).is_ge()).to_bits();
         }
     }
     OtValue::boolean(false).to_bits()
@@ -636,6 +739,70 @@ pub extern "C" fn ot_to_number(a: u64) -> u64 {
     OtValue::number(f64::NAN).to_bits()
 }
 
+// =========================================================================
+// Math Stubs
+// =========================================================================
+
+/// ToNumber of a stub argument
+fn number_arg(a: u64) -> f64 {
+    f64::from_bits(ot_to_number(a))
+}
+
+macro_rules! math_stubs {
+    ($($stub:ident($($arg:ident),*) => $f:ident,)*) => {
+        $(
+            /// `Math.*` on NaN-boxed arguments; see `runtime::math`.
+            #[unsafe(no_mangle)]
+            pub extern "C" fn $stub($($arg: u64),*) -> u64 {
+                OtValue::number(MathFn::$f.apply(&[$(number_arg($arg)),*])).to_bits()
+            }
+        )*
+
+        /// Names and addresses of the `ot_math_*` stubs, for the JIT
+        pub fn math_stub_symbols() -> Vec<(&'static str, *const u8)> {
+            vec![$((stringify!($stub), $stub as *const u8),)*]
+        }
+    };
+}
+
+math_stubs! {
+    ot_math_abs(x) => Abs,
+    ot_math_acos(x) => Acos,
+    ot_math_acosh(x) => Acosh,
+    ot_math_asin(x) => Asin,
+    ot_math_asinh(x) => Asinh,
+    ot_math_atan(x) => Atan,
+    ot_math_atan2(y, x) => Atan2,
+    ot_math_atanh(x) => Atanh,
+    ot_math_cbrt(x) => Cbrt,
+    ot_math_ceil(x) => Ceil,
+    ot_math_clz32(x) => Clz32,
+    ot_math_cos(x) => Cos,
+    ot_math_cosh(x) => Cosh,
+    ot_math_exp(x) => Exp,
+    ot_math_expm1(x) => Expm1,
+    ot_math_floor(x) => Floor,
+    ot_math_fround(x) => Fround,
+    ot_math_hypot(x, y) => Hypot,
+    ot_math_imul(x, y) => Imul,
+    ot_math_log(x) => Log,
+    ot_math_log10(x) => Log10,
+    ot_math_log1p(x) => Log1p,
+    ot_math_log2(x) => Log2,
+    ot_math_max(x, y) => Max,
+    ot_math_min(x, y) => Min,
+    ot_math_pow(x, y) => Pow,
+    ot_math_random() => Random,
+    ot_math_round(x) => Round,
+    ot_math_sign(x) => Sign,
+    ot_math_sin(x) => Sin,
+    ot_math_sinh(x) => Sinh,
+    ot_math_sqrt(x) => Sqrt,
+    ot_math_tan(x) => Tan,
+    ot_math_tanh(x) => Tanh,
+    ot_math_trunc(x) => Trunc,
+}
+
 // =========================================================================
 // Function Call Stubs
 // =========================================================================
@@ -688,12 +855,19 @@ pub extern "C" fn ot_make_closure(func_addr: u64, env: u64) -> u64 {
     OtValue::number(func_addr as f64).to_bits()
 }
 
-/// Print a value to the console.
+/// Print a value to the console. Returns undefined, the value of the call.
 #[unsafe(no_mangle)]
-pub extern "C" fn ot_console_log(value: u64) {
+pub extern "C" fn ot_console_log(value: u64) -> u64 {
     let va = OtValue::from_bits(value);
     let s = value_to_string(va);
     println!("{}", s);
+    OtValue::undefined().to_bits()
+}
+
+/// `performance.now()`: milliseconds on the monotonic clock; see `runtime::time`.
+#[unsafe(no_mangle)]
+pub extern "C" fn ot_performance_now() -> u64 {
+    OtValue::number(super::time::performance_now()).to_bits()
 }
 
 // =========================================================================
@@ -703,19 +877,7 @@ pub extern "C" fn ot_console_log(value: u64) {
 /// Convert a OtValue to a string representation.
 fn value_to_string(val: OtValue) -> String {
     if val.is_number() {
-        let n = val.as_number_unchecked();
-        if n.is_nan() {
-            return "NaN".to_string();
-        }
-        if n.is_infinite() {
-            return if n.is_sign_positive() {
-                "Infinity"
-            } else {
-                "-Infinity"
-            }
-            .to_string();
-        }
-        return format!("{}", n);
+        return number::to_string(val.as_number_unchecked());
     }
 
     if val.is_boolean() {
@@ -760,6 +922,16 @@ fn value_to_string(val: OtValue) -> String {
                 ObjectKind::ByteStream => {
                     return "[ByteStream]".to_string();
                 }
+                ObjectKind::TypedArray => {
+                    let arr = ptr.as_ref::<NativeTypedArray>();
+                    let kind = arr.kind();
+                    let parts: Vec<String> = arr
+                        .bytes()
+                        .chunks_exact(kind.size())
+                        .map(|chunk| value_to_string(OtValue::number(kind.read(chunk, true))))
+                        .collect();
+                    return format!("[{}]", parts.join(","));
+                }
             }
         }
     }
@@ -964,6 +1136,33 @@ mod tests {
         assert!((div - 3.333333).abs() < 0.001);
     }
 
+    #[test]
+    fn test_math_stubs() {
+        let symbols = math_stub_symbols();
+        assert_eq!(symbols.len(), MathFn::ALL.len());
+        for (&(name, _), f) in symbols.iter().zip(MathFn::ALL) {
+            assert_eq!(name, f.stub_name());
+        }
+
+        let floor = ot_math_floor(OtValue::number(2.7).to_bits());
+        assert_eq!(OtValue::from_bits(floor).as_number(), Some(2.0));
+        let max = ot_math_max(OtValue::boolean(true).to_bits(), OtValue::null().to_bits());
+        assert_eq!(OtValue::from_bits(max).as_number(), Some(1.0));
+        let sqrt = OtValue::from_bits(ot_math_sqrt(OtValue::undefined().to_bits()));
+        assert!(sqrt.as_number().unwrap().is_nan());
+    }
+
+    #[test]
+    fn test_performance_now() {
+        let a = OtValue::from_bits(ot_performance_now())
+            .as_number()
+            .unwrap();
+        let b = OtValue::from_bits(ot_performance_now())
+            .as_number()
+            .unwrap();
+        assert!(a >= 0.0 && b >= a);
+    }
+
     #[test]
     fn test_object_property() {
         let obj_bits = ot_alloc_object();
@@ -977,4 +1176,47 @@ mod tests {
 
         assert_eq!(OtValue::from_bits(retrieved).as_number(), Some(42.0));
     }
+
+    #[test]
+    fn test_typed_array_elements() {
+        let num = |n: f64| OtValue::number(n).to_bits();
+        let arr = ot_new_typed_array(ElementKind::Uint8 as u64, num(3.0));
+        ot_set_element(arr, num(1.0), num(257.0));
+        ot_set_element(arr, num(3.0), num(9.0));
+        let get = |i: f64| OtValue::from_bits(ot_get_element(arr, num(i))).as_number();
+        assert_eq!(get(0.0), Some(0.0));
+        assert_eq!(get(1.0), Some(1.0));
+        assert_eq!(get(3.0), None);
+        assert_eq!(value_to_string(OtValue::from_bits(arr)), "[0,1,0]");
+
+        let negative = ot_new_typed_array(ElementKind::Int32 as u64, num(-1.0));
+        assert!(OtValue::from_bits(negative).is_undefined());
+    }
+
+    #[test]
+    fn test_element_access_on_objects_uses_keys() {
+        let obj = ot_alloc_object();
+        let value = OtValue::number(7.0).to_bits();
+        ot_set_element(obj, OtValue::number(2.0).to_bits(), value);
+        let key = "2";
+        let retrieved = ot_get_prop(obj, key.as_ptr(), key.len());
+        assert_eq!(OtValue::from_bits(retrieved).as_number(), Some(7.0));
+    }
+
+    #[test]
+    fn test_strings_index_by_code_unit() {
+        let string = |s: &str| ot_alloc_string(s.as_ptr(), s.len());
+        let s = string("é😀");
+        let get = |key: &str| {
+            value_to_string(OtValue::from_bits(ot_get_prop(s, key.as_ptr(), key.len())))
+        };
+        assert_eq!(get("length"), "3");
+        assert_eq!(get("0"), "é");
+        assert_eq!(get("1"), "\u{FFFD}");
+        assert_eq!(get("3"), "undefined");
+
+        let less = |a: &str, b: &str| OtValue::from_bits(ot_lt(string(a), string(b))).as_boolean();
+        assert_eq!(less("a", "b"), Some(true));
+        assert_eq!(less("😀", "\u{FF61}"), Some(true));
+    }
 }
@@ -0,0 +1,54 @@
+//! Clocks shared by the VM and native code
+//!
+//! `performance.now()` counts milliseconds on a monotonic clock from a time
+//! origin fixed the first time any clock is read; `Date.now()` reads the
+//! system clock.
+
+use std::sync::OnceLock;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// The monotonic instant and wall-clock time of the time origin
+fn origin() -> &'static (Instant, f64) {
+    static ORIGIN: OnceLock<(Instant, f64)> = OnceLock::new();
+    ORIGIN.get_or_init(|| (Instant::now(), epoch_millis()))
+}
+
+/// Milliseconds since the Unix epoch on the system clock (`Date.now()`)
+pub fn epoch_millis() -> f64 {
+    match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(since) => since.as_millis() as f64,
+        Err(before) => -(before.duration().as_millis() as f64),
+    }
+}
+
+/// Milliseconds since the time origin, with sub-millisecond precision
+/// (`performance.now()`)
+pub fn performance_now() -> f64 {
+    origin().0.elapsed().as_secs_f64() * 1000.0
+}
+
+/// The time origin in milliseconds since the Unix epoch
+/// (`performance.timeOrigin`)
+pub fn time_origin() -> f64 {
+    origin().1
+}
+
+/// Start the `performance.now()` clock if nothing has read it yet
+pub fn init() {
+    origin();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_performance_now_is_monotonic() {
+        let a = performance_now();
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        let b = performance_now();
+        assert!(a >= 0.0);
+        assert!(b - a >= 2.0, "{} then {}", a, b);
+        assert!((time_origin() + b - epoch_millis()).abs() < 1000.0);
+    }
+}
@@ -0,0 +1,217 @@
+//! Element kinds of typed arrays
+//!
+//! The VM's `Uint8Array` and friends and native code's typed array objects
+//! (see `heap::NativeTypedArray`) store elements the same way: packed, each
+//! converted from a number with the ES rules for its kind. Integer kinds wrap
+//! modulo 2^n, `Uint8ClampedArray` rounds half to even and clamps, and
+//! `Float32Array` rounds to single precision.
+
+/// The element type of a typed array
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ElementKind {
+    Int8 = 0,
+    Uint8 = 1,
+    Uint8Clamped = 2,
+    Int16 = 3,
+    Uint16 = 4,
+    Int32 = 5,
+    Uint32 = 6,
+    Float32 = 7,
+    Float64 = 8,
+}
+
+impl ElementKind {
+    /// Every kind, in the order of their constructors in the ES spec
+    pub const ALL: [ElementKind; 9] = [
+        ElementKind::Int8,
+        ElementKind::Uint8,
+        ElementKind::Uint8Clamped,
+        ElementKind::Int16,
+        ElementKind::Uint16,
+        ElementKind::Int32,
+        ElementKind::Uint32,
+        ElementKind::Float32,
+        ElementKind::Float64,
+    ];
+
+    /// The kind with the given `repr(u8)` tag
+    pub fn from_tag(tag: u8) -> Option<Self> {
+        Self::ALL.get(tag as usize).copied()
+    }
+
+    /// The kind of a constructor name such as `"Uint8Array"`
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+
+    /// The constructor name
+    pub fn name(self) -> &'static str {
+        match self {
+            ElementKind::Int8 => "Int8Array",
+            ElementKind::Uint8 => "Uint8Array",
+            ElementKind::Uint8Clamped => "Uint8ClampedArray",
+            ElementKind::Int16 => "Int16Array",
+            ElementKind::Uint16 => "Uint16Array",
+            ElementKind::Int32 => "Int32Array",
+            ElementKind::Uint32 => "Uint32Array",
+            ElementKind::Float32 => "Float32Array",
+            ElementKind::Float64 => "Float64Array",
+        }
+    }
+
+    /// The `DataView` accessor suffix, as in `getUint8`
+    pub fn view_name(self) -> &'static str {
+        match self {
+            ElementKind::Int8 => "Int8",
+            ElementKind::Uint8 | ElementKind::Uint8Clamped => "Uint8",
+            ElementKind::Int16 => "Int16",
+            ElementKind::Uint16 => "Uint16",
+            ElementKind::Int32 => "Int32",
+            ElementKind::Uint32 => "Uint32",
+            ElementKind::Float32 => "Float32",
+            ElementKind::Float64 => "Float64",
+        }
+    }
+
+    /// Short name for IR dumps, like `u8` or `f64`
+    pub fn short_name(self) -> &'static str {
+        match self {
+            ElementKind::Int8 => "i8",
+            ElementKind::Uint8 => "u8",
+            ElementKind::Uint8Clamped => "u8c",
+            ElementKind::Int16 => "i16",
+            ElementKind::Uint16 => "u16",
+            ElementKind::Int32 => "i32",
+            ElementKind::Uint32 => "u32",
+            ElementKind::Float32 => "f32",
+            ElementKind::Float64 => "f64",
+        }
+    }
+
+    /// Bytes per element, `BYTES_PER_ELEMENT`
+    pub fn size(self) -> usize {
+        match self {
+            ElementKind::Int8 | ElementKind::Uint8 | ElementKind::Uint8Clamped => 1,
+            ElementKind::Int16 | ElementKind::Uint16 => 2,
+            ElementKind::Int32 | ElementKind::Uint32 | ElementKind::Float32 => 4,
+            ElementKind::Float64 => 8,
+        }
+    }
+
+    /// Whether elements are stored as IEEE 754 floats
+    pub fn is_float(self) -> bool {
+        matches!(self, ElementKind::Float32 | ElementKind::Float64)
+    }
+
+    /// Read one element from the first [`size`](Self::size) bytes of `bytes`
+    pub fn read(self, bytes: &[u8], little_endian: bool) -> f64 {
+        let mut buf = [0u8; 8];
+        let size = self.size();
+        buf[..size].copy_from_slice(&bytes[..size]);
+        if !little_endian {
+            buf[..size].reverse();
+        }
+        match self {
+            ElementKind::Int8 => buf[0] as i8 as f64,
+            ElementKind::Uint8 | ElementKind::Uint8Clamped => buf[0] as f64,
+            ElementKind::Int16 => i16::from_le_bytes([buf[0], buf[1]]) as f64,
+            ElementKind::Uint16 => u16::from_le_bytes([buf[0], buf[1]]) as f64,
+            ElementKind::Int32 => i32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) as f64,
+            ElementKind::Uint32 => u32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) as f64,
+            ElementKind::Float32 => f32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) as f64,
+            ElementKind::Float64 => f64::from_le_bytes(buf),
+        }
+    }
+
+    /// Convert `value` to this kind and write it to the first
+    /// [`size`](Self::size) bytes of `bytes`
+    pub fn write(self, bytes: &mut [u8], value: f64, little_endian: bool) {
+        let size = self.size();
+        let mut buf = match self {
+            ElementKind::Uint8Clamped => {
+                let clamped = if value.is_nan() {
+                    0.0
+                } else {
+                    value.clamp(0.0, 255.0).round_ties_even()
+                };
+                (clamped as u8 as u64).to_le_bytes()
+            }
+            ElementKind::Float32 => ((value as f32).to_bits() as u64).to_le_bytes(),
+            ElementKind::Float64 => value.to_bits().to_le_bytes(),
+            // Every integer kind keeps the low bits of ToUint32
+            _ => (to_uint32(value) as u64).to_le_bytes(),
+        };
+        if !little_endian {
+            buf[..size].reverse();
+        }
+        bytes[..size].copy_from_slice(&buf[..size]);
+    }
+}
+
+/// ES ToUint32: NaN and infinities become 0, others truncate modulo 2^32
+fn to_uint32(value: f64) -> u32 {
+    if !value.is_finite() {
+        return 0;
+    }
+    value.trunc().rem_euclid(4_294_967_296.0) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roundtrip(kind: ElementKind, value: f64) -> f64 {
+        let mut bytes = [0u8; 8];
+        kind.write(&mut bytes, value, true);
+        kind.read(&bytes, true)
+    }
+
+    #[test]
+    fn test_integer_kinds_wrap() {
+        assert_eq!(roundtrip(ElementKind::Uint8, 256.0), 0.0);
+        assert_eq!(roundtrip(ElementKind::Uint8, -1.0), 255.0);
+        assert_eq!(roundtrip(ElementKind::Int8, 200.0), -56.0);
+        assert_eq!(roundtrip(ElementKind::Int16, 32768.0), -32768.0);
+        assert_eq!(roundtrip(ElementKind::Uint16, 1.9), 1.0);
+        assert_eq!(roundtrip(ElementKind::Int32, 2147483648.0), -2147483648.0);
+        assert_eq!(roundtrip(ElementKind::Uint32, -1.0), 4294967295.0);
+        assert_eq!(roundtrip(ElementKind::Int32, f64::NAN), 0.0);
+        assert_eq!(roundtrip(ElementKind::Int32, f64::INFINITY), 0.0);
+    }
+
+    #[test]
+    fn test_clamped_rounds_half_to_even() {
+        assert_eq!(roundtrip(ElementKind::Uint8Clamped, 300.0), 255.0);
+        assert_eq!(roundtrip(ElementKind::Uint8Clamped, -5.0), 0.0);
+        assert_eq!(roundtrip(ElementKind::Uint8Clamped, 1.5), 2.0);
+        assert_eq!(roundtrip(ElementKind::Uint8Clamped, 2.5), 2.0);
+        assert_eq!(roundtrip(ElementKind::Uint8Clamped, f64::NAN), 0.0);
+    }
+
+    #[test]
+    fn test_floats() {
+        assert_eq!(roundtrip(ElementKind::Float32, 0.1), 0.1f32 as f64);
+        assert_eq!(roundtrip(ElementKind::Float64, 0.1), 0.1);
+        assert!(roundtrip(ElementKind::Float64, f64::NAN).is_nan());
+    }
+
+    #[test]
+    fn test_byte_order() {
+        let mut bytes = [0u8; 4];
+        ElementKind::Uint32.write(&mut bytes, 0x01020304 as f64, false);
+        assert_eq!(bytes, [1, 2, 3, 4]);
+        assert_eq!(ElementKind::Uint16.read(&bytes, true), 0x0201 as f64);
+        assert_eq!(ElementKind::Uint16.read(&bytes, false), 0x0102 as f64);
+    }
+
+    #[test]
+    fn test_names() {
+        for kind in ElementKind::ALL {
+            assert_eq!(ElementKind::from_name(kind.name()), Some(kind));
+            assert_eq!(ElementKind::from_tag(kind as u8), Some(kind));
+        }
+        assert_eq!(ElementKind::from_name("Array"), None);
+        assert_eq!(ElementKind::Uint8Clamped.view_name(), "Uint8");
+    }
+}
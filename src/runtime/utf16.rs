@@ -0,0 +1,474 @@
+//! UTF-16 semantics for strings
+//!
+//! JS indexes strings by UTF-16 code unit: `"😀".length` is 2 and
+//! `"😀".charCodeAt(0)` is the high surrogate. The VM's [`JsString`] keeps its
+//! text as UTF-8 and its code units alongside once it is indexed, so each
+//! `charCodeAt` in a loop is O(1). UTF-8 can't hold a lone surrogate, so a
+//! string with one keeps its code units from the start: `"😀".charAt(0)` is a
+//! string of its own that joins back up with `"😀".charAt(1)`.
+//!
+//! The `&str` helpers serve the compiled runtime, whose strings are plain
+//! UTF-8. They
+//! have fast paths for ASCII, where code units and bytes coincide, and a
+//! piece of a string that cuts a surrogate pair in half has U+FFFD in place
+//! of the half.
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use std::sync::{Arc, OnceLock};
+
+/// A JS string value. Clones share the text and its code units.
+#[derive(Clone)]
+pub struct JsString(Arc<Inner>);
+
+struct Inner {
+    /// The text, with U+FFFD in place of each lone surrogate
+    text: String,
+    /// Code units and bytes coincide
+    ascii: bool,
+    /// The string has a lone surrogate, so `units` holds it and `text` doesn't
+    lossy: bool,
+    /// The code units of a string that is not ASCII, filled in the first time
+    /// it is indexed unless the string is lossy
+    units: OnceLock<Box<[u16]>>,
+}
+
+impl JsString {
+    pub fn new(text: String) -> Self {
+        let ascii = text.is_ascii();
+        JsString(Arc::new(Inner {
+            text,
+            ascii,
+            lossy: false,
+            units: OnceLock::new(),
+        }))
+    }
+
+    /// The string of `units`, which may have lone surrogates
+    pub fn from_units(units: &[u16]) -> Self {
+        let lossy = char::decode_utf16(units.iter().copied()).any(|c| c.is_err());
+        let text = String::from_utf16_lossy(units);
+        let ascii = text.is_ascii();
+        let cache = OnceLock::new();
+        if !ascii {
+            let _ = cache.set(units.into());
+        }
+        JsString(Arc::new(Inner {
+            text,
+            ascii,
+            lossy,
+            units: cache,
+        }))
+    }
+
+    /// The text, with U+FFFD in place of each lone surrogate
+    pub fn as_str(&self) -> &str {
+        &self.0.text
+    }
+
+    pub fn into_string(self) -> String {
+        match Arc::try_unwrap(self.0) {
+            Ok(inner) => inner.text,
+            Err(shared) => shared.text.clone(),
+        }
+    }
+
+    /// Whether the string has a lone surrogate, which [`JsString::as_str`]
+    /// shows as U+FFFD
+    pub fn has_lone_surrogates(&self) -> bool {
+        self.0.lossy
+    }
+
+    /// The code units, or `None` for ASCII, whose code units are its bytes
+    fn wide_units(&self) -> Option<&[u16]> {
+        if self.0.ascii {
+            return None;
+        }
+        Some(
+            self.0
+                .units
+                .get_or_init(|| self.0.text.encode_utf16().collect()),
+        )
+    }
+
+    /// The code units
+    pub fn units(&self) -> impl Iterator<Item = u16> + '_ {
+        let bytes = self.0.ascii.then(|| self.0.text.bytes().map(u16::from));
+        let units = self.wide_units().map(|units| units.iter().copied());
+        bytes
+            .into_iter()
+            .flatten()
+            .chain(units.into_iter().flatten())
+    }
+
+    /// The number of code units, JS's `length`
+    pub fn length(&self) -> usize {
+        match self.wide_units() {
+            Some(units) => units.len(),
+            None => self.0.text.len(),
+        }
+    }
+
+    /// The code unit at `index`
+    pub fn code_unit_at(&self, index: usize) -> Option<u16> {
+        match self.wide_units() {
+            Some(units) => units.get(index).copied(),
+            None => self.0.text.as_bytes().get(index).map(|&b| b as u16),
+        }
+    }
+
+    /// The code point starting at code unit `index`. Inside a surrogate pair
+    /// that is the low surrogate, as `codePointAt` returns it.
+    pub fn code_point_at(&self, index: usize) -> Option<u32> {
+        let first = self.code_unit_at(index)? as u32;
+        match (first, self.code_unit_at(index + 1).map(u32::from)) {
+            (0xD800..=0xDBFF, Some(low @ 0xDC00..=0xDFFF)) => {
+                Some(0x10000 + ((first - 0xD800) << 10) + (low - 0xDC00))
+            }
+            _ => Some(first),
+        }
+    }
+
+    /// The code units `start..end`, clamped to the string
+    pub fn substring(&self, start: usize, end: usize) -> JsString {
+        let end = end.min(self.length());
+        if end <= start {
+            return JsString::default();
+        }
+        match self.wide_units() {
+            Some(units) => JsString::from_units(&units[start..end]),
+            None => JsString::new(self.0.text[start..end].to_string()),
+        }
+    }
+
+    /// The two strings joined, pairing up surrogates that meet at the seam
+    pub fn concat(&self, other: &JsString) -> JsString {
+        if !self.0.lossy && !other.0.lossy {
+            return JsString::new(format!("{}{}", self.0.text, other.0.text));
+        }
+        let units: Vec<u16> = self.units().chain(other.units()).collect();
+        JsString::from_units(&units)
+    }
+
+    /// `parts` joined by `separator`, pairing up surrogates as [`concat`] does
+    ///
+    /// [`concat`]: JsString::concat
+    pub fn join(parts: &[JsString], separator: &JsString) -> JsString {
+        if parts.iter().chain([separator]).all(|part| !part.0.lossy) {
+            let texts: Vec<&str> = parts.iter().map(JsString::as_str).collect();
+            return JsString::new(texts.join(separator.as_str()));
+        }
+        let mut units = Vec::new();
+        for (index, part) in parts.iter().enumerate() {
+            if index > 0 {
+                units.extend(separator.units());
+            }
+            units.extend(part.units());
+        }
+        JsString::from_units(&units)
+    }
+
+    /// The first index of `search` at or after code unit `from`
+    pub fn index_of(&self, search: &JsString, from: usize) -> Option<usize> {
+        let from = from.min(self.length());
+        if self.0.ascii && search.0.ascii {
+            let text = &self.0.text;
+            return text
+                .get(from..)
+                .and_then(|rest| rest.find(search.as_str()))
+                .map(|found| from + found);
+        }
+        let haystack: Vec<u16> = self.units().collect();
+        let needle: Vec<u16> = search.units().collect();
+        (from..=haystack.len().checked_sub(needle.len())?)
+            .find(|&at| haystack[at..at + needle.len()] == needle[..])
+    }
+
+    /// The last index of `search` at or before code unit `from`
+    pub fn last_index_of(&self, search: &JsString, from: usize) -> Option<usize> {
+        let haystack: Vec<u16> = self.units().collect();
+        let needle: Vec<u16> = search.units().collect();
+        let last = haystack.len().checked_sub(needle.len())?.min(from);
+        (0..=last)
+            .rev()
+            .find(|&at| haystack[at..at + needle.len()] == needle[..])
+    }
+
+    /// Each code unit as a string of its own, as `split("")` returns them
+    pub fn unit_strings(&self) -> Vec<JsString> {
+        (0..self.length())
+            .map(|index| self.substring(index, index + 1))
+            .collect()
+    }
+}
+
+impl Default for JsString {
+    fn default() -> Self {
+        JsString::new(String::new())
+    }
+}
+
+impl Deref for JsString {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0.text
+    }
+}
+
+impl AsRef<str> for JsString {
+    fn as_ref(&self) -> &str {
+        &self.0.text
+    }
+}
+
+impl AsRef<[u8]> for JsString {
+    fn as_ref(&self) -> &[u8] {
+        self.0.text.as_bytes()
+    }
+}
+
+impl AsRef<std::ffi::OsStr> for JsString {
+    fn as_ref(&self) -> &std::ffi::OsStr {
+        self.0.text.as_ref()
+    }
+}
+
+impl AsRef<std::path::Path> for JsString {
+    fn as_ref(&self) -> &std::path::Path {
+        self.0.text.as_ref()
+    }
+}
+
+impl From<String> for JsString {
+    fn from(text: String) -> Self {
+        JsString::new(text)
+    }
+}
+
+impl From<&str> for JsString {
+    fn from(text: &str) -> Self {
+        JsString::new(text.to_string())
+    }
+}
+
+impl From<&String> for JsString {
+    fn from(text: &String) -> Self {
+        JsString::new(text.clone())
+    }
+}
+
+impl PartialEq for JsString {
+    fn eq(&self, other: &Self) -> bool {
+        if Arc::ptr_eq(&self.0, &other.0) {
+            return true;
+        }
+        match (self.0.lossy, other.0.lossy) {
+            (false, false) => self.0.text == other.0.text,
+            (true, true) => self.wide_units() == other.wide_units(),
+            _ => false,
+        }
+    }
+}
+
+impl Eq for JsString {}
+
+impl PartialEq<str> for JsString {
+    fn eq(&self, other: &str) -> bool {
+        !self.0.lossy && self.0.text == other
+    }
+}
+
+impl PartialEq<&str> for JsString {
+    fn eq(&self, other: &&str) -> bool {
+        *self == **other
+    }
+}
+
+impl PartialEq<String> for JsString {
+    fn eq(&self, other: &String) -> bool {
+        *self == **other
+    }
+}
+
+impl Hash for JsString {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // Equal strings are either both lossy or both not
+        match self.wide_units() {
+            Some(units) if self.0.lossy => units.hash(state),
+            _ => self.0.text.hash(state),
+        }
+    }
+}
+
+impl PartialOrd for JsString {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Code unit order, as JS's relational operators compare strings
+impl Ord for JsString {
+    fn cmp(&self, other: &Self) -> Ordering {
+        if !self.0.lossy && !other.0.lossy {
+            return compare(&self.0.text, &other.0.text);
+        }
+        self.units().cmp(other.units())
+    }
+}
+
+impl fmt::Display for JsString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0.text, f)
+    }
+}
+
+impl fmt::Debug for JsString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.0.lossy {
+            return fmt::Debug::fmt(&self.0.text, f);
+        }
+        // Show the lone surrogates that the text has U+FFFD for
+        f.write_str("\"")?;
+        for c in char::decode_utf16(self.units()) {
+            match c {
+                Ok(c) => write!(f, "{}", c.escape_debug())?,
+                Err(lone) => write!(f, "\\u{{{:x}}}", lone.unpaired_surrogate())?,
+            }
+        }
+        f.write_str("\"")
+    }
+}
+
+/// Whether the code units before `end` are the bytes before it
+fn ascii_prefix(s: &str, end: usize) -> bool {
+    s.as_bytes()[..end.min(s.len())].is_ascii()
+}
+
+/// The number of UTF-16 code units in `s`
+pub fn len(s: &str) -> usize {
+    if s.is_ascii() {
+        s.len()
+    } else {
+        s.encode_utf16().count()
+    }
+}
+
+/// The code units `start..end`, clamped to the string
+pub fn slice(s: &str, start: usize, end: usize) -> String {
+    if end <= start {
+        return String::new();
+    }
+    if ascii_prefix(s, end) {
+        let end = end.min(s.len());
+        return s[start.min(end)..end].to_string();
+    }
+    let mut result = String::new();
+    let mut unit = 0;
+    for c in s.chars() {
+        if unit >= end {
+            break;
+        }
+        let next = unit + c.len_utf16();
+        if unit >= start && next <= end {
+            result.push(c);
+        } else if next > start {
+            // One half of a surrogate pair
+            result.push(char::REPLACEMENT_CHARACTER);
+        }
+        unit = next;
+    }
+    result
+}
+
+/// Compare strings by code units, as JS's relational operators do
+pub fn compare(a: &str, b: &str) -> Ordering {
+    // UTF-8 byte order differs from code unit order only past U+FFFF
+    if a.is_ascii() || b.is_ascii() {
+        a.cmp(b)
+    } else {
+        a.encode_utf16().cmp(b.encode_utf16())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn js(s: &str) -> JsString {
+        JsString::from(s)
+    }
+
+    #[test]
+    fn test_length_counts_code_units() {
+        assert_eq!(len("abc"), 3);
+        assert_eq!(len("héllo"), 5);
+        assert_eq!(len("a😀b"), 4);
+        assert_eq!(js("a😀b").length(), 4);
+        assert_eq!(js("é😀").code_unit_at(1), Some(0xD83D));
+        assert_eq!(js("é😀").code_unit_at(2), Some(0xDE00));
+        assert_eq!(js("é😀").code_unit_at(3), None);
+        assert_eq!(js("é😀").code_point_at(1), Some(0x1F600));
+        assert_eq!(js("é😀").code_point_at(2), Some(0xDE00));
+        // The byte at a unit index is not the unit once a wide char precedes it
+        assert_eq!(js("éab").code_unit_at(2), Some('b' as u16));
+    }
+
+    #[test]
+    fn test_slice_replaces_split_pairs() {
+        assert_eq!(slice("hello", 1, 3), "el");
+        assert_eq!(slice("hello", 3, 99), "lo");
+        assert_eq!(slice("a😀b", 0, 3), "a😀");
+        assert_eq!(slice("a😀b", 0, 2), "a\u{FFFD}");
+        assert_eq!(slice("a😀b", 2, 4), "\u{FFFD}b");
+        assert_eq!(slice("a😀b", 2, 2), "");
+    }
+
+    #[test]
+    fn test_substring_keeps_lone_surrogates() {
+        let emoji = js("😀");
+        let (high, low) = (emoji.substring(0, 1), emoji.substring(1, 2));
+        assert!(high.has_lone_surrogates());
+        assert_ne!(high, low);
+        assert_ne!(high, js("\u{FFFD}"));
+        assert_eq!(high.code_point_at(0), Some(0xD83D));
+        assert_eq!(high.concat(&low), emoji);
+        assert_eq!(high.concat(&low), "😀");
+        assert_eq!(js("a😀b").substring(0, 3), "a😀");
+        assert_eq!(js("hello").substring(3, 99), "lo");
+        assert_eq!(js("a😀b").substring(2, 2), "");
+        let halves = js("a😀").unit_strings();
+        assert_eq!(halves.len(), 3);
+        assert_eq!(halves[1], high);
+    }
+
+    #[test]
+    fn test_search_returns_code_unit_indices() {
+        let s = js("😀ab😀ab");
+        assert_eq!(s.index_of(&js("ab"), 0), Some(2));
+        assert_eq!(s.index_of(&js("ab"), 3), Some(6));
+        assert_eq!(js("😀ab").index_of(&js("ab"), 1), Some(2));
+        assert_eq!(js("😀ab").index_of(&js("x"), 0), None);
+        assert_eq!(js("abc").index_of(&js(""), 100), Some(3));
+        assert_eq!(s.last_index_of(&js("ab"), 100), Some(6));
+        assert_eq!(s.last_index_of(&js("ab"), 5), Some(2));
+        assert_eq!(js("aaa").last_index_of(&js("aa"), 0), Some(0));
+        assert_eq!(js("abc").last_index_of(&js(""), 100), Some(3));
+        assert_eq!(js("abc").last_index_of(&js("x"), 100), None);
+        // A lone half is found inside a pair
+        assert_eq!(s.index_of(&s.substring(1, 2), 0), Some(1));
+    }
+
+    #[test]
+    fn test_compare_orders_by_code_unit() {
+        // U+FF61 sorts after U+1F600 by code unit, before it by code point
+        assert_eq!(compare("\u{FF61}", "😀"), Ordering::Greater);
+        assert_eq!(compare("a", "b"), Ordering::Less);
+        assert!(js("\u{FF61}") > js("😀"));
+        assert!(js("😀").substring(0, 1) < js("\u{FF61}"));
+        assert_eq!(JsString::from_units(&[0xD83D, 0xDE00, 0x61]), "😀a");
+        assert_eq!(JsString::from_units(&[0xD83D]).as_str(), "\u{FFFD}");
+    }
+}
@@ -0,0 +1,75 @@
+//! The `Array` constructor
+//!
+//! Array literals and methods are built into the VM; this only covers
+//! `new Array(length)`, `new Array(a, b, ...)` and the same calls without
+//! `new`.
+
+use crate::vm::VM;
+use crate::vm::value::{HeapData, HeapObject, JsValue};
+
+use super::typed_array::Thrown;
+
+/// Largest array length, 2^32 - 1
+const MAX_LENGTH: f64 = 4294967295.0;
+
+/// `new Array(...)`: a single number is the length of an array of
+/// undefined, any other arguments are the elements
+pub fn construct(vm: &mut VM, args: &[JsValue]) -> Result<JsValue, Thrown> {
+    let elements = match args {
+        [JsValue::Number(n)] => {
+            if n.fract() != 0.0 || !(0.0..=MAX_LENGTH).contains(n) {
+                return Err(("RangeError", "Invalid array length".to_string()));
+            }
+            vec![JsValue::Undefined; *n as usize]
+        }
+        _ => args.to_vec(),
+    };
+    let ptr = vm.heap.len();
+    vm.heap.push(HeapObject {
+        data: HeapData::Array(elements),
+    });
+    Ok(JsValue::Object(ptr))
+}
+
+/// `Array(...)` called without `new`, which constructs all the same
+pub fn native_array_call(vm: &mut VM, args: Vec<JsValue>) -> JsValue {
+    match construct(vm, &args) {
+        Ok(array) => array,
+        Err((name, message)) => vm.throw_from_native(name, message),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_construct_length_and_elements() {
+        let mut vm = VM::new();
+        let JsValue::Object(ptr) = construct(&mut vm, &[JsValue::Number(3.0)]).unwrap() else {
+            panic!("expected an array");
+        };
+        assert!(matches!(
+            &vm.heap[ptr].data,
+            HeapData::Array(elements) if elements.len() == 3
+        ));
+
+        let args = [JsValue::Number(1.0), JsValue::Number(2.0)];
+        let JsValue::Object(ptr) = construct(&mut vm, &args).unwrap() else {
+            panic!("expected an array");
+        };
+        assert!(matches!(
+            &vm.heap[ptr].data,
+            HeapData::Array(elements) if elements.len() == 2
+        ));
+    }
+
+    #[test]
+    fn test_construct_rejects_invalid_lengths() {
+        let mut vm = VM::new();
+        for length in [-1.0, 1.5, f64::NAN, MAX_LENGTH + 1.0] {
+            let (name, _) = construct(&mut vm, &[JsValue::Number(length)]).unwrap_err();
+            assert_eq!(name, "RangeError");
+        }
+    }
+}
@@ -0,0 +1,421 @@
+//! `Map`, `Set`, `WeakMap` and `WeakSet`
+//!
+//! Maps and sets are [`OrderedMap`]s, hashed by SameValueZero. `keys`,
+//! `values` and `entries` return iterators with a `next` method. Like
+//! `forEach`, an iterator visits the keys present when it was created,
+//! skipping any deleted since and reading each value when it gets there.
+//! The VM has no general iterator protocol, so `for...of`, spread and the
+//! constructors read iterables through [`iterable_values`]. `forEach` calls
+//! back into script, so the VM runs it.
+//!
+//! Weak collections are a reduced form: they key their entries by heap
+//! index rather than by value, so they never hold their keys the way a
+//! `Map` does, and a collector could drop an entry whose key is unreachable
+//! without tracing through it. The heap has no collector, so every entry
+//! lasts as long as its collection.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::vm::VM;
+use crate::vm::ordered_map::OrderedMap;
+use crate::vm::value::{HeapData, HeapObject, IterationKind, JsValue};
+
+use super::inspect_value;
+use super::typed_array::{self, Thrown};
+
+/// Whether `__type__` names a constructor of this module
+pub fn is_constructor(name: &str) -> bool {
+    matches!(name, "Map" | "Set" | "WeakMap" | "WeakSet")
+}
+
+/// `new Map(iterable)` and the other collections, by constructor name
+pub fn construct(vm: &mut VM, name: &str, args: &[JsValue]) -> Result<JsValue, Thrown> {
+    let items = match args.first() {
+        None | Some(JsValue::Undefined | JsValue::Null) => Vec::new(),
+        Some(iterable) => {
+            iterable_values(vm, iterable).ok_or_else(|| not_iterable(vm, iterable))?
+        }
+    };
+    let data = match name {
+        "Map" => {
+            let mut map = OrderedMap::new();
+            for item in &items {
+                let (key, value) = entry(vm, item)?;
+                map.insert(key, value);
+            }
+            HeapData::Map(map)
+        }
+        "Set" => {
+            let mut set = OrderedMap::new();
+            for item in items {
+                set.insert(item, ());
+            }
+            HeapData::Set(set)
+        }
+        "WeakMap" => {
+            let mut map = HashMap::new();
+            for item in &items {
+                let (key, value) = entry(vm, item)?;
+                map.insert(weak_map_key(&key)?, value);
+            }
+            HeapData::WeakMap(map)
+        }
+        "WeakSet" => {
+            let mut set = HashSet::new();
+            for item in &items {
+                set.insert(weak_set_value(item)?);
+            }
+            HeapData::WeakSet(set)
+        }
+        _ => return Ok(JsValue::Undefined),
+    };
+    Ok(JsValue::Object(alloc(vm, data)))
+}
+
+/// The values `for...of` visits in `value`: the elements of an array or
+/// typed array, the code points of a string, the values of a set, the
+/// `[key, value]` entries of a map or what is left of a collection
+/// iterator, which this uses up. `None` when `value` is not iterable.
+pub fn iterable_values(vm: &mut VM, value: &JsValue) -> Option<Vec<JsValue>> {
+    let ptr = match value {
+        JsValue::String(s) => {
+            return Some(
+                s.chars()
+                    .map(|c| JsValue::String(c.to_string().into()))
+                    .collect(),
+            );
+        }
+        JsValue::Object(ptr) => *ptr,
+        _ => return None,
+    };
+    match &vm.heap.get(ptr)?.data {
+        HeapData::Array(items) => Some(items.clone()),
+        HeapData::Set(set) => Some(set.keys().cloned().collect()),
+        HeapData::Map(_) => {
+            let pairs = entries(vm, ptr);
+            Some(pairs.into_iter().map(|pair| pair_array(vm, pair)).collect())
+        }
+        HeapData::CollectionIterator { .. } => {
+            let mut values = Vec::new();
+            while let Some(value) = next_value(vm, ptr) {
+                values.push(value);
+            }
+            Some(values)
+        }
+        data @ HeapData::TypedArray { .. } => Some(
+            typed_array::elements(vm, data)
+                .into_iter()
+                .map(JsValue::Number)
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+/// The `[key, value]` entries of the map or set at `ptr`, in insertion
+/// order; a set's entries pair each value with itself
+pub fn entries(vm: &VM, ptr: usize) -> Vec<(JsValue, JsValue)> {
+    match vm.heap.get(ptr).map(|obj| &obj.data) {
+        Some(HeapData::Map(map)) => map.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
+        Some(HeapData::Set(set)) => set.keys().map(|v| (v.clone(), v.clone())).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// The current value of `key` in the map or set at `ptr`, or `None` once
+/// it has been deleted
+pub fn lookup(vm: &VM, ptr: usize, key: &JsValue) -> Option<JsValue> {
+    match vm.heap.get(ptr).map(|obj| &obj.data) {
+        Some(HeapData::Map(map)) => map.get(key).cloned(),
+        Some(HeapData::Set(set)) => set.contains_key(key).then(|| key.clone()),
+        _ => None,
+    }
+}
+
+/// A new iterator over the map or set at `ptr`
+fn iterator(vm: &mut VM, ptr: usize, kind: IterationKind) -> JsValue {
+    let keys = entries(vm, ptr).into_iter().map(|(key, _)| key).collect();
+    let iterator = HeapData::CollectionIterator {
+        collection: ptr,
+        kind,
+        keys,
+        position: 0,
+    };
+    JsValue::Object(alloc(vm, iterator))
+}
+
+/// Advance the collection iterator at `ptr`, returning what it yields for
+/// the next entry still in its collection, or `None` once it is done
+fn next_value(vm: &mut VM, ptr: usize) -> Option<JsValue> {
+    loop {
+        let Some(HeapData::CollectionIterator {
+            collection,
+            kind,
+            keys,
+            position,
+        }) = vm.heap.get_mut(ptr).map(|obj| &mut obj.data)
+        else {
+            return None;
+        };
+        let key = keys.get(*position)?.clone();
+        *position += 1;
+        let (collection, kind) = (*collection, *kind);
+        let Some(value) = lookup(vm, collection, &key) else {
+            continue;
+        };
+        return Some(match kind {
+            IterationKind::Keys => key,
+            IterationKind::Values => value,
+            IterationKind::Entries => pair_array(vm, (key, value)),
+        });
+    }
+}
+
+/// `{ value, done }`, as `next` returns
+fn iterator_result(vm: &mut VM, value: Option<JsValue>) -> JsValue {
+    let mut props = HashMap::new();
+    props.insert("done".to_string(), JsValue::Boolean(value.is_none()));
+    props.insert("value".to_string(), value.unwrap_or(JsValue::Undefined));
+    JsValue::Object(alloc(vm, HeapData::Object(props)))
+}
+
+/// Call a method on the collection or collection iterator at `ptr`, other
+/// than `forEach`; unknown methods return undefined
+pub fn call_method(
+    vm: &mut VM,
+    ptr: usize,
+    name: &str,
+    args: &[JsValue],
+) -> Result<JsValue, Thrown> {
+    let arg = |i: usize| args.get(i).cloned().unwrap_or(JsValue::Undefined);
+    let this = JsValue::Object(ptr);
+    let Some(data) = vm.heap.get_mut(ptr).map(|obj| &mut obj.data) else {
+        return Ok(JsValue::Undefined);
+    };
+    let kind = match (data, name) {
+        (HeapData::Map(map), "get") => {
+            return Ok(map.get(&arg(0)).cloned().unwrap_or(JsValue::Undefined));
+        }
+        (HeapData::Map(map), "set") => {
+            map.insert(arg(0), arg(1));
+            return Ok(this);
+        }
+        (HeapData::Map(map), "has") => return Ok(JsValue::Boolean(map.contains_key(&arg(0)))),
+        (HeapData::Map(map), "delete") => return Ok(JsValue::Boolean(map.remove(&arg(0)))),
+        (HeapData::Map(map), "clear") => {
+            map.clear();
+            return Ok(JsValue::Undefined);
+        }
+        (HeapData::Map(_), "keys") => IterationKind::Keys,
+        (HeapData::Map(_), "values") => IterationKind::Values,
+        (HeapData::Set(set), "add") => {
+            set.insert(arg(0), ());
+            return Ok(this);
+        }
+        (HeapData::Set(set), "has") => return Ok(JsValue::Boolean(set.contains_key(&arg(0)))),
+        (HeapData::Set(set), "delete") => return Ok(JsValue::Boolean(set.remove(&arg(0)))),
+        (HeapData::Set(set), "clear") => {
+            set.clear();
+            return Ok(JsValue::Undefined);
+        }
+        (HeapData::Set(_), "keys" | "values") => IterationKind::Values,
+        (HeapData::Map(_) | HeapData::Set(_), "entries") => IterationKind::Entries,
+        (HeapData::WeakMap(map), "get") => {
+            let value = weak_key(&arg(0)).and_then(|key| map.get(&key));
+            return Ok(value.cloned().unwrap_or(JsValue::Undefined));
+        }
+        (HeapData::WeakMap(map), "set") => {
+            map.insert(weak_map_key(&arg(0))?, arg(1));
+            return Ok(this);
+        }
+        (HeapData::WeakMap(map), "has") => {
+            let found = weak_key(&arg(0)).is_some_and(|key| map.contains_key(&key));
+            return Ok(JsValue::Boolean(found));
+        }
+        (HeapData::WeakMap(map), "delete") => {
+            let found = weak_key(&arg(0)).is_some_and(|key| map.remove(&key).is_some());
+            return Ok(JsValue::Boolean(found));
+        }
+        (HeapData::WeakSet(set), "add") => {
+            set.insert(weak_set_value(&arg(0))?);
+            return Ok(this);
+        }
+        (HeapData::WeakSet(set), "has") => {
+            let found = weak_key(&arg(0)).is_some_and(|key| set.contains(&key));
+            return Ok(JsValue::Boolean(found));
+        }
+        (HeapData::WeakSet(set), "delete") => {
+            let found = weak_key(&arg(0)).is_some_and(|key| set.remove(&key));
+            return Ok(JsValue::Boolean(found));
+        }
+        (HeapData::CollectionIterator { .. }, "next") => {
+            let value = next_value(vm, ptr);
+            return Ok(iterator_result(vm, value));
+        }
+        _ => return Ok(JsValue::Undefined),
+    };
+    Ok(iterator(vm, ptr, kind))
+}
+
+/// `console.log` of a collection or collection iterator, like Node's
+pub fn inspect(vm: &VM, data: &HeapData, show: impl Fn(&JsValue) -> String) -> String {
+    match data {
+        HeapData::Map(map) if map.is_empty() => "Map(0) {}".to_string(),
+        HeapData::Map(map) => {
+            let items: Vec<String> = map
+                .iter()
+                .map(|(k, v)| format!("{} => {}", show(k), show(v)))
+                .collect();
+            format!("Map({}) {{ {} }}", map.len(), items.join(", "))
+        }
+        HeapData::Set(set) if set.is_empty() => "Set(0) {}".to_string(),
+        HeapData::Set(set) => {
+            let items: Vec<String> = set.keys().map(show).collect();
+            format!("Set({}) {{ {} }}", set.len(), items.join(", "))
+        }
+        // Which keys are still reachable is up to the collector, so Node
+        // doesn't list them either
+        HeapData::WeakMap(_) => "WeakMap { <items unknown> }".to_string(),
+        HeapData::WeakSet(_) => "WeakSet { <items unknown> }".to_string(),
+        HeapData::CollectionIterator {
+            collection,
+            kind,
+            keys,
+            position,
+        } => {
+            let items: Vec<String> = keys[*position..]
+                .iter()
+                .filter_map(|key| Some((key, lookup(vm, *collection, key)?)))
+                .map(|(key, value)| match kind {
+                    IterationKind::Keys => show(key),
+                    IterationKind::Values => show(&value),
+                    IterationKind::Entries => format!("[ {}, {} ]", show(key), show(&value)),
+                })
+                .collect();
+            let name = match vm.heap.get(*collection).map(|obj| &obj.data) {
+                Some(HeapData::Map(_)) => "Map",
+                _ => "Set",
+            };
+            if items.is_empty() {
+                format!("[{} Iterator] {{  }}", name)
+            } else {
+                format!("[{} Iterator] {{ {} }}", name, items.join(", "))
+            }
+        }
+        _ => String::new(),
+    }
+}
+
+fn alloc(vm: &mut VM, data: HeapData) -> usize {
+    let ptr = vm.heap.len();
+    vm.heap.push(HeapObject { data });
+    ptr
+}
+
+fn pair_array(vm: &mut VM, (key, value): (JsValue, JsValue)) -> JsValue {
+    JsValue::Object(alloc(vm, HeapData::Array(vec![key, value])))
+}
+
+/// The key and value of an entry object, as the `Map` constructor reads it
+pub fn entry(vm: &VM, item: &JsValue) -> Result<(JsValue, JsValue), Thrown> {
+    match item {
+        JsValue::Object(ptr) => match vm.heap.get(*ptr).map(|obj| &obj.data) {
+            Some(HeapData::Array(pair)) => Ok((
+                pair.first().cloned().unwrap_or(JsValue::Undefined),
+                pair.get(1).cloned().unwrap_or(JsValue::Undefined),
+            )),
+            _ => Ok((
+                vm.get_prop_with_proto_chain(*ptr, "0"),
+                vm.get_prop_with_proto_chain(*ptr, "1"),
+            )),
+        },
+        _ => Err((
+            "TypeError",
+            format!(
+                "Iterator value {} is not an entry object",
+                inspect_value(vm, item)
+            ),
+        )),
+    }
+}
+
+/// The heap index a weak collection keys `value` by; only objects can be
+/// held weakly
+fn weak_key(value: &JsValue) -> Option<usize> {
+    match value {
+        JsValue::Object(ptr) => Some(*ptr),
+        _ => None,
+    }
+}
+
+fn weak_map_key(value: &JsValue) -> Result<usize, Thrown> {
+    weak_key(value).ok_or((
+        "TypeError",
+        "Invalid value used as weak map key".to_string(),
+    ))
+}
+
+fn weak_set_value(value: &JsValue) -> Result<usize, Thrown> {
+    weak_key(value).ok_or(("TypeError", "Invalid value used in weak set".to_string()))
+}
+
+/// The TypeError for iterating a value that isn't iterable
+pub fn not_iterable(vm: &VM, value: &JsValue) -> Thrown {
+    let shown = match value {
+        JsValue::Object(_) => "object".to_string(),
+        _ => inspect_value(vm, value),
+    };
+    ("TypeError", format!("{} is not iterable", shown))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map_of(vm: &mut VM, pairs: &[(f64, &str)]) -> JsValue {
+        let entries: Vec<JsValue> = pairs
+            .iter()
+            .map(|(k, v)| {
+                let pair = (JsValue::Number(*k), JsValue::String(v.to_string().into()));
+                pair_array(vm, pair)
+            })
+            .collect();
+        let iterable = JsValue::Object(alloc(vm, HeapData::Array(entries)));
+        construct(vm, "Map", &[iterable]).unwrap()
+    }
+
+    #[test]
+    fn test_map_from_entries_keeps_last_value_in_first_place() {
+        let mut vm = VM::new();
+        let map = map_of(&mut vm, &[(1.0, "a"), (2.0, "b"), (1.0, "c")]);
+        let JsValue::Object(ptr) = map else {
+            panic!("expected a map")
+        };
+        let pairs = entries(&vm, ptr);
+        assert_eq!(
+            pairs,
+            vec![
+                (JsValue::Number(1.0), JsValue::String("c".into())),
+                (JsValue::Number(2.0), JsValue::String("b".into())),
+            ]
+        );
+        let got = call_method(&mut vm, ptr, "get", &[JsValue::Number(2.0)]).unwrap();
+        assert_eq!(got, JsValue::String("b".into()));
+    }
+
+    #[test]
+    fn test_weak_collections_reject_primitives() {
+        let mut vm = VM::new();
+        let weak_map = construct(&mut vm, "WeakMap", &[]).unwrap();
+        let JsValue::Object(ptr) = weak_map else {
+            panic!("expected a weak map")
+        };
+        let key = JsValue::Number(1.0);
+        let err = call_method(&mut vm, ptr, "set", &[key.clone(), key.clone()]).unwrap_err();
+        assert_eq!(err.1, "Invalid value used as weak map key");
+        let has = call_method(&mut vm, ptr, "has", &[key]).unwrap();
+        assert_eq!(has, JsValue::Boolean(false));
+        let err = construct(&mut vm, "WeakSet", &[JsValue::Number(1.0)]).unwrap_err();
+        assert_eq!(err.1, "1 is not iterable");
+    }
+}
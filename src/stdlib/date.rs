@@ -0,0 +1,774 @@
+//! `Date` and `performance` for the VM
+//!
+//! A date is a `HeapData::Date` holding its time value: milliseconds since
+//! the Unix epoch, or NaN for an invalid date. Calendar arithmetic follows
+//! the ES spec (proleptic Gregorian, no leap seconds); local time comes from
+//! the system time zone via `localtime_r`.
+
+use crate::runtime::time;
+use crate::vm::VM;
+use crate::vm::value::{HeapData, HeapObject, JsValue};
+
+use super::to_number;
+
+const MS_PER_DAY: f64 = 86_400_000.0;
+/// Largest time value, 100 million days either side of the epoch
+const MAX_TIME: f64 = 8.64e15;
+
+const WEEKDAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// Year, month (0-11), day of month (1-31), hours, minutes, seconds and
+/// milliseconds, in the order the `set*` methods take them
+type Fields = [f64; 7];
+
+const YEAR: usize = 0;
+const MONTH: usize = 1;
+const DATE: usize = 2;
+const HOURS: usize = 3;
+const MINUTES: usize = 4;
+const SECONDS: usize = 5;
+const MILLIS: usize = 6;
+
+// ============================================================================
+// Calendar
+// ============================================================================
+
+/// Days since 1970-01-01 of a proleptic Gregorian date (month 1-12)
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Year, month (1-12) and day of `days_from_civil`
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    days_from_civil(year + month / 12, month % 12 + 1, 1) - days_from_civil(year, month, 1)
+}
+
+/// ES MakeDay: months outside 0-11 carry into the year
+fn make_day(year: f64, month: f64, date: f64) -> f64 {
+    if !(year.is_finite() && month.is_finite() && date.is_finite()) {
+        return f64::NAN;
+    }
+    let (month, date) = (month.trunc(), date.trunc());
+    let year = year.trunc() + (month / 12.0).floor();
+    // Far outside the time value range; also keeps the i64 math exact
+    if year.abs() > 400_000.0 {
+        return f64::NAN;
+    }
+    days_from_civil(year as i64, month.rem_euclid(12.0) as i64 + 1, 1) as f64 + date - 1.0
+}
+
+/// ES MakeTime
+fn make_time(hours: f64, minutes: f64, seconds: f64, millis: f64) -> f64 {
+    if !(hours.is_finite() && minutes.is_finite() && seconds.is_finite() && millis.is_finite()) {
+        return f64::NAN;
+    }
+    hours.trunc() * 3_600_000.0
+        + minutes.trunc() * 60_000.0
+        + seconds.trunc() * 1000.0
+        + millis.trunc()
+}
+
+/// ES TimeClip: NaN outside ±8.64e15 ms, otherwise an integer
+pub fn time_clip(t: f64) -> f64 {
+    if !t.is_finite() || t.abs() > MAX_TIME {
+        f64::NAN
+    } else {
+        t.trunc() + 0.0
+    }
+}
+
+/// Time value of calendar fields, before TimeClip
+fn fields_to_time(fields: &Fields) -> f64 {
+    let day = make_day(fields[YEAR], fields[MONTH], fields[DATE]);
+    let time = make_time(
+        fields[HOURS],
+        fields[MINUTES],
+        fields[SECONDS],
+        fields[MILLIS],
+    );
+    day * MS_PER_DAY + time
+}
+
+/// Calendar fields of a finite time value
+fn time_to_fields(t: f64) -> Fields {
+    let day = (t / MS_PER_DAY).floor();
+    let ms_in_day = t - day * MS_PER_DAY;
+    let (year, month, date) = civil_from_days(day as i64);
+    [
+        year as f64,
+        (month - 1) as f64,
+        date as f64,
+        (ms_in_day / 3_600_000.0).floor(),
+        (ms_in_day / 60_000.0).floor() % 60.0,
+        (ms_in_day / 1000.0).floor() % 60.0,
+        ms_in_day % 1000.0,
+    ]
+}
+
+/// Day of the week, 0 for Sunday, of a finite time value
+fn week_day(t: f64) -> usize {
+    ((t / MS_PER_DAY).floor() + 4.0).rem_euclid(7.0) as usize
+}
+
+// ============================================================================
+// Local Time
+// ============================================================================
+
+/// Offset of local time from UTC in milliseconds at the instant `t`
+fn local_offset(t: f64) -> f64 {
+    if !t.is_finite() {
+        return 0.0;
+    }
+    let secs = (t / 1000.0).floor() as libc::time_t;
+    // SAFETY: localtime_r only writes the tm we pass it
+    unsafe {
+        let mut tm: libc::tm = std::mem::zeroed();
+        if libc::localtime_r(&secs, &mut tm).is_null() {
+            return 0.0;
+        }
+        tm.tm_gmtoff as f64 * 1000.0
+    }
+}
+
+/// ES LocalTime
+fn local_time(t: f64) -> f64 {
+    t + local_offset(t)
+}
+
+/// ES UTC: the instant a local time names. In an hour that a transition
+/// repeats or skips, the offset from before the transition applies.
+fn utc(local: f64) -> f64 {
+    let before = local_offset(local - MS_PER_DAY);
+    let after = local_offset(local + MS_PER_DAY);
+    let names_local = |offset: f64| local_offset(local - offset) == offset;
+    if before == after || names_local(before) || !names_local(after) {
+        local - before
+    } else {
+        local - after
+    }
+}
+
+// ============================================================================
+// Formatting
+// ============================================================================
+
+fn format_year(year: f64) -> String {
+    if year < 0.0 {
+        format!("-{:04}", -year)
+    } else {
+        format!("{:04}", year)
+    }
+}
+
+/// `toISOString`: `YYYY-MM-DDTHH:mm:ss.sssZ`, with a signed six-digit year
+/// outside 0-9999; `None` for an invalid date
+pub fn to_iso_string(t: f64) -> Option<String> {
+    if t.is_nan() {
+        return None;
+    }
+    let f = time_to_fields(t);
+    let year = if (0.0..=9999.0).contains(&f[YEAR]) {
+        format!("{:04}", f[YEAR])
+    } else if f[YEAR] < 0.0 {
+        format!("-{:06}", -f[YEAR])
+    } else {
+        format!("+{:06}", f[YEAR])
+    };
+    Some(format!(
+        "{}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        f[MONTH] + 1.0,
+        f[DATE],
+        f[HOURS],
+        f[MINUTES],
+        f[SECONDS],
+        f[MILLIS]
+    ))
+}
+
+fn date_string(f: &Fields, week_day: usize) -> String {
+    format!(
+        "{} {} {:02} {}",
+        WEEKDAYS[week_day],
+        MONTHS[f[MONTH] as usize],
+        f[DATE],
+        format_year(f[YEAR])
+    )
+}
+
+fn time_string(f: &Fields, offset: f64) -> String {
+    let minutes = (offset / 60_000.0).round();
+    let sign = if minutes < 0.0 { '-' } else { '+' };
+    format!(
+        "{:02}:{:02}:{:02} GMT{}{:02}{:02}",
+        f[HOURS],
+        f[MINUTES],
+        f[SECONDS],
+        sign,
+        (minutes.abs() / 60.0).floor(),
+        minutes.abs() % 60.0
+    )
+}
+
+/// `toString`: `Tue Mar 05 2024 10:00:00 GMT+0100` in local time
+pub fn to_string(t: f64) -> String {
+    if t.is_nan() {
+        return "Invalid Date".to_string();
+    }
+    let offset = local_offset(t);
+    let local = t + offset;
+    let f = time_to_fields(local);
+    format!(
+        "{} {}",
+        date_string(&f, week_day(local)),
+        time_string(&f, offset)
+    )
+}
+
+/// `toUTCString`: `Tue, 05 Mar 2024 09:00:00 GMT`
+pub fn to_utc_string(t: f64) -> String {
+    if t.is_nan() {
+        return "Invalid Date".to_string();
+    }
+    let f = time_to_fields(t);
+    format!(
+        "{}, {:02} {} {} {:02}:{:02}:{:02} GMT",
+        WEEKDAYS[week_day(t)],
+        f[DATE],
+        MONTHS[f[MONTH] as usize],
+        format_year(f[YEAR]),
+        f[HOURS],
+        f[MINUTES],
+        f[SECONDS]
+    )
+}
+
+// ============================================================================
+// Parsing
+// ============================================================================
+
+/// Cursor over the ASCII bytes of a date string
+struct Scanner<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Scanner<'_> {
+    fn eat(&mut self, c: u8) -> bool {
+        if self.bytes.get(self.pos) == Some(&c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    /// Exactly `n` digits
+    fn digits(&mut self, n: usize) -> Option<f64> {
+        let digits = self.bytes.get(self.pos..self.pos + n)?;
+        if !digits.iter().all(u8::is_ascii_digit) {
+            return None;
+        }
+        self.pos += n;
+        Some(
+            digits
+                .iter()
+                .fold(0.0, |acc, d| acc * 10.0 + (d - b'0') as f64),
+        )
+    }
+
+    /// One or more digits
+    fn number(&mut self) -> Option<f64> {
+        let len = self.bytes[self.pos..]
+            .iter()
+            .take_while(|b| b.is_ascii_digit())
+            .count();
+        if len == 0 { None } else { self.digits(len) }
+    }
+
+    fn skip_spaces(&mut self) {
+        while self.eat(b' ') {}
+    }
+
+    fn word(&mut self) -> &str {
+        let start = self.pos;
+        while self.peek().is_some_and(|b| b.is_ascii_alphabetic()) {
+            self.pos += 1;
+        }
+        std::str::from_utf8(&self.bytes[start..self.pos]).unwrap_or("")
+    }
+
+    fn at_end(&self) -> bool {
+        self.pos == self.bytes.len()
+    }
+}
+
+/// `Date.parse`: the ISO-8601 subset of the ES Date Time String Format,
+/// plus the output of `toString` and `toUTCString`. NaN if unrecognised.
+pub fn parse(s: &str) -> f64 {
+    let s = s.trim();
+    parse_iso(s)
+        .or_else(|| parse_display(s))
+        .map(time_clip)
+        .unwrap_or(f64::NAN)
+}
+
+/// `YYYY[-MM[-DD]][THH:mm[:ss[.sss]][Z|±HH:mm]]`; date-only forms are UTC,
+/// date-times without an offset are local
+fn parse_iso(s: &str) -> Option<f64> {
+    let mut sc = Scanner {
+        bytes: s.as_bytes(),
+        pos: 0,
+    };
+    let year = match sc.peek()? {
+        sign @ (b'+' | b'-') => {
+            sc.pos += 1;
+            let negative = sign == b'-';
+            let year = sc.digits(6)?;
+            // -000000 is not a valid year
+            if negative && year == 0.0 {
+                return None;
+            }
+            if negative { -year } else { year }
+        }
+        _ => sc.digits(4)?,
+    };
+    let mut fields: Fields = [year, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0];
+    if sc.eat(b'-') {
+        fields[MONTH] = sc.digits(2)? - 1.0;
+        if sc.eat(b'-') {
+            fields[DATE] = sc.digits(2)?;
+        }
+    }
+    if !(0.0..12.0).contains(&fields[MONTH])
+        || fields[DATE] < 1.0
+        || fields[DATE] > days_in_month(year as i64, fields[MONTH] as i64 + 1) as f64
+    {
+        return None;
+    }
+    if sc.at_end() {
+        return Some(fields_to_time(&fields));
+    }
+
+    if !(sc.eat(b'T') || sc.eat(b't') || sc.eat(b' ')) {
+        return None;
+    }
+    fields[HOURS] = sc.digits(2)?;
+    if !sc.eat(b':') {
+        return None;
+    }
+    fields[MINUTES] = sc.digits(2)?;
+    if sc.eat(b':') {
+        fields[SECONDS] = sc.digits(2)?;
+        if sc.eat(b'.') || sc.eat(b',') {
+            let start = sc.pos;
+            let fraction = sc.number()?;
+            let len = (sc.pos - start) as i32;
+            fields[MILLIS] = (fraction * 10f64.powi(3 - len)).trunc();
+        }
+    }
+    let is_midnight_24 = fields[HOURS] == 24.0
+        && fields[MINUTES] == 0.0
+        && fields[SECONDS] == 0.0
+        && fields[MILLIS] == 0.0;
+    if (fields[HOURS] > 23.0 && !is_midnight_24) || fields[MINUTES] > 59.0 || fields[SECONDS] > 59.0
+    {
+        return None;
+    }
+
+    let time = fields_to_time(&fields);
+    let offset = match sc.peek() {
+        None => return Some(utc(time)),
+        Some(b'Z' | b'z') => {
+            sc.pos += 1;
+            0.0
+        }
+        Some(sign @ (b'+' | b'-')) => {
+            sc.pos += 1;
+            let hours = sc.digits(2)?;
+            sc.eat(b':');
+            let minutes = sc.digits(2)?;
+            if hours > 23.0 || minutes > 59.0 {
+                return None;
+            }
+            let offset = hours * 3_600_000.0 + minutes * 60_000.0;
+            if sign == b'-' { -offset } else { offset }
+        }
+        Some(_) => return None,
+    };
+    sc.at_end().then_some(time - offset)
+}
+
+/// `Tue Mar 05 2024 10:00:00 GMT+0100 (…)` or `Tue, 05 Mar 2024 09:00:00 GMT`
+fn parse_display(s: &str) -> Option<f64> {
+    let mut sc = Scanner {
+        bytes: s.as_bytes(),
+        pos: 0,
+    };
+    let week_day = sc.word().to_string();
+    if !WEEKDAYS.iter().any(|d| d.eq_ignore_ascii_case(&week_day)) {
+        return None;
+    }
+    let utc_form = sc.eat(b',');
+    sc.skip_spaces();
+    let (month, date) = if utc_form {
+        let date = sc.number()?;
+        sc.skip_spaces();
+        (sc.word().to_string(), date)
+    } else {
+        let month = sc.word().to_string();
+        sc.skip_spaces();
+        (month, sc.number()?)
+    };
+    let month = MONTHS.iter().position(|m| m.eq_ignore_ascii_case(&month))? as f64;
+    sc.skip_spaces();
+    let negative = sc.eat(b'-');
+    let year = sc.number()?;
+    let year = if negative { -year } else { year };
+    let mut fields: Fields = [year, month, date, 0.0, 0.0, 0.0, 0.0];
+    if date < 1.0 || date > days_in_month(year as i64, month as i64 + 1) as f64 {
+        return None;
+    }
+
+    sc.skip_spaces();
+    if sc.at_end() {
+        return Some(utc(fields_to_time(&fields)));
+    }
+    fields[HOURS] = sc.digits(2)?;
+    if !sc.eat(b':') {
+        return None;
+    }
+    fields[MINUTES] = sc.digits(2)?;
+    if sc.eat(b':') {
+        fields[SECONDS] = sc.digits(2)?;
+    }
+    if fields[HOURS] > 23.0 || fields[MINUTES] > 59.0 || fields[SECONDS] > 59.0 {
+        return None;
+    }
+    let time = fields_to_time(&fields);
+
+    sc.skip_spaces();
+    if sc.at_end() {
+        return Some(utc(time));
+    }
+    if !matches!(sc.word(), "GMT" | "UTC" | "Z") {
+        return None;
+    }
+    let offset = match sc.peek() {
+        Some(sign @ (b'+' | b'-')) => {
+            sc.pos += 1;
+            let hours = sc.digits(2)?;
+            let minutes = sc.digits(2)?;
+            let offset = hours * 3_600_000.0 + minutes * 60_000.0;
+            if sign == b'-' { -offset } else { offset }
+        }
+        _ => 0.0,
+    };
+    // Anything after the offset, such as a time zone name, is ignored
+    Some(time - offset)
+}
+
+// ============================================================================
+// VM Natives
+// ============================================================================
+
+/// Time value of a `Date` object
+pub fn time_value(vm: &VM, value: &JsValue) -> Option<f64> {
+    match value {
+        JsValue::Object(ptr) => match vm.heap.get(*ptr) {
+            Some(HeapObject {
+                data: HeapData::Date(t),
+            }) => Some(*t),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn alloc_date(vm: &mut VM, t: f64) -> JsValue {
+    let ptr = vm.heap.len();
+    vm.heap.push(HeapObject {
+        data: HeapData::Date(t),
+    });
+    JsValue::Object(ptr)
+}
+
+/// Local fields from `Date(year, month, …)`-style arguments
+fn fields_from_args(args: &[JsValue]) -> Fields {
+    let mut fields: Fields = [f64::NAN, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0];
+    for (field, arg) in fields.iter_mut().zip(args) {
+        *field = to_number(arg);
+    }
+    // Two-digit years are 1900-1999
+    let year = fields[YEAR];
+    if year.is_finite() && (0.0..=99.0).contains(&year.trunc()) {
+        fields[YEAR] = 1900.0 + year.trunc();
+    }
+    fields
+}
+
+/// `new Date()`, `new Date(value)` and `new Date(year, month[, …])`
+pub fn construct(vm: &mut VM, args: &[JsValue]) -> JsValue {
+    let t = match args {
+        [] => time::epoch_millis(),
+        [value] => match time_value(vm, value) {
+            Some(t) => t,
+            None => match value {
+                JsValue::String(s) => parse(s),
+                other => time_clip(to_number(other)),
+            },
+        },
+        _ => time_clip(utc(fields_to_time(&fields_from_args(args)))),
+    };
+    alloc_date(vm, t)
+}
+
+/// `Date()` called without `new` ignores its arguments
+pub fn native_date_call(_vm: &mut VM, _args: Vec<JsValue>) -> JsValue {
+    JsValue::String(to_string(time::epoch_millis()).into())
+}
+
+pub fn native_date_now(_vm: &mut VM, _args: Vec<JsValue>) -> JsValue {
+    JsValue::Number(time::epoch_millis())
+}
+
+pub fn native_date_parse(_vm: &mut VM, args: Vec<JsValue>) -> JsValue {
+    match args.first() {
+        Some(JsValue::String(s)) => JsValue::Number(parse(s)),
+        _ => JsValue::Number(f64::NAN),
+    }
+}
+
+/// `Date.UTC(year[, month[, …]])`
+pub fn native_date_utc(_vm: &mut VM, args: Vec<JsValue>) -> JsValue {
+    JsValue::Number(time_clip(fields_to_time(&fields_from_args(&args))))
+}
+
+pub fn native_performance_now(_vm: &mut VM, _args: Vec<JsValue>) -> JsValue {
+    JsValue::Number(time::performance_now())
+}
+
+/// First field and maximum argument count of each `set*` method
+fn setter(name: &str) -> Option<(usize, usize)> {
+    Some(match name {
+        "FullYear" => (YEAR, 3),
+        "Month" => (MONTH, 2),
+        "Date" => (DATE, 1),
+        "Hours" => (HOURS, 4),
+        "Minutes" => (MINUTES, 3),
+        "Seconds" => (SECONDS, 2),
+        "Milliseconds" => (MILLIS, 1),
+        _ => return None,
+    })
+}
+
+/// Field read by each `get*` method, or `None` for `getDay`
+fn getter(name: &str) -> Option<Option<usize>> {
+    Some(match name {
+        "FullYear" => Some(YEAR),
+        "Month" => Some(MONTH),
+        "Date" => Some(DATE),
+        "Hours" => Some(HOURS),
+        "Minutes" => Some(MINUTES),
+        "Seconds" => Some(SECONDS),
+        "Milliseconds" => Some(MILLIS),
+        "Day" => None,
+        _ => return None,
+    })
+}
+
+/// Call a `Date.prototype` method on the date at `ptr`. `Err` is the
+/// message of a `RangeError`; unknown methods return undefined.
+pub fn call_method(
+    vm: &mut VM,
+    ptr: usize,
+    name: &str,
+    args: &[JsValue],
+) -> Result<JsValue, String> {
+    let Some(HeapObject {
+        data: HeapData::Date(t),
+    }) = vm.heap.get(ptr)
+    else {
+        return Ok(JsValue::Undefined);
+    };
+    let t = *t;
+
+    let result = match name {
+        "getTime" | "valueOf" => JsValue::Number(t),
+        "getTimezoneOffset" if t.is_nan() => JsValue::Number(f64::NAN),
+        "getTimezoneOffset" => JsValue::Number(-local_offset(t) / 60_000.0),
+        "toISOString" => JsValue::String(to_iso_string(t).ok_or("Invalid time value")?.into()),
+        "toJSON" => to_iso_string(t).map_or(JsValue::Null, |s| JsValue::String(s.into())),
+        "toString" => JsValue::String(to_string(t).into()),
+        "toUTCString" | "toGMTString" => JsValue::String(to_utc_string(t).into()),
+        "toDateString" | "toTimeString" if t.is_nan() => JsValue::String("Invalid Date".into()),
+        "toDateString" => {
+            let local = local_time(t);
+            JsValue::String(date_string(&time_to_fields(local), week_day(local)).into())
+        }
+        "toTimeString" => {
+            let offset = local_offset(t);
+            JsValue::String(time_string(&time_to_fields(t + offset), offset).into())
+        }
+        "setTime" => {
+            let t = time_clip(args.first().map_or(f64::NAN, to_number));
+            vm.heap[ptr].data = HeapData::Date(t);
+            JsValue::Number(t)
+        }
+        _ => {
+            let (is_utc, rest) = match name.strip_prefix("getUTC") {
+                Some(rest) => (true, rest),
+                None => match name.strip_prefix("setUTC") {
+                    Some(rest) => (true, rest),
+                    None => (false, &name[name.len().min(3)..]),
+                },
+            };
+            let to_view = |t: f64| if is_utc { t } else { local_time(t) };
+
+            if name.starts_with("get")
+                && let Some(field) = getter(rest)
+            {
+                if t.is_nan() {
+                    return Ok(JsValue::Number(f64::NAN));
+                }
+                let view = to_view(t);
+                let value = match field {
+                    Some(field) => time_to_fields(view)[field],
+                    None => week_day(view) as f64,
+                };
+                JsValue::Number(value)
+            } else if name.starts_with("set")
+                && let Some((first, max_args)) = setter(rest)
+            {
+                // setFullYear on an invalid date starts from +0
+                let base = if t.is_nan() && first == YEAR { 0.0 } else { t };
+                let new_time = if base.is_nan() {
+                    f64::NAN
+                } else {
+                    let mut fields = time_to_fields(to_view(base));
+                    let count = args.len().clamp(1, max_args);
+                    for i in 0..count {
+                        fields[first + i] = args.get(i).map_or(f64::NAN, to_number);
+                    }
+                    let view = fields_to_time(&fields);
+                    time_clip(if is_utc { view } else { utc(view) })
+                };
+                vm.heap[ptr].data = HeapData::Date(new_time);
+                JsValue::Number(new_time)
+            } else {
+                JsValue::Undefined
+            }
+        }
+    };
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_civil_round_trip() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(2000, 3, 1), 11_017);
+        for days in [-719_468, -1, 0, 59, 365, 11_016, 19_782, 2_932_896] {
+            let (y, m, d) = civil_from_days(days);
+            assert_eq!(days_from_civil(y, m, d), days);
+        }
+        assert_eq!(days_in_month(2024, 2), 29);
+        assert_eq!(days_in_month(1900, 2), 28);
+        assert_eq!(days_in_month(2023, 12), 31);
+    }
+
+    #[test]
+    fn test_make_day_carries_months() {
+        let jan_2025 = make_day(2025.0, 0.0, 1.0);
+        assert_eq!(make_day(2024.0, 12.0, 1.0), jan_2025);
+        assert_eq!(make_day(2025.0, -1.0, 32.0), jan_2025);
+        assert!(make_day(f64::NAN, 0.0, 1.0).is_nan());
+    }
+
+    #[test]
+    fn test_iso_round_trip() {
+        let t = parse("2024-03-05T10:20:30.456Z");
+        assert_eq!(t, 1_709_634_030_456.0);
+        assert_eq!(to_iso_string(t).unwrap(), "2024-03-05T10:20:30.456Z");
+        assert_eq!(to_iso_string(0.0).unwrap(), "1970-01-01T00:00:00.000Z");
+        assert_eq!(
+            to_iso_string(-62_198_755_200_000.0).unwrap(),
+            "-000001-01-01T00:00:00.000Z"
+        );
+        assert_eq!(parse("-000001-01-01T00:00:00Z"), -62_198_755_200_000.0);
+        assert_eq!(
+            to_iso_string(MAX_TIME).unwrap(),
+            "+275760-09-13T00:00:00.000Z"
+        );
+        assert_eq!(to_iso_string(f64::NAN), None);
+    }
+
+    #[test]
+    fn test_parse_iso_forms() {
+        assert_eq!(parse("1970"), 0.0);
+        assert_eq!(parse("1970-02"), 31.0 * MS_PER_DAY);
+        assert_eq!(parse("1970-01-02"), MS_PER_DAY);
+        assert_eq!(parse("1970-01-01T01:00+01:00"), 0.0);
+        assert_eq!(parse("1970-01-01T00:00:00.5Z"), 500.0);
+        assert_eq!(parse("1970-01-01T24:00:00Z"), MS_PER_DAY);
+        assert_eq!(parse("1969-12-31T19:00:00-0500"), 0.0);
+        for bad in [
+            "",
+            "2024-13-01",
+            "2023-02-29",
+            "2024-01-01T25:00Z",
+            "2024-01-01T10:00Zjunk",
+            "-000000-01-01",
+            "hello",
+        ] {
+            assert!(parse(bad).is_nan(), "{:?}", bad);
+        }
+    }
+
+    #[test]
+    fn test_display_strings() {
+        let t = parse("2024-03-05T09:07:03Z");
+        assert_eq!(to_utc_string(t), "Tue, 05 Mar 2024 09:07:03 GMT");
+        assert_eq!(parse(&to_utc_string(t)), t);
+        assert_eq!(parse(&to_string(t)), t);
+        assert_eq!(parse("Tue Mar 05 2024 10:07:03 GMT+0100 (CET)"), t);
+        assert_eq!(to_string(f64::NAN), "Invalid Date");
+    }
+
+    #[test]
+    fn test_time_clip() {
+        assert_eq!(time_clip(1.9), 1.0);
+        assert!(time_clip(MAX_TIME + 1.0).is_nan());
+        assert!(time_clip(f64::INFINITY).is_nan());
+        assert!(time_clip(-0.0).is_sign_positive());
+    }
+}
@@ -0,0 +1,267 @@
+//! `Error` and its built-in subclasses
+//!
+//! Each class is a wrapper object like a compiled `class`: `constructor` is a
+//! native that fills in `this`, `prototype` carries `name` and `toString`,
+//! and subclasses point `__super__` at `Error` so user classes can extend
+//! any of them with `super(message)`. Errors the VM raises itself are built
+//! by [`create`], so they are indistinguishable from ones user code throws.
+
+use std::collections::HashMap;
+
+use crate::runtime::number;
+use crate::vm::VM;
+use crate::vm::value::{HeapData, HeapObject, JsValue, NativeFn};
+
+use super::{inspect_value, native_string_constructor};
+
+/// The built-in error classes, `Error` first
+pub const ERROR_TYPES: [&str; 8] = [
+    "Error",
+    "TypeError",
+    "RangeError",
+    "SyntaxError",
+    "ReferenceError",
+    "EvalError",
+    "URIError",
+    "AggregateError",
+];
+
+/// `Error(...)` called without `new`, one per entry of [`ERROR_TYPES`]
+pub const CALLS: [NativeFn; 8] = [
+    |vm, args| call(vm, 0, args),
+    |vm, args| call(vm, 1, args),
+    |vm, args| call(vm, 2, args),
+    |vm, args| call(vm, 3, args),
+    |vm, args| call(vm, 4, args),
+    |vm, args| call(vm, 5, args),
+    |vm, args| call(vm, 6, args),
+    |vm, args| call(vm, 7, args),
+];
+
+fn alloc(vm: &mut VM, props: HashMap<String, JsValue>) -> usize {
+    let ptr = vm.heap.len();
+    vm.heap.push(HeapObject {
+        data: HeapData::Object(props),
+    });
+    ptr
+}
+
+fn set(vm: &mut VM, ptr: usize, key: &str, value: JsValue) {
+    if let Some(HeapObject {
+        data: HeapData::Object(props),
+    }) = vm.heap.get_mut(ptr)
+    {
+        props.insert(key.to_string(), value);
+    }
+}
+
+/// A property as `toString` shows it; `undefined` is empty
+fn get_string(vm: &VM, ptr: usize, key: &str) -> String {
+    match vm.get_prop_with_proto_chain(ptr, key) {
+        JsValue::String(s) => s.to_string(),
+        JsValue::Undefined => String::new(),
+        JsValue::Number(n) => number::to_string(n),
+        JsValue::Boolean(b) => b.to_string(),
+        JsValue::Null => "null".to_string(),
+        other => inspect_value(vm, &other),
+    }
+}
+
+/// A new `name` error with `message`, its stack starting at the current
+/// instruction. Falls back to a plain `{ name, message }` object in a VM
+/// without the standard library.
+pub fn create(vm: &mut VM, name: &str, message: String) -> JsValue {
+    let Some(&proto) = vm.error_prototypes.get(name) else {
+        let mut props = HashMap::new();
+        props.insert("name".to_string(), JsValue::String(name.into()));
+        props.insert("message".to_string(), JsValue::String(message.into()));
+        return JsValue::Object(alloc(vm, props));
+    };
+    let mut props = HashMap::new();
+    props.insert("__proto__".to_string(), JsValue::Object(proto));
+    let ptr = alloc(vm, props);
+    init(vm, ptr, Some(JsValue::String(message.into())), None, 0);
+    JsValue::Object(ptr)
+}
+
+/// Whether `ptr` has `Error.prototype` on its prototype chain
+pub fn is_error(vm: &VM, ptr: usize) -> bool {
+    let Some(&error_proto) = vm.error_prototypes.get("Error") else {
+        return false;
+    };
+    let mut current = ptr;
+    // Bounded like `instanceof`, in case of a prototype cycle
+    for _ in 0..100 {
+        let Some(HeapObject {
+            data: HeapData::Object(props),
+        }) = vm.heap.get(current)
+        else {
+            return false;
+        };
+        match props.get("__proto__") {
+            Some(JsValue::Object(proto)) if *proto == error_proto => return true,
+            Some(JsValue::Object(proto)) => current = *proto,
+            _ => return false,
+        }
+    }
+    false
+}
+
+/// `Error.prototype.toString`: `name: message`, leaving out whichever is empty
+pub fn to_string(vm: &VM, ptr: usize) -> String {
+    let name = match vm.get_prop_with_proto_chain(ptr, "name") {
+        JsValue::Undefined => "Error".to_string(),
+        _ => get_string(vm, ptr, "name"),
+    };
+    let message = get_string(vm, ptr, "message");
+    match (name.is_empty(), message.is_empty()) {
+        (_, true) => name,
+        (true, false) => message,
+        (false, false) => format!("{}: {}", name, message),
+    }
+}
+
+/// The own `stack` of an error, if it has one
+pub fn stack(vm: &VM, ptr: usize) -> Option<&str> {
+    match vm.heap.get(ptr) {
+        Some(HeapObject {
+            data: HeapData::Object(props),
+        }) => match props.get("stack") {
+            Some(JsValue::String(stack)) => Some(stack),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Set the own `message`, `cause` and `stack` of a new error. `skip` counts
+/// the native frames above the code that created it.
+fn init(vm: &mut VM, ptr: usize, message: Option<JsValue>, options: Option<JsValue>, skip: usize) {
+    match message {
+        None | Some(JsValue::Undefined) => {}
+        Some(message) => {
+            let message = native_string_constructor(vm, vec![message]);
+            set(vm, ptr, "message", message);
+        }
+    }
+    if let Some(JsValue::Object(options_ptr)) = options
+        && let Some(HeapObject {
+            data: HeapData::Object(props),
+        }) = vm.heap.get(options_ptr)
+        && let Some(cause) = props.get("cause").cloned()
+    {
+        set(vm, ptr, "cause", cause);
+    }
+    let trace = vm.stack_trace(skip);
+    let header = to_string(vm, ptr);
+    let stack = if trace.is_empty() {
+        header
+    } else {
+        format!("{}\n{}", header, trace)
+    };
+    set(vm, ptr, "stack", JsValue::String(stack.into()));
+}
+
+/// The object under construction, from the native constructor's frame
+fn this(vm: &VM) -> Option<usize> {
+    match vm.call_stack.last().map(|frame| &frame.this_context) {
+        Some(JsValue::Object(ptr)) => Some(*ptr),
+        _ => None,
+    }
+}
+
+/// `new Error(message, options)`, also reached through `super(...)`
+pub fn native_error_construct(vm: &mut VM, args: Vec<JsValue>) -> JsValue {
+    let Some(ptr) = this(vm) else {
+        return JsValue::Undefined;
+    };
+    let mut args = args.into_iter();
+    init(vm, ptr, args.next(), args.next(), 1);
+    JsValue::Object(ptr)
+}
+
+/// `new AggregateError(errors, message, options)`
+pub fn native_aggregate_error_construct(vm: &mut VM, args: Vec<JsValue>) -> JsValue {
+    let Some(ptr) = this(vm) else {
+        return JsValue::Undefined;
+    };
+    construct_aggregate(vm, ptr, args, 1);
+    JsValue::Object(ptr)
+}
+
+fn construct_aggregate(vm: &mut VM, ptr: usize, args: Vec<JsValue>, skip: usize) {
+    let mut args = args.into_iter();
+    let errors = match args.next() {
+        Some(JsValue::Object(errors_ptr)) => match vm.heap.get(errors_ptr) {
+            Some(HeapObject {
+                data: HeapData::Array(errors),
+            }) => errors.clone(),
+            _ => Vec::new(),
+        },
+        _ => Vec::new(),
+    };
+    let errors_ptr = vm.heap.len();
+    vm.heap.push(HeapObject {
+        data: HeapData::Array(errors),
+    });
+    set(vm, ptr, "errors", JsValue::Object(errors_ptr));
+    init(vm, ptr, args.next(), args.next(), skip);
+}
+
+/// `Error(...)` without `new`, which constructs all the same
+fn call(vm: &mut VM, index: usize, args: Vec<JsValue>) -> JsValue {
+    let name = ERROR_TYPES[index];
+    let mut props = HashMap::new();
+    props.insert(
+        "__proto__".to_string(),
+        JsValue::Object(vm.error_prototypes[name]),
+    );
+    let ptr = alloc(vm, props);
+    // A plain native call has no frame of its own
+    if name == "AggregateError" {
+        construct_aggregate(vm, ptr, args, 0);
+    } else {
+        let mut args = args.into_iter();
+        init(vm, ptr, args.next(), args.next(), 0);
+    }
+    JsValue::Object(ptr)
+}
+
+/// `Error.prototype.toString()`
+pub fn native_error_to_string(vm: &mut VM, _args: Vec<JsValue>) -> JsValue {
+    match this(vm) {
+        Some(ptr) => JsValue::String(to_string(vm, ptr).into()),
+        None => JsValue::String("Error".into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_create_links_to_the_class_prototype() {
+        let mut vm = VM::new();
+        let JsValue::Object(ptr) = create(&mut vm, "RangeError", "too far".to_string()) else {
+            panic!("error is not an object");
+        };
+        assert!(is_error(&vm, ptr));
+        assert_eq!(to_string(&vm, ptr), "RangeError: too far");
+        assert_eq!(stack(&vm, ptr), Some("RangeError: too far\n    at <main>"));
+        set(
+            &mut vm,
+            ptr,
+            "message",
+            JsValue::String(String::new().into()),
+        );
+        assert_eq!(to_string(&vm, ptr), "RangeError");
+    }
+
+    #[test]
+    fn test_plain_objects_are_not_errors() {
+        let mut vm = VM::new();
+        let ptr = alloc(&mut vm, HashMap::new());
+        assert!(!is_error(&vm, ptr));
+        assert_eq!(stack(&vm, ptr), None);
+    }
+}
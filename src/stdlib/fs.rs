@@ -0,0 +1,704 @@
+//! `fs.promises`: file system calls that run on the VM's blocking pool and
+//! return promises, settled by the event loop
+//!
+//! Failures reject with errors shaped like Node's, with `code`, `errno`,
+//! `syscall` and `path` properties and messages such as
+//! `ENOENT: no such file or directory, open 'missing.txt'`. Arguments of the
+//! wrong type reject with a TypeError. `open` resolves with a file handle
+//! whose `read` calls continue where the last one stopped, so a file can be
+//! streamed a buffer at a time.
+
+use std::collections::HashMap;
+use std::fs::{self, File, Metadata, OpenOptions};
+use std::io::{self, Read, Write};
+use std::os::unix::fs::{FileExt, MetadataExt, OpenOptionsExt};
+use std::os::unix::io::AsRawFd;
+use std::sync::{Arc, Mutex};
+use std::time::UNIX_EPOCH;
+
+use crate::vm::VM;
+use crate::vm::value::{HeapData, HeapObject, JsValue, NativeFn, Promise};
+
+use super::{date, error, is_truthy, reads_bytes, to_integer, typed_array};
+
+/// Bytes `FileHandle.read()` reads without a buffer of its own
+const DEFAULT_READ_SIZE: usize = 16 * 1024;
+
+/// File type bits of `Stats.mode`
+const S_IFMT: u32 = 0o170_000;
+const S_IFREG: u32 = 0o100_000;
+const S_IFDIR: u32 = 0o040_000;
+const S_IFLNK: u32 = 0o120_000;
+
+/// Natives of `fs.promises`
+pub const PROMISES_NATIVES: [(&str, NativeFn); 8] = [
+    ("readFile", native_read_file),
+    ("writeFile", native_write_file),
+    ("readdir", native_readdir),
+    ("stat", native_stat),
+    ("mkdir", native_mkdir),
+    ("rm", native_rm),
+    ("rename", native_rename),
+    ("open", native_open),
+];
+
+/// Methods of the handles `fs.promises.open` resolves with
+pub const FILE_HANDLE_NATIVES: [(&str, NativeFn); 6] = [
+    ("read", native_handle_read),
+    ("readFile", native_handle_read_file),
+    ("write", native_handle_write),
+    ("writeFile", native_handle_write_file),
+    ("stat", native_handle_stat),
+    ("close", native_handle_close),
+];
+
+/// Methods of `Stats` objects
+pub const STATS_NATIVES: [(&str, NativeFn); 3] = [
+    ("isFile", native_stats_is_file),
+    ("isDirectory", native_stats_is_directory),
+    ("isSymbolicLink", native_stats_is_symbolic_link),
+];
+
+/// A failed system call and the paths it was given
+struct FsError {
+    error: io::Error,
+    syscall: &'static str,
+    path: Option<String>,
+    dest: Option<String>,
+}
+
+type FsResult<T> = Result<T, FsError>;
+
+/// Attach `syscall` and `path` to an I/O error
+fn failed(syscall: &'static str, path: &str) -> impl FnOnce(io::Error) -> FsError {
+    let path = path.to_string();
+    move |error| FsError {
+        error,
+        syscall,
+        path: Some(path),
+        dest: None,
+    }
+}
+
+/// The error of a call on an open handle, which has no path
+fn handle_error(syscall: &'static str) -> impl FnOnce(io::Error) -> FsError {
+    move |error| FsError {
+        error,
+        syscall,
+        path: None,
+        dest: None,
+    }
+}
+
+/// Node's code and libuv's description of an OS error number
+fn error_code(errno: i32) -> Option<(&'static str, &'static str)> {
+    Some(match errno {
+        libc::ENOENT => ("ENOENT", "no such file or directory"),
+        libc::EEXIST => ("EEXIST", "file already exists"),
+        libc::EACCES => ("EACCES", "permission denied"),
+        libc::EPERM => ("EPERM", "operation not permitted"),
+        libc::EISDIR => ("EISDIR", "illegal operation on a directory"),
+        libc::ENOTDIR => ("ENOTDIR", "not a directory"),
+        libc::ENOTEMPTY => ("ENOTEMPTY", "directory not empty"),
+        libc::EBADF => ("EBADF", "bad file descriptor"),
+        libc::EINVAL => ("EINVAL", "invalid argument"),
+        libc::EMFILE => ("EMFILE", "too many open files"),
+        libc::EXDEV => ("EXDEV", "cross-device link not permitted"),
+        libc::EBUSY => ("EBUSY", "resource busy or locked"),
+        libc::ENOSPC => ("ENOSPC", "no space left on device"),
+        libc::EROFS => ("EROFS", "read-only file system"),
+        libc::ELOOP => ("ELOOP", "too many symbolic links encountered"),
+        libc::ENAMETOOLONG => ("ENAMETOOLONG", "name too long"),
+        _ => return None,
+    })
+}
+
+/// The `Error` a failed call rejects with
+fn to_error(vm: &mut VM, failure: FsError) -> JsValue {
+    let errno = failure.error.raw_os_error();
+    let (code, description) = match errno.and_then(error_code) {
+        Some((code, description)) => (code, description.to_string()),
+        None => ("UNKNOWN", failure.error.to_string()),
+    };
+    let mut message = format!("{}: {}, {}", code, description, failure.syscall);
+    if let Some(path) = &failure.path {
+        message.push_str(&format!(" '{}'", path));
+    }
+    if let Some(dest) = &failure.dest {
+        message.push_str(&format!(" -> '{}'", dest));
+    }
+    let error = error::create(vm, "Error", message);
+    if let JsValue::Object(ptr) = error {
+        set(vm, ptr, "code", JsValue::String(code.into()));
+        if let Some(errno) = errno {
+            set(vm, ptr, "errno", JsValue::Number(-errno as f64));
+        }
+        set(vm, ptr, "syscall", JsValue::String(failure.syscall.into()));
+        if let Some(path) = failure.path {
+            set(vm, ptr, "path", JsValue::String(path.into()));
+        }
+        if let Some(dest) = failure.dest {
+            set(vm, ptr, "dest", JsValue::String(dest.into()));
+        }
+    }
+    error
+}
+
+fn alloc(vm: &mut VM, props: HashMap<String, JsValue>) -> usize {
+    let ptr = vm.heap.len();
+    vm.heap.push(HeapObject {
+        data: HeapData::Object(props),
+    });
+    ptr
+}
+
+fn set(vm: &mut VM, ptr: usize, key: &str, value: JsValue) {
+    if let Some(HeapObject {
+        data: HeapData::Object(props),
+    }) = vm.heap.get_mut(ptr)
+    {
+        props.insert(key.to_string(), value);
+    }
+}
+
+/// An object with the given properties, inheriting from the `Stats` or
+/// `FileHandle` prototype if `proto` names one
+fn object(vm: &mut VM, proto: Option<&str>, fields: Vec<(&str, JsValue)>) -> JsValue {
+    let mut props: HashMap<String, JsValue> = fields
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value))
+        .collect();
+    if let Some(&proto) = proto.and_then(|proto| vm.fs_prototypes.get(proto)) {
+        props.insert("__proto__".to_string(), JsValue::Object(proto));
+    }
+    JsValue::Object(alloc(vm, props))
+}
+
+/// A promise already rejected with a new `name` error
+fn rejected(vm: &mut VM, name: &str, message: String) -> JsValue {
+    let promise = Promise::new();
+    promise.set_value(error::create(vm, name, message), false);
+    JsValue::Promise(promise)
+}
+
+/// Run `work` on the blocking pool, resolving with `finish` of its result
+fn spawn<T, W, F>(vm: &mut VM, work: W, finish: F) -> JsValue
+where
+    T: Send + 'static,
+    W: FnOnce() -> FsResult<T> + Send + 'static,
+    F: FnOnce(&mut VM, T) -> JsValue + Send + 'static,
+{
+    JsValue::Promise(vm.spawn_blocking(work, finish, to_error))
+}
+
+/// A path argument, or the TypeError message for anything but a string
+fn path_arg(args: &[JsValue], index: usize, name: &str) -> Result<String, String> {
+    match args.get(index) {
+        Some(JsValue::String(path)) => Ok(path.to_string()),
+        _ => Err(format!("The \"{}\" argument must be of type string", name)),
+    }
+}
+
+/// An option of an options object
+fn option(vm: &VM, options: Option<&JsValue>, key: &str) -> bool {
+    match options {
+        Some(JsValue::Object(ptr)) => is_truthy(&vm.get_prop_with_proto_chain(*ptr, key)),
+        _ => false,
+    }
+}
+
+/// The bytes of a string or binary `data` argument
+fn data_arg(vm: &VM, data: Option<&JsValue>) -> Result<Vec<u8>, String> {
+    match data {
+        Some(JsValue::String(s)) => Some(s.as_bytes().to_vec()),
+        Some(data) => typed_array::bytes(vm, data),
+        None => None,
+    }
+    .ok_or_else(|| {
+        "The \"data\" argument must be a string, ArrayBuffer, typed array or DataView".to_string()
+    })
+}
+
+/// File contents as `readFileSync` would return them
+fn contents(vm: &mut VM, bytes: Vec<u8>, as_bytes: bool) -> JsValue {
+    if as_bytes {
+        typed_array::new_uint8_array(vm, bytes)
+    } else {
+        JsValue::String(String::from_utf8_lossy(&bytes).as_ref().into())
+    }
+}
+
+/// Milliseconds since the epoch of a seconds and nanoseconds timestamp
+fn millis(secs: i64, nanos: i64) -> f64 {
+    secs as f64 * 1000.0 + nanos as f64 / 1e6
+}
+
+/// The numeric fields of a `Stats` object
+fn stat_fields(metadata: &Metadata) -> Vec<(&'static str, f64)> {
+    let ctime = millis(metadata.ctime(), metadata.ctime_nsec());
+    let birthtime = metadata
+        .created()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map_or(ctime, |elapsed| elapsed.as_secs_f64() * 1000.0);
+    vec![
+        ("dev", metadata.dev() as f64),
+        ("ino", metadata.ino() as f64),
+        ("mode", metadata.mode() as f64),
+        ("nlink", metadata.nlink() as f64),
+        ("uid", metadata.uid() as f64),
+        ("gid", metadata.gid() as f64),
+        ("rdev", metadata.rdev() as f64),
+        ("size", metadata.size() as f64),
+        ("blksize", metadata.blksize() as f64),
+        ("blocks", metadata.blocks() as f64),
+        ("atimeMs", millis(metadata.atime(), metadata.atime_nsec())),
+        ("mtimeMs", millis(metadata.mtime(), metadata.mtime_nsec())),
+        ("ctimeMs", ctime),
+        ("birthtimeMs", birthtime),
+    ]
+}
+
+/// A `Stats` object, with a `Date` for each `*Ms` time
+fn stats(vm: &mut VM, fields: Vec<(&'static str, f64)>) -> JsValue {
+    let mut props = Vec::new();
+    for (key, value) in fields {
+        if let Some(time) = key.strip_suffix("Ms") {
+            let date = date::construct(vm, &[JsValue::Number(value)]);
+            props.push((time, date));
+        }
+        props.push((key, JsValue::Number(value)));
+    }
+    object(vm, Some("Stats"), props)
+}
+
+/// `fs.promises.readFile(path[, options])`: a string, or a `Uint8Array`
+/// with the options `readFileSync` reads bytes for
+fn native_read_file(vm: &mut VM, args: Vec<JsValue>) -> JsValue {
+    let path = match path_arg(&args, 0, "path") {
+        Ok(path) => path,
+        Err(message) => return rejected(vm, "TypeError", message),
+    };
+    let as_bytes = reads_bytes(vm, args.get(1));
+    spawn(
+        vm,
+        move || fs::read(&path).map_err(failed("open", &path)),
+        move |vm, bytes| contents(vm, bytes, as_bytes),
+    )
+}
+
+/// `fs.promises.writeFile(path, data)` with a string, `ArrayBuffer`, typed
+/// array or `DataView`
+fn native_write_file(vm: &mut VM, args: Vec<JsValue>) -> JsValue {
+    let (path, data) = match (path_arg(&args, 0, "path"), data_arg(vm, args.get(1))) {
+        (Ok(path), Ok(data)) => (path, data),
+        (Err(message), _) | (_, Err(message)) => return rejected(vm, "TypeError", message),
+    };
+    spawn(
+        vm,
+        move || fs::write(&path, data).map_err(failed("open", &path)),
+        |_, ()| JsValue::Undefined,
+    )
+}
+
+/// `fs.promises.readdir(path)`: the entry names, sorted
+fn native_readdir(vm: &mut VM, args: Vec<JsValue>) -> JsValue {
+    let path = match path_arg(&args, 0, "path") {
+        Ok(path) => path,
+        Err(message) => return rejected(vm, "TypeError", message),
+    };
+    spawn(
+        vm,
+        move || {
+            let entries = fs::read_dir(&path).map_err(failed("scandir", &path))?;
+            let mut names = Vec::new();
+            for entry in entries {
+                let entry = entry.map_err(failed("scandir", &path))?;
+                names.push(entry.file_name().to_string_lossy().into_owned());
+            }
+            names.sort();
+            Ok(names)
+        },
+        |vm, names| {
+            let ptr = vm.heap.len();
+            vm.heap.push(HeapObject {
+                data: HeapData::Array(
+                    names
+                        .into_iter()
+                        .map(|s| JsValue::String(s.into()))
+                        .collect(),
+                ),
+            });
+            JsValue::Object(ptr)
+        },
+    )
+}
+
+/// `fs.promises.stat(path)`, following symbolic links
+fn native_stat(vm: &mut VM, args: Vec<JsValue>) -> JsValue {
+    let path = match path_arg(&args, 0, "path") {
+        Ok(path) => path,
+        Err(message) => return rejected(vm, "TypeError", message),
+    };
+    spawn(
+        vm,
+        move || {
+            fs::metadata(&path)
+                .map(|metadata| stat_fields(&metadata))
+                .map_err(failed("stat", &path))
+        },
+        stats,
+    )
+}
+
+/// `fs.promises.mkdir(path[, { recursive }])`
+fn native_mkdir(vm: &mut VM, args: Vec<JsValue>) -> JsValue {
+    let path = match path_arg(&args, 0, "path") {
+        Ok(path) => path,
+        Err(message) => return rejected(vm, "TypeError", message),
+    };
+    let recursive = option(vm, args.get(1), "recursive");
+    spawn(
+        vm,
+        move || {
+            let created = if recursive {
+                fs::create_dir_all(&path)
+            } else {
+                fs::create_dir(&path)
+            };
+            created.map_err(failed("mkdir", &path))
+        },
+        |_, ()| JsValue::Undefined,
+    )
+}
+
+/// `fs.promises.rm(path[, { recursive, force }])`: directories only with
+/// `recursive`; a missing path only fails without `force`
+fn native_rm(vm: &mut VM, args: Vec<JsValue>) -> JsValue {
+    let path = match path_arg(&args, 0, "path") {
+        Ok(path) => path,
+        Err(message) => return rejected(vm, "TypeError", message),
+    };
+    let recursive = option(vm, args.get(1), "recursive");
+    let force = option(vm, args.get(1), "force");
+    spawn(
+        vm,
+        move || {
+            let removed = match fs::symlink_metadata(&path) {
+                Ok(metadata) if metadata.is_dir() && recursive => fs::remove_dir_all(&path),
+                Ok(metadata) if metadata.is_dir() => {
+                    Err(io::Error::from_raw_os_error(libc::EISDIR))
+                }
+                Ok(_) => fs::remove_file(&path),
+                Err(error) => Err(error),
+            };
+            match removed {
+                Err(error) if force && error.kind() == io::ErrorKind::NotFound => Ok(()),
+                removed => removed.map_err(failed("rm", &path)),
+            }
+        },
+        |_, ()| JsValue::Undefined,
+    )
+}
+
+/// `fs.promises.rename(oldPath, newPath)`
+fn native_rename(vm: &mut VM, args: Vec<JsValue>) -> JsValue {
+    let (from, to) = match (path_arg(&args, 0, "oldPath"), path_arg(&args, 1, "newPath")) {
+        (Ok(from), Ok(to)) => (from, to),
+        (Err(message), _) | (_, Err(message)) => return rejected(vm, "TypeError", message),
+    };
+    spawn(
+        vm,
+        move || {
+            fs::rename(&from, &to).map_err(|error| FsError {
+                error,
+                syscall: "rename",
+                path: Some(from),
+                dest: Some(to),
+            })
+        },
+        |_, ()| JsValue::Undefined,
+    )
+}
+
+/// Open options for a Node `flags` string
+fn open_options(flags: &str) -> Option<OpenOptions> {
+    let mut options = OpenOptions::new();
+    match flags {
+        "r" | "rs" | "sr" => options.read(true),
+        "r+" | "rs+" | "sr+" => options.read(true).write(true),
+        "w" => options.write(true).create(true).truncate(true),
+        "wx" | "xw" => options.write(true).create_new(true),
+        "w+" => options.read(true).write(true).create(true).truncate(true),
+        "wx+" | "xw+" => options.read(true).write(true).create_new(true),
+        "a" | "as" | "sa" => options.append(true).create(true),
+        "ax" | "xa" => options.append(true).create_new(true),
+        "a+" | "as+" | "sa+" => options.read(true).append(true).create(true),
+        "ax+" | "xa+" => options.read(true).append(true).create_new(true),
+        _ => return None,
+    };
+    Some(options)
+}
+
+/// `fs.promises.open(path[, flags[, mode]])`: a `FileHandle`, opened for
+/// reading unless `flags` say otherwise
+fn native_open(vm: &mut VM, args: Vec<JsValue>) -> JsValue {
+    let path = match path_arg(&args, 0, "path") {
+        Ok(path) => path,
+        Err(message) => return rejected(vm, "TypeError", message),
+    };
+    let flags = match args.get(1) {
+        None | Some(JsValue::Undefined) => "r".to_string(),
+        Some(JsValue::String(flags)) => flags.to_string(),
+        Some(other) => super::inspect_value(vm, other),
+    };
+    let Some(mut options) = open_options(&flags) else {
+        let message = format!("The value \"{}\" is invalid for option \"flags\"", flags);
+        return rejected(vm, "TypeError", message);
+    };
+    if let Some(JsValue::Number(mode)) = args.get(2) {
+        options.mode(*mode as u32);
+    }
+    spawn(
+        vm,
+        move || options.open(&path).map_err(failed("open", &path)),
+        |vm, file| {
+            let fd = file.as_raw_fd();
+            vm.open_files.insert(fd, Arc::new(Mutex::new(file)));
+            object(
+                vm,
+                Some("FileHandle"),
+                vec![("fd", JsValue::Number(fd as f64))],
+            )
+        },
+    )
+}
+
+/// The handle a `FileHandle` method is called on, its descriptor and its
+/// open file, or `None` once it is closed
+fn this_file(vm: &VM) -> Option<(usize, i32, Arc<Mutex<File>>)> {
+    let Some(JsValue::Object(ptr)) = vm.call_stack.last().map(|frame| &frame.this_context) else {
+        return None;
+    };
+    let JsValue::Number(fd) = vm.get_prop_with_proto_chain(*ptr, "fd") else {
+        return None;
+    };
+    let file = vm.open_files.get(&(fd as i32))?;
+    Some((*ptr, fd as i32, Arc::clone(file)))
+}
+
+/// A promise rejected with `EBADF`, for a call on a closed handle
+fn closed(vm: &mut VM, syscall: &'static str) -> JsValue {
+    let failure = handle_error(syscall)(io::Error::from_raw_os_error(libc::EBADF));
+    let promise = Promise::new();
+    promise.set_value(to_error(vm, failure), false);
+    JsValue::Promise(promise)
+}
+
+/// A `position` argument: `None` to use and advance the file position
+fn position_arg(value: Option<&JsValue>) -> Option<u64> {
+    match value {
+        Some(JsValue::Number(n)) if *n >= 0.0 => Some(*n as u64),
+        _ => None,
+    }
+}
+
+/// `handle.read([buffer[, offset[, length[, position]]]])`: resolves with
+/// `{ bytesRead, buffer }`, reading into a new 16 KiB `Uint8Array` without
+/// a buffer. `bytesRead` is 0 at the end of the file.
+fn native_handle_read(vm: &mut VM, args: Vec<JsValue>) -> JsValue {
+    let Some((_, _, file)) = this_file(vm) else {
+        return closed(vm, "read");
+    };
+    let buffer = match args.first() {
+        None | Some(JsValue::Undefined) => {
+            typed_array::new_uint8_array(vm, vec![0; DEFAULT_READ_SIZE])
+        }
+        Some(buffer) => buffer.clone(),
+    };
+    let Some(capacity) = typed_array::uint8_array_mut(vm, &buffer).map(|bytes| bytes.len()) else {
+        let message = "The \"buffer\" argument must be a Uint8Array".to_string();
+        return rejected(vm, "TypeError", message);
+    };
+    let offset = args.get(1).map_or(0.0, to_integer);
+    let length = match args.get(2) {
+        None | Some(JsValue::Undefined) => capacity as f64 - offset,
+        Some(length) => to_integer(length),
+    };
+    if offset < 0.0 || length < 0.0 || offset + length > capacity as f64 {
+        let message = "The value of \"offset\" or \"length\" is out of range".to_string();
+        return rejected(vm, "RangeError", message);
+    }
+    let (offset, length) = (offset as usize, length as usize);
+    let position = position_arg(args.get(3));
+    spawn(
+        vm,
+        move || {
+            let mut chunk = vec![0; length];
+            let mut file = file.lock().unwrap();
+            let read = match position {
+                Some(position) => file.read_at(&mut chunk, position),
+                None => file.read(&mut chunk),
+            };
+            let read = read.map_err(handle_error("read"))?;
+            chunk.truncate(read);
+            Ok(chunk)
+        },
+        move |vm, chunk| {
+            if let Some(bytes) = typed_array::uint8_array_mut(vm, &buffer)
+                && let Some(target) = bytes.get_mut(offset..offset + chunk.len())
+            {
+                target.copy_from_slice(&chunk);
+            }
+            let bytes_read = JsValue::Number(chunk.len() as f64);
+            object(
+                vm,
+                None,
+                vec![("bytesRead", bytes_read), ("buffer", buffer)],
+            )
+        },
+    )
+}
+
+/// `handle.readFile([options])`: the rest of the file from the current
+/// position, as `fs.promises.readFile` returns it
+fn native_handle_read_file(vm: &mut VM, args: Vec<JsValue>) -> JsValue {
+    let Some((_, _, file)) = this_file(vm) else {
+        return closed(vm, "read");
+    };
+    let as_bytes = reads_bytes(vm, args.first());
+    spawn(
+        vm,
+        move || {
+            let mut bytes = Vec::new();
+            file.lock()
+                .unwrap()
+                .read_to_end(&mut bytes)
+                .map_err(handle_error("read"))?;
+            Ok(bytes)
+        },
+        move |vm, bytes| contents(vm, bytes, as_bytes),
+    )
+}
+
+/// `handle.write(string[, position])` or
+/// `handle.write(buffer[, offset[, length[, position]]])`: resolves with
+/// `{ bytesWritten, buffer }`, writing at the file position without a
+/// `position`
+fn native_handle_write(vm: &mut VM, args: Vec<JsValue>) -> JsValue {
+    let Some((_, _, file)) = this_file(vm) else {
+        return closed(vm, "write");
+    };
+    let data = match data_arg(vm, args.first()) {
+        Ok(data) => data,
+        Err(message) => return rejected(vm, "TypeError", message),
+    };
+    let (data, position) = if let Some(JsValue::String(_)) = args.first() {
+        (data, position_arg(args.get(1)))
+    } else {
+        let offset = args.get(1).map_or(0.0, to_integer);
+        let length = match args.get(2) {
+            None | Some(JsValue::Undefined) => data.len() as f64 - offset,
+            Some(length) => to_integer(length),
+        };
+        if offset < 0.0 || length < 0.0 || offset + length > data.len() as f64 {
+            let message = "The value of \"offset\" or \"length\" is out of range".to_string();
+            return rejected(vm, "RangeError", message);
+        }
+        let start = offset as usize;
+        let data = data[start..start + length as usize].to_vec();
+        (data, position_arg(args.get(3)))
+    };
+    let buffer = args.first().cloned().unwrap_or(JsValue::Undefined);
+    spawn(
+        vm,
+        move || {
+            let mut file = file.lock().unwrap();
+            match position {
+                Some(position) => file.write_all_at(&data, position),
+                None => file.write_all(&data),
+            }
+            .map_err(handle_error("write"))?;
+            Ok(data.len())
+        },
+        move |vm, written| {
+            let bytes_written = JsValue::Number(written as f64);
+            object(
+                vm,
+                None,
+                vec![("bytesWritten", bytes_written), ("buffer", buffer)],
+            )
+        },
+    )
+}
+
+/// `handle.writeFile(data)`: write all of `data` at the file position
+fn native_handle_write_file(vm: &mut VM, args: Vec<JsValue>) -> JsValue {
+    let Some((_, _, file)) = this_file(vm) else {
+        return closed(vm, "write");
+    };
+    let data = match data_arg(vm, args.first()) {
+        Ok(data) => data,
+        Err(message) => return rejected(vm, "TypeError", message),
+    };
+    spawn(
+        vm,
+        move || {
+            file.lock()
+                .unwrap()
+                .write_all(&data)
+                .map_err(handle_error("write"))
+        },
+        |_, ()| JsValue::Undefined,
+    )
+}
+
+/// `handle.stat()`
+fn native_handle_stat(vm: &mut VM, _args: Vec<JsValue>) -> JsValue {
+    let Some((_, _, file)) = this_file(vm) else {
+        return closed(vm, "fstat");
+    };
+    spawn(
+        vm,
+        move || {
+            let metadata = file.lock().unwrap().metadata();
+            metadata
+                .map(|metadata| stat_fields(&metadata))
+                .map_err(handle_error("fstat"))
+        },
+        stats,
+    )
+}
+
+/// `handle.close()`. Calls still running keep the file open until they
+/// finish; the handle's `fd` becomes -1.
+fn native_handle_close(vm: &mut VM, _args: Vec<JsValue>) -> JsValue {
+    if let Some((ptr, fd, _)) = this_file(vm) {
+        vm.open_files.remove(&fd);
+        set(vm, ptr, "fd", JsValue::Number(-1.0));
+    }
+    JsValue::Promise(Promise::with_value(JsValue::Undefined))
+}
+
+/// The file type bits of the `Stats` object a method is called on
+fn this_file_type(vm: &VM) -> u32 {
+    match vm.call_stack.last().map(|frame| &frame.this_context) {
+        Some(JsValue::Object(ptr)) => match vm.get_prop_with_proto_chain(*ptr, "mode") {
+            JsValue::Number(mode) => mode as u32 & S_IFMT,
+            _ => 0,
+        },
+        _ => 0,
+    }
+}
+
+fn native_stats_is_file(vm: &mut VM, _args: Vec<JsValue>) -> JsValue {
+    JsValue::Boolean(this_file_type(vm) == S_IFREG)
+}
+
+fn native_stats_is_directory(vm: &mut VM, _args: Vec<JsValue>) -> JsValue {
+    JsValue::Boolean(this_file_type(vm) == S_IFDIR)
+}
+
+fn native_stats_is_symbolic_link(vm: &mut VM, _args: Vec<JsValue>) -> JsValue {
+    JsValue::Boolean(this_file_type(vm) == S_IFLNK)
+}
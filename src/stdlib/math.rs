@@ -0,0 +1,63 @@
+//! The `Math` global for the VM, backed by `runtime::math`
+
+use crate::runtime::math::MathFn;
+use crate::vm::VM;
+use crate::vm::value::{JsValue, NativeFn};
+
+use super::to_number;
+
+fn call(func: MathFn, args: &[JsValue]) -> JsValue {
+    let numbers: Vec<f64> = args.iter().map(to_number).collect();
+    JsValue::Number(func.apply(&numbers))
+}
+
+macro_rules! math_natives {
+    ($($native:ident => $f:ident,)*) => {
+        $(
+            fn $native(_vm: &mut VM, args: Vec<JsValue>) -> JsValue {
+                call(MathFn::$f, &args)
+            }
+        )*
+
+        /// A native for every `Math` function
+        pub const MATH_NATIVES: &[(MathFn, NativeFn)] = &[$((MathFn::$f, $native),)*];
+    };
+}
+
+math_natives! {
+    native_math_abs => Abs,
+    native_math_acos => Acos,
+    native_math_acosh => Acosh,
+    native_math_asin => Asin,
+    native_math_asinh => Asinh,
+    native_math_atan => Atan,
+    native_math_atan2 => Atan2,
+    native_math_atanh => Atanh,
+    native_math_cbrt => Cbrt,
+    native_math_ceil => Ceil,
+    native_math_clz32 => Clz32,
+    native_math_cos => Cos,
+    native_math_cosh => Cosh,
+    native_math_exp => Exp,
+    native_math_expm1 => Expm1,
+    native_math_floor => Floor,
+    native_math_fround => Fround,
+    native_math_hypot => Hypot,
+    native_math_imul => Imul,
+    native_math_log => Log,
+    native_math_log10 => Log10,
+    native_math_log1p => Log1p,
+    native_math_log2 => Log2,
+    native_math_max => Max,
+    native_math_min => Min,
+    native_math_pow => Pow,
+    native_math_random => Random,
+    native_math_round => Round,
+    native_math_sign => Sign,
+    native_math_sin => Sin,
+    native_math_sinh => Sinh,
+    native_math_sqrt => Sqrt,
+    native_math_tan => Tan,
+    native_math_tanh => Tanh,
+    native_math_trunc => Trunc,
+}
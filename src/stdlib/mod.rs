@@ -3,53 +3,198 @@
 //! Contains only essential primitives needed by the language:
 //! - console.log / console.error (debugging)
 //! - ByteStream (binary serialization for bootstrap compiler)
+//! - Math (shared with native code through `runtime::math`)
+//! - Number, parseInt and parseFloat (shared through `runtime::number`)
+//! - Object and Reflect (property descriptors and prototypes)
+//! - The Array constructor
+//! - Map, Set, WeakMap and WeakSet
+//! - Date and performance.now (clocks shared through `runtime::time`)
+//! - Timers and queueMicrotask (on the VM event loop)
+//! - Error and its subclasses (also thrown by the VM itself)
+//! - ArrayBuffer, typed arrays and DataView
+//! - TextEncoder, TextDecoder and Unicode normalization
+//! - fs.promises (on worker threads, settled by the VM event loop)
+//! - Promise.resolve and Promise.reject
 //!
-//! Full standard library functionality (fs, path, json, math, date, etc.)
+//! Full standard library functionality (fs, path, json, etc.)
 //! will be provided by Rolls packages in the future.
 
+pub mod array;
+pub mod collections;
+pub mod date;
+pub mod error;
+pub mod fs;
+mod math;
+pub mod number;
+pub mod object;
+pub mod promise;
+pub mod text;
+pub mod timers;
+pub mod typed_array;
+
+pub use math::MATH_NATIVES;
+
+use crate::runtime::number::{string_to_number, to_string as number_to_string};
+use crate::runtime::utf16::JsString;
 use crate::vm::VM;
-use crate::vm::value::{HeapData, HeapObject, JsValue};
+use crate::vm::property;
+use crate::vm::value::{HeapData, HeapObject, JsValue, PromiseState};
+
+/// ES ToNumber of a primitive; objects and functions are `NaN`
+pub fn to_number(value: &JsValue) -> f64 {
+    match value {
+        JsValue::Number(n) => *n,
+        JsValue::Boolean(b) => *b as u8 as f64,
+        JsValue::Null => 0.0,
+        JsValue::String(s) => string_to_number(s),
+        _ => f64::NAN,
+    }
+}
+
+/// ES ToBoolean
+pub fn is_truthy(value: &JsValue) -> bool {
+    match value {
+        JsValue::Boolean(b) => *b,
+        JsValue::Number(n) => *n != 0.0 && !n.is_nan(),
+        JsValue::String(s) => !s.is_empty(),
+        JsValue::Null | JsValue::Undefined => false,
+        _ => true,
+    }
+}
+
+/// ES ToIntegerOrInfinity
+pub fn to_integer(value: &JsValue) -> f64 {
+    let n = to_number(value);
+    if n.is_nan() { 0.0 } else { n.trunc() }
+}
 
 // ============================================================================
 // Console Functions
 // ============================================================================
 
-pub fn native_log(_vm: &mut VM, args: Vec<JsValue>) -> JsValue {
-    for arg in args {
-        match arg {
-            JsValue::String(s) => print!("{}", s),
-            JsValue::Number(n) => print!("{}", n),
-            JsValue::Boolean(b) => print!("{}", b),
-            JsValue::Null => print!("null"),
-            JsValue::Undefined => print!("undefined"),
-            JsValue::Object(ptr) => print!("Object({})", ptr),
-            JsValue::Function { address, env: _ } => print!("Function({})", address),
-            JsValue::NativeFunction(idx) => print!("NativeFunction({})", idx),
-            JsValue::Promise(_) => print!("Promise"),
-            JsValue::Accessor(_, _) => print!("Accessor"),
-        }
-    }
-    println!();
+pub fn native_log(vm: &mut VM, args: Vec<JsValue>) -> JsValue {
+    let line = format_log_args(vm, &args) + "\n";
+    vm.write_console("stdout", &line);
+    JsValue::Undefined
+}
+
+pub fn native_error(vm: &mut VM, args: Vec<JsValue>) -> JsValue {
+    let line = format_log_args(vm, &args) + "\n";
+    vm.write_console("stderr", &line);
     JsValue::Undefined
 }
 
-pub fn native_error(_vm: &mut VM, args: Vec<JsValue>) -> JsValue {
-    for arg in args {
-        match arg {
-            JsValue::String(s) => eprint!("{}", s),
-            JsValue::Number(n) => eprint!("{}", n),
-            JsValue::Boolean(b) => eprint!("{}", b),
-            JsValue::Null => eprint!("null"),
-            JsValue::Undefined => eprint!("undefined"),
-            JsValue::Object(ptr) => eprint!("Object({})", ptr),
-            JsValue::Function { address, env: _ } => eprint!("Function({})", address),
-            JsValue::NativeFunction(idx) => eprint!("NativeFunction({})", idx),
-            JsValue::Promise(_) => eprint!("Promise"),
-            JsValue::Accessor(_, _) => eprint!("Accessor"),
+/// Nesting depth after which objects are abbreviated as `[Object]` / `[Array]`
+const INSPECT_MAX_DEPTH: usize = 2;
+
+/// Format console.log arguments: strings are printed raw, everything else
+/// goes through `inspect_value`, and arguments are separated by spaces.
+pub fn format_log_args(vm: &VM, args: &[JsValue]) -> String {
+    args.iter()
+        .map(|arg| match arg {
+            JsValue::String(s) => s.to_string(),
+            other => inspect_value(vm, other),
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// Render a value the way console.log shows it (strings are quoted).
+pub fn inspect_value(vm: &VM, value: &JsValue) -> String {
+    inspect_value_at(vm, value, 0)
+}
+
+fn inspect_value_at(vm: &VM, value: &JsValue, depth: usize) -> String {
+    match value {
+        JsValue::String(s) => format!("'{}'", s.replace('\'', "\\'")),
+        // console.log shows the sign of -0, which String() drops
+        JsValue::Number(n) if *n == 0.0 && n.is_sign_negative() => "-0".to_string(),
+        JsValue::Number(n) => number_to_string(*n),
+        JsValue::Boolean(b) => b.to_string(),
+        JsValue::Null => "null".to_string(),
+        JsValue::Undefined => "undefined".to_string(),
+        JsValue::Function { .. } => "[Function]".to_string(),
+        JsValue::NativeFunction(_) => "[Function: native]".to_string(),
+        JsValue::Accessor(Some(_), None) => "[Getter]".to_string(),
+        JsValue::Accessor(None, Some(_)) => "[Setter]".to_string(),
+        JsValue::Accessor(_, _) => "[Getter/Setter]".to_string(),
+        JsValue::Promise(p) => match p.get_state() {
+            PromiseState::Pending => "Promise { <pending> }".to_string(),
+            PromiseState::Fulfilled => format!(
+                "Promise {{ {} }}",
+                inspect_value_at(vm, &p.get_value().unwrap_or(JsValue::Undefined), depth + 1)
+            ),
+            PromiseState::Rejected => format!(
+                "Promise {{ <rejected> {} }}",
+                inspect_value_at(vm, &p.get_value().unwrap_or(JsValue::Undefined), depth + 1)
+            ),
+        },
+        JsValue::Object(ptr) => {
+            let Some(HeapObject { data }) = vm.heap.get(*ptr) else {
+                return "[Object]".to_string();
+            };
+            match data {
+                HeapData::Array(arr) => {
+                    if arr.is_empty() {
+                        "[]".to_string()
+                    } else if depth > INSPECT_MAX_DEPTH {
+                        "[Array]".to_string()
+                    } else {
+                        let items: Vec<String> = arr
+                            .iter()
+                            .map(|v| inspect_value_at(vm, v, depth + 1))
+                            .collect();
+                        format!("[ {} ]", items.join(", "))
+                    }
+                }
+                HeapData::Object(props) if error::is_error(vm, *ptr) => {
+                    let shown = error::stack(vm, *ptr)
+                        .map(str::to_string)
+                        .unwrap_or_else(|| error::to_string(vm, *ptr));
+                    match props.get("cause") {
+                        Some(cause) if depth <= INSPECT_MAX_DEPTH => format!(
+                            "{} {{ [cause]: {} }}",
+                            shown,
+                            inspect_value_at(vm, cause, depth + 1)
+                        ),
+                        _ => shown,
+                    }
+                }
+                HeapData::Object(props) => {
+                    // Internal bookkeeping (__proto__, __type__, getter:/setter: slots)
+                    // and non-enumerable properties are not shown
+                    let keys = property::own_enumerable_keys(vm, *ptr);
+                    if keys.is_empty() {
+                        "{}".to_string()
+                    } else if depth > INSPECT_MAX_DEPTH {
+                        "[Object]".to_string()
+                    } else {
+                        let items: Vec<String> = keys
+                            .iter()
+                            .map(|k| {
+                                format!("{}: {}", k, inspect_value_at(vm, &props[k], depth + 1))
+                            })
+                            .collect();
+                        format!("{{ {} }}", items.join(", "))
+                    }
+                }
+                HeapData::ByteStream(bytes) => format!("ByteStream({})", bytes.len()),
+                HeapData::Date(t) => {
+                    date::to_iso_string(*t).unwrap_or_else(|| "Invalid Date".to_string())
+                }
+                HeapData::Map(_)
+                | HeapData::Set(_)
+                | HeapData::WeakMap(_)
+                | HeapData::WeakSet(_)
+                | HeapData::CollectionIterator { .. } => {
+                    collections::inspect(vm, data, |v| inspect_value_at(vm, v, depth + 1))
+                }
+                HeapData::ArrayBuffer(_)
+                | HeapData::TypedArray { .. }
+                | HeapData::DataView { .. } => typed_array::inspect(vm, data),
+            }
         }
     }
-    eprintln!();
-    JsValue::Undefined
 }
 
 // ============================================================================
@@ -58,7 +203,7 @@ pub fn native_error(_vm: &mut VM, args: Vec<JsValue>) -> JsValue {
 
 pub fn native_require(vm: &mut VM, args: Vec<JsValue>) -> JsValue {
     if let Some(JsValue::String(module_name)) = args.first() {
-        if let Some(module) = vm.modules.get(module_name) {
+        if let Some(module) = vm.modules.get(module_name.as_str()) {
             return module.clone();
         } else {
             eprintln!("Module '{}' not found", module_name);
@@ -71,10 +216,32 @@ pub fn native_require(vm: &mut VM, args: Vec<JsValue>) -> JsValue {
 // File I/O (minimal - needed for bootstrap compiler output)
 // ============================================================================
 
-pub fn native_read_file(_vm: &mut VM, args: Vec<JsValue>) -> JsValue {
+/// Whether `readFileSync` options ask for bytes: a `null` encoding, or an
+/// options object without one. With no options the file is read as text.
+fn reads_bytes(vm: &VM, options: Option<&JsValue>) -> bool {
+    match options {
+        Some(JsValue::Null) => true,
+        Some(JsValue::Object(ptr)) => match vm.heap.get(*ptr) {
+            Some(HeapObject {
+                data: HeapData::Object(props),
+            }) => !matches!(props.get("encoding"), Some(JsValue::String(_))),
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// `fs.readFileSync(path[, options])`: a string, or a `Uint8Array` when
+/// the encoding is `null`
+pub fn native_read_file(vm: &mut VM, args: Vec<JsValue>) -> JsValue {
     if let Some(JsValue::String(filename)) = args.first() {
-        match std::fs::read_to_string(filename) {
-            Ok(contents) => JsValue::String(contents),
+        let contents = if reads_bytes(vm, args.get(1)) {
+            std::fs::read(filename).map(|bytes| typed_array::new_uint8_array(vm, bytes))
+        } else {
+            std::fs::read_to_string(filename).map(|s| JsValue::String(s.into()))
+        };
+        match contents {
+            Ok(contents) => contents,
             Err(e) => {
                 eprintln!("Error reading file '{}': {}", filename, e);
                 JsValue::Undefined
@@ -85,10 +252,15 @@ pub fn native_read_file(_vm: &mut VM, args: Vec<JsValue>) -> JsValue {
     }
 }
 
-pub fn native_write_file(_vm: &mut VM, args: Vec<JsValue>) -> JsValue {
-    if let (Some(JsValue::String(filename)), Some(JsValue::String(contents))) =
-        (args.first(), args.get(1))
-    {
+/// `fs.writeFileSync(path, data)` with a string, `ArrayBuffer`, typed array
+/// or `DataView`
+pub fn native_write_file(vm: &mut VM, args: Vec<JsValue>) -> JsValue {
+    let contents = match args.get(1) {
+        Some(JsValue::String(contents)) => Some(contents.as_bytes().to_vec()),
+        Some(data) => typed_array::bytes(vm, data),
+        None => None,
+    };
+    if let (Some(JsValue::String(filename)), Some(contents)) = (args.first(), contents) {
         match std::fs::write(filename, contents) {
             Ok(()) => JsValue::Boolean(true),
             Err(e) => {
@@ -150,7 +322,7 @@ pub fn native_readdir_sync(vm: &mut VM, args: Vec<JsValue>) -> JsValue {
                 let mut files: Vec<JsValue> = Vec::new();
                 for entry in entries.flatten() {
                     if let Some(name) = entry.file_name().to_str() {
-                        files.push(JsValue::String(name.to_string()));
+                        files.push(JsValue::String(name.into()));
                     }
                 }
                 let arr_ptr = vm.heap.len();
@@ -403,12 +575,13 @@ pub fn native_byte_stream_to_array(vm: &mut VM, args: Vec<JsValue>) -> JsValue {
 /// String constructor - converts any value to a string
 pub fn native_string_constructor(vm: &mut VM, args: Vec<JsValue>) -> JsValue {
     if args.is_empty() {
-        return JsValue::String(String::new());
+        return JsValue::String(String::new().into());
     }
     let value = &args[0];
     let result = match value {
-        JsValue::String(s) => s.clone(),
-        JsValue::Number(n) => n.to_string(),
+        // Kept as is, lone surrogates and all
+        JsValue::String(s) => return JsValue::String(s.clone()),
+        JsValue::Number(n) => number_to_string(*n),
         JsValue::Boolean(b) => b.to_string(),
         JsValue::Null => "null".to_string(),
         JsValue::Undefined => "undefined".to_string(),
@@ -419,8 +592,8 @@ pub fn native_string_constructor(vm: &mut VM, args: Vec<JsValue>) -> JsValue {
                         let parts: Vec<String> = arr
                             .iter()
                             .map(|v| match v {
-                                JsValue::String(s) => s.clone(),
-                                JsValue::Number(n) => n.to_string(),
+                                JsValue::String(s) => s.to_string(),
+                                JsValue::Number(n) => number_to_string(*n),
                                 JsValue::Boolean(b) => b.to_string(),
                                 JsValue::Null => "null".to_string(),
                                 JsValue::Undefined => "".to_string(),
@@ -429,10 +602,23 @@ pub fn native_string_constructor(vm: &mut VM, args: Vec<JsValue>) -> JsValue {
                             .collect();
                         parts.join(",")
                     }
+                    HeapData::Object(_) if error::is_error(vm, *ptr) => error::to_string(vm, *ptr),
                     HeapData::Object(_) => "[object Object]".to_string(),
                     HeapData::ByteStream(_) => "[object ByteStream]".to_string(),
                     HeapData::Map(_) => "[object Map]".to_string(),
                     HeapData::Set(_) => "[object Set]".to_string(),
+                    HeapData::WeakMap(_) => "[object WeakMap]".to_string(),
+                    HeapData::WeakSet(_) => "[object WeakSet]".to_string(),
+                    HeapData::CollectionIterator { collection, .. } => {
+                        match vm.heap.get(*collection).map(|obj| &obj.data) {
+                            Some(HeapData::Map(_)) => "[object Map Iterator]".to_string(),
+                            _ => "[object Set Iterator]".to_string(),
+                        }
+                    }
+                    HeapData::Date(t) => date::to_string(*t),
+                    data @ (HeapData::ArrayBuffer(_)
+                    | HeapData::TypedArray { .. }
+                    | HeapData::DataView { .. }) => typed_array::to_string(vm, data),
                 }
             } else {
                 "[object Object]".to_string()
@@ -443,22 +629,45 @@ pub fn native_string_constructor(vm: &mut VM, args: Vec<JsValue>) -> JsValue {
         JsValue::Promise(_) => "[object Promise]".to_string(),
         JsValue::Accessor(_, _) => "".to_string(),
     };
-    JsValue::String(result)
+    JsValue::String(result.into())
 }
 
 pub fn native_string_from_char_code(_vm: &mut VM, args: Vec<JsValue>) -> JsValue {
-    let mut result = String::new();
-
-    for arg in args {
-        if let JsValue::Number(code) = arg {
-            let code_u32 = code as u32;
-            if let Some(ch) = char::from_u32(code_u32) {
-                result.push(ch);
+    // Each argument is a UTF-16 code unit, so surrogate pairs combine
+    let units: Vec<u16> = args
+        .iter()
+        .map(|arg| {
+            let n = to_number(arg);
+            if n.is_finite() {
+                n.trunc().rem_euclid(65536.0) as u16
+            } else {
+                0
             }
+        })
+        .collect();
+    JsValue::String(JsString::from_units(&units))
+}
+
+/// String.fromCodePoint - creates a string from code points
+pub fn native_string_from_code_point(vm: &mut VM, args: Vec<JsValue>) -> JsValue {
+    let mut units = Vec::with_capacity(args.len());
+    for arg in &args {
+        let n = to_number(arg);
+        if n.fract() != 0.0 || !(0.0..=0x10FFFF as f64).contains(&n) {
+            let shown = match native_string_constructor(vm, vec![JsValue::Number(n)]) {
+                JsValue::String(s) => s.to_string(),
+                _ => String::new(),
+            };
+            return vm.throw_from_native("RangeError", format!("Invalid code point {}", shown));
+        }
+        let mut buf = [0; 2];
+        match char::from_u32(n as u32) {
+            Some(c) => units.extend_from_slice(c.encode_utf16(&mut buf)),
+            // A surrogate, which pairs with its neighbour if it can
+            None => units.push(n as u16),
         }
     }
-
-    JsValue::String(result)
+    JsValue::String(JsString::from_units(&units))
 }
 
 // ============================================================================
@@ -489,7 +698,7 @@ fn json_stringify_value(vm: &VM, value: &JsValue, indent: usize, pretty: bool) -
             } else if n.is_infinite() {
                 "null".to_string()
             } else {
-                n.to_string()
+                number_to_string(*n)
             }
         }
         JsValue::String(s) => {
@@ -535,22 +744,22 @@ fn json_stringify_value(vm: &VM, value: &JsValue, indent: usize, pretty: bool) -
                         }
                     }
                     HeapData::Object(props) => {
-                        if props.is_empty() {
+                        let keys = property::own_enumerable_keys(vm, *ptr);
+                        if keys.is_empty() {
                             "{}".to_string()
                         } else {
-                            let mut items: Vec<String> = props
+                            let items: Vec<String> = keys
                                 .iter()
-                                .map(|(k, v)| {
+                                .map(|k| {
                                     format!(
                                         "{}\"{}\":{}{}",
                                         next_indent,
                                         k,
                                         space,
-                                        json_stringify_value(vm, v, indent + 1, pretty)
+                                        json_stringify_value(vm, &props[k], indent + 1, pretty)
                                     )
                                 })
                                 .collect();
-                            items.sort(); // Sort for consistent output
                             format!(
                                 "{{{}{}{}{}}}",
                                 newline,
@@ -560,6 +769,40 @@ fn json_stringify_value(vm: &VM, value: &JsValue, indent: usize, pretty: bool) -
                             )
                         }
                     }
+                    // Date.prototype.toJSON
+                    HeapData::Date(t) => match date::to_iso_string(*t) {
+                        Some(iso) => format!("\"{}\"", iso),
+                        None => "null".to_string(),
+                    },
+                    // A typed array is an object with index keys
+                    HeapData::TypedArray { .. } => {
+                        let items: Vec<String> = typed_array::elements(vm, data)
+                            .into_iter()
+                            .enumerate()
+                            .map(|(i, n)| {
+                                let value = json_stringify_value(vm, &JsValue::Number(n), 0, false);
+                                format!("{}\"{}\":{}{}", next_indent, i, space, value)
+                            })
+                            .collect();
+                        if items.is_empty() {
+                            "{}".to_string()
+                        } else {
+                            format!(
+                                "{{{}{}{}{}}}",
+                                newline,
+                                items.join(&format!(",{}", newline)),
+                                newline,
+                                indent_str
+                            )
+                        }
+                    }
+                    // Buffers and collections keep their contents out of own keys
+                    HeapData::ArrayBuffer(_)
+                    | HeapData::DataView { .. }
+                    | HeapData::Map(_)
+                    | HeapData::Set(_)
+                    | HeapData::WeakMap(_)
+                    | HeapData::WeakSet(_) => "{}".to_string(),
                     _ => "null".to_string(),
                 }
             } else {
@@ -581,7 +824,7 @@ pub fn native_json_stringify(vm: &mut VM, args: Vec<JsValue>) -> JsValue {
             _ => None,
         });
         let pretty = indent.is_some();
-        JsValue::String(json_stringify_value(vm, value, 0, pretty))
+        JsValue::String(json_stringify_value(vm, value, 0, pretty).into())
     } else {
         JsValue::Undefined
     }
@@ -601,7 +844,7 @@ pub fn native_json_parse(_vm: &mut VM, _args: Vec<JsValue>) -> JsValue {
 pub fn native_getenv(_vm: &mut VM, args: Vec<JsValue>) -> JsValue {
     if let Some(JsValue::String(name)) = args.first() {
         match std::env::var(name) {
-            Ok(value) => JsValue::String(value),
+            Ok(value) => JsValue::String(value.into()),
             Err(_) => JsValue::Undefined,
         }
     } else {
@@ -627,7 +870,7 @@ pub fn native_setenv(_vm: &mut VM, args: Vec<JsValue>) -> JsValue {
 /// Get current working directory
 pub fn native_cwd(_vm: &mut VM, _args: Vec<JsValue>) -> JsValue {
     match std::env::current_dir() {
-        Ok(path) => JsValue::String(path.to_string_lossy().to_string()),
+        Ok(path) => JsValue::String(path.to_string_lossy().as_ref().into()),
         Err(_) => JsValue::Undefined,
     }
 }
@@ -674,7 +917,7 @@ pub fn native_exec(vm: &mut VM, args: Vec<JsValue>) -> JsValue {
     {
         for item in arr {
             if let JsValue::String(s) = item {
-                cmd_args.push(s.clone());
+                cmd_args.push(s.to_string());
             }
         }
     }
@@ -691,8 +934,8 @@ pub fn native_exec(vm: &mut VM, args: Vec<JsValue>) -> JsValue {
             // Create result object
             let mut response = std::collections::HashMap::new();
             response.insert("exitCode".to_string(), JsValue::Number(exit_code as f64));
-            response.insert("stdout".to_string(), JsValue::String(stdout));
-            response.insert("stderr".to_string(), JsValue::String(stderr));
+            response.insert("stdout".to_string(), JsValue::String(stdout.into()));
+            response.insert("stderr".to_string(), JsValue::String(stderr.into()));
 
             let response_ptr = vm.heap.len();
             vm.heap.push(HeapObject {
@@ -707,8 +950,8 @@ pub fn native_exec(vm: &mut VM, args: Vec<JsValue>) -> JsValue {
 fn create_exec_error(vm: &mut VM, message: &str) -> JsValue {
     let mut response = std::collections::HashMap::new();
     response.insert("exitCode".to_string(), JsValue::Number(-1.0));
-    response.insert("stdout".to_string(), JsValue::String("".to_string()));
-    response.insert("stderr".to_string(), JsValue::String(message.to_string()));
+    response.insert("stdout".to_string(), JsValue::String("".into()));
+    response.insert("stderr".to_string(), JsValue::String(message.into()));
 
     let response_ptr = vm.heap.len();
     vm.heap.push(HeapObject {
@@ -745,7 +988,7 @@ pub fn native_fetch(vm: &mut VM, args: Vec<JsValue>) -> JsValue {
             method = m.to_uppercase();
         }
         if let Some(JsValue::String(b)) = opts.get("body") {
-            body = Some(b.clone());
+            body = Some(b.to_string());
         }
         if let Some(JsValue::Object(hdrs_ptr)) = opts.get("headers")
             && let Some(HeapObject {
@@ -754,7 +997,7 @@ pub fn native_fetch(vm: &mut VM, args: Vec<JsValue>) -> JsValue {
         {
             for (k, v) in hdrs {
                 if let JsValue::String(val) = v {
-                    headers.push((k.clone(), val.clone()));
+                    headers.push((k.clone(), val.to_string()));
                 }
             }
         }
@@ -792,7 +1035,7 @@ pub fn native_fetch(vm: &mut VM, args: Vec<JsValue>) -> JsValue {
             let mut resp_headers = std::collections::HashMap::new();
             for name in resp.headers_names() {
                 if let Some(value) = resp.header(&name) {
-                    resp_headers.insert(name.to_lowercase(), JsValue::String(value.to_string()));
+                    resp_headers.insert(name.to_lowercase(), JsValue::String(value.into()));
                 }
             }
 
@@ -807,7 +1050,7 @@ pub fn native_fetch(vm: &mut VM, args: Vec<JsValue>) -> JsValue {
             let mut resp_headers = std::collections::HashMap::new();
             for name in resp.headers_names() {
                 if let Some(value) = resp.header(&name) {
-                    resp_headers.insert(name.to_lowercase(), JsValue::String(value.to_string()));
+                    resp_headers.insert(name.to_lowercase(), JsValue::String(value.into()));
                 }
             }
             let body_text = resp.into_string().unwrap_or_default();
@@ -836,14 +1079,14 @@ fn create_fetch_response(
     response.insert("status".to_string(), JsValue::Number(status as f64));
     response.insert(
         "statusText".to_string(),
-        JsValue::String(status_text.to_string()),
+        JsValue::String(status_text.into()),
     );
     response.insert(
         "ok".to_string(),
         JsValue::Boolean((200..300).contains(&status)),
     );
     response.insert("headers".to_string(), JsValue::Object(headers_ptr));
-    response.insert("body".to_string(), JsValue::String(body.to_string()));
+    response.insert("body".to_string(), JsValue::String(body.into()));
     response.insert("error".to_string(), JsValue::Undefined);
 
     let response_ptr = vm.heap.len();
@@ -856,10 +1099,10 @@ fn create_fetch_response(
 fn create_fetch_error(vm: &mut VM, message: &str) -> JsValue {
     let mut response = std::collections::HashMap::new();
     response.insert("status".to_string(), JsValue::Number(0.0));
-    response.insert("statusText".to_string(), JsValue::String("".to_string()));
+    response.insert("statusText".to_string(), JsValue::String("".into()));
     response.insert("ok".to_string(), JsValue::Boolean(false));
-    response.insert("body".to_string(), JsValue::String("".to_string()));
-    response.insert("error".to_string(), JsValue::String(message.to_string()));
+    response.insert("body".to_string(), JsValue::String("".into()));
+    response.insert("error".to_string(), JsValue::String(message.into()));
 
     let response_ptr = vm.heap.len();
     vm.heap.push(HeapObject {
@@ -880,7 +1123,7 @@ pub fn native_stdin_read_line(_vm: &mut VM, _args: Vec<JsValue>) -> JsValue {
         Ok(0) => JsValue::Null, // EOF
         Ok(_) => {
             let trimmed = line.trim_end_matches('\n').trim_end_matches('\r');
-            JsValue::String(trimmed.to_string())
+            JsValue::String(trimmed.into())
         }
         Err(_) => JsValue::Null,
     }
@@ -896,55 +1139,23 @@ pub fn native_stdin_read_bytes(_vm: &mut VM, args: Vec<JsValue>) -> JsValue {
     let mut buf = vec![0u8; n];
     use std::io::Read;
     match std::io::stdin().read_exact(&mut buf) {
-        Ok(()) => JsValue::String(String::from_utf8_lossy(&buf).to_string()),
+        Ok(()) => JsValue::String(String::from_utf8_lossy(&buf).as_ref().into()),
         Err(_) => JsValue::Null,
     }
 }
 
 /// Write string to stdout WITHOUT trailing newline
-pub fn native_stdout_write(_vm: &mut VM, args: Vec<JsValue>) -> JsValue {
-    use std::io::Write;
+pub fn native_stdout_write(vm: &mut VM, args: Vec<JsValue>) -> JsValue {
     if let Some(val) = args.first() {
         let s = match val {
-            JsValue::String(s) => s.clone(),
-            JsValue::Number(n) => n.to_string(),
+            JsValue::String(s) => s.to_string(),
+            JsValue::Number(n) => number_to_string(*n),
             JsValue::Boolean(b) => b.to_string(),
             JsValue::Null => "null".to_string(),
             JsValue::Undefined => "undefined".to_string(),
             _ => String::new(),
         };
-        let _ = std::io::stdout().write_all(s.as_bytes());
-        let _ = std::io::stdout().flush();
+        vm.write_console("stdout", &s);
     }
     JsValue::Undefined
 }
-
-// ============================================================================
-// Object Functions
-// ============================================================================
-
-/// Object.keys(obj) - Returns an array of the object's own enumerable property names
-pub fn native_object_keys(vm: &mut VM, args: Vec<JsValue>) -> JsValue {
-    if let Some(JsValue::Object(ptr)) = args.first()
-        && let Some(HeapObject { data }) = vm.heap.get(*ptr)
-    {
-        let keys: Vec<JsValue> = match data {
-            HeapData::Object(props) => props.keys().map(|k| JsValue::String(k.clone())).collect(),
-            HeapData::Array(arr) => (0..arr.len())
-                .map(|i| JsValue::String(i.to_string()))
-                .collect(),
-            _ => Vec::new(),
-        };
-        let arr_ptr = vm.heap.len();
-        vm.heap.push(HeapObject {
-            data: HeapData::Array(keys),
-        });
-        return JsValue::Object(arr_ptr);
-    }
-    // Return empty array for non-objects
-    let arr_ptr = vm.heap.len();
-    vm.heap.push(HeapObject {
-        data: HeapData::Array(Vec::new()),
-    });
-    JsValue::Object(arr_ptr)
-}
@@ -0,0 +1,203 @@
+//! The `Number` global, the number parsing globals and `Number.prototype`
+//! methods for the VM, backed by `runtime::number`
+//!
+//! Numbers are primitives without a prototype object, so the VM calls
+//! [`call_method`] for `n.toFixed(2)` and the like.
+
+use crate::runtime::number::{self, MAX_DIGITS, MAX_SAFE_INTEGER};
+use crate::vm::VM;
+use crate::vm::value::{JsValue, NativeFn};
+
+use super::typed_array::Thrown;
+use super::{date, native_string_constructor, to_integer};
+
+/// `Number` value properties
+pub const CONSTANTS: &[(&str, f64)] = &[
+    ("EPSILON", f64::EPSILON),
+    ("MAX_SAFE_INTEGER", MAX_SAFE_INTEGER),
+    ("MIN_SAFE_INTEGER", -MAX_SAFE_INTEGER),
+    ("MAX_VALUE", f64::MAX),
+    // The smallest subnormal, where Rust's MIN_POSITIVE is the smallest normal
+    ("MIN_VALUE", 5e-324),
+    ("POSITIVE_INFINITY", f64::INFINITY),
+    ("NEGATIVE_INFINITY", f64::NEG_INFINITY),
+    ("NaN", f64::NAN),
+];
+
+/// Natives of the `Number` namespace
+pub const NUMBER_NATIVES: [(&str, NativeFn); 6] = [
+    ("isInteger", native_number_is_integer),
+    ("isSafeInteger", native_number_is_safe_integer),
+    ("isFinite", native_number_is_finite),
+    ("isNaN", native_number_is_nan),
+    ("parseFloat", native_parse_float),
+    ("parseInt", native_parse_int),
+];
+
+/// Global functions, which convert their argument to a number first. The
+/// global `parseFloat` and `parseInt` are the `Number` ones.
+pub const GLOBAL_NATIVES: [(&str, NativeFn); 2] =
+    [("isFinite", native_is_finite), ("isNaN", native_is_nan)];
+
+/// ES ToNumber: a date is its time value, and other objects convert
+/// through their string form
+pub fn to_number(vm: &mut VM, value: &JsValue) -> f64 {
+    if let Some(t) = date::time_value(vm, value) {
+        return t;
+    }
+    match value {
+        JsValue::Object(_) => match native_string_constructor(vm, vec![value.clone()]) {
+            JsValue::String(s) => number::string_to_number(&s),
+            _ => f64::NAN,
+        },
+        _ => super::to_number(value),
+    }
+}
+
+/// The argument as a string, as the parsing functions read it
+fn to_string_arg(vm: &mut VM, args: &[JsValue]) -> String {
+    match args.first() {
+        Some(JsValue::String(s)) => s.to_string(),
+        Some(value) => match native_string_constructor(vm, vec![value.clone()]) {
+            JsValue::String(s) => s.to_string(),
+            _ => String::new(),
+        },
+        None => "undefined".to_string(),
+    }
+}
+
+/// The argument if it is a number, without conversion
+fn number_arg(args: &[JsValue]) -> Option<f64> {
+    match args.first() {
+        Some(JsValue::Number(n)) => Some(*n),
+        _ => None,
+    }
+}
+
+fn is_integer(n: f64) -> bool {
+    n.is_finite() && n.trunc() == n
+}
+
+/// `Number(value)`: the value converted to a number, `0` without one
+pub fn native_number_constructor(vm: &mut VM, args: Vec<JsValue>) -> JsValue {
+    JsValue::Number(match args.first() {
+        Some(value) => to_number(vm, value),
+        None => 0.0,
+    })
+}
+
+fn native_number_is_integer(_vm: &mut VM, args: Vec<JsValue>) -> JsValue {
+    JsValue::Boolean(number_arg(&args).is_some_and(is_integer))
+}
+
+fn native_number_is_safe_integer(_vm: &mut VM, args: Vec<JsValue>) -> JsValue {
+    let safe = |n: f64| is_integer(n) && n.abs() <= MAX_SAFE_INTEGER;
+    JsValue::Boolean(number_arg(&args).is_some_and(safe))
+}
+
+fn native_number_is_finite(_vm: &mut VM, args: Vec<JsValue>) -> JsValue {
+    JsValue::Boolean(number_arg(&args).is_some_and(f64::is_finite))
+}
+
+fn native_number_is_nan(_vm: &mut VM, args: Vec<JsValue>) -> JsValue {
+    JsValue::Boolean(number_arg(&args).is_some_and(f64::is_nan))
+}
+
+fn native_is_finite(vm: &mut VM, args: Vec<JsValue>) -> JsValue {
+    let n = args.first().map_or(f64::NAN, |value| to_number(vm, value));
+    JsValue::Boolean(n.is_finite())
+}
+
+fn native_is_nan(vm: &mut VM, args: Vec<JsValue>) -> JsValue {
+    let n = args.first().map_or(f64::NAN, |value| to_number(vm, value));
+    JsValue::Boolean(n.is_nan())
+}
+
+fn native_parse_float(vm: &mut VM, args: Vec<JsValue>) -> JsValue {
+    JsValue::Number(number::parse_float(&to_string_arg(vm, &args)))
+}
+
+fn native_parse_int(vm: &mut VM, args: Vec<JsValue>) -> JsValue {
+    let s = to_string_arg(vm, &args);
+    // ToInt32 of the radix; NaN and Infinity mean 0
+    let radix = args.get(1).map_or(0.0, |radix| to_number(vm, radix));
+    let radix = if radix.is_finite() {
+        radix.trunc().rem_euclid(2f64.powi(32)) as u32 as i32
+    } else {
+        0
+    };
+    JsValue::Number(number::parse_int(&s, radix))
+}
+
+/// An integer argument in `range`, or the RangeError `message`
+fn digits_arg(
+    arg: Option<&JsValue>,
+    range: std::ops::RangeInclusive<f64>,
+    message: &str,
+) -> Result<usize, Thrown> {
+    let n = arg.map_or(0.0, to_integer);
+    if range.contains(&n) {
+        Ok(n as usize)
+    } else {
+        Err(("RangeError", message.to_string()))
+    }
+}
+
+/// Call `Number.prototype` method `name` on `n`
+pub fn call_method(n: f64, name: &str, args: &[JsValue]) -> Result<JsValue, Thrown> {
+    let max = MAX_DIGITS as f64;
+    let result = match name {
+        "toString" => {
+            let radix = match args.first() {
+                None | Some(JsValue::Undefined) => 10,
+                radix => digits_arg(
+                    radix,
+                    2.0..=36.0,
+                    "toString() radix argument must be between 2 and 36",
+                )?,
+            };
+            number::to_string_radix(n, radix as u32)
+        }
+        "toLocaleString" => number::to_string(n),
+        "toFixed" => {
+            let digits = digits_arg(
+                args.first(),
+                0.0..=max,
+                "toFixed() digits argument must be between 0 and 100",
+            )?;
+            number::to_fixed(n, digits)
+        }
+        // Non-finite numbers ignore the argument's range
+        "toExponential" | "toPrecision" if !n.is_finite() => number::to_string(n),
+        "toExponential" => {
+            let digits = match args.first() {
+                None | Some(JsValue::Undefined) => None,
+                digits => Some(digits_arg(
+                    digits,
+                    0.0..=max,
+                    "toExponential() argument must be between 0 and 100",
+                )?),
+            };
+            number::to_exponential(n, digits)
+        }
+        "toPrecision" => match args.first() {
+            None | Some(JsValue::Undefined) => number::to_string(n),
+            precision => {
+                let precision = digits_arg(
+                    precision,
+                    1.0..=max,
+                    "toPrecision() argument must be between 1 and 100",
+                )?;
+                number::to_precision(n, precision)
+            }
+        },
+        "valueOf" => return Ok(JsValue::Number(n)),
+        _ => {
+            return Err((
+                "TypeError",
+                format!("{}.{} is not a function", number::to_string(n), name),
+            ));
+        }
+    };
+    Ok(JsValue::String(result.into()))
+}
@@ -0,0 +1,720 @@
+//! `Object` and `Reflect`
+//!
+//! Both are namespaces of natives over [`crate::vm::property`], which holds
+//! the lookup and attribute rules. `Object.defineProperty` stores accessors
+//! as `JsValue::Accessor` values and records attributes other than the
+//! defaults in `VM::property_attributes`. The `Object` functions throw where
+//! their `Reflect` counterparts return `false`. Objects without a
+//! `__proto__` link have a `null` prototype, and every object is extensible
+//! until it is frozen, so there is no `Object.preventExtensions` or
+//! `Reflect.preventExtensions`, and no `Reflect.construct` yet.
+
+use std::collections::HashMap;
+
+use crate::runtime::number;
+use crate::vm::VM;
+use crate::vm::property::{self, Attributes};
+use crate::vm::value::{HeapData, HeapObject, JsValue, NativeFn};
+
+use super::collections::{self, iterable_values};
+use super::{error, inspect_value, is_truthy, native_string_constructor};
+
+/// Natives of the `Object` namespace
+pub const OBJECT_NATIVES: [(&str, NativeFn); 14] = [
+    ("keys", |vm, args| native(vm, args, keys)),
+    ("values", |vm, args| native(vm, args, values)),
+    ("entries", |vm, args| native(vm, args, entries)),
+    ("assign", |vm, args| native(vm, args, assign)),
+    ("freeze", |vm, args| native(vm, args, freeze)),
+    ("isFrozen", |vm, args| native(vm, args, is_frozen)),
+    ("create", |vm, args| native(vm, args, create)),
+    ("getPrototypeOf", |vm, args| {
+        native(vm, args, get_prototype_of)
+    }),
+    ("setPrototypeOf", |vm, args| {
+        native(vm, args, set_prototype_of)
+    }),
+    ("defineProperty", |vm, args| {
+        native(vm, args, define_property_or_throw)
+    }),
+    ("defineProperties", |vm, args| {
+        native(vm, args, define_properties_or_throw)
+    }),
+    ("getOwnPropertyNames", |vm, args| {
+        native(vm, args, get_own_property_names)
+    }),
+    ("getOwnPropertyDescriptor", |vm, args| {
+        native(vm, args, get_own_property_descriptor)
+    }),
+    ("fromEntries", |vm, args| native(vm, args, from_entries)),
+];
+
+/// Natives of the `Reflect` namespace
+pub const REFLECT_NATIVES: [(&str, NativeFn); 11] = [
+    ("apply", |vm, args| native(vm, args, apply)),
+    ("defineProperty", |vm, args| {
+        native(vm, args, define_property_or_false)
+    }),
+    ("deleteProperty", |vm, args| {
+        native(vm, args, delete_property)
+    }),
+    ("get", |vm, args| native(vm, args, get)),
+    ("set", |vm, args| native(vm, args, set)),
+    ("getOwnPropertyDescriptor", |vm, args| {
+        native(vm, args, own_property_descriptor)
+    }),
+    ("getPrototypeOf", |vm, args| native(vm, args, prototype_of)),
+    ("setPrototypeOf", |vm, args| {
+        native(vm, args, set_prototype_or_false)
+    }),
+    ("has", |vm, args| native(vm, args, has)),
+    ("ownKeys", |vm, args| native(vm, args, own_keys)),
+    ("isExtensible", |vm, args| native(vm, args, is_extensible)),
+];
+
+/// A property descriptor, with the fields it leaves out as `None`
+#[derive(Debug, Default)]
+struct Descriptor {
+    value: Option<JsValue>,
+    writable: Option<bool>,
+    get: Option<JsValue>,
+    set: Option<JsValue>,
+    enumerable: Option<bool>,
+    configurable: Option<bool>,
+}
+
+impl Descriptor {
+    fn is_accessor(&self) -> bool {
+        self.get.is_some() || self.set.is_some()
+    }
+
+    fn is_data(&self) -> bool {
+        self.value.is_some() || self.writable.is_some()
+    }
+}
+
+/// Why a property couldn't be defined
+enum DefineError {
+    /// The object is frozen and lacks the property
+    NotExtensible,
+    /// The property isn't configurable and the descriptor changes it
+    NotConfigurable,
+}
+
+fn arg(args: &[JsValue], index: usize) -> JsValue {
+    args.get(index).cloned().unwrap_or(JsValue::Undefined)
+}
+
+/// Run `f` as a native: return its result or throw its exception
+fn native(
+    vm: &mut VM,
+    args: Vec<JsValue>,
+    f: fn(&mut VM, &[JsValue]) -> Result<JsValue, JsValue>,
+) -> JsValue {
+    f(vm, &args).unwrap_or_else(|exception| vm.rethrow_from_native(exception))
+}
+
+fn type_error(vm: &mut VM, message: String) -> JsValue {
+    error::create(vm, "TypeError", message)
+}
+
+fn alloc(vm: &mut VM, data: HeapData) -> JsValue {
+    let ptr = vm.heap.len();
+    vm.heap.push(HeapObject { data });
+    JsValue::Object(ptr)
+}
+
+fn array(vm: &mut VM, items: Vec<JsValue>) -> JsValue {
+    alloc(vm, HeapData::Array(items))
+}
+
+/// ES ToPropertyKey
+fn property_key(vm: &mut VM, key: &JsValue) -> String {
+    match key {
+        JsValue::String(s) => s.to_string(),
+        JsValue::Number(n) => number::to_string(*n),
+        _ => match native_string_constructor(vm, vec![key.clone()]) {
+            JsValue::String(s) => s.to_string(),
+            _ => String::new(),
+        },
+    }
+}
+
+/// The heap object `value` points to, or a TypeError for a primitive
+fn target(vm: &mut VM, value: &JsValue, function: &str) -> Result<usize, JsValue> {
+    match value {
+        JsValue::Object(ptr) => Ok(*ptr),
+        _ => Err(type_error(vm, format!("{} called on non-object", function))),
+    }
+}
+
+/// Check an `Object` argument that must not be null or undefined
+fn require_coercible(vm: &mut VM, value: &JsValue) -> Result<(), JsValue> {
+    match value {
+        JsValue::Null | JsValue::Undefined => Err(type_error(
+            vm,
+            "Cannot convert undefined or null to object".to_string(),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// A prototype argument: an object or null
+fn prototype_arg(vm: &mut VM, value: &JsValue) -> Result<Option<usize>, JsValue> {
+    match value {
+        JsValue::Object(ptr) => Ok(Some(*ptr)),
+        JsValue::Null => Ok(None),
+        _ => {
+            let shown = inspect_value(vm, value);
+            Err(type_error(
+                vm,
+                format!("Object prototype may only be an Object or null: {}", shown),
+            ))
+        }
+    }
+}
+
+/// SameValue, as non-writable properties compare their redefinitions
+fn same_value(a: &JsValue, b: &JsValue) -> bool {
+    match (a, b) {
+        (JsValue::Number(a), JsValue::Number(b)) => {
+            (a.is_nan() && b.is_nan()) || (a == b && a.is_sign_negative() == b.is_sign_negative())
+        }
+        _ => a == b,
+    }
+}
+
+fn is_callable(value: &JsValue) -> bool {
+    matches!(value, JsValue::Function { .. } | JsValue::NativeFunction(_))
+}
+
+/// ES ToPropertyDescriptor
+fn to_descriptor(vm: &mut VM, value: &JsValue) -> Result<Descriptor, JsValue> {
+    let JsValue::Object(ptr) = *value else {
+        let shown = inspect_value(vm, value);
+        return Err(type_error(
+            vm,
+            format!("Property description must be an object: {}", shown),
+        ));
+    };
+    let field = |vm: &mut VM, name: &str| -> Result<Option<JsValue>, JsValue> {
+        if property::has_property(vm, ptr, name) {
+            property::get(vm, ptr, name).map(Some)
+        } else {
+            Ok(None)
+        }
+    };
+    let descriptor = Descriptor {
+        enumerable: field(vm, "enumerable")?.map(|v| is_truthy(&v)),
+        configurable: field(vm, "configurable")?.map(|v| is_truthy(&v)),
+        value: field(vm, "value")?,
+        writable: field(vm, "writable")?.map(|v| is_truthy(&v)),
+        get: field(vm, "get")?,
+        set: field(vm, "set")?,
+    };
+    for (kind, accessor) in [("Getter", &descriptor.get), ("Setter", &descriptor.set)] {
+        if let Some(accessor) = accessor
+            && !is_callable(accessor)
+            && *accessor != JsValue::Undefined
+        {
+            let shown = inspect_value(vm, accessor);
+            return Err(type_error(
+                vm,
+                format!("{} must be a function: {}", kind, shown),
+            ));
+        }
+    }
+    if descriptor.is_accessor() && descriptor.is_data() {
+        return Err(type_error(
+            vm,
+            "Invalid property descriptor. Cannot both specify accessors and a value or writable attribute"
+                .to_string(),
+        ));
+    }
+    Ok(descriptor)
+}
+
+/// ES FromPropertyDescriptor of own property `key`, or undefined without one
+fn from_descriptor(vm: &mut VM, ptr: usize, key: &str) -> JsValue {
+    let Some(slot) = property::own_slot(vm, ptr, key) else {
+        return JsValue::Undefined;
+    };
+    let attributes = property::attributes(vm, ptr, key);
+    let mut props = HashMap::new();
+    match slot {
+        JsValue::Accessor(get, set) => {
+            let unbox =
+                |accessor: Option<Box<JsValue>>| accessor.map_or(JsValue::Undefined, |f| *f);
+            props.insert("get".to_string(), unbox(get));
+            props.insert("set".to_string(), unbox(set));
+        }
+        value => {
+            props.insert("value".to_string(), value);
+            props.insert(
+                "writable".to_string(),
+                JsValue::Boolean(attributes.writable),
+            );
+        }
+    }
+    props.insert(
+        "enumerable".to_string(),
+        JsValue::Boolean(attributes.enumerable),
+    );
+    props.insert(
+        "configurable".to_string(),
+        JsValue::Boolean(attributes.configurable),
+    );
+    alloc(vm, HeapData::Object(props))
+}
+
+/// ES ValidateAndApplyPropertyDescriptor on own property `key`
+fn define_property(
+    vm: &mut VM,
+    ptr: usize,
+    key: &str,
+    descriptor: Descriptor,
+) -> Result<(), DefineError> {
+    let accessor = |f: Option<JsValue>| f.filter(is_callable).map(Box::new);
+    let (slot, attributes) = match property::own_slot(vm, ptr, key) {
+        None => {
+            if !property::is_extensible(vm, ptr) {
+                return Err(DefineError::NotExtensible);
+            }
+            let slot = if descriptor.is_accessor() {
+                JsValue::Accessor(accessor(descriptor.get), accessor(descriptor.set))
+            } else {
+                descriptor.value.unwrap_or(JsValue::Undefined)
+            };
+            let attributes = Attributes {
+                writable: descriptor.writable.unwrap_or(false),
+                enumerable: descriptor.enumerable.unwrap_or(false),
+                configurable: descriptor.configurable.unwrap_or(false),
+            };
+            (slot, attributes)
+        }
+        Some(current) => {
+            let mut attributes = property::attributes(vm, ptr, key);
+            let is_accessor = matches!(current, JsValue::Accessor(..));
+            if !attributes.configurable {
+                let changes_kind = (is_accessor && descriptor.is_data())
+                    || (!is_accessor && descriptor.is_accessor());
+                let changes_accessor = match &current {
+                    JsValue::Accessor(get, set) => {
+                        let differs = |old: &Option<Box<JsValue>>, new: &Option<JsValue>| {
+                            new.as_ref().is_some_and(|new| {
+                                old.as_deref().unwrap_or(&JsValue::Undefined) != new
+                            })
+                        };
+                        differs(get, &descriptor.get) || differs(set, &descriptor.set)
+                    }
+                    value => {
+                        !attributes.writable
+                            && (descriptor.writable == Some(true)
+                                || descriptor
+                                    .value
+                                    .as_ref()
+                                    .is_some_and(|new| !same_value(new, value)))
+                    }
+                };
+                if descriptor.configurable == Some(true)
+                    || descriptor
+                        .enumerable
+                        .is_some_and(|enumerable| enumerable != attributes.enumerable)
+                    || changes_kind
+                    || changes_accessor
+                {
+                    return Err(DefineError::NotConfigurable);
+                }
+            }
+            let slot = match current {
+                JsValue::Accessor(get, set) if !descriptor.is_data() => JsValue::Accessor(
+                    descriptor.get.map_or(get, |f| accessor(Some(f))),
+                    descriptor.set.map_or(set, |f| accessor(Some(f))),
+                ),
+                // An accessor property becomes a data property
+                JsValue::Accessor(..) => {
+                    attributes.writable = false;
+                    descriptor.value.unwrap_or(JsValue::Undefined)
+                }
+                _ if descriptor.is_accessor() => {
+                    JsValue::Accessor(accessor(descriptor.get), accessor(descriptor.set))
+                }
+                value => descriptor.value.unwrap_or(value),
+            };
+            attributes.writable = descriptor.writable.unwrap_or(attributes.writable);
+            attributes.enumerable = descriptor.enumerable.unwrap_or(attributes.enumerable);
+            attributes.configurable = descriptor.configurable.unwrap_or(attributes.configurable);
+            (slot, attributes)
+        }
+    };
+    match vm.heap.get_mut(ptr).map(|obj| &mut obj.data) {
+        Some(HeapData::Object(props)) => {
+            // A class accessor slot is replaced by the property itself
+            props.remove(&format!("getter:{}", key));
+            props.remove(&format!("setter:{}", key));
+            props.insert(key.to_string(), slot);
+        }
+        Some(HeapData::Array(items)) if !matches!(slot, JsValue::Accessor(..)) => {
+            match key.parse::<usize>() {
+                Ok(i) => {
+                    if i >= items.len() {
+                        items.resize(i + 1, JsValue::Undefined);
+                    }
+                    items[i] = slot;
+                }
+                Err(_) => return Err(DefineError::NotConfigurable),
+            }
+        }
+        _ => return Err(DefineError::NotConfigurable),
+    }
+    property::set_attributes(vm, ptr, key, attributes);
+    Ok(())
+}
+
+/// `Object.defineProperty` and `Object.defineProperties`, which throw when
+/// `Reflect.defineProperty` would return false
+fn define_or_throw(
+    vm: &mut VM,
+    ptr: usize,
+    key: &str,
+    descriptor: Descriptor,
+) -> Result<(), JsValue> {
+    match define_property(vm, ptr, key, descriptor) {
+        Ok(()) => Ok(()),
+        Err(DefineError::NotExtensible) => Err(type_error(
+            vm,
+            format!("Cannot define property {}, object is not extensible", key),
+        )),
+        Err(DefineError::NotConfigurable) => {
+            Err(type_error(vm, format!("Cannot redefine property: {}", key)))
+        }
+    }
+}
+
+fn define_properties(vm: &mut VM, ptr: usize, properties: &JsValue) -> Result<(), JsValue> {
+    require_coercible(vm, properties)?;
+    let JsValue::Object(props_ptr) = *properties else {
+        return Ok(());
+    };
+    // Every descriptor is read before any property is defined
+    let mut descriptors = Vec::new();
+    for key in property::own_enumerable_keys(vm, props_ptr) {
+        let value = property::get(vm, props_ptr, &key)?;
+        descriptors.push((key, to_descriptor(vm, &value)?));
+    }
+    for (key, descriptor) in descriptors {
+        define_or_throw(vm, ptr, &key, descriptor)?;
+    }
+    Ok(())
+}
+
+/// ES OrdinarySetPrototypeOf, returning whether the prototype was set
+fn set_prototype(vm: &mut VM, ptr: usize, proto: Option<usize>) -> bool {
+    if property::prototype(vm, ptr) == proto {
+        return true;
+    }
+    if !property::is_extensible(vm, ptr) {
+        return false;
+    }
+    // The new chain must not lead back to the object
+    let mut current = proto;
+    while let Some(link) = current {
+        if link == ptr {
+            return false;
+        }
+        current = property::prototype(vm, link);
+    }
+    let Some(HeapObject {
+        data: HeapData::Object(props),
+    }) = vm.heap.get_mut(ptr)
+    else {
+        return false;
+    };
+    match proto {
+        Some(proto) => props.insert("__proto__".to_string(), JsValue::Object(proto)),
+        None => props.remove("__proto__"),
+    };
+    true
+}
+
+/// The own enumerable `[key, value]` pairs of `value`, calling getters
+fn enumerable_entries(vm: &mut VM, value: &JsValue) -> Result<Vec<(String, JsValue)>, JsValue> {
+    require_coercible(vm, value)?;
+    let JsValue::Object(ptr) = *value else {
+        return Ok(Vec::new());
+    };
+    let mut entries = Vec::new();
+    for key in property::own_enumerable_keys(vm, ptr) {
+        let value = property::get(vm, ptr, &key)?;
+        entries.push((key, value));
+    }
+    Ok(entries)
+}
+
+/// Object.keys(obj) - The object's own enumerable property names
+fn keys(vm: &mut VM, args: &[JsValue]) -> Result<JsValue, JsValue> {
+    let value = arg(args, 0);
+    require_coercible(vm, &value)?;
+    let keys = match value {
+        JsValue::Object(ptr) => property::own_enumerable_keys(vm, ptr)
+            .into_iter()
+            .map(|s| JsValue::String(s.into()))
+            .collect(),
+        _ => Vec::new(),
+    };
+    Ok(array(vm, keys))
+}
+
+fn values(vm: &mut VM, args: &[JsValue]) -> Result<JsValue, JsValue> {
+    let entries = enumerable_entries(vm, &arg(args, 0))?;
+    let values = entries.into_iter().map(|(_, value)| value).collect();
+    Ok(array(vm, values))
+}
+
+fn entries(vm: &mut VM, args: &[JsValue]) -> Result<JsValue, JsValue> {
+    let entries = enumerable_entries(vm, &arg(args, 0))?;
+    let pairs = entries
+        .into_iter()
+        .map(|(key, value)| array(vm, vec![JsValue::String(key.into()), value]))
+        .collect();
+    Ok(array(vm, pairs))
+}
+
+/// Object.assign(target, ...sources) - Copies own enumerable properties
+/// through the target's setters
+fn assign(vm: &mut VM, args: &[JsValue]) -> Result<JsValue, JsValue> {
+    let target = arg(args, 0);
+    require_coercible(vm, &target)?;
+    let JsValue::Object(ptr) = target else {
+        return Ok(target);
+    };
+    for source in args.iter().skip(1) {
+        if matches!(source, JsValue::Null | JsValue::Undefined) {
+            continue;
+        }
+        for (key, value) in enumerable_entries(vm, source)? {
+            if !property::set(vm, ptr, &key, value)? {
+                let message = property::check_write(vm, ptr, &key)
+                    .err()
+                    .unwrap_or_else(|| format!("Cannot assign to property '{}' of object", key));
+                return Err(type_error(vm, message));
+            }
+        }
+    }
+    Ok(target)
+}
+
+/// Object.freeze(obj) - Makes every property read-only and permanent
+fn freeze(vm: &mut VM, args: &[JsValue]) -> Result<JsValue, JsValue> {
+    let value = arg(args, 0);
+    if let JsValue::Object(ptr) = value {
+        vm.frozen_objects.insert(ptr);
+    }
+    Ok(value)
+}
+
+/// Object.isFrozen(obj) - Primitives count as frozen
+fn is_frozen(vm: &mut VM, args: &[JsValue]) -> Result<JsValue, JsValue> {
+    Ok(JsValue::Boolean(match arg(args, 0) {
+        JsValue::Object(ptr) => vm.frozen_objects.contains(&ptr),
+        _ => true,
+    }))
+}
+
+/// Object.create(proto, descriptors)
+fn create(vm: &mut VM, args: &[JsValue]) -> Result<JsValue, JsValue> {
+    let proto = prototype_arg(vm, &arg(args, 0))?;
+    let mut props = HashMap::new();
+    if let Some(proto) = proto {
+        props.insert("__proto__".to_string(), JsValue::Object(proto));
+    }
+    let object = alloc(vm, HeapData::Object(props));
+    if let (JsValue::Object(ptr), Some(properties)) = (&object, args.get(1))
+        && *properties != JsValue::Undefined
+    {
+        define_properties(vm, *ptr, properties)?;
+    }
+    Ok(object)
+}
+
+fn get_prototype_of(vm: &mut VM, args: &[JsValue]) -> Result<JsValue, JsValue> {
+    let value = arg(args, 0);
+    require_coercible(vm, &value)?;
+    Ok(match value {
+        JsValue::Object(ptr) => property::prototype(vm, ptr).map_or(JsValue::Null, JsValue::Object),
+        _ => JsValue::Null,
+    })
+}
+
+fn set_prototype_of(vm: &mut VM, args: &[JsValue]) -> Result<JsValue, JsValue> {
+    let value = arg(args, 0);
+    require_coercible(vm, &value)?;
+    let proto = prototype_arg(vm, &arg(args, 1))?;
+    let JsValue::Object(ptr) = value else {
+        return Ok(value);
+    };
+    if set_prototype(vm, ptr, proto) {
+        return Ok(value);
+    }
+    let message = if property::is_extensible(vm, ptr) {
+        "Cyclic __proto__ value".to_string()
+    } else {
+        "#<Object> is not extensible".to_string()
+    };
+    Err(type_error(vm, message))
+}
+
+fn define_property_or_throw(vm: &mut VM, args: &[JsValue]) -> Result<JsValue, JsValue> {
+    let ptr = target(vm, &arg(args, 0), "Object.defineProperty")?;
+    let key = property_key(vm, &arg(args, 1));
+    let descriptor = to_descriptor(vm, &arg(args, 2))?;
+    define_or_throw(vm, ptr, &key, descriptor)?;
+    Ok(JsValue::Object(ptr))
+}
+
+fn define_properties_or_throw(vm: &mut VM, args: &[JsValue]) -> Result<JsValue, JsValue> {
+    let ptr = target(vm, &arg(args, 0), "Object.defineProperties")?;
+    define_properties(vm, ptr, &arg(args, 1))?;
+    Ok(JsValue::Object(ptr))
+}
+
+/// Object.getOwnPropertyNames(obj) - Own keys, enumerable or not
+fn get_own_property_names(vm: &mut VM, args: &[JsValue]) -> Result<JsValue, JsValue> {
+    let value = arg(args, 0);
+    require_coercible(vm, &value)?;
+    let keys = match value {
+        JsValue::Object(ptr) => property::own_keys(vm, ptr)
+            .into_iter()
+            .map(|s| JsValue::String(s.into()))
+            .collect(),
+        _ => Vec::new(),
+    };
+    Ok(array(vm, keys))
+}
+
+fn get_own_property_descriptor(vm: &mut VM, args: &[JsValue]) -> Result<JsValue, JsValue> {
+    let value = arg(args, 0);
+    require_coercible(vm, &value)?;
+    let JsValue::Object(ptr) = value else {
+        return Ok(JsValue::Undefined);
+    };
+    let key = property_key(vm, &arg(args, 1));
+    Ok(from_descriptor(vm, ptr, &key))
+}
+
+/// Object.fromEntries(iterable) - An object of `[key, value]` pairs
+fn from_entries(vm: &mut VM, args: &[JsValue]) -> Result<JsValue, JsValue> {
+    let iterable = arg(args, 0);
+    let Some(items) = iterable_values(vm, &iterable) else {
+        let (name, message) = collections::not_iterable(vm, &iterable);
+        return Err(error::create(vm, name, message));
+    };
+    let mut props = HashMap::new();
+    for item in &items {
+        let (key, value) = collections::entry(vm, item)
+            .map_err(|(name, message)| error::create(vm, name, message))?;
+        props.insert(property_key(vm, &key), value);
+    }
+    Ok(alloc(vm, HeapData::Object(props)))
+}
+
+/// Reflect.apply(f, thisArg, args)
+fn apply(vm: &mut VM, args: &[JsValue]) -> Result<JsValue, JsValue> {
+    let call_args = match arg(args, 2) {
+        JsValue::Object(ptr) => match &vm.heap[ptr].data {
+            HeapData::Array(items) => items.clone(),
+            _ => Vec::new(),
+        },
+        _ => {
+            return Err(type_error(
+                vm,
+                "CreateListFromArrayLike called on non-object".to_string(),
+            ));
+        }
+    };
+    vm.call_sync(&arg(args, 0), arg(args, 1), call_args)
+}
+
+/// Reflect.defineProperty(obj, key, descriptor) - Whether it was defined
+fn define_property_or_false(vm: &mut VM, args: &[JsValue]) -> Result<JsValue, JsValue> {
+    let ptr = target(vm, &arg(args, 0), "Reflect.defineProperty")?;
+    let key = property_key(vm, &arg(args, 1));
+    let descriptor = to_descriptor(vm, &arg(args, 2))?;
+    let defined = define_property(vm, ptr, &key, descriptor).is_ok();
+    Ok(JsValue::Boolean(defined))
+}
+
+/// Reflect.deleteProperty(obj, key) - Whether it is gone
+fn delete_property(vm: &mut VM, args: &[JsValue]) -> Result<JsValue, JsValue> {
+    let ptr = target(vm, &arg(args, 0), "Reflect.deleteProperty")?;
+    let key = property_key(vm, &arg(args, 1));
+    if property::check_delete(vm, ptr, &key).is_err() {
+        return Ok(JsValue::Boolean(false));
+    }
+    if let Some(HeapObject {
+        data: HeapData::Object(props),
+    }) = vm.heap.get_mut(ptr)
+    {
+        props.remove(&key);
+        props.remove(&format!("getter:{}", key));
+        props.remove(&format!("setter:{}", key));
+    }
+    if let Some(attributes) = vm.property_attributes.get_mut(&ptr) {
+        attributes.remove(&key);
+    }
+    Ok(JsValue::Boolean(true))
+}
+
+fn get(vm: &mut VM, args: &[JsValue]) -> Result<JsValue, JsValue> {
+    let ptr = target(vm, &arg(args, 0), "Reflect.get")?;
+    let key = property_key(vm, &arg(args, 1));
+    property::get(vm, ptr, &key)
+}
+
+/// Reflect.set(obj, key, value) - Whether it was set
+fn set(vm: &mut VM, args: &[JsValue]) -> Result<JsValue, JsValue> {
+    let ptr = target(vm, &arg(args, 0), "Reflect.set")?;
+    let key = property_key(vm, &arg(args, 1));
+    property::set(vm, ptr, &key, arg(args, 2)).map(JsValue::Boolean)
+}
+
+fn own_property_descriptor(vm: &mut VM, args: &[JsValue]) -> Result<JsValue, JsValue> {
+    let ptr = target(vm, &arg(args, 0), "Reflect.getOwnPropertyDescriptor")?;
+    let key = property_key(vm, &arg(args, 1));
+    Ok(from_descriptor(vm, ptr, &key))
+}
+
+fn prototype_of(vm: &mut VM, args: &[JsValue]) -> Result<JsValue, JsValue> {
+    let ptr = target(vm, &arg(args, 0), "Reflect.getPrototypeOf")?;
+    Ok(property::prototype(vm, ptr).map_or(JsValue::Null, JsValue::Object))
+}
+
+/// Reflect.setPrototypeOf(obj, proto) - Whether it was set
+fn set_prototype_or_false(vm: &mut VM, args: &[JsValue]) -> Result<JsValue, JsValue> {
+    let ptr = target(vm, &arg(args, 0), "Reflect.setPrototypeOf")?;
+    let proto = prototype_arg(vm, &arg(args, 1))?;
+    Ok(JsValue::Boolean(set_prototype(vm, ptr, proto)))
+}
+
+/// Reflect.has(obj, key) - The `in` operator
+fn has(vm: &mut VM, args: &[JsValue]) -> Result<JsValue, JsValue> {
+    let ptr = target(vm, &arg(args, 0), "Reflect.has")?;
+    let key = property_key(vm, &arg(args, 1));
+    Ok(JsValue::Boolean(property::has_property(vm, ptr, &key)))
+}
+
+/// Reflect.ownKeys(obj) - Own keys, enumerable or not
+fn own_keys(vm: &mut VM, args: &[JsValue]) -> Result<JsValue, JsValue> {
+    let ptr = target(vm, &arg(args, 0), "Reflect.ownKeys")?;
+    let keys = property::own_keys(vm, ptr)
+        .into_iter()
+        .map(|s| JsValue::String(s.into()))
+        .collect();
+    Ok(array(vm, keys))
+}
+
+/// Reflect.isExtensible(obj) - False once frozen
+fn is_extensible(vm: &mut VM, args: &[JsValue]) -> Result<JsValue, JsValue> {
+    let ptr = target(vm, &arg(args, 0), "Reflect.isExtensible")?;
+    Ok(JsValue::Boolean(property::is_extensible(vm, ptr)))
+}
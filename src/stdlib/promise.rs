@@ -0,0 +1,23 @@
+//! The `Promise` global
+//!
+//! Only the static `Promise.resolve` and `Promise.reject`, which the compiler
+//! also uses to wrap the result of an async function. `then` and `catch` are
+//! methods of promise values, run by the VM as reactions on its task queue.
+
+use crate::vm::VM;
+use crate::vm::value::{JsValue, Promise};
+
+/// `Promise.resolve(value)`: a promise is returned as is
+pub fn native_resolve(_vm: &mut VM, args: Vec<JsValue>) -> JsValue {
+    match args.into_iter().next() {
+        Some(JsValue::Promise(promise)) => JsValue::Promise(promise),
+        value => JsValue::Promise(Promise::with_value(value.unwrap_or(JsValue::Undefined))),
+    }
+}
+
+/// `Promise.reject(reason)`
+pub fn native_reject(_vm: &mut VM, args: Vec<JsValue>) -> JsValue {
+    let promise = Promise::new();
+    promise.set_value(args.into_iter().next().unwrap_or(JsValue::Undefined), false);
+    JsValue::Promise(promise)
+}
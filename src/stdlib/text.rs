@@ -0,0 +1,356 @@
+//! `TextEncoder`, `TextDecoder` and Unicode normalization
+//!
+//! The two classes are native like the error classes: the constructor fills
+//! in `this` and the prototype carries the methods. A decoder reads UTF-8 or
+//! UTF-16LE with the WHATWG Encoding rules: malformed input decodes to
+//! U+FFFD, or throws with `fatal`, and a leading BOM is dropped unless
+//! `ignoreBOM`. With `{ stream: true }` an incomplete sequence at the end of
+//! a chunk is held back for the next call.
+
+use std::collections::HashMap;
+
+use icu_normalizer::{ComposingNormalizerBorrowed, DecomposingNormalizerBorrowed};
+
+use crate::vm::VM;
+use crate::vm::value::{HeapData, HeapObject, JsValue};
+
+use super::{is_truthy, native_string_constructor, typed_array};
+
+/// An encoding a `TextDecoder` reads
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Encoding {
+    Utf8,
+    Utf16Le,
+}
+
+impl Encoding {
+    /// The encoding a WHATWG label names, ignoring case and whitespace
+    fn from_label(label: &str) -> Option<Encoding> {
+        match label.trim().to_ascii_lowercase().as_str() {
+            "unicode-1-1-utf-8" | "unicode11utf8" | "unicode20utf8" | "utf-8" | "utf8"
+            | "x-unicode20utf8" => Some(Encoding::Utf8),
+            "csunicode" | "iso-10646-ucs-2" | "ucs-2" | "unicode" | "unicodefeff" | "utf-16"
+            | "utf-16le" => Some(Encoding::Utf16Le),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Encoding::Utf8 => "utf-8",
+            Encoding::Utf16Le => "utf-16le",
+        }
+    }
+
+    /// Decode `bytes`, returning the text and how many bytes it used; the
+    /// rest is an incomplete sequence held back while streaming. `None` is
+    /// malformed input when `fatal`.
+    fn decode(self, bytes: &[u8], fatal: bool, stream: bool) -> Option<(String, usize)> {
+        match self {
+            Encoding::Utf8 => decode_utf8(bytes, fatal, stream),
+            Encoding::Utf16Le => decode_utf16le(bytes, fatal, stream),
+        }
+    }
+}
+
+fn decode_utf8(bytes: &[u8], fatal: bool, stream: bool) -> Option<(String, usize)> {
+    let mut text = String::with_capacity(bytes.len());
+    let mut rest = bytes;
+    loop {
+        let error = match std::str::from_utf8(rest) {
+            Ok(valid) => {
+                text.push_str(valid);
+                return Some((text, bytes.len()));
+            }
+            Err(error) => error,
+        };
+        let (valid, invalid) = rest.split_at(error.valid_up_to());
+        text.push_str(std::str::from_utf8(valid).ok()?);
+        match error.error_len() {
+            // The input ends inside a sequence
+            None if stream => return Some((text, bytes.len() - invalid.len())),
+            _ if fatal => return None,
+            // Each maximal invalid subpart is one U+FFFD, as `from_utf8_lossy` has it
+            Some(len) => {
+                text.push(char::REPLACEMENT_CHARACTER);
+                rest = &invalid[len..];
+            }
+            None => {
+                text.push(char::REPLACEMENT_CHARACTER);
+                return Some((text, bytes.len()));
+            }
+        }
+    }
+}
+
+fn decode_utf16le(bytes: &[u8], fatal: bool, stream: bool) -> Option<(String, usize)> {
+    let mut used = bytes.len() & !1;
+    let mut units: Vec<u16> = bytes[..used]
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .collect();
+    if stream {
+        // A high surrogate may be completed by the next chunk
+        if let Some(0xD800..=0xDBFF) = units.last() {
+            units.pop();
+            used -= 2;
+        }
+    }
+    let mut text = String::with_capacity(units.len());
+    for c in char::decode_utf16(units) {
+        match c {
+            Ok(c) => text.push(c),
+            Err(_) if fatal => return None,
+            Err(_) => text.push(char::REPLACEMENT_CHARACTER),
+        }
+    }
+    if !stream && used < bytes.len() {
+        // A lone byte at the end
+        if fatal {
+            return None;
+        }
+        text.push(char::REPLACEMENT_CHARACTER);
+        used = bytes.len();
+    }
+    Some((text, used))
+}
+
+/// `String.prototype.normalize(form)`; `None` for an unknown form
+pub fn normalize(s: &str, form: &str) -> Option<String> {
+    let normalized = match form {
+        "NFC" => ComposingNormalizerBorrowed::new_nfc().normalize(s),
+        "NFD" => DecomposingNormalizerBorrowed::new_nfd().normalize(s),
+        "NFKC" => ComposingNormalizerBorrowed::new_nfkc().normalize(s),
+        "NFKD" => DecomposingNormalizerBorrowed::new_nfkd().normalize(s),
+        _ => return None,
+    };
+    Some(normalized.into_owned())
+}
+
+/// The encoder or decoder a method is called on
+fn this(vm: &VM) -> Option<usize> {
+    match vm.call_stack.last().map(|frame| &frame.this_context) {
+        Some(JsValue::Object(ptr)) => Some(*ptr),
+        _ => None,
+    }
+}
+
+fn props(vm: &VM, ptr: usize) -> Option<&HashMap<String, JsValue>> {
+    match vm.heap.get(ptr) {
+        Some(HeapObject {
+            data: HeapData::Object(props),
+        }) => Some(props),
+        _ => None,
+    }
+}
+
+fn set(vm: &mut VM, ptr: usize, key: &str, value: JsValue) {
+    if let Some(HeapObject {
+        data: HeapData::Object(props),
+    }) = vm.heap.get_mut(ptr)
+    {
+        props.insert(key.to_string(), value);
+    }
+}
+
+/// A string argument, `undefined` being empty
+fn string_arg(vm: &mut VM, value: Option<&JsValue>) -> String {
+    match value {
+        None | Some(JsValue::Undefined) => String::new(),
+        Some(JsValue::String(s)) => s.to_string(),
+        Some(value) => match native_string_constructor(vm, vec![value.clone()]) {
+            JsValue::String(s) => s.to_string(),
+            _ => String::new(),
+        },
+    }
+}
+
+/// An option of an options object
+fn option(vm: &VM, options: Option<&JsValue>, key: &str) -> bool {
+    match options {
+        Some(JsValue::Object(ptr)) => is_truthy(&vm.get_prop_with_proto_chain(*ptr, key)),
+        _ => false,
+    }
+}
+
+/// `new TextEncoder()`
+pub fn native_text_encoder_construct(vm: &mut VM, _args: Vec<JsValue>) -> JsValue {
+    let Some(ptr) = this(vm) else {
+        return JsValue::Undefined;
+    };
+    set(vm, ptr, "encoding", JsValue::String("utf-8".into()));
+    JsValue::Object(ptr)
+}
+
+/// `TextEncoder.prototype.encode(input)`: the UTF-8 bytes of `input`
+pub fn native_text_encoder_encode(vm: &mut VM, args: Vec<JsValue>) -> JsValue {
+    let input = string_arg(vm, args.first());
+    typed_array::new_uint8_array(vm, input.into_bytes())
+}
+
+/// `TextEncoder.prototype.encodeInto(source, destination)`: writes the
+/// chars of `source` that fit, returning `{ read, written }` in code units
+/// and bytes
+pub fn native_text_encoder_encode_into(vm: &mut VM, args: Vec<JsValue>) -> JsValue {
+    let source = string_arg(vm, args.first());
+    let destination = args.get(1).cloned().unwrap_or(JsValue::Undefined);
+    let Some(bytes) = typed_array::uint8_array_mut(vm, &destination) else {
+        return vm.throw_from_native(
+            "TypeError",
+            "The \"dest\" argument must be an instance of Uint8Array".to_string(),
+        );
+    };
+    let (mut read, mut written) = (0, 0);
+    for c in source.chars() {
+        let end = written + c.len_utf8();
+        if end > bytes.len() {
+            break;
+        }
+        c.encode_utf8(&mut bytes[written..end]);
+        read += c.len_utf16();
+        written = end;
+    }
+    let mut result = HashMap::new();
+    result.insert("read".to_string(), JsValue::Number(read as f64));
+    result.insert("written".to_string(), JsValue::Number(written as f64));
+    let ptr = vm.heap.len();
+    vm.heap.push(HeapObject {
+        data: HeapData::Object(result),
+    });
+    JsValue::Object(ptr)
+}
+
+/// `new TextDecoder(label, { fatal, ignoreBOM })`
+pub fn native_text_decoder_construct(vm: &mut VM, args: Vec<JsValue>) -> JsValue {
+    let Some(ptr) = this(vm) else {
+        return JsValue::Undefined;
+    };
+    let label = match args.first() {
+        None | Some(JsValue::Undefined) => "utf-8".to_string(),
+        label => string_arg(vm, label),
+    };
+    let Some(encoding) = Encoding::from_label(&label) else {
+        return vm.throw_from_native(
+            "RangeError",
+            format!("The \"{}\" encoding is not supported", label),
+        );
+    };
+    let fatal = option(vm, args.get(1), "fatal");
+    let ignore_bom = option(vm, args.get(1), "ignoreBOM");
+    set(vm, ptr, "encoding", JsValue::String(encoding.name().into()));
+    set(vm, ptr, "fatal", JsValue::Boolean(fatal));
+    set(vm, ptr, "ignoreBOM", JsValue::Boolean(ignore_bom));
+    JsValue::Object(ptr)
+}
+
+/// `TextDecoder.prototype.decode(input, { stream })`
+pub fn native_text_decoder_decode(vm: &mut VM, args: Vec<JsValue>) -> JsValue {
+    let Some(ptr) = this(vm) else {
+        return JsValue::Undefined;
+    };
+    let input = match args.first() {
+        None | Some(JsValue::Undefined) => Vec::new(),
+        Some(input) => match typed_array::bytes(vm, input) {
+            Some(bytes) => bytes,
+            None => {
+                return vm.throw_from_native(
+                    "TypeError",
+                    "The \"input\" argument must be an instance of ArrayBuffer or ArrayBufferView"
+                        .to_string(),
+                );
+            }
+        },
+    };
+    let stream = option(vm, args.get(1), "stream");
+    let Some(props) = props(vm, ptr) else {
+        return JsValue::Undefined;
+    };
+    let encoding = match props.get("encoding") {
+        Some(JsValue::String(name)) => Encoding::from_label(name).unwrap_or(Encoding::Utf8),
+        _ => Encoding::Utf8,
+    };
+    let flag = |key: &str| matches!(props.get(key), Some(JsValue::Boolean(true)));
+    let (fatal, ignore_bom, bom_seen) = (flag("fatal"), flag("ignoreBOM"), flag("__bomSeen__"));
+    // Bytes held back by the last streaming call
+    let mut bytes: Vec<u8> = match props.get("__pending__") {
+        Some(JsValue::Object(pending)) => match vm.heap.get(*pending) {
+            Some(HeapObject {
+                data: HeapData::Array(pending),
+            }) => pending.iter().map(|b| super::to_number(b) as u8).collect(),
+            _ => Vec::new(),
+        },
+        _ => Vec::new(),
+    };
+    bytes.extend(input);
+
+    let decoded = encoding.decode(&bytes, fatal, stream);
+    // A failed or finished decode leaves the decoder as new
+    let (pending, next_bom_seen) = match &decoded {
+        Some((text, used)) if stream => (&bytes[*used..], bom_seen || !text.is_empty()),
+        _ => (&[][..], false),
+    };
+    let pending = pending.iter().map(|&b| JsValue::Number(b as f64)).collect();
+    let pending_ptr = vm.heap.len();
+    vm.heap.push(HeapObject {
+        data: HeapData::Array(pending),
+    });
+    set(vm, ptr, "__pending__", JsValue::Object(pending_ptr));
+    set(vm, ptr, "__bomSeen__", JsValue::Boolean(next_bom_seen));
+    let Some((mut text, _)) = decoded else {
+        return vm.throw_from_native(
+            "TypeError",
+            format!(
+                "The encoded data was not valid for encoding {}",
+                encoding.name()
+            ),
+        );
+    };
+    if !ignore_bom && !bom_seen && text.starts_with('\u{FEFF}') {
+        text.remove(0);
+    }
+    JsValue::String(text.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_utf8_replaces_maximal_subparts() {
+        let decode = |bytes: &[u8]| Encoding::Utf8.decode(bytes, false, false);
+        assert_eq!(decode(b"h\xC3\xA9"), Some(("hé".to_string(), 3)));
+        // A truncated sequence is one U+FFFD, a stray continuation byte another
+        assert_eq!(
+            decode(b"\xF0\x9F\x98a\x80"),
+            Some(("\u{FFFD}a\u{FFFD}".to_string(), 5))
+        );
+        assert_eq!(Encoding::Utf8.decode(b"a\xFF", true, false), None);
+    }
+
+    #[test]
+    fn test_streaming_holds_back_incomplete_sequences() {
+        assert_eq!(
+            Encoding::Utf8.decode(b"a\xF0\x9F", false, true),
+            Some(("a".to_string(), 1))
+        );
+        assert_eq!(
+            Encoding::Utf16Le.decode(&[0x61, 0, 0x3D, 0xD8, 0x00], false, true),
+            Some(("a".to_string(), 2))
+        );
+        assert_eq!(
+            Encoding::Utf16Le.decode(&[0x3D, 0xD8, 0x00, 0xDE, 0x62], false, false),
+            Some(("😀\u{FFFD}".to_string(), 5))
+        );
+    }
+
+    #[test]
+    fn test_labels_and_normalization_forms() {
+        assert_eq!(Encoding::from_label(" UTF8 "), Some(Encoding::Utf8));
+        assert_eq!(Encoding::from_label("utf-16"), Some(Encoding::Utf16Le));
+        assert_eq!(Encoding::from_label("latin1"), None);
+        assert_eq!(normalize("e\u{301}", "NFC").as_deref(), Some("é"));
+        assert_eq!(normalize("é", "NFD").as_deref(), Some("e\u{301}"));
+        assert_eq!(normalize("ﬁ", "NFKC").as_deref(), Some("fi"));
+        assert_eq!(normalize("é", "nfc"), None);
+    }
+}
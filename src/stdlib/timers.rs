@@ -0,0 +1,112 @@
+//! Timers and microtasks for the VM event loop
+//!
+//! `setTimeout`, `setInterval` and `setImmediate` schedule timers that fire
+//! in due order; `queueMicrotask` callbacks share the queue of promise
+//! reactions, which drains completely before the next timer fires. Delays
+//! follow Node: anything outside 1..=2^31-1 ms becomes 1 ms, so
+//! `setImmediate` callbacks run ahead of `setTimeout(f, 0)`.
+
+use std::time::Duration;
+
+use crate::vm::value::JsValue;
+use crate::vm::{Task, VM};
+
+use super::{inspect_value, to_number};
+
+/// Longest timer delay, the largest 32-bit signed millisecond count
+const TIMEOUT_MAX: f64 = 2_147_483_647.0;
+
+/// The callback and trailing arguments of a scheduling call, or `None`
+/// (after throwing a TypeError) if the callback cannot be called
+fn task(vm: &mut VM, name: &str, args: &[JsValue], skip: usize) -> Option<Task> {
+    match args.first() {
+        Some(callback @ (JsValue::Function { .. } | JsValue::NativeFunction(_))) => Some(Task {
+            function_ptr: callback.clone(),
+            args: args.iter().skip(skip).cloned().collect(),
+        }),
+        callback => {
+            let received = inspect_value(vm, callback.unwrap_or(&JsValue::Undefined));
+            vm.throw_from_native(
+                "TypeError",
+                format!(
+                    "{}: callback must be a function, received {}",
+                    name, received
+                ),
+            );
+            None
+        }
+    }
+}
+
+fn delay(value: Option<&JsValue>) -> Duration {
+    let ms = value.map_or(f64::NAN, to_number);
+    let ms = if (1.0..=TIMEOUT_MAX).contains(&ms) {
+        ms.trunc()
+    } else {
+        1.0
+    };
+    Duration::from_millis(ms as u64)
+}
+
+fn schedule(vm: &mut VM, name: &str, args: &[JsValue], repeat: bool) -> JsValue {
+    let Some(task) = task(vm, name, args, 2) else {
+        return JsValue::Undefined;
+    };
+    let delay = delay(args.get(1));
+    let id = vm.schedule_timer(task, delay, repeat.then_some(delay));
+    JsValue::Number(id as f64)
+}
+
+/// `setTimeout(callback, delay, ...args)`
+pub fn native_set_timeout(vm: &mut VM, args: Vec<JsValue>) -> JsValue {
+    schedule(vm, "setTimeout", &args, false)
+}
+
+/// `setInterval(callback, delay, ...args)`
+pub fn native_set_interval(vm: &mut VM, args: Vec<JsValue>) -> JsValue {
+    schedule(vm, "setInterval", &args, true)
+}
+
+/// `setImmediate(callback, ...args)`
+pub fn native_set_immediate(vm: &mut VM, args: Vec<JsValue>) -> JsValue {
+    let Some(task) = task(vm, "setImmediate", &args, 1) else {
+        return JsValue::Undefined;
+    };
+    JsValue::Number(vm.schedule_timer(task, Duration::ZERO, None) as f64)
+}
+
+/// `clearTimeout(id)` and `clearInterval(id)`, which are interchangeable
+pub fn native_clear_timer(vm: &mut VM, args: Vec<JsValue>) -> JsValue {
+    if let Some(JsValue::Number(id)) = args.first()
+        && *id >= 1.0
+        && *id <= u32::MAX as f64
+    {
+        vm.clear_timer(*id as u32);
+    }
+    JsValue::Undefined
+}
+
+/// `queueMicrotask(callback)`
+pub fn native_queue_microtask(vm: &mut VM, args: Vec<JsValue>) -> JsValue {
+    if let Some(task) = task(vm, "queueMicrotask", &args, 1) {
+        vm.queue_microtask(task);
+    }
+    JsValue::Undefined
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delay_clamps_like_node() {
+        let ms = |v: JsValue| delay(Some(&v)).as_millis();
+        assert_eq!(ms(JsValue::Number(25.9)), 25);
+        assert_eq!(ms(JsValue::Number(0.0)), 1);
+        assert_eq!(ms(JsValue::Number(-5.0)), 1);
+        assert_eq!(ms(JsValue::Number(f64::NAN)), 1);
+        assert_eq!(ms(JsValue::Number(3e9)), 1);
+        assert_eq!(ms(JsValue::String("10".into())), 10);
+        assert_eq!(delay(None).as_millis(), 1);
+    }
+}
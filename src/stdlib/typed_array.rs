@@ -0,0 +1,896 @@
+//! `ArrayBuffer`, the typed arrays and `DataView`
+//!
+//! An `ArrayBuffer` owns its bytes; typed arrays and data views are windows
+//! onto one, so a write through any view is seen by all of them. Elements
+//! convert with [`ElementKind`], as native code's typed arrays do. Integer
+//! and in-range keys address elements; other numeric keys read undefined and
+//! ignore writes. Buffers have a fixed length.
+
+use crate::runtime::number;
+use crate::runtime::typed_array::ElementKind;
+use crate::vm::VM;
+use crate::vm::value::{HeapData, HeapObject, JsValue, NativeFn};
+
+use super::{is_truthy, to_integer, to_number};
+
+/// The class and message of an error to throw
+pub type Thrown = (&'static str, String);
+
+/// Longest buffer, in bytes, and longest typed array, in elements
+const MAX_LENGTH: f64 = u32::MAX as f64;
+
+/// Elements shown by `console.log` before `... n more items`, like Node
+const INSPECT_MAX_ITEMS: usize = 100;
+
+/// Bytes shown for an `ArrayBuffer` before `... n more bytes`, like Node
+const INSPECT_MAX_BYTES: usize = 50;
+
+/// Typed array methods, reported as functions by property reads
+const METHODS: [&str; 12] = [
+    "at",
+    "fill",
+    "includes",
+    "indexOf",
+    "join",
+    "lastIndexOf",
+    "reverse",
+    "set",
+    "slice",
+    "sort",
+    "subarray",
+    "toString",
+];
+
+/// `Uint8Array.from(source)`, one per entry of [`ElementKind::ALL`]
+pub const FROM: [NativeFn; 9] = [
+    |vm, args| from(vm, ElementKind::Int8, args),
+    |vm, args| from(vm, ElementKind::Uint8, args),
+    |vm, args| from(vm, ElementKind::Uint8Clamped, args),
+    |vm, args| from(vm, ElementKind::Int16, args),
+    |vm, args| from(vm, ElementKind::Uint16, args),
+    |vm, args| from(vm, ElementKind::Int32, args),
+    |vm, args| from(vm, ElementKind::Uint32, args),
+    |vm, args| from(vm, ElementKind::Float32, args),
+    |vm, args| from(vm, ElementKind::Float64, args),
+];
+
+/// `Uint8Array.of(...items)`, one per entry of [`ElementKind::ALL`]
+pub const OF: [NativeFn; 9] = [
+    |vm, args| of(vm, ElementKind::Int8, args),
+    |vm, args| of(vm, ElementKind::Uint8, args),
+    |vm, args| of(vm, ElementKind::Uint8Clamped, args),
+    |vm, args| of(vm, ElementKind::Int16, args),
+    |vm, args| of(vm, ElementKind::Uint16, args),
+    |vm, args| of(vm, ElementKind::Int32, args),
+    |vm, args| of(vm, ElementKind::Uint32, args),
+    |vm, args| of(vm, ElementKind::Float32, args),
+    |vm, args| of(vm, ElementKind::Float64, args),
+];
+
+/// A typed array: `length` elements of `kind`, `offset` bytes into `buffer`
+#[derive(Debug, Clone, Copy)]
+struct View {
+    kind: ElementKind,
+    buffer: usize,
+    offset: usize,
+    length: usize,
+}
+
+impl View {
+    fn of(vm: &VM, ptr: usize) -> Option<View> {
+        match vm.heap.get(ptr)?.data {
+            HeapData::TypedArray {
+                kind,
+                buffer,
+                offset,
+                length,
+            } => Some(View {
+                kind,
+                buffer,
+                offset,
+                length,
+            }),
+            _ => None,
+        }
+    }
+
+    fn byte_range(self, index: usize) -> std::ops::Range<usize> {
+        let start = self.offset + index * self.kind.size();
+        start..start + self.kind.size()
+    }
+
+    fn get(self, vm: &VM, index: usize) -> f64 {
+        let bytes = buffer_bytes(vm, self.buffer);
+        self.kind.read(&bytes[self.byte_range(index)], true)
+    }
+
+    fn set(self, vm: &mut VM, index: usize, value: f64) {
+        let range = self.byte_range(index);
+        if let Some(bytes) = buffer_bytes_mut(vm, self.buffer) {
+            self.kind.write(&mut bytes[range], value, true);
+        }
+    }
+
+    fn values(self, vm: &VM) -> Vec<f64> {
+        (0..self.length).map(|i| self.get(vm, i)).collect()
+    }
+}
+
+fn buffer_bytes(vm: &VM, ptr: usize) -> &[u8] {
+    match vm.heap.get(ptr) {
+        Some(HeapObject {
+            data: HeapData::ArrayBuffer(bytes),
+        }) => bytes,
+        _ => &[],
+    }
+}
+
+fn buffer_bytes_mut(vm: &mut VM, ptr: usize) -> Option<&mut Vec<u8>> {
+    match vm.heap.get_mut(ptr) {
+        Some(HeapObject {
+            data: HeapData::ArrayBuffer(bytes),
+        }) => Some(bytes),
+        _ => None,
+    }
+}
+
+fn alloc(vm: &mut VM, data: HeapData) -> usize {
+    let ptr = vm.heap.len();
+    vm.heap.push(HeapObject { data });
+    ptr
+}
+
+/// A new typed array over a new buffer holding `bytes`
+fn alloc_typed_array(vm: &mut VM, kind: ElementKind, bytes: Vec<u8>) -> JsValue {
+    let length = bytes.len() / kind.size();
+    let buffer = alloc(vm, HeapData::ArrayBuffer(bytes));
+    JsValue::Object(alloc(
+        vm,
+        HeapData::TypedArray {
+            kind,
+            buffer,
+            offset: 0,
+            length,
+        },
+    ))
+}
+
+/// A new typed array holding `values`, converted to `kind`
+fn typed_array_of(vm: &mut VM, kind: ElementKind, values: &[f64]) -> JsValue {
+    let mut bytes = vec![0; values.len() * kind.size()];
+    for (chunk, value) in bytes.chunks_exact_mut(kind.size()).zip(values) {
+        kind.write(chunk, *value, true);
+    }
+    alloc_typed_array(vm, kind, bytes)
+}
+
+/// A new `Uint8Array`, as `fs.readFileSync` returns for binary reads
+pub fn new_uint8_array(vm: &mut VM, bytes: Vec<u8>) -> JsValue {
+    alloc_typed_array(vm, ElementKind::Uint8, bytes)
+}
+
+/// The elements of a `Uint8Array`, to write in place
+pub fn uint8_array_mut<'a>(vm: &'a mut VM, value: &JsValue) -> Option<&'a mut [u8]> {
+    let JsValue::Object(ptr) = value else {
+        return None;
+    };
+    let view = View::of(vm, *ptr).filter(|view| view.kind == ElementKind::Uint8)?;
+    let bytes = buffer_bytes_mut(vm, view.buffer)?;
+    bytes.get_mut(view.offset..view.offset + view.length)
+}
+
+/// The bytes of an `ArrayBuffer`, typed array or `DataView`
+pub fn bytes(vm: &VM, value: &JsValue) -> Option<Vec<u8>> {
+    let JsValue::Object(ptr) = value else {
+        return None;
+    };
+    match &vm.heap.get(*ptr)?.data {
+        HeapData::ArrayBuffer(bytes) => Some(bytes.clone()),
+        HeapData::TypedArray {
+            kind,
+            buffer,
+            offset,
+            length,
+        } => Some(buffer_bytes(vm, *buffer)[*offset..*offset + length * kind.size()].to_vec()),
+        HeapData::DataView {
+            buffer,
+            offset,
+            length,
+        } => Some(buffer_bytes(vm, *buffer)[*offset..*offset + length].to_vec()),
+        _ => None,
+    }
+}
+
+/// ES ToIndex, limited to [`MAX_LENGTH`]
+fn to_index(value: Option<&JsValue>) -> Option<usize> {
+    let n = match value {
+        None | Some(JsValue::Undefined) => 0.0,
+        Some(value) => to_integer(value),
+    };
+    (0.0..=MAX_LENGTH).contains(&n).then_some(n as usize)
+}
+
+/// A start or end argument of `slice` and friends: negative counts from
+/// `len`, and the result is clamped to `0..=len`
+fn relative_index(value: Option<&JsValue>, len: usize, default: usize) -> usize {
+    let n = match value {
+        None | Some(JsValue::Undefined) => return default,
+        Some(value) => to_integer(value),
+    };
+    let len = len as f64;
+    (if n < 0.0 {
+        (len + n).max(0.0)
+    } else {
+        n.min(len)
+    }) as usize
+}
+
+/// The element index a key names, if it is a number or a string that
+/// spells one: `Some(None)` for numbers that are not valid indices
+fn numeric_key(key: &JsValue) -> Option<Option<usize>> {
+    let n = match key {
+        JsValue::Number(n) => *n,
+        // "-0" is numeric but names no element
+        JsValue::String(s) if s == "-0" => return Some(None),
+        JsValue::String(s) => {
+            let n = to_number(key);
+            if *s != number::to_string(n) {
+                return None;
+            }
+            n
+        }
+        _ => return None,
+    };
+    let valid = n.fract() == 0.0 && (0.0..=MAX_LENGTH).contains(&n);
+    Some(valid.then_some(n as usize))
+}
+
+/// Whether `__type__` names a constructor of this module
+pub fn is_constructor(name: &str) -> bool {
+    matches!(name, "ArrayBuffer" | "DataView") || ElementKind::from_name(name).is_some()
+}
+
+/// `new ArrayBuffer(...)`, `new DataView(...)` or `new Uint8Array(...)` and
+/// the other typed arrays, by constructor name
+pub fn construct(vm: &mut VM, name: &str, args: &[JsValue]) -> Result<JsValue, Thrown> {
+    match name {
+        "ArrayBuffer" => {
+            let length = to_index(args.first())
+                .ok_or(("RangeError", "Invalid array buffer length".to_string()))?;
+            Ok(JsValue::Object(alloc(
+                vm,
+                HeapData::ArrayBuffer(vec![0; length]),
+            )))
+        }
+        "DataView" => construct_data_view(vm, args),
+        _ => match ElementKind::from_name(name) {
+            Some(kind) => construct_typed_array(vm, kind, args),
+            None => Ok(JsValue::Undefined),
+        },
+    }
+}
+
+fn construct_data_view(vm: &mut VM, args: &[JsValue]) -> Result<JsValue, Thrown> {
+    let buffer = match args.first() {
+        Some(JsValue::Object(ptr))
+            if matches!(
+                vm.heap.get(*ptr),
+                Some(HeapObject {
+                    data: HeapData::ArrayBuffer(_)
+                })
+            ) =>
+        {
+            *ptr
+        }
+        _ => {
+            return Err((
+                "TypeError",
+                "First argument to DataView constructor must be an ArrayBuffer".to_string(),
+            ));
+        }
+    };
+    let buffer_len = buffer_bytes(vm, buffer).len();
+    let offset = to_index(args.get(1))
+        .filter(|offset| *offset <= buffer_len)
+        .ok_or_else(|| out_of_bounds_start(args.get(1)))?;
+    let length = match args.get(2) {
+        None | Some(JsValue::Undefined) => buffer_len - offset,
+        Some(arg) => to_index(Some(arg))
+            .filter(|length| offset + length <= buffer_len)
+            .ok_or_else(|| {
+                (
+                    "RangeError",
+                    format!("Invalid DataView length {}", to_integer(arg)),
+                )
+            })?,
+    };
+    Ok(JsValue::Object(alloc(
+        vm,
+        HeapData::DataView {
+            buffer,
+            offset,
+            length,
+        },
+    )))
+}
+
+fn out_of_bounds_start(offset: Option<&JsValue>) -> Thrown {
+    let offset = offset.map_or(0.0, to_integer);
+    (
+        "RangeError",
+        format!(
+            "Start offset {} is outside the bounds of the buffer",
+            offset
+        ),
+    )
+}
+
+fn invalid_length(length: f64) -> Thrown {
+    (
+        "RangeError",
+        format!("Invalid typed array length: {}", length),
+    )
+}
+
+fn construct_typed_array(
+    vm: &mut VM,
+    kind: ElementKind,
+    args: &[JsValue],
+) -> Result<JsValue, Thrown> {
+    let source = match args.first() {
+        Some(JsValue::Object(ptr)) => *ptr,
+        arg => {
+            let length =
+                to_index(arg).ok_or_else(|| invalid_length(arg.map_or(0.0, to_integer)))?;
+            return Ok(alloc_typed_array(vm, kind, vec![0; length * kind.size()]));
+        }
+    };
+    match vm.heap.get(source).map(|obj| &obj.data) {
+        Some(HeapData::ArrayBuffer(bytes)) => {
+            let buffer_len = bytes.len();
+            let size = kind.size();
+            let offset = to_index(args.get(1)).ok_or_else(|| out_of_bounds_start(args.get(1)))?;
+            if offset % size != 0 {
+                return Err((
+                    "RangeError",
+                    format!(
+                        "start offset of {} should be a multiple of {}",
+                        kind.name(),
+                        size
+                    ),
+                ));
+            }
+            let length = match args.get(2) {
+                None | Some(JsValue::Undefined) => {
+                    if buffer_len % size != 0 {
+                        return Err((
+                            "RangeError",
+                            format!(
+                                "byte length of {} should be a multiple of {}",
+                                kind.name(),
+                                size
+                            ),
+                        ));
+                    }
+                    if offset > buffer_len {
+                        return Err(out_of_bounds_start(args.get(1)));
+                    }
+                    (buffer_len - offset) / size
+                }
+                Some(arg) => to_index(Some(arg))
+                    .filter(|length| offset + length * size <= buffer_len)
+                    .ok_or_else(|| invalid_length(to_integer(arg)))?,
+            };
+            Ok(JsValue::Object(alloc(
+                vm,
+                HeapData::TypedArray {
+                    kind,
+                    buffer: source,
+                    offset,
+                    length,
+                },
+            )))
+        }
+        _ => {
+            let values = array_like_values(vm, source);
+            Ok(typed_array_of(vm, kind, &values))
+        }
+    }
+}
+
+/// The numbers in an array or typed array; other objects have none
+fn array_like_values(vm: &VM, ptr: usize) -> Vec<f64> {
+    match vm.heap.get(ptr).map(|obj| &obj.data) {
+        Some(HeapData::Array(items)) => items.iter().map(to_number).collect(),
+        Some(HeapData::TypedArray { .. }) => View::of(vm, ptr).unwrap().values(vm),
+        _ => Vec::new(),
+    }
+}
+
+fn from(vm: &mut VM, kind: ElementKind, args: Vec<JsValue>) -> JsValue {
+    let values = match args.first() {
+        Some(JsValue::Object(ptr)) => array_like_values(vm, *ptr),
+        _ => Vec::new(),
+    };
+    typed_array_of(vm, kind, &values)
+}
+
+fn of(vm: &mut VM, kind: ElementKind, args: Vec<JsValue>) -> JsValue {
+    let values: Vec<f64> = args.iter().map(to_number).collect();
+    typed_array_of(vm, kind, &values)
+}
+
+/// `ArrayBuffer.isView(value)`
+pub fn native_array_buffer_is_view(vm: &mut VM, args: Vec<JsValue>) -> JsValue {
+    let is_view = match args.first() {
+        Some(JsValue::Object(ptr)) => matches!(
+            vm.heap.get(*ptr).map(|obj| &obj.data),
+            Some(HeapData::TypedArray { .. } | HeapData::DataView { .. })
+        ),
+        _ => false,
+    };
+    JsValue::Boolean(is_view)
+}
+
+/// `obj[key]` on a buffer or view; `None` if `ptr` is neither
+pub fn get(vm: &VM, ptr: usize, key: &JsValue) -> Option<JsValue> {
+    let name = match key {
+        JsValue::String(name) => name.as_str(),
+        _ => "",
+    };
+    let value = match &vm.heap.get(ptr)?.data {
+        HeapData::TypedArray {
+            kind,
+            buffer,
+            offset,
+            length,
+        } => {
+            if let Some(index) = numeric_key(key) {
+                return Some(match index {
+                    Some(i) if i < *length => JsValue::Number(View::of(vm, ptr)?.get(vm, i)),
+                    _ => JsValue::Undefined,
+                });
+            }
+            match name {
+                "length" => JsValue::Number(*length as f64),
+                "byteLength" => JsValue::Number((length * kind.size()) as f64),
+                "byteOffset" => JsValue::Number(*offset as f64),
+                "buffer" => JsValue::Object(*buffer),
+                "BYTES_PER_ELEMENT" => JsValue::Number(kind.size() as f64),
+                // Methods are handled by CallMethod
+                _ if METHODS.contains(&name) => JsValue::NativeFunction(0),
+                _ => JsValue::Undefined,
+            }
+        }
+        HeapData::ArrayBuffer(bytes) => match name {
+            "byteLength" => JsValue::Number(bytes.len() as f64),
+            "slice" => JsValue::NativeFunction(0),
+            _ => JsValue::Undefined,
+        },
+        HeapData::DataView {
+            buffer,
+            offset,
+            length,
+        } => match name {
+            "byteLength" => JsValue::Number(*length as f64),
+            "byteOffset" => JsValue::Number(*offset as f64),
+            "buffer" => JsValue::Object(*buffer),
+            _ if data_view_accessor(name).is_some() => JsValue::NativeFunction(0),
+            _ => JsValue::Undefined,
+        },
+        _ => return None,
+    };
+    Some(value)
+}
+
+/// `obj[key] = value` on a buffer or view, returning whether `ptr` is one.
+/// Only in-bounds elements of typed arrays can be written.
+pub fn set(vm: &mut VM, ptr: usize, key: &JsValue, value: &JsValue) -> bool {
+    match vm.heap.get(ptr).map(|obj| &obj.data) {
+        Some(HeapData::TypedArray { .. }) => {
+            let view = View::of(vm, ptr).unwrap();
+            if let Some(Some(i)) = numeric_key(key)
+                && i < view.length
+            {
+                view.set(vm, i, to_number(value));
+            }
+            true
+        }
+        Some(HeapData::ArrayBuffer(_) | HeapData::DataView { .. }) => true,
+        _ => false,
+    }
+}
+
+/// Call a method on the buffer or view at `ptr`; unknown methods return
+/// undefined
+pub fn call_method(
+    vm: &mut VM,
+    ptr: usize,
+    name: &str,
+    args: &[JsValue],
+) -> Result<JsValue, Thrown> {
+    if let Some(view) = View::of(vm, ptr) {
+        return typed_array_method(vm, ptr, view, name, args);
+    }
+    match vm.heap.get(ptr).map(|obj| &obj.data) {
+        Some(HeapData::ArrayBuffer(bytes)) if name == "slice" => {
+            let start = relative_index(args.first(), bytes.len(), 0);
+            let end = relative_index(args.get(1), bytes.len(), bytes.len());
+            let slice = bytes[start..end.max(start)].to_vec();
+            Ok(JsValue::Object(alloc(vm, HeapData::ArrayBuffer(slice))))
+        }
+        Some(&HeapData::DataView {
+            buffer,
+            offset,
+            length,
+        }) => match data_view_accessor(name) {
+            Some((kind, setter)) => {
+                let index = to_index(args.first())
+                    .filter(|index| index + kind.size() <= length)
+                    .ok_or((
+                        "RangeError",
+                        "Offset is outside the bounds of the DataView".to_string(),
+                    ))?;
+                let range = offset + index..offset + index + kind.size();
+                let little_endian = |arg: Option<&JsValue>| arg.is_some_and(is_truthy);
+                if setter {
+                    let value = args.get(1).map_or(f64::NAN, to_number);
+                    if let Some(bytes) = buffer_bytes_mut(vm, buffer) {
+                        kind.write(&mut bytes[range], value, little_endian(args.get(2)));
+                    }
+                    Ok(JsValue::Undefined)
+                } else {
+                    let bytes = &buffer_bytes(vm, buffer)[range];
+                    Ok(JsValue::Number(
+                        kind.read(bytes, little_endian(args.get(1))),
+                    ))
+                }
+            }
+            None => Ok(JsValue::Undefined),
+        },
+        _ => Ok(JsValue::Undefined),
+    }
+}
+
+/// The kind a `DataView` method such as `getInt16` accesses, and whether it
+/// writes
+fn data_view_accessor(name: &str) -> Option<(ElementKind, bool)> {
+    let (setter, view_name) = match name.split_at_checked(3)? {
+        ("get", rest) => (false, rest),
+        ("set", rest) => (true, rest),
+        _ => return None,
+    };
+    ElementKind::ALL
+        .into_iter()
+        .filter(|kind| *kind != ElementKind::Uint8Clamped)
+        .find(|kind| kind.view_name() == view_name)
+        .map(|kind| (kind, setter))
+}
+
+fn typed_array_method(
+    vm: &mut VM,
+    ptr: usize,
+    view: View,
+    name: &str,
+    args: &[JsValue],
+) -> Result<JsValue, Thrown> {
+    let len = view.length;
+    let result = match name {
+        "at" => {
+            let n = args.first().map_or(0.0, to_integer);
+            let i = if n < 0.0 { len as f64 + n } else { n };
+            if i >= 0.0 && i < len as f64 {
+                JsValue::Number(view.get(vm, i as usize))
+            } else {
+                JsValue::Undefined
+            }
+        }
+        "fill" => {
+            let value = args.first().map_or(f64::NAN, to_number);
+            let start = relative_index(args.get(1), len, 0);
+            let end = relative_index(args.get(2), len, len);
+            for i in start..end {
+                view.set(vm, i, value);
+            }
+            JsValue::Object(ptr)
+        }
+        "includes" | "indexOf" | "lastIndexOf" => {
+            let target = match args.first() {
+                Some(JsValue::Number(n)) => *n,
+                // Elements are all numbers
+                _ => return Ok(search_miss(name)),
+            };
+            let values = view.values(vm);
+            let found = match name {
+                "lastIndexOf" => {
+                    let from = match args.get(1) {
+                        None => len as f64 - 1.0,
+                        Some(arg) => {
+                            let n = to_integer(arg);
+                            if n < 0.0 {
+                                len as f64 + n
+                            } else {
+                                n.min(len as f64 - 1.0)
+                            }
+                        }
+                    };
+                    (from >= 0.0)
+                        .then(|| values[..=from as usize].iter().rposition(|v| *v == target))
+                        .flatten()
+                }
+                _ => {
+                    let from = relative_index(args.get(1), len, 0);
+                    let same = |v: &f64| {
+                        *v == target || (name == "includes" && v.is_nan() && target.is_nan())
+                    };
+                    values[from..].iter().position(same).map(|i| i + from)
+                }
+            };
+            match name {
+                "includes" => JsValue::Boolean(found.is_some()),
+                _ => JsValue::Number(found.map_or(-1.0, |i| i as f64)),
+            }
+        }
+        "join" | "toString" => {
+            let separator = match (name, args.first()) {
+                ("join", Some(JsValue::String(s))) => s.clone(),
+                _ => ",".into(),
+            };
+            let parts: Vec<String> = view.values(vm).iter().map(f64::to_string).collect();
+            JsValue::String(parts.join(&separator).into())
+        }
+        "reverse" => {
+            let values = view.values(vm);
+            for (i, value) in values.into_iter().rev().enumerate() {
+                view.set(vm, i, value);
+            }
+            JsValue::Object(ptr)
+        }
+        "set" => {
+            let values = match args.first() {
+                Some(JsValue::Object(source)) => array_like_values(vm, *source),
+                _ => Vec::new(),
+            };
+            let offset = args.get(1).map_or(0.0, to_integer);
+            if offset < 0.0 || offset + values.len() as f64 > len as f64 {
+                return Err(("RangeError", "offset is out of bounds".to_string()));
+            }
+            for (i, value) in values.into_iter().enumerate() {
+                view.set(vm, offset as usize + i, value);
+            }
+            JsValue::Undefined
+        }
+        "slice" => {
+            let start = relative_index(args.first(), len, 0);
+            let end = relative_index(args.get(1), len, len).max(start);
+            let values = view.values(vm);
+            typed_array_of(vm, view.kind, &values[start..end])
+        }
+        "sort" => {
+            // Numeric order with NaN last; comparators are not supported
+            let mut values = view.values(vm);
+            values.sort_by(|a, b| a.total_cmp(b));
+            values.sort_by_key(|v| v.is_nan());
+            for (i, value) in values.into_iter().enumerate() {
+                view.set(vm, i, value);
+            }
+            JsValue::Object(ptr)
+        }
+        "subarray" => {
+            let start = relative_index(args.first(), len, 0);
+            let end = relative_index(args.get(1), len, len).max(start);
+            JsValue::Object(alloc(
+                vm,
+                HeapData::TypedArray {
+                    kind: view.kind,
+                    buffer: view.buffer,
+                    offset: view.offset + start * view.kind.size(),
+                    length: end - start,
+                },
+            ))
+        }
+        _ => JsValue::Undefined,
+    };
+    Ok(result)
+}
+
+fn search_miss(name: &str) -> JsValue {
+    match name {
+        "includes" => JsValue::Boolean(false),
+        _ => JsValue::Number(-1.0),
+    }
+}
+
+/// How `console.log` shows a buffer or view
+pub fn inspect(vm: &VM, data: &HeapData) -> String {
+    match data {
+        HeapData::TypedArray { kind, length, .. } => {
+            let values = elements(vm, data)
+                .iter()
+                .take(INSPECT_MAX_ITEMS)
+                .map(f64::to_string)
+                .collect::<Vec<_>>();
+            let mut items = values.join(", ");
+            if *length > INSPECT_MAX_ITEMS {
+                items += &format!(", ... {} more items", length - INSPECT_MAX_ITEMS);
+            }
+            if items.is_empty() {
+                format!("{}({}) []", kind.name(), length)
+            } else {
+                format!("{}({}) [ {} ]", kind.name(), length, items)
+            }
+        }
+        HeapData::ArrayBuffer(bytes) => {
+            let mut contents = bytes
+                .iter()
+                .take(INSPECT_MAX_BYTES)
+                .map(|b| format!("{:02x}", b))
+                .collect::<Vec<_>>()
+                .join(" ");
+            if bytes.len() > INSPECT_MAX_BYTES {
+                contents += &format!(" ... {} more bytes", bytes.len() - INSPECT_MAX_BYTES);
+            }
+            format!(
+                "ArrayBuffer {{ [Uint8Contents]: <{}>, byteLength: {} }}",
+                contents,
+                bytes.len()
+            )
+        }
+        HeapData::DataView {
+            buffer,
+            offset,
+            length,
+        } => {
+            let buffer = vm.heap.get(*buffer).map(|obj| inspect(vm, &obj.data));
+            format!(
+                "DataView {{ byteLength: {}, byteOffset: {}, buffer: {} }}",
+                length,
+                offset,
+                buffer.unwrap_or_default()
+            )
+        }
+        _ => String::new(),
+    }
+}
+
+fn bytes_of_view<'a>(vm: &'a VM, data: &HeapData) -> &'a [u8] {
+    match data {
+        HeapData::TypedArray {
+            kind,
+            buffer,
+            offset,
+            length,
+        } => &buffer_bytes(vm, *buffer)[*offset..offset + length * kind.size()],
+        _ => &[],
+    }
+}
+
+/// The elements of a typed array
+pub fn elements(vm: &VM, data: &HeapData) -> Vec<f64> {
+    match data {
+        HeapData::TypedArray { kind, .. } => bytes_of_view(vm, data)
+            .chunks_exact(kind.size())
+            .map(|chunk| kind.read(chunk, true))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// `String(value)` of a buffer or view: a typed array joins its elements
+pub fn to_string(vm: &VM, data: &HeapData) -> String {
+    match data {
+        HeapData::TypedArray { .. } => elements(vm, data)
+            .iter()
+            .map(f64::to_string)
+            .collect::<Vec<_>>()
+            .join(","),
+        HeapData::ArrayBuffer(_) => "[object ArrayBuffer]".to_string(),
+        HeapData::DataView { .. } => "[object DataView]".to_string(),
+        _ => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn number(value: JsValue) -> f64 {
+        match value {
+            JsValue::Number(n) => n,
+            other => panic!("expected a number, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_views_share_their_buffer() {
+        let mut vm = VM::new();
+        let buffer = construct(&mut vm, "ArrayBuffer", &[JsValue::Number(8.0)]).unwrap();
+        let JsValue::Object(bytes) =
+            construct(&mut vm, "Uint8Array", std::slice::from_ref(&buffer)).unwrap()
+        else {
+            panic!("typed array is not an object");
+        };
+        let args = [buffer, JsValue::Number(4.0), JsValue::Number(1.0)];
+        let JsValue::Object(word) = construct(&mut vm, "Uint32Array", &args).unwrap() else {
+            panic!("typed array is not an object");
+        };
+        assert!(set(
+            &mut vm,
+            word,
+            &JsValue::Number(0.0),
+            &JsValue::Number(-1.0)
+        ));
+        let key = JsValue::String("7".into());
+        assert_eq!(number(get(&vm, bytes, &key).unwrap()), 255.0);
+        assert_eq!(number(get(&vm, bytes, &JsValue::Number(3.0)).unwrap()), 0.0);
+        assert!(matches!(
+            get(&vm, bytes, &JsValue::Number(8.0)),
+            Some(JsValue::Undefined)
+        ));
+        let key = JsValue::String("byteOffset".into());
+        assert_eq!(number(get(&vm, word, &key).unwrap()), 4.0);
+    }
+
+    #[test]
+    fn test_construct_errors() {
+        let mut vm = VM::new();
+        let error =
+            |vm: &mut VM, name: &str, args: &[JsValue]| construct(vm, name, args).unwrap_err();
+        assert_eq!(
+            error(&mut vm, "Uint8Array", &[JsValue::Number(-1.0)]),
+            ("RangeError", "Invalid typed array length: -1".to_string())
+        );
+        let buffer = construct(&mut vm, "ArrayBuffer", &[JsValue::Number(6.0)]).unwrap();
+        assert_eq!(
+            error(&mut vm, "Int32Array", std::slice::from_ref(&buffer)),
+            (
+                "RangeError",
+                "byte length of Int32Array should be a multiple of 4".to_string()
+            )
+        );
+        assert_eq!(
+            error(
+                &mut vm,
+                "Int16Array",
+                &[buffer.clone(), JsValue::Number(1.0)]
+            ),
+            (
+                "RangeError",
+                "start offset of Int16Array should be a multiple of 2".to_string()
+            )
+        );
+        assert_eq!(
+            error(&mut vm, "DataView", &[JsValue::Number(1.0)]).0,
+            "TypeError"
+        );
+        let args = [buffer, JsValue::Number(2.0), JsValue::Number(5.0)];
+        assert_eq!(
+            error(&mut vm, "DataView", &args),
+            ("RangeError", "Invalid DataView length 5".to_string())
+        );
+    }
+
+    #[test]
+    fn test_data_view_accessors() {
+        assert_eq!(
+            data_view_accessor("getUint16"),
+            Some((ElementKind::Uint16, false))
+        );
+        assert_eq!(
+            data_view_accessor("setFloat64"),
+            Some((ElementKind::Float64, true))
+        );
+        assert_eq!(data_view_accessor("getUint8Clamped"), None);
+        assert_eq!(data_view_accessor("get"), None);
+    }
+
+    #[test]
+    fn test_numeric_keys() {
+        assert_eq!(numeric_key(&JsValue::Number(2.0)), Some(Some(2)));
+        assert_eq!(numeric_key(&JsValue::Number(1.5)), Some(None));
+        assert_eq!(numeric_key(&JsValue::String("-1".into())), Some(None));
+        assert_eq!(numeric_key(&JsValue::String("-0".into())), Some(None));
+        assert_eq!(numeric_key(&JsValue::String("01".into())), None);
+        assert_eq!(numeric_key(&JsValue::String("length".into())), None);
+    }
+}
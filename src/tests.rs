@@ -1,8 +1,8 @@
 use crate::compiler::Codegen;
 use crate::compiler::borrow_ck::BorrowChecker;
-use crate::vm::VM;
 use crate::vm::opcodes::OpCode;
-use crate::vm::value::JsValue;
+use crate::vm::value::{JsString, JsValue};
+use crate::vm::{Task, UncaughtError, VM};
 use swc_common::{FileName, SourceMap, sync::Lrc};
 use swc_ecma_parser::{Parser, StringInput, Syntax, lexer::Lexer};
 
@@ -59,7 +59,7 @@ fn test_vm_math_execution() {
     ];
 
     vm.load_program(program);
-    vm.run_event_loop();
+    vm.run_event_loop().unwrap();
 
     // The result should be 30 on top of the stack
     // (Note: To run this, ensure your VM stack is accessible or add a getter)
@@ -105,7 +105,7 @@ fn test_function_execution() {
 
     // 3. Execute bytecode
     vm.load_program(bytecode);
-    vm.run_event_loop();
+    vm.run_event_loop().unwrap();
 
     // 4. Verify result - the function should return 15
     // Since the function returns 15 and we're not in a function context,
@@ -137,7 +137,7 @@ fn test_function_execution_with_args() {
 
     // 3. Execute bytecode
     vm.load_program(bytecode);
-    vm.run_event_loop();
+    vm.run_event_loop().unwrap();
 
     // 4. Verify result - the function should return 15
     // Since the function returns 15 and we're not in a function context,
@@ -166,7 +166,7 @@ fn test_object_creation() {
 
     // 3. Execute bytecode
     vm.load_program(bytecode);
-    vm.run_event_loop();
+    vm.run_event_loop().unwrap();
 
     // 4. Verify result - the function should return 15
     // Since the function returns 15 and we're not in a function context,
@@ -199,7 +199,7 @@ fn test_function_execution_with_object_args() {
 
     // 3. Execute bytecode
     vm.load_program(bytecode);
-    vm.run_event_loop();
+    vm.run_event_loop().unwrap();
 
     // 4. Verify result - the function should return 15
     // Since the function returns 15 and we're not in a function context,
@@ -215,23 +215,13 @@ let admin = user;  // 'user' moves to 'admin'
 let x = user.a;    // THIS SHOULD THROW AN ERROR";
 }
 
-// NOTE: setTimeout callback execution tests removed - the callback mechanism
-// is not fully implemented (native_set_timeout only sleeps, doesn't invoke callback).
-// TODO: Implement proper async callback execution in the VM event loop.
-
 // ==================== CLOSURE CAPTURING TESTS ====================
-// NOTE: Tests using setTimeout have been disabled because async functionality
-// (setTimeout, Promise) has been moved to the @rolls/async ecosystem.
-// These tests will be re-enabled in the Rolls repository.
 
 /// Test that closure captures work correctly with setTimeout.
 /// This is the "Stack Frame Paradox" scenario: the outer function's
 /// stack frame would normally be destroyed, but the captured variable
 /// is lifted to the heap.
-///
-/// DISABLED: Requires setTimeout which is in @rolls/async
 #[test]
-#[ignore = "setTimeout moved to @rolls/async"]
 fn test_closure_captures_variable_for_async() {
     let mut vm = VM::new();
     // This code creates a closure that captures `data` from outer scope.
@@ -255,16 +245,13 @@ fn test_closure_captures_variable_for_async() {
     }
 
     vm.load_program(bytecode);
-    vm.run_event_loop();
+    vm.run_event_loop().unwrap();
     // Test passes if no panic occurs - the closure accessed captured data
 }
 
 /// Test that the borrow checker prevents use of a captured variable
 /// after it has been moved into a closure.
-///
-/// DISABLED: Requires setTimeout which is in @rolls/async
 #[test]
-#[ignore = "setTimeout moved to @rolls/async"]
 fn test_borrow_checker_prevents_use_after_capture() {
     let mut bc = BorrowChecker::new();
 
@@ -306,3 +293,1914 @@ fn test_borrow_checker_prevents_use_after_capture() {
         err
     );
 }
+
+/// Write `files` into a fresh directory and run `entry` the way `oitec` runs a script
+fn run_module_graph(name: &str, files: &[(&str, &str)], entry: &str) -> VM {
+    try_run_module_graph(name, files, entry).unwrap_or_else(|error| panic!("{}", error))
+}
+
+/// Like `run_module_graph`, returning the uncaught error that ends the run
+fn try_run_module_graph(
+    name: &str,
+    files: &[(&str, &str)],
+    entry: &str,
+) -> Result<VM, UncaughtError> {
+    let root = std::env::temp_dir().join(format!("oite_modules_{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(&root).unwrap();
+    for (file, source) in files {
+        std::fs::write(root.join(file), source).unwrap();
+    }
+    let entry_path = root.join(entry);
+    let source = std::fs::read_to_string(&entry_path).unwrap();
+
+    let mut vm = VM::new();
+    let mut compiler = crate::compiler::Compiler::new();
+    compiler.set_source_path(&entry_path);
+    let module = compiler
+        .compile_module(&source, None, 0)
+        .expect("entry should compile");
+    let len = module.bytecode.len();
+    let offset = vm.append_program(module.bytecode);
+    vm.add_source_map(offset..offset + len, &entry_path, module.source_map);
+    vm.register_entry_module(&entry_path, &source, &module.exports);
+    vm.set_current_module_path(entry_path);
+    let result = vm.run_event_loop();
+
+    let _ = std::fs::remove_dir_all(&root);
+    result.map(|()| vm)
+}
+
+fn global(vm: &VM, name: &str) -> JsValue {
+    vm.call_stack[0]
+        .locals
+        .get(name)
+        .cloned()
+        .unwrap_or(JsValue::Undefined)
+}
+
+/// Run `source` as the entry module of its own module graph
+fn run_script(source: &str) -> VM {
+    try_run_script(source).unwrap_or_else(|error| panic!("{}", error))
+}
+
+/// Like `run_script`, returning the uncaught error that ends the run
+fn try_run_script(source: &str) -> Result<VM, UncaughtError> {
+    static NEXT: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+    let n = NEXT.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    try_run_module_graph(&format!("script_{}", n), &[("main.ot", source)], "main.ot")
+}
+
+#[track_caller]
+fn assert_string(vm: &VM, name: &str, expected: &str) {
+    assert_eq!(
+        global(vm, name),
+        JsValue::String(expected.into()),
+        "global {}",
+        name
+    );
+}
+
+#[test]
+fn test_import_cycle_calls_hoisted_functions() {
+    let vm = run_module_graph(
+        "cycle",
+        &[
+            (
+                "a.ot",
+                r#"
+                import { b } from "./b.ot";
+                export function a() { return "a"; }
+                let result = b();
+                "#,
+            ),
+            (
+                "b.ot",
+                r#"
+                import { a } from "./a.ot";
+                export function b() { return a() + "b"; }
+                "#,
+            ),
+        ],
+        "a.ot",
+    );
+    assert_eq!(global(&vm, "result"), JsValue::String("ab".into()));
+}
+
+#[test]
+fn test_imports_are_live_bindings() {
+    let vm = run_module_graph(
+        "live",
+        &[
+            (
+                "counter.ot",
+                r#"
+                export let count = 0;
+                export function increment() { count = count + 1; }
+                "#,
+            ),
+            (
+                "reexport.ot",
+                r#"export { count as total } from "./counter.ot";"#,
+            ),
+            (
+                "main.ot",
+                r#"
+                import { count as current, increment } from "./counter.ot";
+                import { total } from "./reexport.ot";
+                let before = current;
+                increment();
+                increment();
+                let after = current;
+                let forwarded = total;
+                "#,
+            ),
+        ],
+        "main.ot",
+    );
+    assert_eq!(global(&vm, "before"), JsValue::Number(0.0));
+    assert_eq!(global(&vm, "after"), JsValue::Number(2.0));
+    assert_eq!(global(&vm, "forwarded"), JsValue::Number(2.0));
+}
+
+#[test]
+fn test_modules_have_separate_environments() {
+    let vm = run_module_graph(
+        "environments",
+        &[
+            ("a.ot", "export let count = 1;"),
+            (
+                "b.ot",
+                r#"
+                let count = 99;
+                export function bump() { count = count + 1; return count; }
+                "#,
+            ),
+            (
+                "main.ot",
+                r#"
+                import { count } from "./a.ot";
+                import { bump } from "./b.ot";
+                let before = count;
+                let bumped = bump();
+                let after = count;
+                "#,
+            ),
+        ],
+        "main.ot",
+    );
+    assert_eq!(global(&vm, "before"), JsValue::Number(1.0));
+    assert_eq!(global(&vm, "bumped"), JsValue::Number(100.0));
+    assert_eq!(global(&vm, "after"), JsValue::Number(1.0));
+    assert_eq!(global(&vm, "count"), JsValue::Undefined);
+}
+
+#[test]
+fn test_import_cycle_reports_uninitialized_binding() {
+    let vm = run_module_graph(
+        "tdz",
+        &[
+            (
+                "a.ot",
+                r#"
+                import { seen } from "./b.ot";
+                export const value = 42;
+                let result = seen;
+                "#,
+            ),
+            (
+                "b.ot",
+                r#"
+                import { value } from "./a.ot";
+                let message = "unread";
+                try { message = "read " + value; } catch (e) { message = e.name + ": " + e.message; }
+                export const seen = message;
+                "#,
+            ),
+        ],
+        "a.ot",
+    );
+    assert_eq!(
+        global(&vm, "result"),
+        JsValue::String("ReferenceError: Cannot access 'value' before initialization".into())
+    );
+}
+
+#[test]
+fn test_default_and_namespace_imports() {
+    let vm = run_module_graph(
+        "default",
+        &[
+            (
+                "math.ot",
+                r#"
+                export const PI = 3;
+                export default function mul(a, b) { return a * b; }
+                "#,
+            ),
+            (
+                "main.ot",
+                r#"
+                import mul from "./math.ot";
+                import * as math from "./math.ot";
+                let product = mul(2, math.PI);
+                "#,
+            ),
+        ],
+        "main.ot",
+    );
+    assert_eq!(global(&vm, "product"), JsValue::Number(6.0));
+}
+
+#[test]
+fn test_asset_imports_embed_file_contents() {
+    let vm = run_module_graph(
+        "assets",
+        &[
+            (
+                "config.json",
+                r#"{ "name": "oite", "tags": ["a", "b"], "limits": { "max": 3 } }"#,
+            ),
+            ("greeting.txt", "hello\n"),
+            ("blob.bin", "AB"),
+            (
+                "main.ot",
+                r#"
+                import config from "./config.json" with { type: "json" };
+                import * as ns from "./config.json" with { type: "json" };
+                import greeting from "./greeting.txt" with { type: "text" };
+                import blob from "./blob.bin" with { type: "bytes" };
+                let summary = config.name + ":" + config.tags[1] + ":" + config.limits.max;
+                let same = ns.default === config;
+                let text = greeting;
+                let bytes = blob[0] + blob[1];
+                let error = "none";
+                try { config.limits.max = 4; } catch (e) { error = e.name; }
+                let max = config.limits.max;
+                "#,
+            ),
+        ],
+        "main.ot",
+    );
+    assert_eq!(global(&vm, "summary"), JsValue::String("oite:b:3".into()));
+    assert_eq!(global(&vm, "same"), JsValue::Boolean(true));
+    assert_eq!(global(&vm, "text"), JsValue::String("hello\n".into()));
+    assert_eq!(global(&vm, "bytes"), JsValue::Number(131.0));
+    assert_eq!(global(&vm, "error"), JsValue::String("TypeError".into()));
+    assert_eq!(global(&vm, "max"), JsValue::Number(3.0));
+}
+
+#[test]
+fn test_json_import_requires_type_attribute() {
+    let mut compiler = crate::compiler::Compiler::new();
+    let err = compiler
+        .compile(r#"import data from "./data.json";"#)
+        .unwrap_err();
+    assert!(err.contains(r#"with { type: "json" }"#), "{}", err);
+
+    let err = compiler
+        .compile(r#"import data from "./data.css" with { type: "css" };"#)
+        .unwrap_err();
+    assert!(err.contains("Unsupported import type 'css'"), "{}", err);
+}
+
+/// Write a watched module file, dated so the change is seen even within the
+/// file system's mtime resolution
+fn write_changed(path: &std::path::Path, source: &str) {
+    std::fs::write(path, source).unwrap();
+    let later = std::time::SystemTime::now() + std::time::Duration::from_secs(5);
+    std::fs::File::options()
+        .write(true)
+        .open(path)
+        .unwrap()
+        .set_modified(later)
+        .unwrap();
+}
+
+/// Run the entry module `main.ot` in `root` with hot reloading enabled
+fn run_watched_entry(root: &std::path::Path) -> VM {
+    let entry_path = root.join("main.ot");
+    let source = std::fs::read_to_string(&entry_path).unwrap();
+    let mut vm = VM::new();
+    vm.enable_hot_reload();
+    let mut compiler = crate::compiler::Compiler::new();
+    compiler.set_source_path(&entry_path);
+    let module = compiler.compile_module(&source, None, 0).unwrap();
+    vm.append_program(module.bytecode);
+    vm.register_entry_module(&entry_path, &source, &module.exports);
+    vm.set_current_module_path(entry_path);
+    vm.run_event_loop().unwrap();
+    vm
+}
+
+#[test]
+fn test_hot_reload_swaps_exports_in_place() {
+    let root = std::env::temp_dir().join(format!("oite_modules_hot_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(&root).unwrap();
+    let write = |file: &str, source: &str| write_changed(&root.join(file), source);
+    let counter = |label: &str| {
+        format!(
+            r#"
+            export const label = "{}";
+            if (import.meta.hot) {{
+                import.meta.hot.dispose((data) => {{ data.count = (data.count || 0) + 1; }});
+                import.meta.hot.accept();
+            }}
+            export let disposed = import.meta.hot.data.count;
+            "#,
+            label
+        )
+    };
+    write("counter.ot", &counter("v1"));
+    write("view.ot", r#"export const view = "view1";"#);
+    write(
+        "main.ot",
+        r#"
+        import { label } from "./counter.ot";
+        import { view } from "./view.ot";
+        let seen = label + ":" + view;
+        "#,
+    );
+
+    let mut vm = run_watched_entry(&root);
+    assert_eq!(global(&vm, "seen"), JsValue::String("v1:view1".into()));
+    assert!(vm.reload_changed_modules().is_empty());
+
+    // An accepting module is swapped alone; importers see its new exports
+    write("counter.ot", &counter("v2"));
+    let reloaded = vm.reload_changed_modules();
+    let names: Vec<_> = reloaded.iter().map(|p| p.file_name().unwrap()).collect();
+    assert_eq!(names, ["counter.ot"]);
+    assert_eq!(global(&vm, "seen"), JsValue::String("v1:view1".into()));
+    let counter_path = std::fs::canonicalize(root.join("counter.ot")).unwrap();
+    let namespace = vm.module_cache.entries()[&counter_path].namespace_object;
+    assert_eq!(
+        vm.get_prop_with_proto_chain(namespace, "disposed"),
+        JsValue::Number(1.0)
+    );
+    assert_eq!(
+        vm.get_prop_with_proto_chain(namespace, "label"),
+        JsValue::String("v2".into())
+    );
+
+    // Otherwise the update reaches the importers
+    write("view.ot", r#"export const view = "view2";"#);
+    let reloaded = vm.reload_changed_modules();
+    let names: Vec<_> = reloaded.iter().map(|p| p.file_name().unwrap()).collect();
+    assert_eq!(names, ["view.ot", "main.ot"]);
+    assert_eq!(global(&vm, "seen"), JsValue::String("v2:view2".into()));
+
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[test]
+fn test_hot_reload_keeps_previous_version_when_new_one_throws() {
+    let root = std::env::temp_dir().join(format!("oite_modules_hot_throw_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(&root).unwrap();
+    let write = |file: &str, source: &str| write_changed(&root.join(file), source);
+    write("lib.ot", r#"export let value = "v1";"#);
+    write(
+        "main.ot",
+        r#"
+        import { value } from "./lib.ot";
+        let seen = value;
+        "#,
+    );
+    let mut vm = run_watched_entry(&root);
+    let lib_path = std::fs::canonicalize(root.join("lib.ot")).unwrap();
+    let namespace = vm.module_cache.entries()[&lib_path].namespace_object;
+
+    // The export is written before the throw, and rolled back after it
+    write(
+        "lib.ot",
+        r#"
+        export let value = "v2";
+        throw new Error("broken");
+        "#,
+    );
+    assert!(vm.reload_changed_modules().is_empty());
+    assert_eq!(
+        vm.get_prop_with_proto_chain(namespace, "value"),
+        JsValue::String("v1".into())
+    );
+    assert_eq!(global(&vm, "seen"), JsValue::String("v1".into()));
+    assert!(vm.run_event_loop().is_ok());
+
+    // A fixed version is picked up by the next reload
+    write("lib.ot", r#"export let value = "v3";"#);
+    let reloaded = vm.reload_changed_modules();
+    let names: Vec<_> = reloaded.iter().map(|p| p.file_name().unwrap()).collect();
+    assert_eq!(names, ["lib.ot", "main.ot"]);
+    assert_eq!(global(&vm, "seen"), JsValue::String("v3".into()));
+
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[test]
+fn test_dynamic_import_resolves_after_graph_is_evaluated() {
+    let vm = run_module_graph(
+        "dynamic",
+        &[
+            ("helper.ot", "export function helper(x) { return x + 1; }"),
+            (
+                "lib.ot",
+                r#"
+                import { helper } from "./helper.ot";
+                export const value = helper(20);
+                "#,
+            ),
+            (
+                "main.ot",
+                r#"
+                export {};
+                let order = "";
+                const pending = import("./lib.ot");
+                order = order + "sync;";
+                const ns = await pending;
+                let value = ns.value;
+                let later = 0;
+                import("./lib.ot").then((m) => { later = m.value + 1; });
+                let same = (await import("./lib.ot")) === ns;
+                "#,
+            ),
+        ],
+        "main.ot",
+    );
+    assert_eq!(global(&vm, "order"), JsValue::String("sync;".into()));
+    assert_eq!(global(&vm, "value"), JsValue::Number(21.0));
+    assert_eq!(global(&vm, "later"), JsValue::Number(22.0));
+    assert_eq!(global(&vm, "same"), JsValue::Boolean(true));
+}
+
+#[test]
+fn test_await_import_inside_async_function() {
+    let vm = run_module_graph(
+        "await_in_function",
+        &[
+            ("lib.ot", "export const value = 41;"),
+            (
+                "main.ot",
+                r#"
+                async function load() {
+                    const ns = await import("./lib.ot");
+                    return ns.value + 1;
+                }
+                let result = 0;
+                load().then((v) => { result = v; });
+                "#,
+            ),
+        ],
+        "main.ot",
+    );
+    assert_eq!(global(&vm, "result"), JsValue::Number(42.0));
+}
+
+/// The uncaught error that ends a script
+fn uncaught<T>(result: Result<T, UncaughtError>) -> String {
+    match result {
+        Ok(_) => panic!("the script should throw"),
+        Err(error) => error.to_string(),
+    }
+}
+
+#[test]
+fn test_static_import_of_missing_module_throws_error() {
+    let message = uncaught(try_run_module_graph(
+        "static_missing",
+        &[("main.ot", r#"import { x } from "./missing.ot";"#)],
+        "main.ot",
+    ));
+    assert!(
+        message.starts_with("Uncaught Error: Module './missing.ot' not found"),
+        "{}",
+        message
+    );
+}
+
+#[test]
+fn test_static_import_of_unparsable_module_throws_syntax_error() {
+    let message = uncaught(try_run_module_graph(
+        "static_unparsable",
+        &[
+            ("broken.ot", "export const = ;"),
+            ("main.ot", r#"import { x } from "./broken.ot";"#),
+        ],
+        "main.ot",
+    ));
+    assert!(message.starts_with("Uncaught SyntaxError: "), "{}", message);
+    assert!(message.contains("broken.ot"), "{}", message);
+}
+
+#[test]
+fn test_module_resolution_error_throws() {
+    let mut vm = VM::new();
+    vm.load_program(vec![
+        OpCode::Push(JsValue::String("./lib".into())),
+        OpCode::Push(JsValue::String("main.ot".into())),
+        OpCode::ModuleResolutionError {
+            message: "no such package".into(),
+            specifier: "./lib".into(),
+            importer: "main.ot".into(),
+            dependency_chain: vec!["main.ot".into()],
+        },
+        OpCode::Halt,
+    ]);
+    let message = uncaught(vm.run_event_loop());
+    assert!(
+        message.starts_with(
+            "Uncaught Error: Module resolution error: no such package (importing ./lib from main.ot)"
+        ),
+        "{}",
+        message
+    );
+}
+
+#[test]
+fn test_failed_import_calls_then_rejection_handler() {
+    let vm = run_module_graph(
+        "then_rejected",
+        &[(
+            "main.ot",
+            r#"
+            let outcome = "pending";
+            import("./missing.ot").then(
+                (m) => { outcome = "loaded"; },
+                (e) => { outcome = "rejected " + e.specifier; }
+            );
+            "#,
+        )],
+        "main.ot",
+    );
+    assert_eq!(
+        global(&vm, "outcome"),
+        JsValue::String("rejected ./missing.ot".into())
+    );
+}
+
+#[test]
+fn test_dynamic_import_rejects_with_dependency_chain() {
+    let vm = run_module_graph(
+        "dynamic_missing",
+        &[
+            (
+                "entry.ot",
+                r#"import "./middle.ot"; export const ran = true;"#,
+            ),
+            ("middle.ot", r#"import "./missing.ot";"#),
+            (
+                "main.ot",
+                r#"
+                export {};
+                let message = "";
+                let chain = "";
+                try {
+                    await import("./entry.ot");
+                } catch (e) {
+                    message = e.message;
+                    chain = e.dependencyChain.join(",");
+                }
+                "#,
+            ),
+        ],
+        "main.ot",
+    );
+    let JsValue::String(message) = global(&vm, "message") else {
+        panic!("import() should reject with an error");
+    };
+    assert!(
+        message.contains("Module './missing.ot' not found"),
+        "{}",
+        message
+    );
+    let JsValue::String(chain) = global(&vm, "chain") else {
+        panic!("dependencyChain should be an array");
+    };
+    let chain: Vec<&str> = chain.split(',').collect();
+    assert_eq!(chain.len(), 2, "{:?}", chain);
+    assert!(chain[0].ends_with("entry.ot"), "{:?}", chain);
+    assert!(chain[1].ends_with("middle.ot"), "{:?}", chain);
+    // Nothing in the failed graph ran
+    assert_eq!(global(&vm, "ran"), JsValue::Undefined);
+}
+
+#[test]
+fn test_math_rounding_and_extremes() {
+    let vm = run_script(
+        r#"
+        let floor = Math.floor(-2.5);
+        let round = Math.round(2.5);
+        let max = Math.max(1, "7", 3);
+        let noArgs = Math.min();
+        "#,
+    );
+    assert_eq!(global(&vm, "floor"), JsValue::Number(-3.0));
+    assert_eq!(global(&vm, "round"), JsValue::Number(3.0));
+    assert_eq!(global(&vm, "max"), JsValue::Number(7.0));
+    assert_eq!(global(&vm, "noArgs"), JsValue::Number(f64::INFINITY));
+}
+
+#[test]
+fn test_math_functions_and_constants() {
+    let vm = run_script(
+        r#"
+        let hypot = Math.hypot(3, 4);
+        let circle = Math.PI * 2;
+        let r = Math.random();
+        let inRange = r >= 0 && r < 1;
+        let bad = Math.sqrt("x");
+        "#,
+    );
+    assert_eq!(global(&vm, "hypot"), JsValue::Number(5.0));
+    assert_eq!(
+        global(&vm, "circle"),
+        JsValue::Number(std::f64::consts::PI * 2.0)
+    );
+    assert_eq!(global(&vm, "inRange"), JsValue::Boolean(true));
+    assert!(matches!(global(&vm, "bad"), JsValue::Number(n) if n.is_nan()));
+}
+
+#[test]
+fn test_date_parses_and_formats_iso() {
+    let vm = run_script(
+        r#"
+        let d = new Date("2024-03-05T10:20:30.456Z");
+        let iso = d.toISOString();
+        let time = d.getTime();
+        let utc = Date.UTC(2024, 2, 5, 10, 20, 30, 456);
+        let utcDate = d.getUTCFullYear() + "-" + d.getUTCMonth() + "-" + d.getUTCDate();
+        let weekDay = d.getUTCDay();
+        let json = JSON.stringify({ at: d, bad: new Date("nope") });
+        "#,
+    );
+    assert_string(&vm, "iso", "2024-03-05T10:20:30.456Z");
+    assert_eq!(global(&vm, "time"), JsValue::Number(1_709_634_030_456.0));
+    assert_eq!(global(&vm, "utc"), JsValue::Number(1_709_634_030_456.0));
+    assert_string(&vm, "utcDate", "2024-2-5");
+    assert_eq!(global(&vm, "weekDay"), JsValue::Number(2.0));
+    assert_string(
+        &vm,
+        "json",
+        r#"{"at":"2024-03-05T10:20:30.456Z","bad":null}"#,
+    );
+}
+
+#[test]
+fn test_date_arithmetic_and_comparison() {
+    let vm = run_script(
+        r#"
+        let d = new Date("2024-03-05T10:20:30.456Z");
+        let later = new Date(d.getTime() + 1000);
+        let elapsed = later - d;
+        let ordered = d < later;
+        let copy = new Date(d).getTime();
+        "#,
+    );
+    assert_eq!(global(&vm, "elapsed"), JsValue::Number(1000.0));
+    assert_eq!(global(&vm, "ordered"), JsValue::Boolean(true));
+    assert_eq!(global(&vm, "copy"), JsValue::Number(1_709_634_030_456.0));
+}
+
+#[test]
+fn test_date_local_setters_roll_over() {
+    let vm = run_script(
+        r#"
+        let local = new Date(2024, 0, 31, 12);
+        let rolled = local.setMonth(1);
+        let localDate = local.getFullYear() + "-" + local.getMonth() + "-" + local.getDate();
+        let localHours = local.getHours();
+        "#,
+    );
+    // February 31st rolls over to March 2nd
+    assert_string(&vm, "localDate", "2024-2-2");
+    assert_eq!(global(&vm, "localHours"), JsValue::Number(12.0));
+    assert!(matches!(global(&vm, "rolled"), JsValue::Number(_)));
+}
+
+#[test]
+fn test_date_to_string_is_local_time() {
+    let vm = run_script(r#"let text = "at " + new Date(0);"#);
+    let JsValue::String(text) = global(&vm, "text") else {
+        panic!("text is not a string");
+    };
+    // The epoch in local time
+    assert!(
+        text.starts_with("at Thu Jan 01 1970") || text.starts_with("at Wed Dec 31 1969"),
+        "{}",
+        text
+    );
+}
+
+#[test]
+fn test_invalid_date() {
+    let vm = run_script(
+        r#"
+        let invalid = new Date(NaN).getTime();
+        let rangeError = "";
+        try { new Date(NaN).toISOString(); } catch (e) { rangeError = e.name; }
+        "#,
+    );
+    assert!(matches!(global(&vm, "invalid"), JsValue::Number(n) if n.is_nan()));
+    assert_string(&vm, "rangeError", "RangeError");
+}
+
+#[test]
+fn test_performance_now_and_date_now() {
+    let vm = run_script(
+        r#"
+        let t0 = performance.now();
+        let t1 = performance.now();
+        let monotonic = t1 >= t0 && t0 >= 0;
+        let recent = Date.now() > Date.UTC(2024, 2, 5);
+        "#,
+    );
+    assert_eq!(global(&vm, "monotonic"), JsValue::Boolean(true));
+    assert_eq!(global(&vm, "recent"), JsValue::Boolean(true));
+}
+
+#[test]
+fn test_timer_with_non_callable_callback_throws_type_error() {
+    let vm = run_script(
+        r#"
+        let timeout = "";
+        try { setTimeout(5, 10); } catch (e) { timeout = e.name + ": " + e.message; }
+        let microtask = "";
+        try { queueMicrotask(); } catch (e) { microtask = e instanceof TypeError; }
+        "#,
+    );
+    assert_string(
+        &vm,
+        "timeout",
+        "TypeError: setTimeout: callback must be a function, received 5",
+    );
+    assert_eq!(global(&vm, "microtask"), JsValue::Boolean(true));
+}
+
+#[test]
+fn test_native_timer_callback_error_is_uncaught() {
+    let message = uncaught(try_run_script("setTimeout(queueMicrotask, 0);"));
+    assert!(message.starts_with("Uncaught TypeError: "), "{}", message);
+}
+
+#[test]
+fn test_native_microtask_error_is_uncaught() {
+    let message = uncaught(try_run_script(
+        "queueMicrotask(() => queueMicrotask(setTimeout));",
+    ));
+    assert!(
+        message.starts_with("Uncaught TypeError: setTimeout: callback must be a function"),
+        "{}",
+        message
+    );
+}
+
+#[test]
+fn test_task_past_maximum_call_depth_throws_range_error() {
+    let mut vm = VM::new();
+    let frame = vm.call_stack[0].clone();
+    vm.call_stack.resize(crate::vm::MAX_CALL_STACK_DEPTH, frame);
+    let callback = vm.call_stack[0].locals["setTimeout"].clone();
+    vm.queue_microtask(crate::vm::Task {
+        function_ptr: callback,
+        args: Vec::new(),
+    });
+    let message = uncaught(vm.run_event_loop_step());
+    assert!(
+        message.starts_with("Uncaught RangeError: Maximum call stack size exceeded"),
+        "{}",
+        message
+    );
+}
+
+#[test]
+fn test_timers_and_microtasks() {
+    let vm = run_script(
+        r#"
+        let trace = "";
+        function record(step) { trace = trace + step + " "; }
+        record("sync");
+        setTimeout(() => record("timeout30"), 30);
+        setTimeout((a, b) => {
+            record("timeout0:" + a + b);
+            queueMicrotask(() => record("micro-in-timer"));
+        }, 0, "x", "y");
+        setImmediate(() => record("immediate"));
+        queueMicrotask(() => record("micro"));
+        let cancelled = setTimeout(() => record("cancelled"), 5);
+        clearTimeout(cancelled);
+        let ticks = { count: 0 };
+        let interval = setInterval(() => {
+            ticks.count = ticks.count + 1;
+            record("tick" + ticks.count);
+            if (ticks.count == 3) { clearInterval(interval); }
+        }, 1);
+        record("end");
+        "#,
+    );
+    assert_string(
+        &vm,
+        "trace",
+        "sync end micro immediate timeout0:xy micro-in-timer tick1 tick2 tick3 timeout30 ",
+    );
+}
+
+#[test]
+fn test_error_subclass_and_stack() {
+    let vm = run_script(
+        r#"
+        class HttpError extends Error {
+            constructor(message, status) {
+                super(message);
+                this.name = "HttpError";
+                this.status = status;
+            }
+        }
+        function fail() {
+            throw new HttpError("not found", 404);
+        }
+        let custom = "";
+        let customStack = "";
+        try { fail(); } catch (e) {
+            custom = e.name + " " + e.message + " " + e.status + " "
+                + (e instanceof HttpError) + " " + (e instanceof Error)
+                + " " + (e instanceof TypeError);
+            customStack = e.stack;
+        }
+        "#,
+    );
+    assert_string(&vm, "custom", "HttpError not found 404 true true false");
+    let JsValue::String(stack) = global(&vm, "customStack") else {
+        panic!("stack is not a string");
+    };
+    let lines: Vec<&str> = stack.lines().collect();
+    assert_eq!(lines[0], "Error: not found");
+    assert!(lines[1].starts_with("    at new HttpError ("), "{}", stack);
+    assert!(lines[2].starts_with("    at fail ("), "{}", stack);
+    assert!(lines[2].contains("main.ot:10:"), "{}", stack);
+}
+
+#[test]
+fn test_error_cause_and_to_string() {
+    let vm = run_script(
+        r#"
+        let inner = new TypeError("inner");
+        let outer = new Error("outer", { cause: inner });
+        let cause = outer.cause === inner;
+        let text = String(outer) + "|" + inner + "|" + new RangeError().toString();
+        let called = Error("no new");
+        let calledOk = called instanceof Error && called.message == "no new";
+        "#,
+    );
+    assert_eq!(global(&vm, "cause"), JsValue::Boolean(true));
+    assert_string(&vm, "text", "Error: outer|TypeError: inner|RangeError");
+    assert_eq!(global(&vm, "calledOk"), JsValue::Boolean(true));
+}
+
+#[test]
+fn test_aggregate_error() {
+    let vm = run_script(
+        r#"
+        let agg = new AggregateError([new TypeError("a"), new Error("b")], "both");
+        let aggregate = agg.name + " " + agg.message + " " + agg.errors.length
+            + " " + (agg instanceof Error);
+        "#,
+    );
+    assert_string(&vm, "aggregate", "AggregateError both 2 true");
+}
+
+#[test]
+fn test_vm_throws_type_errors() {
+    let vm = run_script(
+        r#"
+        let notFunction = "";
+        try { let n = 5; n(); } catch (e) {
+            notFunction = e.name + ": " + e.message + " " + (e instanceof TypeError);
+        }
+        let missing = "";
+        try { let u; u.run(); } catch (e) { missing = e.name + ": " + e.message; }
+        let notConstructor = "";
+        try { new (5)(); } catch (e) { notConstructor = e.name + ": " + e.message; }
+        "#,
+    );
+    assert_string(&vm, "notFunction", "TypeError: 5 is not a function true");
+    assert_string(
+        &vm,
+        "missing",
+        "TypeError: Cannot read properties of undefined (reading 'run')",
+    );
+    assert_string(&vm, "notConstructor", "TypeError: 5 is not a constructor");
+}
+
+#[test]
+fn test_undeclared_variable_throws_reference_error() {
+    let vm = run_script(
+        r#"
+        let reference = "";
+        try { missingName; } catch (e) {
+            reference = e.name + ": " + e.message + " " + (e instanceof ReferenceError);
+        }
+        let declared;
+        let kinds = typeof missingName + " " + typeof declared;
+        "#,
+    );
+    assert_string(
+        &vm,
+        "reference",
+        "ReferenceError: missingName is not defined true",
+    );
+    assert_string(&vm, "kinds", "undefined undefined");
+}
+
+#[test]
+fn test_nullish_property_access_throws_type_error() {
+    let vm = run_script(
+        r#"
+        let read = "";
+        try { let n = null; n.x; } catch (e) { read = e.name + ": " + e.message; }
+        let written = "";
+        try { let u; u.y = 1; } catch (e) { written = e.name + ": " + e.message; }
+        let computed = "";
+        try { let n = null; n["k"]; } catch (e) { computed = e.name + ": " + e.message; }
+        "#,
+    );
+    assert_string(
+        &vm,
+        "read",
+        "TypeError: Cannot read properties of null (reading 'x')",
+    );
+    assert_string(
+        &vm,
+        "written",
+        "TypeError: Cannot set properties of undefined (setting 'y')",
+    );
+    assert_string(
+        &vm,
+        "computed",
+        "TypeError: Cannot read properties of null (reading 'k')",
+    );
+}
+
+#[test]
+fn test_vm_invariant_failures_throw_type_errors() {
+    let mut vm = VM::new();
+    vm.load_program(vec![
+        OpCode::Push(JsValue::Number(5.0)),
+        OpCode::Push(JsValue::Null),
+        OpCode::SetProto,
+        OpCode::Halt,
+    ]);
+    let message = uncaught(vm.run_event_loop());
+    assert!(
+        message.starts_with("Uncaught TypeError: Cannot set the prototype of 5"),
+        "{}",
+        message
+    );
+
+    let mut vm = VM::new();
+    vm.load_program(vec![OpCode::GetPropComputed, OpCode::Halt]);
+    let message = uncaught(vm.run_event_loop());
+    assert!(message.starts_with("Uncaught TypeError: "), "{}", message);
+
+    let mut vm = VM::new();
+    vm.load_program(vec![OpCode::Halt]);
+    vm.queue_microtask(Task {
+        function_ptr: JsValue::Number(5.0),
+        args: Vec::new(),
+    });
+    let message = uncaught(vm.run_event_loop());
+    assert!(
+        message.starts_with("Uncaught TypeError: 5 is not a function"),
+        "{}",
+        message
+    );
+}
+
+#[test]
+fn test_invalid_array_length_throws_range_error() {
+    let vm = run_script(
+        r#"
+        let lengths = new Array(3).length + " " + Array(1, 2).length;
+        let invalid = "";
+        try { new Array(-1); } catch (e) {
+            invalid = e.name + ": " + e.message + " " + (e instanceof RangeError);
+        }
+        "#,
+    );
+    assert_string(&vm, "lengths", "3 2");
+    assert_string(&vm, "invalid", "RangeError: Invalid array length true");
+}
+
+#[test]
+fn test_await_continues_after_synchronous_code() {
+    let vm = run_script(
+        r#"
+        let order = "";
+        async function settled() {
+            order = order + "start,";
+            await Promise.resolve(1);
+            order = order + "awaited";
+        }
+        settled();
+        order = order + "sync end,";
+        "#,
+    );
+    assert_string(&vm, "order", "start,sync end,awaited");
+}
+
+#[test]
+fn test_throw_in_async_function_rejects_its_promise() {
+    let vm = run_script(
+        r#"
+        async function early() { throw new Error("early"); }
+        async function late() { await null; throw new Error("late"); }
+        let rejectedEarly = "";
+        let rejectedLate = "";
+        early().catch((e) => { rejectedEarly = e.message; });
+        late().catch((e) => { rejectedLate = e.message; });
+        let caught = "";
+        async function guarded() {
+            try { await Promise.reject(new TypeError("refused")); } catch (e) {
+                caught = e.name + ": " + e.message;
+            }
+        }
+        guarded();
+        "#,
+    );
+    assert_string(&vm, "rejectedEarly", "early");
+    assert_string(&vm, "rejectedLate", "late");
+    assert_string(&vm, "caught", "TypeError: refused");
+}
+
+#[test]
+fn test_stack_overflow_throws_range_error() {
+    let vm = run_script(
+        r#"
+        function recurse(n) { return recurse(n + 1); }
+        let overflow = "";
+        try { recurse(0); } catch (e) {
+            overflow = e.name + ": " + e.message + " " + (e instanceof RangeError);
+        }
+        "#,
+    );
+    assert_string(
+        &vm,
+        "overflow",
+        "RangeError: Maximum call stack size exceeded true",
+    );
+}
+
+#[test]
+fn test_typed_arrays_share_buffers() {
+    let vm = run_script(
+        r#"
+        let buf = new ArrayBuffer(8);
+        let bytes = new Uint8Array(buf);
+        let words = new Uint32Array(buf, 4, 1);
+        words[0] = 0xdeadbeef;
+        let shared = bytes[4] + "," + bytes["7"] + "," + bytes[8] + "," + words.byteOffset;
+        let sub = bytes.subarray(4, 6);
+        sub[0] = 1;
+        let subarray = sub.length + "," + bytes[4] + "," + sub.byteOffset;
+        let isView = ArrayBuffer.isView(bytes) + "," + ArrayBuffer.isView(buf);
+        "#,
+    );
+    assert_string(&vm, "shared", "239,222,undefined,4");
+    assert_string(&vm, "subarray", "2,1,4");
+    assert_string(&vm, "isView", "true,false");
+}
+
+#[test]
+fn test_typed_arrays_convert_elements() {
+    let vm = run_script(
+        r#"
+        let clamped = String(new Uint8ClampedArray([300, -5, 1.5, 2.5, NaN]));
+        let wrapped = String(Int8Array.of(200, -129, 1.9));
+        "#,
+    );
+    assert_string(&vm, "clamped", "255,0,2,2,0");
+    assert_string(&vm, "wrapped", "-56,127,1");
+}
+
+#[test]
+fn test_data_view_endianness() {
+    let vm = run_script(
+        r#"
+        let buf = new ArrayBuffer(8);
+        let bytes = new Uint8Array(buf);
+        let view = new DataView(buf);
+        view.setUint16(0, 0x1234);
+        let endian = view.getUint16(0) + "," + view.getUint16(0, true) + "," + bytes[0];
+        view.setFloat64(0, 0.1, true);
+        let float = new Float64Array(buf, 0, 1)[0];
+        "#,
+    );
+    assert_string(&vm, "endian", "4660,13330,18");
+    assert_eq!(global(&vm, "float"), JsValue::Number(0.1));
+}
+
+#[test]
+fn test_typed_array_methods() {
+    let vm = run_script(
+        r#"
+        let ints = new Int16Array(4);
+        ints.set([3, 1, 2], 1);
+        ints.fill(-1, 0, 1);
+        let methods = ints.join(" ") + "|" + ints.indexOf(2) + "|" + ints.at(-1) + "|"
+            + ints.slice(1).sort().join(" ") + "|" + ints.includes(5);
+        let sum = 0;
+        for (const x of ints) { sum = sum + x; }
+        let inspected = JSON.stringify(Uint8Array.from([1, 2]));
+        "#,
+    );
+    assert_string(&vm, "methods", "-1 3 1 2|3|2|1 2 3|false");
+    assert_eq!(global(&vm, "sum"), JsValue::Number(5.0));
+    assert_string(&vm, "inspected", r#"{"0":1,"1":2}"#);
+}
+
+#[test]
+fn test_typed_array_errors() {
+    let vm = run_script(
+        r#"
+        let errors = "";
+        try { new Uint8Array(-1); } catch (e) { errors = e.name + ": " + e.message; }
+        try { new Int32Array(new ArrayBuffer(3)); } catch (e) {
+            errors = errors + "\n" + e.message;
+        }
+        try { new DataView(new ArrayBuffer(8)).getUint32(6); } catch (e) {
+            errors = errors + "\n" + e.name + ": " + e.message;
+        }
+        try { new DataView(5); } catch (e) { errors = errors + "\n" + e.name; }
+        try { new Int16Array(4).set([1, 2], 3); } catch (e) { errors = errors + "\n" + e.message; }
+        "#,
+    );
+    assert_string(
+        &vm,
+        "errors",
+        "RangeError: Invalid typed array length: -1\n\
+         byte length of Int32Array should be a multiple of 4\n\
+         RangeError: Offset is outside the bounds of the DataView\n\
+         TypeError\n\
+         offset is out of bounds",
+    );
+}
+
+#[test]
+fn test_fs_reads_and_writes_binary_data() {
+    let bin = std::env::temp_dir().join(format!("oite_bytes_{}.bin", std::process::id()));
+    let source = format!(
+        r#"
+        fs.writeFileSync("{path}", new Uint8Array([104, 105, 0, 255]));
+        let read = fs.readFileSync("{path}", null);
+        let binary = read.length + "," + read[3] + "," + fs.readFileSync("{path}", {{}}).length;
+        "#,
+        path = bin.display()
+    );
+    let vm = run_script(&source);
+    let _ = std::fs::remove_file(&bin);
+    assert_string(&vm, "binary", "4,255,4");
+}
+
+#[test]
+fn test_array_index_assignment_extends_array() {
+    let vm = run_script(
+        r#"
+        let array = [1, 2];
+        array[0] = 5;
+        array[3] = 7;
+        let assigned = array.length + "," + array[0] + "," + array[3];
+        "#,
+    );
+    assert_string(&vm, "assigned", "4,5,7");
+}
+
+#[test]
+fn test_string_length_and_code_units() {
+    let vm = run_script(
+        r#"
+        let s = "a😀é";
+        let units = s.length + "," + s.charCodeAt(1) + "," + s.charCodeAt(2) + ","
+            + s.codePointAt(1) + "," + s.codePointAt(2) + "," + s.charCodeAt(4);
+        "#,
+    );
+    assert_string(&vm, "units", "4,55357,56832,128512,56832,NaN");
+}
+
+#[test]
+fn test_string_pieces_count_code_units() {
+    let vm = run_script(
+        r#"
+        let s = "a😀é";
+        let pieces = s.charAt(3) + s[3] + s.at(-1) + "|" + s.slice(0, 3) + "|"
+            + s.substring(2, 1) + "|" + s.slice(-1);
+        "#,
+    );
+    // substring(1, 2) is the high surrogate on its own
+    let pieces: Vec<u16> = "ééé|a😀|"
+        .encode_utf16()
+        .chain([0xD83D])
+        .chain("|é".encode_utf16())
+        .collect();
+    assert_eq!(
+        global(&vm, "pieces"),
+        JsValue::String(JsString::from_units(&pieces))
+    );
+}
+
+#[test]
+fn test_string_search_and_padding_count_code_units() {
+    let vm = run_script(
+        r#"
+        let s = "a😀é";
+        let search = s.indexOf("é") + "," + s.lastIndexOf("😀") + "," + "éab".charCodeAt(2);
+        let padded = "é".padStart(3, "x") + "|" + "a".padEnd(3, "é");
+        "#,
+    );
+    assert_string(&vm, "search", "3,1,98");
+    assert_string(&vm, "padded", "xxé|aéé");
+}
+
+#[test]
+fn test_string_from_char_code_and_code_point() {
+    let vm = run_script(
+        r#"
+        let built = String.fromCharCode(0xD83D, 0xDE00, 97) + String.fromCodePoint(0x1F600, 98);
+        let error = "";
+        try { String.fromCodePoint(1.5); } catch (e) { error = e.name + ": " + e.message; }
+        "#,
+    );
+    assert_string(&vm, "built", "😀a😀b");
+    assert_string(&vm, "error", "RangeError: Invalid code point 1.5");
+}
+
+#[test]
+fn test_string_normalize() {
+    let vm = run_script(
+        r#"
+        let normalized = "é".normalize() + "," + "é".normalize("NFD").length
+            + "," + "ﬁ".normalize("NFKC");
+        let error = "";
+        try { "x".normalize("nfc"); } catch (e) { error = e.name; }
+        "#,
+    );
+    assert_string(&vm, "normalized", "é,2,fi");
+    assert_string(&vm, "error", "RangeError");
+}
+
+#[test]
+fn test_string_comparison_by_code_unit() {
+    let vm = run_script(r#"let ordered = ("｡" > "😀") + "," + ("a" < "b");"#);
+    assert_string(&vm, "ordered", "true,true");
+}
+
+#[test]
+fn test_strings_keep_lone_surrogates() {
+    let vm = run_script(
+        r#"
+        let emoji = "😀";
+        let high = emoji.charAt(0);
+        let low = emoji.charAt(1);
+        let halvesDiffer = "\uD83D" === "\uDE00";
+        let rejoined = (high + low) === emoji;
+        let concatenated = high.concat(low) === emoji;
+        let loneCodePoint = high.codePointAt(0);
+        let escapedCodePoint = "\uDE00".codePointAt(0);
+        let splitLength = emoji.split("").length;
+        let splitRejoined = emoji.split("").join("") === emoji;
+        "#,
+    );
+    assert_eq!(global(&vm, "halvesDiffer"), JsValue::Boolean(false));
+    assert_eq!(global(&vm, "rejoined"), JsValue::Boolean(true));
+    assert_eq!(global(&vm, "concatenated"), JsValue::Boolean(true));
+    assert_eq!(global(&vm, "loneCodePoint"), JsValue::Number(55357.0));
+    assert_eq!(global(&vm, "escapedCodePoint"), JsValue::Number(56832.0));
+    assert_eq!(global(&vm, "splitLength"), JsValue::Number(2.0));
+    assert_eq!(global(&vm, "splitRejoined"), JsValue::Boolean(true));
+}
+
+#[test]
+fn test_text_encoder() {
+    let vm = run_script(
+        r#"
+        let encoder = new TextEncoder();
+        let bytes = encoder.encode("hé😀");
+        let encoded = encoder.encoding + "," + bytes.length + "," + bytes[1] + "," + bytes[6];
+        let into = encoder.encodeInto("hé😀", new Uint8Array(5));
+        let partial = into.read + "," + into.written;
+        "#,
+    );
+    assert_string(&vm, "encoded", "utf-8,7,195,128");
+    assert_string(&vm, "partial", "2,3");
+}
+
+#[test]
+fn test_text_decoder() {
+    let vm = run_script(
+        r#"
+        let decoder = new TextDecoder();
+        let decoded = decoder.decode(new TextEncoder().encode("hé😀")) + "|"
+            + decoder.decode(new Uint8Array([0xEF, 0xBB, 0xBF, 0x68, 0xFF])) + "|"
+            + new TextDecoder("utf-8", { ignoreBOM: true }).decode(new Uint8Array([0xEF, 0xBB, 0xBF])).length;
+        let streamed = decoder.decode(new Uint8Array([0x61, 0xF0, 0x9F]), { stream: true })
+            + "|" + decoder.decode(new Uint8Array([0x98, 0x80]));
+        let utf16 = new TextDecoder("UTF-16").decode(new Uint8Array([0x3D, 0xD8, 0x00, 0xDE, 0x62, 0]));
+        let classes = (decoder instanceof TextDecoder) + "," + decoder.encoding + "," + decoder.fatal;
+        "#,
+    );
+    assert_string(&vm, "decoded", "hé😀|h\u{FFFD}|1");
+    assert_string(&vm, "streamed", "a|😀");
+    assert_string(&vm, "utf16", "😀b");
+    assert_string(&vm, "classes", "true,utf-8,false");
+}
+
+#[test]
+fn test_text_decoder_errors() {
+    let vm = run_script(
+        r#"
+        let errors = "";
+        try { new TextDecoder("utf-8", { fatal: true }).decode(new Uint8Array([0xFF])); }
+        catch (e) { errors = e.name + ": " + e.message; }
+        try { new TextDecoder("latin9"); } catch (e) { errors = errors + "\n" + e.name + ": " + e.message; }
+        try { new TextDecoder().decode("text"); } catch (e) { errors = errors + "\n" + e.name; }
+        "#,
+    );
+    assert_string(
+        &vm,
+        "errors",
+        "TypeError: The encoded data was not valid for encoding utf-8\n\
+         RangeError: The \"latin9\" encoding is not supported\n\
+         TypeError",
+    );
+}
+
+#[test]
+fn test_map_keys_by_same_value_zero() {
+    let vm = run_script(
+        r#"
+        let m = new Map([["a", 1], ["b", 2]]);
+        m.set(0 / 0, "nan").set(-0, "zero").set("a", 10);
+        let lookups = m.get(0 / 0) + "," + m.get(0) + "," + m.get("a") + "," + m.size + "," + m.has("1");
+        let order = [...m.keys()].join(",") + "|" + [...m.values()].join(",");
+        "#,
+    );
+    assert_string(&vm, "lookups", "nan,zero,10,4,false");
+    assert_string(&vm, "order", "a,b,NaN,0|10,2,nan,zero");
+}
+
+#[test]
+fn test_map_iteration_sees_live_changes() {
+    let vm = run_script(
+        r#"
+        let m = new Map([["a", 10], ["b", 2], [0 / 0, "nan"], [0, "zero"]]);
+        let pairs = "";
+        for (const [key, value] of m) { pairs = pairs + key + "=" + value + ";"; }
+        let seen = [];
+        m.forEach(function (value, key, map) {
+            if (key == "a") { map.delete("b"); map.set("late", 1); }
+            this.push(key + "=" + value);
+        }, seen);
+        let visited = seen.join(",");
+        "#,
+    );
+    assert_string(&vm, "pairs", "a=10;b=2;NaN=nan;0=zero;");
+    assert_string(&vm, "visited", "a=10,NaN=nan,0=zero");
+}
+
+#[test]
+fn test_collection_iterators() {
+    let vm = run_script(
+        r#"
+        let m = new Map([["a", 1], ["b", 2], ["c", 3]]);
+        let it = m.entries();
+        let first = it.next();
+        m.delete("b");
+        m.set("c", 30);
+        let second = it.next();
+        let third = it.next();
+        let stepped = first.value.join("=") + "," + second.value.join("=") + "," + second.done
+            + "," + third.value + "," + third.done;
+        let keys = "";
+        for (const key of m.keys()) { keys = keys + key; }
+        let values = [...new Set([1, 2]).values()].join(",");
+        let rest = m.values();
+        rest.next();
+        let remaining = [...rest].join(",") + "|" + rest.next().done;
+        let shown = String(m.keys());
+        "#,
+    );
+    assert_string(&vm, "stepped", "a=1,c=30,false,undefined,true");
+    assert_string(&vm, "keys", "ac");
+    assert_string(&vm, "values", "1,2");
+    assert_string(&vm, "remaining", "30|true");
+    assert_string(&vm, "shown", "[object Map Iterator]");
+}
+
+#[test]
+fn test_set_iteration() {
+    let vm = run_script(
+        r#"
+        let s = new Set("hello");
+        s.add(1).add(1);
+        let setState = s.size + "|" + [...s].join("") + "|" + s.entries().next().value.join("") + "|" + s.delete("l") + s.delete("l");
+        let letters = "";
+        for (const c of new Set("a😀a")) { letters = letters + c + ","; }
+        "#,
+    );
+    assert_string(&vm, "setState", "5|helo1|hh|truefalse");
+    assert_string(&vm, "letters", "a,😀,");
+}
+
+#[test]
+fn test_map_and_set_errors() {
+    let vm = run_script(
+        r#"
+        let errors = "";
+        try { new Set(5); } catch (e) { errors = e.name + ": " + e.message; }
+        try { new Map([1]); } catch (e) { errors = errors + "\n" + e.message; }
+        try { for (const x of {}) {} } catch (e) { errors = errors + "\n" + e.message; }
+        try { new Map([["a", 1]]).forEach(function () { throw new Error("boom"); }); } catch (e) { errors = errors + "\n" + e.message; }
+        "#,
+    );
+    assert_string(
+        &vm,
+        "errors",
+        "TypeError: 5 is not iterable\n\
+         Iterator value 1 is not an entry object\n\
+         object is not iterable\n\
+         boom",
+    );
+}
+
+#[test]
+fn test_weak_map_holds_objects_by_identity() {
+    let vm = run_script(
+        r#"
+        let key = { id: 1 };
+        let other = { id: 1 };
+        let wm = new WeakMap([[key, "first"]]);
+        wm.set(other, "second");
+        let lookups = wm.get(key) + "," + wm.get(other) + "," + wm.get({ id: 1 }) + "," + wm.has(1);
+        let removed = wm.delete(key) + "," + wm.has(key) + "," + wm.delete(key);
+        "#,
+    );
+    assert_string(&vm, "lookups", "first,second,undefined,false");
+    assert_string(&vm, "removed", "true,false,false");
+}
+
+#[test]
+fn test_weak_set_holds_objects_by_identity() {
+    let vm = run_script(
+        r#"
+        let key = { id: 1 };
+        let ws = new WeakSet([key]);
+        let members = ws.has(key) + "," + ws.has({ id: 1 }) + "," + ws.delete(key) + "," + ws.has(key);
+        "#,
+    );
+    assert_string(&vm, "members", "true,false,true,false");
+}
+
+#[test]
+fn test_weak_collections_reject_primitives() {
+    let vm = run_script(
+        r#"
+        let wm = new WeakMap();
+        let ws = new WeakSet();
+        let errors = "";
+        try { wm.set("key", 1); } catch (e) { errors = e.name + ": " + e.message; }
+        try { ws.add(1); } catch (e) { errors = errors + "\n" + e.message; }
+        let shown = String(wm) + "," + String(ws) + "," + wm.size;
+        "#,
+    );
+    assert_string(
+        &vm,
+        "errors",
+        "TypeError: Invalid value used as weak map key\nInvalid value used in weak set",
+    );
+    assert_string(&vm, "shown", "[object WeakMap],[object WeakSet],undefined");
+}
+
+#[test]
+fn test_define_property_hidden_value_and_getter() {
+    let vm = run_script(
+        r#"
+        let o = { a: 1, b: 2 };
+        Object.defineProperty(o, "hidden", { value: 42 });
+        Object.defineProperty(o, "sum", {
+            get: function() { return this.a + this.b; },
+            enumerable: true,
+            configurable: true,
+        });
+        let listed = Object.keys(o).join(",") + "|" + Object.getOwnPropertyNames(o).join(",");
+        let read = o.hidden + "," + o.sum + "," + o["sum"] + "," + Object.values(o).join(",");
+        let d = Object.getOwnPropertyDescriptor(o, "hidden");
+        let described = d.value + "," + d.writable + "," + d.enumerable + "," + d.configurable;
+        Object.defineProperty(o, "sum", { value: 5, writable: true, enumerable: true });
+        o.sum = 6;
+        let redefined = o.sum + "," + JSON.stringify(o);
+        "#,
+    );
+    assert_string(&vm, "listed", "a,b,sum|a,b,hidden,sum");
+    assert_string(&vm, "read", "42,3,3,1,2,3");
+    assert_string(&vm, "described", "42,false,false,false");
+    assert_string(&vm, "redefined", "6,{\"a\":1,\"b\":2,\"sum\":6}");
+}
+
+#[test]
+fn test_property_attributes_reject_writes() {
+    let vm = run_script(
+        r#"
+        let o = { a: 1 };
+        Object.defineProperty(o, "hidden", { value: 42 });
+        let errors = "";
+        try { o.hidden = 1; } catch (e) { errors = e.name + ": " + e.message; }
+        try { delete o.hidden; } catch (e) { errors = errors + "\n" + e.message; }
+        try { Object.defineProperty(o, "hidden", { value: 1 }); } catch (e) { errors = errors + "\n" + e.message; }
+        try { Object.defineProperty(o, "x", { get: 1 }); } catch (e) { errors = errors + "\n" + e.message; }
+        try { Object.defineProperty(o, "x", { value: 1, get: function() {} }); } catch (e) { errors = errors + "\n" + e.message; }
+        let frozen = Object.freeze({ x: 1 });
+        try { frozen.x = 2; } catch (e) { errors = errors + "\n" + e.message; }
+        try { frozen["y"] = 2; } catch (e) { errors = errors + "\n" + e.message; }
+        try { Object.assign(frozen, { x: 3 }); } catch (e) { errors = errors + "\n" + e.message; }
+        "#,
+    );
+    assert_string(
+        &vm,
+        "errors",
+        "TypeError: Cannot assign to read only property 'hidden' of object\n\
+         Cannot delete property 'hidden' of object\n\
+         Cannot redefine property: hidden\n\
+         Getter must be a function: 1\n\
+         Invalid property descriptor. Cannot both specify accessors and a value or writable attribute\n\
+         Cannot assign to read only property 'x' of object\n\
+         Cannot add property y, object is not extensible\n\
+         Cannot assign to read only property 'x' of object",
+    );
+}
+
+#[test]
+fn test_reflect_reports_rejected_writes() {
+    let vm = run_script(
+        r#"
+        let o = { a: 1 };
+        Object.defineProperty(o, "hidden", { value: 42 });
+        let frozen = Object.freeze({ x: 1 });
+        let reflected = Reflect.set(frozen, "x", 3) + "," + Reflect.defineProperty(o, "hidden", { value: 0 })
+            + "," + Reflect.deleteProperty(o, "hidden") + "," + Reflect.deleteProperty(o, "a")
+            + "," + Object.isFrozen(frozen) + "," + Reflect.isExtensible(frozen) + "," + frozen.x;
+        "#,
+    );
+    assert_string(&vm, "reflected", "false,false,false,true,true,false,1");
+}
+
+#[test]
+fn test_object_create_and_set_prototype() {
+    let vm = run_script(
+        r#"
+        let proto = { greet: function() { return "hi " + this.name; } };
+        let c = Object.create(proto, { name: { value: "ada", enumerable: true } });
+        let created = c.greet() + "," + (Object.getPrototypeOf(c) === proto) + ","
+            + Reflect.has(c, "greet") + "," + Object.keys(c).join(",") + "," + JSON.stringify(c);
+        let bare = Object.create(null);
+        let other = { greet: function() { return "hello " + this.name; } };
+        Object.setPrototypeOf(c, other);
+        let swapped = c.greet() + "," + (Object.getPrototypeOf(bare) === null)
+            + "," + Reflect.setPrototypeOf(other, c);
+        let cyclic = "";
+        try { Object.setPrototypeOf(other, c); } catch (e) { cyclic = e.message; }
+        "#,
+    );
+    assert_string(&vm, "created", "hi ada,true,true,name,{\"name\":\"ada\"}");
+    assert_string(&vm, "swapped", "hello ada,true,false");
+    assert_string(&vm, "cyclic", "Cyclic __proto__ value");
+}
+
+#[test]
+fn test_object_and_reflect_reject_non_objects() {
+    let vm = run_script(
+        r#"
+        let errors = "";
+        try { Object.create(1); } catch (e) { errors = e.message; }
+        try { Reflect.get(1, "x"); } catch (e) { errors = errors + "\n" + e.message; }
+        try { Object.fromEntries([1]); } catch (e) { errors = errors + "\n" + e.message; }
+        try { Object.keys(null); } catch (e) { errors = errors + "\n" + e.message; }
+        "#,
+    );
+    assert_string(
+        &vm,
+        "errors",
+        "Object prototype may only be an Object or null: 1\n\
+         Reflect.get called on non-object\n\
+         Iterator value 1 is not an entry object\n\
+         Cannot convert undefined or null to object",
+    );
+}
+
+#[test]
+fn test_object_entries_list_integer_keys_first() {
+    let vm = run_script(
+        r#"
+        let keyed = { b: 2, a: 1 };
+        keyed[10] = "ten";
+        keyed["2"] = "two";
+        let ordered = "";
+        for (const [key, value] of Object.entries(keyed)) {
+            ordered = ordered + key + "=" + value + ",";
+        }
+        "#,
+    );
+    assert_string(&vm, "ordered", "2=two,10=ten,a=1,b=2,");
+}
+
+#[test]
+fn test_object_from_entries_assign_and_spread() {
+    let vm = run_script(
+        r#"
+        let c = Object.create({}, { name: { value: "ada", enumerable: true } });
+        let rebuilt = Object.fromEntries(new Map([["k", "v"], ["n", 1]]));
+        let merged = Object.assign({ a: 0 }, { a: 1 }, null, { b: 2 });
+        let spread = { ...c, extra: true };
+        let joined = rebuilt.k + rebuilt.n + "," + merged.a + merged.b + "," + Object.keys(spread).join(",");
+        "#,
+    );
+    assert_string(&vm, "joined", "v1,12,extra,name");
+}
+
+#[test]
+fn test_reflect_apply_and_own_keys() {
+    let vm = run_script(
+        r#"
+        let applied = Reflect.apply(function(x, y) { return this.base + x + y; }, { base: 10 }, [1, 2])
+            + "," + Reflect.apply(Math.max, null, [1, 5, 3]) + "," + Reflect.ownKeys([7, 8]).join(",");
+        "#,
+    );
+    assert_string(&vm, "applied", "13,5,0,1,length");
+}
+
+#[test]
+fn test_number_to_string_is_shortest_round_trip() {
+    let vm = run_script(
+        r#"
+        let shortest = String(0.1 + 0.2) + "," + 1e21 + "," + (1 / 3) + "," + 2 ** -20 + "," + (-0)
+            + "," + (1 / 0) + "," + [1.5, -1e-7].join("|") + "," + JSON.stringify([1e300, NaN]);
+        let keyed = {};
+        keyed[1.5] = "a";
+        keyed[1e21] = "b";
+        let keys = Object.keys(keyed).join(",");
+        "#,
+    );
+    assert_string(
+        &vm,
+        "shortest",
+        "0.30000000000000004,1e+21,0.3333333333333333,9.5367431640625e-7,0,Infinity,1.5|-1e-7,[1e+300,null]",
+    );
+    assert_string(&vm, "keys", "1.5,1e+21");
+}
+
+#[test]
+fn test_number_formatting_methods() {
+    let vm = run_script(
+        r#"
+        let formatted = (255).toString(16) + "," + (-255.5).toString(2) + "," + (1.005).toFixed(2)
+            + "," + (1e21).toFixed(2) + "," + (123.456).toPrecision(4) + "," + (0.00001).toPrecision(1)
+            + "," + (12345).toExponential(2) + "," + (0).toExponential() + "," + Infinity.toFixed(1);
+        "#,
+    );
+    assert_string(
+        &vm,
+        "formatted",
+        "ff,-11111111.1,1.00,1e+21,123.5,0.00001,1.23e+4,0e+0,Infinity",
+    );
+}
+
+#[test]
+fn test_number_formatting_range_errors() {
+    let vm = run_script(
+        r#"
+        let errors = "";
+        try { (1).toFixed(101); } catch (e) { errors = e.name + ": " + e.message; }
+        try { (1).toString(37); } catch (e) { errors = errors + "\n" + e.message; }
+        try { (1).toPrecision(0); } catch (e) { errors = errors + "\n" + e.message; }
+        "#,
+    );
+    assert_string(
+        &vm,
+        "errors",
+        "RangeError: toFixed() digits argument must be between 0 and 100\n\
+         toString() radix argument must be between 2 and 36\n\
+         toPrecision() argument must be between 1 and 100",
+    );
+}
+
+#[test]
+fn test_number_conversion() {
+    let vm = run_script(
+        r#"
+        let converted = [Number(" 0x1F "), Number(""), Number("1e3"), Number(null), Number(true),
+            Number("12px"), Number()].join(",");
+        "#,
+    );
+    assert_string(&vm, "converted", "31,0,1000,0,1,NaN,0");
+}
+
+#[test]
+fn test_parse_int_and_parse_float() {
+    let vm = run_script(
+        r#"
+        let parsed = [parseInt("42px"), parseInt("ff", 16), parseInt("0x10"), parseInt("  -12"),
+            parseInt("z", 37), parseFloat("3.14abc"), parseFloat(".5e1"), parseFloat("-Infinityx"),
+            parseFloat("e5")].join(",");
+        "#,
+    );
+    assert_string(&vm, "parsed", "42,255,16,-12,NaN,3.14,5,-Infinity,NaN");
+}
+
+#[test]
+fn test_number_predicates_and_constants() {
+    let vm = run_script(
+        r#"
+        let checks = [Number.isInteger(5), Number.isInteger(5.5), Number.isSafeInteger(2 ** 53),
+            Number.isNaN("abc"), isNaN("abc"), Number.isFinite("12"), isFinite("12"),
+            parseInt === Number.parseInt].join(",");
+        let constants = [Number.MAX_SAFE_INTEGER, Number.EPSILON, Number.MIN_VALUE,
+            Number.MAX_VALUE, Number.NEGATIVE_INFINITY, NaN].join(",");
+        "#,
+    );
+    assert_string(&vm, "checks", "true,false,false,false,true,false,true,true");
+    assert_string(
+        &vm,
+        "constants",
+        "9007199254740991,2.220446049250313e-16,5e-324,1.7976931348623157e+308,-Infinity,NaN",
+    );
+}
+
+/// A fresh scratch directory for an fs test
+fn fs_test_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("oite_{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn test_fs_promises_settle_on_the_event_loop() {
+    let dir = fs_test_dir("fs_promises");
+    let source = format!(
+        r#"
+        export {{}};
+        const fsp = require("fs/promises");
+        const dir = "{dir}";
+        let order = "";
+        const pending = fsp.mkdir(dir + "/sub/deep", {{ recursive: true }});
+        order = order + "sync;";
+        await pending;
+        await fsp.writeFile(dir + "/a.txt", "hello world");
+        await fsp.writeFile(dir + "/b.bin", new Uint8Array([1, 2, 3]));
+        const bytes = await fsp.readFile(dir + "/b.bin", null);
+        let read = (await fsp.readFile(dir + "/a.txt")) + "," + bytes.length + bytes[2] + ","
+            + (await fsp.readdir(dir)).join(" ");
+        const stats = await fsp.stat(dir + "/a.txt");
+        let stat = stats.size + "," + stats.isFile() + "," + (await fsp.stat(dir)).isDirectory()
+            + "," + (stats.mtime.getTime() === Math.floor(stats.mtimeMs));
+        await fsp.rename(dir + "/a.txt", dir + "/c.txt");
+        let renamed = (await fsp.readdir(dir)).join(" ");
+        let later = "";
+        fsp.readFile(dir + "/c.txt").then((text) => {{ later = text; }});
+        await fsp.rm(dir + "/missing", {{ force: true }});
+        "#,
+        dir = dir.display()
+    );
+    let vm = run_script(&source);
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_string(&vm, "order", "sync;");
+    assert_string(&vm, "read", "hello world,33,a.txt b.bin sub");
+    assert_string(&vm, "stat", "11,true,true,true");
+    assert_string(&vm, "renamed", "b.bin c.txt sub");
+    assert_string(&vm, "later", "hello world");
+}
+
+#[test]
+fn test_fs_promises_reject_like_node() {
+    let dir = fs_test_dir("fs_errors");
+    std::fs::create_dir_all(dir.join("sub")).unwrap();
+    std::fs::write(dir.join("c.txt"), "hello world").unwrap();
+    let source = format!(
+        r#"
+        export {{}};
+        const fsp = require("fs/promises");
+        const dir = "{dir}";
+        let errors = "";
+        try {{ await fsp.readFile(dir + "/a.txt"); }} catch (e) {{
+            errors = e.code + " " + e.errno + " " + e.syscall + " " + (e.path === dir + "/a.txt");
+        }}
+        try {{ await fsp.rm(dir + "/sub"); }} catch (e) {{ errors = errors + "\n" + e.code; }}
+        try {{ await fsp.mkdir(dir + "/sub"); }} catch (e) {{ errors = errors + "\n" + e.code; }}
+        try {{ await fsp.readFile(1); }} catch (e) {{ errors = errors + "\n" + e.name + ": " + e.message; }}
+        try {{ await fsp.open(dir + "/c.txt", "q"); }} catch (e) {{ errors = errors + "\n" + e.message; }}
+        "#,
+        dir = dir.display()
+    );
+    let vm = run_script(&source);
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_string(
+        &vm,
+        "errors",
+        "ENOENT -2 open true\n\
+         EISDIR\n\
+         EEXIST\n\
+         TypeError: The \"path\" argument must be of type string\n\
+         The value \"q\" is invalid for option \"flags\"",
+    );
+}
+
+#[test]
+fn test_blocking_work_error_rejects_its_promise() {
+    let mut vm = VM::new();
+    let promise = vm.spawn_blocking(
+        || Err::<f64, _>("disk on fire".to_string()),
+        |_, value| JsValue::Number(value),
+        |_, message| JsValue::String(message.as_str().into()),
+    );
+    vm.run_event_loop().unwrap();
+    assert_eq!(
+        promise.get_state(),
+        crate::vm::value::PromiseState::Rejected
+    );
+    assert_eq!(
+        promise.get_value(),
+        Some(JsValue::String("disk on fire".into()))
+    );
+}
+
+#[test]
+fn test_fs_promises_file_handles() {
+    let dir = fs_test_dir("fs_handles");
+    std::fs::write(dir.join("c.txt"), "hello world").unwrap();
+    let source = format!(
+        r#"
+        export {{}};
+        const fsp = require("fs/promises");
+        const dir = "{dir}";
+        const handle = await fsp.open(dir + "/c.txt");
+        const buf = new Uint8Array(4);
+        let chunks = "";
+        while (true) {{
+            const result = await handle.read(buf, 0, 4, null);
+            if (result.bytesRead === 0) {{ break; }}
+            chunks = chunks + new TextDecoder().decode(buf.subarray(0, result.bytesRead)) + "|";
+        }}
+        const at = await handle.read(buf, 0, 3, 6);
+        chunks = chunks + at.bytesRead + new TextDecoder().decode(buf.subarray(0, 3))
+            + "," + (await handle.stat()).size;
+        await handle.close();
+        try {{ await handle.read(); }} catch (e) {{ chunks = chunks + "," + e.code + handle.fd; }}
+
+        const out = await fsp.open(dir + "/out.txt", "w+");
+        const written = await out.write("abc");
+        await out.write(new Uint8Array([100, 101, 102, 103]), 1, 2);
+        await out.writeFile("!");
+        await out.close();
+        const reread = await fsp.open(dir + "/out.txt");
+        let writes = written.bytesWritten + "," + (await reread.readFile());
+        await reread.close();
+        "#,
+        dir = dir.display()
+    );
+    let vm = run_script(&source);
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_string(&vm, "chunks", "hell|o wo|rld|3wor,11,EBADF-1");
+    assert_string(&vm, "writes", "3,abcef!");
+    assert!(vm.open_files.is_empty());
+}
+
+#[test]
+fn test_fs_promises_then_chains() {
+    let dir = fs_test_dir("fs_then");
+    let source = format!(
+        r#"
+        const fsp = require("fs/promises");
+        let chained = "";
+        let length = 0;
+        let rejected = "";
+        let passed = "";
+        fsp.writeFile("{dir}/a.txt", "one")
+            .then(() => require("fs/promises").readFile("{dir}/a.txt"))
+            .then((text) => {{ chained = text; return text.length; }})
+            .then((n) => {{ length = n; }});
+        fsp.readFile("{dir}/missing.txt").then(
+            (text) => {{ rejected = "fulfilled " + text; }},
+            (e) => {{ rejected = e.code; }}
+        );
+        fsp.readFile("{dir}/missing.txt")
+            .then((text) => text)
+            .then(null, (e) => require("fs/promises").readFile("{dir}/a.txt"))
+            .then((text) => {{ passed = "recovered " + text; }});
+        "#,
+        dir = dir.display()
+    );
+    let vm = run_script(&source);
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_string(&vm, "chained", "one");
+    assert_eq!(global(&vm, "length"), JsValue::Number(3.0));
+    assert_string(&vm, "rejected", "ENOENT");
+    assert_string(&vm, "passed", "recovered one");
+}
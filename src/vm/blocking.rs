@@ -0,0 +1,98 @@
+//! Blocking calls run off the VM thread and settled by the event loop
+//!
+//! Work runs on a [`BlockingPool`] thread; its result comes back over a
+//! channel together with the code that turns it into a value, which needs
+//! the VM and so runs on the VM thread once the event loop takes it.
+
+use std::collections::HashMap;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::Instant;
+
+use crate::runtime::r#async::blocking::BlockingPool;
+
+use super::VM;
+use super::value::{JsValue, Promise};
+
+/// Turns the result of a blocking call into the value its promise settles
+/// with: fulfilled on `Ok`, rejected on `Err`
+pub type Completion = Box<dyn FnOnce(&mut VM) -> Result<JsValue, JsValue> + Send>;
+
+pub struct BlockingCalls {
+    pool: BlockingPool,
+    sender: Sender<(u64, Completion)>,
+    receiver: Receiver<(u64, Completion)>,
+    /// Promises of the calls still running, by call id
+    pending: HashMap<u64, Promise>,
+    next_id: u64,
+}
+
+impl Default for BlockingCalls {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BlockingCalls {
+    pub fn new() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Self {
+            pool: BlockingPool::default(),
+            sender,
+            receiver,
+            pending: HashMap::new(),
+            next_id: 0,
+        }
+    }
+
+    /// Whether no call is running
+    pub fn is_idle(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Run `work` on the pool. On the VM thread, `fulfill` turns its `Ok`
+    /// value into the promise's value and `reject` its `Err` into the reason.
+    /// Work reports failures as `Err`; it must not panic.
+    pub fn spawn<T, E, W, F, R>(&mut self, work: W, fulfill: F, reject: R) -> Promise
+    where
+        T: Send + 'static,
+        E: Send + 'static,
+        W: FnOnce() -> Result<T, E> + Send + 'static,
+        F: FnOnce(&mut VM, T) -> JsValue + Send + 'static,
+        R: FnOnce(&mut VM, E) -> JsValue + Send + 'static,
+    {
+        let id = self.next_id;
+        self.next_id += 1;
+        let promise = Promise::new();
+        self.pending.insert(id, promise.clone());
+        let sender = self.sender.clone();
+        self.pool.execute(move || {
+            let completion: Completion = match work() {
+                Ok(value) => Box::new(move |vm: &mut VM| Ok(fulfill(vm, value))),
+                Err(failure) => Box::new(move |vm: &mut VM| Err(reject(vm, failure))),
+            };
+            let _ = sender.send((id, completion));
+        });
+        promise
+    }
+
+    /// The next finished call, waiting for one until `deadline` (forever
+    /// without one) if none has finished yet
+    pub fn next_finished(&mut self, deadline: Option<Instant>) -> Option<(Promise, Completion)> {
+        let (id, completion) = match deadline {
+            Some(deadline) => {
+                let timeout = deadline.saturating_duration_since(Instant::now());
+                self.receiver.recv_timeout(timeout).ok()?
+            }
+            None => self.receiver.recv().ok()?,
+        };
+        let promise = self.pending.remove(&id)?;
+        Some((promise, completion))
+    }
+
+    /// The next finished call, without waiting
+    pub fn try_next_finished(&mut self) -> Option<(Promise, Completion)> {
+        let (id, completion) = self.receiver.try_recv().ok()?;
+        let promise = self.pending.remove(&id)?;
+        Some((promise, completion))
+    }
+}
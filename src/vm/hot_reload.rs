@@ -0,0 +1,311 @@
+//! Hot module reloading for `oitec --watch`
+//!
+//! Every loaded module is watched by content hash. A changed module is compiled
+//! again and its new body runs against the module's existing namespace object,
+//! so importers see the new exports through their live bindings.
+//!
+//! `import.meta.hot` lets a module take part in its own replacement:
+//! - `hot.data` is an object kept across reloads of the module
+//! - `hot.dispose(cb)` runs `cb(data)` before the module is replaced
+//! - `hot.accept(cb?)` declares that the module can be swapped on its own;
+//!   `cb` gets the new namespace. A change to a module that did not accept also
+//!   re-runs the modules that import it, up to the entry module.
+//!
+//! A new version that throws while its body runs is rolled back: module
+//! bindings, namespace objects and hooks return to their previous values.
+//! Objects the failed body mutated stay mutated.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::module::diagnostics::ModuleError;
+use crate::vm::module_cache::{CachedModule, ModuleCache};
+use crate::vm::module_linker::ModuleLinker;
+use crate::vm::value::{HeapData, HeapObject, JsValue};
+use crate::vm::{Task, VM};
+
+/// `import.meta.hot` state of one module file
+#[derive(Debug)]
+struct HotModule {
+    hot_object: usize,
+    data: usize,
+    /// Set by `hot.accept()` in the running version of the module
+    accepted: bool,
+    accept_callbacks: Vec<JsValue>,
+    dispose_callbacks: Vec<JsValue>,
+}
+
+/// Module state from before a reload, restored if the new version throws
+struct ReloadSnapshot {
+    linker: ModuleLinker,
+    /// Contents of every registered namespace object
+    namespaces: Vec<(usize, HeapData)>,
+    /// The entry script's top-level variables
+    globals: HashMap<String, JsValue>,
+    call_depth: usize,
+    handler_depth: usize,
+}
+
+#[derive(Debug, Default)]
+pub struct HotReloader {
+    modules: HashMap<PathBuf, HotModule>,
+    /// `import.meta.hot` object -> module file
+    hot_objects: HashMap<usize, PathBuf>,
+    /// Module file -> files that import it
+    importers: HashMap<PathBuf, Vec<PathBuf>>,
+}
+
+impl HotReloader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record_import(&mut self, importer: PathBuf, module: PathBuf) {
+        let importers = self.importers.entry(module).or_default();
+        if !importers.contains(&importer) {
+            importers.push(importer);
+        }
+    }
+
+    pub fn is_hot_object(&self, object: usize) -> bool {
+        self.hot_objects.contains_key(&object)
+    }
+
+    pub fn importers(&self, module: &Path) -> &[PathBuf] {
+        self.importers.get(module).map_or(&[], Vec::as_slice)
+    }
+}
+
+impl VM {
+    /// Track imports and expose `import.meta.hot` so changed modules can be
+    /// reloaded with [`VM::reload_changed_modules`]
+    pub fn enable_hot_reload(&mut self) {
+        self.hot_reloader.get_or_insert_with(HotReloader::new);
+    }
+
+    /// `import.meta.hot` of a module, created on first use
+    pub(super) fn hot_object(&mut self, path: &Path) -> Option<usize> {
+        let hot = self.hot_reloader.as_ref()?;
+        if let Some(module) = hot.modules.get(path) {
+            return Some(module.hot_object);
+        }
+
+        let data = self.heap.len();
+        self.heap.push(HeapObject {
+            data: HeapData::Object(HashMap::new()),
+        });
+        let mut props = HashMap::new();
+        props.insert("data".to_string(), JsValue::Object(data));
+        let hot_object = self.heap.len();
+        self.heap.push(HeapObject {
+            data: HeapData::Object(props),
+        });
+
+        let hot = self.hot_reloader.as_mut()?;
+        hot.hot_objects.insert(hot_object, path.to_path_buf());
+        hot.modules.insert(
+            path.to_path_buf(),
+            HotModule {
+                hot_object,
+                data,
+                accepted: false,
+                accept_callbacks: Vec::new(),
+                dispose_callbacks: Vec::new(),
+            },
+        );
+        Some(hot_object)
+    }
+
+    /// `hot.accept(cb?)` and `hot.dispose(cb)`. Returns `None` when `object`
+    /// is not an `import.meta.hot` object or `name` is not one of its methods.
+    pub(super) fn call_hot_method(
+        &mut self,
+        object: usize,
+        name: &str,
+        args: &[JsValue],
+    ) -> Option<JsValue> {
+        let hot = self.hot_reloader.as_mut()?;
+        let path = hot.hot_objects.get(&object)?;
+        let module = hot.modules.get_mut(path)?;
+        let callback = args
+            .first()
+            .filter(|arg| matches!(arg, JsValue::Function { .. } | JsValue::NativeFunction(_)));
+        match name {
+            "accept" => {
+                module.accepted = true;
+                module.accept_callbacks.extend(callback.cloned());
+            }
+            "dispose" => module.dispose_callbacks.extend(callback.cloned()),
+            _ => return None,
+        }
+        Some(JsValue::Undefined)
+    }
+
+    /// Loaded modules whose file changed since they were evaluated
+    pub fn changed_modules(&self) -> Vec<PathBuf> {
+        let mut changed: Vec<PathBuf> = self
+            .module_cache
+            .entries()
+            .values()
+            .filter(|cached| self.module_cache.should_reload(&cached.path))
+            .filter(|cached| {
+                let hash = ModuleCache::compute_hash(&cached.path);
+                !hash.is_empty() && hash != cached.hash
+            })
+            .map(|cached| cached.path.clone())
+            .collect();
+        changed.sort();
+        changed
+    }
+
+    /// Re-run every changed module, and the importers of those that did not
+    /// accept the update. Returns the modules that were re-run; a module that
+    /// fails to compile or throws keeps running its previous version.
+    pub fn reload_changed_modules(&mut self) -> Vec<PathBuf> {
+        let mut queue: VecDeque<PathBuf> = self.changed_modules().into();
+        let mut visited = HashSet::new();
+        let mut reloaded = Vec::new();
+        while let Some(path) = queue.pop_front() {
+            if !visited.insert(path.clone()) {
+                continue;
+            }
+            match self.reload_module(&path) {
+                Ok(accepted) => {
+                    if !accepted && let Some(hot) = &self.hot_reloader {
+                        queue.extend(hot.importers(&path).iter().cloned());
+                    }
+                    reloaded.push(path);
+                }
+                Err(e) => eprintln!("{}", e.trim_end()),
+            }
+        }
+        reloaded
+    }
+
+    /// Evaluate the current source of a loaded module into its namespace.
+    /// Returns whether the replaced version accepted the update.
+    fn reload_module(&mut self, path: &Path) -> Result<bool, String> {
+        let Some(namespace_ptr) = self
+            .module_cache
+            .entries()
+            .get(path)
+            .map(|cached| cached.namespace_object)
+        else {
+            return Ok(false);
+        };
+        let source = fs::read_to_string(path)
+            .map_err(|e| ModuleError::io_error(path.to_path_buf(), e.to_string()).to_string())?;
+        // Record the new version first, so a broken file is reported once
+        self.module_cache.insert(CachedModule {
+            path: path.to_path_buf(),
+            source: source.clone(),
+            hash: ModuleCache::compute_hash(&path.to_path_buf()),
+            load_time: SystemTime::now(),
+            namespace_object: namespace_ptr,
+        });
+
+        let module_id = self.module_linker.next_module_id();
+        self.compiler.set_source_path(path);
+        let compiled = self
+            .compiler
+            .compile_module(
+                &source,
+                Some(crate::compiler::syntax_for_path(path)),
+                module_id,
+            )
+            .map_err(|e| ModuleError::compile_error(path.to_path_buf(), e).to_string())?;
+
+        // The outgoing version's hooks; the new version registers its own
+        let (accepted, accept_callbacks, dispose_callbacks, data) = match self
+            .hot_reloader
+            .as_mut()
+            .and_then(|hot| hot.modules.get_mut(path))
+        {
+            Some(module) => (
+                std::mem::take(&mut module.accepted),
+                std::mem::take(&mut module.accept_callbacks),
+                std::mem::take(&mut module.dispose_callbacks),
+                JsValue::Object(module.data),
+            ),
+            None => (false, Vec::new(), Vec::new(), JsValue::Undefined),
+        };
+        for callback in &dispose_callbacks {
+            self.run_callback(callback.clone(), vec![data.clone()]);
+        }
+
+        let snapshot = self.reload_snapshot();
+        self.module_linker.unregister(namespace_ptr);
+        self.module_linker
+            .register(namespace_ptr, path.to_path_buf(), &compiled.exports);
+        self.execute_module(namespace_ptr, compiled.bytecode, compiled.source_map, path);
+        if let Err(error) = self.take_uncaught() {
+            self.restore_snapshot(snapshot);
+            if let Some(module) = self
+                .hot_reloader
+                .as_mut()
+                .and_then(|hot| hot.modules.get_mut(path))
+            {
+                module.accepted = accepted;
+                module.accept_callbacks = accept_callbacks;
+                module.dispose_callbacks = dispose_callbacks;
+            }
+            return Err(format!(
+                "{}\n[watch] {} threw; keeping the previous version",
+                error,
+                path.display()
+            ));
+        }
+        self.module_linker.mark_evaluated(namespace_ptr);
+
+        for callback in accept_callbacks {
+            self.run_callback(callback, vec![JsValue::Object(namespace_ptr)]);
+        }
+        Ok(accepted)
+    }
+
+    fn reload_snapshot(&self) -> ReloadSnapshot {
+        let namespaces = self
+            .module_cache
+            .entries()
+            .values()
+            .map(|cached| cached.namespace_object)
+            .filter_map(|ptr| Some((ptr, self.heap.get(ptr)?.data.clone())))
+            .collect();
+        ReloadSnapshot {
+            linker: self.module_linker.clone(),
+            namespaces,
+            globals: self.call_stack[0].locals.clone(),
+            call_depth: self.call_stack.len(),
+            handler_depth: self.exception_handlers.len(),
+        }
+    }
+
+    fn restore_snapshot(&mut self, snapshot: ReloadSnapshot) {
+        self.call_stack.truncate(snapshot.call_depth);
+        self.exception_handlers.truncate(snapshot.handler_depth);
+        self.current_exception = None;
+        self.module_linker = snapshot.linker;
+        for (ptr, data) in snapshot.namespaces {
+            self.heap[ptr].data = data;
+        }
+        self.call_stack[0].locals = snapshot.globals;
+    }
+
+    /// Call a function to completion from outside the running program. An
+    /// exception it throws is reported and does not stop the reload.
+    fn run_callback(&mut self, function_ptr: JsValue, args: Vec<JsValue>) {
+        let saved_ip = self.ip;
+        let saved_stack_len = self.stack.len();
+        let depth = self.call_stack.len();
+        self.execute_task(Task { function_ptr, args });
+        self.ip = saved_ip;
+        self.stack.truncate(saved_stack_len);
+        if let Err(error) = self.take_uncaught() {
+            self.call_stack.truncate(depth);
+            self.current_exception = None;
+            eprintln!("{}", error);
+        }
+    }
+}
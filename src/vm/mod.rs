@@ -1,15 +1,27 @@
 /// Maximum call stack depth to prevent stack overflow in deeply recursive code
 pub const MAX_CALL_STACK_DEPTH: usize = 1000;
 
+/// Catch address of the handler [`VM::call_sync`] installs, past any code
+const CALL_SYNC_CATCH: usize = usize::MAX - 1;
+
+mod blocking;
+pub mod hot_reload;
 pub mod module_cache;
+pub mod module_linker;
+mod module_loader;
 pub mod opcodes;
+pub mod ordered_map;
 pub mod property;
+mod stack_trace;
 pub mod stdlib_setup;
 pub mod value;
 
-pub use crate::compiler::Compiler;
+pub use crate::compiler::{Compiler, ExportBinding};
+pub use crate::module::ModuleResolver;
+pub use crate::vm::hot_reload::HotReloader;
 pub use crate::vm::module_cache::CachedModule;
 pub use crate::vm::module_cache::ModuleCache;
+pub use crate::vm::module_linker::ModuleLinker;
 pub use crate::vm::opcodes::OpCode;
 pub use crate::vm::value::AsyncContext;
 pub use crate::vm::value::ContinuationCallback;
@@ -19,175 +31,22 @@ pub use crate::vm::value::JsValue;
 pub use crate::vm::value::NativeFn;
 pub use crate::vm::value::Promise;
 pub use crate::vm::value::PromiseState;
+use crate::vm::value::{Continuation, PromiseHandler};
 pub use sha2::Digest;
-pub use std::collections::{HashMap, VecDeque};
+pub use std::collections::{HashMap, HashSet, VecDeque};
 pub use std::fs;
 pub use std::path::{Path, PathBuf};
+pub use std::sync::{Arc, Mutex};
 pub use std::time::{Duration, Instant};
-pub use swc_common::{FileName, input::StringInput};
-pub use swc_ecma_parser::{Parser, Syntax, TsSyntax, lexer::Lexer};
 pub use tokio::runtime::Runtime;
 pub use tokio::sync::mpsc;
 
-/// Parse module source and extract exports as a HashMap
-fn parse_module_exports(source: &str, file_name: &str) -> HashMap<String, JsValue> {
-    let mut exports = HashMap::new();
-
-    let cm: swc_common::SourceMap = Default::default();
-    let fm = cm.new_source_file(
-        FileName::Custom(file_name.to_string()).into(),
-        source.to_string(),
-    );
-
-    let lexer = Lexer::new(
-        Syntax::Typescript(TsSyntax {
-            decorators: true,
-            tsx: false,
-            dts: false,
-            no_early_errors: false,
-            disallow_ambiguous_jsx_like: true,
-        }),
-        Default::default(),
-        StringInput::from(&*fm),
-        None,
-    );
-
-    let mut parser = Parser::new_from(lexer);
-
-    match parser.parse_module() {
-        Ok(ast) => {
-            for item in &ast.body {
-                if let swc_ecma_ast::ModuleItem::ModuleDecl(decl) = item {
-                    match decl {
-                        swc_ecma_ast::ModuleDecl::ExportNamed(named) => {
-                            if let Some(_src) = &named.src {
-                                for spec in &named.specifiers {
-                                    match spec {
-                                        swc_ecma_ast::ExportSpecifier::Named(named) => {
-                                            let export_name = named
-                                                .exported
-                                                .as_ref()
-                                                .map(|e| {
-                                                    let atom = e.atom();
-                                                    let s: &str = &atom;
-                                                    s.to_string()
-                                                })
-                                                .unwrap_or_else(|| {
-                                                    let atom = named.orig.atom();
-                                                    let s: &str = &atom;
-                                                    s.to_string()
-                                                });
-                                            exports.insert(export_name, JsValue::Undefined);
-                                        }
-                                        swc_ecma_ast::ExportSpecifier::Default(_) => {
-                                            exports
-                                                .insert("default".to_string(), JsValue::Undefined);
-                                        }
-                                        swc_ecma_ast::ExportSpecifier::Namespace(ns) => {
-                                            let atom = ns.name.atom();
-                                            let s: &str = &atom;
-                                            exports.insert(s.to_string(), JsValue::Undefined);
-                                        }
-                                    }
-                                }
-                            } else {
-                                for spec in &named.specifiers {
-                                    match spec {
-                                        swc_ecma_ast::ExportSpecifier::Named(named) => {
-                                            let export_name = named
-                                                .exported
-                                                .as_ref()
-                                                .map(|e| {
-                                                    let atom = e.atom();
-                                                    let s: &str = &atom;
-                                                    s.to_string()
-                                                })
-                                                .unwrap_or_else(|| {
-                                                    let atom = named.orig.atom();
-                                                    let s: &str = &atom;
-                                                    s.to_string()
-                                                });
-                                            exports.insert(export_name, JsValue::Undefined);
-                                        }
-                                        swc_ecma_ast::ExportSpecifier::Default(_) => {
-                                            exports
-                                                .insert("default".to_string(), JsValue::Undefined);
-                                        }
-                                        swc_ecma_ast::ExportSpecifier::Namespace(ns) => {
-                                            let atom = ns.name.atom();
-                                            let s: &str = &atom;
-                                            exports.insert(s.to_string(), JsValue::Undefined);
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                        swc_ecma_ast::ModuleDecl::ExportAll(_all) => {
-                            exports.insert("*".to_string(), JsValue::Undefined);
-                        }
-                        swc_ecma_ast::ModuleDecl::ExportDefaultDecl(_default_decl) => {
-                            exports.insert("default".to_string(), JsValue::Undefined);
-                        }
-                        swc_ecma_ast::ModuleDecl::ExportDefaultExpr(_) => {
-                            exports.insert("default".to_string(), JsValue::Undefined);
-                        }
-                        swc_ecma_ast::ModuleDecl::ExportDecl(decl) => {
-                            use swc_ecma_ast::Decl::*;
-                            match &decl.decl {
-                                Fn(fn_decl) => {
-                                    exports
-                                        .insert(fn_decl.ident.sym.to_string(), JsValue::Undefined);
-                                }
-                                Var(var_decl) => {
-                                    for declarator in &var_decl.decls {
-                                        if let swc_ecma_ast::Pat::Ident(ident) = &declarator.name {
-                                            exports.insert(
-                                                ident.id.sym.to_string(),
-                                                JsValue::Undefined,
-                                            );
-                                        }
-                                    }
-                                }
-                                Class(class_decl) => {
-                                    exports.insert(
-                                        class_decl.ident.sym.to_string(),
-                                        JsValue::Undefined,
-                                    );
-                                }
-                                _ => {}
-                            }
-                        }
-                        _ => {}
-                    }
-                } else if let swc_ecma_ast::ModuleItem::Stmt(stmt) = item
-                    && let swc_ecma_ast::Stmt::Decl(decl) = stmt
-                {
-                    match decl {
-                        swc_ecma_ast::Decl::Var(var_decl) => {
-                            for declarator in &var_decl.decls {
-                                if let swc_ecma_ast::Pat::Ident(ident) = &declarator.name {
-                                    exports.insert(ident.id.sym.to_string(), JsValue::Undefined);
-                                }
-                            }
-                        }
-                        swc_ecma_ast::Decl::Fn(fn_decl) => {
-                            exports.insert(fn_decl.ident.sym.to_string(), JsValue::Undefined);
-                        }
-                        swc_ecma_ast::Decl::Class(class_decl) => {
-                            exports.insert(class_decl.ident.sym.to_string(), JsValue::Undefined);
-                        }
-                        _ => {}
-                    }
-                }
-            }
-        }
-        Err(e) => {
-            eprintln!("Warning: Failed to parse module for exports: {:?}", e);
-        }
-    }
-
-    exports
-}
+use crate::compiler::CompiledModule;
+use crate::compiler::source_map::BytecodeSourceMap;
+use crate::runtime::number;
+use crate::runtime::utf16::JsString;
+use crate::vm::blocking::{BlockingCalls, Completion};
+use crate::vm::property::Attributes;
 
 #[derive(Clone, Debug)]
 pub struct Frame {
@@ -200,17 +59,40 @@ pub struct Frame {
     /// Tracks whether super() has been called in a derived class constructor
     /// JavaScript requires super() to be called before accessing `this`
     pub super_called: bool,
-    /// For async functions: where to resume after await
-    pub resume_ip: Option<usize>,
+    /// Set in the frame of an async function call
+    pub async_call: Option<AsyncCall>,
 }
 
+/// A running async function call: the promise it returned, and the operand
+/// stack height below which the stack belongs to its callers
+#[derive(Clone, Debug)]
+pub struct AsyncCall {
+    pub promise: Promise,
+    pub stack_base: usize,
+}
+
+/// An async function call suspended in `await`
+struct SuspendedCall {
+    frame: Frame,
+    /// The call's part of the operand stack
+    stack: Vec<JsValue>,
+    /// The call's `try` blocks, with depths relative to its frame
+    handlers: Vec<ExceptionHandler>,
+    current_exception: Option<JsValue>,
+    resume_ip: usize,
+}
+
+#[derive(Clone)]
 pub struct Task {
     pub function_ptr: JsValue,
     pub args: Vec<JsValue>,
 }
 
 pub struct TimerTask {
+    id: u32,
     due: Instant,
+    /// Set for `setInterval`: the timer is re-armed each time it fires
+    interval: Option<Duration>,
     task: Task,
 }
 
@@ -227,13 +109,37 @@ pub struct ExceptionHandler {
     pub call_stack_depth: usize,
 }
 
+/// An exception that no handler caught, which ends the run that threw it
+#[derive(Clone, Debug)]
+pub struct UncaughtError {
+    /// The thrown value
+    pub value: JsValue,
+    /// The error's stack trace, or the value as `console.log` shows it
+    pub report: String,
+}
+
+impl std::fmt::Display for UncaughtError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Uncaught {}", self.report)
+    }
+}
+
 pub struct VM {
     pub stack: Vec<JsValue>,
     pub call_stack: Vec<Frame>,
     pub heap: Vec<HeapObject>,
     pub native_functions: Vec<NativeFn>,
+    /// Microtasks: promise reactions and `queueMicrotask` callbacks, all of
+    /// which run before the next timer fires
     pub task_queue: VecDeque<Task>,
     timers: Vec<TimerTask>,
+    next_timer_id: u32,
+    /// Calls running on worker threads; see [`VM::spawn_blocking`]
+    blocking: BlockingCalls,
+    /// Files opened with `fs.promises.open`, by descriptor
+    pub open_files: HashMap<i32, Arc<Mutex<fs::File>>>,
+    /// Prototypes of the `Stats` and `FileHandle` objects of `fs.promises`
+    pub fs_prototypes: HashMap<&'static str, usize>,
     pub program: Vec<OpCode>,
     pub modules: HashMap<String, JsValue>,
     pub ip: usize,
@@ -245,6 +151,38 @@ pub struct VM {
     pub async_runtime: Option<Runtime>,
     pub async_task_tx: Option<mpsc::Sender<JsValue>>,
     pub module_cache: ModuleCache,
+    /// Resolves import specifiers; built from the project's `oite.json` on first import
+    pub module_resolver: Option<ModuleResolver>,
+    /// Keeps imported bindings live across modules
+    pub module_linker: ModuleLinker,
+    /// Modules fetched and compiled by a dynamic `import()`, waiting to be evaluated
+    prepared_modules: HashMap<PathBuf, (String, CompiledModule)>,
+    /// Native task that loads a dynamic `import()`, registered on first use
+    dynamic_import_loader: Option<usize>,
+    /// Native task that runs a `then` callback, registered on first use
+    promise_reaction: Option<usize>,
+    /// Native task that resumes a suspended async call, registered on first use
+    async_resume: Option<usize>,
+    /// Async function calls waiting in `await`, by id
+    suspended_calls: HashMap<usize, SuspendedCall>,
+    next_suspended_call: usize,
+    /// `import.meta` object of each module file
+    import_metas: HashMap<PathBuf, usize>,
+    /// Set in watch mode; see [`VM::enable_hot_reload`]
+    pub hot_reloader: Option<HotReloader>,
+    /// Heap objects whose properties and elements are read-only
+    pub frozen_objects: HashSet<usize>,
+    /// Attributes of the properties defined with `Object.defineProperty`;
+    /// see [`property::attributes`]
+    pub property_attributes: HashMap<usize, HashMap<String, Attributes>>,
+    /// Prototype of each built-in error class, for the errors the VM throws
+    pub error_prototypes: HashMap<String, usize>,
+    /// Error raised by a native function with [`VM::throw_from_native`]
+    native_exception: Option<JsValue>,
+    /// Exception no handler caught; the run loops stop and return it
+    uncaught: Option<UncaughtError>,
+    /// Source maps of the files the program was loaded from
+    sources: Vec<stack_trace::LoadedSource>,
     pub compiler: Compiler,
     /// Async/await continuation state
     pub async_context: Option<AsyncContext>,
@@ -252,6 +190,9 @@ pub struct VM {
     pub resolved_queue: Vec<(ContinuationCallback, JsValue)>,
     /// Current promise being constructed (for resolve/reject callbacks)
     pub current_promise: Option<Promise>,
+    /// When set, console output is buffered here as (category, text) pairs
+    /// instead of being written to stdout/stderr (used by the debug adapter)
+    pub console_capture: Option<Vec<(String, String)>>,
 }
 
 impl Default for VM {
@@ -279,12 +220,16 @@ impl VM {
                 this_context: JsValue::Undefined,
                 new_target: None,
                 super_called: false,
-                resume_ip: None,
+                async_call: None,
             }],
             heap: Vec::new(),
             native_functions: Vec::new(),
             task_queue: VecDeque::new(),
             timers: Vec::new(),
+            next_timer_id: 1,
+            blocking: BlockingCalls::new(),
+            open_files: HashMap::new(),
+            fs_prototypes: HashMap::new(),
             program: Vec::new(),
             modules: HashMap::new(),
             ip: 0,
@@ -296,10 +241,27 @@ impl VM {
             async_runtime: None,
             async_task_tx: Some(tx),
             module_cache: ModuleCache::new(),
+            module_resolver: None,
+            module_linker: ModuleLinker::new(),
+            prepared_modules: HashMap::new(),
+            dynamic_import_loader: None,
+            promise_reaction: None,
+            async_resume: None,
+            suspended_calls: HashMap::new(),
+            next_suspended_call: 0,
+            import_metas: HashMap::new(),
+            hot_reloader: None,
+            frozen_objects: HashSet::new(),
+            property_attributes: HashMap::new(),
+            error_prototypes: HashMap::new(),
+            native_exception: None,
+            uncaught: None,
+            sources: Vec::new(),
             compiler: Compiler::new(),
             async_context: None,
             resolved_queue: Vec::new(),
             current_promise: None,
+            console_capture: None,
         }
     }
 
@@ -394,38 +356,228 @@ impl VM {
         })
     }
 
-    /// Compile and execute a module source, returning the export values
-    /// This is used by the ImportAsync handler to actually run imported modules
-    pub fn execute_module(
-        &mut self,
-        source: &str,
-        path: &Path,
-        export_names: &[String],
-    ) -> Result<HashMap<String, JsValue>, String> {
-        let syntax = if path.to_string_lossy().ends_with(".ts")
-            || path.to_string_lossy().ends_with(".tsx")
-        {
-            Some(Syntax::Typescript(TsSyntax {
-                decorators: true,
-                tsx: path.to_string_lossy().ends_with(".tsx"),
-                ..Default::default()
-            }))
-        } else if path.to_string_lossy().ends_with(".js")
-            || path.to_string_lossy().ends_with(".jsx")
+    /// Unwind to the innermost exception handler. With none left, the
+    /// exception is uncaught: the run stops and returns it as an error.
+    fn throw_value(&mut self, exception: JsValue) -> ExecResult {
+        // An exception that would unwind an async call rejects its promise
+        let handler_depth = self
+            .exception_handlers
+            .last()
+            .map_or(0, |handler| handler.call_stack_depth);
+        if let Some(index) = self
+            .call_stack
+            .iter()
+            .rposition(|frame| frame.async_call.is_some())
+            .filter(|&index| index >= handler_depth)
         {
-            Some(Syntax::Es(Default::default()))
+            return self.reject_async_call(index, exception);
+        }
+
+        // Find a handler
+        if let Some(handler) = self.exception_handlers.pop() {
+            // Unwind the stack to the handler's saved state
+            self.stack.truncate(handler.stack_depth);
+
+            // Unwind call stack if needed
+            while self.call_stack.len() > handler.call_stack_depth {
+                self.call_stack.pop();
+            }
+
+            if handler.catch_addr != 0 {
+                // We have a catch block - push exception and jump there
+                self.stack.push(exception);
+                self.ip = handler.catch_addr;
+
+                // If there's a finally, we need to remember to run it
+                // after the catch completes
+                if handler.finally_addr != 0 {
+                    // Re-push a handler for finally (catch_addr=0 means no catch, just finally)
+                    self.exception_handlers.push(ExceptionHandler {
+                        catch_addr: 0,
+                        finally_addr: handler.finally_addr,
+                        stack_depth: self.stack.len() - 1, // Exclude the exception value
+                        call_stack_depth: handler.call_stack_depth,
+                    });
+                }
+                return ExecResult::ContinueNoIpInc;
+            } else if handler.finally_addr != 0 {
+                // No catch, but there's a finally block
+                // Store exception for rethrow after finally
+                self.current_exception = Some(exception);
+                self.ip = handler.finally_addr;
+                return ExecResult::ContinueNoIpInc;
+            }
+        }
+
+        let report = match &exception {
+            JsValue::Object(ptr) => crate::stdlib::error::stack(self, *ptr).map(str::to_string),
+            _ => None,
+        };
+        let report = report.unwrap_or_else(|| crate::stdlib::inspect_value(self, &exception));
+        self.uncaught = Some(UncaughtError {
+            value: exception,
+            report,
+        });
+        ExecResult::Stop
+    }
+
+    /// Value of variable `name`: function frames are searched from innermost
+    /// to outermost, then the environment of the module the code belongs
+    /// to, then globals
+    fn lookup_variable(&self, name: &str) -> Option<JsValue> {
+        let (globals, frames) = self.call_stack.split_first()?;
+        frames
+            .iter()
+            .rev()
+            .find_map(|frame| frame.locals.get(name))
+            .or_else(|| self.module_linker.lookup(self.ip, name))
+            .or_else(|| globals.locals.get(name))
+            .cloned()
+    }
+
+    /// Unwind to the async call in frame `index`, reject its promise with
+    /// `exception` and return the promise to the call's caller
+    fn reject_async_call(&mut self, index: usize, exception: JsValue) -> ExecResult {
+        self.call_stack.truncate(index + 1);
+        let frame = self.call_stack.pop().expect("Missing frame");
+        let Some(async_call) = frame.async_call else {
+            unreachable!("frame {} is not an async call", index);
+        };
+        self.exception_handlers
+            .retain(|handler| handler.call_stack_depth <= index);
+        self.stack.truncate(async_call.stack_base);
+        self.settle_promise(&async_call.promise, exception, false);
+        self.stack.push(JsValue::Promise(async_call.promise));
+        self.ip = frame.return_address;
+        if self.ip == usize::MAX {
+            return ExecResult::Stop;
+        }
+        ExecResult::ContinueNoIpInc
+    }
+
+    /// Hand over the exception that stopped the last run, if any
+    fn take_uncaught(&mut self) -> Result<(), UncaughtError> {
+        match self.uncaught.take() {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+
+    /// Drop the frames, handlers and pending work an uncaught error left
+    /// behind, so the next program appended to the VM runs from the top level
+    pub fn reset_after_uncaught(&mut self) {
+        self.call_stack.truncate(1);
+        self.exception_handlers.clear();
+        self.current_exception = None;
+        self.task_queue.clear();
+        self.ip = self.program.len();
+    }
+
+    /// Throw a new instance of the built-in error class `name`
+    fn throw_error(&mut self, name: &str, message: String) -> ExecResult {
+        let error = self.error_value(name, message);
+        self.throw_value(error)
+    }
+
+    /// Raise a `name` error from a native function. The VM throws it when the
+    /// native returns, discarding the native's result.
+    pub fn throw_from_native(&mut self, name: &str, message: String) -> JsValue {
+        let error = self.error_value(name, message);
+        self.native_exception = Some(error);
+        JsValue::Undefined
+    }
+
+    /// Rethrow from a native function an exception raised by script it
+    /// called, such as a getter
+    pub fn rethrow_from_native(&mut self, exception: JsValue) -> JsValue {
+        self.native_exception = Some(exception);
+        JsValue::Undefined
+    }
+
+    /// A new instance of the built-in error class `name`, with a stack
+    /// trace from the current instruction
+    fn error_value(&mut self, name: &str, message: String) -> JsValue {
+        crate::stdlib::error::create(self, name, message)
+    }
+
+    /// `Maximum call stack size exceeded` once the call stack is full
+    fn check_call_depth(&mut self) -> Option<ExecResult> {
+        (self.call_stack.len() >= MAX_CALL_STACK_DEPTH)
+            .then(|| self.throw_error("RangeError", "Maximum call stack size exceeded".to_string()))
+    }
+
+    /// A TypeError for calling a value that is not a function
+    fn throw_not_callable(&mut self, value: &JsValue) -> ExecResult {
+        let value = crate::stdlib::inspect_value(self, value);
+        self.throw_error("TypeError", format!("{} is not a function", value))
+    }
+
+    /// A TypeError for `new` on a value that is not a constructor
+    fn throw_not_constructor(&mut self, value: &JsValue) -> ExecResult {
+        let value = crate::stdlib::inspect_value(self, value);
+        self.throw_error("TypeError", format!("{} is not a constructor", value))
+    }
+
+    /// A TypeError for reading (or with `setting`, assigning) property `key`
+    /// of null or undefined
+    fn throw_nullish_property(
+        &mut self,
+        target: &JsValue,
+        key: &JsValue,
+        setting: bool,
+    ) -> ExecResult {
+        let target = crate::stdlib::inspect_value(self, target);
+        let key = match key {
+            JsValue::String(s) => s.to_string(),
+            JsValue::Number(n) => number::to_string(*n),
+            key => crate::stdlib::inspect_value(self, key),
+        };
+        let message = if setting {
+            format!("Cannot set properties of {} (setting '{}')", target, key)
         } else {
-            Some(Syntax::Typescript(TsSyntax {
-                decorators: true,
-                ..Default::default()
-            }))
+            format!("Cannot read properties of {} (reading '{}')", target, key)
         };
+        self.throw_error("TypeError", message)
+    }
+
+    /// Throw if property `key` of the object at `ptr` can't be assigned:
+    /// modules are strict code, so a failed write throws
+    fn check_write(&mut self, ptr: usize, key: &str) -> Option<ExecResult> {
+        let message = property::check_write(self, ptr, key).err()?;
+        Some(self.throw_error("TypeError", message))
+    }
 
-        let bytecode = self
-            .compiler
-            .compile_with_syntax(source, syntax)
-            .map_err(|e| format!("Failed to compile module {}: {}", path.display(), e))?;
+    /// ToPrimitive of a date for arithmetic and comparison: its time value
+    fn date_to_number(&self, value: JsValue) -> JsValue {
+        match crate::stdlib::date::time_value(self, &value) {
+            Some(t) => JsValue::Number(t),
+            None => value,
+        }
+    }
+
+    /// ToPrimitive of a date or error for `+`: its `toString`
+    fn to_string_primitive(&self, value: JsValue) -> JsValue {
+        if let Some(t) = crate::stdlib::date::time_value(self, &value) {
+            return JsValue::String(crate::stdlib::date::to_string(t).into());
+        }
+        match value {
+            JsValue::Object(ptr) if crate::stdlib::error::is_error(self, ptr) => {
+                JsValue::String(crate::stdlib::error::to_string(self, ptr).into())
+            }
+            value => value,
+        }
+    }
 
+    /// Run a compiled module's top level in the current frame, with its
+    /// variables in the module's own environment. Exports reach the module's
+    /// namespace through the module linker.
+    pub fn execute_module(
+        &mut self,
+        namespace: usize,
+        bytecode: Vec<OpCode>,
+        source_map: BytecodeSourceMap,
+        path: &Path,
+    ) {
         // Save IP BEFORE appending program, because append_program modifies IP
         let saved_ip = self.ip;
         let saved_module_path = self.current_module_path.clone();
@@ -434,49 +586,85 @@ impl VM {
 
         let start_offset = self.append_program(bytecode);
         let end_offset = self.program.len();
+        self.add_source_map(start_offset..end_offset, path, source_map);
+        self.module_linker
+            .add_code(namespace, start_offset..end_offset);
 
         self.current_module_path = Some(path.to_path_buf());
         self.ip = start_offset;
 
-        // Execute only the module's bytecode, not the entire program
-        // We run until we hit the module's Halt or reach the end of module bytecode
-        loop {
-            if self.ip >= end_offset {
-                break;
-            }
-            if self.ip >= self.program.len() {
+        // Execute only the module's bytecode, not the entire program. Calls
+        // into functions of other modules leave this range, so it only ends
+        // the run while the module's top level is executing.
+        let depth = self.call_stack.len();
+        self.module_linker.begin_body(namespace, depth);
+        while self.ip < self.program.len() {
+            if self.ip >= end_offset && self.call_stack.len() <= depth {
                 break;
             }
-            let result = self.exec_one();
-            // Don't stop the entire VM on module Halt - just break from module execution
-            if result == ExecResult::Stop {
-                // Check if this is a Halt within the module
-                if self.ip > 0 && self.ip <= end_offset + 5 {
-                    // This is likely the module's Halt, break normally
-                    break;
-                }
-                // Otherwise, it's a real stop
+            // The module's Halt ends the module, not the VM
+            if self.exec_one() == ExecResult::Stop {
                 break;
             }
         }
+        self.module_linker.end_body();
 
         self.ip = saved_ip;
         self.current_module_path = saved_module_path;
         // Restore stack to prevent module execution from corrupting caller's stack
         self.stack = saved_stack;
+    }
 
-        let mut exports = HashMap::new();
-        let global_locals = &self.call_stack[0].locals;
+    /// Declare a variable in the current scope: the running module's
+    /// environment at its top level, otherwise the current frame
+    fn declare_variable(&mut self, name: String, value: JsValue) {
+        let depth = self.call_stack.len();
+        if let Some(namespace) = self.module_linker.body_at(depth) {
+            self.module_linker
+                .define(&mut self.heap, namespace, name, value);
+            return;
+        }
+        if depth == 1 && !self.module_linker.is_empty() {
+            self.module_linker
+                .publish_global(&mut self.heap, &name, &value);
+        }
+        self.call_stack
+            .last_mut()
+            .unwrap()
+            .locals
+            .insert(name, value);
+    }
 
-        for name in export_names {
-            if let Some(value) = global_locals.get(name) {
-                exports.insert(name.clone(), value.clone());
-            } else {
-                exports.insert(name.clone(), JsValue::Undefined);
+    /// Assign to an existing binding if found, otherwise declare it in the
+    /// current scope. Function frames come first, then the environment of the
+    /// module the code belongs to, then the globals.
+    fn assign_variable(&mut self, name: String, value: JsValue) {
+        let frame_index = self
+            .call_stack
+            .iter()
+            .skip(1)
+            .rposition(|frame| frame.locals.contains_key(&name))
+            .map(|index| index + 1);
+        if let Some(index) = frame_index {
+            self.call_stack[index].locals.insert(name, value);
+            return;
+        }
+        if self
+            .module_linker
+            .assign(&mut self.heap, self.ip, &name, &value)
+        {
+            return;
+        }
+        if self.call_stack[0].locals.contains_key(&name) {
+            // The entry script's top-level bindings live in the global frame
+            if !self.module_linker.is_empty() {
+                self.module_linker
+                    .publish_global(&mut self.heap, &name, &value);
             }
+            self.call_stack[0].locals.insert(name, value);
+        } else {
+            self.declare_variable(name, value);
         }
-
-        Ok(exports)
     }
 
     /// Poll a promise until it's resolved (synchronous wait)
@@ -489,18 +677,15 @@ impl VM {
             match promise.get_state() {
                 PromiseState::Fulfilled => {
                     let value = promise.get_value().unwrap_or(JsValue::Undefined);
-                    eprintln!("DEBUG poll_promise: fulfilled, value = {:?}", value);
                     return value;
                 }
                 PromiseState::Rejected => {
                     let value = promise.get_value().unwrap_or(JsValue::Undefined);
-                    eprintln!("DEBUG poll_promise: rejected, value = {:?}", value);
                     return value;
                 }
                 PromiseState::Pending => {
                     let elapsed = start.elapsed().as_millis();
                     if elapsed > timeout_ms as u128 {
-                        eprintln!("DEBUG poll_promise: timeout after {}ms", elapsed);
                         return JsValue::Undefined;
                     }
                     // Brief sleep to avoid busy-waiting
@@ -570,26 +755,346 @@ impl VM {
         idx
     }
 
-    pub fn schedule_timer(&mut self, callback: JsValue, delay_ms: u64) {
+    /// Run `task` after `delay`, then every `interval` if one is given.
+    /// Returns the id that [`VM::clear_timer`] cancels.
+    pub fn schedule_timer(
+        &mut self,
+        task: Task,
+        delay: Duration,
+        interval: Option<Duration>,
+    ) -> u32 {
+        let id = self.next_timer_id;
+        self.next_timer_id = self.next_timer_id.wrapping_add(1).max(1);
         self.timers.push(TimerTask {
-            due: Instant::now() + Duration::from_millis(delay_ms),
-            task: Task {
-                function_ptr: callback,
-                args: vec![],
-            },
+            id,
+            due: Instant::now() + delay,
+            interval,
+            task,
         });
+        id
+    }
+
+    /// Cancel a pending timeout or interval; unknown ids are ignored
+    pub fn clear_timer(&mut self, id: u32) {
+        self.timers.retain(|timer| timer.id != id);
+    }
+
+    /// Run `work` on a worker thread. The returned promise fulfills with
+    /// `fulfill` of an `Ok` result or rejects with `reject` of an `Err`; the
+    /// event loop runs both on this thread.
+    pub fn spawn_blocking<T, E, W, F, R>(&mut self, work: W, fulfill: F, reject: R) -> Promise
+    where
+        T: Send + 'static,
+        E: Send + 'static,
+        W: FnOnce() -> Result<T, E> + Send + 'static,
+        F: FnOnce(&mut VM, T) -> JsValue + Send + 'static,
+        R: FnOnce(&mut VM, E) -> JsValue + Send + 'static,
+    {
+        self.blocking.spawn(work, fulfill, reject)
+    }
+
+    /// Settle the promise of a finished blocking call
+    fn finish_blocking(&mut self, (promise, completion): (Promise, Completion)) {
+        match completion(self) {
+            Ok(value) => self.settle_promise(&promise, value, true),
+            Err(reason) => self.settle_promise(&promise, reason, false),
+        }
+    }
+
+    /// Queue `task` to run once the current task and earlier microtasks finish
+    pub fn queue_microtask(&mut self, task: Task) {
+        self.task_queue.push_back(task);
     }
 
     pub fn load_program(&mut self, bytecode: Vec<OpCode>) {
         self.program = bytecode;
         self.ip = 0;
         self.current_module_path = None;
+        self.sources.clear();
     }
 
     pub fn load_program_with_path(&mut self, bytecode: Vec<OpCode>, path: PathBuf) {
         self.program = bytecode;
         self.ip = 0;
         self.current_module_path = Some(path);
+        self.sources.clear();
+    }
+
+    /// Create a module's namespace object, cache it and register its exports
+    fn link_module(&mut self, path: &Path, source: String, exports: &[ExportBinding]) -> usize {
+        let path = path.to_path_buf();
+        let hash = ModuleCache::compute_hash(&path);
+        let mut namespace_props = HashMap::new();
+        namespace_props.insert(
+            "__path__".to_string(),
+            JsValue::String(path.to_string_lossy().into_owned().into()),
+        );
+        namespace_props.insert(
+            "__source__".to_string(),
+            JsValue::String(source.as_str().into()),
+        );
+        namespace_props.insert(
+            "__hash__".to_string(),
+            JsValue::String(hash.as_str().into()),
+        );
+        let namespace_ptr = self.heap.len();
+        self.heap.push(HeapObject {
+            data: HeapData::Object(namespace_props),
+        });
+        self.module_cache.insert(CachedModule {
+            path: path.clone(),
+            source,
+            hash,
+            load_time: std::time::SystemTime::now(),
+            namespace_object: namespace_ptr,
+        });
+        self.module_linker.register(namespace_ptr, path, exports);
+        namespace_ptr
+    }
+
+    /// Register the entry script as a module, so an import cycle back to it
+    /// shares its bindings instead of running it a second time
+    pub fn register_entry_module(&mut self, path: &Path, source: &str, exports: &[ExportBinding]) {
+        let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        let namespace_ptr = self.link_module(&path, source.to_string(), exports);
+        self.module_linker.set_entry(namespace_ptr);
+    }
+
+    /// Settle a promise and queue the `then`/`catch` callbacks waiting on it
+    fn settle_promise(&mut self, promise: &Promise, value: JsValue, fulfilled: bool) {
+        let handlers = std::mem::take(&mut promise.state.lock().unwrap().handlers);
+        promise.set_value(value.clone(), fulfilled);
+        for handler in handlers {
+            self.queue_reaction(handler, value.clone(), fulfilled);
+        }
+    }
+
+    /// `promise.then(on_fulfilled, on_rejected)`. A handler that is not a
+    /// function passes the settled value on to the returned promise.
+    pub fn promise_then(
+        &mut self,
+        promise: &Promise,
+        on_fulfilled: JsValue,
+        on_rejected: JsValue,
+    ) -> Promise {
+        let derived = Promise::new();
+        let handler = PromiseHandler {
+            on_fulfilled: Some(Box::new(on_fulfilled)),
+            on_rejected: Some(Box::new(on_rejected)),
+            continuation: None,
+            derived: Some(derived.clone()),
+        };
+        if let Some((handler, value, fulfilled)) = promise.react(handler) {
+            self.queue_reaction(handler, value, fulfilled);
+        }
+        derived
+    }
+
+    /// Queue the callback of `handler` for a promise that settled with `value`
+    fn queue_reaction(&mut self, handler: PromiseHandler, value: JsValue, fulfilled: bool) {
+        if let Some(continuation) = handler.continuation {
+            let resume = match self.async_resume {
+                Some(idx) => idx,
+                None => {
+                    let idx = self.register_native(Self::resume_async_call);
+                    self.async_resume = Some(idx);
+                    idx
+                }
+            };
+            self.task_queue.push_back(Task {
+                function_ptr: JsValue::NativeFunction(resume),
+                args: vec![
+                    JsValue::Number(continuation.call_id as f64),
+                    value,
+                    JsValue::Boolean(fulfilled),
+                ],
+            });
+            return;
+        }
+        let callback = if fulfilled {
+            handler.on_fulfilled
+        } else {
+            handler.on_rejected
+        };
+        let Some(derived) = handler.derived else {
+            if let Some(callback) = callback {
+                self.task_queue.push_back(Task {
+                    function_ptr: *callback,
+                    args: vec![value],
+                });
+            }
+            return;
+        };
+        let reaction = match self.promise_reaction {
+            Some(idx) => idx,
+            None => {
+                let idx = self.register_native(Self::run_promise_reaction);
+                self.promise_reaction = Some(idx);
+                idx
+            }
+        };
+        self.task_queue.push_back(Task {
+            function_ptr: JsValue::NativeFunction(reaction),
+            args: vec![
+                callback.map_or(JsValue::Undefined, |callback| *callback),
+                value,
+                JsValue::Boolean(fulfilled),
+                JsValue::Promise(derived),
+            ],
+        });
+    }
+
+    /// Task queued for a `then` reaction: args are [callback, settled value,
+    /// fulfilled, promise returned by `then`]
+    fn run_promise_reaction(vm: &mut VM, args: Vec<JsValue>) -> JsValue {
+        let mut args = args.into_iter();
+        let callback = args.next().unwrap_or(JsValue::Undefined);
+        let value = args.next().unwrap_or(JsValue::Undefined);
+        let fulfilled = matches!(args.next(), Some(JsValue::Boolean(true)));
+        let Some(JsValue::Promise(derived)) = args.next() else {
+            return JsValue::Undefined;
+        };
+        if !matches!(
+            callback,
+            JsValue::Function { .. } | JsValue::NativeFunction(_)
+        ) {
+            vm.settle_promise(&derived, value, fulfilled);
+            return JsValue::Undefined;
+        }
+        match vm.call_sync(&callback, JsValue::Undefined, vec![value]) {
+            Ok(result) => vm.resolve_promise(&derived, result),
+            Err(reason) => vm.settle_promise(&derived, reason, false),
+        }
+        JsValue::Undefined
+    }
+
+    /// Suspend the async call running in the top frame until `awaited`
+    /// settles, and return its promise to the caller
+    fn suspend_async_call(&mut self, awaited: Promise) -> ExecResult {
+        let depth = self.call_stack.len();
+        let frame = self.call_stack.pop().expect("Missing frame");
+        let Some(async_call) = frame.async_call.clone() else {
+            unreachable!("only async calls are suspended");
+        };
+        let stack = self.stack.split_off(async_call.stack_base);
+        let first_handler = self
+            .exception_handlers
+            .iter()
+            .position(|handler| handler.call_stack_depth >= depth)
+            .unwrap_or(self.exception_handlers.len());
+        let handlers = self
+            .exception_handlers
+            .split_off(first_handler)
+            .into_iter()
+            .map(|handler| ExceptionHandler {
+                stack_depth: handler.stack_depth - async_call.stack_base,
+                call_stack_depth: handler.call_stack_depth - depth,
+                ..handler
+            })
+            .collect();
+
+        let call_id = self.next_suspended_call;
+        self.next_suspended_call += 1;
+        let return_address = frame.return_address;
+        self.suspended_calls.insert(
+            call_id,
+            SuspendedCall {
+                frame,
+                stack,
+                handlers,
+                current_exception: self.current_exception.take(),
+                resume_ip: self.ip + 1,
+            },
+        );
+        // Even a settled promise resumes the call from a microtask
+        let handler = PromiseHandler {
+            on_fulfilled: None,
+            on_rejected: None,
+            continuation: Some(Continuation { call_id }),
+            derived: None,
+        };
+        if let Some((handler, value, fulfilled)) = awaited.react(handler) {
+            self.queue_reaction(handler, value, fulfilled);
+        }
+
+        self.stack.push(JsValue::Promise(async_call.promise));
+        self.ip = return_address;
+        if self.ip == usize::MAX {
+            return ExecResult::Stop;
+        }
+        ExecResult::ContinueNoIpInc
+    }
+
+    /// Task queued when the promise a suspended async call awaits settles:
+    /// args are [call id, settled value, fulfilled]
+    fn resume_async_call(vm: &mut VM, args: Vec<JsValue>) -> JsValue {
+        let mut args = args.into_iter();
+        let Some(JsValue::Number(call_id)) = args.next() else {
+            return JsValue::Undefined;
+        };
+        let value = args.next().unwrap_or(JsValue::Undefined);
+        let fulfilled = matches!(args.next(), Some(JsValue::Boolean(true)));
+        let Some(mut call) = vm.suspended_calls.remove(&(call_id as usize)) else {
+            return JsValue::Undefined;
+        };
+
+        let stack_base = vm.stack.len();
+        if let Some(async_call) = &mut call.frame.async_call {
+            async_call.stack_base = stack_base;
+        }
+        // The caller already has the promise; the resumed call returns to the task
+        call.frame.return_address = usize::MAX;
+        vm.call_stack.push(call.frame);
+        let depth = vm.call_stack.len();
+        vm.stack.extend(call.stack);
+        vm.exception_handlers
+            .extend(call.handlers.into_iter().map(|handler| ExceptionHandler {
+                stack_depth: handler.stack_depth + stack_base,
+                call_stack_depth: handler.call_stack_depth + depth,
+                ..handler
+            }));
+        vm.current_exception = call.current_exception;
+
+        vm.ip = call.resume_ip;
+        if fulfilled {
+            vm.stack.push(value);
+        } else {
+            // Awaiting a rejected promise throws its reason at the `await`
+            vm.ip -= 1;
+            if vm.throw_value(value) == ExecResult::Stop {
+                return JsValue::Undefined;
+            }
+        }
+        vm.run_until_return_sentinel();
+        JsValue::Undefined
+    }
+
+    /// Resolve `promise` with `value`. A promise value is adopted: `promise`
+    /// settles the way `value` does, once it does.
+    fn resolve_promise(&mut self, promise: &Promise, value: JsValue) {
+        let JsValue::Promise(inner) = value else {
+            self.settle_promise(promise, value, true);
+            return;
+        };
+        if Arc::ptr_eq(&inner.state, &promise.state) {
+            let error = crate::stdlib::error::create(
+                self,
+                "TypeError",
+                "Chaining cycle detected for promise".to_string(),
+            );
+            self.settle_promise(promise, error, false);
+            return;
+        }
+        // No callbacks, so the reaction passes the settled value straight on
+        let handler = PromiseHandler {
+            on_fulfilled: None,
+            on_rejected: None,
+            continuation: None,
+            derived: Some(promise.clone()),
+        };
+        if let Some((handler, value, fulfilled)) = inner.react(handler) {
+            self.queue_reaction(handler, value, fulfilled);
+        }
     }
 
     /// Update the current module path (for relative imports)
@@ -639,32 +1144,90 @@ impl VM {
         start_offset
     }
 
-    pub fn run_event_loop(&mut self) {
+    pub fn run_event_loop(&mut self) -> Result<(), UncaughtError> {
         // 1) Run the initial script to completion.
-        self.run_until_halt();
+        self.run_until_halt()?;
 
-        // 2) Drain the event loop: timers -> task queue -> execute task.
-        loop {
-            self.pump_timers();
+        // 2) Drain the event loop: microtasks first, then due timers.
+        self.drain_event_loop()
+    }
 
-            if let Some(task) = self.task_queue.pop_front() {
-                self.execute_task(task);
-                continue;
-            }
+    /// Run queued tasks and timers until no work is left.
+    pub fn drain_event_loop(&mut self) -> Result<(), UncaughtError> {
+        while self.run_event_loop_step()? {}
+        Ok(())
+    }
 
-            // No immediate tasks left.
-            if self.timers.is_empty() {
-                break;
-            }
+    /// Run a single unit of event loop work, sleeping until the next timer
+    /// or blocking call is done if nothing is ready. Returns false once the
+    /// loop is idle.
+    ///
+    /// The instruction pointer and operand stack are restored afterwards, so
+    /// this is safe to call while a program is suspended mid-instruction.
+    pub fn run_event_loop_step(&mut self) -> Result<bool, UncaughtError> {
+        self.run_event_loop_step_until(None)
+    }
 
-            // Timers exist but none ready: sleep until the next one is due.
-            if let Some(next_due) = self.next_timer_due() {
+    /// Like [`VM::run_event_loop_step`], but never sleeps past `deadline`
+    pub fn run_event_loop_step_until(
+        &mut self,
+        deadline: Option<Instant>,
+    ) -> Result<bool, UncaughtError> {
+        let busy = self.event_loop_step(deadline);
+        self.take_uncaught()?;
+        Ok(busy)
+    }
+
+    /// One unit of event loop work; an exception a task doesn't catch is
+    /// left in `uncaught`
+    fn event_loop_step(&mut self, deadline: Option<Instant>) -> bool {
+        // A timer only fires once the microtask queue is empty
+        let task = match self.task_queue.pop_front() {
+            Some(task) => Some(task),
+            None => self.take_due_timer(),
+        };
+        if let Some(task) = task {
+            let saved_ip = self.ip;
+            let saved_stack_len = self.stack.len();
+            self.execute_task(task);
+            self.ip = saved_ip;
+            self.stack.truncate(saved_stack_len);
+            return true;
+        }
+        if let Some(finished) = self.blocking.try_next_finished() {
+            self.finish_blocking(finished);
+            return true;
+        }
+
+        // Nothing ready: wait for the next timer or blocking call
+        let next_due = self.next_timer_due();
+        let wake = match (next_due, deadline) {
+            (Some(next_due), Some(deadline)) => Some(next_due.min(deadline)),
+            (next_due, deadline) => next_due.or(deadline),
+        };
+        if !self.blocking.is_idle() {
+            if let Some(finished) = self.blocking.next_finished(wake) {
+                self.finish_blocking(finished);
+            }
+            return true;
+        }
+        match wake {
+            Some(wake) if next_due.is_some() => {
                 let now = Instant::now();
-                if next_due > now {
-                    std::thread::sleep(next_due - now);
+                if wake > now {
+                    std::thread::sleep(wake - now);
                 }
-            } else {
-                // This shouldn't happen if timers is not empty, but handle it anyway
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Drive the event loop until `promise` settles, no work is left or a
+    /// task throws an exception it doesn't catch
+    fn settle_on_event_loop(&mut self, promise: &Promise) {
+        while promise.get_state() == PromiseState::Pending && self.uncaught.is_none() {
+            if !self.event_loop_step(None) {
                 break;
             }
         }
@@ -674,17 +1237,24 @@ impl VM {
         self.timers.iter().map(|t| t.due).min()
     }
 
-    fn pump_timers(&mut self) {
+    /// Take the task of the earliest due timer, in scheduling order among
+    /// timers due at the same instant. Intervals are re-armed, not removed.
+    fn take_due_timer(&mut self) -> Option<Task> {
         let now = Instant::now();
-        // Move all due timers into the task queue.
-        let mut i = 0;
-        while i < self.timers.len() {
-            if self.timers[i].due <= now {
-                let timer = self.timers.remove(i);
-                self.task_queue.push_back(timer.task);
-            } else {
-                i += 1;
+        let index = self
+            .timers
+            .iter()
+            .enumerate()
+            .filter(|(_, timer)| timer.due <= now)
+            .min_by_key(|(_, timer)| (timer.due, timer.id))
+            .map(|(index, _)| index)?;
+        match self.timers[index].interval {
+            Some(interval) => {
+                let timer = &mut self.timers[index];
+                timer.due = now + interval;
+                Some(timer.task.clone())
             }
+            None => Some(self.timers.remove(index).task),
         }
     }
 
@@ -703,12 +1273,17 @@ impl VM {
     }
 
     fn execute_task(&mut self, task: Task) {
+        // A task starts on an empty handler stack: the `try` blocks of code
+        // suspended in an `await` must not catch its exceptions
+        let handlers = std::mem::take(&mut self.exception_handlers);
+        self.run_task(task);
+        self.exception_handlers = handlers;
+    }
+
+    fn run_task(&mut self, task: Task) {
         // Stack overflow protection
-        if self.call_stack.len() >= MAX_CALL_STACK_DEPTH {
-            panic!(
-                "Stack overflow: maximum call depth of {} exceeded",
-                MAX_CALL_STACK_DEPTH
-            );
+        if self.check_call_depth().is_some() {
+            return;
         }
 
         match task.function_ptr {
@@ -725,7 +1300,7 @@ impl VM {
                     this_context: JsValue::Undefined,
                     new_target: None,
                     super_called: false,
-                    resume_ip: None,
+                    async_call: None,
                 };
 
                 // CLOSURE MAGIC: If this function has captured variables (env),
@@ -748,10 +1323,139 @@ impl VM {
             JsValue::NativeFunction(idx) => {
                 let func = self.native_functions[idx];
                 let _ = func(self, task.args);
+                // The task's handler stack is empty, so this ends the run
+                if let Some(exception) = self.native_exception.take() {
+                    self.throw_value(exception);
+                }
+            }
+
+            target => {
+                self.throw_not_callable(&target);
+            }
+        }
+    }
+
+    /// Call `callee` to completion from inside an instruction or a native,
+    /// for built-ins that take callbacks. An exception the callee doesn't
+    /// catch comes back as `Err` for the caller to rethrow.
+    pub fn call_sync(
+        &mut self,
+        callee: &JsValue,
+        this: JsValue,
+        args: Vec<JsValue>,
+    ) -> Result<JsValue, JsValue> {
+        match *callee {
+            JsValue::Function { address, env } => {
+                if self.call_stack.len() >= MAX_CALL_STACK_DEPTH {
+                    let message = "Maximum call stack size exceeded".to_string();
+                    return Err(self.error_value("RangeError", message));
+                }
+                let saved_ip = self.ip;
+                let depth = self.call_stack.len();
+                let handlers = self.exception_handlers.len();
+                // Catches what the callee throws, by jumping to the sentinel
+                self.exception_handlers.push(ExceptionHandler {
+                    catch_addr: CALL_SYNC_CATCH,
+                    finally_addr: 0,
+                    stack_depth: self.stack.len(),
+                    call_stack_depth: depth,
+                });
+
+                // The prologue pops exactly one argument per parameter
+                let mut args = args;
+                args.resize(self.param_count(address), JsValue::Undefined);
+                self.stack.extend(args);
+
+                let mut frame = Frame {
+                    return_address: usize::MAX,
+                    locals: HashMap::new(),
+                    indexed_locals: Vec::new(),
+                    this_context: this,
+                    new_target: None,
+                    super_called: false,
+                    async_call: None,
+                };
+                if let Some(HeapObject {
+                    data: HeapData::Object(props),
+                }) = env.and_then(|ptr| self.heap.get(ptr))
+                {
+                    for (name, value) in props {
+                        frame.locals.insert(name.clone(), value.clone());
+                    }
+                }
+
+                self.call_stack.push(frame);
+                self.ip = address;
+                while self.ip != usize::MAX && self.ip != CALL_SYNC_CATCH {
+                    if self.exec_one() == ExecResult::Stop {
+                        break;
+                    }
+                }
+                let result = self.stack.pop().unwrap_or(JsValue::Undefined);
+                let caught = self.ip == CALL_SYNC_CATCH;
+                self.ip = saved_ip;
+                self.call_stack.truncate(depth);
+                self.exception_handlers.truncate(handlers);
+                if caught { Err(result) } else { Ok(result) }
             }
+            JsValue::NativeFunction(idx) => {
+                self.call_stack.push(Frame {
+                    return_address: self.ip + 1,
+                    locals: HashMap::new(),
+                    indexed_locals: Vec::new(),
+                    this_context: this,
+                    new_target: None,
+                    super_called: false,
+                    async_call: None,
+                });
+                let result = self.native_functions[idx](self, args);
+                self.call_stack.pop();
+                match self.native_exception.take() {
+                    Some(exception) => Err(exception),
+                    None => Ok(result),
+                }
+            }
+            _ => {
+                let shown = crate::stdlib::inspect_value(self, callee);
+                Err(self.error_value("TypeError", format!("{} is not a function", shown)))
+            }
+        }
+    }
 
-            _ => panic!("Target is not callable"),
+    /// The number of parameters of the function at `address`: its prologue
+    /// binds each with a `Let`
+    fn param_count(&self, address: usize) -> usize {
+        self.program[address..]
+            .iter()
+            .take_while(|op| matches!(op, OpCode::Let(_)))
+            .count()
+    }
+
+    /// `map.forEach(callback, thisArg)` and `set.forEach(...)`. Entries
+    /// deleted before their turn are skipped; entries added during the loop
+    /// are not visited.
+    fn collection_for_each(&mut self, ptr: usize, args: Vec<JsValue>) -> ExecResult {
+        let mut args = args.into_iter();
+        let callback = args.next().unwrap_or(JsValue::Undefined);
+        let this = args.next().unwrap_or(JsValue::Undefined);
+        if !matches!(
+            callback,
+            JsValue::Function { .. } | JsValue::NativeFunction(_)
+        ) {
+            return self.throw_not_callable(&callback);
         }
+        for (key, _) in crate::stdlib::collections::entries(self, ptr) {
+            let Some(value) = crate::stdlib::collections::lookup(self, ptr, &key) else {
+                continue;
+            };
+            let args = vec![value, key, JsValue::Object(ptr)];
+            if let Err(exception) = self.call_sync(&callback, this.clone(), args) {
+                return self.throw_value(exception);
+            }
+        }
+        self.stack.push(JsValue::Undefined);
+        self.ip += 1;
+        ExecResult::Continue
     }
 
     fn run_until_return_sentinel(&mut self) {
@@ -769,7 +1473,35 @@ impl VM {
         }
     }
 
-    pub fn run_until_halt(&mut self) {
+    /// Execute a single instruction. Returns false once the program halts.
+    pub fn step(&mut self) -> Result<bool, UncaughtError> {
+        if self.ip >= self.program.len() {
+            return Ok(false);
+        }
+        self.total_instructions += 1;
+        let running = self.exec_one() != ExecResult::Stop;
+        self.take_uncaught()?;
+        Ok(running)
+    }
+
+    /// Write console output, honouring `console_capture`.
+    /// `category` is "stdout" or "stderr"; `text` should include any trailing newline.
+    pub fn write_console(&mut self, category: &str, text: &str) {
+        use std::io::Write;
+        match &mut self.console_capture {
+            Some(buffer) => buffer.push((category.to_string(), text.to_string())),
+            None if category == "stderr" => {
+                let _ = std::io::stderr().write_all(text.as_bytes());
+            }
+            None => {
+                let mut stdout = std::io::stdout();
+                let _ = stdout.write_all(text.as_bytes());
+                let _ = stdout.flush();
+            }
+        }
+    }
+
+    pub fn run_until_halt(&mut self) -> Result<(), UncaughtError> {
         loop {
             if self.ip >= self.program.len() {
                 break;
@@ -778,10 +1510,13 @@ impl VM {
                 break;
             }
         }
+        self.take_uncaught()
     }
 
     fn exec_one(&mut self) -> ExecResult {
-        if self.ip >= self.program.len() {
+        // An uncaught exception in a nested run, such as a module body or a
+        // task run by `await`, stops every run loop above it too
+        if self.ip >= self.program.len() || self.uncaught.is_some() {
             return ExecResult::Stop;
         }
         let op = self.program[self.ip].clone();
@@ -821,9 +1556,8 @@ impl VM {
                 let target = self.stack.pop().unwrap();
                 if let JsValue::Object(ptr) = target {
                     // Check for setter in prototype chain
-                    let setter_addr_and_env = self.find_setter_with_proto_chain(ptr, &name);
-
-                    if let Some((address, env)) = setter_addr_and_env {
+                    let setter = property::find_setter(self, ptr, &name);
+                    if let Some(JsValue::Function { address, env }) = setter {
                         self.stack.push(value.clone());
                         let this_context = JsValue::Object(ptr);
                         let mut frame = Frame {
@@ -833,7 +1567,7 @@ impl VM {
                             this_context,
                             new_target: None,
                             super_called: false,
-                            resume_ip: None,
+                            async_call: None,
                         };
 
                         if let Some(HeapObject {
@@ -850,14 +1584,27 @@ impl VM {
                         return ExecResult::ContinueNoIpInc;
                     }
 
+                    // A native setter from Object.defineProperty
+                    if setter.is_some() {
+                        if let Err(exception) = property::set(self, ptr, &name, value) {
+                            return self.throw_value(exception);
+                        }
+                        self.ip += 1;
+                        return ExecResult::Continue;
+                    }
+
                     // No setter found, store the value directly
+                    if let Some(thrown) = self.check_write(ptr, &name) {
+                        return thrown;
+                    }
                     if let Some(heap_item) = self.heap.get_mut(ptr)
                         && let HeapData::Object(props) = &mut heap_item.data
                     {
                         props.insert(name.to_string(), value);
                     }
-                } else {
-                    // Object was not an Object, silently ignore or could panic
+                } else if let JsValue::Undefined | JsValue::Null = target {
+                    let key = JsValue::String(name.as_str().into());
+                    return self.throw_nullish_property(&target, &key, true);
                 }
             }
 
@@ -870,27 +1617,59 @@ impl VM {
                 if let JsValue::Object(ptr) = target {
                     // Convert key to string
                     let key_name = match &key_val {
-                        JsValue::String(s) => s.clone(),
-                        JsValue::Number(n) => n.to_string(),
+                        JsValue::String(s) => s.to_string(),
+                        JsValue::Number(n) => number::to_string(*n),
                         JsValue::Object(_) => {
                             // For objects, use default string representation
                             "[object Object]".to_string()
                         }
                         _ => format!("{:?}", key_val),
                     };
+                    if property::find_setter(self, ptr, &key_name).is_some() {
+                        if let Err(exception) = property::set(self, ptr, &key_name, value) {
+                            return self.throw_value(exception);
+                        }
+                        self.ip += 1;
+                        return ExecResult::Continue;
+                    }
+                    if let Some(thrown) = self.check_write(ptr, &key_name) {
+                        return thrown;
+                    }
+                    if crate::stdlib::typed_array::set(self, ptr, &key_val, &value) {
+                        self.ip += 1;
+                        return ExecResult::Continue;
+                    }
 
-                    if let Some(heap_item) = self.heap.get_mut(ptr)
-                        && let HeapData::Object(props) = &mut heap_item.data
-                    {
-                        props.insert(key_name, value);
+                    match self.heap.get_mut(ptr).map(|obj| &mut obj.data) {
+                        Some(HeapData::Object(props)) => {
+                            props.insert(key_name, value);
+                        }
+                        // Array index: extend the array like StoreElement
+                        Some(HeapData::Array(arr)) => {
+                            if let Ok(i) = key_name.parse::<usize>() {
+                                if i >= arr.len() {
+                                    arr.resize(i + 1, JsValue::Undefined);
+                                }
+                                arr[i] = value;
+                            }
+                        }
+                        _ => {}
                     }
+                } else if let JsValue::Undefined | JsValue::Null = target {
+                    return self.throw_nullish_property(&target, &key_val, true);
                 }
             }
 
             OpCode::GetPropComputed => {
                 // Pops [obj, key] -> pushes obj[key]
                 if self.stack.len() < 2 {
-                    panic!("GetPropComputed with insufficient stack at ip={}", self.ip);
+                    return self.throw_error(
+                        "TypeError",
+                        format!(
+                            "Missing operands for a computed property read at ip={}",
+                            self.ip
+                        ),
+                    );
                 }
                 let key_val = self.stack.pop().unwrap();
                 let target = self.stack.pop().unwrap();
@@ -907,13 +1686,21 @@ impl VM {
                             self.ip += 1;
                             return ExecResult::Continue;
                         }
-                        self.stack.push(JsValue::Undefined);
+                        let key = JsValue::Number(idx);
+                        let value = crate::stdlib::typed_array::get(self, ptr, &key);
+                        self.stack.push(value.unwrap_or(JsValue::Undefined));
+                    }
+                    (JsValue::Object(ptr), key_val)
+                        if let Some(value) =
+                            crate::stdlib::typed_array::get(self, ptr, &key_val) =>
+                    {
+                        self.stack.push(value);
                     }
                     (JsValue::Object(ptr), key_val) => {
                         // Convert key to string
                         let key_name = match &key_val {
-                            JsValue::String(s) => s.clone(),
-                            JsValue::Number(n) => n.to_string(),
+                            JsValue::String(s) => s.to_string(),
+                            JsValue::Number(n) => number::to_string(*n),
                             JsValue::Object(_) => "[object Object]".to_string(),
                             _ => format!("{:?}", key_val),
                         };
@@ -963,8 +1750,10 @@ impl VM {
                                 }
                                 _ => {
                                     // Object access: obj[key] - look up with prototype chain
-                                    let value = self.get_prop_with_proto_chain(ptr, &key_name);
-                                    self.stack.push(value.clone());
+                                    match property::get(self, ptr, &key_name) {
+                                        Ok(value) => self.stack.push(value),
+                                        Err(exception) => return self.throw_value(exception),
+                                    }
                                 }
                             }
                         } else {
@@ -972,26 +1761,11 @@ impl VM {
                         }
                     }
                     (JsValue::String(s), JsValue::Number(idx)) => {
-                        // String char access: str[index]
-                        // Use O(1) byte indexing for ASCII strings (common case)
-                        let i = idx as usize;
-                        let bytes = s.as_bytes();
-                        let char_val = if i < bytes.len() {
-                            let b = bytes[i];
-                            if b < 128 {
-                                // ASCII: O(1) fast path
-                                JsValue::String((b as char).to_string())
-                            } else {
-                                // Non-ASCII: fallback to chars().nth() - O(n) but rare
-                                s.chars()
-                                    .nth(i)
-                                    .map(|c| JsValue::String(c.to_string()))
-                                    .unwrap_or(JsValue::Undefined)
-                            }
-                        } else {
-                            JsValue::Undefined
-                        };
-                        self.stack.push(char_val);
+                        // String code unit access: str[index]
+                        self.stack.push(string_element(&s, idx));
+                    }
+                    (target @ (JsValue::Undefined | JsValue::Null), key_val) => {
+                        return self.throw_nullish_property(&target, &key_val, false);
                     }
                     _ => {
                         self.stack.push(JsValue::Undefined);
@@ -1020,7 +1794,7 @@ impl VM {
                                             this_context,
                                             new_target: None,
                                             super_called: false,
-                                            resume_ip: None,
+                                            async_call: None,
                                         };
 
                                         if let Some(HeapObject {
@@ -1038,7 +1812,14 @@ impl VM {
                                     }
 
                                     let val = self.get_prop_with_proto_chain(ptr, &name);
-                                    self.stack.push(val);
+                                    if let JsValue::Accessor(..) = val {
+                                        match property::get(self, ptr, &name) {
+                                            Ok(val) => self.stack.push(val),
+                                            Err(exception) => return self.throw_value(exception),
+                                        }
+                                    } else {
+                                        self.stack.push(val);
+                                    }
                                 }
                                 HeapData::Array(arr) => {
                                     if name == "length" {
@@ -1099,6 +1880,20 @@ impl VM {
                                         self.stack.push(JsValue::Undefined);
                                     }
                                 }
+                                // Date, weak collection and iterator methods are handled by CallMethod
+                                HeapData::Date(_)
+                                | HeapData::WeakMap(_)
+                                | HeapData::WeakSet(_)
+                                | HeapData::CollectionIterator { .. } => {
+                                    self.stack.push(JsValue::Undefined);
+                                }
+                                HeapData::ArrayBuffer(_)
+                                | HeapData::TypedArray { .. }
+                                | HeapData::DataView { .. } => {
+                                    let key = JsValue::String(name.into());
+                                    let value = crate::stdlib::typed_array::get(self, ptr, &key);
+                                    self.stack.push(value.unwrap_or(JsValue::Undefined));
+                                }
                             }
                         } else {
                             self.stack.push(JsValue::Undefined);
@@ -1115,11 +1910,15 @@ impl VM {
                     }
                     Some(JsValue::String(s)) => {
                         if name == "length" {
-                            self.stack.push(JsValue::Number(s.len() as f64));
+                            self.stack.push(JsValue::Number(s.length() as f64));
                         } else {
                             self.stack.push(JsValue::Undefined);
                         }
                     }
+                    Some(target @ (JsValue::Undefined | JsValue::Null)) => {
+                        let key = JsValue::String(name.as_str().into());
+                        return self.throw_nullish_property(&target, &key, false);
+                    }
                     _ => {
                         // For any other type, push undefined
                         self.stack.push(JsValue::Undefined);
@@ -1136,35 +1935,23 @@ impl VM {
                     eprintln!("Stack depth: {}", self.stack.len());
                     return ExecResult::Stop;
                 }
-                self.call_stack.last_mut().unwrap().locals.insert(name, val);
+                self.declare_variable(name, val);
             }
 
             OpCode::Store(name) => {
                 let val = self.stack.pop().unwrap_or(JsValue::Undefined);
-                // Assign to an existing binding if found, otherwise create in current frame.
-                let mut stored = false;
-                for frame in self.call_stack.iter_mut().rev() {
-                    if frame.locals.contains_key(&name) {
-                        frame.locals.insert(name.clone(), val.clone());
-                        stored = true;
-                        break;
-                    }
-                }
-                if !stored {
-                    self.call_stack.last_mut().unwrap().locals.insert(name, val);
-                }
+                self.assign_variable(name, val);
             }
 
-            OpCode::Load(name) => {
-                // Search for variable from innermost to outermost frame.
-                let mut found = None;
-                for frame in self.call_stack.iter().rev() {
-                    if let Some(v) = frame.locals.get(&name) {
-                        found = Some(v.clone());
-                        break;
-                    }
+            OpCode::Load(name) => match self.lookup_variable(&name) {
+                Some(value) => self.stack.push(value),
+                None => {
+                    return self.throw_error("ReferenceError", format!("{} is not defined", name));
                 }
-                let value = found.unwrap_or(JsValue::Undefined);
+            },
+
+            OpCode::LoadOrUndefined(name) => {
+                let value = self.lookup_variable(&name).unwrap_or(JsValue::Undefined);
                 self.stack.push(value);
             }
 
@@ -1179,11 +1966,8 @@ impl VM {
 
             OpCode::Call(arg_count) => {
                 // Stack overflow protection
-                if self.call_stack.len() >= MAX_CALL_STACK_DEPTH {
-                    panic!(
-                        "Stack overflow: maximum call depth of {} exceeded",
-                        MAX_CALL_STACK_DEPTH
-                    );
+                if let Some(result) = self.check_call_depth() {
+                    return result;
                 }
 
                 let callee = self.stack.pop().expect("Missing callee");
@@ -1209,7 +1993,7 @@ impl VM {
                             this_context: JsValue::Undefined,
                             new_target: None,
                             super_called: false,
-                            resume_ip: None,
+                            async_call: None,
                         };
 
                         // CLOSURE CONTEXT SWITCH: Load captured variables from
@@ -1229,9 +2013,11 @@ impl VM {
                         return ExecResult::ContinueNoIpInc;
                     }
                     JsValue::NativeFunction(idx) => {
-                        args.reverse();
                         let func = self.native_functions[idx];
                         let result = func(self, args);
+                        if let Some(exception) = self.native_exception.take() {
+                            return self.throw_value(exception);
+                        }
                         self.stack.push(result);
                     }
                     JsValue::Object(ptr) => {
@@ -1242,9 +2028,11 @@ impl VM {
                         {
                             if let Some(JsValue::NativeFunction(idx)) = props.get("__call__") {
                                 let idx = *idx;
-                                args.reverse();
                                 let func = self.native_functions[idx];
                                 let result = func(self, args);
+                                if let Some(exception) = self.native_exception.take() {
+                                    return self.throw_value(exception);
+                                }
                                 self.stack.push(result);
                             } else if let Some(JsValue::Function { address, env }) =
                                 props.get("__call__")
@@ -1261,7 +2049,7 @@ impl VM {
                                     this_context: JsValue::Object(ptr),
                                     new_target: None,
                                     super_called: false,
-                                    resume_ip: None,
+                                    async_call: None,
                                 };
                                 if let Some(HeapObject {
                                     data: HeapData::Object(env_props),
@@ -1275,31 +2063,13 @@ impl VM {
                                 self.ip = address;
                                 return ExecResult::ContinueNoIpInc;
                             } else {
-                                panic!(
-                                    "Object is not callable (no __call__ property): Object({})",
-                                    ptr
-                                );
+                                return self.throw_not_callable(&JsValue::Object(ptr));
                             }
                         } else {
                             panic!("Object reference invalid: Object({})", ptr);
                         }
                     }
-                    other => {
-                        // Print the last few instructions for context
-                        let start = self.ip.saturating_sub(5);
-                        let end = (self.ip + 3).min(self.program.len());
-                        eprintln!("Context around ip={}:", self.ip);
-                        for i in start..end {
-                            let marker = if i == self.ip { ">>>" } else { "   " };
-                            eprintln!("{} {}: {:?}", marker, i, self.program.get(i));
-                        }
-                        panic!(
-                            "Target is not callable: {:?} at ip={}, call_stack_depth={}",
-                            other,
-                            self.ip,
-                            self.call_stack.len()
-                        );
-                    }
+                    other => return self.throw_not_callable(&other),
                 }
             }
 
@@ -1308,6 +2078,13 @@ impl VM {
                     return ExecResult::Stop;
                 }
                 let frame = self.call_stack.pop().expect("Missing frame");
+                // An async call resolves its promise with the value and returns the promise
+                if let Some(async_call) = frame.async_call {
+                    let value = self.stack.pop().unwrap_or(JsValue::Undefined);
+                    self.stack.truncate(async_call.stack_base);
+                    self.resolve_promise(&async_call.promise, value);
+                    self.stack.push(JsValue::Promise(async_call.promise));
+                }
                 self.ip = frame.return_address;
                 if self.ip == usize::MAX {
                     return ExecResult::Stop;
@@ -1316,28 +2093,31 @@ impl VM {
             }
 
             OpCode::Drop(name) => {
+                if let Some(namespace) = self.module_linker.body_at(self.call_stack.len()) {
+                    self.module_linker.remove(namespace, &name);
+                }
                 self.call_stack.last_mut().unwrap().locals.remove(&name);
             }
 
             OpCode::Add => {
                 let b = self.stack.pop().unwrap();
                 let a = self.stack.pop().unwrap();
+                let (a, b) = (self.to_string_primitive(a), self.to_string_primitive(b));
 
                 match (a, b) {
                     (JsValue::Number(a_num), JsValue::Number(b_num)) => {
                         self.stack.push(JsValue::Number(a_num + b_num));
                     }
-                    (JsValue::String(mut a_str), JsValue::String(b_str)) => {
-                        a_str.push_str(&b_str);
-                        self.stack.push(JsValue::String(a_str));
+                    (JsValue::String(a_str), JsValue::String(b_str)) => {
+                        self.stack.push(JsValue::String(a_str.concat(&b_str)));
                     }
                     (JsValue::String(a_str), b) => {
                         let b_str = match b {
-                            JsValue::Number(n) => n.to_string(),
+                            JsValue::Number(n) => number::to_string(n),
                             JsValue::Boolean(b) => b.to_string(),
                             JsValue::Null => "null".to_string(),
                             JsValue::Undefined => "undefined".to_string(),
-                            JsValue::String(s) => s,
+                            JsValue::String(s) => s.to_string(),
                             JsValue::Object(ptr) => format!("Object({})", ptr),
                             JsValue::Function { address, env: _env } => {
                                 format!("Function({})", address)
@@ -1347,15 +2127,16 @@ impl VM {
                             }
                             _ => "".to_string(),
                         };
-                        self.stack.push(JsValue::String(a_str + &b_str[..]));
+                        self.stack
+                            .push(JsValue::String(a_str.concat(&b_str.into())));
                     }
                     (a, JsValue::String(b_str)) => {
                         let a_str = match a {
-                            JsValue::Number(n) => n.to_string(),
+                            JsValue::Number(n) => number::to_string(n),
                             JsValue::Boolean(b) => b.to_string(),
                             JsValue::Null => "null".to_string(),
                             JsValue::Undefined => "undefined".to_string(),
-                            JsValue::String(s) => s,
+                            JsValue::String(s) => s.to_string(),
                             JsValue::Object(ptr) => format!("Object({})", ptr),
                             JsValue::Function { address, env: _env } => {
                                 format!("Function({})", address)
@@ -1365,7 +2146,8 @@ impl VM {
                             }
                             _ => "".to_string(),
                         };
-                        self.stack.push(JsValue::String(a_str + &b_str[..]));
+                        self.stack
+                            .push(JsValue::String(JsString::from(a_str).concat(&b_str)));
                     }
                     _ => {
                         self.stack.push(JsValue::Undefined);
@@ -1442,15 +2224,23 @@ impl VM {
                     JsValue::Accessor(_, _) => "function",
                     JsValue::Promise(_) => "object",
                 };
-                self.stack.push(JsValue::String(type_str.to_string()));
+                self.stack.push(JsValue::String(type_str.into()));
             }
 
             OpCode::Delete(ref prop_name) => {
                 let obj_val = self.stack.pop().unwrap_or(JsValue::Undefined);
+                if let JsValue::Object(obj_id) = obj_val
+                    && let Err(message) = property::check_delete(self, obj_id, prop_name)
+                {
+                    return self.throw_error("TypeError", message);
+                }
                 if let JsValue::Object(obj_id) = obj_val {
                     if obj_id < self.heap.len() {
                         if let HeapData::Object(ref mut props) = self.heap[obj_id].data {
                             props.remove(prop_name);
+                            if let Some(attributes) = self.property_attributes.get_mut(&obj_id) {
+                                attributes.remove(prop_name);
+                            }
                             self.stack.push(JsValue::Boolean(true));
                         } else {
                             self.stack.push(JsValue::Boolean(false));
@@ -1464,9 +2254,9 @@ impl VM {
             }
 
             OpCode::Sub => {
-                if let (Some(JsValue::Number(b)), Some(JsValue::Number(a))) =
-                    (self.stack.pop(), self.stack.pop())
-                {
+                let b = self.stack.pop().map(|v| self.date_to_number(v));
+                let a = self.stack.pop().map(|v| self.date_to_number(v));
+                if let (Some(JsValue::Number(b)), Some(JsValue::Number(a))) = (b, a) {
                     self.stack.push(JsValue::Number(a - b));
                 } else {
                     self.stack.push(JsValue::Undefined);
@@ -1706,8 +2496,8 @@ impl VM {
             }
 
             OpCode::Lt => {
-                let b = self.stack.pop();
-                let a = self.stack.pop();
+                let b = self.stack.pop().map(|v| self.date_to_number(v));
+                let a = self.stack.pop().map(|v| self.date_to_number(v));
                 let result = match (a, b) {
                     (Some(JsValue::Number(a)), Some(JsValue::Number(b))) => a < b,
                     (Some(JsValue::String(a)), Some(JsValue::String(b))) => a < b,
@@ -1717,8 +2507,8 @@ impl VM {
             }
 
             OpCode::LtEq => {
-                let b = self.stack.pop();
-                let a = self.stack.pop();
+                let b = self.stack.pop().map(|v| self.date_to_number(v));
+                let a = self.stack.pop().map(|v| self.date_to_number(v));
                 let result = match (a, b) {
                     (Some(JsValue::Number(a)), Some(JsValue::Number(b))) => a <= b,
                     (Some(JsValue::String(a)), Some(JsValue::String(b))) => a <= b,
@@ -1728,8 +2518,8 @@ impl VM {
             }
 
             OpCode::Gt => {
-                let b = self.stack.pop();
-                let a = self.stack.pop();
+                let b = self.stack.pop().map(|v| self.date_to_number(v));
+                let a = self.stack.pop().map(|v| self.date_to_number(v));
                 let result = match (a, b) {
                     (Some(JsValue::Number(a)), Some(JsValue::Number(b))) => a > b,
                     (Some(JsValue::String(a)), Some(JsValue::String(b))) => a > b,
@@ -1739,8 +2529,8 @@ impl VM {
             }
 
             OpCode::GtEq => {
-                let b = self.stack.pop();
-                let a = self.stack.pop();
+                let b = self.stack.pop().map(|v| self.date_to_number(v));
+                let a = self.stack.pop().map(|v| self.date_to_number(v));
                 let result = match (a, b) {
                     (Some(JsValue::Number(a)), Some(JsValue::Number(b))) => a >= b,
                     (Some(JsValue::String(a)), Some(JsValue::String(b))) => a >= b,
@@ -1767,7 +2557,23 @@ impl VM {
                 let index_val = self.stack.pop().unwrap();
                 let value = self.stack.pop().unwrap();
                 let array_ptr = self.stack.pop().unwrap();
+                if let JsValue::Object(ptr) = array_ptr {
+                    let key = match &index_val {
+                        JsValue::Number(n) => number::to_string(*n),
+                        JsValue::String(s) => s.to_string(),
+                        other => format!("{:?}", other),
+                    };
+                    if let Some(thrown) = self.check_write(ptr, &key) {
+                        return thrown;
+                    }
+                }
 
+                if let JsValue::Object(ptr) = array_ptr
+                    && crate::stdlib::typed_array::set(self, ptr, &index_val, &value)
+                {
+                    self.ip += 1;
+                    return ExecResult::Continue;
+                }
                 if let (JsValue::Object(ptr), JsValue::Number(idx)) = (array_ptr, index_val)
                     && let Some(HeapObject {
                         data: HeapData::Array(arr),
@@ -1795,6 +2601,11 @@ impl VM {
                 let index_val = self.stack.pop().expect("Missing index");
                 let target = self.stack.pop().expect("Missing target (array or String)");
                 match (target, index_val) {
+                    (JsValue::Object(ptr), index)
+                        if let Some(value) = crate::stdlib::typed_array::get(self, ptr, &index) =>
+                    {
+                        self.stack.push(value);
+                    }
                     (JsValue::Object(ptr), JsValue::Number(idx)) => {
                         if let Some(heap_obj) = self.heap.get(ptr)
                             && let HeapData::Array(arr) = &heap_obj.data
@@ -1819,8 +2630,10 @@ impl VM {
                                 }
                                 HeapData::Object(props) => {
                                     // Object property access by string key
-                                    let val =
-                                        props.get(&idx_str).cloned().unwrap_or(JsValue::Undefined);
+                                    let val = props
+                                        .get(idx_str.as_str())
+                                        .cloned()
+                                        .unwrap_or(JsValue::Undefined);
                                     self.stack.push(val);
                                 }
                                 _ => {
@@ -1832,13 +2645,7 @@ impl VM {
                         }
                     }
                     (JsValue::String(s), JsValue::Number(idx)) => {
-                        let i = idx as usize;
-                        let char_val = s
-                            .chars()
-                            .nth(i)
-                            .map(|c| JsValue::String(c.to_string()))
-                            .unwrap_or(JsValue::Undefined);
-                        self.stack.push(char_val);
+                        self.stack.push(string_element(&s, idx));
                     }
                     _ => {
                         self.stack.push(JsValue::Undefined);
@@ -1868,18 +2675,17 @@ impl VM {
                 let source_val = self.stack.pop().expect("ArraySpread: missing source");
                 let target_val = self.stack.pop().expect("ArraySpread: missing target");
 
-                if let (JsValue::Object(target_ptr), JsValue::Object(source_ptr)) =
-                    (target_val, source_val)
-                {
-                    // First, collect elements from source array
-                    let source_elements: Vec<JsValue> = if let Some(HeapObject {
-                        data: HeapData::Array(arr),
-                    }) = self.heap.get(source_ptr)
-                    {
-                        arr.clone()
-                    } else {
-                        Vec::new()
-                    };
+                if let JsValue::Object(target_ptr) = target_val {
+                    // First, collect elements from the source iterable
+                    let source_elements =
+                        match crate::stdlib::collections::iterable_values(self, &source_val) {
+                            Some(elements) => elements,
+                            None => {
+                                let (name, message) =
+                                    crate::stdlib::collections::not_iterable(self, &source_val);
+                                return self.throw_error(name, message);
+                            }
+                        };
                     // Then, append to target array
                     if let Some(HeapObject {
                         data: HeapData::Array(target_arr),
@@ -1894,22 +2700,22 @@ impl VM {
             }
 
             OpCode::ObjectSpread => {
-                // Pops [target_obj, source_obj] -> copies all properties from source to target, pushes target
+                // Pops [target_obj, source_obj] -> copies the source's own enumerable
+                // properties to target, pushes target
                 let source_val = self.stack.pop().expect("ObjectSpread: missing source");
                 let target_val = self.stack.pop().expect("ObjectSpread: missing target");
 
                 if let (JsValue::Object(target_ptr), JsValue::Object(source_ptr)) =
                     (target_val, source_val)
                 {
-                    // First, collect properties from source object
-                    let source_props: HashMap<String, JsValue> = if let Some(HeapObject {
-                        data: HeapData::Object(props),
-                    }) = self.heap.get(source_ptr)
-                    {
-                        props.clone()
-                    } else {
-                        HashMap::new()
-                    };
+                    // First, read properties from source object, calling its getters
+                    let mut source_props = Vec::new();
+                    for key in property::own_enumerable_keys(self, source_ptr) {
+                        match property::get(self, source_ptr, &key) {
+                            Ok(value) => source_props.push((key, value)),
+                            Err(exception) => return self.throw_value(exception),
+                        }
+                    }
                     // Then, insert into target object
                     if let Some(HeapObject {
                         data: HeapData::Object(target_props),
@@ -1944,11 +2750,8 @@ impl VM {
 
             OpCode::Construct(arg_count) => {
                 // Stack overflow protection
-                if self.call_stack.len() >= MAX_CALL_STACK_DEPTH {
-                    panic!(
-                        "Stack overflow: maximum call depth of {} exceeded",
-                        MAX_CALL_STACK_DEPTH
-                    );
+                if let Some(result) = self.check_call_depth() {
+                    return result;
                 }
 
                 // Stack layout: [..., arg1, arg2, ..., constructor]
@@ -1991,26 +2794,26 @@ impl VM {
                                     // The native function will handle construction itself
                                     (0usize, None, proto, constructor_val.clone())
                                 }
-                                Some(other) => {
-                                    panic!("Constructor is not a Function, it's {:?}", other);
-                                }
+                                Some(_) => return self.throw_not_constructor(&constructor_val),
                                 None => {
                                     // No constructor property - this is a "constructor object" like Promise
                                     // For Promise-like objects, we treat the object itself as the constructor
                                     // and call a special constructor handler
-                                    // For now, we'll panic with a helpful message
-                                    eprintln!(
-                                        "Warning: 'new' on object without constructor - treating as constructor object"
-                                    );
+                                    // Builtins such as Map and Date are marked with __type__
+                                    if !props.contains_key("__type__") {
+                                        eprintln!(
+                                            "Warning: 'new' on object without constructor - treating as constructor object"
+                                        );
+                                    }
                                     // Create a placeholder that will be handled specially
                                     (0usize, None, proto, constructor_val.clone())
                                 }
                             }
                         } else {
-                            panic!("Constructor is not an object with properties");
+                            return self.throw_not_constructor(&constructor_val);
                         }
                     }
-                    _ => panic!("Constructor is not a function or class"),
+                    _ => return self.throw_not_constructor(&constructor_val),
                 };
 
                 // Create new object with prototype
@@ -2042,7 +2845,7 @@ impl VM {
                     this_context: this_obj.clone(),
                     new_target: Some(new_target_val.clone()),
                     super_called: false,
-                    resume_ip: None,
+                    async_call: None,
                 };
 
                 // Load captured environment if present
@@ -2063,7 +2866,7 @@ impl VM {
                             if let HeapData::Object(props) = &heap_obj.data {
                                 // Check for __type__ property first
                                 if let Some(JsValue::String(t)) = props.get("__type__") {
-                                    t.clone()
+                                    t.to_string()
                                 } else if props.contains_key("then") && props.contains_key("catch")
                                 {
                                     "Promise".to_string()
@@ -2080,22 +2883,45 @@ impl VM {
                         String::new()
                     };
 
-                    if constructor_type == "Map" {
-                        // Handle Map construction: new Map() or new Map(iterable)
-                        let map_ptr = self.heap.len();
-                        self.heap.push(HeapObject {
-                            data: HeapData::Map(Vec::new()),
-                        });
-                        // If an iterable is passed, we'd need to iterate it - for now just create empty
-                        self.stack.push(JsValue::Object(map_ptr));
-                    } else if constructor_type == "Set" {
-                        // Handle Set construction: new Set() or new Set(iterable)
-                        let set_ptr = self.heap.len();
-                        self.heap.push(HeapObject {
-                            data: HeapData::Set(Vec::new()),
-                        });
-                        // If an iterable is passed, we'd need to iterate it - for now just create empty
-                        self.stack.push(JsValue::Object(set_ptr));
+                    if constructor_type == "Date"
+                        || constructor_type == "Array"
+                        || crate::stdlib::typed_array::is_constructor(&constructor_type)
+                        || crate::stdlib::collections::is_constructor(&constructor_type)
+                    {
+                        // The arguments were pushed back for a function prologue, and
+                        // below them is the `NewObject, Dup` pair the compiler emits
+                        // for `this`, which the new object replaces
+                        self.stack.truncate(self.stack.len() - args.len());
+                        if let [.., JsValue::Object(a), JsValue::Object(b)] = self.stack.as_slice()
+                            && a == b
+                        {
+                            self.stack.truncate(self.stack.len() - 2);
+                        }
+                        if constructor_type == "Date" {
+                            let date = crate::stdlib::date::construct(self, &args);
+                            self.stack.push(date);
+                        } else {
+                            let constructed = if constructor_type == "Array" {
+                                crate::stdlib::array::construct(self, &args)
+                            } else if crate::stdlib::collections::is_constructor(&constructor_type)
+                            {
+                                crate::stdlib::collections::construct(
+                                    self,
+                                    &constructor_type,
+                                    &args,
+                                )
+                            } else {
+                                crate::stdlib::typed_array::construct(
+                                    self,
+                                    &constructor_type,
+                                    &args,
+                                )
+                            };
+                            match constructed {
+                                Ok(value) => self.stack.push(value),
+                                Err((name, message)) => return self.throw_error(name, message),
+                            }
+                        }
                     } else if constructor_type == "Promise" {
                         // Handle Promise construction specially
                         // new Promise((resolve, reject) => { ... })
@@ -2148,7 +2974,7 @@ impl VM {
                                 this_context: JsValue::Undefined,
                                 new_target: Some(executor.clone()),
                                 super_called: false,
-                                resume_ip: None,
+                                async_call: None,
                             };
 
                             // Set up locals: resolve and reject
@@ -2180,7 +3006,15 @@ impl VM {
                         // If no executor or invalid executor, just return the Promise
                         self.stack.push(JsValue::Promise(promise));
                     } else {
-                        // Regular native constructor - push a frame with this_context
+                        // Regular native constructor - push a frame with this_context.
+                        // Its result replaces the arguments pushed back above and
+                        // the `NewObject, Dup` pair, as for Date
+                        self.stack.truncate(self.stack.len() - args.len());
+                        if let [.., JsValue::Object(a), JsValue::Object(b)] = self.stack.as_slice()
+                            && a == b
+                        {
+                            self.stack.truncate(self.stack.len() - 2);
+                        }
                         let native_frame = Frame {
                             return_address: self.ip + 1,
                             locals: HashMap::new(),
@@ -2188,7 +3022,7 @@ impl VM {
                             this_context: this_obj.clone(),
                             new_target: Some(new_target_val.clone()),
                             super_called: false,
-                            resume_ip: None,
+                            async_call: None,
                         };
                         self.call_stack.push(native_frame);
 
@@ -2215,6 +3049,9 @@ impl VM {
 
                         // Pop the native frame
                         self.call_stack.pop();
+                        if let Some(exception) = self.native_exception.take() {
+                            return self.throw_value(exception);
+                        }
 
                         // Push result and continue
                         self.stack.push(native_result);
@@ -2232,7 +3069,7 @@ impl VM {
                 let module = match module_name {
                     JsValue::String(module_name) => self
                         .modules
-                        .get(&module_name)
+                        .get(module_name.as_str())
                         .cloned()
                         .unwrap_or(JsValue::Undefined),
                     _ => JsValue::Undefined,
@@ -2242,111 +3079,112 @@ impl VM {
 
             OpCode::CallMethod(name, arg_count) => {
                 let reciever = self.stack.pop().expect("Missing reciever");
+                if let JsValue::Object(ptr) = reciever
+                    && self.frozen_objects.contains(&ptr)
+                    && matches!(
+                        name.as_str(),
+                        "push"
+                            | "pop"
+                            | "shift"
+                            | "unshift"
+                            | "splice"
+                            | "reverse"
+                            | "fill"
+                            | "sort"
+                            | "copyWithin"
+                    )
+                {
+                    for _ in 0..arg_count {
+                        self.stack.pop();
+                    }
+                    return self.throw_error(
+                        "TypeError",
+                        format!("Cannot call '{}' on a frozen array", name),
+                    );
+                }
+                if let JsValue::Object(ptr) = reciever
+                    && self
+                        .hot_reloader
+                        .as_ref()
+                        .is_some_and(|hot| hot.is_hot_object(ptr))
+                {
+                    let split = self.stack.len().saturating_sub(arg_count);
+                    let args = self.stack.split_off(split);
+                    if let Some(result) = self.call_hot_method(ptr, &name, &args) {
+                        self.stack.push(result);
+                        self.ip += 1;
+                        return ExecResult::Continue;
+                    }
+                    self.stack.extend(args);
+                }
 
                 match reciever {
                     // -- String methods --
-                    // Core string methods needed for bootstrap compiler
-                    JsValue::String(s) => {
-                        match name.as_str() {
-                            "length" => {
-                                // Pop any args (shouldn't be any for length property)
-                                for _ in 0..arg_count {
-                                    self.stack.pop();
-                                }
-                                // O(1) for ASCII strings
-                                self.stack.push(JsValue::Number(s.len() as f64));
-                            }
-                            "charCodeAt" => {
-                                // Get char code at index
-                                let index = if arg_count > 0 {
-                                    match self.stack.pop() {
-                                        Some(JsValue::Number(n)) => n as usize,
-                                        _ => 0,
-                                    }
-                                } else {
-                                    0
-                                };
-                                // Pop remaining args if any
-                                for _ in 1..arg_count {
-                                    self.stack.pop();
-                                }
-                                // O(1) for ASCII strings (common case)
-                                let bytes = s.as_bytes();
-                                let result = if index < bytes.len() {
-                                    let b = bytes[index];
-                                    if b < 128 {
-                                        // ASCII: O(1) fast path
-                                        JsValue::Number(b as f64)
-                                    } else {
-                                        // Non-ASCII: fallback to chars().nth()
-                                        s.chars()
-                                            .nth(index)
-                                            .map(|c| JsValue::Number(c as u32 as f64))
-                                            .unwrap_or(JsValue::Number(f64::NAN))
-                                    }
-                                } else {
-                                    JsValue::Number(f64::NAN)
-                                };
-                                self.stack.push(result);
-                            }
-                            "slice" => {
-                                // Get start and end indices
-                                let mut args = Vec::with_capacity(arg_count);
-                                for _ in 0..arg_count {
-                                    args.push(self.stack.pop().expect("Missing argument"));
-                                }
-                                args.reverse();
-
-                                // O(1) length for ASCII strings
-                                let len = s.len() as i64;
-                                let start = args
-                                    .first()
-                                    .and_then(|v| match v {
-                                        JsValue::Number(n) => {
-                                            let n = *n as i64;
-                                            if n < 0 {
-                                                Some((len + n).max(0) as usize)
-                                            } else {
-                                                Some(n as usize)
-                                            }
-                                        }
-                                        _ => None,
-                                    })
-                                    .unwrap_or(0);
-                                let end = args
-                                    .get(1)
-                                    .and_then(|v| match v {
-                                        JsValue::Number(n) => {
-                                            let n = *n as i64;
-                                            if n < 0 {
-                                                Some((len + n).max(0) as usize)
-                                            } else {
-                                                Some(n as usize)
-                                            }
-                                        }
-                                        _ => None,
-                                    })
-                                    .unwrap_or(len as usize);
-
-                                // For ASCII strings, use byte slicing (O(1) + copy)
-                                let bytes = s.as_bytes();
-                                let is_ascii = bytes.iter().all(|&b| b < 128);
-                                let result = if is_ascii && end <= bytes.len() {
-                                    let start = start.min(bytes.len());
-                                    let end = end.min(bytes.len());
-                                    // Safe: we verified all bytes are ASCII
-                                    unsafe {
-                                        std::str::from_utf8_unchecked(&bytes[start..end.max(start)])
-                                            .to_string()
+                    // Core string methods needed for bootstrap compiler
+                    JsValue::String(s) => {
+                        match name.as_str() {
+                            "length" => {
+                                // Pop any args (shouldn't be any for length property)
+                                for _ in 0..arg_count {
+                                    self.stack.pop();
+                                }
+                                // Counted in UTF-16 code units, like every index below
+                                self.stack.push(JsValue::Number(s.length() as f64));
+                            }
+                            "charCodeAt" | "codePointAt" | "charAt" | "at" => {
+                                let split = self.stack.len().saturating_sub(arg_count);
+                                let args = self.stack.split_off(split);
+                                let index = args.first().map_or(0.0, crate::stdlib::to_integer);
+                                // `at` counts a negative index from the end
+                                let index = if name == "at" && index < 0.0 {
+                                    index + s.length() as f64
+                                } else {
+                                    index
+                                };
+                                let index = (index >= 0.0).then_some(index as usize);
+                                let result = match name.as_str() {
+                                    "charCodeAt" => JsValue::Number(
+                                        index
+                                            .and_then(|i| s.code_unit_at(i))
+                                            .map_or(f64::NAN, f64::from),
+                                    ),
+                                    "codePointAt" => index
+                                        .and_then(|i| s.code_point_at(i))
+                                        .map_or(JsValue::Undefined, |c| JsValue::Number(c as f64)),
+                                    _ => match index.filter(|&i| i < s.length()) {
+                                        Some(i) => JsValue::String(s.substring(i, i + 1)),
+                                        None if name == "at" => JsValue::Undefined,
+                                        None => JsValue::String(String::new().into()),
+                                    },
+                                };
+                                self.stack.push(result);
+                            }
+                            "slice" | "substring" => {
+                                let split = self.stack.len().saturating_sub(arg_count);
+                                let args = self.stack.split_off(split);
+                                let len = s.length() as f64;
+                                let index = |value: Option<&JsValue>, default: f64| match value {
+                                    None | Some(JsValue::Undefined) => default as usize,
+                                    Some(value) => {
+                                        let n = crate::stdlib::to_integer(value);
+                                        // `slice` counts a negative index from the end
+                                        let n = if name == "slice" && n < 0.0 {
+                                            len + n
+                                        } else {
+                                            n
+                                        };
+                                        n.clamp(0.0, len) as usize
                                     }
+                                };
+                                let start = index(args.first(), 0.0);
+                                let end = index(args.get(1), len);
+                                // substring swaps start/end if start > end
+                                let (start, end) = if name == "substring" && start > end {
+                                    (end, start)
                                 } else {
-                                    // Non-ASCII fallback
-                                    s.chars()
-                                        .skip(start)
-                                        .take(end.saturating_sub(start))
-                                        .collect()
+                                    (start, end)
                                 };
-                                self.stack.push(JsValue::String(result));
+                                self.stack.push(JsValue::String(s.substring(start, end)));
                             }
                             "indexOf" => {
                                 // Pop args in reverse order (last arg on top of stack)
@@ -2361,19 +3199,18 @@ impl VM {
                                 let search = if arg_count > 0 {
                                     match self.stack.pop() {
                                         Some(JsValue::String(ss)) => ss,
-                                        Some(JsValue::Number(n)) => n.to_string(),
-                                        _ => String::new(),
+                                        Some(JsValue::Number(n)) => number::to_string(n).into(),
+                                        _ => String::new().into(),
                                     }
                                 } else {
-                                    String::new()
+                                    String::new().into()
                                 };
                                 for _ in 2..arg_count {
                                     self.stack.pop();
                                 }
                                 let result = s
-                                    .get(start_index..)
-                                    .and_then(|sub| sub.find(&search))
-                                    .map(|i| (i + start_index) as f64)
+                                    .index_of(&search, start_index)
+                                    .map(|i| i as f64)
                                     .unwrap_or(-1.0);
                                 self.stack.push(JsValue::Number(result));
                             }
@@ -2382,22 +3219,22 @@ impl VM {
                                 let separator = if arg_count > 0 {
                                     match self.stack.pop() {
                                         Some(JsValue::String(sep)) => sep,
-                                        Some(JsValue::Number(n)) => n.to_string(),
-                                        _ => String::new(),
+                                        Some(JsValue::Number(n)) => number::to_string(n).into(),
+                                        _ => String::new().into(),
                                     }
                                 } else {
-                                    String::new()
+                                    String::new().into()
                                 };
                                 // Pop remaining args
                                 for _ in 1..arg_count {
                                     self.stack.pop();
                                 }
                                 let parts: Vec<JsValue> = if separator.is_empty() {
-                                    // Empty separator: split into characters
-                                    s.chars().map(|c| JsValue::String(c.to_string())).collect()
+                                    // Empty separator: split into code units
+                                    s.unit_strings().into_iter().map(JsValue::String).collect()
                                 } else {
-                                    s.split(&separator)
-                                        .map(|part| JsValue::String(part.to_string()))
+                                    s.split(separator.as_str())
+                                        .map(|part| JsValue::String(part.into()))
                                         .collect()
                                 };
                                 let arr_ptr = self.heap.len();
@@ -2406,136 +3243,104 @@ impl VM {
                                 });
                                 self.stack.push(JsValue::Object(arr_ptr));
                             }
-                            "charAt" => {
-                                // Get character at index
-                                let index = if arg_count > 0 {
-                                    match self.stack.pop() {
-                                        Some(JsValue::Number(n)) => n as usize,
-                                        _ => 0,
-                                    }
-                                } else {
-                                    0
+                            "normalize" => {
+                                let split = self.stack.len().saturating_sub(arg_count);
+                                let args = self.stack.split_off(split);
+                                let form = match args.first() {
+                                    None | Some(JsValue::Undefined) => "NFC".to_string(),
+                                    Some(form) => match crate::stdlib::native_string_constructor(
+                                        self,
+                                        vec![form.clone()],
+                                    ) {
+                                        JsValue::String(form) => form.to_string(),
+                                        _ => String::new(),
+                                    },
                                 };
-                                // Pop remaining args
-                                for _ in 1..arg_count {
-                                    self.stack.pop();
-                                }
-                                let result = s
-                                    .chars()
-                                    .nth(index)
-                                    .map(|c| JsValue::String(c.to_string()))
-                                    .unwrap_or(JsValue::String(String::new()));
-                                self.stack.push(result);
-                            }
-                            "substring" => {
-                                // Get substring from start to end
-                                let mut args = Vec::with_capacity(arg_count);
-                                for _ in 0..arg_count {
-                                    args.push(self.stack.pop().expect("Missing argument"));
+                                match crate::stdlib::text::normalize(&s, &form) {
+                                    Some(result) => self.stack.push(JsValue::String(result.into())),
+                                    None => {
+                                        return self.throw_error(
+                                            "RangeError",
+                                            "The normalization form should be one of NFC, NFD, NFKC, NFKD."
+                                                .to_string(),
+                                        );
+                                    }
                                 }
-                                args.reverse();
-
-                                let len = s.chars().count();
-                                let start = args
-                                    .first()
-                                    .and_then(|v| match v {
-                                        JsValue::Number(n) => Some((*n as usize).min(len)),
-                                        _ => None,
-                                    })
-                                    .unwrap_or(0);
-                                let end = args
-                                    .get(1)
-                                    .and_then(|v| match v {
-                                        JsValue::Number(n) => Some((*n as usize).min(len)),
-                                        _ => None,
-                                    })
-                                    .unwrap_or(len);
-
-                                // substring swaps start/end if start > end
-                                let (actual_start, actual_end) = if start > end {
-                                    (end, start)
-                                } else {
-                                    (start, end)
-                                };
-
-                                let result: String = s
-                                    .chars()
-                                    .skip(actual_start)
-                                    .take(actual_end - actual_start)
-                                    .collect();
-                                self.stack.push(JsValue::String(result));
                             }
                             "trim" => {
                                 for _ in 0..arg_count {
                                     self.stack.pop();
                                 }
-                                self.stack.push(JsValue::String(s.trim().to_string()));
+                                self.stack.push(JsValue::String(s.trim().into()));
                             }
                             "trimStart" | "trimLeft" => {
                                 for _ in 0..arg_count {
                                     self.stack.pop();
                                 }
-                                self.stack.push(JsValue::String(s.trim_start().to_string()));
+                                self.stack.push(JsValue::String(s.trim_start().into()));
                             }
                             "trimEnd" | "trimRight" => {
                                 for _ in 0..arg_count {
                                     self.stack.pop();
                                 }
-                                self.stack.push(JsValue::String(s.trim_end().to_string()));
+                                self.stack.push(JsValue::String(s.trim_end().into()));
                             }
                             "toLowerCase" => {
                                 for _ in 0..arg_count {
                                     self.stack.pop();
                                 }
-                                self.stack.push(JsValue::String(s.to_lowercase()));
+                                self.stack.push(JsValue::String(s.to_lowercase().into()));
                             }
                             "toUpperCase" => {
                                 for _ in 0..arg_count {
                                     self.stack.pop();
                                 }
-                                self.stack.push(JsValue::String(s.to_uppercase()));
+                                self.stack.push(JsValue::String(s.to_uppercase().into()));
                             }
                             "startsWith" => {
                                 let prefix = if arg_count > 0 {
                                     match self.stack.pop() {
                                         Some(JsValue::String(ss)) => ss,
-                                        _ => String::new(),
+                                        _ => String::new().into(),
                                     }
                                 } else {
-                                    String::new()
+                                    String::new().into()
                                 };
                                 for _ in 1..arg_count {
                                     self.stack.pop();
                                 }
-                                self.stack.push(JsValue::Boolean(s.starts_with(&prefix)));
+                                self.stack
+                                    .push(JsValue::Boolean(s.starts_with(prefix.as_str())));
                             }
                             "endsWith" => {
                                 let suffix = if arg_count > 0 {
                                     match self.stack.pop() {
                                         Some(JsValue::String(ss)) => ss,
-                                        _ => String::new(),
+                                        _ => String::new().into(),
                                     }
                                 } else {
-                                    String::new()
+                                    String::new().into()
                                 };
                                 for _ in 1..arg_count {
                                     self.stack.pop();
                                 }
-                                self.stack.push(JsValue::Boolean(s.ends_with(&suffix)));
+                                self.stack
+                                    .push(JsValue::Boolean(s.ends_with(suffix.as_str())));
                             }
                             "includes" => {
                                 let search = if arg_count > 0 {
                                     match self.stack.pop() {
                                         Some(JsValue::String(ss)) => ss,
-                                        _ => String::new(),
+                                        _ => String::new().into(),
                                     }
                                 } else {
-                                    String::new()
+                                    String::new().into()
                                 };
                                 for _ in 1..arg_count {
                                     self.stack.pop();
                                 }
-                                self.stack.push(JsValue::Boolean(s.contains(&search)));
+                                self.stack
+                                    .push(JsValue::Boolean(s.contains(search.as_str())));
                             }
                             "replace" => {
                                 let mut args = Vec::with_capacity(arg_count);
@@ -2560,8 +3365,8 @@ impl VM {
                                     .unwrap_or_default();
 
                                 // Only replace first occurrence (JS behavior)
-                                let result = s.replacen(&search, &replacement, 1);
-                                self.stack.push(JsValue::String(result));
+                                let result = s.replacen(search.as_str(), &replacement, 1);
+                                self.stack.push(JsValue::String(result.into()));
                             }
                             "repeat" => {
                                 let count = if arg_count > 0 {
@@ -2575,87 +3380,48 @@ impl VM {
                                 for _ in 1..arg_count {
                                     self.stack.pop();
                                 }
-                                self.stack.push(JsValue::String(s.repeat(count)));
+                                self.stack.push(JsValue::String(s.repeat(count).into()));
                             }
                             "concat" => {
-                                let mut result = s.clone();
-                                for _ in 0..arg_count {
-                                    if let Some(JsValue::String(part)) = self.stack.pop() {
-                                        result.push_str(&part);
-                                    }
-                                }
+                                let split = self.stack.len().saturating_sub(arg_count);
+                                let parts = self.stack.split_off(split);
+                                let result =
+                                    parts.iter().fold(s.clone(), |result, part| match part {
+                                        JsValue::String(part) => result.concat(part),
+                                        _ => result,
+                                    });
                                 self.stack.push(JsValue::String(result));
                             }
                             "lastIndexOf" => {
                                 // Pop args in reverse order (last arg on top of stack)
                                 let end_index = if arg_count > 1 {
                                     match self.stack.pop() {
-                                        Some(JsValue::Number(n)) if n >= 0.0 => Some(n as usize),
-                                        _ => None,
+                                        Some(JsValue::Number(n)) if n >= 0.0 => n as usize,
+                                        Some(JsValue::Number(n)) if n < 0.0 => 0,
+                                        _ => usize::MAX,
                                     }
                                 } else {
-                                    None
+                                    usize::MAX
                                 };
                                 let search = if arg_count > 0 {
                                     match self.stack.pop() {
                                         Some(JsValue::String(ss)) => ss,
-                                        Some(JsValue::Number(n)) => n.to_string(),
-                                        _ => String::new(),
+                                        Some(JsValue::Number(n)) => number::to_string(n).into(),
+                                        _ => String::new().into(),
                                     }
                                 } else {
-                                    String::new()
+                                    String::new().into()
                                 };
                                 for _ in 2..arg_count {
                                     self.stack.pop();
                                 }
-                                let result = match end_index {
-                                    Some(end) => {
-                                        let end = (end + search.len()).min(s.len());
-                                        s.get(..end)
-                                            .and_then(|sub| sub.rfind(&search))
-                                            .map(|i| i as f64)
-                                            .unwrap_or(-1.0)
-                                    }
-                                    None => s.rfind(&search).map(|i| i as f64).unwrap_or(-1.0),
-                                };
+                                let result = s
+                                    .last_index_of(&search, end_index)
+                                    .map(|i| i as f64)
+                                    .unwrap_or(-1.0);
                                 self.stack.push(JsValue::Number(result));
                             }
-                            "padStart" => {
-                                let mut args = Vec::with_capacity(arg_count);
-                                for _ in 0..arg_count {
-                                    args.push(self.stack.pop().expect("Missing argument"));
-                                }
-                                args.reverse();
-
-                                let target_len = args
-                                    .first()
-                                    .and_then(|v| match v {
-                                        JsValue::Number(n) => Some(*n as usize),
-                                        _ => None,
-                                    })
-                                    .unwrap_or(0);
-                                let pad_str = args
-                                    .get(1)
-                                    .and_then(|v| match v {
-                                        JsValue::String(ss) => Some(ss.clone()),
-                                        _ => None,
-                                    })
-                                    .unwrap_or_else(|| " ".to_string());
-
-                                let current_len = s.chars().count();
-                                if current_len >= target_len || pad_str.is_empty() {
-                                    self.stack.push(JsValue::String(s.clone()));
-                                } else {
-                                    let pad_len = target_len - current_len;
-                                    let mut padding = String::new();
-                                    while padding.chars().count() < pad_len {
-                                        padding.push_str(&pad_str);
-                                    }
-                                    let padding: String = padding.chars().take(pad_len).collect();
-                                    self.stack.push(JsValue::String(padding + s.as_str()));
-                                }
-                            }
-                            "padEnd" => {
+                            "padStart" | "padEnd" => {
                                 let mut args = Vec::with_capacity(arg_count);
                                 for _ in 0..arg_count {
                                     args.push(self.stack.pop().expect("Missing argument"));
@@ -2675,20 +3441,23 @@ impl VM {
                                         JsValue::String(ss) => Some(ss.clone()),
                                         _ => None,
                                     })
-                                    .unwrap_or_else(|| " ".to_string());
+                                    .unwrap_or_else(|| " ".into());
 
-                                let current_len = s.chars().count();
+                                let current_len = s.length();
                                 if current_len >= target_len || pad_str.is_empty() {
                                     self.stack.push(JsValue::String(s.clone()));
                                 } else {
+                                    // Lengths are in code units, so the padding is too
                                     let pad_len = target_len - current_len;
-                                    let mut padding = String::new();
-                                    while padding.chars().count() < pad_len {
-                                        padding.push_str(&pad_str);
-                                    }
-                                    let padding: String = padding.chars().take(pad_len).collect();
-                                    self.stack
-                                        .push(JsValue::String(s.clone() + padding.as_str()));
+                                    let repeats = pad_len.div_ceil(pad_str.length());
+                                    let padding = JsString::from(pad_str.repeat(repeats))
+                                        .substring(0, pad_len);
+                                    let result = if name == "padStart" {
+                                        padding.concat(&s)
+                                    } else {
+                                        s.concat(&padding)
+                                    };
+                                    self.stack.push(JsValue::String(result));
                                 }
                             }
                             _ => {
@@ -2817,29 +3586,30 @@ impl VM {
                                     let separator = if arg_count > 0 {
                                         match self.stack.pop() {
                                             Some(JsValue::String(s)) => s,
-                                            Some(JsValue::Number(n)) => n.to_string(),
-                                            _ => ",".to_string(),
+                                            Some(JsValue::Number(n)) => number::to_string(n).into(),
+                                            _ => ",".into(),
                                         }
                                     } else {
-                                        ",".to_string()
+                                        ",".into()
                                     };
                                     // Pop any remaining args
                                     for _ in 1..arg_count {
                                         self.stack.pop();
                                     }
                                     // Join array elements into string
-                                    let parts: Vec<String> = arr
+                                    let parts: Vec<JsString> = arr
                                         .iter()
                                         .map(|v| match v {
                                             JsValue::String(s) => s.clone(),
-                                            JsValue::Number(n) => n.to_string(),
-                                            JsValue::Boolean(b) => b.to_string(),
-                                            JsValue::Null => "null".to_string(),
-                                            JsValue::Undefined => "undefined".to_string(),
-                                            _ => "".to_string(),
+                                            JsValue::Number(n) => number::to_string(*n).into(),
+                                            JsValue::Boolean(b) => b.to_string().into(),
+                                            JsValue::Null => "null".into(),
+                                            JsValue::Undefined => "undefined".into(),
+                                            _ => JsString::default(),
                                         })
                                         .collect();
-                                    self.stack.push(JsValue::String(parts.join(&separator)));
+                                    self.stack
+                                        .push(JsValue::String(JsString::join(&parts, &separator)));
                                     self.ip += 1;
                                     return ExecResult::Continue;
                                 }
@@ -3015,207 +3785,20 @@ impl VM {
                                     self.heap.push(HeapObject {
                                         data: HeapData::Array(result),
                                     });
-                                    self.stack.push(JsValue::Object(arr_ptr));
-                                    self.ip += 1;
-                                    return ExecResult::Continue;
-                                }
-                                "reverse" => {
-                                    for _ in 0..arg_count {
-                                        self.stack.pop();
-                                    }
-                                    arr.reverse();
-                                    self.stack.push(JsValue::Object(ptr));
-                                    self.ip += 1;
-                                    return ExecResult::Continue;
-                                }
-                                "fill" => {
-                                    let value = if arg_count > 0 {
-                                        self.stack.pop().unwrap_or(JsValue::Undefined)
-                                    } else {
-                                        JsValue::Undefined
-                                    };
-                                    for _ in 1..arg_count {
-                                        self.stack.pop();
-                                    }
-                                    for elem in arr.iter_mut() {
-                                        *elem = value.clone();
-                                    }
-                                    self.stack.push(JsValue::Object(ptr));
-                                    self.ip += 1;
-                                    return ExecResult::Continue;
-                                }
-                                "at" => {
-                                    let index = if arg_count > 0 {
-                                        match self.stack.pop() {
-                                            Some(JsValue::Number(n)) => n as i64,
-                                            _ => 0,
-                                        }
-                                    } else {
-                                        0
-                                    };
-                                    for _ in 1..arg_count {
-                                        self.stack.pop();
-                                    }
-                                    let len = arr.len() as i64;
-                                    let actual_idx = if index < 0 {
-                                        (len + index) as usize
-                                    } else {
-                                        index as usize
-                                    };
-                                    let result =
-                                        arr.get(actual_idx).cloned().unwrap_or(JsValue::Undefined);
-                                    self.stack.push(result);
-                                    self.ip += 1;
-                                    return ExecResult::Continue;
-                                }
-                                _ => {
-                                    // Unsupported array method - pop args and return undefined
-                                    for _ in 0..arg_count {
-                                        self.stack.pop();
-                                    }
-                                    self.stack.push(JsValue::Undefined);
-                                    self.ip += 1;
-                                    return ExecResult::Continue;
-                                }
-                            }
-                        }
-
-                        // Check if this is a Map and handle Map methods
-                        if let Some(HeapObject {
-                            data: HeapData::Map(map),
-                        }) = self.heap.get_mut(ptr)
-                        {
-                            match name.as_str() {
-                                "get" => {
-                                    let key = if arg_count > 0 {
-                                        self.stack.pop().unwrap_or(JsValue::Undefined)
-                                    } else {
-                                        JsValue::Undefined
-                                    };
-                                    for _ in 1..arg_count {
-                                        self.stack.pop();
-                                    }
-                                    let result = map
-                                        .iter()
-                                        .find(|(k, _)| match (k, &key) {
-                                            (JsValue::Number(a), JsValue::Number(b)) => a == b,
-                                            (JsValue::String(a), JsValue::String(b)) => a == b,
-                                            (JsValue::Boolean(a), JsValue::Boolean(b)) => a == b,
-                                            (JsValue::Null, JsValue::Null) => true,
-                                            (JsValue::Undefined, JsValue::Undefined) => true,
-                                            (JsValue::Object(a), JsValue::Object(b)) => a == b,
-                                            _ => false,
-                                        })
-                                        .map(|(_, v)| v.clone())
-                                        .unwrap_or(JsValue::Undefined);
-                                    self.stack.push(result);
-                                    self.ip += 1;
-                                    return ExecResult::Continue;
-                                }
-                                "set" => {
-                                    let mut args = Vec::with_capacity(arg_count);
-                                    for _ in 0..arg_count {
-                                        args.push(self.stack.pop().expect("Missing argument"));
-                                    }
-                                    args.reverse();
-                                    let key = args.first().cloned().unwrap_or(JsValue::Undefined);
-                                    let value = args.get(1).cloned().unwrap_or(JsValue::Undefined);
-
-                                    // Remove existing key if present
-                                    map.retain(|(k, _)| match (k, &key) {
-                                        (JsValue::Number(a), JsValue::Number(b)) => a != b,
-                                        (JsValue::String(a), JsValue::String(b)) => a != b,
-                                        (JsValue::Boolean(a), JsValue::Boolean(b)) => a != b,
-                                        (JsValue::Null, JsValue::Null) => false,
-                                        (JsValue::Undefined, JsValue::Undefined) => false,
-                                        (JsValue::Object(a), JsValue::Object(b)) => a != b,
-                                        _ => true,
-                                    });
-                                    map.push((key, value));
-                                    self.stack.push(JsValue::Object(ptr)); // Return the map itself
-                                    self.ip += 1;
-                                    return ExecResult::Continue;
-                                }
-                                "has" => {
-                                    let key = if arg_count > 0 {
-                                        self.stack.pop().unwrap_or(JsValue::Undefined)
-                                    } else {
-                                        JsValue::Undefined
-                                    };
-                                    for _ in 1..arg_count {
-                                        self.stack.pop();
-                                    }
-                                    let found = map.iter().any(|(k, _)| match (k, &key) {
-                                        (JsValue::Number(a), JsValue::Number(b)) => a == b,
-                                        (JsValue::String(a), JsValue::String(b)) => a == b,
-                                        (JsValue::Boolean(a), JsValue::Boolean(b)) => a == b,
-                                        (JsValue::Null, JsValue::Null) => true,
-                                        (JsValue::Undefined, JsValue::Undefined) => true,
-                                        (JsValue::Object(a), JsValue::Object(b)) => a == b,
-                                        _ => false,
-                                    });
-                                    self.stack.push(JsValue::Boolean(found));
-                                    self.ip += 1;
-                                    return ExecResult::Continue;
-                                }
-                                "delete" => {
-                                    let key = if arg_count > 0 {
-                                        self.stack.pop().unwrap_or(JsValue::Undefined)
-                                    } else {
-                                        JsValue::Undefined
-                                    };
-                                    for _ in 1..arg_count {
-                                        self.stack.pop();
-                                    }
-                                    let initial_len = map.len();
-                                    map.retain(|(k, _)| match (k, &key) {
-                                        (JsValue::Number(a), JsValue::Number(b)) => a != b,
-                                        (JsValue::String(a), JsValue::String(b)) => a != b,
-                                        (JsValue::Boolean(a), JsValue::Boolean(b)) => a != b,
-                                        (JsValue::Null, JsValue::Null) => false,
-                                        (JsValue::Undefined, JsValue::Undefined) => false,
-                                        (JsValue::Object(a), JsValue::Object(b)) => a != b,
-                                        _ => true,
-                                    });
-                                    self.stack.push(JsValue::Boolean(map.len() < initial_len));
-                                    self.ip += 1;
-                                    return ExecResult::Continue;
-                                }
-                                "clear" => {
-                                    for _ in 0..arg_count {
-                                        self.stack.pop();
-                                    }
-                                    map.clear();
-                                    self.stack.push(JsValue::Undefined);
-                                    self.ip += 1;
-                                    return ExecResult::Continue;
-                                }
-                                "size" => {
-                                    for _ in 0..arg_count {
-                                        self.stack.pop();
-                                    }
-                                    self.stack.push(JsValue::Number(map.len() as f64));
+                                    self.stack.push(JsValue::Object(arr_ptr));
                                     self.ip += 1;
                                     return ExecResult::Continue;
                                 }
-                                _ => {
+                                "reverse" => {
                                     for _ in 0..arg_count {
                                         self.stack.pop();
                                     }
-                                    self.stack.push(JsValue::Undefined);
+                                    arr.reverse();
+                                    self.stack.push(JsValue::Object(ptr));
                                     self.ip += 1;
                                     return ExecResult::Continue;
                                 }
-                            }
-                        }
-
-                        // Check if this is a Set and handle Set methods
-                        if let Some(HeapObject {
-                            data: HeapData::Set(set),
-                        }) = self.heap.get_mut(ptr)
-                        {
-                            match name.as_str() {
-                                "add" => {
+                                "fill" => {
                                     let value = if arg_count > 0 {
                                         self.stack.pop().unwrap_or(JsValue::Undefined)
                                     } else {
@@ -3224,86 +3807,39 @@ impl VM {
                                     for _ in 1..arg_count {
                                         self.stack.pop();
                                     }
-                                    // Check if value already exists
-                                    let exists = set.iter().any(|v| match (v, &value) {
-                                        (JsValue::Number(a), JsValue::Number(b)) => a == b,
-                                        (JsValue::String(a), JsValue::String(b)) => a == b,
-                                        (JsValue::Boolean(a), JsValue::Boolean(b)) => a == b,
-                                        (JsValue::Null, JsValue::Null) => true,
-                                        (JsValue::Undefined, JsValue::Undefined) => true,
-                                        (JsValue::Object(a), JsValue::Object(b)) => a == b,
-                                        _ => false,
-                                    });
-                                    if !exists {
-                                        set.push(value);
+                                    for elem in arr.iter_mut() {
+                                        *elem = value.clone();
                                     }
-                                    self.stack.push(JsValue::Object(ptr)); // Return the set itself
+                                    self.stack.push(JsValue::Object(ptr));
                                     self.ip += 1;
                                     return ExecResult::Continue;
                                 }
-                                "has" => {
-                                    let value = if arg_count > 0 {
-                                        self.stack.pop().unwrap_or(JsValue::Undefined)
+                                "at" => {
+                                    let index = if arg_count > 0 {
+                                        match self.stack.pop() {
+                                            Some(JsValue::Number(n)) => n as i64,
+                                            _ => 0,
+                                        }
                                     } else {
-                                        JsValue::Undefined
+                                        0
                                     };
                                     for _ in 1..arg_count {
                                         self.stack.pop();
                                     }
-                                    let found = set.iter().any(|v| match (v, &value) {
-                                        (JsValue::Number(a), JsValue::Number(b)) => a == b,
-                                        (JsValue::String(a), JsValue::String(b)) => a == b,
-                                        (JsValue::Boolean(a), JsValue::Boolean(b)) => a == b,
-                                        (JsValue::Null, JsValue::Null) => true,
-                                        (JsValue::Undefined, JsValue::Undefined) => true,
-                                        (JsValue::Object(a), JsValue::Object(b)) => a == b,
-                                        _ => false,
-                                    });
-                                    self.stack.push(JsValue::Boolean(found));
-                                    self.ip += 1;
-                                    return ExecResult::Continue;
-                                }
-                                "delete" => {
-                                    let value = if arg_count > 0 {
-                                        self.stack.pop().unwrap_or(JsValue::Undefined)
+                                    let len = arr.len() as i64;
+                                    let actual_idx = if index < 0 {
+                                        (len + index) as usize
                                     } else {
-                                        JsValue::Undefined
+                                        index as usize
                                     };
-                                    for _ in 1..arg_count {
-                                        self.stack.pop();
-                                    }
-                                    let initial_len = set.len();
-                                    set.retain(|v| match (v, &value) {
-                                        (JsValue::Number(a), JsValue::Number(b)) => a != b,
-                                        (JsValue::String(a), JsValue::String(b)) => a != b,
-                                        (JsValue::Boolean(a), JsValue::Boolean(b)) => a != b,
-                                        (JsValue::Null, JsValue::Null) => false,
-                                        (JsValue::Undefined, JsValue::Undefined) => false,
-                                        (JsValue::Object(a), JsValue::Object(b)) => a != b,
-                                        _ => true,
-                                    });
-                                    self.stack.push(JsValue::Boolean(set.len() < initial_len));
-                                    self.ip += 1;
-                                    return ExecResult::Continue;
-                                }
-                                "clear" => {
-                                    for _ in 0..arg_count {
-                                        self.stack.pop();
-                                    }
-                                    set.clear();
-                                    self.stack.push(JsValue::Undefined);
-                                    self.ip += 1;
-                                    return ExecResult::Continue;
-                                }
-                                "size" => {
-                                    for _ in 0..arg_count {
-                                        self.stack.pop();
-                                    }
-                                    self.stack.push(JsValue::Number(set.len() as f64));
+                                    let result =
+                                        arr.get(actual_idx).cloned().unwrap_or(JsValue::Undefined);
+                                    self.stack.push(result);
                                     self.ip += 1;
                                     return ExecResult::Continue;
                                 }
                                 _ => {
+                                    // Unsupported array method - pop args and return undefined
                                     for _ in 0..arg_count {
                                         self.stack.pop();
                                     }
@@ -3314,6 +3850,67 @@ impl VM {
                             }
                         }
 
+                        // Map, Set, WeakMap, WeakSet and their iterators' methods
+                        if let Some(HeapObject {
+                            data:
+                                HeapData::Map(_)
+                                | HeapData::Set(_)
+                                | HeapData::WeakMap(_)
+                                | HeapData::WeakSet(_)
+                                | HeapData::CollectionIterator { .. },
+                        }) = self.heap.get(ptr)
+                        {
+                            let split = self.stack.len().saturating_sub(arg_count);
+                            let args = self.stack.split_off(split);
+                            if name == "forEach"
+                                && matches!(
+                                    self.heap[ptr].data,
+                                    HeapData::Map(_) | HeapData::Set(_)
+                                )
+                            {
+                                return self.collection_for_each(ptr, args);
+                            }
+                            match crate::stdlib::collections::call_method(self, ptr, &name, &args) {
+                                Ok(result) => self.stack.push(result),
+                                Err((name, message)) => return self.throw_error(name, message),
+                            }
+                            self.ip += 1;
+                            return ExecResult::Continue;
+                        }
+
+                        // Check if this is a Date and handle Date methods
+                        if let Some(HeapObject {
+                            data: HeapData::Date(_),
+                        }) = self.heap.get(ptr)
+                        {
+                            let split = self.stack.len().saturating_sub(arg_count);
+                            let args = self.stack.split_off(split);
+                            match crate::stdlib::date::call_method(self, ptr, &name, &args) {
+                                Ok(result) => self.stack.push(result),
+                                Err(message) => return self.throw_error("RangeError", message),
+                            }
+                            self.ip += 1;
+                            return ExecResult::Continue;
+                        }
+
+                        // Buffer, typed array and DataView methods
+                        if let Some(HeapObject {
+                            data:
+                                HeapData::ArrayBuffer(_)
+                                | HeapData::TypedArray { .. }
+                                | HeapData::DataView { .. },
+                        }) = self.heap.get(ptr)
+                        {
+                            let split = self.stack.len().saturating_sub(arg_count);
+                            let args = self.stack.split_off(split);
+                            match crate::stdlib::typed_array::call_method(self, ptr, &name, &args) {
+                                Ok(result) => self.stack.push(result),
+                                Err((name, message)) => return self.throw_error(name, message),
+                            }
+                            self.ip += 1;
+                            return ExecResult::Continue;
+                        }
+
                         // Lookup the method in the object through prototype chain
                         let method = self.get_prop_with_proto_chain(ptr, &name);
 
@@ -3324,19 +3921,30 @@ impl VM {
                                 args.push(self.stack.pop().expect("Missing argument"));
                             }
                             args.reverse();
+                            // A frame only to give the native its `this`
+                            self.call_stack.push(Frame {
+                                return_address: self.ip + 1,
+                                locals: HashMap::new(),
+                                indexed_locals: Vec::new(),
+                                this_context: JsValue::Object(ptr),
+                                new_target: None,
+                                super_called: false,
+                                async_call: None,
+                            });
                             let func = self.native_functions[idx];
                             let result = func(self, args);
+                            self.call_stack.pop();
+                            if let Some(exception) = self.native_exception.take() {
+                                return self.throw_value(exception);
+                            }
                             self.stack.push(result);
                             // Increment IP before returning since we return early
                             self.ip += 1;
                             return ExecResult::Continue;
                         } else if let JsValue::Function { address, env } = method {
                             // Stack overflow protection
-                            if self.call_stack.len() >= MAX_CALL_STACK_DEPTH {
-                                panic!(
-                                    "Stack overflow: maximum call depth of {} exceeded",
-                                    MAX_CALL_STACK_DEPTH
-                                );
+                            if let Some(result) = self.check_call_depth() {
+                                return result;
                             }
 
                             // Collect arguments
@@ -3359,7 +3967,7 @@ impl VM {
                                 this_context: JsValue::Object(ptr),
                                 new_target: None,
                                 super_called: false,
-                                resume_ip: None,
+                                async_call: None,
                             };
 
                             // Load captured variables from environment
@@ -3376,33 +3984,47 @@ impl VM {
                             self.ip = address;
                             return ExecResult::ContinueNoIpInc;
                         }
-                        panic!("Method {} not found on object", name);
+                        return self
+                            .throw_error("TypeError", format!("{} is not a function", name));
                     }
                     // Handle Promise.then and Promise.catch methods
                     JsValue::Promise(promise) => {
-                        match name.as_str() {
-                            "then" => {
-                                // promise.then(onFulfilled)
-                                let on_fulfilled = self.stack.pop().unwrap_or(JsValue::Undefined);
-                                let result_promise = promise.then(Some(on_fulfilled));
-                                self.stack.push(JsValue::Promise(result_promise));
-                                self.ip += 1;
-                                return ExecResult::Continue;
-                            }
-                            "catch" => {
-                                // promise.catch(onRejected)
-                                let on_rejected = self.stack.pop().unwrap_or(JsValue::Undefined);
-                                let result_promise = promise.catch(Some(on_rejected));
-                                self.stack.push(JsValue::Promise(result_promise));
-                                self.ip += 1;
-                                return ExecResult::Continue;
-                            }
+                        let split = self.stack.len().saturating_sub(arg_count);
+                        let mut args = self.stack.split_off(split).into_iter();
+                        let (on_fulfilled, on_rejected) = match name.as_str() {
+                            // promise.then(onFulfilled, onRejected)
+                            "then" => (args.next(), args.next()),
+                            // promise.catch(onRejected)
+                            "catch" => (None, args.next()),
                             _ => {
                                 self.stack.push(JsValue::Undefined);
                                 self.ip += 1;
                                 return ExecResult::Continue;
                             }
+                        };
+                        let result_promise = self.promise_then(
+                            &promise,
+                            on_fulfilled.unwrap_or(JsValue::Undefined),
+                            on_rejected.unwrap_or(JsValue::Undefined),
+                        );
+                        self.stack.push(JsValue::Promise(result_promise));
+                        self.ip += 1;
+                        return ExecResult::Continue;
+                    }
+                    // -- Number methods --
+                    JsValue::Number(n) => {
+                        let split = self.stack.len().saturating_sub(arg_count);
+                        let args = self.stack.split_off(split);
+                        match crate::stdlib::number::call_method(n, &name, &args) {
+                            Ok(result) => self.stack.push(result),
+                            Err((name, message)) => return self.throw_error(name, message),
                         }
+                        self.ip += 1;
+                        return ExecResult::Continue;
+                    }
+                    JsValue::Undefined | JsValue::Null => {
+                        let key = JsValue::String(name.as_str().into());
+                        return self.throw_nullish_property(&reciever, &key, false);
                     }
                     _ => {
                         self.stack.push(JsValue::Undefined);
@@ -3455,44 +4077,7 @@ impl VM {
                 // Pop the exception value
                 let exception = self.stack.pop().unwrap_or(JsValue::Undefined);
 
-                // Find a handler
-                if let Some(handler) = self.exception_handlers.pop() {
-                    // Unwind the stack to the handler's saved state
-                    self.stack.truncate(handler.stack_depth);
-
-                    // Unwind call stack if needed
-                    while self.call_stack.len() > handler.call_stack_depth {
-                        self.call_stack.pop();
-                    }
-
-                    if handler.catch_addr != 0 {
-                        // We have a catch block - push exception and jump there
-                        self.stack.push(exception);
-                        self.ip = handler.catch_addr;
-
-                        // If there's a finally, we need to remember to run it
-                        // after the catch completes
-                        if handler.finally_addr != 0 {
-                            // Re-push a handler for finally (catch_addr=0 means no catch, just finally)
-                            self.exception_handlers.push(ExceptionHandler {
-                                catch_addr: 0,
-                                finally_addr: handler.finally_addr,
-                                stack_depth: self.stack.len() - 1, // Exclude the exception value
-                                call_stack_depth: handler.call_stack_depth,
-                            });
-                        }
-                        return ExecResult::ContinueNoIpInc;
-                    } else if handler.finally_addr != 0 {
-                        // No catch, but there's a finally block
-                        // Store exception for rethrow after finally
-                        self.current_exception = Some(exception);
-                        self.ip = handler.finally_addr;
-                        return ExecResult::ContinueNoIpInc;
-                    }
-                }
-
-                // No handler found - panic with uncaught exception
-                panic!("Uncaught exception: {:?}", exception);
+                return self.throw_value(exception);
             }
 
             OpCode::EnterFinally(rethrow) => {
@@ -3525,7 +4110,9 @@ impl VM {
                     }
                     self.stack.push(JsValue::Object(obj_ptr));
                 } else {
-                    panic!("SetProto: expected object, got {:?}", obj);
+                    let obj = crate::stdlib::inspect_value(self, &obj);
+                    return self
+                        .throw_error("TypeError", format!("Cannot set the prototype of {}", obj));
                 }
             }
 
@@ -3589,13 +4176,38 @@ impl VM {
                             JsValue::Undefined
                         }
                     }
-                    _ => panic!(
-                        "CallSuper: super is not a constructor, got {:?}",
-                        super_ctor
-                    ),
+                    _ => JsValue::Undefined,
+                };
+                // The parent's own `super(...)` resolves against the parent class
+                let new_target = match super_ctor {
+                    JsValue::Object(_) => Some(super_ctor.clone()),
+                    _ => None,
                 };
 
-                if let JsValue::Function { address, env } = ctor_fn {
+                if let JsValue::NativeFunction(idx) = ctor_fn {
+                    // A built-in parent such as Error initializes `this` in place
+                    let this_context = self.call_stack.last().unwrap().this_context.clone();
+                    args.reverse();
+                    self.call_stack.push(Frame {
+                        return_address: self.ip + 1,
+                        locals: HashMap::new(),
+                        indexed_locals: Vec::new(),
+                        this_context,
+                        new_target,
+                        super_called: false,
+                        async_call: None,
+                    });
+                    let func = self.native_functions[idx];
+                    let result = func(self, args);
+                    self.call_stack.pop();
+                    if let Some(exception) = self.native_exception.take() {
+                        return self.throw_value(exception);
+                    }
+                    self.stack.push(result);
+                } else if let JsValue::Function { address, env } = ctor_fn {
+                    if let Some(result) = self.check_call_depth() {
+                        return result;
+                    }
                     // Get current this context
                     let this_context = self.call_stack.last().unwrap().this_context.clone();
 
@@ -3609,9 +4221,9 @@ impl VM {
                         locals: HashMap::new(),
                         indexed_locals: Vec::new(),
                         this_context,
-                        new_target: None,
+                        new_target,
                         super_called: false,
-                        resume_ip: None,
+                        async_call: None,
                     };
 
                     // Load captured variables from closure environment
@@ -3628,7 +4240,11 @@ impl VM {
                     self.ip = address;
                     return ExecResult::ContinueNoIpInc;
                 } else {
-                    panic!("CallSuper: super constructor is not a function");
+                    let value = crate::stdlib::inspect_value(self, &super_ctor);
+                    return self.throw_error(
+                        "TypeError",
+                        format!("Super constructor {} is not a constructor", value),
+                    );
                 }
             }
 
@@ -3906,7 +4522,7 @@ impl VM {
                             this_context: target_for_frame.clone(),
                             new_target: Some(target_for_frame),
                             super_called: false,
-                            resume_ip: None,
+                            async_call: None,
                         };
 
                         // Load captured variables from environment
@@ -3930,242 +4546,207 @@ impl VM {
                 }
             }
 
+            OpCode::Freeze => {
+                if let Some(JsValue::Object(ptr)) = self.stack.last() {
+                    self.frozen_objects.insert(*ptr);
+                }
+            }
+
+            OpCode::ToIterable => {
+                let value = self.stack.pop().unwrap_or(JsValue::Undefined);
+                // An array is iterated in place, so the loop sees elements
+                // pushed onto it while it runs
+                if let JsValue::Object(ptr) = value
+                    && let Some(HeapObject {
+                        data: HeapData::Array(_),
+                    }) = self.heap.get(ptr)
+                {
+                    self.stack.push(value);
+                } else {
+                    match crate::stdlib::collections::iterable_values(self, &value) {
+                        Some(values) => {
+                            let ptr = self.heap.len();
+                            self.heap.push(HeapObject {
+                                data: HeapData::Array(values),
+                            });
+                            self.stack.push(JsValue::Object(ptr));
+                        }
+                        None => {
+                            let (name, message) =
+                                crate::stdlib::collections::not_iterable(self, &value);
+                            return self.throw_error(name, message);
+                        }
+                    }
+                }
+            }
+
             OpCode::ImportAsync(_specifier) => {
-                let specifier_str = match self.stack.pop() {
+                let specifier = match self.stack.pop() {
                     Some(JsValue::String(s)) => s,
-                    Some(_) => {
-                        self.stack.push(JsValue::Undefined);
-                        return ExecResult::Continue;
-                    }
-                    None => {
+                    _ => {
                         self.stack.push(JsValue::Undefined);
+                        self.ip += 1;
                         return ExecResult::Continue;
                     }
                 };
-
-                let importer_path = self.current_module_path.clone();
-
-                let resolved_path = {
-                    let importer_dir = importer_path
-                        .as_ref()
-                        .and_then(|p| {
-                            if p.is_file() {
-                                p.parent()
-                            } else if p.exists() && p.is_dir() {
-                                Some(p)
-                            } else {
-                                Some(p)
-                            }
-                        })
-                        .map(|p| {
-                            if p.as_os_str().is_empty() {
-                                Path::new(".")
-                            } else {
-                                p
-                            }
-                        })
-                        .unwrap_or(Path::new("."));
-
-                    let mut resolved = importer_dir.to_path_buf();
-
-                    for component in specifier_str.split('/') {
-                        match component {
-                            "." => {}
-                            ".." => {
-                                if !resolved.as_os_str().is_empty() {
-                                    resolved.pop();
-                                }
-                            }
-                            "" if specifier_str.starts_with("./") => {}
-                            "" if specifier_str.starts_with("../") => {}
-                            _ => resolved.push(component),
-                        };
+                let importer = self
+                    .current_module_path
+                    .clone()
+                    .unwrap_or_else(|| PathBuf::from("."));
+                match self.import_module(&specifier, &importer) {
+                    Ok(namespace_ptr) => self.stack.push(JsValue::Object(namespace_ptr)),
+                    Err(e) => {
+                        let error = self.module_error_value(&specifier, &e);
+                        return self.throw_value(error);
                     }
+                }
+            }
 
-                    let extensions = ["ot", "ts", "js"];
-                    if resolved.as_os_str().is_empty() || specifier_str.ends_with('/') {
-                        for ext in &extensions {
-                            let index_path = resolved.join("index").with_extension(ext);
-                            if index_path.exists() {
-                                resolved = index_path;
-                                break;
-                            }
-                        }
-                    } else if !resolved.exists() {
-                        for ext in &extensions {
-                            let with_ext = resolved.with_extension(ext);
-                            if with_ext.exists() {
-                                resolved = with_ext;
-                                break;
-                            }
-                        }
-                    }
-                    resolved
+            OpCode::ImportMeta => {
+                let path = match self.stack.pop() {
+                    Some(JsValue::String(path)) => PathBuf::from(path.as_str()),
+                    _ => self.current_module_path.clone().unwrap_or_default(),
                 };
+                let meta = self.import_meta(&path);
+                self.stack.push(JsValue::Object(meta));
+            }
 
-                if !resolved_path.exists() {
-                    eprintln!("Error: Module not found: {}", specifier_str);
-                    self.stack.push(JsValue::Undefined);
-                    return ExecResult::Continue;
-                }
-
-                // Check cache first
-                let canonical_path = match fs::canonicalize(&resolved_path) {
-                    Ok(p) => p,
-                    Err(e) => {
-                        eprintln!("Error canonicalizing path: {}", e);
-                        self.stack.push(JsValue::Undefined);
-                        return ExecResult::Continue;
+            OpCode::ImportDynamic => {
+                let specifier = self.stack.pop().unwrap_or(JsValue::Undefined);
+                let importer = self
+                    .current_module_path
+                    .clone()
+                    .unwrap_or_else(|| PathBuf::from("."));
+                let promise = Promise::new();
+                // Loading starts once the current task yields, like a network fetch
+                let loader = match self.dynamic_import_loader {
+                    Some(idx) => idx,
+                    None => {
+                        let idx = self.register_native(module_loader::load_dynamic_import);
+                        self.dynamic_import_loader = Some(idx);
+                        idx
                     }
                 };
+                self.task_queue.push_back(Task {
+                    function_ptr: JsValue::NativeFunction(loader),
+                    args: vec![
+                        specifier,
+                        JsValue::String(importer.to_string_lossy().into_owned().into()),
+                        JsValue::Promise(promise.clone()),
+                    ],
+                });
+                self.stack.push(JsValue::Promise(promise));
+            }
 
-                // Check if we have a valid cached version
-                if let Some(cached) = self.module_cache.get(&canonical_path) {
-                    // Cache hit - return cached namespace object
-                    self.stack.push(JsValue::Object(cached.namespace_object));
-                    // Fall through to ip += 1 at end of exec_one
-                } else {
-                    // Cache miss - load the module
-                    let result = fs::read_to_string(&canonical_path)
-                        .map_err(|e| format!("Failed to read module: {}", e));
-
-                    match result {
-                        Ok(source) => {
-                            let hash = ModuleCache::compute_hash(&canonical_path);
-                            let exports =
-                                parse_module_exports(&source, &canonical_path.to_string_lossy());
-                            let export_names: Vec<String> = exports.keys().cloned().collect();
-                            let mut namespace_props = HashMap::new();
-                            namespace_props.insert(
-                                "__path__".to_string(),
-                                JsValue::String(canonical_path.to_string_lossy().into_owned()),
-                            );
-                            namespace_props
-                                .insert("__source__".to_string(), JsValue::String(source.clone()));
-                            namespace_props
-                                .insert("__hash__".to_string(), JsValue::String(hash.clone()));
-                            match self.execute_module(&source, &canonical_path, &export_names) {
-                                Ok(module_exports) => {
-                                    for (name, value) in module_exports {
-                                        namespace_props.insert(name, value);
-                                    }
-                                }
-                                Err(e) => {
-                                    eprintln!("Error executing module '{}': {}", specifier_str, e);
-                                    for name in &export_names {
-                                        namespace_props.insert(name.clone(), JsValue::Undefined);
-                                    }
-                                }
-                            }
-                            let namespace_ptr = self.heap.len();
-                            self.heap.push(HeapObject {
-                                data: HeapData::Object(namespace_props),
-                            });
-                            let cached_module = CachedModule {
-                                path: canonical_path.clone(),
-                                source,
-                                hash,
-                                load_time: std::time::SystemTime::now(),
-                                namespace_object: namespace_ptr,
-                            };
-                            self.module_cache.insert(cached_module);
-                            self.stack.push(JsValue::Object(namespace_ptr));
-                        }
-                        Err(e) => {
-                            eprintln!("Error loading module '{}': {}", specifier_str, e);
-                            self.stack.push(JsValue::Undefined);
-                        }
-                    }
+            OpCode::EnterAsync => {
+                let async_call = AsyncCall {
+                    promise: Promise::new(),
+                    stack_base: self.stack.len(),
+                };
+                if let Some(frame) = self.call_stack.last_mut() {
+                    frame.async_call = Some(async_call);
                 }
             }
 
+            OpCode::Await
+                if self
+                    .call_stack
+                    .last()
+                    .is_some_and(|f| f.async_call.is_some()) =>
+            {
+                let awaited = match self.stack.pop() {
+                    Some(JsValue::Promise(promise)) => promise,
+                    other => Promise::with_value(other.unwrap_or(JsValue::Undefined)),
+                };
+                return self.suspend_async_call(awaited);
+            }
+
             OpCode::Await => {
+                // Top-level await: run the event loop until the promise settles.
                 // Stack: [promise] -> [result]
                 let promise = match self.stack.pop() {
                     Some(JsValue::Promise(p)) => p,
                     Some(other) => {
                         // Non-promise values are passed through (thenable check simplified)
                         self.stack.push(other);
-                        self.stack.push(JsValue::Undefined);
+                        self.ip += 1;
                         return ExecResult::Continue;
                     }
                     None => {
                         self.stack.push(JsValue::Undefined);
+                        self.ip += 1;
                         return ExecResult::Continue;
                     }
                 };
 
-                // Poll the promise synchronously (simplified implementation)
-                let state = promise.get_state();
-                eprintln!("DEBUG Await: promise state = {:?}", state);
-
-                match state {
-                    PromiseState::Fulfilled => {
-                        let value = promise.get_value().unwrap_or(JsValue::Undefined);
-                        eprintln!("DEBUG Await: fulfilled with value = {:?}", value);
-                        self.stack.push(value);
-                    }
-                    PromiseState::Rejected => {
-                        let value = promise.get_value().unwrap_or(JsValue::Undefined);
-                        eprintln!("DEBUG Await: rejected with value = {:?}", value);
-                        self.stack.push(value);
-                    }
-                    PromiseState::Pending => {
-                        eprintln!("DEBUG Await: still pending, polling...");
-                        // Poll until resolved (with timeout)
-                        let result = self.poll_promise(&promise, 1000);
-                        eprintln!("DEBUG Await: poll result = {:?}", result);
-                        self.stack.push(result);
+                if promise.get_state() == PromiseState::Pending {
+                    // Let queued tasks and timers settle the promise first,
+                    // then fall back to polling for promises resolved off-thread
+                    self.settle_on_event_loop(&promise);
+                    if self.uncaught.is_some() {
+                        return ExecResult::Stop;
                     }
+                    self.poll_promise(&promise, 1000);
+                }
+                let value = promise.get_value().unwrap_or(JsValue::Undefined);
+                // Awaiting a rejected promise throws its reason
+                if promise.get_state() == PromiseState::Rejected {
+                    return self.throw_value(value);
                 }
+                self.stack.push(value);
             }
 
             OpCode::GetExport {
                 name,
                 is_default: _,
             } => {
-                let namespace = match self.stack.pop() {
-                    Some(JsValue::Object(ptr)) => {
-                        if let Some(HeapObject {
-                            data: HeapData::Object(props),
-                            ..
-                        }) = self.heap.get(ptr)
-                        {
-                            props.clone()
-                        } else {
-                            HashMap::new()
-                        }
-                    }
+                let namespace_ptr = match self.stack.pop() {
+                    Some(JsValue::Object(ptr)) => ptr,
                     Some(_) => {
                         self.stack.push(JsValue::Undefined);
+                        self.ip += 1;
                         return ExecResult::Continue;
                     }
                     None => {
                         self.stack.push(JsValue::Undefined);
+                        self.ip += 1;
                         return ExecResult::Continue;
                     }
                 };
 
-                let export_value = namespace.get(&name).cloned().unwrap_or(JsValue::Undefined);
-                self.stack.push(export_value);
+                let export_value = match self.heap.get(namespace_ptr) {
+                    Some(HeapObject {
+                        data: HeapData::Object(props),
+                    }) => props.get(&name).cloned(),
+                    _ => None,
+                };
+                match export_value {
+                    Some(value) => self.stack.push(value),
+                    // Reached through an import cycle before the exporter ran
+                    None if self.module_linker.is_uninitialized(namespace_ptr, &name) => {
+                        return self.throw_error(
+                            "ReferenceError",
+                            format!("Cannot access '{}' before initialization", name),
+                        );
+                    }
+                    None => self.stack.push(JsValue::Undefined),
+                }
             }
 
             OpCode::ModuleResolutionError {
                 message,
                 specifier,
                 importer,
-                dependency_chain: _,
+                dependency_chain,
             } => {
-                let _specifier = self.stack.pop().unwrap_or(JsValue::Undefined);
-                let _importer = self.stack.pop().unwrap_or(JsValue::Undefined);
-                eprintln!(
-                    "Error: Module resolution error: {} (trying to import {} from {})",
+                self.stack.pop();
+                self.stack.pop();
+                let message = format!(
+                    "Module resolution error: {} (importing {} from {})",
                     message, specifier, importer
                 );
-                self.stack.push(JsValue::String(format!(
-                    "ModuleResolutionError: {} (importing {} from {})",
-                    message, specifier, importer
-                )));
+                let error = self.import_error_value("Error", message, &specifier, dependency_chain);
+                return self.throw_value(error);
             }
         }
 
@@ -4185,7 +4766,7 @@ impl VM {
                     .as_bytes(),
             ) {
                 Ok(_) => JsValue::Undefined,
-                Err(e) => JsValue::String(format!("Error writing bytecode file: {}", e)),
+                Err(e) => JsValue::String(format!("Error writing bytecode file: {}", e).into()),
             }
         } else {
             JsValue::Undefined
@@ -4199,3 +4780,12 @@ enum ExecResult {
     ContinueNoIpInc,
     Stop,
 }
+
+/// `s[index]`: the code unit at a valid index, as a string
+fn string_element(s: &JsString, index: f64) -> JsValue {
+    if index.fract() != 0.0 || index < 0.0 || index >= s.length() as f64 {
+        return JsValue::Undefined;
+    }
+    let index = index as usize;
+    JsValue::String(s.substring(index, index + 1))
+}
@@ -0,0 +1,429 @@
+//! Module environments and live bindings between ES modules
+//!
+//! Each imported module's top-level variables live in an environment of its
+//! own, so two modules can declare the same name. The entry script's top level
+//! is the global frame. Code finds its module's environment by address: the
+//! linker records the bytecode range of every module body.
+//!
+//! The linker remembers which variables of which module are exported by which
+//! namespace object and copies every write into those namespaces. Importers
+//! read through the namespace (`GetExport`), so they always see the current
+//! value.
+//!
+//! Writes are forwarded along `export { x } from` and `export * from` edges too.
+//! A module is registered before its body runs, which lets an import cycle hand
+//! out the half-initialized namespace; reading an export that has not been
+//! written yet is a TDZ error rather than a silent `undefined`.
+
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::path::PathBuf;
+
+use crate::compiler::ExportBinding;
+use crate::vm::value::{HeapData, HeapObject, JsValue};
+
+/// A loaded module, keyed by its namespace object
+#[derive(Debug, Clone)]
+pub struct ModuleRecord {
+    pub path: PathBuf,
+    /// Statically declared exports (`export *` names are not included)
+    pub export_names: HashSet<String>,
+    /// The module body has finished running
+    pub evaluated: bool,
+}
+
+/// What to do when a watched module variable is written
+#[derive(Debug, Clone, PartialEq)]
+enum Watcher {
+    /// Copy the value to `namespace[export]`
+    Export { namespace: usize, export: String },
+    /// The variable holds a source namespace; link its `name` to `namespace[export]`
+    Reexport {
+        namespace: usize,
+        export: String,
+        name: String,
+    },
+    /// The variable holds a source namespace; link all its exports to `namespace`
+    Star { namespace: usize },
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct ModuleLinker {
+    records: HashMap<usize, ModuleRecord>,
+    /// Watchers of each (namespace, variable)
+    watchers: HashMap<(usize, String), Vec<Watcher>>,
+    /// Top-level variables of each imported module
+    environments: HashMap<usize, HashMap<String, JsValue>>,
+    /// Bytecode of module bodies in address order, with their namespace
+    code: Vec<(Range<usize>, usize)>,
+    /// Module bodies being evaluated: call stack depth of their top level, and namespace
+    running: Vec<(usize, usize)>,
+    /// Namespace of the entry script, whose variables are the globals
+    entry: Option<usize>,
+    /// (source namespace, export) -> namespace properties re-exporting it
+    forwards: HashMap<(usize, String), Vec<(usize, String)>>,
+    /// Source namespace -> namespaces that `export *` from it
+    stars: HashMap<usize, Vec<usize>>,
+    next_module_id: usize,
+}
+
+impl ModuleLinker {
+    pub fn new() -> Self {
+        Self {
+            // 0 is the entry program
+            next_module_id: 1,
+            ..Default::default()
+        }
+    }
+
+    /// No variable is exported, so writes need no forwarding
+    pub fn is_empty(&self) -> bool {
+        self.watchers.is_empty()
+    }
+
+    /// Make `namespace` the entry script's module, whose top level is the
+    /// global frame
+    pub fn set_entry(&mut self, namespace: usize) {
+        self.environments.remove(&namespace);
+        self.entry = Some(namespace);
+    }
+
+    /// Record that `code` holds the body and functions of module `namespace`
+    pub fn add_code(&mut self, namespace: usize, code: Range<usize>) {
+        let index = self
+            .code
+            .partition_point(|(range, _)| range.start < code.start);
+        self.code.insert(index, (code, namespace));
+    }
+
+    /// The imported module whose code is at `ip`
+    pub fn module_at(&self, ip: usize) -> Option<usize> {
+        let index = self.code.partition_point(|(range, _)| range.start <= ip);
+        let (range, namespace) = self.code.get(index.checked_sub(1)?)?;
+        (range.contains(&ip) && Some(*namespace) != self.entry).then_some(*namespace)
+    }
+
+    /// Run the body of module `namespace` with its top level at call stack `depth`
+    pub fn begin_body(&mut self, namespace: usize, depth: usize) {
+        self.running.push((depth, namespace));
+    }
+
+    pub fn end_body(&mut self) {
+        self.running.pop();
+    }
+
+    /// The imported module whose top level is running at call stack `depth`
+    pub fn body_at(&self, depth: usize) -> Option<usize> {
+        match self.running.last() {
+            Some(&(body_depth, namespace))
+                if body_depth == depth && Some(namespace) != self.entry =>
+            {
+                Some(namespace)
+            }
+            _ => None,
+        }
+    }
+
+    /// A top-level variable of the module whose code is at `ip`
+    pub fn lookup(&self, ip: usize, name: &str) -> Option<&JsValue> {
+        self.environments.get(&self.module_at(ip)?)?.get(name)
+    }
+
+    /// Declare a top-level variable of module `namespace`
+    pub fn define(
+        &mut self,
+        heap: &mut [HeapObject],
+        namespace: usize,
+        name: String,
+        value: JsValue,
+    ) {
+        self.publish(heap, namespace, &name, &value);
+        self.environments
+            .entry(namespace)
+            .or_default()
+            .insert(name, value);
+    }
+
+    /// Assign a top-level variable of the module whose code is at `ip`, if
+    /// it has one called `name`
+    pub fn assign(
+        &mut self,
+        heap: &mut [HeapObject],
+        ip: usize,
+        name: &str,
+        value: &JsValue,
+    ) -> bool {
+        let Some(namespace) = self.module_at(ip) else {
+            return false;
+        };
+        match self
+            .environments
+            .get_mut(&namespace)
+            .and_then(|env| env.get_mut(name))
+        {
+            Some(slot) => *slot = value.clone(),
+            None => return false,
+        }
+        self.publish(heap, namespace, name, value);
+        true
+    }
+
+    /// Remove a top-level variable of module `namespace`
+    pub fn remove(&mut self, namespace: usize, name: &str) {
+        if let Some(env) = self.environments.get_mut(&namespace) {
+            env.remove(name);
+        }
+    }
+
+    /// Propagate a write to the global variable `name`, which belongs to the
+    /// entry script
+    pub fn publish_global(&mut self, heap: &mut [HeapObject], name: &str, value: &JsValue) {
+        if let Some(entry) = self.entry {
+            self.publish(heap, entry, name, value);
+        }
+    }
+
+    /// A fresh id for naming a module's hidden compiler variables
+    pub fn next_module_id(&mut self) -> usize {
+        let id = self.next_module_id;
+        self.next_module_id += 1;
+        id
+    }
+
+    pub fn record(&self, namespace: usize) -> Option<&ModuleRecord> {
+        self.records.get(&namespace)
+    }
+
+    /// Register a module before its body runs
+    pub fn register(&mut self, namespace: usize, path: PathBuf, exports: &[ExportBinding]) {
+        let mut export_names = HashSet::new();
+        for binding in exports {
+            let (variable, watcher) = match binding {
+                ExportBinding::Local { export, local } => {
+                    export_names.insert(export.clone());
+                    (
+                        local,
+                        Watcher::Export {
+                            namespace,
+                            export: export.clone(),
+                        },
+                    )
+                }
+                ExportBinding::Reexport {
+                    export,
+                    namespace_var,
+                    name,
+                } => {
+                    export_names.insert(export.clone());
+                    (
+                        namespace_var,
+                        Watcher::Reexport {
+                            namespace,
+                            export: export.clone(),
+                            name: name.clone(),
+                        },
+                    )
+                }
+                ExportBinding::Star { namespace_var } => {
+                    (namespace_var, Watcher::Star { namespace })
+                }
+            };
+            self.watchers
+                .entry((namespace, variable.clone()))
+                .or_default()
+                .push(watcher);
+        }
+        // A reloaded module starts over with a fresh environment
+        if self.entry != Some(namespace) {
+            self.environments.insert(namespace, HashMap::new());
+        }
+        self.records.insert(
+            namespace,
+            ModuleRecord {
+                path,
+                export_names,
+                evaluated: false,
+            },
+        );
+    }
+
+    pub fn mark_evaluated(&mut self, namespace: usize) {
+        if let Some(record) = self.records.get_mut(&namespace) {
+            record.evaluated = true;
+        }
+    }
+
+    /// Forget a module (e.g. one that failed to load) so it can be retried
+    pub fn unregister(&mut self, namespace: usize) {
+        self.records.remove(&namespace);
+        self.watchers.retain(|(ns, _), _| *ns != namespace);
+    }
+
+    /// An export that is declared but has not been initialized yet
+    pub fn is_uninitialized(&self, namespace: usize, name: &str) -> bool {
+        self.records
+            .get(&namespace)
+            .is_some_and(|r| !r.evaluated && r.export_names.contains(name))
+    }
+
+    /// Propagate a write to variable `name` of module `module` into namespace objects
+    pub fn publish(&mut self, heap: &mut [HeapObject], module: usize, name: &str, value: &JsValue) {
+        let Some(watchers) = self.watchers.get(&(module, name.to_string())).cloned() else {
+            return;
+        };
+        let mut pending = Vec::new();
+        for watcher in watchers {
+            match watcher {
+                Watcher::Export { namespace, export } => {
+                    pending.push((namespace, export, value.clone()));
+                }
+                Watcher::Reexport {
+                    namespace,
+                    export,
+                    name,
+                } => {
+                    let JsValue::Object(source) = value else {
+                        continue;
+                    };
+                    let targets = self.forwards.entry((*source, name.clone())).or_default();
+                    if !targets.contains(&(namespace, export.clone())) {
+                        targets.push((namespace, export.clone()));
+                    }
+                    if let Some(current) = namespace_get(heap, *source, &name) {
+                        pending.push((namespace, export, current));
+                    }
+                }
+                Watcher::Star { namespace } => {
+                    let JsValue::Object(source) = value else {
+                        continue;
+                    };
+                    let targets = self.stars.entry(*source).or_default();
+                    if !targets.contains(&namespace) {
+                        targets.push(namespace);
+                    }
+                    for (export, current) in namespace_exports(heap, *source) {
+                        if export != "default" {
+                            pending.push((namespace, export, current));
+                        }
+                    }
+                }
+            }
+        }
+        self.apply(heap, pending);
+    }
+
+    /// Write namespace properties, following re-export edges
+    fn apply(&self, heap: &mut [HeapObject], mut pending: Vec<(usize, String, JsValue)>) {
+        let mut visited = HashSet::new();
+        while let Some((namespace, export, value)) = pending.pop() {
+            if !visited.insert((namespace, export.clone())) {
+                continue;
+            }
+            if let Some(HeapObject {
+                data: HeapData::Object(props),
+            }) = heap.get_mut(namespace)
+            {
+                props.insert(export.clone(), value.clone());
+            }
+            if let Some(targets) = self.forwards.get(&(namespace, export.clone())) {
+                for (target, target_export) in targets {
+                    pending.push((*target, target_export.clone(), value.clone()));
+                }
+            }
+            if export != "default"
+                && let Some(targets) = self.stars.get(&namespace)
+            {
+                for target in targets {
+                    // A module's own exports win over names it star-exports
+                    let shadowed = self
+                        .records
+                        .get(target)
+                        .is_some_and(|r| r.export_names.contains(&export));
+                    if !shadowed {
+                        pending.push((*target, export.clone(), value.clone()));
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn namespace_get(heap: &[HeapObject], namespace: usize, name: &str) -> Option<JsValue> {
+    match heap.get(namespace) {
+        Some(HeapObject {
+            data: HeapData::Object(props),
+        }) => props.get(name).cloned(),
+        _ => None,
+    }
+}
+
+/// Exported values of a namespace, without its `__path__`-style bookkeeping entries
+fn namespace_exports(heap: &[HeapObject], namespace: usize) -> Vec<(String, JsValue)> {
+    match heap.get(namespace) {
+        Some(HeapObject {
+            data: HeapData::Object(props),
+        }) => props
+            .iter()
+            .filter(|(k, _)| !(k.starts_with("__") && k.ends_with("__")))
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn namespace(heap: &mut Vec<HeapObject>) -> usize {
+        heap.push(HeapObject {
+            data: HeapData::Object(HashMap::new()),
+        });
+        heap.len() - 1
+    }
+
+    #[test]
+    fn test_writes_reach_exporting_and_reexporting_namespaces() {
+        let mut heap = Vec::new();
+        let mut linker = ModuleLinker::new();
+        let a = namespace(&mut heap);
+        let b = namespace(&mut heap);
+        let c = namespace(&mut heap);
+        linker.register(
+            a,
+            PathBuf::from("a.ot"),
+            &[ExportBinding::Local {
+                export: "count".to_string(),
+                local: "count".to_string(),
+            }],
+        );
+        linker.register(
+            b,
+            PathBuf::from("b.ot"),
+            &[ExportBinding::Reexport {
+                export: "total".to_string(),
+                namespace_var: "__ns2_0__".to_string(),
+                name: "count".to_string(),
+            }],
+        );
+        linker.register(
+            c,
+            PathBuf::from("c.ot"),
+            &[ExportBinding::Star {
+                namespace_var: "__ns3_0__".to_string(),
+            }],
+        );
+        assert!(linker.is_uninitialized(a, "count"));
+
+        linker.publish(&mut heap, a, "count", &JsValue::Number(1.0));
+        linker.publish(&mut heap, b, "__ns2_0__", &JsValue::Object(a));
+        linker.publish(&mut heap, c, "__ns3_0__", &JsValue::Object(a));
+        assert_eq!(namespace_get(&heap, b, "total"), Some(JsValue::Number(1.0)));
+        assert_eq!(namespace_get(&heap, c, "count"), Some(JsValue::Number(1.0)));
+
+        linker.publish(&mut heap, a, "count", &JsValue::Number(2.0));
+        assert_eq!(namespace_get(&heap, a, "count"), Some(JsValue::Number(2.0)));
+        assert_eq!(namespace_get(&heap, b, "total"), Some(JsValue::Number(2.0)));
+        assert_eq!(namespace_get(&heap, c, "count"), Some(JsValue::Number(2.0)));
+    }
+}
@@ -0,0 +1,217 @@
+//! Loading modules for `import` and `import()`
+//!
+//! A static import is loaded, linked and evaluated on the spot. A dynamic
+//! `import()` first fetches and compiles the whole static graph beneath the
+//! imported module, so a missing or broken file rejects the promise with the
+//! chain of imports that led to it before any of the graph has run.
+
+use crate::compiler::CompiledModule;
+use crate::module::ModuleResolver;
+use crate::module::diagnostics::{DependencyInfo, ModuleError, ModuleErrorKind};
+use crate::vm::VM;
+use crate::vm::value::{HeapData, HeapObject, JsValue};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+impl VM {
+    /// Resolve an import specifier to a canonical module path
+    fn resolve_import(&mut self, specifier: &str, importer: &Path) -> Result<PathBuf, ModuleError> {
+        let importer = fs::canonicalize(importer).unwrap_or_else(|_| importer.to_path_buf());
+        // The project's oite.json is read once, from the first importer
+        let resolver = self.module_resolver.get_or_insert_with(|| {
+            ModuleResolver::discover(&importer).unwrap_or_else(|e| {
+                eprintln!("Error reading project config: {}", e);
+                ModuleResolver::new()
+            })
+        });
+        resolver
+            .resolve(specifier, &importer)
+            .map(|resolved| resolved.path.as_ref().clone())
+    }
+
+    /// `import.meta` of a module: `{ url }`, plus `hot` in watch mode
+    pub(super) fn import_meta(&mut self, path: &Path) -> usize {
+        if let Some(&meta) = self.import_metas.get(path) {
+            return meta;
+        }
+        let mut props = HashMap::new();
+        props.insert(
+            "url".to_string(),
+            JsValue::String(format!("file://{}", path.display()).into()),
+        );
+        if let Some(hot) = self.hot_object(path) {
+            props.insert("hot".to_string(), JsValue::Object(hot));
+        }
+        let meta = self.heap.len();
+        self.heap.push(HeapObject {
+            data: HeapData::Object(props),
+        });
+        self.import_metas.insert(path.to_path_buf(), meta);
+        meta
+    }
+
+    /// Load, link and evaluate a module unless it is already cached, returning
+    /// its namespace object
+    pub(super) fn import_module(
+        &mut self,
+        specifier: &str,
+        importer: &Path,
+    ) -> Result<usize, ModuleError> {
+        let path = self.resolve_import(specifier, importer)?;
+        if let Some(hot) = &mut self.hot_reloader {
+            let importer = fs::canonicalize(importer).unwrap_or_else(|_| importer.to_path_buf());
+            hot.record_import(importer, path.clone());
+        }
+        if let Some(cached) = self.module_cache.get(&path) {
+            return Ok(cached.namespace_object);
+        }
+
+        let (source, compiled) = match self.prepared_modules.remove(&path) {
+            Some(prepared) => prepared,
+            None => self.compile_module_file(&path)?,
+        };
+        // Link before running the body, so a module that imports this one
+        // back gets the same namespace
+        let namespace_ptr = self.link_module(&path, source, &compiled.exports);
+        self.execute_module(namespace_ptr, compiled.bytecode, compiled.source_map, &path);
+        self.module_linker.mark_evaluated(namespace_ptr);
+        Ok(namespace_ptr)
+    }
+
+    fn compile_module_file(
+        &mut self,
+        path: &Path,
+    ) -> Result<(String, CompiledModule), ModuleError> {
+        let source = fs::read_to_string(path)
+            .map_err(|e| ModuleError::io_error(path.to_path_buf(), e.to_string()))?;
+        let module_id = self.module_linker.next_module_id();
+        self.compiler.set_source_path(path);
+        let compiled = self
+            .compiler
+            .compile_module(
+                &source,
+                Some(crate::compiler::syntax_for_path(path)),
+                module_id,
+            )
+            .map_err(|e| ModuleError::compile_error(path.to_path_buf(), e))?;
+        Ok((source, compiled))
+    }
+
+    /// Fetch and compile a module and everything it statically imports, so a
+    /// missing or broken file fails the whole `import()` before any of it runs.
+    /// `chain` holds the modules on the path from the dynamically imported one.
+    fn prepare_module_graph(
+        &mut self,
+        specifier: &str,
+        importer: &Path,
+        chain: &mut Vec<DependencyInfo>,
+        visited: &mut HashSet<PathBuf>,
+    ) -> Result<(), ModuleError> {
+        let path = self
+            .resolve_import(specifier, importer)
+            .map_err(|e| e.with_dependency_chain(chain.clone()))?;
+        if self.module_cache.get(&path).is_some() || !visited.insert(path.clone()) {
+            return Ok(());
+        }
+
+        chain.push(DependencyInfo::new(path.clone(), specifier.to_string()));
+        let (source, compiled) = self
+            .compile_module_file(&path)
+            .map_err(|e| e.with_dependency_chain(chain.clone()))?;
+        for import in &compiled.imports {
+            self.prepare_module_graph(import, &path, chain, visited)?;
+        }
+        chain.pop();
+
+        self.prepared_modules.insert(path, (source, compiled));
+        Ok(())
+    }
+
+    /// The error thrown by a failed static import or rejecting a failed
+    /// `import()`: a SyntaxError if a module does not compile
+    pub(super) fn module_error_value(&mut self, specifier: &str, error: &ModuleError) -> JsValue {
+        let name = match *error.kind {
+            ModuleErrorKind::ParseError { .. } | ModuleErrorKind::CompileError { .. } => {
+                "SyntaxError"
+            }
+            _ => "Error",
+        };
+        let chain = error
+            .dependency_chain
+            .iter()
+            .map(|dep| dep.path.to_string_lossy().into_owned())
+            .collect();
+        let message = error.to_string().trim_end().to_string();
+        self.import_error_value(name, message, specifier, chain)
+    }
+
+    /// An error with the `specifier` and `dependencyChain` of a failed import
+    pub(super) fn import_error_value(
+        &mut self,
+        name: &str,
+        message: String,
+        specifier: &str,
+        chain: Vec<String>,
+    ) -> JsValue {
+        let chain_ptr = self.heap.len();
+        self.heap.push(HeapObject {
+            data: HeapData::Array(
+                chain
+                    .into_iter()
+                    .map(|s| JsValue::String(s.into()))
+                    .collect(),
+            ),
+        });
+
+        let value = self.error_value(name, message);
+        if let JsValue::Object(ptr) = value
+            && let Some(HeapObject {
+                data: HeapData::Object(props),
+            }) = self.heap.get_mut(ptr)
+        {
+            props.insert("specifier".to_string(), JsValue::String(specifier.into()));
+            props.insert("dependencyChain".to_string(), JsValue::Object(chain_ptr));
+        }
+        value
+    }
+}
+
+/// Task queued by `import()`: args are [specifier, importer path, promise]
+pub(super) fn load_dynamic_import(vm: &mut VM, args: Vec<JsValue>) -> JsValue {
+    let mut args = args.into_iter();
+    let specifier = args.next().unwrap_or(JsValue::Undefined);
+    let importer = match args.next() {
+        Some(JsValue::String(path)) => PathBuf::from(path.as_str()),
+        _ => PathBuf::from("."),
+    };
+    let Some(JsValue::Promise(promise)) = args.next() else {
+        return JsValue::Undefined;
+    };
+    let JsValue::String(specifier) = specifier else {
+        let error = vm.error_value(
+            "TypeError",
+            "import() expects a string specifier".to_string(),
+        );
+        vm.settle_promise(&promise, error, false);
+        return JsValue::Undefined;
+    };
+
+    let mut chain = Vec::new();
+    let mut visited = HashSet::new();
+    let result = vm
+        .prepare_module_graph(&specifier, &importer, &mut chain, &mut visited)
+        .and_then(|()| vm.import_module(&specifier, &importer));
+    match result {
+        Ok(namespace_ptr) => vm.settle_promise(&promise, JsValue::Object(namespace_ptr), true),
+        Err(e) => {
+            // Nothing from a failed graph is kept for a later import
+            for path in &visited {
+                vm.prepared_modules.remove(path);
+            }
+            let error = vm.module_error_value(&specifier, &e);
+            vm.settle_promise(&promise, error, false);
+        }
+    }
+    JsValue::Undefined
+}
@@ -14,6 +14,9 @@ pub enum OpCode {
     /// Assign to an existing variable (searches frames from inner to outer)
     Store(String),
     Load(String),
+    /// Like `Load`, but an undeclared name reads as undefined instead of
+    /// throwing a ReferenceError (`typeof x`)
+    LoadOrUndefined(String),
     Drop(String),
     Call(usize),
     Return,
@@ -51,7 +54,7 @@ pub enum OpCode {
     ArrayPush,
     /// ArraySpread: pops [target_array, source_array] -> appends all source elements to target, pushes target
     ArraySpread,
-    /// ObjectSpread: pops [target_obj, source_obj] -> copies all properties from source to target, pushes target
+    /// ObjectSpread: pops [target_obj, source_obj] -> copies the source's own enumerable properties to target, pushes target
     ObjectSpread,
     JumpIfFalse(usize),
     Halt,
@@ -135,15 +138,36 @@ pub enum OpCode {
     /// The decorator is called with the target and returns the decorated result
     ApplyDecorator,
 
+    /// Freeze: make the object on top of the stack read-only
+    /// Stack: [obj] -> [obj]
+    Freeze,
+
+    /// ToIterable: the values `for...of` visits in an iterable, as an array
+    /// Stack: [iterable] -> [array]
+    ToIterable,
+
     // === ES Modules ===
-    /// ImportAsync: Asynchronously load a module
-    /// Stack: [module_url] -> [promise]
-    /// The promise resolves to the module namespace object
+    /// ImportAsync: Load, link and evaluate a statically imported module
+    /// Stack: [module_url] -> [namespace]
     ImportAsync(String),
+    /// ImportDynamic: `import(specifier)`
+    /// Stack: [specifier] -> [promise]
+    /// The promise resolves to the module namespace once the module and its
+    /// static imports are loaded and evaluated
+    ImportDynamic,
+    /// ImportMeta: `import.meta` of the module at `path`
+    /// Stack: [path] -> [meta]
+    ImportMeta,
     /// Await: Await a promise value (must be in async context)
     /// Stack: [promise] -> [result]
-    /// Suspends execution until promise resolves
+    /// In an async call, suspends the call and returns its promise to the
+    /// caller; it resumes from a microtask once the promise settles. At the
+    /// top level, runs the event loop until the promise settles.
     Await,
+    /// EnterAsync: Make the current call an async call, after its parameters
+    /// are bound. Its `Return` resolves the call's promise and returns that;
+    /// an exception leaving the call rejects it.
+    EnterAsync,
     /// GetExport: Get named export from module namespace
     /// Stack: [namespace] -> [export_value]
     GetExport {
@@ -0,0 +1,201 @@
+//! Insertion-ordered hash tables behind `Map` and `Set`
+//!
+//! Keys compare by SameValueZero: `NaN` equals itself and `-0` equals `+0`,
+//! primitives otherwise compare by value and heap values by identity.
+//! Deleting an entry leaves a hole so the rest keep their insertion order
+//! without shifting; the holes are compacted away once they outnumber the
+//! live entries.
+
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+use super::value::JsValue;
+
+/// Holes tolerated before compaction, however few the live entries
+const MIN_HOLES_TO_COMPACT: usize = 8;
+
+/// A value hashed and compared by SameValueZero
+#[derive(Debug, Clone)]
+struct MapKey(JsValue);
+
+impl PartialEq for MapKey {
+    fn eq(&self, other: &Self) -> bool {
+        match (&self.0, &other.0) {
+            (JsValue::Number(a), JsValue::Number(b)) => a == b || (a.is_nan() && b.is_nan()),
+            (a, b) => a == b,
+        }
+    }
+}
+
+impl Eq for MapKey {}
+
+impl Hash for MapKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(&self.0).hash(state);
+        match &self.0 {
+            // +0 and -0 are equal, and all NaNs are
+            JsValue::Number(n) if *n == 0.0 => 0u64.hash(state),
+            JsValue::Number(n) if n.is_nan() => f64::NAN.to_bits().hash(state),
+            JsValue::Number(n) => n.to_bits().hash(state),
+            JsValue::String(s) => s.hash(state),
+            JsValue::Boolean(b) => b.hash(state),
+            JsValue::Object(ptr) | JsValue::NativeFunction(ptr) => ptr.hash(state),
+            JsValue::Function { address, env } => (address, env).hash(state),
+            JsValue::Promise(promise) => Arc::as_ptr(&promise.state).hash(state),
+            JsValue::Null | JsValue::Undefined | JsValue::Accessor(..) => {}
+        }
+    }
+}
+
+/// Entries in insertion order, indexed by key
+#[derive(Debug, Clone)]
+pub struct OrderedMap<V> {
+    entries: Vec<Option<(JsValue, V)>>,
+    index: HashMap<MapKey, usize>,
+}
+
+impl<V> Default for OrderedMap<V> {
+    fn default() -> Self {
+        Self {
+            entries: Vec::new(),
+            index: HashMap::new(),
+        }
+    }
+}
+
+impl<V> OrderedMap<V> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    pub fn get(&self, key: &JsValue) -> Option<&V> {
+        let slot = *self.index.get(&MapKey(key.clone()))?;
+        self.entries[slot].as_ref().map(|(_, value)| value)
+    }
+
+    pub fn contains_key(&self, key: &JsValue) -> bool {
+        self.index.contains_key(&MapKey(key.clone()))
+    }
+
+    /// Set the value of `key`. A new key goes last; an existing one keeps
+    /// its place. `-0` is stored as `+0`, as `Map.prototype.set` does.
+    pub fn insert(&mut self, key: JsValue, value: V) {
+        let key = match key {
+            // -0 + 0 is +0
+            JsValue::Number(n) => JsValue::Number(n + 0.0),
+            key => key,
+        };
+        match self.index.get(&MapKey(key.clone())) {
+            Some(&slot) => self.entries[slot] = Some((key, value)),
+            None => {
+                self.index.insert(MapKey(key.clone()), self.entries.len());
+                self.entries.push(Some((key, value)));
+            }
+        }
+    }
+
+    /// Delete `key`, returning whether it was present
+    pub fn remove(&mut self, key: &JsValue) -> bool {
+        let Some(slot) = self.index.remove(&MapKey(key.clone())) else {
+            return false;
+        };
+        self.entries[slot] = None;
+        let holes = self.entries.len() - self.index.len();
+        if holes >= MIN_HOLES_TO_COMPACT && holes > self.index.len() {
+            self.compact();
+        }
+        true
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.index.clear();
+    }
+
+    /// The entries in insertion order
+    pub fn iter(&self) -> impl Iterator<Item = (&JsValue, &V)> {
+        self.entries
+            .iter()
+            .flatten()
+            .map(|(key, value)| (key, value))
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &JsValue> {
+        self.iter().map(|(key, _)| key)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.iter().map(|(_, value)| value)
+    }
+
+    fn compact(&mut self) {
+        self.entries.retain(Option::is_some);
+        for (slot, (key, _)) in self.entries.iter().flatten().enumerate() {
+            self.index.insert(MapKey(key.clone()), slot);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(map: &OrderedMap<()>) -> Vec<JsValue> {
+        map.keys().cloned().collect()
+    }
+
+    #[test]
+    fn test_keys_compare_by_same_value_zero() {
+        let mut map = OrderedMap::new();
+        map.insert(JsValue::Number(f64::NAN), 1.0);
+        map.insert(JsValue::Number(-0.0), 2.0);
+        map.insert(JsValue::String("1".into()), 3.0);
+        map.insert(JsValue::Object(7), 4.0);
+        assert_eq!(map.get(&JsValue::Number(f64::NAN)), Some(&1.0));
+        assert_eq!(map.get(&JsValue::Number(0.0)), Some(&2.0));
+        assert_eq!(map.get(&JsValue::Number(1.0)), None);
+        assert_eq!(map.get(&JsValue::Object(7)), Some(&4.0));
+        assert_eq!(map.get(&JsValue::Object(8)), None);
+        assert_eq!(map.len(), 4);
+        // -0 is stored as +0
+        let zero = map.keys().nth(1).cloned();
+        assert!(matches!(zero, Some(JsValue::Number(n)) if n.is_sign_positive()));
+    }
+
+    #[test]
+    fn test_entries_keep_insertion_order() {
+        let mut set = OrderedMap::new();
+        for n in 0..20 {
+            set.insert(JsValue::Number(n as f64), ());
+        }
+        // Setting an existing key keeps its place
+        set.insert(JsValue::Number(0.0), ());
+        assert!(set.remove(&JsValue::Number(1.0)));
+        assert!(!set.remove(&JsValue::Number(1.0)));
+        set.insert(JsValue::Number(1.0), ());
+        assert_eq!(keys(&set)[0], JsValue::Number(0.0));
+        assert_eq!(keys(&set)[19], JsValue::Number(1.0));
+        // Enough deletes compact the holes without reordering
+        for n in 2..15 {
+            set.remove(&JsValue::Number(n as f64));
+        }
+        assert!(set.entries.len() < 20);
+        let expected: Vec<JsValue> = [0.0, 15.0, 16.0, 17.0, 18.0, 19.0, 1.0]
+            .into_iter()
+            .map(JsValue::Number)
+            .collect();
+        assert_eq!(keys(&set), expected);
+        assert!(set.contains_key(&JsValue::Number(19.0)));
+        set.clear();
+        assert!(set.is_empty());
+    }
+}
@@ -1,8 +1,295 @@
+//! Property lookup, accessors and attributes
+//!
+//! Objects keep their properties in a map of values. A getter or setter is
+//! either a `getter:name`/`setter:name` slot, as classes compile them, or a
+//! `JsValue::Accessor` under the name itself, as `Object.defineProperty`
+//! creates them. Properties are writable, enumerable and configurable unless
+//! `VM::property_attributes` says otherwise, and every property of a frozen
+//! object is read-only and permanent.
+
 use crate::vm::VM;
 use crate::vm::value::{HeapData, HeapObject, JsValue};
 
 const MAX_PROTO_DEPTH: usize = 100;
 
+/// The attributes of an own property
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Attributes {
+    pub writable: bool,
+    pub enumerable: bool,
+    pub configurable: bool,
+}
+
+impl Attributes {
+    /// Those of a property created by assignment
+    pub const DEFAULT: Attributes = Attributes {
+        writable: true,
+        enumerable: true,
+        configurable: true,
+    };
+}
+
+/// Whether `key` is VM bookkeeping rather than a property: the prototype
+/// link and other `__` slots, and class accessor slots
+pub fn is_internal_key(key: &str) -> bool {
+    key.starts_with("__") || key.starts_with("getter:") || key.starts_with("setter:")
+}
+
+/// The attributes of own property `key` of the object at `ptr`. An accessor
+/// property is never writable: assignment goes to its setter.
+pub fn attributes(vm: &VM, ptr: usize, key: &str) -> Attributes {
+    let mut attributes = vm
+        .property_attributes
+        .get(&ptr)
+        .and_then(|attributes| attributes.get(key))
+        .copied()
+        .unwrap_or(Attributes::DEFAULT);
+    if vm.frozen_objects.contains(&ptr) {
+        attributes.writable = false;
+        attributes.configurable = false;
+    }
+    if let Some(JsValue::Accessor(..)) = own_slot(vm, ptr, key) {
+        attributes.writable = false;
+    }
+    attributes
+}
+
+/// Record the attributes of own property `key`
+pub fn set_attributes(vm: &mut VM, ptr: usize, key: &str, attributes: Attributes) {
+    vm.property_attributes
+        .entry(ptr)
+        .or_default()
+        .insert(key.to_string(), attributes);
+}
+
+/// Whether every property of the object at `ptr` has default attributes,
+/// so writes and deletes need no checks
+fn is_plain(vm: &VM, ptr: usize) -> bool {
+    !vm.frozen_objects.contains(&ptr) && !vm.property_attributes.contains_key(&ptr)
+}
+
+/// Whether new properties can be added to the object at `ptr`
+pub fn is_extensible(vm: &VM, ptr: usize) -> bool {
+    !vm.frozen_objects.contains(&ptr)
+}
+
+/// The error message for assigning `key` on the object at `ptr`, if it
+/// can't be assigned
+pub fn check_write(vm: &VM, ptr: usize, key: &str) -> Result<(), String> {
+    if is_plain(vm, ptr) {
+        return Ok(());
+    }
+    if has_own(vm, ptr, key) {
+        if !attributes(vm, ptr, key).writable {
+            return Err(format!(
+                "Cannot assign to read only property '{}' of object",
+                key
+            ));
+        }
+    } else if !is_extensible(vm, ptr) {
+        return Err(format!(
+            "Cannot add property {}, object is not extensible",
+            key
+        ));
+    }
+    Ok(())
+}
+
+/// The error message for deleting `key` from the object at `ptr`, if it
+/// can't be deleted
+pub fn check_delete(vm: &VM, ptr: usize, key: &str) -> Result<(), String> {
+    if is_plain(vm, ptr) {
+        return Ok(());
+    }
+    if has_own(vm, ptr, key) && !attributes(vm, ptr, key).configurable {
+        return Err(format!("Cannot delete property '{}' of object", key));
+    }
+    Ok(())
+}
+
+/// The value stored under own property `key`, without calling a getter. A
+/// class accessor slot reads as the `JsValue::Accessor` it stands for.
+pub fn own_slot(vm: &VM, ptr: usize, key: &str) -> Option<JsValue> {
+    match &vm.heap.get(ptr)?.data {
+        HeapData::Object(props) => {
+            if let Some(value) = props.get(key) {
+                return Some(value.clone());
+            }
+            let getter = props.get(&format!("getter:{}", key));
+            let setter = props.get(&format!("setter:{}", key));
+            (getter.is_some() || setter.is_some()).then(|| {
+                JsValue::Accessor(getter.cloned().map(Box::new), setter.cloned().map(Box::new))
+            })
+        }
+        HeapData::Array(items) if key == "length" => Some(JsValue::Number(items.len() as f64)),
+        HeapData::Array(items) => items.get(key.parse::<usize>().ok()?).cloned(),
+        data @ HeapData::TypedArray { .. } => {
+            let index = key.parse::<usize>().ok()?;
+            let elements = crate::stdlib::typed_array::elements(vm, data);
+            elements.get(index).map(|&n| JsValue::Number(n))
+        }
+        _ => None,
+    }
+}
+
+pub fn has_own(vm: &VM, ptr: usize, key: &str) -> bool {
+    own_slot(vm, ptr, key).is_some()
+}
+
+/// The own property keys of the object at `ptr`: integer keys ascending,
+/// then the rest. Objects don't record insertion order, so the rest are
+/// sorted.
+pub fn own_keys(vm: &VM, ptr: usize) -> Vec<String> {
+    let mut keys: Vec<String> = match vm.heap.get(ptr).map(|obj| &obj.data) {
+        Some(HeapData::Object(props)) => {
+            let mut keys: Vec<String> = props
+                .keys()
+                .filter_map(|key| {
+                    let accessor = key
+                        .strip_prefix("getter:")
+                        .or_else(|| key.strip_prefix("setter:"));
+                    match accessor {
+                        Some(name) => Some(name.to_string()),
+                        None if is_internal_key(key) => None,
+                        None => Some(key.clone()),
+                    }
+                })
+                .collect();
+            keys.sort();
+            keys.dedup();
+            keys
+        }
+        Some(HeapData::Array(items)) => {
+            let mut keys: Vec<String> = (0..items.len()).map(|i| i.to_string()).collect();
+            keys.push("length".to_string());
+            return keys;
+        }
+        Some(data @ HeapData::TypedArray { .. }) => {
+            let length = crate::stdlib::typed_array::elements(vm, data).len();
+            return (0..length).map(|i| i.to_string()).collect();
+        }
+        _ => return Vec::new(),
+    };
+    let index = |key: &String| key.parse::<u32>().ok().filter(|i| i.to_string() == *key);
+    keys.sort_by_key(|key| (index(key).is_none(), index(key)));
+    keys
+}
+
+/// The own enumerable property keys of the object at `ptr`, as
+/// `Object.keys` lists them. Class accessors live on prototypes and aren't
+/// enumerable.
+pub fn own_enumerable_keys(vm: &VM, ptr: usize) -> Vec<String> {
+    let props = match vm.heap.get(ptr).map(|obj| &obj.data) {
+        Some(HeapData::Object(props)) => Some(props),
+        Some(HeapData::Array(_)) => None,
+        _ => return own_keys(vm, ptr),
+    };
+    own_keys(vm, ptr)
+        .into_iter()
+        .filter(|key| match props {
+            Some(props) => props.contains_key(key),
+            None => key != "length",
+        })
+        .filter(|key| attributes(vm, ptr, key).enumerable)
+        .collect()
+}
+
+/// The object the `__proto__` link of the object at `ptr` points to
+pub fn prototype(vm: &VM, ptr: usize) -> Option<usize> {
+    match &vm.heap.get(ptr)?.data {
+        HeapData::Object(props) => match props.get("__proto__") {
+            Some(JsValue::Object(proto)) => Some(*proto),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// `key in object`: whether the object at `ptr` or its prototype chain has
+/// property `key`
+pub fn has_property(vm: &VM, ptr: usize, key: &str) -> bool {
+    let mut current = Some(ptr);
+    for _ in 0..=MAX_PROTO_DEPTH {
+        let Some(ptr) = current else {
+            return false;
+        };
+        if has_own(vm, ptr, key) {
+            return true;
+        }
+        current = prototype(vm, ptr);
+    }
+    false
+}
+
+/// `object[key]`, calling a getter found on the prototype chain
+pub fn get(vm: &mut VM, ptr: usize, key: &str) -> Result<JsValue, JsValue> {
+    match vm.heap.get(ptr).map(|obj| &obj.data) {
+        Some(HeapData::Object(_)) => {}
+        _ => {
+            let key_value = JsValue::String(key.into());
+            return Ok(own_slot(vm, ptr, key)
+                .or_else(|| crate::stdlib::typed_array::get(vm, ptr, &key_value))
+                .unwrap_or(JsValue::Undefined));
+        }
+    }
+    let getter = get_prop_with_proto_chain(vm, ptr, &format!("getter:{}", key));
+    if let JsValue::Function { .. } = getter {
+        return vm.call_sync(&getter, JsValue::Object(ptr), Vec::new());
+    }
+    match get_prop_with_proto_chain(vm, ptr, key) {
+        JsValue::Accessor(Some(getter), _) => {
+            vm.call_sync(&getter, JsValue::Object(ptr), Vec::new())
+        }
+        JsValue::Accessor(None, _) => Ok(JsValue::Undefined),
+        value => Ok(value),
+    }
+}
+
+/// `object[key] = value`, calling a setter found on the prototype chain.
+/// `Ok(false)` when the property is read-only or can't be added.
+pub fn set(vm: &mut VM, ptr: usize, key: &str, value: JsValue) -> Result<bool, JsValue> {
+    if let Some(setter) = find_setter(vm, ptr, key) {
+        vm.call_sync(&setter, JsValue::Object(ptr), vec![value])?;
+        return Ok(true);
+    }
+    if check_write(vm, ptr, key).is_err() {
+        return Ok(false);
+    }
+    let key_value = JsValue::String(key.into());
+    if crate::stdlib::typed_array::set(vm, ptr, &key_value, &value) {
+        return Ok(true);
+    }
+    match vm.heap.get_mut(ptr).map(|obj| &mut obj.data) {
+        Some(HeapData::Object(props)) => {
+            props.insert(key.to_string(), value);
+            Ok(true)
+        }
+        Some(HeapData::Array(items)) => match key.parse::<usize>() {
+            Ok(i) => {
+                if i >= items.len() {
+                    items.resize(i + 1, JsValue::Undefined);
+                }
+                items[i] = value;
+                Ok(true)
+            }
+            Err(_) => Ok(false),
+        },
+        _ => Ok(false),
+    }
+}
+
+/// The setter `object[key] = value` calls, from a class accessor slot or
+/// an accessor property on the prototype chain
+pub fn find_setter(vm: &VM, ptr: usize, key: &str) -> Option<JsValue> {
+    if let Some((address, env)) = find_setter_with_proto_chain(vm, ptr, key) {
+        return Some(JsValue::Function { address, env });
+    }
+    match get_prop_with_proto_chain(vm, ptr, key) {
+        JsValue::Accessor(_, Some(setter)) => Some(*setter),
+        _ => None,
+    }
+}
+
 pub fn get_prop_with_proto_chain(vm: &VM, obj_ptr: usize, name: &str) -> JsValue {
     let mut current_ptr = Some(obj_ptr);
     let mut depth = 0;
@@ -71,3 +358,69 @@ pub fn find_setter_with_proto_chain(
 
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn object(vm: &mut VM, props: &[(&str, JsValue)]) -> usize {
+        let props: HashMap<String, JsValue> = props
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.clone()))
+            .collect();
+        vm.heap.push(HeapObject {
+            data: HeapData::Object(props),
+        });
+        vm.heap.len() - 1
+    }
+
+    #[test]
+    fn test_own_keys_put_indices_first_and_hide_bookkeeping() {
+        let mut vm = VM::new();
+        let proto = object(&mut vm, &[]);
+        let ptr = object(
+            &mut vm,
+            &[
+                ("b", JsValue::Null),
+                ("10", JsValue::Null),
+                ("2", JsValue::Null),
+                ("01", JsValue::Null),
+                ("__proto__", JsValue::Object(proto)),
+                ("getter:size", JsValue::Null),
+                ("setter:size", JsValue::Null),
+            ],
+        );
+        assert_eq!(own_keys(&vm, ptr), ["2", "10", "01", "b", "size"]);
+        // Class accessor slots aren't enumerable
+        assert_eq!(own_enumerable_keys(&vm, ptr), ["2", "10", "01", "b"]);
+        let hidden = Attributes {
+            enumerable: false,
+            ..Attributes::DEFAULT
+        };
+        set_attributes(&mut vm, ptr, "b", hidden);
+        assert_eq!(own_enumerable_keys(&vm, ptr), ["2", "10", "01"]);
+        assert_eq!(prototype(&vm, ptr), Some(proto));
+    }
+
+    #[test]
+    fn test_frozen_objects_reject_writes_and_deletes() {
+        let mut vm = VM::new();
+        let ptr = object(&mut vm, &[("x", JsValue::Number(1.0))]);
+        assert_eq!(check_write(&vm, ptr, "y"), Ok(()));
+        vm.frozen_objects.insert(ptr);
+        assert_eq!(
+            check_write(&vm, ptr, "x"),
+            Err("Cannot assign to read only property 'x' of object".to_string())
+        );
+        assert_eq!(
+            check_write(&vm, ptr, "y"),
+            Err("Cannot add property y, object is not extensible".to_string())
+        );
+        // A missing property can always be deleted
+        assert_eq!(check_delete(&vm, ptr, "y"), Ok(()));
+        assert!(check_delete(&vm, ptr, "x").is_err());
+        assert_eq!(set(&mut vm, ptr, "x", JsValue::Number(2.0)), Ok(false));
+        assert_eq!(get(&mut vm, ptr, "x"), Ok(JsValue::Number(1.0)));
+    }
+}
@@ -0,0 +1,104 @@
+//! Stack traces for error objects
+//!
+//! Bytecode loaded from a file registers its source map here, so a frame's
+//! position can be reported as `file:line:column`. Function names come from
+//! the source map's named functions, like the debugger's call stack.
+
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use crate::compiler::source_map::BytecodeSourceMap;
+
+use super::VM;
+use super::value::{HeapData, HeapObject, JsValue};
+
+/// Frames listed in a stack trace, like V8's default `Error.stackTraceLimit`
+const STACK_TRACE_LIMIT: usize = 10;
+
+/// A file's bytecode within the program
+pub(super) struct LoadedSource {
+    range: Range<usize>,
+    path: PathBuf,
+    map: BytecodeSourceMap,
+}
+
+impl VM {
+    /// Map the instructions in `range` back to `path`. `map` holds addresses
+    /// relative to the start of the range.
+    pub fn add_source_map(&mut self, range: Range<usize>, path: &Path, mut map: BytecodeSourceMap) {
+        map.rebase(range.start);
+        self.sources.push(LoadedSource {
+            range,
+            path: path.to_path_buf(),
+            map,
+        });
+    }
+
+    fn source_at(&self, ip: usize) -> Option<&LoadedSource> {
+        self.sources
+            .iter()
+            .rev()
+            .find(|source| source.range.contains(&ip))
+    }
+
+    /// One `    at name (file:line:column)` line per active frame, innermost
+    /// first, leaving out the innermost `skip` frames (a native's own frame).
+    /// The trace ends at the event loop task that started the call chain.
+    pub fn stack_trace(&self, skip: usize) -> String {
+        let mut lines = Vec::new();
+        let mut ip = self.ip;
+        for depth in (0..self.call_stack.len()).rev() {
+            if depth + skip < self.call_stack.len() {
+                if lines.len() == STACK_TRACE_LIMIT {
+                    break;
+                }
+                lines.push(self.frame_line(depth, ip));
+            }
+            // The caller waits at its call instruction
+            match self.call_stack[depth].return_address {
+                usize::MAX => break,
+                return_address => ip = return_address.wrapping_sub(1),
+            }
+        }
+        lines.join("\n")
+    }
+
+    /// `new Name` for a class constructor's frame
+    fn constructor_name(&self, depth: usize) -> Option<String> {
+        let Some(JsValue::Object(ptr)) = &self.call_stack[depth].new_target else {
+            return None;
+        };
+        match self.heap.get(*ptr) {
+            Some(HeapObject {
+                data: HeapData::Object(props),
+            }) => match props.get("name") {
+                Some(JsValue::String(name)) => Some(format!("new {}", name)),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn frame_line(&self, depth: usize, ip: usize) -> String {
+        let source = self.source_at(ip);
+        let constructor = self.constructor_name(depth);
+        let name = match source {
+            _ if depth == 0 => None,
+            _ if constructor.is_some() => constructor.as_deref(),
+            Some(source) => Some(source.map.enclosing_function(ip).unwrap_or("<anonymous>")),
+            None => Some("<anonymous>"),
+        };
+        let location = source.and_then(|source| {
+            source
+                .map
+                .lookup(ip)
+                .map(|loc| format!("{}:{}:{}", source.path.display(), loc.line, loc.column + 1))
+        });
+        match (name, location) {
+            (Some(name), Some(location)) => format!("    at {} ({})", name, location),
+            (Some(name), None) => format!("    at {}", name),
+            (None, Some(location)) => format!("    at {}", location),
+            (None, None) => "    at <main>".to_string(),
+        }
+    }
+}
@@ -3,9 +3,19 @@
 //! Sets up only essential globals needed for language operation:
 //! - console (log, error)
 //! - ByteStream (binary serialization)
-//! - String.fromCharCode
+//! - String.fromCharCode and String.fromCodePoint
 //! - require (module loading)
-//! - fs (minimal file I/O for bootstrap compiler)
+//! - Object and Reflect
+//! - Map, Set, WeakMap and WeakSet
+//! - fs (minimal file I/O for bootstrap compiler) and fs.promises
+//! - Math
+//! - Number, NaN, Infinity, isNaN, isFinite, parseFloat and parseInt
+//! - Date and performance
+//! - setTimeout, setInterval, setImmediate, clearTimeout, clearInterval, queueMicrotask
+//! - Promise.resolve and Promise.reject
+//! - Error, TypeError, RangeError and the other built-in error classes
+//! - ArrayBuffer, Uint8Array and the other typed arrays, DataView
+//! - TextEncoder and TextDecoder
 
 use crate::vm::VM;
 use crate::vm::value::{HeapData, HeapObject, JsValue};
@@ -17,10 +27,19 @@ pub fn setup_stdlib(vm: &mut VM) {
     setup_fs(vm);
     setup_json(vm);
     setup_globals(vm);
+    setup_array(vm);
     setup_map_set(vm);
     setup_process(vm);
     setup_fetch(vm);
     setup_object(vm);
+    setup_math(vm);
+    setup_number(vm);
+    setup_date(vm);
+    setup_timers(vm);
+    setup_promise(vm);
+    setup_errors(vm);
+    setup_typed_arrays(vm);
+    setup_text(vm);
 }
 
 fn setup_console(vm: &mut VM) {
@@ -97,11 +116,14 @@ fn setup_bytestream(vm: &mut VM) {
 }
 
 fn setup_string(vm: &mut VM) {
-    use crate::stdlib::{native_string_constructor, native_string_from_char_code};
+    use crate::stdlib::{
+        native_string_constructor, native_string_from_char_code, native_string_from_code_point,
+    };
 
     // Register the String constructor as a callable function
     let string_constructor_idx = vm.register_native(native_string_constructor);
     let string_from_char_code_idx = vm.register_native(native_string_from_char_code);
+    let string_from_code_point_idx = vm.register_native(native_string_from_code_point);
 
     // Create String as an object with methods
     let string_ptr = vm.heap.len();
@@ -110,6 +132,10 @@ fn setup_string(vm: &mut VM) {
         "fromCharCode".to_string(),
         JsValue::NativeFunction(string_from_char_code_idx),
     );
+    string_props.insert(
+        "fromCodePoint".to_string(),
+        JsValue::NativeFunction(string_from_code_point_idx),
+    );
     // Store the constructor function for when String is called
     string_props.insert(
         "__call__".to_string(),
@@ -185,6 +211,48 @@ fn setup_fs(vm: &mut VM) {
         .insert("fs".into(), JsValue::Object(fs_ptr));
 
     vm.modules.insert("fs".to_string(), JsValue::Object(fs_ptr));
+
+    setup_fs_promises(vm, fs_ptr);
+}
+
+fn setup_fs_promises(vm: &mut VM, fs_ptr: usize) {
+    use crate::stdlib::fs::{FILE_HANDLE_NATIVES, PROMISES_NATIVES, STATS_NATIVES};
+
+    let promises_ptr = native_object(vm, &PROMISES_NATIVES);
+    if let Some(HeapObject {
+        data: HeapData::Object(fs_props),
+    }) = vm.heap.get_mut(fs_ptr)
+    {
+        fs_props.insert("promises".to_string(), JsValue::Object(promises_ptr));
+    }
+    vm.modules
+        .insert("fs/promises".to_string(), JsValue::Object(promises_ptr));
+
+    // Prototypes of the handles and stats the promises resolve with
+    let prototypes = [
+        ("FileHandle", FILE_HANDLE_NATIVES.as_slice()),
+        ("Stats", &STATS_NATIVES),
+    ];
+    for (name, natives) in prototypes {
+        let proto_ptr = native_object(vm, natives);
+        vm.fs_prototypes.insert(name, proto_ptr);
+    }
+}
+
+/// An object of native methods
+fn native_object(vm: &mut VM, natives: &[(&str, crate::vm::NativeFn)]) -> usize {
+    let mut props = std::collections::HashMap::new();
+    for &(name, native) in natives {
+        props.insert(
+            name.to_string(),
+            JsValue::NativeFunction(vm.register_native(native)),
+        );
+    }
+    let ptr = vm.heap.len();
+    vm.heap.push(HeapObject {
+        data: HeapData::Object(props),
+    });
+    ptr
 }
 
 fn setup_json(vm: &mut VM) {
@@ -217,37 +285,47 @@ fn setup_globals(vm: &mut VM) {
         .insert("require".into(), JsValue::NativeFunction(require_idx));
 }
 
-fn setup_map_set(vm: &mut VM) {
-    // Create Map constructor object
-    let map_ptr = vm.heap.len();
-    let mut map_props = std::collections::HashMap::new();
-    // Mark this as a Map constructor for detection in Construct opcode
-    map_props.insert("__type__".to_string(), JsValue::String("Map".to_string()));
-    vm.heap.push(HeapObject {
-        data: HeapData::Object(map_props),
-    });
-    vm.call_stack[0]
-        .locals
-        .insert("Map".into(), JsValue::Object(map_ptr));
+fn setup_array(vm: &mut VM) {
+    let call_idx = vm.register_native(crate::stdlib::array::native_array_call);
 
-    // Create Set constructor object
-    let set_ptr = vm.heap.len();
-    let mut set_props = std::collections::HashMap::new();
-    // Mark this as a Set constructor for detection in Construct opcode
-    set_props.insert("__type__".to_string(), JsValue::String("Set".to_string()));
+    // Marked with __type__ for detection in Construct opcode
+    let mut props = std::collections::HashMap::new();
+    props.insert("__type__".to_string(), JsValue::String("Array".into()));
+    props.insert("__call__".to_string(), JsValue::NativeFunction(call_idx));
+    props.insert("name".to_string(), JsValue::String("Array".into()));
+    let ptr = vm.heap.len();
     vm.heap.push(HeapObject {
-        data: HeapData::Object(set_props),
+        data: HeapData::Object(props),
     });
     vm.call_stack[0]
         .locals
-        .insert("Set".into(), JsValue::Object(set_ptr));
+        .insert("Array".into(), JsValue::Object(ptr));
+}
+
+fn setup_map_set(vm: &mut VM) {
+    // Constructors are marked with __type__ for detection in Construct opcode
+    for name in ["Map", "Set", "WeakMap", "WeakSet"] {
+        let mut props = std::collections::HashMap::new();
+        props.insert("__type__".to_string(), JsValue::String(name.into()));
+        props.insert("name".to_string(), JsValue::String(name.into()));
+        let ptr = vm.heap.len();
+        vm.heap.push(HeapObject {
+            data: HeapData::Object(props),
+        });
+        vm.call_stack[0]
+            .locals
+            .insert(name.into(), JsValue::Object(ptr));
+    }
 }
 
 /// Set script arguments as __args__ global variable.
 /// Arguments are provided as strings and converted to a JS array.
 pub fn set_script_args(vm: &mut VM, args: Vec<String>) {
     // Convert args to JsValue strings
-    let js_args: Vec<JsValue> = args.into_iter().map(JsValue::String).collect();
+    let js_args: Vec<JsValue> = args
+        .into_iter()
+        .map(|s| JsValue::String(s.into()))
+        .collect();
 
     // Create array on heap (arrays are stored as Object pointing to HeapData::Array)
     let array_ptr = vm.heap.len();
@@ -371,19 +449,338 @@ fn setup_fetch(vm: &mut VM) {
 }
 
 fn setup_object(vm: &mut VM) {
-    use crate::stdlib::native_object_keys;
+    use crate::stdlib::object::{OBJECT_NATIVES, REFLECT_NATIVES};
+
+    for (name, natives) in [
+        ("Object", OBJECT_NATIVES.as_slice()),
+        ("Reflect", &REFLECT_NATIVES),
+    ] {
+        let mut props = std::collections::HashMap::new();
+        for &(method, native) in natives {
+            props.insert(
+                method.to_string(),
+                JsValue::NativeFunction(vm.register_native(native)),
+            );
+        }
+        let ptr = vm.heap.len();
+        vm.heap.push(HeapObject {
+            data: HeapData::Object(props),
+        });
+        vm.call_stack[0]
+            .locals
+            .insert(name.into(), JsValue::Object(ptr));
+    }
+}
+
+fn setup_math(vm: &mut VM) {
+    use crate::runtime::math::CONSTANTS;
+    use crate::stdlib::MATH_NATIVES;
+
+    let mut math_props = std::collections::HashMap::new();
+    for &(name, value) in CONSTANTS {
+        math_props.insert(name.to_string(), JsValue::Number(value));
+    }
+    for &(func, native) in MATH_NATIVES {
+        let idx = vm.register_native(native);
+        math_props.insert(func.name().to_string(), JsValue::NativeFunction(idx));
+    }
+
+    let math_ptr = vm.heap.len();
+    vm.heap.push(HeapObject {
+        data: HeapData::Object(math_props),
+    });
+    vm.call_stack[0]
+        .locals
+        .insert("Math".into(), JsValue::Object(math_ptr));
+}
+
+fn setup_number(vm: &mut VM) {
+    use crate::stdlib::number::{
+        CONSTANTS, GLOBAL_NATIVES, NUMBER_NATIVES, native_number_constructor,
+    };
+
+    let mut number_props = std::collections::HashMap::new();
+    for &(name, value) in CONSTANTS {
+        number_props.insert(name.to_string(), JsValue::Number(value));
+    }
+    for (name, native) in NUMBER_NATIVES {
+        let idx = vm.register_native(native);
+        number_props.insert(name.to_string(), JsValue::NativeFunction(idx));
+    }
+    let call_idx = vm.register_native(native_number_constructor);
+    number_props.insert("__call__".to_string(), JsValue::NativeFunction(call_idx));
+
+    let globals = &mut vm.call_stack[0].locals;
+    for name in ["parseFloat", "parseInt"] {
+        globals.insert(name.into(), number_props[name].clone());
+    }
+    globals.insert("NaN".into(), JsValue::Number(f64::NAN));
+    globals.insert("Infinity".into(), JsValue::Number(f64::INFINITY));
 
-    let keys_idx = vm.register_native(native_object_keys);
+    let number_ptr = vm.heap.len();
+    vm.heap.push(HeapObject {
+        data: HeapData::Object(number_props),
+    });
+    vm.call_stack[0]
+        .locals
+        .insert("Number".into(), JsValue::Object(number_ptr));
+    for (name, native) in GLOBAL_NATIVES {
+        let idx = vm.register_native(native);
+        vm.call_stack[0]
+            .locals
+            .insert(name.into(), JsValue::NativeFunction(idx));
+    }
+}
+
+fn setup_date(vm: &mut VM) {
+    use crate::stdlib::date::{
+        native_date_call, native_date_now, native_date_parse, native_date_utc,
+        native_performance_now,
+    };
 
-    // Create Object global with keys method
-    let object_ptr = vm.heap.len();
-    let mut object_props = std::collections::HashMap::new();
-    object_props.insert("keys".to_string(), JsValue::NativeFunction(keys_idx));
+    let call_idx = vm.register_native(native_date_call);
+    let now_idx = vm.register_native(native_date_now);
+    let parse_idx = vm.register_native(native_date_parse);
+    let utc_idx = vm.register_native(native_date_utc);
+    let performance_now_idx = vm.register_native(native_performance_now);
+
+    // Create Date constructor object
+    let date_ptr = vm.heap.len();
+    let mut date_props = std::collections::HashMap::new();
+    // Mark this as a Date constructor for detection in Construct opcode
+    date_props.insert("__type__".to_string(), JsValue::String("Date".into()));
+    date_props.insert("__call__".to_string(), JsValue::NativeFunction(call_idx));
+    date_props.insert("now".to_string(), JsValue::NativeFunction(now_idx));
+    date_props.insert("parse".to_string(), JsValue::NativeFunction(parse_idx));
+    date_props.insert("UTC".to_string(), JsValue::NativeFunction(utc_idx));
     vm.heap.push(HeapObject {
-        data: HeapData::Object(object_props),
+        data: HeapData::Object(date_props),
     });
+    vm.call_stack[0]
+        .locals
+        .insert("Date".into(), JsValue::Object(date_ptr));
+
+    // performance.now() counts from here unless native code read the clock first
+    crate::runtime::time::init();
+    let performance_ptr = vm.heap.len();
+    let mut performance_props = std::collections::HashMap::new();
+    performance_props.insert(
+        "now".to_string(),
+        JsValue::NativeFunction(performance_now_idx),
+    );
+    performance_props.insert(
+        "timeOrigin".to_string(),
+        JsValue::Number(crate::runtime::time::time_origin()),
+    );
+    vm.heap.push(HeapObject {
+        data: HeapData::Object(performance_props),
+    });
+    vm.call_stack[0]
+        .locals
+        .insert("performance".into(), JsValue::Object(performance_ptr));
+}
+
+fn setup_timers(vm: &mut VM) {
+    use crate::stdlib::timers::{
+        native_clear_timer, native_queue_microtask, native_set_immediate, native_set_interval,
+        native_set_timeout,
+    };
 
+    let clear_idx = vm.register_native(native_clear_timer);
+    let globals: [(&str, usize); 6] = [
+        ("setTimeout", vm.register_native(native_set_timeout)),
+        ("setInterval", vm.register_native(native_set_interval)),
+        ("setImmediate", vm.register_native(native_set_immediate)),
+        ("clearTimeout", clear_idx),
+        ("clearInterval", clear_idx),
+        ("queueMicrotask", vm.register_native(native_queue_microtask)),
+    ];
+    for (name, idx) in globals {
+        vm.call_stack[0]
+            .locals
+            .insert(name.into(), JsValue::NativeFunction(idx));
+    }
+}
+
+fn setup_promise(vm: &mut VM) {
+    use crate::stdlib::promise::{native_reject, native_resolve};
+
+    let resolve_idx = vm.register_native(native_resolve);
+    let reject_idx = vm.register_native(native_reject);
+    let promise_ptr = vm.heap.len();
+    let mut promise_props = std::collections::HashMap::new();
+    promise_props.insert("resolve".to_string(), JsValue::NativeFunction(resolve_idx));
+    promise_props.insert("reject".to_string(), JsValue::NativeFunction(reject_idx));
+    vm.heap.push(HeapObject {
+        data: HeapData::Object(promise_props),
+    });
     vm.call_stack[0]
         .locals
-        .insert("Object".into(), JsValue::Object(object_ptr));
+        .insert("Promise".into(), JsValue::Object(promise_ptr));
+}
+
+fn setup_errors(vm: &mut VM) {
+    use crate::stdlib::error::{
+        CALLS, ERROR_TYPES, native_aggregate_error_construct, native_error_construct,
+        native_error_to_string,
+    };
+
+    let construct_idx = vm.register_native(native_error_construct);
+    let aggregate_idx = vm.register_native(native_aggregate_error_construct);
+    let to_string_idx = vm.register_native(native_error_to_string);
+
+    // Every other class extends Error, which is set up first
+    let mut error_ptr = None;
+    for (name, call) in ERROR_TYPES.into_iter().zip(CALLS) {
+        let proto_ptr = vm.heap.len();
+        let wrapper_ptr = proto_ptr + 1;
+
+        let mut proto_props = std::collections::HashMap::new();
+        proto_props.insert("name".to_string(), JsValue::String(name.into()));
+        proto_props.insert("message".to_string(), JsValue::String(String::new().into()));
+        proto_props.insert("constructor".to_string(), JsValue::Object(wrapper_ptr));
+        match error_ptr {
+            None => {
+                proto_props.insert(
+                    "toString".to_string(),
+                    JsValue::NativeFunction(to_string_idx),
+                );
+            }
+            Some(_) => {
+                proto_props.insert(
+                    "__proto__".to_string(),
+                    JsValue::Object(vm.error_prototypes["Error"]),
+                );
+            }
+        }
+        vm.heap.push(HeapObject {
+            data: HeapData::Object(proto_props),
+        });
+
+        // A class wrapper like the compiler emits, callable without `new` too
+        let constructor_idx = if name == "AggregateError" {
+            aggregate_idx
+        } else {
+            construct_idx
+        };
+        let mut wrapper_props = std::collections::HashMap::new();
+        wrapper_props.insert("name".to_string(), JsValue::String(name.into()));
+        wrapper_props.insert(
+            "constructor".to_string(),
+            JsValue::NativeFunction(constructor_idx),
+        );
+        wrapper_props.insert("prototype".to_string(), JsValue::Object(proto_ptr));
+        wrapper_props.insert(
+            "__call__".to_string(),
+            JsValue::NativeFunction(vm.register_native(call)),
+        );
+        if let Some(error_ptr) = error_ptr {
+            wrapper_props.insert("__super__".to_string(), JsValue::Object(error_ptr));
+        }
+        vm.heap.push(HeapObject {
+            data: HeapData::Object(wrapper_props),
+        });
+
+        error_ptr.get_or_insert(wrapper_ptr);
+        vm.error_prototypes.insert(name.to_string(), proto_ptr);
+        vm.call_stack[0]
+            .locals
+            .insert(name.into(), JsValue::Object(wrapper_ptr));
+    }
+}
+
+fn setup_typed_arrays(vm: &mut VM) {
+    use crate::runtime::typed_array::ElementKind;
+    use crate::stdlib::typed_array::{FROM, OF, native_array_buffer_is_view};
+
+    let is_view_idx = vm.register_native(native_array_buffer_is_view);
+
+    // Constructors are marked with __type__ for detection in Construct opcode
+    let mut constructors = Vec::new();
+    let mut array_buffer_props = std::collections::HashMap::new();
+    array_buffer_props.insert("isView".to_string(), JsValue::NativeFunction(is_view_idx));
+    constructors.push(("ArrayBuffer", array_buffer_props));
+    constructors.push(("DataView", std::collections::HashMap::new()));
+    for ((kind, from), of) in ElementKind::ALL.into_iter().zip(FROM).zip(OF) {
+        let mut props = std::collections::HashMap::new();
+        props.insert(
+            "BYTES_PER_ELEMENT".to_string(),
+            JsValue::Number(kind.size() as f64),
+        );
+        props.insert(
+            "from".to_string(),
+            JsValue::NativeFunction(vm.register_native(from)),
+        );
+        props.insert(
+            "of".to_string(),
+            JsValue::NativeFunction(vm.register_native(of)),
+        );
+        constructors.push((kind.name(), props));
+    }
+
+    for (name, mut props) in constructors {
+        props.insert("__type__".to_string(), JsValue::String(name.into()));
+        props.insert("name".to_string(), JsValue::String(name.into()));
+        let ptr = vm.heap.len();
+        vm.heap.push(HeapObject {
+            data: HeapData::Object(props),
+        });
+        vm.call_stack[0]
+            .locals
+            .insert(name.into(), JsValue::Object(ptr));
+    }
+}
+
+fn setup_text(vm: &mut VM) {
+    use crate::stdlib::text::{
+        native_text_decoder_construct, native_text_decoder_decode, native_text_encoder_construct,
+        native_text_encoder_encode, native_text_encoder_encode_into,
+    };
+
+    let classes: [(&str, crate::vm::NativeFn, Vec<(&str, crate::vm::NativeFn)>); 2] = [
+        (
+            "TextEncoder",
+            native_text_encoder_construct,
+            vec![
+                ("encode", native_text_encoder_encode),
+                ("encodeInto", native_text_encoder_encode_into),
+            ],
+        ),
+        (
+            "TextDecoder",
+            native_text_decoder_construct,
+            vec![("decode", native_text_decoder_decode)],
+        ),
+    ];
+    for (name, constructor, methods) in classes {
+        let proto_ptr = vm.heap.len();
+        let wrapper_ptr = proto_ptr + 1;
+
+        let mut proto_props = std::collections::HashMap::new();
+        proto_props.insert("constructor".to_string(), JsValue::Object(wrapper_ptr));
+        for (method, native) in methods {
+            proto_props.insert(
+                method.to_string(),
+                JsValue::NativeFunction(vm.register_native(native)),
+            );
+        }
+        vm.heap.push(HeapObject {
+            data: HeapData::Object(proto_props),
+        });
+
+        // A class wrapper like the compiler emits, as for the error classes
+        let mut wrapper_props = std::collections::HashMap::new();
+        wrapper_props.insert("name".to_string(), JsValue::String(name.into()));
+        wrapper_props.insert(
+            "constructor".to_string(),
+            JsValue::NativeFunction(vm.register_native(constructor)),
+        );
+        wrapper_props.insert("prototype".to_string(), JsValue::Object(proto_ptr));
+        vm.heap.push(HeapObject {
+            data: HeapData::Object(wrapper_props),
+        });
+        vm.call_stack[0]
+            .locals
+            .insert(name.into(), JsValue::Object(wrapper_ptr));
+    }
 }
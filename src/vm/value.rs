@@ -1,14 +1,18 @@
 // Memory representation. We will use a enum to implement ownership,
 // and we track wheter a value is "Owned" or a "reference" in the low-level representation
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, Mutex};
 
+use super::ordered_map::OrderedMap;
+use crate::runtime::typed_array::ElementKind;
+pub use crate::runtime::utf16::JsString;
+
 pub type NativeFn = fn(&mut crate::vm::VM, Vec<JsValue>) -> JsValue;
 
 #[derive(Debug, Clone)]
 pub enum JsValue {
     Number(f64),
-    String(String),
+    String(JsString),
     Boolean(bool),
     // In a real low-level VM, this would be a pointer to a Heap
     Object(usize),
@@ -151,77 +155,21 @@ impl Promise {
         }
     }
 
-    pub fn then(&self, on_fulfilled: Option<JsValue>) -> Self {
-        let mut internal = self.state.lock().unwrap();
-
-        match internal.state {
-            PromiseState::Pending => {
-                internal.handlers.push(PromiseHandler {
-                    on_fulfilled: on_fulfilled.map(Box::new),
-                    on_rejected: None,
-                    continuation: None,
-                });
-                self.clone()
-            }
-            PromiseState::Fulfilled => {
-                if let Some(handler) = on_fulfilled {
-                    Promise::with_value(handler)
-                } else {
-                    Promise::with_value(internal.value.clone().unwrap_or(JsValue::Undefined))
-                }
-            }
-            PromiseState::Rejected => {
-                Promise::with_value(internal.value.clone().unwrap_or(JsValue::Undefined))
-            }
-        }
-    }
-
-    pub fn catch(&self, on_rejected: Option<JsValue>) -> Self {
-        let mut internal = self.state.lock().unwrap();
-
-        match internal.state {
-            PromiseState::Pending => {
-                internal.handlers.push(PromiseHandler {
-                    on_fulfilled: None,
-                    on_rejected: on_rejected.map(Box::new),
-                    continuation: None,
-                });
-                self.clone()
-            }
-            PromiseState::Fulfilled => {
-                Promise::with_value(internal.value.clone().unwrap_or(JsValue::Undefined))
-            }
-            PromiseState::Rejected => {
-                if let Some(handler) = on_rejected {
-                    Promise::with_value(handler)
-                } else {
-                    Promise::with_value(internal.value.clone().unwrap_or(JsValue::Undefined))
-                }
-            }
-        }
-    }
-
-    /// Register a continuation for async/await
-    pub fn then_await(&self, on_fulfilled: Option<JsValue>, continuation: Continuation) -> Self {
+    /// Register `handler` to run once the promise settles. If it already
+    /// has, the handler comes back with the settled value and whether it
+    /// was fulfilled.
+    pub fn react(&self, handler: PromiseHandler) -> Option<(PromiseHandler, JsValue, bool)> {
         let mut internal = self.state.lock().unwrap();
-
-        match internal.state {
+        let fulfilled = match internal.state {
             PromiseState::Pending => {
-                internal.handlers.push(PromiseHandler {
-                    on_fulfilled: on_fulfilled.map(Box::new),
-                    on_rejected: None,
-                    continuation: Some(continuation),
-                });
-                self.clone()
+                internal.handlers.push(handler);
+                return None;
             }
-            PromiseState::Fulfilled => {
-                // Already fulfilled, create resolved promise with value
-                Promise::with_value(internal.value.clone().unwrap_or(JsValue::Undefined))
-            }
-            PromiseState::Rejected => {
-                Promise::with_value(internal.value.clone().unwrap_or(JsValue::Undefined))
-            }
-        }
+            PromiseState::Fulfilled => true,
+            PromiseState::Rejected => false,
+        };
+        let value = internal.value.clone().unwrap_or(JsValue::Undefined);
+        Some((handler, value, fulfilled))
     }
 }
 
@@ -236,18 +184,18 @@ pub struct PromiseInternal {
 pub struct PromiseHandler {
     pub on_fulfilled: Option<Box<JsValue>>,
     pub on_rejected: Option<Box<JsValue>>,
-    /// Continuation for async/await - stores resume IP when awaiting
-    /// Uses Arc<Frame> to avoid import cycles
+    /// The async function call awaiting the promise; resumed instead of a callback
     pub continuation: Option<Continuation>,
+    /// The promise `then` returned, settled with the callback's result;
+    /// `None` for a bare callback
+    pub derived: Option<Promise>,
 }
 
+/// An async function call suspended in `await`
 #[derive(Debug, Clone)]
 pub struct Continuation {
-    pub resume_ip: usize,
-    /// Stores the frame state for resuming async function
-    /// We store locals separately to avoid import cycle
-    pub locals: HashMap<String, JsValue>,
-    pub this_context: JsValue,
+    /// Id of the suspended call in the VM
+    pub call_id: usize,
 }
 
 /// Continuation callback type for async operations
@@ -263,6 +211,14 @@ pub struct AsyncContext {
     pub promise: Promise,
 }
 
+/// What a collection iterator yields for each entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IterationKind {
+    Keys,
+    Values,
+    Entries,
+}
+
 #[derive(Debug, Clone)]
 pub struct HeapObject {
     pub data: HeapData,
@@ -280,8 +236,40 @@ pub enum HeapData {
     Array(Vec<JsValue>),
     /// ByteStream for building binary bytecode buffers
     ByteStream(Vec<u8>),
-    /// Map - ordered key-value pairs with any key type
-    Map(Vec<(JsValue, JsValue)>),
-    /// Set - ordered unique values
-    Set(Vec<JsValue>),
+    /// Map - key-value pairs with any key type, in insertion order
+    Map(OrderedMap<JsValue>),
+    /// Set - unique values in insertion order
+    Set(OrderedMap<()>),
+    /// WeakMap - values keyed by the heap index of their key object. The
+    /// heap has no collector, so entries are never dropped.
+    WeakMap(HashMap<usize, JsValue>),
+    /// WeakSet - the heap indices of its objects, never dropped either
+    WeakSet(HashSet<usize>),
+    /// Iterator from `keys`, `values` or `entries` of the Map or Set at
+    /// `collection`, over the keys it had when created. `position` is the
+    /// index in `keys` of the next one to visit.
+    CollectionIterator {
+        collection: usize,
+        kind: IterationKind,
+        keys: Vec<JsValue>,
+        position: usize,
+    },
+    /// Date - milliseconds since the Unix epoch, NaN when invalid
+    Date(f64),
+    /// ArrayBuffer - fixed-length bytes shared by the views onto them
+    ArrayBuffer(Vec<u8>),
+    /// Typed array - `length` elements of `kind`, `offset` bytes into the
+    /// ArrayBuffer at `buffer`
+    TypedArray {
+        kind: ElementKind,
+        buffer: usize,
+        offset: usize,
+        length: usize,
+    },
+    /// DataView - `length` bytes, `offset` bytes into the ArrayBuffer at `buffer`
+    DataView {
+        buffer: usize,
+        offset: usize,
+        length: usize,
+    },
 }